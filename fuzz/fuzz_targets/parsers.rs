@@ -0,0 +1,48 @@
+#![no_main]
+
+use chrono::{DateTime, Utc};
+use dsd_util::parsers::{
+    format_uptime, parse_container_env, parse_container_list, parse_container_users,
+    parse_depends_on, parse_event, parse_graph_containers, parse_image_id, parse_image_inspect,
+    parse_image_metadata, parse_inspect_output, parse_log_configs, parse_manifest_size,
+    parse_name_label_pairs, parse_prune_containers, parse_pull_progress, parse_security_profiles,
+    parse_stats_output, parse_volume_sizes, uptime_seconds,
+};
+use dsd_util::units::{parse_duration, parse_size};
+use libfuzzer_sys::fuzz_target;
+
+// every parser must return an error on malformed docker output instead of panicking
+fuzz_target!(|data: &[u8]| {
+    let _ = parse_graph_containers(data);
+    let _ = parse_prune_containers(data);
+    let _ = parse_log_configs(data);
+    let _ = parse_container_env(data);
+    let _ = parse_security_profiles(data);
+    let _ = parse_container_users(data);
+    let _ = parse_image_inspect(data);
+    let _ = parse_image_metadata(data);
+    let _ = parse_volume_sizes(data);
+    let _ = parse_manifest_size(data, "linux/amd64");
+
+    let Ok(input) = std::str::from_utf8(data) else {
+        return;
+    };
+
+    let now = DateTime::<Utc>::UNIX_EPOCH;
+
+    let _ = parse_stats_output(input);
+    let _ = parse_container_list(input);
+    let _ = parse_inspect_output(input, now);
+    let _ = format_uptime(input, now);
+    let _ = uptime_seconds(input, now);
+    let _ = parse_image_id(input);
+    let _ = parse_name_label_pairs(input);
+    let _ = parse_depends_on(Some(input));
+    let _ = parse_duration(input);
+    let _ = parse_size(input);
+
+    for line in input.lines() {
+        let _ = parse_pull_progress(line);
+        let _ = parse_event(line);
+    }
+});
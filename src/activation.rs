@@ -0,0 +1,62 @@
+use anyhow::Context;
+use std::net::TcpListener;
+use std::os::fd::{FromRawFd, RawFd};
+
+/// First file descriptor systemd passes sockets on, as SD_LISTEN_FDS_START
+const LISTEN_FDS_START: RawFd = 3;
+
+/// Number of sockets systemd passed to the process with the given pid, as sd_listen_fds reads
+/// them from LISTEN_PID and LISTEN_FDS. Sockets meant for another process, such as a parent
+/// that started this one, are ignored.
+pub fn listen_fds(env: impl Fn(&str) -> Option<String>, pid: u32) -> usize {
+    let for_us = env("LISTEN_PID")
+        .and_then(|listen_pid| listen_pid.trim().parse::<u32>().ok())
+        .is_some_and(|listen_pid| listen_pid == pid);
+    if !for_us {
+        return 0;
+    }
+
+    env("LISTEN_FDS")
+        .and_then(|count| count.trim().parse::<usize>().ok())
+        .unwrap_or(0)
+}
+
+/// Takes the socket systemd listens on for the process when started through a socket unit,
+/// returning None when the process was started otherwise
+pub fn activated_listener() -> anyhow::Result<Option<TcpListener>> {
+    match listen_fds(|name| std::env::var(name).ok(), std::process::id()) {
+        0 => return Ok(None),
+        1 => {}
+        count => anyhow::bail!(
+            "systemd passed {count} sockets, expected a single ListenStream in the socket unit"
+        ),
+    }
+
+    let fd = LISTEN_FDS_START;
+    let mut stat = std::mem::MaybeUninit::<libc::stat>::zeroed();
+
+    // SAFETY: fstat only writes into the provided struct
+    if unsafe { libc::fstat(fd, stat.as_mut_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to read socket from systemd");
+    }
+    // SAFETY: fstat succeeded and filled in the struct
+    let stat = unsafe { stat.assume_init() };
+    if stat.st_mode & libc::S_IFMT != libc::S_IFSOCK {
+        anyhow::bail!("File descriptor {fd} passed by systemd is not a socket");
+    }
+
+    // commands run by the daemon, such as docker compose, must not keep the socket open
+    // SAFETY: fd is open, as fstat succeeded
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } != 0 {
+        return Err(std::io::Error::last_os_error())
+            .context("Failed to set up socket from systemd");
+    }
+
+    // SAFETY: systemd hands the socket over to this process, nothing else owns the descriptor
+    let listener = unsafe { TcpListener::from_raw_fd(fd) };
+    listener
+        .local_addr()
+        .context("Socket passed by systemd is not a TCP socket, expected ListenStream=<port>")?;
+
+    Ok(Some(listener))
+}
@@ -0,0 +1,219 @@
+use crate::commands::DOCKER;
+use crate::prune::DiskContainer;
+use anyhow::Context;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Image running tar against volumes, so no tools are needed on the host
+const TAR_IMAGE: &str = "alpine:3";
+const ARCHIVE_EXTENSION: &str = ".tar.gz";
+/// Timestamp in archive names, sortable and free of characters awkward in file names
+const ARCHIVE_TIMESTAMP: &str = "%Y%m%dT%H%M%SZ";
+/// Extracts an archive into a staging directory on the volume and swaps it in only once tar
+/// succeeded, so a corrupt or truncated archive leaves the volume as it was
+const RESTORE_SCRIPT: &str = r#"set -e
+staging=/volume/.dsd-util-restore
+rm -rf "$staging"
+mkdir "$staging"
+if ! tar -xzf - -C "$staging"; then
+    rm -rf "$staging"
+    exit 1
+fi
+find /volume -mindepth 1 -maxdepth 1 ! -path "$staging" -exec rm -rf {} +
+find "$staging" -mindepth 1 -maxdepth 1 -exec mv {} /volume/ \;
+rmdir "$staging""#;
+
+/// Named volumes mounted by containers of a stack, sorted and without duplicates
+pub fn stack_volumes(stack: &str, containers: &[DiskContainer]) -> Vec<String> {
+    let mut volumes = containers
+        .iter()
+        .filter(|container| container.stack.as_deref() == Some(stack))
+        .flat_map(|container| container.volumes.clone())
+        .collect::<Vec<String>>();
+    volumes.sort();
+    volumes.dedup();
+    volumes
+}
+
+/// Running containers of a stack that mount one of the volumes
+pub fn containers_using(
+    stack: &str,
+    containers: &[DiskContainer],
+    volumes: &[String],
+) -> Vec<String> {
+    containers
+        .iter()
+        .filter(|container| {
+            container.stack.as_deref() == Some(stack)
+                && container.status == "running"
+                && container
+                    .volumes
+                    .iter()
+                    .any(|volume| volumes.contains(volume))
+        })
+        .map(|container| container.name.to_string())
+        .collect()
+}
+
+/// File name of the archive of a volume, such as web_data-20250601T120000Z.tar.gz
+pub fn archive_name(volume: &str, timestamp: DateTime<Utc>) -> String {
+    format!(
+        "{volume}-{}{ARCHIVE_EXTENSION}",
+        timestamp.format(ARCHIVE_TIMESTAMP)
+    )
+}
+
+/// Path of the archive of a volume in a backup directory
+pub fn archive_path(dir: &Path, volume: &str, timestamp: DateTime<Utc>) -> PathBuf {
+    dir.join(archive_name(volume, timestamp))
+}
+
+/// Volume and timestamp of an archive file name, None for other files
+pub fn parse_archive_name(file_name: &str) -> Option<(String, DateTime<Utc>)> {
+    let (volume, timestamp) = file_name
+        .strip_suffix(ARCHIVE_EXTENSION)?
+        .rsplit_once('-')?;
+
+    Some((volume.to_string(), parse_archive_timestamp(timestamp)?))
+}
+
+/// Timestamp as used in archive names, such as 20250601T120000Z
+pub fn parse_archive_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    NaiveDateTime::parse_from_str(timestamp, ARCHIVE_TIMESTAMP)
+        .ok()
+        .map(|timestamp| timestamp.and_utc())
+}
+
+/// Picks the archive of each volume to restore from file names in a backup directory: the
+/// latest one, or the one taken at the given time
+pub fn pick_archives(
+    file_names: &[String],
+    volumes: &[String],
+    at: Option<DateTime<Utc>>,
+) -> BTreeMap<String, String> {
+    let mut picked: BTreeMap<String, (DateTime<Utc>, String)> = BTreeMap::new();
+
+    for file_name in file_names {
+        let Some((volume, timestamp)) = parse_archive_name(file_name) else {
+            continue;
+        };
+
+        if !volumes.contains(&volume) || at.is_some_and(|at| at != timestamp) {
+            continue;
+        }
+
+        if picked
+            .get(&volume)
+            .is_none_or(|(latest, _)| *latest < timestamp)
+        {
+            picked.insert(volume, (timestamp, file_name.to_string()));
+        }
+    }
+
+    picked
+        .into_iter()
+        .map(|(volume, (_, file_name))| (volume, file_name))
+        .collect()
+}
+
+/// Streams a volume through a tar container into a compressed archive
+pub fn backup_volume(volume: &str, archive: &Path) -> anyhow::Result<()> {
+    let file =
+        File::create(archive).with_context(|| format!("Failed to create {}", archive.display()))?;
+
+    let output = Command::new(DOCKER)
+        .args([
+            "run",
+            "--rm",
+            "-v",
+            &format!("{volume}:/volume:ro"),
+            TAR_IMAGE,
+        ])
+        .args(["tar", "-czf", "-", "-C", "/volume", "."])
+        .stdout(file)
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("Failed to back up volume {volume}"))?;
+
+    if !output.status.success() {
+        // a partial archive must not be mistaken for a backup
+        let _ = std::fs::remove_file(archive);
+        anyhow::bail!(
+            "Failed to back up volume {volume}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Replaces the contents of a volume with an archive streamed through a tar container
+pub fn restore_volume(volume: &str, archive: &Path) -> anyhow::Result<()> {
+    let file =
+        File::open(archive).with_context(|| format!("Failed to open {}", archive.display()))?;
+
+    let output = Command::new(DOCKER)
+        .args([
+            "run",
+            "--rm",
+            "-i",
+            "-v",
+            &format!("{volume}:/volume"),
+            TAR_IMAGE,
+        ])
+        .args(["sh", "-c", RESTORE_SCRIPT])
+        .stdin(file)
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .output()
+        .with_context(|| format!("Failed to restore volume {volume}"))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to restore volume {volume}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Lists the file names in a backup directory
+pub fn list_archives(dir: &Path) -> anyhow::Result<Vec<String>> {
+    let entries =
+        std::fs::read_dir(dir).with_context(|| format!("Failed to read {}", dir.display()))?;
+
+    let mut file_names = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect::<Vec<String>>();
+    file_names.sort();
+
+    Ok(file_names)
+}
+
+/// Stops or starts containers with docker
+pub fn set_running(containers: &[String], running: bool) -> anyhow::Result<()> {
+    if containers.is_empty() {
+        return Ok(());
+    }
+
+    let action = if running { "start" } else { "stop" };
+    let output = Command::new(DOCKER)
+        .arg(action)
+        .args(containers)
+        .output()
+        .with_context(|| format!("Failed to {action} containers"))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to {action} containers: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
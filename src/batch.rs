@@ -0,0 +1,108 @@
+use std::fmt;
+
+/// What an action did to one container of a batch
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchOutcome {
+    pub container: String,
+    /// Why the action failed, None when it worked
+    pub error: Option<String>,
+}
+
+/// How a batch went as a whole, which sets the exit code of the command
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BatchStatus {
+    /// Every container succeeded, or there were none
+    Succeeded,
+    /// Some containers failed, others succeeded
+    Partial,
+    /// Every container failed
+    Failed,
+}
+
+impl BatchStatus {
+    /// Exit code scripts can tell a partial failure apart by
+    pub fn exit_code(self) -> i32 {
+        match self {
+            BatchStatus::Succeeded => 0,
+            BatchStatus::Failed => 1,
+            BatchStatus::Partial => 2,
+        }
+    }
+}
+
+/// Outcomes of an action run on many containers, such as restarting a stack, collected so a
+/// single failure does not get lost in the scroll
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BatchResults {
+    pub outcomes: Vec<BatchOutcome>,
+}
+
+impl BatchResults {
+    pub fn succeeded(&mut self, container: &str) {
+        self.outcomes.push(BatchOutcome {
+            container: container.to_string(),
+            error: None,
+        });
+    }
+
+    pub fn failed(&mut self, container: &str, error: impl Into<String>) {
+        self.outcomes.push(BatchOutcome {
+            container: container.to_string(),
+            error: Some(error.into()),
+        });
+    }
+
+    /// Names of the containers the action failed on, in the order they were run
+    pub fn failed_containers(&self) -> Vec<String> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.error.is_some())
+            .map(|outcome| outcome.container.to_string())
+            .collect()
+    }
+
+    pub fn succeeded_count(&self) -> usize {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.error.is_none())
+            .count()
+    }
+
+    pub fn status(&self) -> BatchStatus {
+        match self.succeeded_count() {
+            count if count == self.outcomes.len() => BatchStatus::Succeeded,
+            0 => BatchStatus::Failed,
+            _ => BatchStatus::Partial,
+        }
+    }
+
+    /// Fails with the message when any container failed, carrying the exit code of the batch
+    pub fn into_result(self, message: String) -> Result<(), BatchError> {
+        match self.status() {
+            BatchStatus::Succeeded => Ok(()),
+            status => Err(BatchError { message, status }),
+        }
+    }
+}
+
+/// Error of a batch some or all containers failed in
+#[derive(Debug, Clone, PartialEq)]
+pub struct BatchError {
+    pub message: String,
+    pub status: BatchStatus,
+}
+
+impl fmt::Display for BatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for BatchError {}
+
+/// Exit code of a failed command: that of its batch when only part of it failed, 1 otherwise
+pub fn exit_code(error: &anyhow::Error) -> i32 {
+    error
+        .downcast_ref::<BatchError>()
+        .map_or(1, |batch| batch.status.exit_code())
+}
@@ -0,0 +1,242 @@
+use crate::image::ImageRef;
+use anyhow::Context;
+use serde_json::Value;
+use std::ffi::OsStr;
+use std::path::Path;
+use std::process::Command;
+
+const GIT: &str = "git";
+
+/// Change of the image of a service in a compose file
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageBump {
+    pub service: String,
+    /// Image as the compose file spelled it before
+    pub old_image: String,
+    pub new_image: String,
+    /// Line of the image in the compose file, counting from 1
+    pub line: usize,
+}
+
+/// A `key: value` line of a YAML block mapping
+struct MappingLine<'a> {
+    indent: usize,
+    key: &'a str,
+    /// Offset of the value within the line, after the colon
+    value_start: usize,
+}
+
+/// Splits a line of a block mapping into its key and value, skipping blank lines and comments
+fn mapping_line(line: &str) -> Option<MappingLine<'_>> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
+        return None;
+    }
+
+    let indent = line.len() - trimmed.len();
+    let (key, rest) = match trimmed.chars().next() {
+        Some(quote @ ('"' | '\'')) => {
+            let end = trimmed[1..].find(quote)? + 1;
+            (&trimmed[1..end], &trimmed[end + 1..])
+        }
+        _ => {
+            let end = trimmed.find(':')?;
+            (trimmed[..end].trim_end(), &trimmed[end..])
+        }
+    };
+    let rest = rest.trim_start().strip_prefix(':')?;
+
+    Some(MappingLine {
+        indent,
+        key,
+        value_start: line.len() - rest.len(),
+    })
+}
+
+/// Lines nested below the line at `start`, which belong to the mapping it opens
+fn block_end(lines: &[&str], start: usize, indent: usize) -> usize {
+    lines[start + 1..]
+        .iter()
+        .position(|line| {
+            let trimmed = line.trim_start();
+            !trimmed.is_empty() && !trimmed.starts_with('#') && line.len() - trimmed.len() <= indent
+        })
+        .map_or(lines.len(), |offset| start + 1 + offset)
+}
+
+/// Finds the key of a mapping among the lines of its parent, which are the least indented ones
+fn find_key(lines: &[&str], start: usize, end: usize, key: &str) -> Option<(usize, usize)> {
+    let children = (start..end).filter_map(|index| Some((index, mapping_line(lines[index])?)));
+    let indent = children.clone().map(|(_, line)| line.indent).min()?;
+
+    children
+        .filter(|(_, line)| line.indent == indent && line.key == key)
+        .map(|(index, line)| (index, line.indent))
+        .next()
+}
+
+/// Splits the value of a line into the value itself and what follows it, such as a comment
+fn split_value(value: &str) -> (&str, &str) {
+    let trimmed = value.trim_start();
+    let leading = value.len() - trimmed.len();
+
+    let end = match trimmed.chars().next() {
+        Some(quote @ ('"' | '\'')) => trimmed[1..]
+            .find(quote)
+            .map_or(trimmed.len(), |end| end + 2),
+        _ => {
+            let end = trimmed.find(" #").unwrap_or(trimmed.len());
+            trimmed[..end].trim_end().len()
+        }
+    };
+
+    (&value[..leading + end], &value[leading + end..])
+}
+
+/// Image reference with its tag replaced and its digest dropped, keeping the spelling of the
+/// repository
+pub fn retag(image: &str, tag: &str) -> anyhow::Result<String> {
+    let name = image.split_once('@').map_or(image, |(name, _)| name);
+    // a colon after the last slash starts the tag, before it is a registry port
+    let name = match name.rsplit_once(':') {
+        Some((repository, tag)) if !tag.contains('/') => repository,
+        _ => name,
+    };
+
+    let retagged = format!("{name}:{tag}");
+    ImageRef::parse(&retagged)?;
+
+    Ok(retagged)
+}
+
+/// Sets the tag of the image of a service in the contents of a compose file, editing only the
+/// image line so comments and formatting stay as they are. Returns `None` when the file does
+/// not set an image for the service.
+pub fn bump_image(
+    contents: &str,
+    service: &str,
+    tag: &str,
+) -> anyhow::Result<Option<(String, ImageBump)>> {
+    let lines = contents.lines().collect::<Vec<&str>>();
+
+    let Some((services, services_indent)) = find_key(&lines, 0, lines.len(), "services") else {
+        return Ok(None);
+    };
+    let services_end = block_end(&lines, services, services_indent);
+    let Some((service_line, service_indent)) =
+        find_key(&lines, services + 1, services_end, service)
+    else {
+        return Ok(None);
+    };
+    let service_end = block_end(&lines, service_line, service_indent);
+    let Some((image_line, _)) = find_key(&lines, service_line + 1, service_end, "image") else {
+        return Ok(None);
+    };
+
+    let line = lines[image_line];
+    let value_start = mapping_line(line)
+        .map(|mapping| mapping.value_start)
+        .unwrap_or(line.len());
+    let (value, rest) = split_value(&line[value_start..]);
+    let quote = value
+        .trim_start()
+        .chars()
+        .next()
+        .filter(|c| matches!(c, '"' | '\''));
+    let old_image = value
+        .trim()
+        .trim_matches(|c| c == '"' || c == '\'')
+        .to_string();
+
+    if old_image.contains('$') {
+        anyhow::bail!(
+            "Image of {service} is set through a variable, edit where {old_image} is defined instead"
+        );
+    }
+
+    let new_image = retag(&old_image, tag)?;
+    let new_value = match quote {
+        Some(quote) => format!("{quote}{new_image}{quote}"),
+        None => new_image.clone(),
+    };
+    let leading = &value[..value.len() - value.trim_start().len()];
+    let edited_line = format!("{}{leading}{new_value}{rest}", &line[..value_start]);
+
+    let mut edited = lines
+        .iter()
+        .enumerate()
+        .map(|(index, line)| {
+            if index == image_line {
+                &edited_line
+            } else {
+                *line
+            }
+        })
+        .collect::<Vec<&str>>()
+        .join("\n");
+    if contents.ends_with('\n') {
+        edited.push('\n');
+    }
+
+    // make sure the edit landed where a YAML parser sees the image of the service
+    let parsed: Value =
+        serde_yaml::from_str(&edited).context("Compose file no longer parses after the edit")?;
+    if parsed["services"][service]["image"].as_str() != Some(new_image.as_str()) {
+        anyhow::bail!("Failed to locate the image of {service} in the compose file");
+    }
+
+    Ok(Some((
+        edited,
+        ImageBump {
+            service: service.to_string(),
+            old_image,
+            new_image,
+            line: image_line + 1,
+        },
+    )))
+}
+
+/// Conventional commit message for a bump
+pub fn commit_message(project: &str, bump: &ImageBump) -> String {
+    format!(
+        "chore({project}): bump {} to {}\n\nUpdate the image of {} from {} to {}.",
+        bump.service, bump.new_image, bump.service, bump.old_image, bump.new_image
+    )
+}
+
+/// Commits a compose file with a message, leaving anything else staged in the repository alone
+pub fn commit_file(file: &Path, message: &str) -> anyhow::Result<()> {
+    let dir = file.parent().unwrap_or(Path::new("."));
+    let file_name = file.file_name().unwrap_or(file.as_os_str());
+
+    run_git(dir, &["add".as_ref(), "--".as_ref(), file_name])?;
+    run_git(
+        dir,
+        &[
+            "commit".as_ref(),
+            "-m".as_ref(),
+            message.as_ref(),
+            "--".as_ref(),
+            file_name,
+        ],
+    )
+}
+
+fn run_git(dir: &Path, args: &[&OsStr]) -> anyhow::Result<()> {
+    let output = Command::new(GIT)
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .context("Failed to run git")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args[0].to_string_lossy(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
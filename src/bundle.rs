@@ -0,0 +1,446 @@
+use crate::config::{cache_dir, config_dir, create_private_dir, state_dir};
+use crate::hostinfo::host_name;
+use crate::secrets::IDENTITY_FILE;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use toml_edit::{DocumentMut, Item, Value};
+
+const TAR: &str = "tar";
+/// Version of the bundle layout, bumped when older versions could no longer import it
+pub const BUNDLE_VERSION: u32 = 1;
+/// Placeholder replacing secrets in exported config files
+pub const REDACTED: &str = "<redacted>";
+const MANIFEST_FILE: &str = "manifest.json";
+const CONFIG_FILE: &str = "config.toml";
+/// Directories within a bundle holding the config and state directories
+const BUNDLE_CONFIG_DIR: &str = "config";
+const BUNDLE_STATE_DIR: &str = "state";
+/// Words in config keys that hold secrets, such as `secret` or `api_token`
+const SECRET_WORDS: [&str; 4] = ["secret", "password", "token", "webhook"];
+
+/// Describes what a bundle holds and where it comes from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Manifest {
+    pub version: u32,
+    pub created_at: DateTime<Utc>,
+    pub host: String,
+    pub dsd_util_version: String,
+    /// Config keys whose values were replaced by the placeholder, such as daemon.secret
+    pub redacted: Vec<String>,
+}
+
+/// Outcome of importing a bundle
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ImportSummary {
+    pub written: Vec<PathBuf>,
+    /// Files already present with the same contents
+    pub unchanged: Vec<PathBuf>,
+    /// Secrets still redacted, as the config on this host did not have them either
+    pub missing_secrets: Vec<String>,
+}
+
+/// Whether a config key holds a secret, by its path such as notify.channels.ops.url. Channel urls
+/// carry tokens or SMTP passwords, urls elsewhere are plain addresses.
+pub fn is_secret(path: &[&str]) -> bool {
+    let Some(key) = path.last() else {
+        return false;
+    };
+    let key = key.to_ascii_lowercase();
+
+    SECRET_WORDS.iter().any(|word| key.contains(word))
+        || matches!(path, ["notify", "channels", _, "url"])
+}
+
+/// Url with the credentials before its host replaced by the placeholder, such as a probe url
+/// of `https://<redacted>@example.com/health`, or None when it carries none
+pub fn redact_userinfo(text: &str) -> Option<String> {
+    let authority_start = text.find("://")? + 3;
+    let authority_end = text[authority_start..]
+        .find(['/', '?', '#'])
+        .map_or(text.len(), |end| authority_start + end);
+    let at = text[authority_start..authority_end].rfind('@')? + authority_start;
+
+    Some(format!(
+        "{}{REDACTED}{}",
+        &text[..authority_start],
+        &text[at..]
+    ))
+}
+
+/// Calls a function with the path of every string in a TOML item, such as probes.0.url
+fn visit_strings(item: &mut Item, path: &mut Vec<String>, f: &mut dyn FnMut(&[&str], &mut Value)) {
+    match item {
+        Item::Value(value) => visit_value(value, path, f),
+        Item::Table(table) => {
+            for (key, item) in table.iter_mut() {
+                path.push(key.get().to_string());
+                visit_strings(item, path, f);
+                path.pop();
+            }
+        }
+        Item::ArrayOfTables(tables) => {
+            for (index, table) in tables.iter_mut().enumerate() {
+                path.push(index.to_string());
+                for (key, item) in table.iter_mut() {
+                    path.push(key.get().to_string());
+                    visit_strings(item, path, f);
+                    path.pop();
+                }
+                path.pop();
+            }
+        }
+        Item::None => {}
+    }
+}
+
+fn visit_value(value: &mut Value, path: &mut Vec<String>, f: &mut dyn FnMut(&[&str], &mut Value)) {
+    match value {
+        Value::String(_) => {
+            let path = path.iter().map(String::as_str).collect::<Vec<&str>>();
+            f(&path, value);
+        }
+        Value::Array(array) => {
+            for (index, value) in array.iter_mut().enumerate() {
+                path.push(index.to_string());
+                visit_value(value, path, f);
+                path.pop();
+            }
+        }
+        Value::InlineTable(table) => {
+            for (key, value) in table.iter_mut() {
+                path.push(key.get().to_string());
+                visit_value(value, path, f);
+                path.pop();
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Replaces a string value, keeping the comments and spacing around it
+fn replace_string(value: &mut Value, replacement: &str) {
+    let decor = value.decor().clone();
+    *value = Value::from(replacement);
+    *value.decor_mut() = decor;
+}
+
+/// Replaces the secrets in the contents of a config file by a placeholder, keeping comments and
+/// formatting. Returns the redacted contents and the keys of the secrets.
+pub fn redact_secrets(config: &str) -> anyhow::Result<(String, Vec<String>)> {
+    let mut document = config
+        .parse::<DocumentMut>()
+        .context("Failed to parse config file")?;
+    let mut redacted = vec![];
+
+    visit_strings(document.as_item_mut(), &mut vec![], &mut |path, value| {
+        if is_secret(path) {
+            replace_string(value, REDACTED);
+            redacted.push(path.join("."));
+        } else if let Some(url) = value.as_str().and_then(redact_userinfo) {
+            replace_string(value, &url);
+            redacted.push(path.join("."));
+        }
+    });
+
+    Ok((document.to_string(), redacted))
+}
+
+/// Fills secrets redacted in a bundled config file from the config file already on the host,
+/// where it sets them at the same keys, taking urls with redacted credentials from it as a whole.
+/// Returns the contents and the keys still redacted.
+pub fn restore_secrets(bundled: &str, existing: &str) -> anyhow::Result<(String, Vec<String>)> {
+    let mut document = bundled
+        .parse::<DocumentMut>()
+        .context("Failed to parse config file of the bundle")?;
+    let mut existing = existing
+        .parse::<DocumentMut>()
+        .context("Failed to parse config file")?;
+
+    let mut secrets = vec![];
+    visit_strings(existing.as_item_mut(), &mut vec![], &mut |path, value| {
+        if let Some(secret) = value.as_str().filter(|secret| !secret.contains(REDACTED)) {
+            secrets.push((path.join("."), secret.to_string()));
+        }
+    });
+
+    let mut missing = vec![];
+    visit_strings(document.as_item_mut(), &mut vec![], &mut |path, value| {
+        if !value.as_str().is_some_and(|text| text.contains(REDACTED)) {
+            return;
+        }
+
+        let key = path.join(".");
+        match secrets.iter().find(|(other, _)| *other == key) {
+            Some((_, secret)) => replace_string(value, secret),
+            None => missing.push(key),
+        }
+    });
+
+    Ok((document.to_string(), missing))
+}
+
+/// Relative paths of the files below a directory, sorted, leaving out the directory `skip`
+fn list_files(dir: &Path, skip: Option<&Path>) -> anyhow::Result<Vec<PathBuf>> {
+    let mut files = vec![];
+    if !dir.is_dir() {
+        return Ok(files);
+    }
+
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = std::fs::read_dir(&current)
+            .with_context(|| format!("Failed to read {}", current.display()))?;
+
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            if Some(path.as_path()) == skip {
+                continue;
+            }
+
+            if path.is_dir() {
+                pending.push(path);
+            } else if let Ok(relative) = path.strip_prefix(dir) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+
+    files.sort();
+    Ok(files)
+}
+
+/// Fails on the first symbolic link below a directory, which could point an untrusted bundle
+/// at files outside of it
+fn reject_symlinks(dir: &Path) -> anyhow::Result<()> {
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(current) = pending.pop() {
+        let entries = std::fs::read_dir(&current)
+            .with_context(|| format!("Failed to read {}", current.display()))?;
+
+        for entry in entries.filter_map(|entry| entry.ok()) {
+            let path = entry.path();
+            let metadata = std::fs::symlink_metadata(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+
+            if metadata.file_type().is_symlink() {
+                anyhow::bail!(
+                    "Bundle contains a symbolic link, refusing to import it: {}",
+                    path.strip_prefix(dir).unwrap_or(&path).display()
+                );
+            }
+            if metadata.is_dir() {
+                pending.push(path);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn create_parent(path: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+
+    Ok(())
+}
+
+fn copy_file(from: &Path, to: &Path) -> anyhow::Result<()> {
+    create_parent(to)?;
+    std::fs::copy(from, to)
+        .with_context(|| format!("Failed to copy {} to {}", from.display(), to.display()))?;
+
+    Ok(())
+}
+
+fn write_file(path: &Path, contents: &[u8]) -> anyhow::Result<()> {
+    create_parent(path)?;
+    std::fs::write(path, contents).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+fn run_tar(args: &[&std::ffi::OsStr]) -> anyhow::Result<()> {
+    let output = Command::new(TAR)
+        .args(args)
+        .output()
+        .context("Failed to run tar")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "tar failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Scratch directory only the user can access, removed once dropped
+struct Staging(PathBuf);
+
+impl Staging {
+    fn new(purpose: &str) -> anyhow::Result<Self> {
+        Ok(Staging(create_private_dir(purpose)?))
+    }
+}
+
+impl Drop for Staging {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Writes the config and state directories to a bundle, with the secrets of the config file
+/// redacted and caches left out, as they are rebuilt on their own
+pub fn export_bundle(output: &Path) -> anyhow::Result<Manifest> {
+    let staging = Staging::new("export")?;
+    let config_dir = config_dir()?;
+    let state_dir = state_dir()?;
+    let mut redacted = vec![];
+
+    for file in list_files(&config_dir, None)? {
+        // the key decrypting the secrets is moved by hand, so a bundle alone does not reveal them
+        if file == Path::new(IDENTITY_FILE) {
+            continue;
+        }
+        let target = staging.0.join(BUNDLE_CONFIG_DIR).join(&file);
+
+        if file == Path::new(CONFIG_FILE) {
+            let path = config_dir.join(&file);
+            let contents = std::fs::read_to_string(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let (contents, keys) = redact_secrets(&contents)?;
+            redacted = keys;
+
+            write_file(&target, contents.as_bytes())?;
+        } else {
+            copy_file(&config_dir.join(&file), &target)?;
+        }
+    }
+
+    for file in list_files(&state_dir, Some(&cache_dir()?))? {
+        copy_file(
+            &state_dir.join(&file),
+            &staging.0.join(BUNDLE_STATE_DIR).join(&file),
+        )?;
+    }
+
+    let manifest = Manifest {
+        version: BUNDLE_VERSION,
+        created_at: Utc::now(),
+        host: host_name(),
+        dsd_util_version: env!("CARGO_PKG_VERSION").to_string(),
+        redacted,
+    };
+    std::fs::write(
+        staging.0.join(MANIFEST_FILE),
+        serde_json::to_string_pretty(&manifest)?,
+    )
+    .context("Failed to write bundle manifest")?;
+
+    run_tar(&[
+        "-czf".as_ref(),
+        output.as_os_str(),
+        "-C".as_ref(),
+        staging.0.as_os_str(),
+        ".".as_ref(),
+    ])
+    .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    Ok(manifest)
+}
+
+/// Reads the manifest of a bundle without importing it
+fn read_manifest(dir: &Path) -> anyhow::Result<Manifest> {
+    let contents = std::fs::read_to_string(dir.join(MANIFEST_FILE))
+        .context("Not a dsd-util bundle, it has no manifest")?;
+    let manifest: Manifest =
+        serde_json::from_str(&contents).context("Failed to parse bundle manifest")?;
+
+    if manifest.version > BUNDLE_VERSION {
+        anyhow::bail!(
+            "Bundle version {} is newer than this dsd-util supports ({BUNDLE_VERSION}), update dsd-util first",
+            manifest.version
+        );
+    }
+
+    Ok(manifest)
+}
+
+/// Restores the config and state directories from a bundle. Files that differ from those on the
+/// host are only overwritten with `force`, redacted secrets are taken from the config file
+/// already on the host where it has them.
+pub fn import_bundle(bundle: &Path, force: bool) -> anyhow::Result<(Manifest, ImportSummary)> {
+    let staging = Staging::new("import")?;
+    run_tar(&[
+        "-xzf".as_ref(),
+        bundle.as_os_str(),
+        "-C".as_ref(),
+        staging.0.as_os_str(),
+    ])
+    .with_context(|| format!("Failed to unpack {}", bundle.display()))?;
+    reject_symlinks(&staging.0)?;
+
+    let manifest = read_manifest(&staging.0)?;
+    let mut summary = ImportSummary::default();
+
+    // contents to write, next to where to write them
+    let mut files: Vec<(PathBuf, Vec<u8>)> = vec![];
+    for (bundle_dir, target_dir) in [
+        (BUNDLE_CONFIG_DIR, config_dir()?),
+        (BUNDLE_STATE_DIR, state_dir()?),
+    ] {
+        let dir = staging.0.join(bundle_dir);
+
+        for file in list_files(&dir, None)? {
+            let path = dir.join(&file);
+            let mut contents = std::fs::read(&path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let target = target_dir.join(&file);
+
+            if bundle_dir == BUNDLE_CONFIG_DIR && file == Path::new(CONFIG_FILE) {
+                let existing = std::fs::read_to_string(&target).unwrap_or_default();
+                let (restored, missing) =
+                    restore_secrets(&String::from_utf8_lossy(&contents), &existing)?;
+                contents = restored.into_bytes();
+                summary.missing_secrets = missing;
+            }
+
+            files.push((target, contents));
+        }
+    }
+
+    let mut conflicts = vec![];
+    for (target, contents) in &files {
+        match std::fs::read(target) {
+            Ok(existing) if existing == *contents => summary.unchanged.push(target.clone()),
+            Ok(_) => conflicts.push(target.clone()),
+            Err(_) => {}
+        }
+    }
+
+    if !conflicts.is_empty() && !force {
+        anyhow::bail!(
+            "Files on this host differ from the bundle, use --force to overwrite them: {}",
+            conflicts
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<String>>()
+                .join(", ")
+        );
+    }
+
+    for (target, contents) in files {
+        if summary.unchanged.contains(&target) {
+            continue;
+        }
+
+        write_file(&target, &contents)?;
+        summary.written.push(target);
+    }
+
+    Ok((manifest, summary))
+}
@@ -0,0 +1,111 @@
+use crate::commands::DOCKER;
+use crate::parsers::parse_image_metadata;
+use crate::utils::UpdateResult;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+/// Version of the packaged software, as set by most image builds
+pub const LABEL_OCI_VERSION: &str = "org.opencontainers.image.version";
+/// Source control revision the image was built from
+pub const LABEL_OCI_REVISION: &str = "org.opencontainers.image.revision";
+
+/// What an image says about itself
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageMetadata {
+    pub id: String,
+    /// When the image was built, as RFC3339
+    pub created: Option<String>,
+    pub version: Option<String>,
+    pub revision: Option<String>,
+    pub size: u64,
+}
+
+/// An image an update replaced, along with the image it replaced
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ImageChange {
+    pub image: String,
+    /// Missing when the image was pulled for the first time or the old one is gone already
+    pub previous: Option<ImageMetadata>,
+    pub current: ImageMetadata,
+}
+
+impl ImageChange {
+    /// Growth of the image in bytes, negative when it shrank
+    pub fn size_delta(&self) -> Option<i64> {
+        self.previous
+            .as_ref()
+            .map(|previous| self.current.size as i64 - previous.size as i64)
+    }
+}
+
+/// Entry of a changelog file, one per update that changed images
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ChangelogEntry {
+    pub updated_at: DateTime<Utc>,
+    pub changes: Vec<ImageChange>,
+}
+
+/// Compares the images pulled by an update with the ones they replaced
+pub fn image_changes(pulled: &HashMap<String, UpdateResult>) -> anyhow::Result<Vec<ImageChange>> {
+    let mut updated = pulled
+        .values()
+        .filter(|result| result.is_updated())
+        .collect::<Vec<&UpdateResult>>();
+    updated.sort_by(|a, b| a.image.cmp(&b.image));
+
+    let ids = updated
+        .iter()
+        .flat_map(|result| [Some(&result.current_id), result.previous_id.as_ref()])
+        .flatten()
+        .map(String::as_str)
+        .collect::<Vec<&str>>();
+
+    let metadata = inspect_metadata(&ids)?;
+    let find = |id: &str| metadata.iter().find(|image| image.id == id).cloned();
+
+    Ok(updated
+        .into_iter()
+        .filter_map(|result| {
+            Some(ImageChange {
+                image: result.image.to_string(),
+                previous: result.previous_id.as_deref().and_then(find),
+                current: find(&result.current_id)?,
+            })
+        })
+        .collect())
+}
+
+/// Inspects images by ID, leaving out the ones that no longer exist
+fn inspect_metadata(ids: &[&str]) -> anyhow::Result<Vec<ImageMetadata>> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    // docker still prints the images it found when some are missing, only failing afterwards
+    let output = Command::new(DOCKER)
+        .args(["image", "inspect"])
+        .args(ids)
+        .output()
+        .context("Failed to inspect images")?;
+
+    parse_image_metadata(&output.stdout)
+}
+
+/// Appends an update to a changelog file of JSON lines, creating it if needed
+pub fn append_changelog(path: &Path, entry: &ChangelogEntry) -> anyhow::Result<()> {
+    let mut line = serde_json::to_string(entry).context("Failed to serialize changelog")?;
+    line.push('\n');
+
+    OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| file.write_all(line.as_bytes()))
+        .with_context(|| format!("Failed to write changelog: {}", path.display()))
+}
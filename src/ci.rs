@@ -0,0 +1,74 @@
+use crate::batch::exit_code;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+static CI: AtomicBool = AtomicBool::new(false);
+
+/// Facts recorded by the running command for the final summary
+static SUMMARY: Mutex<BTreeMap<String, Value>> = Mutex::new(BTreeMap::new());
+
+/// Enables CI mode: no color or prompts, grouped output and a final JSON summary
+pub fn set_ci(ci: bool) {
+    CI.store(ci, Ordering::SeqCst);
+}
+
+/// Determine if CI mode was requested
+pub fn is_ci() -> bool {
+    CI.load(Ordering::SeqCst)
+}
+
+/// Starts a collapsible group of output in CI logs, understood by GitHub Actions
+pub fn start_group(name: &str) {
+    if is_ci() {
+        println!("::group::{name}");
+    }
+}
+
+/// Ends the group started by start_group
+pub fn end_group() {
+    if is_ci() {
+        println!("::endgroup::");
+    }
+}
+
+/// Records a fact about the run, such as the number of updated containers, for the summary
+pub fn record(key: &str, value: impl Into<Value>) {
+    if let Ok(mut summary) = SUMMARY.lock() {
+        summary.insert(key.to_string(), value.into());
+    }
+}
+
+/// Builds the single line JSON summary of a command run
+pub fn summary_line(command: &str, result: &anyhow::Result<()>, elapsed: Duration) -> String {
+    let mut summary = SUMMARY
+        .lock()
+        .map(|summary| summary.clone().into_iter().collect::<Map<String, Value>>())
+        .unwrap_or_default();
+
+    summary.insert("command".to_string(), command.into());
+    summary.insert("success".to_string(), result.is_ok().into());
+    summary.insert(
+        "exit_code".to_string(),
+        match result {
+            Ok(()) => 0,
+            Err(e) => exit_code(e),
+        }
+        .into(),
+    );
+    summary.insert(
+        "error".to_string(),
+        match result {
+            Ok(()) => Value::Null,
+            Err(e) => format!("{e:#}").into(),
+        },
+    );
+    summary.insert(
+        "duration_ms".to_string(),
+        (elapsed.as_millis() as u64).into(),
+    );
+
+    Value::Object(summary).to_string()
+}
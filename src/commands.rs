@@ -1,22 +1,141 @@
-use crate::printer::{color_println, color_println_fmt, Color};
+use crate::activation::activated_listener;
+use crate::backup::{
+    archive_path, backup_volume, containers_using, list_archives, parse_archive_timestamp,
+    pick_archives, restore_volume, set_running, stack_volumes,
+};
+use crate::batch::{BatchResults, BatchStatus};
+use crate::bump::{bump_image, commit_file, commit_message};
+use crate::bundle::{export_bundle, import_bundle};
+use crate::changelog::{
+    append_changelog, image_changes, ChangelogEntry, ImageChange, ImageMetadata,
+};
+use crate::ci::{end_group, is_ci, record, start_group};
+use crate::config::{create_private_dir, Config, ProbeConfig, VerifyMode};
+use crate::conflicts::{declared_services, find_project_conflicts, DeclaredService};
+use crate::context::project_name;
+use crate::controls::{
+    read_keys, typed_container, ControlAction, KeyControls, RawInput, MAX_HELD_LINES,
+};
+use crate::copy::{docker_cp, resolve_service, service_directory, CopyLocation};
+use crate::cron::CronSchedule;
+use crate::daemon::{
+    fetch_status, hook_windows, report_window, send_trigger, serve_webhooks, webhook_secret,
+    DeployPolicy,
+};
+use crate::deploy::{
+    annotations_enabled, github_annotation, write_github_outputs, write_override, ImageOverride,
+};
+use crate::docker::{list_all_containers, Container, LogAlert, LogFormat, LogWindow, Logger};
+use crate::doctor::{preflight, run_checks, CheckStatus};
+use crate::document::{Document, DocumentFormat};
+use crate::drift::stack_drift;
+use crate::environment::{diff_env, display_value, inspect_container_env, ContainerEnv};
+use crate::error::DsdError;
+use crate::exclusions::{short_digest, UpdateRules};
+use crate::exporter::serve_metrics;
+use crate::graph::{
+    build_stack_graph, render_dot, render_mermaid, render_text, render_tree, sort_by_dependencies,
+    GraphFormat,
+};
+use crate::history::{record_action, Action, HistoryEntry};
+use crate::hostinfo::LoadGuard;
+use crate::i18n::{tr, tr_args};
+use crate::image::{same_image, ImageRef};
+use crate::jobs::{group_by_stack, run_parallel};
+use crate::lifecycle::Lifecycle;
+use crate::logaudit::{
+    find_double_logging, inspect_log_configs, log_files_size, write_logging_override,
+    DoubleLogging, LogConfig,
+};
+use crate::logger::{
+    parse_log_level, LogLevel, LogMultiplexer, LogOutputs, RecordStart, DEFAULT_RECORD_START,
+};
+use crate::maintenance::{
+    clear_maintenance, load_maintenance, running_stacks, save_maintenance, FrozenStack,
+    MaintenanceState,
+};
+use crate::mute::{find_mute, load_mutes, mute, unmute, Mute};
+use crate::notify::{notify_events, Event, EventSource, Notifier, Severity};
+use crate::pin::{
+    pinned_reference, pinned_service_image, repo_digests, select_repo_digest, unpinned_reference,
+    write_pin_override,
+};
+use crate::plan::{find_compose_files, load_compose_config, plan_stack, PlanAction};
+use crate::ports::{find_conflicts, port_mappings, PortMapping};
+use crate::printer::{
+    color_println, color_println_fmt, container_color, hyperlink, is_accessible, is_deterministic,
+    link_padded, role_color, volatile, Color, Role,
+};
+use crate::privileges::{daemon_user_check, drop_privileges, DaemonUser};
+use crate::probes::{
+    load_history, percentile, record_result, run_probe, ProbeHistoryEntry, ProbeResult,
+};
+use crate::prune::{
+    dangling_image_ids, inspect_disk_containers, inspect_images, log_sizes, plan_prune, remove,
+    repository, stack_usage, volume_sizes,
+};
+use crate::queue::{QueuedAction, Trigger};
+use crate::reboot::{install_thaw_unit, reboot_host, reboot_required, THAW_UNIT};
+use crate::record::{append_samples, load_samples, summarize, StatsSample};
+use crate::registry::{docker_login, login_registry, read_password_stdin, RegistryLogin};
+use crate::report::{compile_daily_report, render_report};
+use crate::rollback::{
+    load_rollbacks, record_rollbacks, roll_back, save_rollbacks, select_targets, RollbackTarget,
+};
+use crate::sbom::{compare_versions, is_license_disallowed, license_ids, load_sbom, Sbom};
+use crate::scaffold::{scaffold_project, Template};
+use crate::scale::{service_replicas, ServiceScale};
+use crate::secrets::{load_secrets, remove_secret, secret_value, set_secret};
+use crate::security::{
+    inspect_container_users, inspect_security_profiles, non_root_suggestion, ContainerUser,
+};
+use crate::selector::{
+    complete_containers, correct_typo, expand_groups, expand_stack_names, is_fuzzy,
+    match_container, resolve_containers, resolve_containers_including_stopped, ContainerMatch,
+};
+use crate::selfupdate::{
+    archive_name, download, extract_binary, has_cosign, latest_release, release_target,
+    replace_executable, verify_checksum, verify_signature, Release, ReleaseAsset,
+};
+use crate::ship::{parse_ship_target, ShipTarget};
+use crate::shutdown::{handle_ctrl_c, shutdown_requested, sleep_unless_shutdown};
+use crate::timeline::build_timeline;
+use crate::top::{container_processes, sort_processes, TopSort};
+use crate::units::{format_duration, NumberFormat};
 use crate::utils::{
-    get_containers_from_stack, get_timestamp, is_terminal, kill_containers, list_containers,
-    parse_inspect_data, parse_stats_data, spawn_container_logger, update_container_by_name,
-    InspectData, StatsData,
+    compose_command, detect_shell, format_time, get_compose_info, get_container_image,
+    get_container_image_id, get_container_labels, get_containers_from_stack, get_image_size,
+    get_stack_services, get_stats, get_timestamp, inspect_containers, is_terminal, kill_containers,
+    list_containers, matches_pattern, pull_images, recreate_container,
+    recreate_standalone_container, set_restart_policy, sort_stats, ComposeInfo, InspectData,
+    StatsData, StatsSort, UpdateResult, LABEL_COMPOSE_PROJECT,
 };
+use crate::verify::{restore_previous_image, should_verify, verify_image};
+use crate::watch::{WatchEvent, Watcher};
 use anyhow::Context;
+use std::cmp::Ordering;
 use std::collections::hash_map::HashMap;
-use std::io::{self, BufRead, BufReader, Write};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use std::io::{self, BufRead, BufReader, IsTerminal, Write};
+use std::net::TcpListener;
+use std::path::{Path, PathBuf};
 use std::process::{Command, Stdio};
+use std::task::Poll;
 
 pub const DOCKER: &str = "docker";
 const DSD: &str = "docker-stack-deploy";
+/// How often containers are checked while waiting to roll them back
+const AUTO_ROLLBACK_INTERVAL: std::time::Duration = std::time::Duration::from_secs(2);
 const PATH_DSD_COMPOSE: &str = "/var/lib/docker-stack-deploy/compose.yml";
 
 /// Initializes a new instance of docker-stack-deploy using bootstrap script
 pub fn init(project_dir: String, git_url: String) -> anyhow::Result<()> {
+    // there is no terminal to attach to in CI
+    let interactive_args: &[&str] = if is_ci() { &[] } else { &["-it"] };
+
     Command::new(DOCKER)
-        .args(["run", "--rm", "-it"])
+        .args(["run", "--rm"])
+        .args(interactive_args)
         .args(["-v", "/var/run/docker.sock:/var/run/docker.sock"])
         .args(["-v", &format!("{project_dir}:{project_dir}")])
         .args(["ghcr.io/wez/docker-stack-deploy"])
@@ -33,10 +152,13 @@ pub fn init(project_dir: String, git_url: String) -> anyhow::Result<()> {
     if use_color {
         color_println(
             Color::Green,
-            "Bootstrap success! Following docker-stack-deploy logs...",
+            &tr("Bootstrap success! Following docker-stack-deploy logs..."),
         );
     } else {
-        println!("Bootstrap success! Following docker-stack-deploy logs...")
+        println!(
+            "{}",
+            tr("Bootstrap success! Following docker-stack-deploy logs...")
+        )
     }
 
     println!();
@@ -68,7 +190,7 @@ pub fn init(project_dir: String, git_url: String) -> anyhow::Result<()> {
             if use_color {
                 println!(
                     "[{} | {}] {}",
-                    color_println_fmt(Color::Cyan, &get_timestamp()),
+                    color_println_fmt(role_color(Role::Timestamp), &get_timestamp()),
                     color_println_fmt(Color::Magenta, DSD),
                     line
                 );
@@ -88,332 +210,4066 @@ pub fn init(project_dir: String, git_url: String) -> anyhow::Result<()> {
     Ok(())
 }
 
-/// Shows logs for specified containers
-pub fn logs(
-    containers: Option<Vec<String>>,
-    stacks: Option<Vec<String>>,
-    tail: u32,
-    all: bool,
-) -> anyhow::Result<()> {
-    let use_color = is_terminal();
+/// Prints the relationship graph of a stack
+pub fn graph(stack: String, output: GraphFormat, ascii: bool) -> anyhow::Result<()> {
+    let graph = build_stack_graph(&stack)?;
 
-    let containers = if all {
-        let container_ids = list_containers()?;
+    let rendered = match output {
+        GraphFormat::Text => render_text(&graph, ascii || is_accessible()),
+        GraphFormat::Tree => render_tree(&graph, ascii || is_accessible()),
+        GraphFormat::Dot => render_dot(&graph),
+        GraphFormat::Mermaid => render_mermaid(&graph),
+    };
 
-        if container_ids.is_empty() {
-            if use_color {
-                color_println(Color::Red, "No containers running");
-            } else {
-                println!("No containers running");
-            }
-            return Ok(());
-        }
+    print!("{rendered}");
 
-        container_ids
-    } else if let Some(containers) = containers {
-        containers
-    } else if let Some(stacks) = stacks {
-        let mut containers = vec![];
+    Ok(())
+}
 
-        for stack in &stacks {
-            let container_names = get_containers_from_stack(stack)?;
-            containers.extend(container_names);
-        }
+/// Prints host ports published by containers, flagging ports claimed by more than one container
+pub fn ports(stack: Option<String>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let links = Config::load()?.links;
+    let links_host = links.host.as_deref().unwrap_or("localhost");
 
-        containers
-    } else {
-        anyhow::bail!("Must specify containers, use --stacks (-s) or use --all (-a)")
+    // conflicts are found across all stacks, including stopped containers
+    let containers = list_all_containers()?;
+    let names = containers
+        .iter()
+        .map(|container| container.name.to_string())
+        .collect::<Vec<String>>();
+    let mappings = port_mappings(&containers, &inspect_containers(&names)?);
+    let conflicts = find_conflicts(&mappings);
+
+    let in_stack = |container_stack: &Option<String>| match &stack {
+        Some(stack) => container_stack.as_ref() == Some(stack),
+        None => true,
     };
 
-    if use_color {
-        color_println(
-            Color::Cyan,
-            &format!("Following logs for container: {}", &containers.len()),
-        );
-    } else {
-        println!("Following logs for container: {}", &containers.len());
+    if stack.is_some()
+        && !containers
+            .iter()
+            .any(|container| in_stack(&container.stack))
+    {
+        anyhow::bail!(tr_args(
+            "No containers found in stack: {stack}",
+            &[("stack", &stack.as_deref().unwrap_or_default())]
+        ));
     }
-    let (tx, rx) = std::sync::mpsc::channel::<String>();
-    let mut handles: Vec<std::thread::JoinHandle<()>> = vec![];
 
-    for container in containers {
-        let tx = tx.clone();
-        let is_container_id = all;
-        let handle = spawn_container_logger(&container, is_container_id, use_color, tail, tx)
-            .with_context(|| format!("Failed to spawn container logger for {container}"))?;
-        handles.push(handle);
+    let mappings = mappings
+        .iter()
+        .filter(|mapping| in_stack(&mapping.stack))
+        .collect::<Vec<_>>();
+    let conflicts = conflicts
+        .iter()
+        .filter(|conflict| {
+            conflict
+                .containers
+                .iter()
+                .any(|(_, container_stack)| in_stack(container_stack))
+        })
+        .collect::<Vec<_>>();
+
+    if mappings.is_empty() {
+        println!("{}", tr("No published ports"));
+        return Ok(());
     }
 
-    drop(tx);
+    let is_conflicting = |mapping: &PortMapping| {
+        conflicts.iter().any(|conflict| {
+            conflict.port == format!("{}/{}", mapping.host_port, mapping.protocol())
+                && conflict
+                    .containers
+                    .iter()
+                    .any(|(container, _)| *container == mapping.container)
+        })
+    };
+
+    if is_accessible() {
+        for mapping in &mappings {
+            println!(
+                "{}",
+                tr_args(
+                    "Host port {host} maps to port {port} of {container}, service {service} of stack {stack}, on networks {networks}.",
+                    &[
+                        ("host", &mapping.host()),
+                        ("port", &mapping.container_port),
+                        ("container", &mapping.container),
+                        ("service", &mapping.service.as_deref().unwrap_or("none")),
+                        ("stack", &mapping.stack.as_deref().unwrap_or("none")),
+                        ("networks", &mapping.networks.join(", ")),
+                    ]
+                )
+            );
+        }
+    } else {
+        println!(
+            "{:<22} {:<40} {:<20} {:<20} {:<30}",
+            "HOST", "CONTAINER", "SERVICE", "STACK", "NETWORKS"
+        );
+
+        for mapping in &mappings {
+            // pad before coloring, as escape codes do not take up space
+            let host = link_padded(mapping.url(links_host).as_deref(), &mapping.host(), 22);
+            let host = if use_color && is_conflicting(mapping) {
+                color_println_fmt(role_color(Role::Error), &host)
+            } else {
+                host
+            };
+            // only the container name is linked, not the port following it
+            let target = format!("{}:{}", mapping.container, mapping.container_port);
+            let padding = " ".repeat(40usize.saturating_sub(target.chars().count()));
+            let target = match links.container_url(&mapping.container, mapping.stack.as_deref()) {
+                Some(url) => format!(
+                    "{}:{}",
+                    hyperlink(&url, &mapping.container),
+                    mapping.container_port
+                ),
+                None => target,
+            };
 
-    for log_line in rx {
-        println!("{log_line}");
+            println!(
+                "{host} {target}{padding} {:<20} {:<20} {:<30}",
+                mapping.service.as_deref().unwrap_or("-"),
+                mapping.stack.as_deref().unwrap_or("-"),
+                mapping.networks.join(", ")
+            );
+        }
     }
 
-    for handle in handles {
-        let _ = handle.join();
+    for conflict in conflicts {
+        let containers = conflict
+            .containers
+            .iter()
+            .map(|(container, stack)| match stack {
+                Some(stack) => format!("{container} ({stack})"),
+                None => container.to_string(),
+            })
+            .collect::<Vec<String>>()
+            .join(", ");
+        let message = tr_args(
+            "Host port {port} is published by more than one container: {containers}",
+            &[("port", &conflict.port), ("containers", &containers)],
+        );
+
+        if use_color {
+            color_println(role_color(Role::Error), &message);
+        } else {
+            println!("{message}");
+        }
     }
 
     Ok(())
 }
 
-/// Kills all running containers, and then redeploys docker-stack-deploy
-pub fn nuke() -> anyhow::Result<()> {
-    // ask user to confirm action
-    color_println(
-        Color::Yellow,
-        "WARNING: All of your containers will be forcefully removed!",
-    );
-    println!(
-        "After removal, {} will be restarted to redeploy all associated containers.\n",
-        color_println_fmt(Color::Magenta, DSD)
-    );
-    print!("Are you sure you want to nuke your docker stacks? [y/N]: ");
-    let _ = io::stdout().flush();
+/// Reports disk usage per stack and removes stopped containers and dangling images of a stack
+pub fn prune(stack: Option<String>, dry_run: bool, yes: bool) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let number_format = Config::load()?.units.number_format();
 
-    // capture user input
-    let mut input = String::new();
-    let _ = io::stdin().read_line(&mut input);
-    let response = input.trim().to_lowercase();
+    let containers = inspect_disk_containers()?;
+    let mut stacks = containers
+        .iter()
+        .filter_map(|container| container.stack.clone())
+        .collect::<Vec<String>>();
+    stacks.sort();
+    stacks.dedup();
 
-    // evaluate response
-    match response.as_str() {
-        "yes" | "y" => {
-            color_println(Color::Yellow, "Nuking docker containers");
+    if let Some(stack) = &stack {
+        if !stacks.contains(stack) {
+            anyhow::bail!(tr_args(
+                "No containers found in stack: {stack}",
+                &[("stack", stack)]
+            ));
         }
-        _ => {
-            color_println(Color::Green, "Nuke aborted!");
-            return Ok(());
+        stacks = vec![stack.to_string()];
+    }
+
+    let mut image_ids = containers
+        .iter()
+        .map(|container| container.image_id.to_string())
+        .collect::<Vec<String>>();
+    image_ids.sort();
+    image_ids.dedup();
+    let images = inspect_images(&image_ids)?;
+    // volumes are left unsized when docker cannot report them
+    let volume_sizes = volume_sizes().unwrap_or_default();
+    let log_sizes = log_sizes(&containers);
+
+    let size_or_unknown =
+        |size: Option<u64>| size.map_or_else(|| "N/A".to_string(), |size| number_format.size(size));
+
+    if !is_accessible() {
+        println!(
+            "{:<25} {:<12} {:<12} {:<12} {:<12}",
+            "STACK", "CONTAINERS", "IMAGES", "VOLUMES", "LOGS"
+        );
+    }
+
+    for stack in &stacks {
+        let usage = stack_usage(stack, &containers, &images, &volume_sizes, &log_sizes);
+
+        if is_accessible() {
+            println!(
+                "{}",
+                tr_args(
+                    "{stack} has {count} container(s) using {images} of images, {volumes} of volumes and {logs} of logs.",
+                    &[
+                        ("stack", &usage.stack),
+                        ("count", &usage.containers),
+                        ("images", &number_format.size(usage.images)),
+                        ("volumes", &size_or_unknown(usage.volumes)),
+                        ("logs", &size_or_unknown(usage.logs)),
+                    ]
+                )
+            );
+        } else {
+            println!(
+                "{:<25} {:<12} {:<12} {:<12} {:<12}",
+                usage.stack,
+                usage.containers,
+                number_format.size(usage.images),
+                size_or_unknown(usage.volumes),
+                size_or_unknown(usage.logs)
+            );
         }
+    }
+
+    // only report disk usage unless a stack to clean up is given
+    let Some(stack) = stack else {
+        return Ok(());
     };
 
-    // get list of currently running docker containers by id
-    let container_ids = list_containers()?;
+    let plan = plan_prune(
+        &stack,
+        &containers,
+        &inspect_images(&dangling_image_ids()?)?,
+    );
 
-    // if docker containers are running, kill them
-    if container_ids.is_empty() {
-        color_println(Color::Red, "No containers running");
+    println!();
+
+    if plan.is_empty() {
+        println!(
+            "{}",
+            tr_args("Nothing to prune in {stack}", &[("stack", &stack)])
+        );
         return Ok(());
-    } else {
-        kill_containers(container_ids)?
     }
 
-    color_println(Color::Green, "Running docker-stack-deploy...");
-
-    // run docker-stack-deploy
-    Command::new(DOCKER)
-        .args(["compose", "-f", PATH_DSD_COMPOSE, "up", "-d"])
-        .status()
-        .context("Failed to start docker-stack-deploy")?;
+    for container in &plan.containers {
+        println!(
+            "{}",
+            tr_args(
+                "Stopped container: {name} ({status})",
+                &[("name", &container.name), ("status", &container.status)]
+            )
+        );
+    }
+    for image in &plan.images {
+        let id = image.id.trim_start_matches("sha256:");
+        let repository = image
+            .repo_digests
+            .first()
+            .map_or_else(|| "<none>".to_string(), |digest| repository(digest));
+        println!(
+            "{}",
+            tr_args(
+                "Dangling image: {id} ({repository}, {size})",
+                &[
+                    ("id", &&id[..id.len().min(12)]),
+                    ("repository", &repository),
+                    ("size", &number_format.size(image.size)),
+                ]
+            )
+        );
+    }
 
-    color_println(
-        Color::Green,
-        "Following logs until all containers deployed...",
+    let summary = tr_args(
+        "{containers} container(s) and {images} image(s), reclaiming {size}",
+        &[
+            ("containers", &plan.containers.len()),
+            ("images", &plan.images.len()),
+            ("size", &number_format.size(plan.reclaimable())),
+        ],
     );
 
-    let start_time = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .context("Failed to get current time")?
-        .as_secs();
+    if dry_run {
+        println!(
+            "{}",
+            tr_args("Dry run, would remove {summary}", &[("summary", &summary)])
+        );
+        return Ok(());
+    }
 
-    // follow docker-stack-deploy logs until first update check has happened
-    let mut logs_process = Command::new(DOCKER)
-        .args([
-            "compose",
-            "-f",
-            PATH_DSD_COMPOSE,
-            "logs",
-            "--follow",
-            "--no-log-prefix",
-            "--since",
-            &start_time.to_string(),
-        ])
-        .stdout(Stdio::piped())
-        .spawn()
-        .context("Failed to start following logs")?;
+    if !yes {
+        if is_ci() {
+            anyhow::bail!(tr("Refusing to prune without --yes in CI mode"));
+        }
 
-    if let Some(stdout) = logs_process.stdout.take() {
-        let reader = BufReader::new(stdout);
-        for (i, line) in reader.lines().map_while(Result::ok).enumerate() {
-            println!(
-                "[{} | {}] {}",
-                color_println_fmt(Color::Cyan, &get_timestamp()),
-                color_println_fmt(Color::Magenta, DSD),
-                line
-            );
-            if line.contains("Already up to date") && i > 0 {
-                // first update check has happened after deployment
-                break;
-            }
+        print!(
+            "{} [y/N]: ",
+            tr_args("Remove {summary}?", &[("summary", &summary)])
+        );
+        let _ = io::stdout().flush();
+
+        let mut input = String::new();
+        let _ = io::stdin().read_line(&mut input);
+
+        if !matches!(input.trim().to_lowercase().as_str(), "yes" | "y") {
+            println!("{}", tr("Prune aborted!"));
+            return Ok(());
         }
     }
 
-    let _ = logs_process.kill();
-    let _ = logs_process.wait();
+    remove(&plan)?;
+
+    let message = tr_args("Removed {summary}", &[("summary", &summary)]);
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
 
     Ok(())
 }
 
-/// Restarts specified docker containers
-pub fn restart(
-    containers: Option<Vec<String>>,
-    stacks: Option<Vec<String>>,
-    all: bool,
+/// Reports the log size and rotation of containers, optionally looking for log files duplicating
+/// stdout and recreating compose services that keep their logs forever with json-file rotation
+pub fn log_audit(
+    stacks: Vec<String>,
+    double_logging: bool,
+    fix: bool,
+    max_size: String,
+    max_file: u32,
+    yes: bool,
 ) -> anyhow::Result<()> {
-    let containers = if all {
-        list_containers()?
-    } else if let Some(containers) = containers {
-        containers
-    } else if let Some(stacks) = stacks {
-        let mut containers = vec![];
+    let use_color = is_terminal();
+    let config_file = Config::load()?;
+    let number_format = config_file.units.number_format();
+
+    let mut configs = inspect_log_configs()?;
+    if !stacks.is_empty() {
+        let stacks = expand_groups(&stacks)?;
+        configs.retain(|config| {
+            config
+                .stack
+                .as_ref()
+                .is_some_and(|stack| stacks.contains(stack))
+        });
 
-        for stack in &stacks {
-            let container_names = get_containers_from_stack(stack)?;
-            containers.extend(container_names);
+        if configs.is_empty() {
+            anyhow::bail!(tr_args(
+                "No containers found in stack: {stack}",
+                &[("stack", &stacks.join(", "))]
+            ));
         }
+    }
+    configs.sort_by(|a, b| a.container.cmp(&b.container));
 
-        containers
-    } else {
-        anyhow::bail!("Must specify containers, use --stacks (-s) or use --all (-a)")
+    let size_or_unknown = |config: &LogConfig| {
+        log_files_size(&config.log_path)
+            .map_or_else(|| "N/A".to_string(), |size| number_format.size(size))
     };
 
-    let use_color = is_terminal();
+    if !is_accessible() {
+        println!(
+            "{:<30} {:<12} {:<28} {:<12}",
+            "CONTAINER", "DRIVER", "ROTATION", "SIZE"
+        );
+    }
 
-    for container in &containers {
-        if use_color {
-            color_println(
-                Color::Cyan,
-                &format!("Restarting container: {}", &container),
-            );
+    for config in &configs {
+        let size = size_or_unknown(config);
+
+        if is_accessible() {
+            let message = match config.rotation() {
+                Some(rotation) => tr_args(
+                    "{container} logs to {driver} with {rotation}, using {size}.",
+                    &[
+                        ("container", &config.container),
+                        ("driver", &config.driver),
+                        ("rotation", &rotation),
+                        ("size", &size),
+                    ],
+                ),
+                None if config.is_rotated() => tr_args(
+                    "{container} logs to {driver}, using {size}.",
+                    &[
+                        ("container", &config.container),
+                        ("driver", &config.driver),
+                        ("size", &size),
+                    ],
+                ),
+                None => tr_args(
+                    "{container} logs to {driver} without rotation, using {size}.",
+                    &[
+                        ("container", &config.container),
+                        ("driver", &config.driver),
+                        ("size", &size),
+                    ],
+                ),
+            };
+            println!("{message}");
+            continue;
+        }
+
+        let rotation = config.rotation().unwrap_or_else(|| "-".to_string());
+        let container = link_padded(
+            config_file
+                .links
+                .container_url(&config.container, config.stack.as_deref())
+                .as_deref(),
+            &config.container,
+            30,
+        );
+        let line = format!(
+            "{container} {:<12} {:<28} {:<12}",
+            config.driver, rotation, size
+        );
+        if use_color && !config.is_rotated() {
+            color_println(role_color(Role::Warning), &line);
         } else {
-            println!("Restarting container: {}", &container)
+            println!("{line}");
         }
+    }
 
-        Command::new(DOCKER)
-            .args(["restart", container])
-            .status()
-            .context(format!("Failed to restart {}", &container))?;
+    if double_logging {
+        print_double_logging(&configs, use_color)?;
     }
 
-    Ok(())
-}
+    let unrotated = configs
+        .iter()
+        .filter(|config| !config.is_rotated())
+        .collect::<Vec<&LogConfig>>();
 
-/// Container stats to be gathered
-#[derive(Debug, Clone)]
-struct ContainerStats {
-    name: String,
-    status: String,
-    health: String,
-    restart_policy: String,
-    uptime: String,
-    cpu_usage: String,
-    memory_usage: String,
-    ports: String,
-}
+    record("unrotated_containers", unrotated.len());
 
-/// View stats for docker containers
-pub fn stats(
-    containers: Option<Vec<String>>,
-    stacks: Option<Vec<String>>,
-    all: bool,
-) -> anyhow::Result<()> {
-    let use_color = is_terminal();
-    let containers = if all {
-        let container_ids = list_containers()?;
+    println!();
 
-        if container_ids.is_empty() {
-            if use_color {
-                color_println(Color::Red, "No containers running");
-            } else {
-                println!("No containers running");
-            }
-            return Ok(());
+    if unrotated.is_empty() {
+        let message = tr("All containers rotate their logs");
+        if use_color {
+            color_println(Color::Green, &message);
+        } else {
+            println!("{message}");
         }
+        return Ok(());
+    }
 
-        container_ids
-    } else if let Some(containers) = containers {
-        containers
-    } else if let Some(stacks) = stacks {
-        let mut containers = vec![];
-
-        for stack in &stacks {
-            let container_names = get_containers_from_stack(stack)?;
-            containers.extend(container_names);
+    if !fix {
+        let message = tr_args(
+            "{count} container(s) keep their logs forever, run log-audit --fix to recreate them with log rotation",
+            &[("count", &unrotated.len())],
+        );
+        if use_color {
+            color_println(role_color(Role::Warning), &message);
+        } else {
+            println!("{message}");
         }
+        return Ok(());
+    }
 
-        containers
-    } else {
-        anyhow::bail!("Must specify containers, use --stacks (-s) or use --all (-a)")
-    };
-
-    // stats format from docker cli
-    let stats_output = Command::new(DOCKER)
-        .args([
-            "stats",
-            "--no-stream",
-            "--format",
-            "table {{.Name}}\t{{.CPUPerc}}\t{{.MemPerc}}",
-        ])
-        .args(&containers)
-        .output()
-        .context("Failed to get stats for containers")?;
-
-    // inspect format from docker cli
-    let inspect_format = concat!(
-        "{{.Name}},",
-        "{{.State.Status}},",
-        "{{if .HostConfig.RestartPolicy}}{{if .HostConfig.RestartPolicy.Name}}{{.HostConfig.RestartPolicy.Name}}{{else}}no{{end}}{{else}}no{{end}},",
-        "{{if index .State \"Health\"}}{{.State.Health.Status}}{{else}}N/A{{end}},",
-        "{{.State.StartedAt}},",
-        "{{if .NetworkSettings.Ports}}{{range $key, $value := .NetworkSettings.Ports}}{{$key}}{{if $value}}:{{(index $value 0).HostPort}}{{end}} {{end}}{{else}}N/A{{end}}"
-        );
-
-    let inspect_output = Command::new(DOCKER)
-        .arg("inspect")
-        .args(&containers)
-        .args(["--format", inspect_format])
-        .output()
-        .context("Failed to inspect containers")?;
-
-    let stats_string = String::from_utf8(stats_output.stdout)?;
-    let inspect_string = String::from_utf8(inspect_output.stdout)?;
+    // services to recreate per stack, along with a container to look up the compose project by
+    let mut services: BTreeMap<String, (String, BTreeSet<String>)> = BTreeMap::new();
+    for config in &unrotated {
+        let (Some(stack), Some(service)) = (&config.stack, &config.service) else {
+            println!(
+                "{}",
+                tr_args(
+                    "{container} is not managed by docker compose, recreate it with --log-opt max-size={max_size} --log-opt max-file={max_file}",
+                    &[
+                        ("container", &config.container),
+                        ("max_size", &max_size),
+                        ("max_file", &max_file),
+                    ]
+                )
+            );
+            continue;
+        };
 
-    let mut temp_stats_map: HashMap<String, StatsData> = HashMap::new();
-    let mut temp_inspect_map: HashMap<String, InspectData> = HashMap::new();
+        services
+            .entry(stack.to_string())
+            .or_insert_with(|| (config.container.to_string(), BTreeSet::new()))
+            .1
+            .insert(service.to_string());
+    }
 
-    // skip header line
-    for line in stats_string.lines().skip(1) {
-        let parsed = parse_stats_data(line)?;
-        temp_stats_map.insert(
-            parsed.container_name.clone(),
-            StatsData {
-                container_name: parsed.container_name,
-                cpu: parsed.cpu,
-                memory: parsed.memory,
-            },
-        );
+    if services.is_empty() {
+        return Ok(());
     }
 
-    for line in inspect_string.lines() {
-        let parsed = parse_inspect_data(line)?;
-        temp_inspect_map.insert(
-            parsed.container_name.clone(),
-            InspectData {
-                container_name: parsed.container_name,
-                status: parsed.status,
-                restart_policy: parsed.restart_policy,
-                health: parsed.health,
-                uptime: parsed.uptime,
-                ports: parsed.ports,
-            },
+    let count = services
+        .values()
+        .map(|(_, services)| services.len())
+        .sum::<usize>();
+    let stack_names = services.keys().cloned().collect::<Vec<String>>().join(", ");
+
+    if !yes {
+        if is_ci() {
+            anyhow::bail!(tr(
+                "Refusing to recreate containers without --yes in CI mode"
+            ));
+        }
+
+        print!(
+            "{} [y/N]: ",
+            tr_args(
+                "Recreate {count} service(s) of {stacks} with max-size={max_size} max-file={max_file}?",
+                &[
+                    ("count", &count),
+                    ("stacks", &stack_names),
+                    ("max_size", &max_size),
+                    ("max_file", &max_file),
+                ]
+            )
         );
+        let _ = io::stdout().flush();
+
+        let mut input = String::new();
+        let _ = io::stdin().read_line(&mut input);
+
+        if !matches!(input.trim().to_lowercase().as_str(), "yes" | "y") {
+            println!("{}", tr("Log rotation aborted!"));
+            return Ok(());
+        }
     }
 
-    assert_eq!(&temp_stats_map.len(), &temp_inspect_map.len());
+    let mut failed: Vec<String> = vec![];
 
-    let mut total_stats_map: HashMap<String, ContainerStats> = HashMap::new();
+    for (stack, (container, stack_services)) in &services {
+        start_group(&tr_args("Recreating {stack}", &[("stack", stack)]));
+        let result = recreate_with_rotation(container, stack_services, &max_size, max_file);
+        end_group();
 
-    for key in temp_stats_map.keys() {
+        if let Err(e) = result {
+            if use_color {
+                color_println(role_color(Role::Error), &format!("{e:#}"));
+            } else {
+                println!("{e:#}");
+            }
+            failed.push(stack.to_string());
+        }
+    }
+
+    if !failed.is_empty() {
+        anyhow::bail!(tr_args(
+            "Failed to recreate: {stacks}",
+            &[("stacks", &failed.join(", "))]
+        ));
+    }
+
+    record("services_recreated", count);
+
+    let message = tr_args(
+        "Recreated {count} service(s) of {stacks} with log rotation",
+        &[("count", &count), ("stacks", &stack_names)],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+/// Lists log files in volumes of running containers that receive the same lines as stdout
+fn print_double_logging(configs: &[LogConfig], use_color: bool) -> anyhow::Result<()> {
+    println!();
+    start_group(&tr("Looking for double logging"));
+
+    let mut found: Vec<DoubleLogging> = vec![];
+    for config in configs.iter().filter(|config| config.running) {
+        found.extend(find_double_logging(config)?);
+    }
+
+    end_group();
+    record(
+        "double_logging_containers",
+        found
+            .iter()
+            .map(|double| double.container.as_str())
+            .collect::<BTreeSet<&str>>()
+            .len(),
+    );
+
+    if found.is_empty() {
+        println!("{}", tr("No log files duplicating stdout found"));
+        return Ok(());
+    }
+
+    for double in &found {
+        let message = tr_args(
+            "{container} also writes its stdout to {path} in a volume, {shared} sampled line(s) match and stdout got {lines} line(s) in the last hour",
+            &[
+                ("container", &double.container),
+                ("path", &double.path),
+                ("shared", &double.shared_lines),
+                ("lines", &double.stdout_lines),
+            ],
+        );
+        if use_color {
+            color_println(role_color(Role::Warning), &message);
+        } else {
+            println!("{message}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Recreates services of the compose project of a container with a logging override
+fn recreate_with_rotation(
+    container: &str,
+    services: &BTreeSet<String>,
+    max_size: &str,
+    max_file: u32,
+) -> anyhow::Result<()> {
+    let compose_info = get_compose_info(container)?
+        .with_context(|| format!("{container} is not managed by docker compose"))?;
+    let services = services.iter().cloned().collect::<Vec<String>>();
+    let override_file =
+        write_logging_override(&compose_info.project, &services, max_size, max_file)?;
+
+    recreate_with_override(&compose_info, &override_file, &services)
+}
+
+/// Recreates services of a compose project with an override from the state directory on top of
+/// the files the project was started with
+fn recreate_with_override(
+    compose_info: &ComposeInfo,
+    override_file: &Path,
+    services: &[String],
+) -> anyhow::Result<()> {
+    let mut command = compose_command(compose_info);
+    // containers recreated before already list the override among their config files
+    let override_path = override_file.to_string_lossy();
+    if !compose_info
+        .config_files
+        .iter()
+        .any(|file| *file == override_path)
+    {
+        command.arg("-f").arg(override_file);
+    }
+
+    let status = command
+        .args(["up", "-d", "--no-deps"])
+        .args(services)
+        .status()
+        .with_context(|| format!("Failed to recreate {}", compose_info.project))?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to recreate {}", compose_info.project);
+    }
+
+    Ok(())
+}
+
+/// Shows logs for specified containers
+pub fn logs(
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    window: LogWindow,
+    all: bool,
+    grep: Option<String>,
+    outputs: LogOutputs,
+    format: LogFormat,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    let containers = resolve_containers(containers, stacks, all)?;
+
+    if containers.is_empty() {
+        if use_color {
+            color_println(role_color(Role::Error), &tr("No containers running"));
+        } else {
+            println!("{}", tr("No containers running"));
+        }
+        return Ok(());
+    }
+
+    // keys only make sense to someone watching the output in a terminal
+    let interactive =
+        window.follow && io::stdin().is_terminal() && io::stdout().is_terminal() && !is_ci();
+
+    let message = if window.follow {
+        "Following logs for container: {count}"
+    } else {
+        "Showing logs for container: {count}"
+    };
+
+    if use_color {
+        color_println(
+            Color::Cyan,
+            &tr_args(message, &[("count", &containers.len())]),
+        );
+    } else {
+        println!("{}", tr_args(message, &[("count", &containers.len())]));
+    }
+    let mut logger = Logger::new(containers.clone())
+        .container_ids(all)
+        .color(use_color)
+        .format(format)
+        .window(window)
+        .ship(outputs.ship);
+
+    if let Some(grep) = grep {
+        logger = logger.grep(grep);
+    }
+
+    // escape sequences would end up in files and pipes
+    match outputs.bell {
+        Some(bell) if io::stdout().is_terminal() => logger = logger.alert(bell),
+        Some(_) => eprintln!("{}", tr("Ignoring --bell, output is not a terminal")),
+        None => {}
+    }
+
+    handle_ctrl_c()?;
+
+    let mut lines = logger.follow()?;
+    if interactive {
+        follow_interactively(&mut lines, use_color)?;
+    } else {
+        for log_line in lines.by_ref() {
+            println!("{log_line}");
+        }
+    }
+
+    // a summary would end up in files and pipes along with the lines
+    if io::stdout().is_terminal() {
+        let known = list_all_containers()?;
+        let summary = group_by_stack(&containers, &known)
+            .into_iter()
+            .map(|(stack, containers)| {
+                let count = containers
+                    .iter()
+                    .filter_map(|container| lines.line_counts().get(container))
+                    .sum::<usize>();
+                (stack, tr_args("{count} line(s)", &[("count", &count)]))
+            })
+            .collect::<Vec<(String, String)>>();
+
+        print_stack_summary(&summary, use_color);
+    }
+
+    io::stdout().flush().context("Failed to flush output")?;
+
+    Ok(())
+}
+
+/// Prints followed lines while handling keys: space pauses and resumes, / sets a live filter and
+/// typing a container name mutes or unmutes it. Lines arriving while paused or typing are held
+/// back and printed once output resumes.
+fn follow_interactively(lines: &mut LogMultiplexer, use_color: bool) -> anyhow::Result<()> {
+    let _raw_input = RawInput::enable()?;
+    let keys = read_keys();
+    let mut controls = KeyControls::default();
+    let mut held = VecDeque::new();
+    let mut dropped = 0;
+
+    // status goes to stderr, so it stays apart from lines redirected elsewhere
+    let status = |message: &str| {
+        eprint!("\r\x1b[K");
+        if use_color {
+            eprintln!("{}", color_println_fmt(Color::White, message));
+        } else {
+            eprintln!("{message}");
+        }
+    };
+
+    status(&tr(
+        "Press space to pause, / to filter, or type a container name and enter to mute it",
+    ));
+
+    loop {
+        while let Ok(key) = keys.try_recv() {
+            match controls.key(key) {
+                ControlAction::None => {}
+                ControlAction::Pause => status(&tr("Paused, press space to resume")),
+                ControlAction::Resume => status(&tr_args(
+                    "Resumed, {count} line(s) held back",
+                    &[("count", &held.len())],
+                )),
+                ControlAction::Prompt(prompt) => {
+                    eprint!("\r\x1b[K{}", prompt.display());
+                    io::stderr().flush().context("Failed to flush output")?;
+                }
+                ControlAction::Cancel => eprint!("\r\x1b[K"),
+                ControlAction::Filter(filter) => {
+                    status(&match &filter {
+                        Some(filter) => tr_args("Filtering on {filter}", &[("filter", filter)]),
+                        None => tr("Filter cleared"),
+                    });
+                    lines.set_filter(filter);
+                }
+                ControlAction::ToggleMute(typed) => {
+                    match typed_container(&typed, lines.containers()).map(str::to_string) {
+                        Some(container) if lines.toggle_mute(&container) => {
+                            status(&tr_args("Muted {container}", &[("container", &container)]))
+                        }
+                        Some(container) => status(&tr_args(
+                            "Unmuted {container}",
+                            &[("container", &container)],
+                        )),
+                        None => status(&tr_args(
+                            "No single container matching {name}",
+                            &[("name", &typed)],
+                        )),
+                    }
+                }
+            }
+
+            if !controls.holds_output() {
+                if dropped > 0 {
+                    status(&tr_args(
+                        "Dropped {count} line(s) while paused",
+                        &[("count", &dropped)],
+                    ));
+                    dropped = 0;
+                }
+                for line in held.drain(..) {
+                    println!("{line}");
+                }
+            }
+        }
+
+        match lines.poll_next() {
+            Poll::Ready(Some(line)) if controls.holds_output() => {
+                if held.len() >= MAX_HELD_LINES {
+                    held.pop_front();
+                    dropped += 1;
+                }
+                held.push_back(line);
+            }
+            Poll::Ready(Some(line)) => println!("{line}"),
+            Poll::Ready(None) => break,
+            Poll::Pending => {}
+        }
+    }
+
+    // lines held back when following ends are not lost
+    for line in held {
+        println!("{line}");
+    }
+
+    Ok(())
+}
+
+/// Prints one line per stack summarizing what a command did, unless only one stack took part
+fn print_stack_summary(summary: &[(String, String)], use_color: bool) {
+    if summary.len() < 2 {
+        return;
+    }
+
+    println!();
+
+    if is_accessible() {
+        for (stack, outcome) in summary {
+            println!("{stack}: {outcome}.");
+        }
+        return;
+    }
+
+    let width = summary
+        .iter()
+        .map(|(stack, _)| stack.chars().count())
+        .max()
+        .unwrap_or_default();
+
+    for (stack, outcome) in summary {
+        if use_color {
+            println!(
+                "{} {outcome}",
+                color_println_fmt(Color::Cyan, &format!("{stack:<width$}"))
+            );
+        } else {
+            println!("{stack:<width$} {outcome}");
+        }
+    }
+}
+
+/// Prints what a batch did to each of its containers, so a failure does not get lost in the
+/// output above it
+fn print_batch_summary(results: &BatchResults, use_color: bool) {
+    if results.outcomes.len() < 2 {
+        return;
+    }
+
+    println!();
+
+    if is_accessible() {
+        for outcome in &results.outcomes {
+            let message = match &outcome.error {
+                None => tr_args(
+                    "{container} succeeded.",
+                    &[("container", &outcome.container)],
+                ),
+                Some(error) => tr_args(
+                    "{container} failed: {error}.",
+                    &[("container", &outcome.container), ("error", error)],
+                ),
+            };
+            println!("{message}");
+        }
+    } else {
+        println!("{:<30} RESULT", "CONTAINER");
+        for outcome in &results.outcomes {
+            let (result, color) = match &outcome.error {
+                None => (tr("ok"), Color::Green),
+                Some(error) => (
+                    tr_args("failed: {error}", &[("error", error)]),
+                    role_color(Role::Error),
+                ),
+            };
+            if use_color {
+                println!(
+                    "{:<30} {}",
+                    outcome.container,
+                    color_println_fmt(color, &result)
+                );
+            } else {
+                println!("{:<30} {result}", outcome.container);
+            }
+        }
+    }
+
+    let message = tr_args(
+        "{succeeded} of {total} container(s) succeeded",
+        &[
+            ("succeeded", &results.succeeded_count()),
+            ("total", &results.outcomes.len()),
+        ],
+    );
+    let color = match results.status() {
+        BatchStatus::Succeeded => Color::Green,
+        BatchStatus::Partial => role_color(Role::Warning),
+        BatchStatus::Failed => role_color(Role::Error),
+    };
+    if use_color {
+        color_println(color, &message);
+    } else {
+        println!("{message}");
+    }
+}
+
+/// Shows logs using a named view from the config file
+pub fn view(name: String) -> anyhow::Result<()> {
+    let config = Config::load_with_secrets()?;
+
+    let view = config
+        .views
+        .get(&name)
+        .with_context(|| tr_args("No view named {name} in config file", &[("name", &name)]))?
+        .clone();
+
+    logs(
+        (!view.containers.is_empty()).then_some(view.containers),
+        (!view.stacks.is_empty()).then_some(view.stacks),
+        LogWindow {
+            tail: Some(view.tail),
+            min_level: view.min_level.as_deref().map(parse_log_level).transpose()?,
+            ..LogWindow::default()
+        },
+        view.all,
+        view.grep,
+        LogOutputs {
+            bell: view.bell.as_deref().map(LogAlert::parse).transpose()?,
+            ship: view
+                .ship
+                .iter()
+                .map(|target| parse_ship_target(target))
+                .collect::<anyhow::Result<Vec<ShipTarget>>>()?,
+        },
+        LogFormat {
+            multiline: view
+                .multiline
+                .unwrap_or_default()
+                .then(|| RecordStart::parse(DEFAULT_RECORD_START))
+                .transpose()?,
+            ..LogFormat::default()
+        },
+    )
+}
+
+/// Serves container stats as Prometheus metrics
+pub fn exporter(listen: String) -> anyhow::Result<()> {
+    let listener =
+        TcpListener::bind(&listen).with_context(|| format!("Failed to listen on {listen}"))?;
+
+    if is_terminal() {
+        color_println(
+            Color::Green,
+            &tr_args(
+                "Serving metrics on {url}",
+                &[("url", &format!("http://{listen}/metrics"))],
+            ),
+        );
+    } else {
+        println!(
+            "{}",
+            tr_args(
+                "Serving metrics on {url}",
+                &[("url", &format!("http://{listen}/metrics"))]
+            )
+        );
+    }
+
+    serve_metrics(listener)
+}
+
+/// Receives webhooks that pull and recreate services allowed in the config file
+pub fn daemon_run(listen: String) -> anyhow::Result<()> {
+    let config = Config::load_with_secrets()?;
+    let guard = LoadGuard::from_config(&config.guard)?;
+    let channels = config.notify.channels;
+    let policy = DeployPolicy {
+        rules: UpdateRules::new(&config.update, &[]),
+        verify: config.verify,
+    };
+    let config = config.daemon;
+    let secret = webhook_secret(&config)?;
+
+    for (stack, hook) in &config.hooks {
+        hook_windows(stack, hook)?;
+    }
+
+    if let Some(report) = &config.report {
+        report_window(report)?;
+        if let Some(name) = report
+            .channels
+            .iter()
+            .find(|name| !channels.contains_key(*name))
+        {
+            anyhow::bail!("Unknown notification channel in daemon.report: {name}");
+        }
+    }
+
+    if config.hooks.is_empty() && config.report.is_none() {
+        anyhow::bail!(tr(
+            "No stacks accept webhooks, add them under daemon.hooks in the config file"
+        ));
+    }
+
+    // with a systemd socket unit, systemd owns the port and starts the daemon on the first request
+    let listener = match activated_listener()? {
+        Some(listener) => listener,
+        None => {
+            TcpListener::bind(&listen).with_context(|| format!("Failed to listen on {listen}"))?
+        }
+    };
+    let address = listener
+        .local_addr()
+        .map_or(listen, |address| address.to_string());
+
+    // nothing but the listener is opened as root, requests are only handled as the user
+    if let Some(user) = &config.user {
+        let user = DaemonUser::lookup(user)?;
+        drop_privileges(&user)?;
+        preflight()
+            .with_context(|| tr_args("User {user} cannot use docker", &[("user", &user.name)]))?;
+    }
+
+    let message = tr_args(
+        "Receiving webhooks on {url}",
+        &[("url", &format!("http://{address}/hooks/<stack>"))],
+    );
+
+    if is_terminal() {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    serve_webhooks(listener, config, policy, guard, secret)
+}
+
+/// Shows running, pending and recently finished actions of a running daemon
+pub fn daemon_status(address: String) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let secret = webhook_secret(&Config::load_with_secrets()?.daemon)?;
+    let state = fetch_status(&address, &secret)?;
+
+    // pulled services wait for the maintenance window after the scheduled pull that got them
+    let pulled = state
+        .pulled
+        .iter()
+        .map(|(stack, pulled)| QueuedAction {
+            stack: stack.to_string(),
+            services: pulled.services.clone(),
+            triggers: BTreeSet::from([Trigger::Schedule]),
+            queued_at: pulled.pulled_at.clone(),
+        })
+        .collect::<Vec<QueuedAction>>();
+
+    let rows = state
+        .running
+        .iter()
+        .map(|action| ("running", action, &action.queued_at, None))
+        .chain(
+            state
+                .pending
+                .iter()
+                .map(|action| ("pending", action, &action.queued_at, None)),
+        )
+        .chain(
+            pulled
+                .iter()
+                .map(|action| ("pulled", action, &action.queued_at, None)),
+        )
+        .chain(state.finished.iter().map(|finished| {
+            (
+                if finished.error.is_some() {
+                    "failed"
+                } else {
+                    "done"
+                },
+                &finished.action,
+                &finished.finished_at,
+                finished.error.as_ref(),
+            )
+        }))
+        .collect::<Vec<_>>();
+
+    if rows.is_empty() {
+        println!("{}", tr("The action queue is empty"));
+        return Ok(());
+    }
+
+    if is_accessible() {
+        for (state, action, time, error) in rows {
+            println!(
+                "{}",
+                tr_args(
+                    "{stack} is {state} for {services}, triggered by {triggers}, at {time}.",
+                    &[
+                        ("stack", &action.stack),
+                        ("state", &state),
+                        ("services", &join_set(&action.services)),
+                        ("triggers", &join_triggers(action)),
+                        ("time", time),
+                    ]
+                )
+            );
+            if let Some(error) = error {
+                println!("{error}");
+            }
+        }
+        return Ok(());
+    }
+
+    println!(
+        "{:<10} {:<25} {:<30} {:<25} {:<20}",
+        "STATE", "STACK", "SERVICES", "TRIGGERS", "TIME"
+    );
+
+    for (state, action, time, error) in rows {
+        // pad before coloring, as escape codes do not take up space
+        let state_column = format!("{state:<10}");
+        let state_column = if use_color {
+            let color = match state {
+                "running" => Color::Cyan,
+                "pending" => role_color(Role::Warning),
+                "pulled" => Color::Blue,
+                "failed" => role_color(Role::Error),
+                _ => Color::Green,
+            };
+            color_println_fmt(color, &state_column)
+        } else {
+            state_column
+        };
+
+        println!(
+            "{state_column} {:<25} {:<30} {:<25} {:<20}",
+            action.stack,
+            join_set(&action.services),
+            join_triggers(action),
+            time
+        );
+
+        if let Some(error) = error {
+            if use_color {
+                color_println(role_color(Role::Error), &format!("  {error}"));
+            } else {
+                println!("  {error}");
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Joins services of a queued action for display
+fn join_set(set: &BTreeSet<String>) -> String {
+    set.iter().cloned().collect::<Vec<String>>().join(", ")
+}
+
+/// Joins the triggers of a queued action for display
+fn join_triggers(action: &QueuedAction) -> String {
+    action
+        .triggers
+        .iter()
+        .map(|trigger| format!("{trigger:?}").to_lowercase())
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Asks a running daemon to pull and recreate services of a stack
+pub fn daemon_trigger(stack: String, services: Vec<String>, address: String) -> anyhow::Result<()> {
+    let config = Config::load_with_secrets()?.daemon;
+    let secret = webhook_secret(&config)?;
+
+    let response = send_trigger(&address, &secret, &stack, services)?;
+
+    let message = if response["status"] == "merged" {
+        tr_args(
+            "Merged into the pending action of {stack}",
+            &[("stack", &stack)],
+        )
+    } else {
+        tr_args("Queued an action for {stack}", &[("stack", &stack)])
+    };
+
+    if is_terminal() {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+/// Samples stats of containers every interval and appends them to a CSV file or SQLite database
+pub fn record_stats(
+    stacks: Option<Vec<String>>,
+    all: bool,
+    interval: std::time::Duration,
+    out: PathBuf,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    // fail early instead of after the first interval when nothing matches
+    if resolve_containers(None, stacks.clone(), all)?.is_empty() {
+        anyhow::bail!(tr("No containers running"));
+    }
+
+    let message = tr_args(
+        "Recording stats to {path} every {interval}, press Ctrl-C to stop",
+        &[
+            ("path", &out.display()),
+            ("interval", &format_duration(interval)),
+        ],
+    );
+
+    if use_color {
+        color_println(Color::Cyan, &message);
+    } else {
+        println!("{message}");
+    }
+
+    handle_ctrl_c()?;
+
+    let mut recorded = 0;
+
+    loop {
+        // resolve again on every sample so recreated containers of a stack are recorded too
+        let containers = resolve_containers(None, stacks.clone(), all)?;
+
+        if !containers.is_empty() {
+            let timestamp = chrono::Utc::now();
+            let samples = get_stats(&containers)?
+                .into_iter()
+                .map(|stats| StatsSample { timestamp, stats })
+                .collect::<Vec<StatsSample>>();
+
+            append_samples(&out, &samples)?;
+            recorded += samples.len();
+        }
+
+        if !sleep_unless_shutdown(interval) {
+            break;
+        }
+    }
+
+    println!(
+        "{}",
+        tr_args("Recorded {count} sample(s)", &[("count", &recorded)])
+    );
+    io::stdout().flush().context("Failed to flush output")?;
+
+    Ok(())
+}
+
+/// Prints min, average and max CPU and memory usage per container from recorded stats
+pub fn report_stats(
+    input: PathBuf,
+    since: std::time::Duration,
+    until: Option<std::time::Duration>,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let number_format = Config::load()?.units.number_format();
+
+    let now = chrono::Utc::now();
+    let ago = |duration: std::time::Duration| {
+        chrono::Duration::from_std(duration)
+            .map(|duration| now - duration)
+            .context("Failed to calculate report window")
+    };
+
+    let summaries = summarize(
+        &load_samples(&input)?,
+        ago(since)?,
+        until.map(ago).transpose()?.unwrap_or(now),
+    );
+
+    if summaries.is_empty() {
+        if use_color {
+            color_println(
+                role_color(Role::Warning),
+                &tr("No stats recorded in this time range"),
+            );
+        } else {
+            println!("{}", tr("No stats recorded in this time range"));
+        }
+        return Ok(());
+    }
+
+    if is_accessible() {
+        for summary in &summaries {
+            println!(
+                "{}",
+                tr_args(
+                    "{name} used {cpu_min} to {cpu_max} CPU, {cpu_avg} on average, and {memory_min} to {memory_max} memory, {memory_avg} on average, over {count} sample(s).",
+                    &[
+                        ("name", &summary.container),
+                        ("cpu_min", &number_format.percent(summary.cpu_min)),
+                        ("cpu_max", &number_format.percent(summary.cpu_max)),
+                        ("cpu_avg", &number_format.percent(summary.cpu_avg)),
+                        ("memory_min", &number_format.size(summary.memory_min)),
+                        ("memory_max", &number_format.size(summary.memory_max)),
+                        ("memory_avg", &number_format.size(summary.memory_avg)),
+                        ("count", &summary.samples),
+                    ]
+                )
+            );
+        }
+        return Ok(());
+    }
+
+    if use_color {
+        println!(
+            "{:<35} {:<8} {:<10} {:<10} {:<10} {:<12} {:<12} {:<12}",
+            &color_println_fmt(Color::White, "NAME"),
+            "SAMPLES",
+            "CPU MIN",
+            "CPU AVG",
+            "CPU MAX",
+            "MEM MIN",
+            "MEM AVG",
+            "MEM MAX"
+        );
+    } else {
+        println!(
+            "{:<35} {:<8} {:<10} {:<10} {:<10} {:<12} {:<12} {:<12}",
+            "NAME", "SAMPLES", "CPU MIN", "CPU AVG", "CPU MAX", "MEM MIN", "MEM AVG", "MEM MAX"
+        );
+    }
+
+    println!();
+
+    for summary in &summaries {
+        let name = if use_color {
+            color_println_fmt(Color::Cyan, &summary.container)
+        } else {
+            summary.container.to_string()
+        };
+
+        println!(
+            "{:<35} {:<8} {:<10} {:<10} {:<10} {:<12} {:<12} {:<12}",
+            name,
+            summary.samples,
+            number_format.percent(summary.cpu_min),
+            number_format.percent(summary.cpu_avg),
+            number_format.percent(summary.cpu_max),
+            number_format.size(summary.memory_min),
+            number_format.size(summary.memory_avg),
+            number_format.size(summary.memory_max)
+        );
+    }
+
+    Ok(())
+}
+
+/// Compiles uptime, restarts, updates, resource highlights, disk trends and open warnings of the
+/// last day into a report, printed as Markdown or written to a Markdown or HTML file
+pub fn daily_report(input: Option<PathBuf>, output: Option<PathBuf>) -> anyhow::Result<()> {
+    let number_format = Config::load()?.units.number_format();
+    let report = compile_daily_report(input.as_deref())?;
+
+    record("warnings", report.warnings.len());
+
+    let document = render_report(&report, &number_format);
+
+    let Some(output) = output else {
+        print!("{}", document.to_markdown());
+        return Ok(());
+    };
+
+    std::fs::write(&output, document.render(DocumentFormat::from_path(&output)))
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    let message = tr_args("Wrote {path}", &[("path", &output.display())]);
+    if is_terminal() {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+/// Scaffolds a new compose project from a template
+pub fn new(name: String, template: Template, dir: Option<PathBuf>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let dir = dir.unwrap_or_else(|| PathBuf::from(&name));
+
+    scaffold_project(&dir, &name, template)?;
+
+    if use_color {
+        color_println(
+            Color::Green,
+            &tr_args(
+                "Created project {name} in {dir}",
+                &[("name", &name), ("dir", &dir.display())],
+            ),
+        );
+    } else {
+        println!(
+            "{}",
+            tr_args(
+                "Created project {name} in {dir}",
+                &[("name", &name), ("dir", &dir.display())]
+            )
+        );
+    }
+
+    println!();
+    println!("  cd {} && make up", dir.display());
+
+    Ok(())
+}
+
+/// Kills all running containers, and then redeploys docker-stack-deploy
+pub fn nuke() -> anyhow::Result<()> {
+    if is_ci() {
+        anyhow::bail!(tr("Refusing to nuke without confirmation in CI mode"));
+    }
+
+    // ask user to confirm action
+    color_println(
+        role_color(Role::Warning),
+        &tr("WARNING: All of your containers will be forcefully removed!"),
+    );
+    println!(
+        "{}\n",
+        tr_args(
+            "After removal, {name} will be restarted to redeploy all associated containers.",
+            &[("name", &color_println_fmt(Color::Magenta, DSD))]
+        )
+    );
+    print!(
+        "{} [y/N]: ",
+        tr("Are you sure you want to nuke your docker stacks?")
+    );
+    let _ = io::stdout().flush();
+
+    // capture user input
+    let mut input = String::new();
+    let _ = io::stdin().read_line(&mut input);
+    let response = input.trim().to_lowercase();
+
+    // evaluate response
+    match response.as_str() {
+        "yes" | "y" => {
+            color_println(role_color(Role::Warning), &tr("Nuking docker containers"));
+        }
+        _ => {
+            color_println(Color::Green, &tr("Nuke aborted!"));
+            return Ok(());
+        }
+    };
+
+    // get names of currently running docker containers
+    let containers = crate::docker::list_containers()?
+        .into_iter()
+        .map(|container| container.name)
+        .collect::<Vec<String>>();
+
+    // if docker containers are running, kill them
+    if containers.is_empty() {
+        color_println(role_color(Role::Error), &tr("No containers running"));
+        return Ok(());
+    }
+
+    let results = kill_containers(&containers)?;
+    let failed = results.failed_containers();
+    record("containers_killed", results.succeeded_count());
+    record("containers_failed", failed.len());
+    print_batch_summary(&results, true);
+
+    color_println(Color::Green, &tr("Running docker-stack-deploy..."));
+
+    // run docker-stack-deploy
+    Command::new(DOCKER)
+        .args(["compose", "-f", PATH_DSD_COMPOSE, "up", "-d"])
+        .status()
+        .context("Failed to start docker-stack-deploy")?;
+
+    color_println(
+        Color::Green,
+        &tr("Following logs until all containers deployed..."),
+    );
+
+    let start_time = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .context("Failed to get current time")?
+        .as_secs();
+
+    // follow docker-stack-deploy logs until first update check has happened
+    let mut logs_process = Command::new(DOCKER)
+        .args([
+            "compose",
+            "-f",
+            PATH_DSD_COMPOSE,
+            "logs",
+            "--follow",
+            "--no-log-prefix",
+            "--since",
+            &start_time.to_string(),
+        ])
+        .stdout(Stdio::piped())
+        .spawn()
+        .context("Failed to start following logs")?;
+
+    if let Some(stdout) = logs_process.stdout.take() {
+        let reader = BufReader::new(stdout);
+        for (i, line) in reader.lines().map_while(Result::ok).enumerate() {
+            println!(
+                "[{} | {}] {}",
+                color_println_fmt(role_color(Role::Timestamp), &get_timestamp()),
+                color_println_fmt(Color::Magenta, DSD),
+                line
+            );
+            if line.contains("Already up to date") && i > 0 {
+                // first update check has happened after deployment
+                break;
+            }
+        }
+    }
+
+    let _ = logs_process.kill();
+    let _ = logs_process.wait();
+
+    Ok(results.into_result(tr_args(
+        "Failed to kill: {containers}",
+        &[("containers", &failed.join(", "))],
+    ))?)
+}
+
+/// Checks the docker root filesystem has room for pulling images, asking whether to pull anyway
+/// when it does not and refusing without a terminal to ask on
+fn confirm_pull_space(
+    guard: &LoadGuard,
+    images: &[String],
+    use_color: bool,
+) -> anyhow::Result<bool> {
+    let Some(shortage) = guard.check_pull_space(images) else {
+        return Ok(true);
+    };
+
+    let message = tr_args(
+        "Not enough disk space to pull images: {reason}",
+        &[("reason", &shortage)],
+    );
+
+    if is_ci() || !io::stdin().is_terminal() {
+        anyhow::bail!(message);
+    }
+
+    if use_color {
+        color_println(role_color(Role::Warning), &message);
+    } else {
+        println!("{message}");
+    }
+
+    print!("{} [y/N]: ", tr("Pull anyway?"));
+    let _ = io::stdout().flush();
+
+    let mut input = String::new();
+    let _ = io::stdin().read_line(&mut input);
+
+    if !matches!(input.trim().to_lowercase().as_str(), "yes" | "y") {
+        println!("{}", tr("Pull aborted!"));
+        return Ok(false);
+    }
+
+    Ok(true)
+}
+
+/// Copies files between the host and a container, addressing containers by compose service
+pub fn cp(source: String, destination: String, all: bool) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let source = CopyLocation::parse(&source);
+    let destination = CopyLocation::parse(&destination);
+
+    let print_copy = |source: &dyn std::fmt::Display, destination: &dyn std::fmt::Display| {
+        let message = tr_args(
+            "Copying {source} to {destination}",
+            &[("source", source), ("destination", destination)],
+        );
+        if use_color {
+            color_println(Color::Cyan, &message);
+        } else {
+            println!("{message}");
+        }
+    };
+
+    if all {
+        let (
+            CopyLocation::Container {
+                container: stack,
+                path,
+            },
+            CopyLocation::Host(dir),
+        ) = (&source, &destination)
+        else {
+            anyhow::bail!(tr(
+                "--all copies from every container of a stack, given as stack:path, into a host directory"
+            ));
+        };
+
+        let services = get_stack_services(stack)?;
+        if services.is_empty() {
+            anyhow::bail!(tr_args(
+                "No containers found in stack: {stack}",
+                &[("stack", stack)]
+            ));
+        }
+
+        for (container, service) in &services {
+            let dir = service_directory(dir, container, service, &services);
+            std::fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+            let source = format!("{container}:{path}");
+            print_copy(&source, &dir.display());
+            docker_cp(&source, &dir.to_string_lossy(), !use_color)?;
+        }
+
+        let message = tr_args(
+            "Copied {path} from {count} container(s) of {stack}",
+            &[("path", path), ("count", &services.len()), ("stack", stack)],
+        );
+        if use_color {
+            color_println(Color::Green, &message);
+        } else {
+            println!("{message}");
+        }
+
+        return Ok(());
+    }
+
+    if source.is_host() == destination.is_host() {
+        anyhow::bail!(tr(
+            "Either the source or the destination must be in a container, such as web/app:/data"
+        ));
+    }
+
+    // services are resolved to their container, which is what docker cp understands
+    let docker_location = |location: &CopyLocation| -> anyhow::Result<String> {
+        Ok(match location {
+            CopyLocation::Host(path) => path.to_string_lossy().to_string(),
+            CopyLocation::Container { container, path } => format!("{container}:{path}"),
+            CopyLocation::Service {
+                stack,
+                service,
+                path,
+            } => {
+                let container = resolve_service(stack, service, &get_stack_services(stack)?)?;
+                format!("{container}:{path}")
+            }
+        })
+    };
+
+    print_copy(&source, &destination);
+    docker_cp(
+        &docker_location(&source)?,
+        &docker_location(&destination)?,
+        !use_color,
+    )
+}
+
+/// Resolves a container or stack given to env, including stopped containers, as the name of a
+/// stack or a full or partial container name
+fn env_targets(target: &str) -> anyhow::Result<Vec<String>> {
+    let containers = list_all_containers()?;
+
+    if !containers.iter().any(|container| container.name == target) {
+        let mut members = containers
+            .iter()
+            .filter(|container| container.stack.as_deref() == Some(target))
+            .map(|container| container.name.to_string())
+            .collect::<Vec<String>>();
+        if !members.is_empty() {
+            members.sort();
+            return Ok(members);
+        }
+    }
+
+    match match_container(target, &containers) {
+        ContainerMatch::Found(name) => Ok(vec![name]),
+        ContainerMatch::NotFound => match correct_typo(target, &containers, is_fuzzy())? {
+            Some(name) => Ok(vec![name]),
+            None => anyhow::bail!(tr_args(
+                "No container or stack matches {query}",
+                &[("query", &target)]
+            )),
+        },
+        ContainerMatch::Ambiguous(candidates) => anyhow::bail!(tr_args(
+            "{query} matches several containers: {containers}",
+            &[("query", &target), ("containers", &candidates.join(", "))]
+        )),
+    }
+}
+
+/// Lists the environment of a container or of all containers of a stack, masking secrets, or
+/// the vars that differ between two containers of the same service
+pub fn env_vars(target: String, diff: Option<String>, show_secrets: bool) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let containers = env_targets(&target)?;
+
+    if let Some(other) = diff {
+        let mut pair = vec![];
+        for (name, resolved) in [(&target, containers), (&other, env_targets(&other)?)] {
+            if resolved.len() != 1 {
+                anyhow::bail!(tr_args(
+                    "{stack} is a stack, compare two of its containers with --diff",
+                    &[("stack", name)]
+                ));
+            }
+            pair.extend(resolved);
+        }
+
+        let inspected = inspect_container_env(&pair)?;
+        let [left, right] = inspected.as_slice() else {
+            anyhow::bail!(tr("Failed to inspect containers"));
+        };
+        if !left.same_service(right) {
+            anyhow::bail!(tr_args(
+                "{left} and {right} are not containers of the same service",
+                &[("left", &left.container), ("right", &right.container)]
+            ));
+        }
+
+        return print_env_diff(left, right, show_secrets, use_color);
+    }
+
+    let inspected = inspect_container_env(&containers)?;
+    let several = inspected.len() > 1;
+
+    if is_accessible() {
+        for container in &inspected {
+            for (key, value) in &container.env {
+                println!(
+                    "{}",
+                    tr_args(
+                        "{container} sets {key} to {value}.",
+                        &[
+                            ("container", &container.container),
+                            ("key", key),
+                            ("value", &display_value(key, value, show_secrets)),
+                        ]
+                    )
+                );
+            }
+        }
+        return Ok(());
+    }
+
+    if several {
+        println!("{:<30} {:<30} VALUE", "CONTAINER", "KEY");
+    } else {
+        println!("{:<30} VALUE", "KEY");
+    }
+    for container in &inspected {
+        for (key, value) in &container.env {
+            let value = display_value(key, value, show_secrets);
+            let key = format!("{key:<30}");
+            let key = if use_color {
+                color_println_fmt(Color::Cyan, &key)
+            } else {
+                key
+            };
+
+            if !several {
+                println!("{key} {value}");
+            } else if use_color {
+                println!(
+                    "{} {key} {value}",
+                    color_println_fmt(
+                        container_color(&container.container),
+                        &format!("{:<30}", container.container)
+                    )
+                );
+            } else {
+                println!("{:<30} {key} {value}", container.container);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the env vars that differ between two containers of a service
+fn print_env_diff(
+    left: &ContainerEnv,
+    right: &ContainerEnv,
+    show_secrets: bool,
+    use_color: bool,
+) -> anyhow::Result<()> {
+    let differences = diff_env(&left.env, &right.env);
+
+    record("env_differences", differences.len());
+
+    if differences.is_empty() {
+        let message = tr_args(
+            "{left} and {right} have the same environment",
+            &[("left", &left.container), ("right", &right.container)],
+        );
+        if use_color {
+            color_println(Color::Green, &message);
+        } else {
+            println!("{message}");
+        }
+        return Ok(());
+    }
+
+    // a secret that differs is still listed, so the difference shows without its value
+    let shown = |key: &str, value: &Option<String>| match value {
+        Some(value) => display_value(key, value, show_secrets).to_string(),
+        None => "-".to_string(),
+    };
+
+    if is_accessible() {
+        for difference in &differences {
+            println!(
+                "{}",
+                tr_args(
+                    "{key} is {left_value} in {left} and {right_value} in {right}.",
+                    &[
+                        ("key", &difference.key),
+                        ("left", &left.container),
+                        ("left_value", &shown(&difference.key, &difference.left)),
+                        ("right", &right.container),
+                        ("right_value", &shown(&difference.key, &difference.right)),
+                    ]
+                )
+            );
+        }
+        return Ok(());
+    }
+
+    println!("{:<30} {:<30} {}", "KEY", left.container, right.container);
+    for difference in &differences {
+        let line = format!(
+            "{:<30} {:<30} {}",
+            difference.key,
+            shown(&difference.key, &difference.left),
+            shown(&difference.key, &difference.right)
+        );
+        if use_color {
+            color_println(role_color(Role::Warning), &line);
+        } else {
+            println!("{line}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Runs a command, or an interactive shell, in the running container a partial name or a prefix
+/// of an id refers to
+pub fn exec(container: String, command: Vec<String>) -> anyhow::Result<()> {
+    let running = crate::docker::list_containers()?;
+
+    let container = match match_container(&container, &running) {
+        ContainerMatch::Found(name) => name,
+        ContainerMatch::NotFound => match correct_typo(&container, &running, is_fuzzy())? {
+            Some(name) => name,
+            None => anyhow::bail!(tr_args(
+                "No running container matches {query}",
+                &[("query", &container)]
+            )),
+        },
+        ContainerMatch::Ambiguous(candidates) => anyhow::bail!(tr_args(
+            "{query} matches several containers: {containers}",
+            &[
+                ("query", &container),
+                ("containers", &candidates.join(", "))
+            ]
+        )),
+    };
+
+    let command = if command.is_empty() {
+        vec![detect_shell(&container).to_string()]
+    } else {
+        command
+    };
+
+    if is_terminal() {
+        color_println(
+            Color::Magenta,
+            &tr_args(
+                "Running {command} in {container}",
+                &[("command", &command.join(" ")), ("container", &container)],
+            ),
+        );
+    }
+
+    // a tty would mangle output piped into another command
+    let tty_args: &[&str] = if io::stdin().is_terminal() && io::stdout().is_terminal() {
+        &["-t"]
+    } else {
+        &[]
+    };
+
+    let status = Command::new(DOCKER)
+        .args(["exec", "-i"])
+        .args(tty_args)
+        .arg(&container)
+        .args(&command)
+        .status()
+        .with_context(|| format!("Failed to exec in {container}"))?;
+
+    // exit like docker exec would, so scripts see the exit code of the command
+    if !status.success() {
+        std::process::exit(status.code().unwrap_or(1));
+    }
+
+    Ok(())
+}
+
+/// Restarts specified docker containers, or all containers of stacks given by name
+pub fn restart(
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    all: bool,
+    ordered: bool,
+) -> anyhow::Result<()> {
+    let containers = match containers {
+        Some(names) if !all => Some(expand_stack_names(names)?),
+        containers => containers,
+    };
+
+    let containers = resolve_containers(containers, stacks, all)?;
+
+    let containers = if ordered {
+        sort_by_dependencies(&containers)?
+    } else {
+        containers
+    };
+
+    let use_color = is_terminal();
+    let mut results = BatchResults::default();
+
+    start_group(&tr("Restarting containers"));
+
+    for container in &containers {
+        if use_color {
+            print!(
+                "{} ",
+                color_println_fmt(
+                    Color::Cyan,
+                    &tr_args("Restarting container: {name}", &[("name", container)])
+                )
+            );
+        } else {
+            print!(
+                "{} ",
+                tr_args("Restarting container: {name}", &[("name", container)])
+            )
+        }
+        let _ = io::stdout().flush();
+
+        let output = Command::new(DOCKER)
+            .args(["restart", container])
+            .output()
+            .context(format!("Failed to restart {}", &container))?;
+
+        if output.status.success() {
+            if use_color {
+                color_println(Color::Green, &tr("ok"));
+            } else {
+                println!("{}", tr("ok"));
+            }
+            record_history(HistoryEntry::new(container, Action::Restart, None));
+            results.succeeded(container);
+        } else {
+            let error = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            if use_color {
+                color_println(
+                    role_color(Role::Error),
+                    &tr_args("failed: {error}", &[("error", &error)]),
+                );
+            } else {
+                println!("{}", tr_args("failed: {error}", &[("error", &error)]));
+            }
+            record_history(HistoryEntry::new(
+                container,
+                Action::Restart,
+                Some(error.clone()),
+            ));
+            results.failed(container, error);
+        }
+    }
+
+    end_group();
+
+    let failed = results.failed_containers();
+    record("containers_restarted", results.succeeded_count());
+    record("containers_failed", failed.len());
+    print_batch_summary(&results, use_color);
+
+    Ok(results.into_result(tr_args(
+        "Failed to restart: {containers}",
+        &[("containers", &failed.join(", "))],
+    ))?)
+}
+
+/// Stops, starts, pauses or unpauses containers, printing for each whether it worked
+pub fn lifecycle(
+    lifecycle: Lifecycle,
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    all: bool,
+    timeout: std::time::Duration,
+) -> anyhow::Result<()> {
+    let containers = if lifecycle.needs_running() {
+        let containers = match containers {
+            Some(names) if !all => Some(expand_stack_names(names)?),
+            containers => containers,
+        };
+        resolve_containers(containers, stacks, all)?
+    } else {
+        resolve_containers_including_stopped(containers, stacks, all)?
+    };
+
+    let use_color = is_terminal();
+    let mut results = BatchResults::default();
+
+    start_group(&tr(lifecycle.title()));
+
+    for container in &containers {
+        let progress = tr_args(lifecycle.progress(), &[("name", container)]);
+        if use_color {
+            print!("{} ", color_println_fmt(Color::Cyan, &progress));
+        } else {
+            print!("{progress} ");
+        }
+        let _ = io::stdout().flush();
+
+        match lifecycle.apply(container, timeout)? {
+            Ok(()) => {
+                if use_color {
+                    color_println(Color::Green, &tr("ok"));
+                } else {
+                    println!("{}", tr("ok"));
+                }
+                record_history(HistoryEntry::new(container, lifecycle.action(), None));
+                results.succeeded(container);
+            }
+            Err(error) => {
+                if use_color {
+                    color_println(
+                        role_color(Role::Error),
+                        &tr_args("failed: {error}", &[("error", &error)]),
+                    );
+                } else {
+                    println!("{}", tr_args("failed: {error}", &[("error", &error)]));
+                }
+                record_history(HistoryEntry::new(
+                    container,
+                    lifecycle.action(),
+                    Some(error.clone()),
+                ));
+                results.failed(container, error);
+            }
+        }
+    }
+
+    end_group();
+
+    let failed = results.failed_containers();
+    record(lifecycle.output_key(), results.succeeded_count());
+    record("containers_failed", failed.len());
+    print_batch_summary(&results, use_color);
+
+    Ok(results.into_result(tr_args(
+        lifecycle.failure(),
+        &[("containers", &failed.join(", "))],
+    ))?)
+}
+
+/// Waits until all containers of the specified stacks are healthy
+pub fn wait_healthy(
+    stacks: Vec<String>,
+    timeout: std::time::Duration,
+    interval: std::time::Duration,
+) -> anyhow::Result<()> {
+    await_healthy(
+        || {
+            let containers = resolve_containers(None, Some(stacks.clone()), false)?;
+
+            if containers.is_empty() {
+                anyhow::bail!(tr_args(
+                    "No containers found in stack: {stack}",
+                    &[("stack", &stacks.join(", "))]
+                ));
+            }
+
+            Ok(containers)
+        },
+        timeout,
+        interval,
+    )
+}
+
+/// Waits until all containers are healthy, looking them up again on every check as they may be
+/// recreated while waiting
+fn await_healthy(
+    containers: impl Fn() -> anyhow::Result<Vec<String>>,
+    timeout: std::time::Duration,
+    interval: std::time::Duration,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let start = std::time::Instant::now();
+    let mut last_waiting: Vec<String> = vec![];
+
+    loop {
+        let containers = containers()?;
+        let inspected = inspect_containers(&containers)?;
+
+        // containers that stopped will not become healthy by waiting
+        let stopped = inspected
+            .iter()
+            .filter(|inspect| matches!(inspect.status.as_str(), "exited" | "dead"))
+            .map(|inspect| format!("{} ({})", inspect.container_name, inspect.status))
+            .collect::<Vec<String>>();
+
+        if !stopped.is_empty() {
+            anyhow::bail!(tr_args(
+                "Containers stopped: {containers}",
+                &[("containers", &stopped.join(", "))]
+            ));
+        }
+
+        // containers without a healthcheck only need to be running
+        let waiting = inspected
+            .iter()
+            .filter(|inspect| {
+                inspect.status != "running"
+                    || (inspect.health != "healthy" && inspect.health != "N/A")
+            })
+            .map(|inspect| {
+                let state = if inspect.status == "running" {
+                    &inspect.health
+                } else {
+                    &inspect.status
+                };
+                format!("{} ({})", inspect.container_name, state)
+            })
+            .collect::<Vec<String>>();
+
+        if waiting.is_empty() {
+            if use_color {
+                color_println(
+                    Color::Green,
+                    &tr_args(
+                        "All {count} container(s) healthy",
+                        &[("count", &inspected.len())],
+                    ),
+                );
+            } else {
+                println!(
+                    "{}",
+                    tr_args(
+                        "All {count} container(s) healthy",
+                        &[("count", &inspected.len())]
+                    )
+                );
+            }
+            return Ok(());
+        }
+
+        if start.elapsed() >= timeout {
+            anyhow::bail!(tr_args(
+                "Timed out waiting for containers to become healthy: {containers}",
+                &[("containers", &waiting.join(", "))]
+            ));
+        }
+
+        if waiting != last_waiting {
+            if use_color {
+                println!(
+                    "[{}] {} {}",
+                    color_println_fmt(role_color(Role::Timestamp), &get_timestamp()),
+                    color_println_fmt(role_color(Role::Warning), &tr("Waiting for:")),
+                    waiting.join(", ")
+                );
+            } else {
+                println!(
+                    "[{}] {} {}",
+                    &get_timestamp(),
+                    tr("Waiting for:"),
+                    waiting.join(", ")
+                );
+            }
+            last_waiting = waiting;
+        }
+
+        std::thread::sleep(interval);
+    }
+}
+
+/// Stops stacks for host maintenance, dependents first, recording what was running
+pub fn maintenance_start(stacks: Vec<String>, all: bool) -> anyhow::Result<()> {
+    if let Some(state) = load_maintenance()? {
+        anyhow::bail!(tr_args(
+            "Maintenance already started at {time}, run maintenance end first",
+            &[("time", &state.started_at)]
+        ));
+    }
+
+    let stacks = if all {
+        running_stacks(&crate::docker::list_containers()?)
+    } else if stacks.is_empty() {
+        anyhow::bail!(tr("Must specify stacks or use --all (-a)"));
+    } else {
+        expand_groups(&stacks)?
+    };
+
+    let mut frozen = vec![];
+    for stack in stacks {
+        let containers = get_containers_from_stack(&stack)?;
+        if containers.is_empty() {
+            continue;
+        }
+
+        frozen.push(FrozenStack {
+            stack,
+            containers: sort_by_dependencies(&containers)?,
+        });
+    }
+
+    if frozen.is_empty() {
+        anyhow::bail!(tr("No containers running"));
+    }
+
+    // recorded before stopping, so a failure halfway can still be undone with maintenance end
+    let state = MaintenanceState {
+        started_at: get_timestamp(),
+        stacks: frozen,
+    };
+    save_maintenance(&state)?;
+
+    let use_color = is_terminal();
+    let mut failed: Vec<String> = vec![];
+
+    start_group(&tr("Stopping containers"));
+    for stack in &state.stacks {
+        for container in stack.containers.iter().rev() {
+            println!(
+                "{}",
+                tr_args("Stopping {container}", &[("container", container)])
+            );
+
+            if let Err(e) = set_running(std::slice::from_ref(container), false) {
+                if use_color {
+                    color_println(role_color(Role::Error), &format!("{e:#}"));
+                } else {
+                    println!("{e:#}");
+                }
+                failed.push(container.to_string());
+            }
+        }
+    }
+    end_group();
+
+    record("containers_stopped", state.container_count() - failed.len());
+
+    if !failed.is_empty() {
+        anyhow::bail!(tr_args(
+            "Failed to stop: {containers}",
+            &[("containers", &failed.join(", "))]
+        ));
+    }
+
+    let message = tr_args(
+        "Stopped {count} container(s) of {stacks}, run maintenance end to start them again",
+        &[
+            ("count", &state.container_count()),
+            ("stacks", &state.stack_names().join(", ")),
+        ],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+/// Starts what maintenance start stopped, dependencies first, and waits until it is healthy,
+/// optionally notifying of the outcome
+pub fn maintenance_end(
+    timeout: std::time::Duration,
+    interval: std::time::Duration,
+    notify: bool,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let result = restore_maintenance(timeout, interval, use_color);
+
+    if notify {
+        let event = match &result {
+            Ok(message) => Event::new(EventSource::Maintenance, Severity::Info, message),
+            Err(e) => Event::new(
+                EventSource::Maintenance,
+                Severity::Critical,
+                tr_args(
+                    "Failed to end maintenance: {error}",
+                    &[("error", &format!("{e:#}"))],
+                ),
+            ),
+        };
+        print_notify_error(notify_events(&[event]), use_color);
+    }
+
+    result.map(|_| ())
+}
+
+/// Starts the containers of the maintenance in progress, returning the message reporting it
+fn restore_maintenance(
+    timeout: std::time::Duration,
+    interval: std::time::Duration,
+    use_color: bool,
+) -> anyhow::Result<String> {
+    let Some(state) = load_maintenance()? else {
+        anyhow::bail!(tr("No maintenance in progress"));
+    };
+
+    let mut failed: Vec<String> = vec![];
+
+    start_group(&tr("Starting containers"));
+    for stack in &state.stacks {
+        for container in &stack.containers {
+            println!(
+                "{}",
+                tr_args("Starting {container}", &[("container", container)])
+            );
+
+            if let Err(e) = set_running(std::slice::from_ref(container), true) {
+                if use_color {
+                    color_println(role_color(Role::Error), &format!("{e:#}"));
+                } else {
+                    println!("{e:#}");
+                }
+                failed.push(container.to_string());
+            }
+        }
+    }
+    end_group();
+
+    record("containers_started", state.container_count() - failed.len());
+
+    // the state is kept until everything is up, so maintenance end can simply be run again
+    if !failed.is_empty() {
+        anyhow::bail!(tr_args(
+            "Failed to start: {containers}",
+            &[("containers", &failed.join(", "))]
+        ));
+    }
+
+    wait_healthy(state.stack_names(), timeout, interval)?;
+    clear_maintenance()?;
+
+    let message = tr_args(
+        "Restored {count} container(s) of {stacks}",
+        &[
+            ("count", &state.container_count()),
+            ("stacks", &state.stack_names().join(", ")),
+        ],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(message)
+}
+
+/// Shows which stacks a maintenance in progress stopped
+pub fn maintenance_status() -> anyhow::Result<()> {
+    let Some(state) = load_maintenance()? else {
+        println!("{}", tr("No maintenance in progress"));
+        record("in_progress", false);
+        return Ok(());
+    };
+
+    record("in_progress", true);
+    println!(
+        "{}",
+        tr_args(
+            "Maintenance started at {time}, stopped containers:",
+            &[("time", &state.started_at)]
+        )
+    );
+
+    for stack in &state.stacks {
+        println!("  {}: {}", stack.stack, stack.containers.join(", "));
+    }
+
+    Ok(())
+}
+
+/// Reports whether the host needs a reboot, or with safe stops the stacks, reboots and leaves
+/// starting them again to a systemd unit after the boot
+pub fn reboot(safe: bool, stacks: Vec<String>, if_required: bool, yes: bool) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let required = reboot_required();
+    record("reboot_required", required.is_some());
+
+    let status = match &required {
+        Some(packages) if !packages.is_empty() => tr_args(
+            "The host needs a reboot for {packages}",
+            &[("packages", &packages.join(", "))],
+        ),
+        Some(_) => tr("The host needs a reboot"),
+        None => tr("No reboot required"),
+    };
+
+    if use_color {
+        let color = if required.is_some() {
+            role_color(Role::Warning)
+        } else {
+            Color::Green
+        };
+        color_println(color, &status);
+    } else {
+        println!("{status}");
+    }
+
+    if !safe || (if_required && required.is_none()) {
+        return Ok(());
+    }
+
+    if !yes {
+        if is_ci() {
+            anyhow::bail!(tr("Refusing to reboot without --yes in CI mode"));
+        }
+
+        print!("{} [y/N]: ", tr("Stop the stacks and reboot the host?"));
+        let _ = io::stdout().flush();
+
+        let mut input = String::new();
+        let _ = io::stdin().read_line(&mut input);
+
+        if !matches!(input.trim().to_lowercase().as_str(), "yes" | "y") {
+            println!("{}", tr("Reboot aborted!"));
+            return Ok(());
+        }
+    }
+
+    // installed first, as stopping the stacks without a way back after the boot is worse than
+    // not rebooting at all
+    install_thaw_unit()?;
+
+    let all = stacks.is_empty();
+    maintenance_start(stacks, all)?;
+
+    let message = tr_args(
+        "Rebooting, {unit} starts the stacks again after the boot",
+        &[("unit", &THAW_UNIT)],
+    );
+    if use_color {
+        color_println(Color::Cyan, &message);
+    } else {
+        println!("{message}");
+    }
+    let _ = io::stdout().flush();
+
+    reboot_host()
+}
+
+/// Deploys new images for services of a stack and waits until the stack is healthy
+pub fn deploy(
+    stack: String,
+    images: Vec<ImageOverride>,
+    timeout: std::time::Duration,
+    interval: std::time::Duration,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    let container = get_containers_from_stack(&stack)?
+        .into_iter()
+        .next()
+        .with_context(|| {
+            tr_args(
+                "No containers found in stack: {stack}",
+                &[("stack", &stack)],
+            )
+        })?;
+    let compose_info = get_compose_info(&container)?
+        .with_context(|| format!("Stack {stack} is not managed by docker compose"))?;
+
+    let guard = LoadGuard::from_config(&Config::load()?.guard)?;
+    let new_images = images
+        .iter()
+        .map(|image| image.image.to_string())
+        .collect::<Vec<String>>();
+    if !confirm_pull_space(&guard, &new_images, use_color)? {
+        return Ok(());
+    }
+
+    let override_file = write_override(&compose_info.project, &images)?;
+    let services = images
+        .iter()
+        .map(|image| image.service.to_string())
+        .collect::<Vec<String>>();
+
+    start_group(&tr_args("Deploying {stack}", &[("stack", &stack)]));
+
+    for image in &images {
+        if use_color {
+            println!(
+                "{} {}",
+                color_println_fmt(Color::Cyan, &image.service),
+                image.image
+            );
+        } else {
+            println!("{} {}", image.service, image.image);
+        }
+    }
+
+    let status = compose_command(&compose_info)
+        .arg("-f")
+        .arg(&override_file)
+        .args(["up", "-d", "--no-deps"])
+        .args(&services)
+        .status()
+        .context(format!("Failed to deploy {stack}"));
+
+    end_group();
+
+    let result = match status {
+        Ok(status) if status.success() => {
+            start_group(&tr("Waiting for healthy containers"));
+            let result = wait_healthy(vec![stack.to_string()], timeout, interval);
+            end_group();
+            result
+        }
+        Ok(_) => Err(anyhow::anyhow!("Failed to deploy {stack}")),
+        Err(e) => Err(e),
+    };
+
+    record("services_deployed", services.len());
+
+    write_github_outputs(&[
+        ("stack", stack.to_string()),
+        ("services", services.join(",")),
+        ("images", new_images.join(",")),
+        (
+            "status",
+            if result.is_ok() { "healthy" } else { "failed" }.to_string(),
+        ),
+    ])?;
+
+    if annotations_enabled() {
+        match &result {
+            Ok(()) => {
+                for image in &images {
+                    println!(
+                        "{}",
+                        github_annotation(
+                            "notice",
+                            &format!("Deployed {stack}"),
+                            &format!("{} is running {}", image.service, image.image)
+                        )
+                    );
+                }
+            }
+            Err(e) => println!(
+                "{}",
+                github_annotation(
+                    "error",
+                    &format!("Deploy of {stack} failed"),
+                    &format!("{e:#}")
+                )
+            ),
+        }
+    }
+
+    result
+}
+
+/// Scales services of a stack without touching its compose files, listing the containers each
+/// service runs afterwards
+pub fn scale(stack: String, scales: Vec<ServiceScale>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    let container = get_containers_from_stack(&stack)?
+        .into_iter()
+        .next()
+        .with_context(|| {
+            tr_args(
+                "No containers found in stack: {stack}",
+                &[("stack", &stack)],
+            )
+        })?;
+    let compose_info = get_compose_info(&container)?
+        .with_context(|| format!("Stack {stack} is not managed by docker compose"))?;
+
+    start_group(&tr_args("Scaling {stack}", &[("stack", &stack)]));
+
+    let mut command = compose_command(&compose_info);
+    // existing replicas keep running, only the missing ones are created or the extra ones removed
+    command.args(["up", "-d", "--no-deps", "--no-recreate"]);
+    for scale in &scales {
+        command
+            .arg("--scale")
+            .arg(format!("{}={}", scale.service, scale.replicas));
+    }
+    let status = command
+        .args(scales.iter().map(|scale| &scale.service))
+        .status()
+        .context(format!("Failed to scale {stack}"))?;
+
+    end_group();
+
+    if !status.success() {
+        anyhow::bail!(tr_args("Failed to scale {stack}", &[("stack", &stack)]));
+    }
+
+    // compose labels every replica with its service, so they tell how many are running
+    let services = get_stack_services(&stack)?;
+    let mut missed = vec![];
+
+    for scale in &scales {
+        let replicas = service_replicas(&services, &scale.service);
+        let message = tr_args(
+            "{service}: {running} of {replicas} replica(s) running",
+            &[
+                ("service", &scale.service),
+                ("running", &replicas.len()),
+                ("replicas", &scale.replicas),
+            ],
+        );
+        let reached = replicas.len() == scale.replicas;
+
+        if use_color {
+            let color = if reached {
+                Color::Green
+            } else {
+                role_color(Role::Error)
+            };
+            color_println(color, &message);
+        } else {
+            println!("{message}");
+        }
+        for replica in &replicas {
+            println!("  {replica}");
+        }
+
+        if !reached {
+            missed.push(scale.service.to_string());
+        }
+    }
+
+    record("services_scaled", scales.len() - missed.len());
+
+    if !missed.is_empty() {
+        anyhow::bail!(tr_args(
+            "Replica count not reached for: {services}",
+            &[("services", &missed.join(", "))]
+        ));
+    }
+
+    Ok(())
+}
+
+/// Generates or loads SBOMs of images and lists their packages
+pub fn sbom(images: Option<Vec<String>>, all: bool, refresh: bool) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    let images = match images {
+        Some(images) if !all => images,
+        _ => {
+            let mut images = list_containers()?
+                .iter()
+                .map(|container| get_container_image(container))
+                .collect::<Result<Vec<String>, DsdError>>()?;
+            images.sort();
+            images.dedup();
+            images
+        }
+    };
+
+    for image in &images {
+        let sbom = load_sbom(image, refresh)?;
+
+        if use_color {
+            color_println(
+                Color::Cyan,
+                &format!(
+                    "{} ({} packages, {:?})",
+                    image,
+                    sbom.packages.len(),
+                    sbom.source
+                ),
+            );
+        } else {
+            println!(
+                "{} ({} packages, {:?})",
+                image,
+                sbom.packages.len(),
+                sbom.source
+            );
+        }
+
+        for package in &sbom.packages {
+            println!(
+                "  {:<40} {:<30} {}",
+                package.name,
+                package.version,
+                package.licenses.join(", ")
+            );
+        }
+
+        println!();
+    }
+
+    Ok(())
+}
+
+/// Searches the SBOMs of container images for a package, optionally older than a version
+pub fn pkg_search(
+    package: String,
+    below: Option<String>,
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    refresh: bool,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    // searching is most useful across the whole host, so default to all containers
+    let all = containers.is_none() && stacks.is_none();
+    let containers = resolve_containers(containers, stacks, all)?;
+
+    let mut sboms: HashMap<String, Sbom> = HashMap::new();
+    let mut matches = vec![];
+
+    for container in &containers {
+        let image = get_container_image(container)?;
+
+        if !sboms.contains_key(&image) {
+            sboms.insert(image.to_string(), load_sbom(&image, refresh)?);
+        }
+
+        for found in &sboms[&image].packages {
+            let is_match = found.name == package || matches_pattern(&package, &found.name);
+            let is_below = match &below {
+                Some(below) => compare_versions(&found.version, below) == Ordering::Less,
+                None => true,
+            };
+
+            if is_match && is_below {
+                matches.push((container.to_string(), image.to_string(), found.clone()));
+            }
+        }
+    }
+
+    if matches.is_empty() {
+        if use_color {
+            color_println(
+                Color::Green,
+                &tr_args("No containers ship {package}", &[("package", &package)]),
+            );
+        } else {
+            println!(
+                "{}",
+                tr_args("No containers ship {package}", &[("package", &package)])
+            );
+        }
+        return Ok(());
+    }
+
+    if use_color {
+        println!(
+            "{:<35} {:<40} {:<40} {:<20}",
+            &color_println_fmt(Color::White, "CONTAINER"),
+            "IMAGE",
+            &color_println_fmt(Color::White, "PACKAGE"),
+            "VERSION"
+        );
+    } else {
+        println!(
+            "{:<35} {:<40} {:<40} {:<20}",
+            "CONTAINER", "IMAGE", "PACKAGE", "VERSION"
+        );
+    }
+
+    println!();
+
+    for (container, image, found) in &matches {
+        let container = if use_color {
+            color_println_fmt(Color::Cyan, container)
+        } else {
+            container.to_string()
+        };
+
+        let version = if use_color && below.is_some() {
+            color_println_fmt(role_color(Role::Error), &found.version)
+        } else {
+            found.version.to_string()
+        };
+
+        println!(
+            "{:<35} {:<40} {:<40} {:<20}",
+            container, image, found.name, version
+        );
+    }
+
+    Ok(())
+}
+
+/// Explains what compose up would do for each service of a project, without invoking compose
+pub fn stack_plan(dir: PathBuf) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let plan = plan_stack(&dir)?;
+
+    if use_color {
+        color_println(
+            Color::Magenta,
+            &tr_args(
+                "Plan for stack {stack} from {files}",
+                &[
+                    ("stack", &plan.project),
+                    (
+                        "files",
+                        &plan
+                            .compose_files
+                            .iter()
+                            .map(|file| file.display().to_string())
+                            .collect::<Vec<String>>()
+                            .join(", "),
+                    ),
+                ],
+            ),
+        );
+        println!();
+        println!(
+            "{:<35} {:<10} {:<24}",
+            &color_println_fmt(Color::White, "SERVICE"),
+            "ACTION",
+            &color_println_fmt(Color::White, "REASONS"),
+        );
+    } else {
+        println!("{:<35} {:<10} {:<24}", "SERVICE", "ACTION", "REASONS");
+    }
+
+    println!();
+
+    for service in &plan.services {
+        let (service_name, action) = if use_color {
+            let color = match service.action {
+                PlanAction::Create => Color::Green,
+                PlanAction::Recreate => role_color(Role::Warning),
+                PlanAction::Start => Color::Cyan,
+                PlanAction::Noop => Color::White,
+            };
+            (
+                color_println_fmt(Color::Cyan, &service.service),
+                color_println_fmt(color, service.action.as_str()),
+            )
+        } else {
+            (
+                service.service.to_string(),
+                service.action.as_str().to_string(),
+            )
+        };
+
+        // the colored action carries escape codes that do not take up space
+        let action_width = if use_color {
+            10 + action.len() - service.action.as_str().len()
+        } else {
+            10
+        };
+
+        println!(
+            "{:<35} {:<action_width$} {}",
+            service_name,
+            action,
+            service.reasons.join("; ")
+        );
+    }
+
+    Ok(())
+}
+
+/// Reports ports, container names and bind mounts that compose projects on the host, and those
+/// in `dirs`, would fight over
+pub fn conflicts(dirs: Vec<PathBuf>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let containers = list_all_containers()?;
+
+    // compose records the files and directory it was run with on the containers it creates
+    let mut projects: BTreeMap<String, (PathBuf, serde_json::Value)> = BTreeMap::new();
+    for container in &containers {
+        let Some(stack) = &container.stack else {
+            continue;
+        };
+        if projects.contains_key(stack) {
+            continue;
+        }
+
+        match load_project_config(&container.name) {
+            Ok(project) => {
+                projects.insert(stack.to_string(), project);
+            }
+            Err(e) => eprintln!(
+                "{}",
+                tr_args(
+                    "Skipping stack {stack}: {error}",
+                    &[("stack", &stack), ("error", &format!("{e:#}"))]
+                )
+            ),
+        }
+    }
+
+    // a directory wins over what running containers recorded, as it is about to be deployed
+    for dir in &dirs {
+        let compose_files = find_compose_files(dir)?;
+        let working_dir = dir
+            .canonicalize()
+            .with_context(|| format!("Failed to resolve {}", dir.display()))?;
+        let config = load_compose_config(&compose_files, dir)?;
+
+        projects.insert(project_name(dir, &compose_files[0]), (working_dir, config));
+    }
+
+    let services = projects
+        .iter()
+        .flat_map(|(project, (working_dir, config))| {
+            declared_services(project, working_dir, config)
+        })
+        .collect::<Vec<DeclaredService>>();
+
+    let standalone = containers
+        .iter()
+        .filter(|container| container.stack.is_none())
+        .map(|container| container.name.to_string())
+        .collect::<Vec<String>>();
+    let standalone_ports = if standalone.is_empty() {
+        vec![]
+    } else {
+        port_mappings(&containers, &inspect_containers(&standalone)?)
+    };
+
+    let conflicts = find_project_conflicts(&services, &containers, &standalone_ports);
+    record("conflicts", conflicts.len());
+
+    if conflicts.is_empty() {
+        let message = tr_args(
+            "No conflicts between compose projects ({count} checked)",
+            &[("count", &projects.len())],
+        );
+        if use_color {
+            color_println(Color::Green, &message);
+        } else {
+            println!("{message}");
+        }
+        return Ok(());
+    }
+
+    for conflict in &conflicts {
+        if is_accessible() {
+            println!(
+                "{}",
+                tr_args("Conflict: {conflict}.", &[("conflict", conflict)])
+            );
+        } else if use_color {
+            color_println(role_color(Role::Error), &conflict.to_string());
+        } else {
+            println!("{conflict}");
+        }
+    }
+
+    anyhow::bail!(tr_args(
+        "Found {count} conflicts between compose projects",
+        &[("count", &conflicts.len())]
+    ));
+}
+
+/// Working directory and merged compose config of the project a container belongs to
+fn load_project_config(container: &str) -> anyhow::Result<(PathBuf, serde_json::Value)> {
+    let compose_info = get_compose_info(container)?
+        .with_context(|| format!("{container} does not belong to a compose project"))?;
+
+    if compose_info.config_files.is_empty() {
+        anyhow::bail!(
+            "Stack {} does not record its compose files",
+            compose_info.project
+        );
+    }
+
+    let compose_files = compose_info
+        .config_files
+        .iter()
+        .map(PathBuf::from)
+        .collect::<Vec<PathBuf>>();
+    let working_dir = PathBuf::from(&compose_info.working_dir);
+    let config = load_compose_config(&compose_files, &working_dir)?;
+
+    Ok((working_dir, config))
+}
+
+/// Checks the docker setup and prints what to do about anything missing or broken
+pub fn doctor() -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let mut checks = run_checks();
+    if let Some(user) = Config::load()?.daemon.user {
+        checks.push(daemon_user_check(&user));
+    }
+
+    for check in &checks {
+        let (status, color, detail) = match &check.status {
+            CheckStatus::Passed(found) => (tr("ok"), Color::Green, found.to_string()),
+            CheckStatus::Failed(problem) => {
+                (tr("failed"), role_color(Role::Error), problem.to_string())
+            }
+            CheckStatus::Skipped => (tr("skipped"), role_color(Role::Warning), String::new()),
+        };
+        let name = tr(check.name);
+
+        if is_accessible() {
+            println!(
+                "{}",
+                tr_args(
+                    "Check {name}: {status}. {detail}",
+                    &[("name", &name), ("status", &status), ("detail", &detail)]
+                )
+                .trim_end()
+            );
+        } else {
+            let status = if use_color {
+                color_println_fmt(color, &status)
+            } else {
+                status
+            };
+            if detail.is_empty() {
+                println!("{name}: {status}");
+            } else {
+                println!("{name}: {status} ({detail})");
+            }
+        }
+
+        if let CheckStatus::Failed(problem) = &check.status {
+            println!(
+                "  {}",
+                tr_args("Hint: {hint}", &[("hint", &problem.hint())])
+            );
+        }
+    }
+
+    let problems = checks
+        .iter()
+        .filter(|check| matches!(check.status, CheckStatus::Failed(_)))
+        .count();
+    record("problems", problems);
+
+    if problems > 0 {
+        anyhow::bail!(tr_args(
+            "Found {count} problems with the docker setup",
+            &[("count", &problems)]
+        ));
+    }
+
+    Ok(())
+}
+
+/// Makes sure docker and compose can be used before a command talks to them, so it fails with a
+/// hint on what to fix rather than with whatever the first docker command it runs prints
+pub fn check_docker() -> anyhow::Result<()> {
+    preflight().map_err(|problem| {
+        anyhow::anyhow!(
+            "{problem}\n{}",
+            tr_args(
+                "Hint: {hint}, or run dsd-util doctor to check the whole setup",
+                &[("hint", &problem.hint())]
+            )
+        )
+    })
+}
+
+/// Reports where the containers of a stack differ from the compose files they were created from
+pub fn drift(stack: String) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let drift = stack_drift(&stack)?;
+
+    let files = drift
+        .compose_files
+        .iter()
+        .map(|file| file.display().to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    let header = tr_args(
+        "Drift of {stack} from {files}",
+        &[("stack", &drift.stack), ("files", &files)],
+    );
+    if use_color {
+        color_println(Color::Magenta, &header);
+    } else {
+        println!("{header}");
+    }
+    println!();
+
+    let drifted = drift
+        .services
+        .iter()
+        .filter(|service| !service.drifts.is_empty())
+        .collect::<Vec<_>>();
+    record("drifted_services", drifted.len());
+
+    if drifted.is_empty() {
+        let message = tr_args(
+            "No drift, {stack} matches its compose files",
+            &[("stack", &drift.stack)],
+        );
+        if use_color {
+            color_println(Color::Green, &message);
+        } else {
+            println!("{message}");
+        }
+        return Ok(());
+    }
+
+    if !is_accessible() {
+        println!("{:<25} {:<30} DRIFT", "SERVICE", "CONTAINER");
+    }
+
+    for service in drifted {
+        let container = service.container.as_deref().unwrap_or("-");
+        let drifts = service
+            .drifts
+            .iter()
+            .map(|drift| drift.to_string())
+            .collect::<Vec<String>>()
+            .join("; ");
+
+        if is_accessible() {
+            println!(
+                "{}",
+                tr_args(
+                    "Service {service} ({container}) drifted: {drifts}.",
+                    &[
+                        ("service", &service.service),
+                        ("container", &container),
+                        ("drifts", &drifts),
+                    ]
+                )
+            );
+        } else if use_color {
+            // pad before coloring, escape codes do not take up space
+            println!(
+                "{} {:<30} {}",
+                color_println_fmt(Color::Cyan, &format!("{:<25}", service.service)),
+                container,
+                color_println_fmt(role_color(Role::Warning), &drifts)
+            );
+        } else {
+            println!("{:<25} {:<30} {}", service.service, container, drifts);
+        }
+    }
+
+    Ok(())
+}
+
+/// Sets the tag of the image of a service in the compose files of a project, optionally
+/// committing the edit, then recreates the service with it unless `apply` is false
+pub fn stack_bump(
+    service: String,
+    tag: String,
+    dir: PathBuf,
+    commit: bool,
+    apply: bool,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let compose_files = find_compose_files(&dir)?;
+    let project = project_name(&dir, &compose_files[0]);
+
+    // the override file wins when it sets the image too
+    let mut edit = None;
+    for file in compose_files.iter().rev() {
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read compose file: {}", file.display()))?;
+
+        if let Some((edited, bump)) = bump_image(&contents, &service, &tag)
+            .with_context(|| format!("Failed to edit {}", file.display()))?
+        {
+            edit = Some((file, contents, edited, bump));
+            break;
+        }
+    }
+
+    let Some((file, contents, edited, bump)) = edit else {
+        anyhow::bail!(tr_args(
+            "No image for {service} in {files}",
+            &[
+                ("service", &service),
+                (
+                    "files",
+                    &compose_files
+                        .iter()
+                        .map(|file| file.display().to_string())
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                ),
+            ],
+        ));
+    };
+
+    if edited == contents {
+        println!(
+            "{}",
+            tr_args(
+                "{service} already runs {image}",
+                &[("service", &service), ("image", &bump.new_image)],
+            )
+        );
+        return Ok(());
+    }
+
+    std::fs::write(file, &edited)
+        .with_context(|| format!("Failed to write compose file: {}", file.display()))?;
+    record("bumped_image", bump.new_image.as_str());
+
+    let message = tr_args(
+        "Bumped {service} from {old} to {new} in {file}:{line}",
+        &[
+            ("service", &service),
+            ("old", &bump.old_image),
+            ("new", &bump.new_image),
+            ("file", &file.display()),
+            ("line", &bump.line),
+        ],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    if commit {
+        commit_file(file, &commit_message(&project, &bump))?;
+        println!(
+            "{}",
+            tr_args("Committed {file}", &[("file", &file.display())])
+        );
+    }
+
+    if !apply {
+        return Ok(());
+    }
+
+    let compose_info = ComposeInfo {
+        project,
+        service: service.clone(),
+        working_dir: dir.to_string_lossy().to_string(),
+        config_files: compose_files
+            .iter()
+            .map(|file| file.to_string_lossy().to_string())
+            .collect(),
+    };
+
+    let status = compose_command(&compose_info)
+        .args(["up", "-d", "--no-deps", &service])
+        .status()
+        .with_context(|| format!("Failed to recreate {service}"))?;
+
+    if !status.success() {
+        anyhow::bail!(tr_args(
+            "Failed to recreate {service} with {image}",
+            &[("service", &service), ("image", &bump.new_image)],
+        ));
+    }
+
+    Ok(())
+}
+
+pub fn stack_backup(stack: String, out: PathBuf, stop: bool) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let containers = inspect_disk_containers()?;
+
+    if !containers
+        .iter()
+        .any(|container| container.stack.as_deref() == Some(stack.as_str()))
+    {
+        anyhow::bail!(tr_args(
+            "No containers found in stack: {stack}",
+            &[("stack", &stack)]
+        ));
+    }
+
+    let volumes = stack_volumes(&stack, &containers);
+    if volumes.is_empty() {
+        anyhow::bail!(tr_args(
+            "No named volumes found in stack: {stack}",
+            &[("stack", &stack)]
+        ));
+    }
+
+    std::fs::create_dir_all(&out)
+        .with_context(|| format!("Failed to create directory: {}", out.display()))?;
+
+    // stopping keeps databases from writing while their files are archived
+    let stopped = if stop {
+        containers_using(&stack, &containers, &volumes)
+    } else {
+        vec![]
+    };
+    for container in &stopped {
+        println!(
+            "{}",
+            tr_args("Stopping {container}", &[("container", container)])
+        );
+    }
+    set_running(&stopped, false)?;
+
+    let timestamp = chrono::Utc::now();
+    let mut result = Ok(());
+    for volume in &volumes {
+        let archive = archive_path(&out, volume, timestamp);
+        println!(
+            "{}",
+            tr_args(
+                "Backing up {volume} to {archive}",
+                &[("volume", volume), ("archive", &archive.display())]
+            )
+        );
+
+        result = backup_volume(volume, &archive);
+        if result.is_err() {
+            break;
+        }
+    }
+
+    // containers are started again even when a backup failed
+    for container in &stopped {
+        println!(
+            "{}",
+            tr_args("Starting {container}", &[("container", container)])
+        );
+    }
+    set_running(&stopped, true)?;
+    result?;
+
+    let message = tr_args(
+        "Backed up {count} volume(s) of {stack} to {dir}",
+        &[
+            ("count", &volumes.len()),
+            ("stack", &stack),
+            ("dir", &out.display()),
+        ],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+pub fn stack_restore(
+    stack: String,
+    from: PathBuf,
+    at: Option<String>,
+    yes: bool,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    let at = match at {
+        Some(at) => Some(parse_archive_timestamp(&at).with_context(|| {
+            tr_args(
+                "Invalid backup timestamp: {at}, expected a time such as 20250601T120000Z",
+                &[("at", &at)],
+            )
+        })?),
+        None => None,
+    };
+
+    let containers = inspect_disk_containers()?;
+    let volumes = stack_volumes(&stack, &containers);
+    if volumes.is_empty() {
+        anyhow::bail!(tr_args(
+            "No named volumes found in stack: {stack}",
+            &[("stack", &stack)]
+        ));
+    }
+
+    let archives = pick_archives(&list_archives(&from)?, &volumes, at);
+    if archives.is_empty() {
+        anyhow::bail!(tr_args(
+            "No backups of {stack} found in {dir}",
+            &[("stack", &stack), ("dir", &from.display())]
+        ));
+    }
+
+    for volume in &volumes {
+        match archives.get(volume) {
+            Some(archive) => println!(
+                "{}",
+                tr_args(
+                    "Restore {volume} from {archive}",
+                    &[("volume", volume), ("archive", archive)]
+                )
+            ),
+            None => println!(
+                "{}",
+                tr_args(
+                    "No backup of {volume}, leaving it as is",
+                    &[("volume", volume)]
+                )
+            ),
+        }
+    }
+
+    let restored = archives.keys().cloned().collect::<Vec<String>>();
+    let stopped = containers_using(&stack, &containers, &restored);
+
+    if !yes {
+        if is_ci() {
+            anyhow::bail!(tr("Refusing to restore without --yes in CI mode"));
+        }
+
+        print!(
+            "{} [y/N]: ",
+            tr_args(
+                "Replace the contents of {count} volume(s), stopping {containers} container(s) meanwhile?",
+                &[("count", &restored.len()), ("containers", &stopped.len())]
+            )
+        );
+        let _ = io::stdout().flush();
+
+        let mut input = String::new();
+        let _ = io::stdin().read_line(&mut input);
+
+        if !matches!(input.trim().to_lowercase().as_str(), "yes" | "y") {
+            println!("{}", tr("Restore aborted!"));
+            return Ok(());
+        }
+    }
+
+    // restoring under a running container would hand it half written files
+    set_running(&stopped, false)?;
+
+    let mut result = Ok(());
+    for (volume, archive) in &archives {
+        result = restore_volume(volume, &from.join(archive));
+        if result.is_err() {
+            break;
+        }
+    }
+
+    set_running(&stopped, true)?;
+    result?;
+
+    let message = tr_args(
+        "Restored {count} volume(s) of {stack}",
+        &[("count", &restored.len()), ("stack", &stack)],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+/// Summarizes licenses of packages across container images, flagging disallowed licenses
+pub fn licenses(
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    all: bool,
+    refresh: bool,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let disallowed = Config::load()?.licenses.disallowed;
+    let containers = resolve_containers(containers, stacks, all)?;
+
+    let mut images = containers
+        .iter()
+        .map(|container| get_container_image(container))
+        .collect::<Result<Vec<String>, DsdError>>()?;
+    images.sort();
+    images.dedup();
+
+    // license -> (number of packages, images shipping it)
+    let mut summary: BTreeMap<String, (usize, Vec<String>)> = BTreeMap::new();
+    let mut violations = vec![];
+
+    for image in &images {
+        let sbom = load_sbom(image, refresh)?;
+
+        for package in &sbom.packages {
+            let expression = package
+                .licenses
+                .first()
+                .map(String::as_str)
+                .unwrap_or("UNKNOWN");
+
+            for id in license_ids(expression) {
+                let (num_packages, license_images) = summary.entry(id).or_default();
+                *num_packages += 1;
+                if !license_images.contains(image) {
+                    license_images.push(image.to_string());
+                }
+            }
+
+            if is_license_disallowed(expression, &disallowed) {
+                violations.push((image.to_string(), package.clone(), expression.to_string()));
+            }
+        }
+    }
+
+    if use_color {
+        println!(
+            "{:<50} {:<10} {:<10}",
+            &color_println_fmt(Color::White, "LICENSE"),
+            "PACKAGES",
+            &color_println_fmt(Color::White, "IMAGES"),
+        );
+    } else {
+        println!("{:<50} {:<10} {:<10}", "LICENSE", "PACKAGES", "IMAGES");
+    }
+
+    println!();
+
+    for (license, (num_packages, license_images)) in &summary {
+        let is_disallowed = is_license_disallowed(license, &disallowed);
+
+        let license = if is_accessible() && is_disallowed {
+            format!("{license} {}", tr("[DISALLOWED]"))
+        } else if !use_color {
+            license.to_string()
+        } else if is_disallowed {
+            color_println_fmt(role_color(Role::Error), license)
+        } else {
+            color_println_fmt(Color::Cyan, license)
+        };
+
+        println!(
+            "{:<50} {:<10} {:<10}",
+            license,
+            num_packages,
+            license_images.len()
+        );
+    }
+
+    if violations.is_empty() {
+        return Ok(());
+    }
+
+    println!();
+
+    if use_color {
+        color_println(
+            role_color(Role::Error),
+            &tr("Packages with disallowed licenses:"),
+        );
+    } else {
+        println!("{}", tr("Packages with disallowed licenses:"));
+    }
+
+    for (image, package, expression) in &violations {
+        println!(
+            "  {:<40} {:<40} {:<20} {}",
+            image, package.name, package.version, expression
+        );
+    }
+
+    let message = tr_args(
+        "{count} package(s) use disallowed licenses",
+        &[("count", &violations.len())],
+    );
+    print_notify_error(
+        notify_events(&[Event::new(
+            EventSource::Audit,
+            Severity::Warning,
+            message.to_string(),
+        )]),
+        use_color,
+    );
+
+    anyhow::bail!(message)
+}
+
+/// Watches containers and sends notifications when they fail
+pub fn watch(
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    all: bool,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let config = Config::load_with_secrets()?;
+    let mut notifier = Notifier::from_config(&config)?;
+    let config = config.watch;
+    let containers = resolve_containers(containers, stacks, all)?;
+
+    if containers.is_empty() {
+        anyhow::bail!(tr("No containers to watch"));
+    }
+
+    if notifier.is_empty() {
+        if use_color {
+            color_println(
+                role_color(Role::Warning),
+                &tr("No webhook configured, failures will only be printed"),
+            );
+        } else {
+            println!(
+                "{}",
+                tr("No webhook configured, failures will only be printed")
+            );
+        }
+    }
+
+    if use_color {
+        color_println(
+            Color::Cyan,
+            &tr_args(
+                "Watching {count} container(s)...",
+                &[("count", &containers.len())],
+            ),
+        );
+    } else {
+        println!(
+            "{}",
+            tr_args(
+                "Watching {count} container(s)...",
+                &[("count", &containers.len())]
+            )
+        );
+    }
+
+    let mut watcher = Watcher::new(
+        config.max_restarts,
+        std::time::Duration::from_secs(config.restart_window * 60),
+    )
+    .flap_detection(
+        config.flap_threshold,
+        std::time::Duration::from_secs(config.flap_window * 60),
+    );
+    // restart policies of flapping containers, restored once they stabilize
+    let mut paused_restarts: HashMap<String, String> = HashMap::new();
+
+    handle_ctrl_c()?;
+
+    loop {
+        let inspected = match inspect_containers(&containers) {
+            Ok(inspected) => inspected,
+            Err(e) => {
+                // a docker hiccup such as a daemon restart should not end the watch
+                let message = tr_args(
+                    "Failed to inspect containers, retrying on the next check: {error}",
+                    &[("error", &e.to_string())],
+                );
+                if use_color {
+                    color_println(role_color(Role::Warning), &message);
+                } else {
+                    println!("{message}");
+                }
+
+                if !sleep_unless_shutdown(std::time::Duration::from_secs(config.interval)) {
+                    break;
+                }
+                continue;
+            }
+        };
+        // reloaded every check, so mutes added while watching apply and expired ones lift
+        let mutes = load_mutes().unwrap_or_default();
+
+        for event in watcher.check(&inspected) {
+            if is_accessible() {
+                println!(
+                    "{}",
+                    tr_args(
+                        "At {time}, {event}.",
+                        &[("time", &get_timestamp()), ("event", &event)]
+                    )
+                );
+            } else if use_color {
+                println!(
+                    "[{} | {}] {}",
+                    color_println_fmt(role_color(Role::Timestamp), &get_timestamp()),
+                    color_println_fmt(role_color(Role::Error), event.container()),
+                    event
+                );
+            } else {
+                println!("[{} | {}] {}", &get_timestamp(), event.container(), event);
+            }
+
+            let mute = find_mute(&mutes, event.container(), chrono::Utc::now());
+
+            if config.flap_pause_restarts {
+                pause_flapping_restarts(
+                    &event,
+                    &inspected,
+                    mute.is_none(),
+                    &mut paused_restarts,
+                    use_color,
+                );
+            }
+
+            if let Some(mute) = mute {
+                println!("{}", describe_mute(mute));
+                continue;
+            }
+
+            // keep watching when a webhook is unreachable
+            let event = Event::new(EventSource::Watch, event.severity(), event.to_string());
+            print_notify_error(notifier.notify(&event), use_color);
+        }
+
+        print_notify_error(notifier.flush_due(), use_color);
+
+        if !sleep_unless_shutdown(std::time::Duration::from_secs(config.interval)) {
+            break;
+        }
+    }
+
+    // pending digests would otherwise be lost
+    print_notify_error(notifier.flush_all(), use_color);
+
+    // containers must not be left without their restart policy
+    for (container, policy) in paused_restarts {
+        print_watch_action(
+            set_restart_policy(&container, &policy).map(|_| {
+                tr_args(
+                    "Restored restart policy {policy} of {container}",
+                    &[("policy", &policy), ("container", &container)],
+                )
+            }),
+            use_color,
+        );
+    }
+
+    io::stdout().flush().context("Failed to flush output")?;
+
+    Ok(())
+}
+
+/// Disables the restart policy of a container that starts flapping and restores it once the
+/// container stabilizes. Muted containers are left alone, but still get their policy back
+fn pause_flapping_restarts(
+    event: &WatchEvent,
+    inspected: &[InspectData],
+    may_pause: bool,
+    paused_restarts: &mut HashMap<String, String>,
+    use_color: bool,
+) {
+    match event {
+        WatchEvent::Flapping { container, .. } if may_pause => {
+            let Some(policy) = inspected
+                .iter()
+                .find(|inspect| inspect.container_name == *container)
+                .map(|inspect| inspect.restart_policy.to_string())
+                .filter(|policy| policy != "no")
+            else {
+                return;
+            };
+
+            let result = set_restart_policy(container, "no").map(|_| {
+                tr_args(
+                    "Paused restarts of {container} until it stabilizes",
+                    &[("container", container)],
+                )
+            });
+            if result.is_ok() {
+                paused_restarts.insert(container.to_string(), policy);
+            }
+            print_watch_action(result, use_color);
+        }
+        WatchEvent::Stabilized { container } => {
+            let Some(policy) = paused_restarts.remove(container) else {
+                return;
+            };
+
+            print_watch_action(
+                set_restart_policy(container, &policy).map(|_| {
+                    tr_args(
+                        "Restored restart policy {policy} of {container}",
+                        &[("policy", &policy), ("container", container)],
+                    )
+                }),
+                use_color,
+            );
+        }
+        _ => {}
+    }
+}
+
+/// Prints the outcome of an action taken while watching
+fn print_watch_action(result: Result<String, DsdError>, use_color: bool) {
+    match result {
+        Ok(message) if use_color => color_println(role_color(Role::Warning), &message),
+        Ok(message) => println!("{message}"),
+        Err(e) if use_color => color_println(role_color(Role::Error), &format!("{e:#}")),
+        Err(e) => println!("{e:#}"),
+    }
+}
+
+/// Mutes watch alerts of a container, or lists active mutes when no container is given
+pub fn mute_container(
+    container: Option<String>,
+    duration: std::time::Duration,
+    reason: Option<String>,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    let Some(container) = container else {
+        let mutes = load_mutes()?;
+
+        if mutes.is_empty() {
+            println!("{}", tr("No containers are muted"));
+        }
+        for mute in &mutes {
+            println!("{}", describe_mute(mute));
+        }
+
+        return Ok(());
+    };
+
+    let mute = mute(&container, duration, reason)?;
+    let message = describe_mute(&mute);
+
+    if use_color {
+        color_println(role_color(Role::Warning), &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+/// Removes the mute of a container
+pub fn unmute_container(container: String) -> anyhow::Result<()> {
+    if !unmute(&container)? {
+        anyhow::bail!(tr_args(
+            "{container} is not muted",
+            &[("container", &container)]
+        ));
+    }
+
+    println!(
+        "{}",
+        tr_args(
+            "Alerts of {container} are no longer muted",
+            &[("container", &container)]
+        )
+    );
+
+    Ok(())
+}
+
+/// Describes a mute with its expiry and reason
+fn describe_mute(mute: &Mute) -> String {
+    let until = format_time(mute.until, "%Y-%m-%d %H:%M");
+
+    match &mute.reason {
+        Some(reason) => tr_args(
+            "{container} is muted until {until}: {reason}",
+            &[
+                ("container", &mute.container),
+                ("until", &until),
+                ("reason", reason),
+            ],
+        ),
+        None => tr_args(
+            "{container} is muted until {until}",
+            &[("container", &mute.container), ("until", &until)],
+        ),
+    }
+}
+
+/// Prints a failure to send notifications without failing the command
+fn print_notify_error(result: anyhow::Result<()>, use_color: bool) {
+    let Err(e) = result else {
+        return;
+    };
+
+    if use_color {
+        color_println(role_color(Role::Error), &format!("{e:#}"));
+    } else {
+        println!("{e:#}");
+    }
+}
+
+/// Container stats to be gathered
+#[derive(Debug, Clone)]
+struct ContainerStats {
+    name: String,
+    status: String,
+    health: String,
+    restart_policy: String,
+    uptime: String,
+    cpu_usage: String,
+    memory_usage: String,
+    memory_used: String,
+    ports: String,
+    /// Whether CPU usage is above the configured threshold
+    cpu_over_threshold: bool,
+    /// Whether memory usage is above the configured threshold
+    memory_over_threshold: bool,
+}
+
+/// View stats for docker containers, redrawing them every interval when watching
+pub fn stats(
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    all: bool,
+    sort: StatsSort,
+    jobs: usize,
+    watch: Option<std::time::Duration>,
+    output: Option<DocumentFormat>,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let config = Config::load_with_secrets()?;
+
+    let Some(interval) = watch else {
+        let containers = resolve_containers(containers, stacks, all)?;
+        return print_stats(&containers, &config, sort, jobs, output);
+    };
+
+    handle_ctrl_c()?;
+
+    loop {
+        // resolve again on every refresh so recreated containers of a stack show up
+        let resolved = resolve_containers(containers.clone(), stacks.clone(), all)?;
+
+        if use_color {
+            // clear the screen and move the cursor home before redrawing
+            print!("\x1b[2J\x1b[H");
+            color_println(
+                Color::White,
+                &tr_args(
+                    "Every {interval}, last refreshed at {time}",
+                    &[
+                        ("interval", &format_duration(interval)),
+                        ("time", &get_timestamp()),
+                    ],
+                ),
+            );
+        } else {
+            println!(
+                "{}",
+                tr_args(
+                    "Every {interval}, last refreshed at {time}",
+                    &[
+                        ("interval", &format_duration(interval)),
+                        ("time", &get_timestamp()),
+                    ]
+                )
+            );
+        }
+        println!();
+
+        print_stats(&resolved, &config, sort, jobs, None)?;
+        io::stdout().flush().context("Failed to flush output")?;
+
+        if !sleep_unless_shutdown(interval) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints a single stats table of the given containers, followed by a summary per stack, as
+/// CSV, Markdown or HTML when another output is given
+fn print_stats(
+    containers: &[String],
+    config: &Config,
+    sort: StatsSort,
+    jobs: usize,
+    output: Option<DocumentFormat>,
+) -> anyhow::Result<()> {
+    // exports are pasted elsewhere, where escape codes would show up as garbage
+    let use_color = is_terminal() && output.is_none();
+
+    if containers.is_empty() {
+        if use_color {
+            color_println(role_color(Role::Error), &tr("No containers running"));
+        } else {
+            println!("{}", tr("No containers running"));
+        }
+        return Ok(());
+    }
+
+    let mut temp_stats_map: HashMap<String, StatsData> = HashMap::new();
+    let mut temp_inspect_map: HashMap<String, InspectData> = HashMap::new();
+
+    for parsed in get_stats(containers)? {
+        temp_stats_map.insert(parsed.container_name.clone(), parsed);
+    }
+
+    for parsed in inspect_containers(containers)? {
+        temp_inspect_map.insert(parsed.container_name.clone(), parsed);
+    }
+
+    assert_eq!(&temp_stats_map.len(), &temp_inspect_map.len());
+
+    // usage is left out of deterministic output, so it cannot order it either
+    let sort = if is_deterministic() {
+        StatsSort::Name
+    } else {
+        sort
+    };
+    let mut ordered = temp_stats_map.values().cloned().collect::<Vec<StatsData>>();
+    sort_stats(&mut ordered, sort);
+
+    // run configured probes so failing apps surface even when docker reports healthy
+    let mut failed_probes: HashMap<String, ProbeResult> = HashMap::new();
+
+    let probes = config
+        .probes
+        .iter()
+        .filter(|probe| temp_stats_map.contains_key(&probe.container))
+        .collect::<Vec<&ProbeConfig>>();
+
+    for result in run_parallel(&probes, jobs, |probe| run_probe(probe)) {
+        let result = result?;
+        if !result.ok {
+            failed_probes.insert(result.container.to_string(), result);
+        }
+    }
+
+    let number_format = config.units.number_format();
+    let mut total_stats_map: HashMap<String, ContainerStats> = HashMap::new();
+
+    for key in temp_stats_map.keys() {
         let stats = temp_stats_map
             .get(key)
             .with_context(|| format!("Failed to get stats for {key}"))?;
@@ -421,148 +4277,2121 @@ pub fn stats(
             .get(key)
             .with_context(|| format!("Failed to get stats for {key}"))?;
 
-        let container_stats = if use_color {
-            ContainerStats {
-                name: color_println_fmt(Color::Cyan, &stats.container_name),
-                status: {
-                    if &inspect.status.to_lowercase() == "running" {
-                        color_println_fmt(Color::Green, &inspect.status)
-                    } else if &inspect.status.to_lowercase() == "created" {
-                        color_println_fmt(Color::Cyan, &inspect.status)
-                    } else if &inspect.status.to_lowercase() == "paused"
-                        || &inspect.status.to_lowercase() == "restarting"
-                    {
-                        color_println_fmt(Color::Yellow, &inspect.status)
-                    } else {
-                        color_println_fmt(Color::Red, &inspect.status)
-                    }
-                },
-                restart_policy: inspect.restart_policy.to_string(),
-                health: {
-                    if &inspect.health.to_lowercase() == "healthy" {
-                        color_println_fmt(Color::Green, &inspect.health)
-                    } else if &inspect.health.to_lowercase() == "unhealthy" {
-                        color_println_fmt(Color::Red, &inspect.health)
-                    } else if &inspect.health.to_lowercase() == "starting" {
-                        color_println_fmt(Color::Cyan, &inspect.health)
-                    } else {
-                        color_println_fmt(Color::White, &inspect.health)
-                    }
-                },
-                uptime: inspect.uptime.to_string(),
-                cpu_usage: stats.cpu.to_string(),
-                memory_usage: stats.memory.to_string(),
-                ports: inspect.ports.to_string(),
-            }
+        let health = match failed_probes.get(key) {
+            Some(probe) => format!("probe failed ({})", probe.detail),
+            None => inspect.health.to_string(),
+        };
+
+        let cpu_over_threshold =
+            !is_deterministic() && stats.cpu_percent > config.stats.cpu_threshold;
+        let memory_over_threshold =
+            !is_deterministic() && stats.memory_percent > config.stats.memory_threshold;
+
+        let container_stats = if use_color {
+            ContainerStats {
+                name: if cpu_over_threshold || memory_over_threshold {
+                    color_println_fmt(role_color(Role::Error), &stats.container_name)
+                } else {
+                    color_println_fmt(Color::Cyan, &stats.container_name)
+                },
+                status: {
+                    if &inspect.status.to_lowercase() == "running" {
+                        color_println_fmt(Color::Green, &inspect.status)
+                    } else if &inspect.status.to_lowercase() == "created" {
+                        color_println_fmt(Color::Cyan, &inspect.status)
+                    } else if &inspect.status.to_lowercase() == "paused"
+                        || &inspect.status.to_lowercase() == "restarting"
+                    {
+                        color_println_fmt(role_color(Role::Warning), &inspect.status)
+                    } else {
+                        color_println_fmt(role_color(Role::Error), &inspect.status)
+                    }
+                },
+                restart_policy: inspect.restart_policy.to_string(),
+                health: {
+                    if failed_probes.contains_key(key) {
+                        color_println_fmt(role_color(Role::Error), &health)
+                    } else if &health.to_lowercase() == "healthy" {
+                        color_println_fmt(Color::Green, &health)
+                    } else if &health.to_lowercase() == "unhealthy" {
+                        color_println_fmt(role_color(Role::Error), &health)
+                    } else if &health.to_lowercase() == "starting" {
+                        color_println_fmt(Color::Cyan, &health)
+                    } else {
+                        color_println_fmt(Color::White, &health)
+                    }
+                },
+                uptime: volatile(inspect.uptime.to_string()),
+                cpu_usage: volatile(number_format.percent(stats.cpu_percent)),
+                memory_usage: volatile(number_format.percent(stats.memory_percent)),
+                memory_used: volatile(number_format.size(stats.memory_used)),
+                ports: inspect.ports.to_string(),
+                cpu_over_threshold,
+                memory_over_threshold,
+            }
+        } else {
+            ContainerStats {
+                name: stats.container_name.to_string(),
+                status: inspect.status.to_string(),
+                restart_policy: inspect.restart_policy.to_string(),
+                health,
+                uptime: volatile(inspect.uptime.to_string()),
+                cpu_usage: volatile(number_format.percent(stats.cpu_percent)),
+                memory_usage: volatile(number_format.percent(stats.memory_percent)),
+                memory_used: volatile(number_format.size(stats.memory_used)),
+                ports: inspect.ports.to_string(),
+                cpu_over_threshold,
+                memory_over_threshold,
+            }
+        };
+
+        total_stats_map.insert(key.to_string(), container_stats);
+    }
+    // muted containers are called out, so silenced alerts do not go unnoticed
+    let now = chrono::Utc::now();
+    let mutes = load_mutes().unwrap_or_default();
+    let mut muted = ordered
+        .iter()
+        .filter_map(|stats| find_mute(&mutes, &stats.container_name, now))
+        .map(describe_mute)
+        .collect::<Vec<String>>();
+    // a pattern can mute several containers
+    muted.dedup();
+
+    let known = list_all_containers()?;
+    let summary = group_by_stack(containers, &known)
+        .into_iter()
+        .map(|(stack, containers)| {
+            let stats = containers
+                .iter()
+                .filter_map(|container| temp_stats_map.get(container))
+                .collect::<Vec<&StatsData>>();
+            let running = containers
+                .iter()
+                .filter_map(|container| temp_inspect_map.get(container))
+                .filter(|inspect| inspect.status.eq_ignore_ascii_case("running"))
+                .count();
+            let unhealthy = containers
+                .iter()
+                .filter(|container| {
+                    failed_probes.contains_key(*container)
+                        || temp_inspect_map
+                            .get(*container)
+                            .is_some_and(|inspect| inspect.health.eq_ignore_ascii_case("unhealthy"))
+                })
+                .count();
+
+            (
+                stack,
+                tr_args(
+                    "{running} of {total} running, {unhealthy} unhealthy, {cpu} CPU, {memory} memory",
+                    &[
+                        ("running", &running),
+                        ("total", &containers.len()),
+                        ("unhealthy", &unhealthy),
+                        (
+                            "cpu",
+                            &volatile(
+                                number_format
+                                    .percent(stats.iter().map(|stats| stats.cpu_percent).sum()),
+                            ),
+                        ),
+                        (
+                            "memory",
+                            &volatile(
+                                number_format
+                                    .size(stats.iter().map(|stats| stats.memory_used).sum()),
+                            ),
+                        ),
+                    ],
+                ),
+            )
+        })
+        .collect::<Vec<(String, String)>>();
+
+    if let Some(format) = output {
+        let mut document = Document::new(tr("Container stats"));
+        document.table(
+            &[
+                "NAME",
+                "STATUS",
+                "RESTART",
+                "HEALTH",
+                "UPTIME",
+                "CPU %",
+                "MEM %",
+                "MEM USAGE",
+                "PORTS",
+            ],
+            ordered
+                .iter()
+                .filter_map(|stats| total_stats_map.get(&stats.container_name))
+                .map(|container| {
+                    vec![
+                        container.name.to_string(),
+                        container.status.to_string(),
+                        container.restart_policy.to_string(),
+                        container.health.to_string(),
+                        container.uptime.to_string(),
+                        container.cpu_usage.to_string(),
+                        container.memory_usage.to_string(),
+                        container.memory_used.to_string(),
+                        container.ports.to_string(),
+                    ]
+                })
+                .collect(),
+        );
+        if !muted.is_empty() {
+            document.list(muted);
+        }
+        if summary.len() > 1 {
+            document.heading(tr("Stacks"));
+            document.list(
+                summary
+                    .iter()
+                    .map(|(stack, outcome)| format!("{stack}: {outcome}"))
+                    .collect(),
+            );
+        }
+
+        print!("{}", document.render(format));
+        return Ok(());
+    }
+
+    // tables are hard to follow with a screen reader, so describe each container instead
+    if is_accessible() {
+        for stats in &ordered {
+            let container = &total_stats_map[&stats.container_name];
+            let ports = if container.ports.is_empty() || container.ports == "N/A" {
+                tr("none")
+            } else {
+                container.ports.to_string()
+            };
+
+            println!(
+                "{}",
+                tr_args(
+                    "{name} is {status} and {health}, up {uptime}, using {cpu} CPU and {memory} memory ({used}), ports {ports}.",
+                    &[
+                        ("name", &container.name),
+                        ("status", &container.status),
+                        ("health", &container.health),
+                        ("uptime", &container.uptime),
+                        ("cpu", &container.cpu_usage),
+                        ("memory", &container.memory_usage),
+                        ("used", &container.memory_used),
+                        ("ports", &ports),
+                    ]
+                )
+            );
+
+            if container.cpu_over_threshold || container.memory_over_threshold {
+                println!(
+                    "{}",
+                    tr_args(
+                        "{name} is above its usage threshold.",
+                        &[("name", &container.name)]
+                    )
+                );
+            }
+        }
+
+        for mute in &muted {
+            println!("{mute}.");
+        }
+
+        print_stack_summary(&summary, use_color);
+        return Ok(());
+    }
+
+    if use_color {
+        println!(
+            "{:<35} {:<20} {:<16} {:<20} {:<18} {:<8} {:<8} {:<12} {:<20}",
+            &color_println_fmt(Color::White, "NAME"),
+            &color_println_fmt(Color::White, "STATUS"),
+            "RESTART",
+            &color_println_fmt(Color::White, "HEALTH"),
+            "UPTIME",
+            "CPU %",
+            "MEM %",
+            &color_println_fmt(Color::White, "MEM USAGE"),
+            "PORTS"
+        );
+    } else {
+        println!(
+            "{:<35} {:<20} {:<16} {:<20} {:<18} {:<8} {:<8} {:<12} {:<20}",
+            "NAME", "STATUS", "RESTART", "HEALTH", "UPTIME", "CPU %", "MEM %", "MEM USAGE", "PORTS"
+        );
+    }
+
+    println!();
+
+    for stats in &ordered {
+        let container = total_stats_map
+            .get(&stats.container_name)
+            .context("Failed to get item")?;
+
+        // pad before coloring, as escape codes do not take up space
+        let highlight = |value: &str, over_threshold: bool| {
+            let padded = format!("{value:<8}");
+            if use_color && over_threshold {
+                color_println_fmt(role_color(Role::Error), &padded)
+            } else {
+                padded
+            }
+        };
+
+        println!(
+            "{:<35} {:<20} {:<16} {:<20} {:<18} {} {} {:<12} {:<20}",
+            container.name,
+            container.status,
+            container.restart_policy,
+            container.health,
+            container.uptime,
+            highlight(&container.cpu_usage, container.cpu_over_threshold),
+            highlight(&container.memory_usage, container.memory_over_threshold),
+            container.memory_used,
+            container.ports
+        );
+    }
+
+    if !muted.is_empty() {
+        println!();
+    }
+    for mute in &muted {
+        if use_color {
+            color_println(role_color(Role::Warning), mute);
+        } else {
+            println!("{mute}");
+        }
+    }
+
+    print_stack_summary(&summary, use_color);
+
+    Ok(())
+}
+
+/// Lists the processes of all containers of a stack in one table, optionally redrawn until
+/// interrupted
+pub fn top(stack: String, sort: TopSort, watch: Option<std::time::Duration>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let cpu_threshold = Config::load()?.stats.cpu_threshold;
+
+    let Some(interval) = watch else {
+        return print_processes(&stack, sort, cpu_threshold);
+    };
+
+    handle_ctrl_c()?;
+
+    loop {
+        let message = tr_args(
+            "Every {interval}, last refreshed at {time}",
+            &[
+                ("interval", &format_duration(interval)),
+                ("time", &get_timestamp()),
+            ],
+        );
+        if use_color {
+            // clear the screen and move the cursor home before redrawing
+            print!("\x1b[2J\x1b[H");
+            color_println(Color::White, &message);
+        } else {
+            println!("{message}");
+        }
+        println!();
+
+        print_processes(&stack, sort, cpu_threshold)?;
+        io::stdout().flush().context("Failed to flush output")?;
+
+        if !sleep_unless_shutdown(interval) {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+/// Prints the processes of the containers of a stack, looked up again on every call as
+/// containers may be recreated while watching
+fn print_processes(stack: &str, sort: TopSort, cpu_threshold: f64) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let containers = resolve_containers(None, Some(vec![stack.to_string()]), false)?;
+
+    if containers.is_empty() {
+        anyhow::bail!(tr_args(
+            "No containers found in stack: {stack}",
+            &[("stack", &stack)]
+        ));
+    }
+
+    let mut processes = vec![];
+    for container in &containers {
+        // a container stopping in the meantime should not hide the processes of the others
+        match container_processes(container) {
+            Ok(found) => processes.extend(found),
+            Err(error) => eprintln!(
+                "{}",
+                tr_args(
+                    "Failed to list processes of {container}: {error}",
+                    &[("container", container), ("error", &format!("{error:#}"))]
+                )
+            ),
+        }
+    }
+    sort_processes(&mut processes, sort);
+
+    record("processes", processes.len());
+
+    if is_accessible() {
+        for process in &processes {
+            println!(
+                "{}",
+                tr_args(
+                    "{container} runs process {pid} at {cpu} CPU: {command}",
+                    &[
+                        ("container", &process.container),
+                        ("pid", &process.pid),
+                        ("cpu", &format!("{:.1}%", process.cpu_percent)),
+                        ("command", &process.command),
+                    ]
+                )
+            );
+        }
+        return Ok(());
+    }
+
+    println!("{:<30} {:>8} {:>7}  COMMAND", "CONTAINER", "PID", "CPU %");
+    for process in &processes {
+        let cpu = format!("{:>7.1}", process.cpu_percent);
+        if use_color {
+            let cpu = if process.cpu_percent > cpu_threshold {
+                color_println_fmt(role_color(Role::Error), &cpu)
+            } else {
+                cpu
+            };
+            println!(
+                "{} {:>8} {cpu}  {}",
+                color_println_fmt(
+                    container_color(&process.container),
+                    &format!("{:<30}", process.container)
+                ),
+                process.pid,
+                process.command
+            );
+        } else {
+            println!(
+                "{:<30} {:>8} {cpu}  {}",
+                process.container, process.pid, process.command
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Keeps the containers of the given stacks or groups, all of them when none are given
+fn retain_stacks<T>(
+    containers: &mut Vec<T>,
+    stacks: &[String],
+    stack_of: impl Fn(&T) -> Option<&str>,
+) -> anyhow::Result<()> {
+    if stacks.is_empty() {
+        return Ok(());
+    }
+
+    let stacks = expand_groups(stacks)?;
+    containers.retain(|container| {
+        stack_of(container).is_some_and(|stack| stacks.iter().any(|name| name == stack))
+    });
+
+    if containers.is_empty() {
+        anyhow::bail!(tr_args(
+            "No containers found in stack: {stack}",
+            &[("stack", &stacks.join(", "))]
+        ));
+    }
+
+    Ok(())
+}
+
+/// Reports which containers run as root and which mount the docker socket, suggesting how to run
+/// images known to work as non-root
+pub fn security_users(stacks: Vec<String>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    let mut users = inspect_container_users()?;
+    retain_stacks(&mut users, &stacks, |user| user.stack.as_deref())?;
+    users.sort_by(|a, b| a.container.cmp(&b.container));
+
+    let suggestion_of = |user: &ContainerUser| {
+        user.is_root()
+            .then(|| non_root_suggestion(&user.image))
+            .flatten()
+    };
+
+    if !is_accessible() {
+        println!(
+            "{:<30} {:<16} {:<14} SUGGESTION",
+            "CONTAINER", "USER", "DOCKER SOCKET"
+        );
+    }
+
+    for user in &users {
+        let name = if user.user.is_empty() {
+            "root".to_string()
+        } else {
+            user.user.clone()
+        };
+
+        if is_accessible() {
+            let mut message = if user.is_root() {
+                tr_args(
+                    "{container} runs as root ({user}).",
+                    &[("container", &user.container), ("user", &name)],
+                )
+            } else {
+                tr_args(
+                    "{container} runs as {user}.",
+                    &[("container", &user.container), ("user", &name)],
+                )
+            };
+            if user.docker_socket {
+                message.push(' ');
+                message.push_str(&tr("It mounts the docker socket."));
+            }
+            if let Some(suggestion) = suggestion_of(user) {
+                message.push(' ');
+                message.push_str(&tr_args(
+                    "To run it as non-root: {suggestion}.",
+                    &[("suggestion", &suggestion)],
+                ));
+            }
+            println!("{message}");
+            continue;
+        }
+
+        let line = format!(
+            "{:<30} {:<16} {:<14} {}",
+            user.container,
+            name,
+            if user.docker_socket { "yes" } else { "-" },
+            suggestion_of(user).unwrap_or("-")
+        );
+        if use_color && user.is_root() {
+            let role = if user.docker_socket {
+                Role::Error
+            } else {
+                Role::Warning
+            };
+            color_println(role_color(role), &line);
+        } else {
+            println!("{line}");
+        }
+    }
+
+    let root = users.iter().filter(|user| user.is_root()).count();
+    let socket = users.iter().filter(|user| user.docker_socket).count();
+
+    record("root_containers", root);
+    record("docker_socket_containers", socket);
+
+    println!();
+
+    let message = tr_args(
+        "{root} of {total} container(s) run as root, {socket} mount the docker socket",
+        &[
+            ("root", &root),
+            ("total", &users.len()),
+            ("socket", &socket),
+        ],
+    );
+    if !use_color {
+        println!("{message}");
+    } else if root == 0 && socket == 0 {
+        color_println(Color::Green, &message);
+    } else {
+        color_println(role_color(Role::Warning), &message);
+    }
+
+    Ok(())
+}
+
+/// Reports the AppArmor, seccomp and SELinux profiles containers run under, failing when any runs
+/// with confinement turned off, such as after a compose change added privileged: true
+pub fn security_profiles(stacks: Vec<String>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+
+    let mut profiles = inspect_security_profiles()?;
+    retain_stacks(&mut profiles, &stacks, |profile| profile.stack.as_deref())?;
+    profiles.sort_by(|a, b| a.container.cmp(&b.container));
+
+    if !is_accessible() {
+        println!(
+            "{:<30} {:<20} {:<12} {:<24} PRIVILEGED",
+            "CONTAINER", "APPARMOR", "SECCOMP", "SELINUX"
+        );
+    }
+
+    for profile in &profiles {
+        let unconfined = profile.unconfined();
+
+        if is_accessible() {
+            let mut message = tr_args(
+                "{container} runs with AppArmor {apparmor}, seccomp {seccomp} and SELinux {selinux}.",
+                &[
+                    ("container", &profile.container),
+                    ("apparmor", &profile.apparmor),
+                    ("seccomp", &profile.seccomp),
+                    ("selinux", &profile.selinux),
+                ],
+            );
+            if !unconfined.is_empty() {
+                message.push(' ');
+                message.push_str(&tr_args(
+                    "Unconfined: {unconfined}.",
+                    &[("unconfined", &unconfined.join(", "))],
+                ));
+            }
+            println!("{message}");
+            continue;
+        }
+
+        let line = format!(
+            "{:<30} {:<20} {:<12} {:<24} {}",
+            profile.container,
+            profile.apparmor,
+            profile.seccomp,
+            profile.selinux,
+            if profile.privileged { "yes" } else { "-" }
+        );
+        if use_color && !unconfined.is_empty() {
+            color_println(role_color(Role::Error), &line);
+        } else {
+            println!("{line}");
+        }
+    }
+
+    let unconfined = profiles
+        .iter()
+        .filter(|profile| !profile.unconfined().is_empty())
+        .count();
+
+    record("unconfined_containers", unconfined);
+
+    println!();
+
+    if unconfined == 0 {
+        let message = tr("All containers run confined");
+        if use_color {
+            color_println(Color::Green, &message);
+        } else {
+            println!("{message}");
+        }
+        return Ok(());
+    }
+
+    anyhow::bail!(tr_args(
+        "{count} of {total} container(s) run with confinement turned off",
+        &[("count", &unconfined), ("total", &profiles.len())]
+    ));
+}
+
+/// Runs the HTTP/TCP probes defined in the config file
+pub fn probes_check(containers: Option<Vec<String>>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let config = Config::load_with_secrets()?;
+
+    let probes = config
+        .probes
+        .iter()
+        .filter(|probe| match &containers {
+            Some(containers) => containers.contains(&probe.container),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+
+    if probes.is_empty() {
+        if use_color {
+            color_println(role_color(Role::Warning), &tr("No probes configured"));
+        } else {
+            println!("{}", tr("No probes configured"));
+        }
+        return Ok(());
+    }
+
+    if use_color {
+        println!(
+            "{:<35} {:<35} {:<20} {:<30} {:<10}",
+            &color_println_fmt(Color::White, "NAME"),
+            "CONTAINER",
+            &color_println_fmt(Color::White, "RESULT"),
+            "TARGET",
+            "LATENCY"
+        );
+    } else {
+        println!(
+            "{:<35} {:<35} {:<20} {:<30} {:<10}",
+            "NAME", "CONTAINER", "RESULT", "TARGET", "LATENCY"
+        );
+    }
+
+    println!();
+
+    let mut num_failed = 0;
+
+    for probe in probes {
+        let result = run_probe(probe)?;
+
+        if !result.ok {
+            num_failed += 1;
+        }
+
+        let detail = if use_color && result.ok {
+            color_println_fmt(Color::Green, &result.detail)
+        } else if use_color {
+            color_println_fmt(role_color(Role::Error), &result.detail)
+        } else if is_accessible() {
+            let marker = if result.ok {
+                tr("[OK]")
+            } else {
+                tr("[FAILED]")
+            };
+            format!("{marker} {}", result.detail)
+        } else {
+            result.detail.to_string()
+        };
+
+        let name = if use_color {
+            color_println_fmt(Color::Cyan, &result.name)
+        } else {
+            result.name.to_string()
+        };
+
+        println!(
+            "{:<35} {:<35} {:<20} {:<30} {:<10}",
+            name,
+            result.container,
+            detail,
+            result.target,
+            format!("{}ms", result.latency.as_millis())
+        );
+    }
+
+    if num_failed > 0 {
+        anyhow::bail!(tr_args(
+            "{count} probe(s) failed",
+            &[("count", &num_failed)]
+        ));
+    }
+
+    Ok(())
+}
+
+/// Runs the configured probes on their interval and records the results
+pub fn probes_schedule(containers: Option<Vec<String>>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let config = Config::load_with_secrets()?;
+
+    let probes = config
+        .probes
+        .iter()
+        .filter(|probe| match &containers {
+            Some(containers) => containers.contains(&probe.container),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+
+    if probes.is_empty() {
+        anyhow::bail!(tr("No probes configured"));
+    }
+
+    if use_color {
+        color_println(
+            Color::Cyan,
+            &tr_args(
+                "Scheduling {count} probe(s)...",
+                &[("count", &probes.len())],
+            ),
+        );
+    } else {
+        println!(
+            "{}",
+            tr_args(
+                "Scheduling {count} probe(s)...",
+                &[("count", &probes.len())]
+            )
+        );
+    }
+
+    let mut last_run: HashMap<String, std::time::Instant> = HashMap::new();
+
+    loop {
+        for probe in &probes {
+            let is_due = match last_run.get(probe.name()) {
+                Some(instant) => instant.elapsed().as_secs() >= probe.interval,
+                None => true,
+            };
+
+            if !is_due {
+                continue;
+            }
+
+            last_run.insert(probe.name().to_string(), std::time::Instant::now());
+
+            // one broken probe must not stop the others, it is recorded as failed instead
+            let started = std::time::Instant::now();
+            let result = run_probe(probe)
+                .unwrap_or_else(|e| ProbeResult::failed(probe, &e, started.elapsed()));
+            if let Err(e) = record_result(&result) {
+                let message = tr_args(
+                    "Failed to record result of {name}: {error}",
+                    &[("name", &result.name), ("error", &format!("{e:#}"))],
+                );
+                if use_color {
+                    color_println(role_color(Role::Warning), &message);
+                } else {
+                    println!("{message}");
+                }
+            }
+
+            let status = if result.ok { "ok" } else { "failed" };
+
+            if use_color {
+                println!(
+                    "[{} | {}] {} ({}, {}ms)",
+                    color_println_fmt(role_color(Role::Timestamp), &get_timestamp()),
+                    color_println_fmt(Color::Green, &result.name),
+                    if result.ok {
+                        color_println_fmt(Color::Green, status)
+                    } else {
+                        color_println_fmt(role_color(Role::Error), status)
+                    },
+                    result.detail,
+                    result.latency.as_millis()
+                );
+            } else {
+                println!(
+                    "[{} | {}] {} ({}, {}ms)",
+                    &get_timestamp(),
+                    result.name,
+                    status,
+                    result.detail,
+                    result.latency.as_millis()
+                );
+            }
+        }
+
+        std::thread::sleep(std::time::Duration::from_secs(1));
+    }
+}
+
+/// Shows availability and latency of recorded probe results
+pub fn probes_history(
+    names: Option<Vec<String>>,
+    since: std::time::Duration,
+) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let since = chrono::Utc::now()
+        - chrono::Duration::from_std(since).context("Failed to calculate history window")?;
+
+    let entries = load_history(since)?
+        .into_iter()
+        .filter(|entry| match &names {
+            Some(names) => names.contains(&entry.name) || names.contains(&entry.container),
+            None => true,
+        })
+        .collect::<Vec<_>>();
+
+    if entries.is_empty() {
+        if use_color {
+            color_println(role_color(Role::Warning), &tr("No probe history recorded"));
+        } else {
+            println!("{}", tr("No probe history recorded"));
+        }
+        return Ok(());
+    }
+
+    let mut history_map: BTreeMap<String, Vec<&ProbeHistoryEntry>> = BTreeMap::new();
+
+    for entry in &entries {
+        history_map
+            .entry(entry.name.to_string())
+            .or_default()
+            .push(entry);
+    }
+
+    if use_color {
+        println!(
+            "{:<35} {:<8} {:<24} {:<10} {:<10}",
+            &color_println_fmt(Color::White, "NAME"),
+            "CHECKS",
+            &color_println_fmt(Color::White, "AVAILABILITY"),
+            "P50",
+            "P95"
+        );
+    } else {
+        println!(
+            "{:<35} {:<8} {:<24} {:<10} {:<10}",
+            "NAME", "CHECKS", "AVAILABILITY", "P50", "P95"
+        );
+    }
+
+    println!();
+
+    for (name, history) in &history_map {
+        let num_ok = history.iter().filter(|entry| entry.ok).count();
+        let availability = num_ok as f64 / history.len() as f64 * 100.0;
+        let latencies = history
+            .iter()
+            .map(|entry| entry.latency_ms)
+            .collect::<Vec<u64>>();
+
+        let availability_fmt = format!("{availability:.2}%");
+        let availability_fmt = if !use_color {
+            availability_fmt
+        } else if num_ok == history.len() {
+            color_println_fmt(Color::Green, &availability_fmt)
+        } else if availability >= 99.0 {
+            color_println_fmt(role_color(Role::Warning), &availability_fmt)
+        } else {
+            color_println_fmt(role_color(Role::Error), &availability_fmt)
+        };
+
+        let name = if use_color {
+            color_println_fmt(Color::Cyan, name)
+        } else {
+            name.to_string()
+        };
+
+        println!(
+            "{:<35} {:<8} {:<24} {:<10} {:<10}",
+            name,
+            history.len(),
+            availability_fmt,
+            format!("{}ms", percentile(&latencies, 50.0)),
+            format!("{}ms", percentile(&latencies, 95.0))
+        );
+    }
+
+    Ok(())
+}
+
+/// Records an action in the history file, warning instead of failing the action when it cannot
+fn record_history(entry: HistoryEntry) {
+    if let Err(e) = record_action(&entry) {
+        eprintln!(
+            "{}",
+            tr_args(
+                "Failed to record history: {error}",
+                &[("error", &format!("{e:#}"))]
+            )
+        );
+    }
+}
+
+/// Shows what happened to a container, merging recorded actions, docker events, updates and
+/// probe results into one timeline
+pub fn timeline(container: String, since: std::time::Duration) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let container = complete_containers(vec![container])?.remove(0);
+    let since = chrono::Utc::now()
+        - chrono::Duration::from_std(since).context("Failed to calculate timeline window")?;
+
+    let events = build_timeline(&container, since)?;
+    record("events", events.len());
+
+    if events.is_empty() {
+        let message = tr_args(
+            "No events for {container} since {time}",
+            &[
+                ("container", &container),
+                ("time", &format_time(since, "%Y-%m-%d %H:%M")),
+            ],
+        );
+        if use_color {
+            color_println(role_color(Role::Warning), &message);
+        } else {
+            println!("{message}");
+        }
+        return Ok(());
+    }
+
+    for event in &events {
+        let time = format_time(event.at, "%Y-%m-%d %H:%M:%S");
+
+        if is_accessible() {
+            println!("{time}, {}: {}", event.source, event.summary);
+        } else if use_color {
+            let summary = match event.level {
+                LogLevel::Error | LogLevel::Fatal => {
+                    color_println_fmt(role_color(Role::Error), &event.summary)
+                }
+                LogLevel::Warn => color_println_fmt(role_color(Role::Warning), &event.summary),
+                _ => event.summary.to_string(),
+            };
+            println!(
+                "{}  {:<7} {summary}",
+                color_println_fmt(role_color(Role::Timestamp), &time),
+                event.source.to_string()
+            );
+        } else {
+            println!("{time}  {:<7} {}", event.source.to_string(), event.summary);
+        }
+    }
+
+    Ok(())
+}
+
+/// Rolls containers, or all containers of stacks, back to the image they ran before their last
+/// update, listing the containers that can be rolled back when none are given
+pub fn rollback(targets: Vec<String>) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let mut state = load_rollbacks()?;
+
+    if targets.is_empty() {
+        if state.is_empty() {
+            println!("{}", tr("No previous images recorded"));
+            return Ok(());
+        }
+
+        if !is_accessible() {
+            println!(
+                "{:<30} {:<40} {:<14} {:<16}",
+                "CONTAINER", "IMAGE", "PREVIOUS", "RECORDED"
+            );
+        }
+
+        for target in state.values() {
+            let recorded = format_time(target.recorded_at, "%Y-%m-%d %H:%M");
+
+            if is_accessible() {
+                println!(
+                    "{}",
+                    tr_args(
+                        "{container} can be rolled back to {image} image {id}, recorded {time}.",
+                        &[
+                            ("container", &target.container),
+                            ("image", &target.image),
+                            ("id", &target.short_image_id()),
+                            ("time", &recorded),
+                        ]
+                    )
+                );
+            } else {
+                println!(
+                    "{:<30} {:<40} {:<14} {:<16}",
+                    target.container,
+                    target.image,
+                    target.short_image_id(),
+                    recorded
+                );
+            }
+        }
+
+        return Ok(());
+    }
+
+    let targets = select_targets(&state, &targets)?;
+    let mut results = BatchResults::default();
+
+    for target in &targets {
+        let message = tr_args(
+            "Rolling back {container} to {image}",
+            &[
+                ("container", &target.container),
+                ("image", &target.short_image_id()),
+            ],
+        );
+        if use_color {
+            color_println(Color::Cyan, &message);
+        } else {
+            println!("{message}");
+        }
+
+        let result = roll_back(target);
+        record_history(
+            HistoryEntry::new(
+                &target.container,
+                Action::Rollback,
+                result.as_ref().err().map(|e| format!("{e:#}")),
+            )
+            .detail(target.short_image_id()),
+        );
+
+        match result {
+            Ok(()) => {
+                state.remove(&target.container);
+                results.succeeded(&target.container);
+            }
+            Err(e) => {
+                if use_color {
+                    color_println(role_color(Role::Error), &format!("{e:#}"));
+                } else {
+                    println!("{e:#}");
+                }
+                results.failed(&target.container, format!("{e:#}"));
+            }
+        }
+    }
+
+    save_rollbacks(&state)?;
+    record("containers_rolled_back", results.succeeded_count());
+    print_batch_summary(&results, use_color);
+
+    let failed = results.failed_containers();
+    results.into_result(tr_args(
+        "Failed to roll back: {containers}",
+        &[("containers", &failed.join(", "))],
+    ))?;
+
+    let message = tr_args(
+        "Rolled back {count} container(s)",
+        &[("count", &targets.len())],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+/// Pins containers to the digest of the image they run, so recreating them brings back exactly
+/// that image and `update` leaves them alone until they are unpinned
+pub fn pin_digest(containers: Vec<String>) -> anyhow::Result<()> {
+    set_digest_pins(containers, true)
+}
+
+/// Returns containers pinned by `pin-digest` to following the tag of their image
+pub fn unpin(containers: Vec<String>) -> anyhow::Result<()> {
+    set_digest_pins(containers, false)
+}
+
+fn set_digest_pins(containers: Vec<String>, pin: bool) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let containers = expand_stack_names(containers)?;
+    let mut failed: Vec<String> = vec![];
+
+    for container in &containers {
+        let result = if pin {
+            pin_container(container)
+        } else {
+            unpin_container(container)
+        };
+
+        match result {
+            Ok(message) => {
+                if use_color {
+                    color_println(Color::Green, &message);
+                } else {
+                    println!("{message}");
+                }
+            }
+            Err(e) => {
+                if use_color {
+                    color_println(role_color(Role::Error), &format!("{e:#}"));
+                } else {
+                    println!("{e:#}");
+                }
+                failed.push(container.to_string());
+            }
+        }
+    }
+
+    let key = if pin {
+        "containers_pinned"
+    } else {
+        "containers_unpinned"
+    };
+    record(key, containers.len() - failed.len());
+
+    if !failed.is_empty() {
+        let message = if pin {
+            "Failed to pin: {containers}"
+        } else {
+            "Failed to unpin: {containers}"
+        };
+        anyhow::bail!(tr_args(message, &[("containers", &failed.join(", "))]));
+    }
+
+    Ok(())
+}
+
+/// Recreates a container with the image it runs referenced by its repo digest
+fn pin_container(container: &str) -> anyhow::Result<String> {
+    let image = ImageRef::parse(&get_container_image(container)?)?;
+
+    if let Some(digest) = &image.digest {
+        return Ok(tr_args(
+            "{container} is already pinned to {digest}",
+            &[("container", &container), ("digest", &short_digest(digest))],
+        ));
+    }
+
+    let digest = select_repo_digest(&image, &repo_digests(&get_container_image_id(container)?)?)
+        .with_context(|| {
+            tr_args(
+                "{image} has no digest from a registry, it was built or loaded locally",
+                &[("image", &image)],
+            )
+        })?;
+    let pinned = pinned_reference(&image, &digest);
+
+    match get_compose_info(container)? {
+        Some(compose_info) => {
+            let override_file =
+                write_pin_override(&compose_info.project, &compose_info.service, Some(&pinned))?;
+            recreate_with_override(
+                &compose_info,
+                &override_file,
+                std::slice::from_ref(&compose_info.service),
+            )?;
+        }
+        None => recreate_standalone_container(container, Some(&pinned))?,
+    }
+
+    Ok(tr_args(
+        "Pinned {container} to {image}",
+        &[("container", &container), ("image", &pinned)],
+    ))
+}
+
+/// Recreates a pinned container with the image referenced by its tag again
+fn unpin_container(container: &str) -> anyhow::Result<String> {
+    let image = ImageRef::parse(&get_container_image(container)?)?;
+
+    if image.digest.is_none() {
+        return Ok(tr_args(
+            "{container} is not pinned",
+            &[("container", &container)],
+        ));
+    }
+
+    let unpinned = unpinned_reference(&image);
+
+    match get_compose_info(container)? {
+        Some(compose_info) => {
+            if pinned_service_image(&compose_info.project, &compose_info.service)?.is_none() {
+                anyhow::bail!(tr_args(
+                    "{container} is pinned by its compose file, edit {files} to unpin it",
+                    &[
+                        ("container", &container),
+                        ("files", &compose_info.config_files.join(", ")),
+                    ]
+                ));
+            }
+
+            let override_file =
+                write_pin_override(&compose_info.project, &compose_info.service, None)?;
+            recreate_with_override(
+                &compose_info,
+                &override_file,
+                std::slice::from_ref(&compose_info.service),
+            )?;
+        }
+        None => recreate_standalone_container(container, Some(&unpinned))?,
+    }
+
+    Ok(tr_args(
+        "Unpinned {container}, following {image} again",
+        &[("container", &container), ("image", &unpinned)],
+    ))
+}
+
+/// Replaces the running executable with the latest release, or with `check` only reports
+/// whether there is a newer one
+pub fn self_update(check: bool) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let current = env!("CARGO_PKG_VERSION");
+    let release = latest_release()?;
+
+    record("latest_version", release.version());
+
+    if !release.is_newer_than(current) {
+        record("update_available", false);
+        println!(
+            "{}",
+            tr_args(
+                "dsd-util {version} is the latest version",
+                &[("version", &current)]
+            )
+        );
+        return Ok(());
+    }
+
+    record("update_available", true);
+    let message = tr_args(
+        "dsd-util {latest} is available, running {current}",
+        &[("latest", &release.version()), ("current", &current)],
+    );
+    if use_color {
+        color_println(role_color(Role::Warning), &message);
+    } else {
+        println!("{message}");
+    }
+
+    if check {
+        return Ok(());
+    }
+
+    let target = release_target();
+    let archive_name = archive_name(&target);
+    let archive = release.asset(&archive_name).with_context(|| {
+        tr_args(
+            "Release {version} has no binary for {target}",
+            &[("version", &release.version()), ("target", &target)],
+        )
+    })?;
+    let checksums = release.checksum_asset(&archive_name).with_context(|| {
+        tr_args(
+            "Release {version} has no checksum for {archive}",
+            &[("version", &release.version()), ("archive", &archive_name)],
+        )
+    })?;
+
+    let executable = std::env::current_exe()
+        .and_then(|path| path.canonicalize())
+        .context("Failed to find the running executable")?;
+    let dir = create_private_dir("update")?;
+
+    let result = download_release(&release, archive, checksums, &dir)
+        .and_then(|binary| replace_executable(&binary, &executable));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    if let Err(e) = result {
+        if e.chain().any(|cause| {
+            cause
+                .downcast_ref::<io::Error>()
+                .is_some_and(|e| e.kind() == io::ErrorKind::PermissionDenied)
+        }) {
+            return Err(e).context(tr_args(
+                "No permission to replace {path}, run self-update as its owner",
+                &[("path", &executable.display())],
+            ));
+        }
+        return Err(e);
+    }
+
+    let message = tr_args(
+        "Updated {path} to {version}",
+        &[
+            ("path", &executable.display()),
+            ("version", &release.version()),
+        ],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    Ok(())
+}
+
+/// Downloads the archive of a release, verifies it and unpacks the binary
+fn download_release(
+    release: &Release,
+    archive: &ReleaseAsset,
+    checksums: &ReleaseAsset,
+    dir: &Path,
+) -> anyhow::Result<PathBuf> {
+    let archive_path = download(archive, dir)?;
+    verify_checksum(&archive_path, &download(checksums, dir)?)?;
+
+    match release.asset(&format!("{}.bundle", archive.name)) {
+        Some(bundle) if has_cosign() => {
+            verify_signature(&archive_path, &download(bundle, dir)?)?;
+        }
+        Some(_) => eprintln!(
+            "{}",
+            tr("cosign is not installed, only the checksum of the release was verified")
+        ),
+        None => anyhow::bail!(tr_args(
+            "Release {version} has no signature for {archive}, refusing to install it",
+            &[("version", &release.version()), ("archive", &archive.name)]
+        )),
+    }
+
+    extract_binary(&archive_path)
+}
+
+/// How `update` pulls and deploys images
+#[derive(Debug, Clone, PartialEq)]
+pub struct UpdateOptions {
+    /// Number of images to pull concurrently
+    pub jobs: usize,
+    /// Recreate updated containers instead of restarting docker-stack-deploy
+    pub recreate: bool,
+    pub ignore_load: bool,
+    pub ignore_disk: bool,
+    /// Containers or services to leave alone on top of the update.exclude setting
+    pub exclude: Vec<String>,
+    /// Roll recreated containers back to their previous image unless healthy within this time
+    pub auto_rollback: Option<std::time::Duration>,
+    /// File of JSON lines to append the image changes of each update to
+    pub changelog: Option<PathBuf>,
+    /// Credentials to log in to a registry with before pulling
+    pub registry_login: Option<RegistryLogin>,
+}
+
+/// Updates images of specified docker containers, once or on a schedule
+pub fn update(
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    all: bool,
+    options: UpdateOptions,
+    schedule: Option<CronSchedule>,
+    notify: bool,
+) -> anyhow::Result<()> {
+    let Some(schedule) = schedule else {
+        return update_once(containers, stacks, all, options).map(|_| ());
+    };
+
+    handle_ctrl_c()?;
+    let use_color = is_terminal();
+
+    loop {
+        let Some(next) = schedule.next_local(chrono::Local::now()) else {
+            anyhow::bail!(tr_args(
+                "Schedule {schedule} never runs",
+                &[("schedule", &schedule)]
+            ));
+        };
+
+        log_scheduled(
+            Color::Cyan,
+            &tr_args(
+                "Next update at {time}",
+                &[("time", &next.format("%Y-%m-%d %H:%M"))],
+            ),
+            use_color,
+        );
+
+        let wait = (next - chrono::Local::now()).to_std().unwrap_or_default();
+        if !sleep_unless_shutdown(wait) {
+            return Ok(());
+        }
+
+        // the containers of stacks are looked up again, as they come and go between runs
+        let result = update_once(containers.clone(), stacks.clone(), all, options.clone());
+
+        let (color, severity, message) = match &result {
+            Ok(None) => (
+                role_color(Role::Warning),
+                Severity::Warning,
+                tr("Scheduled update skipped"),
+            ),
+            Ok(Some(images)) if images.is_empty() => (
+                Color::Green,
+                Severity::Info,
+                tr("Scheduled update found no new images"),
+            ),
+            Ok(Some(images)) => (
+                Color::Green,
+                Severity::Info,
+                tr_args(
+                    "Scheduled update changed {count} image(s): {images}",
+                    &[("count", &images.len()), ("images", &images.join(", "))],
+                ),
+            ),
+            Err(e) => (
+                role_color(Role::Error),
+                Severity::Critical,
+                tr_args(
+                    "Scheduled update failed: {error}",
+                    &[("error", &format!("{e:#}"))],
+                ),
+            ),
+        };
+
+        log_scheduled(color, &message, use_color);
+
+        if notify {
+            print_notify_error(
+                notify_events(&[Event::new(EventSource::Update, severity, message)]),
+                use_color,
+            );
+        }
+
+        if shutdown_requested() {
+            return Ok(());
+        }
+    }
+}
+
+/// Shows what changed between the images an update pulled and the ones they replaced
+fn print_image_changes(changes: &[ImageChange], number_format: &NumberFormat, use_color: bool) {
+    let created = |metadata: &ImageMetadata| {
+        metadata.created.as_deref().map(|created| {
+            chrono::DateTime::parse_from_rfc3339(created).map_or_else(
+                |_| created.to_string(),
+                |created| format_time(created.with_timezone(&chrono::Utc), "%Y-%m-%d %H:%M"),
+            )
+        })
+    };
+
+    for change in changes {
+        let previous = change.previous.as_ref();
+        let size_delta = change.size_delta().map_or_else(String::new, |delta| {
+            let sign = if delta < 0 { "-" } else { "+" };
+            format!(" ({sign}{})", number_format.size(delta.unsigned_abs()))
+        });
+
+        let rows = [
+            (
+                tr("version"),
+                previous.and_then(|previous| previous.version.clone()),
+                change.current.version.clone(),
+            ),
+            (
+                tr("revision"),
+                previous.and_then(|previous| previous.revision.clone()),
+                change.current.revision.clone(),
+            ),
+            (
+                tr("created"),
+                previous.and_then(created),
+                created(&change.current),
+            ),
+            (
+                tr("size"),
+                previous.map(|previous| number_format.size(previous.size)),
+                Some(format!(
+                    "{}{size_delta}",
+                    number_format.size(change.current.size)
+                )),
+            ),
+        ];
+
+        if use_color {
+            color_println(Color::Cyan, &change.image);
         } else {
-            ContainerStats {
-                name: stats.container_name.to_string(),
-                status: inspect.status.to_string(),
-                restart_policy: inspect.restart_policy.to_string(),
-                health: inspect.health.to_string(),
-                uptime: inspect.uptime.to_string(),
-                cpu_usage: stats.cpu.to_string(),
-                memory_usage: stats.memory.to_string(),
-                ports: inspect.ports.to_string(),
+            println!("{}", change.image);
+        }
+
+        // labels neither image sets are left out
+        for (field, old, new) in rows
+            .iter()
+            .filter(|(_, old, new)| old.is_some() || new.is_some())
+        {
+            let old = old.as_deref().unwrap_or("-");
+            let new = new.as_deref().unwrap_or("-");
+
+            if is_accessible() {
+                println!(
+                    "{}",
+                    tr_args(
+                        "The {field} of {image} changed from {old} to {new}.",
+                        &[
+                            ("field", field),
+                            ("image", &change.image),
+                            ("old", &old),
+                            ("new", &new),
+                        ]
+                    )
+                );
+            } else if use_color && old != new {
+                println!(
+                    "  {:<10} {old} -> {}",
+                    field,
+                    color_println_fmt(Color::Green, new)
+                );
+            } else {
+                println!("  {field:<10} {old} -> {new}");
             }
-        };
+        }
+    }
 
-        total_stats_map.insert(key.to_string(), container_stats);
+    if !changes.is_empty() {
+        println!();
     }
+}
+
+/// Rolls containers that did not become healthy after an update back to their previous image
+fn auto_roll_back(
+    containers: &[String],
+    error: &anyhow::Error,
+    use_color: bool,
+) -> anyhow::Result<()> {
     if use_color {
-        println!(
-            "{:<35} {:<20} {:<16} {:<20} {:<18} {:<8} {:<8} {:<20}",
-            &color_println_fmt(Color::White, "NAME"),
-            &color_println_fmt(Color::White, "STATUS"),
-            "RESTART",
-            &color_println_fmt(Color::White, "HEALTH"),
-            "UPTIME",
-            "CPU %",
-            "MEM %",
-            "PORTS"
-        );
+        color_println(role_color(Role::Error), &format!("{error:#}"));
     } else {
+        println!("{error:#}");
+    }
+
+    let targets = select_targets(&load_rollbacks()?, containers)?;
+
+    start_group(&tr("Rolling back containers"));
+    for target in &targets {
         println!(
-            "{:<35} {:<20} {:<16} {:<20} {:<18} {:<8} {:<8} {:<20}",
-            "NAME", "STATUS", "RESTART", "HEALTH", "UPTIME", "CPU %", "MEM %", "PORTS"
+            "{}",
+            tr_args(
+                "Rolling back {container} to {image}",
+                &[
+                    ("container", &target.container),
+                    ("image", &target.short_image_id())
+                ]
+            )
         );
+        let result = roll_back(target);
+        record_history(
+            HistoryEntry::new(
+                &target.container,
+                Action::Rollback,
+                result.as_ref().err().map(|e| format!("{e:#}")),
+            )
+            .detail(target.short_image_id()),
+        );
+        result?;
     }
+    end_group();
 
-    println!();
+    let message = tr_args(
+        "Rolled back {containers} after a failed update: {error}",
+        &[
+            ("containers", &containers.join(", ")),
+            ("error", &format!("{error:#}")),
+        ],
+    );
+    if use_color {
+        color_println(role_color(Role::Warning), &message);
+    } else {
+        println!("{message}");
+    }
 
-    // TODO: sort - probably want to use BTreeMap instead
-    for key in total_stats_map.keys() {
-        let container = total_stats_map.get(key).context("Failed to get item")?;
+    print_notify_error(
+        notify_events(&[Event::new(EventSource::Update, Severity::Critical, message)]),
+        use_color,
+    );
+
+    Ok(())
+}
 
+/// Prints a timestamped line of a scheduled update
+fn log_scheduled(color: Color, message: &str, use_color: bool) {
+    if use_color {
         println!(
-            "{:<35} {:<20} {:<16} {:<20} {:<18} {:<8} {:<8} {:<20}",
-            container.name,
-            container.status,
-            container.restart_policy,
-            container.health,
-            container.uptime,
-            container.cpu_usage,
-            container.memory_usage,
-            container.ports
+            "[{}] {}",
+            color_println_fmt(role_color(Role::Timestamp), &get_timestamp()),
+            color_println_fmt(color, message)
         );
+    } else {
+        println!("[{}] {message}", get_timestamp());
     }
+}
 
-    Ok(())
+/// What an update did to the containers it was given, for the summary per stack
+struct UpdateOutcome<'a> {
+    containers: &'a [String],
+    /// Containers that were not skipped, along with their image
+    container_images: &'a [(String, String)],
+    pulled: &'a HashMap<String, UpdateResult>,
+    rejected_images: &'a [String],
 }
 
-/// Updates images of specified docker containers
-pub fn update(
+impl UpdateOutcome<'_> {
+    /// Counts per stack of containers updated, failed, skipped and up to date, where
+    /// containers with a rejected image or that could not be recreated failed
+    fn summary(&self, failed: &[String], known: &[Container]) -> Vec<(String, String)> {
+        group_by_stack(self.containers, known)
+            .into_iter()
+            .map(|(stack, containers)| {
+                let (mut updated, mut failures, mut skipped, mut current) = (0, 0, 0, 0);
+
+                for container in &containers {
+                    let image = self
+                        .container_images
+                        .iter()
+                        .find(|(other, _)| other == container)
+                        .map(|(_, image)| image);
+
+                    match image {
+                        None => skipped += 1,
+                        Some(image)
+                            if failed.contains(container)
+                                || self.rejected_images.contains(image) =>
+                        {
+                            failures += 1
+                        }
+                        Some(image)
+                            if self
+                                .pulled
+                                .get(image)
+                                .is_some_and(|result| result.is_updated()) =>
+                        {
+                            updated += 1
+                        }
+                        Some(_) => current += 1,
+                    }
+                }
+
+                (
+                    stack,
+                    tr_args(
+                        "updated {updated}, failed {failed}, skipped {skipped}, up to date {current}",
+                        &[
+                            ("updated", &updated),
+                            ("failed", &failures),
+                            ("skipped", &skipped),
+                            ("current", &current),
+                        ],
+                    ),
+                )
+            })
+            .collect()
+    }
+}
+
+/// Pulls new images of the containers and deploys them, returning the images that changed, or
+/// None when the update was skipped
+fn update_once(
     containers: Option<Vec<String>>,
     stacks: Option<Vec<String>>,
     all: bool,
-) -> anyhow::Result<()> {
-    let containers = if all {
-        list_containers()?
-    } else if let Some(containers) = containers {
-        containers
-    } else if let Some(stacks) = stacks {
-        let mut containers = vec![];
+    options: UpdateOptions,
+) -> anyhow::Result<Option<Vec<String>>> {
+    let UpdateOptions {
+        jobs,
+        recreate,
+        ignore_load,
+        ignore_disk,
+        exclude,
+        auto_rollback,
+        changelog,
+        registry_login,
+    } = options;
+    let containers = resolve_containers(containers, stacks, all)?;
 
-        for stack in &stacks {
-            let container_names = get_containers_from_stack(stack)?;
-            containers.extend(container_names);
+    let use_color = is_terminal();
+    let guard = LoadGuard::from_config(&Config::load()?.guard)?;
+
+    // updates usually run unattended, they can wait for the next run on a struggling host
+    if let Some(overload) = guard.check_host().filter(|_| !ignore_load) {
+        let message = tr_args(
+            "Deferring update, host is overloaded: {reason}",
+            &[("reason", &overload)],
+        );
+        if use_color {
+            color_println(role_color(Role::Warning), &message);
+        } else {
+            println!("{message}");
         }
 
-        containers
-    } else {
-        anyhow::bail!("Must specify containers, use --stacks (-s) or use --all (-a)")
-    };
+        record("deferred", true);
+        return Ok(None);
+    }
 
-    let use_color = is_terminal();
+    let rules = UpdateRules::new(&Config::load()?.update, &exclude);
 
-    let mut num_containers_updated = 0;
+    // deduplicate images so containers sharing an image only pull it once
+    let mut container_images: Vec<(String, String)> = vec![];
+    let mut images: Vec<String> = vec![];
+    let mut previous: Vec<RollbackTarget> = vec![];
+    let mut skipped = 0;
 
     for container in &containers {
-        num_containers_updated += update_container_by_name(container)?;
+        // containers can opt out of updates with a label, the command line or the config file
+        let labels = get_container_labels(container)?;
+        let image = get_container_image(container)?;
+
+        if let Some(exclusion) = rules.check(container, &labels, &image) {
+            let message = tr_args(
+                "Skipping {container}: {reason}",
+                &[("container", container), ("reason", &exclusion)],
+            );
+            if use_color {
+                color_println(role_color(Role::Warning), &message);
+            } else {
+                println!("{message}");
+            }
+
+            skipped += 1;
+            continue;
+        }
+
+        // the first spelling of an image stands for all others, such as nginx and
+        // docker.io/library/nginx:latest, so the pull results are found under it
+        let image = match images.iter().find(|other| same_image(other, &image)) {
+            Some(other) => other.to_string(),
+            None => {
+                images.push(image.to_string());
+                image
+            }
+        };
+
+        // what the container runs before the pull, for rollback to return to
+        previous.push(RollbackTarget {
+            container: container.to_string(),
+            stack: labels.get(LABEL_COMPOSE_PROJECT).cloned(),
+            image: image.to_string(),
+            image_id: get_container_image_id(container)?,
+            recorded_at: chrono::Utc::now(),
+        });
+        container_images.push((container.to_string(), image));
+    }
+
+    record("containers_skipped", skipped);
+
+    if use_color {
+        color_println(
+            Color::Cyan,
+            &tr_args(
+                "Pulling {images} image(s) for {containers} container(s)...",
+                &[
+                    ("images", &images.len()),
+                    ("containers", &container_images.len()),
+                ],
+            ),
+        );
+    } else {
+        println!(
+            "{}",
+            tr_args(
+                "Pulling {images} image(s) for {containers} container(s)...",
+                &[
+                    ("images", &images.len()),
+                    ("containers", &container_images.len()),
+                ]
+            )
+        );
+    }
+
+    if !ignore_disk && !confirm_pull_space(&guard, &images, use_color)? {
+        return Ok(None);
+    }
+
+    if let Some(login) = &registry_login {
+        let registry = login_registry(login, &images)?;
+        println!(
+            "{}",
+            tr_args(
+                "Logging in to {registry} as {user}",
+                &[("registry", &registry), ("user", &login.user)]
+            )
+        );
+        docker_login(&registry, &login.user, &login.password)?;
+    }
+
+    start_group(&tr("Pulling images"));
+    let pulled = pull_images(&images, jobs);
+    end_group();
+
+    if let Err(DsdError::PullFailed {
+        authentication: Some(auth),
+        ..
+    }) = &pulled
+    {
+        record("authentication_required", auth.registry.as_str());
     }
+    let mut pulled = pulled?;
+
+    // an image pulled before without being deployed, such as one refused by verification, is
+    // still new to containers running an older one, even though the pull left its tag alone
+    for target in &previous {
+        if let Some(result) = pulled
+            .get_mut(&target.image)
+            .filter(|result| !result.is_updated() && result.current_id != target.image_id)
+        {
+            result.previous_id = Some(target.image_id.to_string());
+        }
+    }
+
+    let mut changed_images = pulled
+        .values()
+        .filter(|result| result.is_updated())
+        .map(|result| result.image.to_string())
+        .collect::<Vec<String>>();
+    changed_images.sort();
+
+    let num_containers_updated = container_images
+        .iter()
+        .filter(|(_, image)| pulled.get(image).is_some_and(|result| result.is_updated()))
+        .count();
+
+    record("images_pulled", images.len());
+    record("containers_updated", num_containers_updated);
 
     if num_containers_updated == 0 {
         if use_color {
-            color_println(Color::Yellow, "No new container images to update");
+            color_println(
+                role_color(Role::Warning),
+                &tr("No new container images to pull"),
+            );
         } else {
-            println!("No new container images to pull");
+            println!("{}", tr("No new container images to pull"));
         }
 
-        return Ok(());
+        return Ok(Some(changed_images));
+    }
+
+    // only updated containers have an image to go back to, others keep their earlier record
+    record_rollbacks(
+        &previous
+            .into_iter()
+            .filter(|target| {
+                pulled
+                    .get(&target.image)
+                    .is_some_and(|result| result.is_updated())
+            })
+            .collect::<Vec<RollbackTarget>>(),
+    )?;
+
+    let config = Config::load_with_secrets()?;
+    let number_format = config.units.number_format();
+
+    // size on disk of the new images, as an estimate of what was downloaded
+    let mut new_images_size: u64 = 0;
+    for result in pulled.values().filter(|result| result.is_updated()) {
+        new_images_size += get_image_size(&result.image)?;
     }
 
     if use_color {
         println!(
-            "{}: {}",
-            &color_println_fmt(Color::Cyan, "New images pulled"),
-            &color_println_fmt(Color::Green, &num_containers_updated.to_string())
+            "{}: {} ({})",
+            &color_println_fmt(Color::Cyan, &tr("New images pulled")),
+            &color_println_fmt(Color::Green, &num_containers_updated.to_string()),
+            number_format.size(new_images_size)
         );
         println!();
-        color_println(Color::Green, &format!("Restarting {DSD}"));
     } else {
-        println!("New images pulled: {num_containers_updated}");
+        println!(
+            "{}: {num_containers_updated} ({})",
+            tr("New images pulled"),
+            number_format.size(new_images_size)
+        );
         println!();
-        println!("Restarting {DSD}");
+    }
+
+    let changes = image_changes(&pulled)?;
+    print_image_changes(&changes, &number_format, use_color);
+
+    if let Some(path) = &changelog {
+        append_changelog(
+            path,
+            &ChangelogEntry {
+                updated_at: chrono::Utc::now(),
+                changes,
+            },
+        )?;
+    }
+
+    // verify signatures of updated images before they go live
+    let mut rejected_images: Vec<String> = vec![];
+
+    if let Some(verify_config) = &config.verify {
+        start_group(&tr("Verifying signatures"));
+
+        for result in pulled
+            .values()
+            .filter(|result| result.is_updated() && should_verify(verify_config, &result.image))
+        {
+            match verify_image(verify_config, result) {
+                Ok(()) => {
+                    if use_color {
+                        color_println(
+                            Color::Green,
+                            &tr_args("Signature verified: {image}", &[("image", &result.image)]),
+                        );
+                    } else {
+                        println!(
+                            "{}",
+                            tr_args("Signature verified: {image}", &[("image", &result.image)])
+                        );
+                    }
+                }
+                Err(e) if verify_config.mode == VerifyMode::Warn => {
+                    if use_color {
+                        color_println(
+                            role_color(Role::Warning),
+                            &tr_args("WARNING: {error}", &[("error", &e)]),
+                        );
+                    } else {
+                        println!("{}", tr_args("WARNING: {error}", &[("error", &e)]));
+                    }
+                }
+                Err(e) => {
+                    if use_color {
+                        color_println(role_color(Role::Error), &e.to_string());
+                    } else {
+                        println!("{e}");
+                    }
+                    // compose and reboots recreate containers from the tag, keep it off the image
+                    if let Err(e) = restore_previous_image(result) {
+                        if use_color {
+                            color_println(role_color(Role::Error), &format!("{e:#}"));
+                        } else {
+                            println!("{e:#}");
+                        }
+                    }
+                    rejected_images.push(result.image.to_string());
+                }
+            }
+        }
+
+        end_group();
+    }
+
+    record("images_rejected", rejected_images.len());
+
+    let updated_containers = container_images
+        .iter()
+        .filter(|(_, image)| pulled.get(image).is_some_and(|result| result.is_updated()))
+        .map(|(container, _)| container.as_str())
+        .collect::<Vec<&str>>();
+    let mut events = vec![Event::new(
+        EventSource::Update,
+        Severity::Info,
+        tr_args(
+            "New images pulled for {count} container(s): {containers}",
+            &[
+                ("count", &updated_containers.len()),
+                ("containers", &updated_containers.join(", ")),
+            ],
+        ),
+    )];
+    if !rejected_images.is_empty() {
+        events.push(Event::new(
+            EventSource::Update,
+            Severity::Critical,
+            tr_args(
+                "Refused to deploy unverified images: {images}",
+                &[("images", &rejected_images.join(", "))],
+            ),
+        ));
+    }
+    print_notify_error(notify_events(&events), use_color);
+
+    let outcome = UpdateOutcome {
+        containers: &containers,
+        container_images: &container_images,
+        pulled: &pulled,
+        rejected_images: &rejected_images,
+    };
+
+    if recreate {
+        start_group(&tr("Recreating containers"));
+
+        let recreated = container_images
+            .iter()
+            .filter(|(_, image)| {
+                pulled.get(image).is_some_and(|result| result.is_updated())
+                    && !rejected_images.contains(image)
+            })
+            .map(|(container, _)| container.to_string())
+            .collect::<Vec<String>>();
+
+        // containers of a stack are recreated one after another, as compose works on the
+        // project as a whole, while separate stacks go in parallel
+        let known = list_all_containers()?;
+        let failed = run_parallel(
+            &group_by_stack(&recreated, &known),
+            jobs,
+            |(_, containers)| {
+                containers
+                    .iter()
+                    .filter_map(|container| {
+                        let message =
+                            tr_args("Recreating container: {name}", &[("name", container)]);
+                        if use_color {
+                            color_println(Color::Cyan, &message);
+                        } else {
+                            println!("{message}");
+                        }
+
+                        let result = recreate_container(container);
+                        let image = container_images
+                            .iter()
+                            .find(|(name, _)| name == container)
+                            .map(|(_, image)| image.as_str())
+                            .unwrap_or_default();
+                        record_history(
+                            HistoryEntry::new(
+                                container,
+                                Action::Update,
+                                result.as_ref().err().map(|e| format!("{e:#}")),
+                            )
+                            .detail(image),
+                        );
+
+                        let error = result.err()?;
+                        if use_color {
+                            color_println(role_color(Role::Error), &format!("{error:#}"));
+                        } else {
+                            println!("{error:#}");
+                        }
+
+                        Some((container.to_string(), format!("{error:#}")))
+                    })
+                    .collect::<Vec<(String, String)>>()
+            },
+        )
+        .into_iter()
+        .flatten()
+        .collect::<Vec<(String, String)>>();
+
+        end_group();
+
+        let mut results = BatchResults::default();
+        for container in &recreated {
+            match failed.iter().find(|(name, _)| name == container) {
+                Some((_, error)) => results.failed(container, error),
+                None => results.succeeded(container),
+            }
+        }
+        let failed = results.failed_containers();
+
+        record("containers_failed", failed.len());
+        print_stack_summary(&outcome.summary(&failed, &known), use_color);
+        print_batch_summary(&results, use_color);
+
+        let recreated = recreated
+            .into_iter()
+            .filter(|container| !failed.contains(container))
+            .collect::<Vec<String>>();
+
+        if let Some(timeout) = auto_rollback.filter(|_| !recreated.is_empty()) {
+            start_group(&tr("Waiting for healthy containers"));
+            let result = await_healthy(|| Ok(recreated.clone()), timeout, AUTO_ROLLBACK_INTERVAL);
+            end_group();
+
+            if let Err(e) = result {
+                auto_roll_back(&recreated, &e, use_color)?;
+                return Err(e);
+            }
+        }
+
+        results.into_result(tr_args(
+            "Failed to recreate {count} container(s): {containers}",
+            &[("count", &failed.len()), ("containers", &failed.join(", "))],
+        ))?;
+
+        if !rejected_images.is_empty() {
+            anyhow::bail!(tr_args(
+                "Refused to deploy unverified images: {images}",
+                &[("images", &rejected_images.join(", "))]
+            ));
+        }
+
+        return Ok(Some(changed_images));
+    }
+
+    print_stack_summary(&outcome.summary(&[], &list_all_containers()?), use_color);
+
+    if !rejected_images.is_empty() {
+        anyhow::bail!(tr_args(
+            "Refused to restart {name}, unverified images: {images}",
+            &[("name", &DSD), ("images", &rejected_images.join(", "))]
+        ));
+    }
+
+    if use_color {
+        color_println(
+            Color::Green,
+            &tr_args("Restarting {name}", &[("name", &DSD)]),
+        );
+    } else {
+        println!("{}", tr_args("Restarting {name}", &[("name", &DSD)]));
     }
 
     // containers updated, restart docker-stack-deploy to deploy new image
@@ -571,5 +6400,126 @@ pub fn update(
         .status()
         .context(format!("Failed to restart {DSD}"))?;
 
+    Ok(Some(changed_images))
+}
+
+/// Exports the config and state of dsd-util to a bundle, for moving the setup to another host
+pub fn bundle_export(output: PathBuf) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let manifest = export_bundle(&output)?;
+
+    record("bundle", output.display().to_string());
+    record("redacted", manifest.redacted.len());
+
+    let message = tr_args(
+        "Exported config and state to {path}",
+        &[("path", &output.display())],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    if !manifest.redacted.is_empty() {
+        let message = tr_args(
+            "Secrets were redacted and are taken from the config on the importing host: {keys}",
+            &[("keys", &manifest.redacted.join(", "))],
+        );
+        if use_color {
+            color_println(role_color(Role::Warning), &message);
+        } else {
+            println!("{message}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Imports the config and state of dsd-util from a bundle
+pub fn bundle_import(bundle: PathBuf, force: bool) -> anyhow::Result<()> {
+    let use_color = is_terminal();
+    let (manifest, summary) = import_bundle(&bundle, force)?;
+
+    record("written", summary.written.len());
+    record("missing_secrets", summary.missing_secrets.len());
+
+    for path in &summary.written {
+        println!("{}", tr_args("Wrote {path}", &[("path", &path.display())]));
+    }
+
+    let message = tr_args(
+        "Imported {count} files from the bundle of {host} created {time}, {unchanged} already up to date",
+        &[
+            ("count", &summary.written.len()),
+            ("host", &manifest.host),
+            ("time", &manifest.created_at.format("%Y-%m-%d %H:%M UTC")),
+            ("unchanged", &summary.unchanged.len()),
+        ],
+    );
+    if use_color {
+        color_println(Color::Green, &message);
+    } else {
+        println!("{message}");
+    }
+
+    if !summary.missing_secrets.is_empty() {
+        let message = tr_args(
+            "Set these secrets again in the config file, the bundle has them redacted: {keys}",
+            &[("keys", &summary.missing_secrets.join(", "))],
+        );
+        if use_color {
+            color_println(role_color(Role::Warning), &message);
+        } else {
+            println!("{message}");
+        }
+    }
+
+    Ok(())
+}
+
+/// Encrypts a secret read from stdin, for `${secret:name}` references in the config file
+pub fn secret_set(name: String) -> anyhow::Result<()> {
+    if io::stdin().is_terminal() {
+        eprintln!("{}", tr("Enter the secret, then press Ctrl-D"));
+    }
+    let value = read_password_stdin()?;
+
+    set_secret(&name, &value)?;
+    println!("{}", tr_args("Stored secret {name}", &[("name", &name)]));
+
+    Ok(())
+}
+
+/// Prints a secret, for scripts passing it on to other tools
+pub fn secret_get(name: String) -> anyhow::Result<()> {
+    println!("{}", secret_value(&name)?);
+
+    Ok(())
+}
+
+/// Lists the names of the stored secrets, never their values
+pub fn secret_list() -> anyhow::Result<()> {
+    let secrets = load_secrets()?;
+    record("secrets", secrets.len());
+
+    if secrets.is_empty() {
+        println!("{}", tr("No secrets stored"));
+    }
+    for name in secrets.keys() {
+        println!("{name}");
+    }
+
+    Ok(())
+}
+
+/// Removes a secret
+pub fn secret_remove(name: String) -> anyhow::Result<()> {
+    if !remove_secret(&name)? {
+        anyhow::bail!(tr_args("No secret named {name}", &[("name", &name)]));
+    }
+
+    println!("{}", tr_args("Removed secret {name}", &[("name", &name)]));
+
     Ok(())
 }
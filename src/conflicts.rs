@@ -0,0 +1,313 @@
+use crate::docker::Container;
+use crate::drift::compose_ports;
+use crate::ports::{find_conflicts, PortMapping};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Component, Path, PathBuf};
+
+/// Host paths that containers of many projects are meant to share, such as the docker socket
+const SHARED_HOST_PATHS: [&str; 9] = [
+    "/var/run/docker.sock",
+    "/run/docker.sock",
+    "/etc/localtime",
+    "/etc/timezone",
+    "/etc/hosts",
+    "/dev",
+    "/proc",
+    "/sys",
+    "/lib/modules",
+];
+
+/// Host directory or file a service mounts
+#[derive(Debug, Clone, PartialEq)]
+pub struct BindMount {
+    pub source: PathBuf,
+    pub target: String,
+    pub read_only: bool,
+}
+
+/// What a compose project declares for one of its services that other projects may collide with
+#[derive(Debug, Clone, PartialEq)]
+pub struct DeclaredService {
+    pub project: String,
+    pub service: String,
+    /// Name set with `container_name`, compose names containers after the project otherwise
+    pub container_name: Option<String>,
+    pub ports: Vec<PortMapping>,
+    pub bind_mounts: Vec<BindMount>,
+}
+
+impl DeclaredService {
+    /// Service qualified by its project, such as web/app
+    pub fn qualified(&self) -> String {
+        format!("{}/{}", self.project, self.service)
+    }
+}
+
+/// Something two compose projects, or a project and an existing container, both claim
+#[derive(Debug, Clone, PartialEq)]
+pub enum Conflict {
+    /// Host port and protocol, such as 8080/tcp, published by several containers
+    Port {
+        port: String,
+        containers: Vec<String>,
+    },
+    /// Container name claimed more than once, by services or containers outside the project
+    ContainerName {
+        name: String,
+        claimants: Vec<String>,
+    },
+    /// Bind mounts of services of different projects overlap, with at least one writable
+    BindMount {
+        first: (String, PathBuf),
+        second: (String, PathBuf),
+    },
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Conflict::Port { port, containers } => {
+                write!(f, "port {port} is published by {}", containers.join(", "))
+            }
+            Conflict::ContainerName { name, claimants } => {
+                write!(
+                    f,
+                    "container name {name} is claimed by {}",
+                    claimants.join(", ")
+                )
+            }
+            Conflict::BindMount { first, second } if first.1 == second.1 => write!(
+                f,
+                "{} and {} both mount {}",
+                first.0,
+                second.0,
+                first.1.display()
+            ),
+            Conflict::BindMount { first, second } => write!(
+                f,
+                "{} mounts {}, which overlaps {} of {}",
+                first.0,
+                first.1.display(),
+                second.1.display(),
+                second.0
+            ),
+        }
+    }
+}
+
+/// Services a compose config declares, with bind mounts resolved against the working directory
+pub fn declared_services(
+    project: &str,
+    working_dir: &Path,
+    config: &Value,
+) -> Vec<DeclaredService> {
+    let mut services = config["services"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(service, service_config)| DeclaredService {
+            project: project.to_string(),
+            service: service.to_string(),
+            container_name: service_config["container_name"].as_str().map(String::from),
+            ports: declared_ports(&service_config["ports"], project, service),
+            bind_mounts: bind_mounts(&service_config["volumes"], working_dir),
+        })
+        .collect::<Vec<DeclaredService>>();
+
+    services.sort_by(|a, b| a.service.cmp(&b.service));
+    services
+}
+
+/// Published ports of a service as mappings, leaving out ports docker picks on start. The
+/// mappings name the service qualified by its project, as container names may collide too.
+fn declared_ports(ports: &Value, project: &str, service: &str) -> Vec<PortMapping> {
+    compose_ports(ports)
+        .iter()
+        .filter_map(|spec| {
+            let (host, container_port) = spec.rsplit_once(':')?;
+            let (host_ip, host_port) = match host.rsplit_once(':') {
+                Some((host_ip, host_port)) => (Some(host_ip.to_string()), host_port),
+                None => (None, host),
+            };
+
+            Some(PortMapping {
+                host_ip,
+                host_port: host_port.to_string(),
+                container_port: container_port.to_string(),
+                container: format!("{project}/{service}"),
+                service: Some(service.to_string()),
+                stack: Some(project.to_string()),
+                networks: vec![],
+            })
+        })
+        .collect()
+}
+
+/// Bind mounts among the volumes of a service, in short or long syntax
+pub fn bind_mounts(volumes: &Value, working_dir: &Path) -> Vec<BindMount> {
+    volumes
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|volume| match volume {
+            Value::String(volume) => {
+                let mut parts = volume.splitn(3, ':');
+                let source = parts.next()?;
+                let target = parts.next()?;
+                let read_only = parts
+                    .next()
+                    .is_some_and(|mode| mode.split(',').any(|option| option == "ro"));
+
+                // anything else is the name of a volume
+                if !source.starts_with(['/', '.', '~']) {
+                    return None;
+                }
+
+                Some(BindMount {
+                    source: resolve_source(source, working_dir),
+                    target: target.to_string(),
+                    read_only,
+                })
+            }
+            Value::Object(volume) if volume.get("type")?.as_str()? == "bind" => Some(BindMount {
+                source: resolve_source(volume.get("source")?.as_str()?, working_dir),
+                target: volume.get("target")?.as_str()?.to_string(),
+                read_only: volume
+                    .get("read_only")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false),
+            }),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Absolute path of the source of a bind mount, without `.` and `..` components
+fn resolve_source(source: &str, working_dir: &Path) -> PathBuf {
+    let path = match source.strip_prefix('~') {
+        Some(rest) => {
+            let home = std::env::var("HOME").unwrap_or_default();
+            PathBuf::from(format!("{home}{rest}"))
+        }
+        None => working_dir.join(source),
+    };
+
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+
+    normalized
+}
+
+fn is_shared_host_path(path: &Path) -> bool {
+    SHARED_HOST_PATHS
+        .iter()
+        .any(|shared| path.starts_with(shared))
+}
+
+/// Finds ports, container names and bind mounts that compose projects, and the containers
+/// outside them, would fight over.
+///
+/// `existing` are all containers on the host, `existing_ports` the ports published by those
+/// not belonging to any of the projects.
+pub fn find_project_conflicts(
+    services: &[DeclaredService],
+    existing: &[Container],
+    existing_ports: &[PortMapping],
+) -> Vec<Conflict> {
+    let mut conflicts = vec![];
+
+    let mut mappings = services
+        .iter()
+        .flat_map(|service| service.ports.iter().cloned())
+        .collect::<Vec<PortMapping>>();
+    mappings.extend(existing_ports.iter().cloned());
+    conflicts.extend(find_conflicts(&mappings).into_iter().map(|conflict| {
+        Conflict::Port {
+            port: conflict.port,
+            containers: conflict
+                .containers
+                .into_iter()
+                .map(|(container, stack)| match stack {
+                    Some(_) => container,
+                    None => format!("container {container}"),
+                })
+                .collect(),
+        }
+    }));
+
+    let mut names: BTreeMap<&str, Vec<&DeclaredService>> = BTreeMap::new();
+    for service in services {
+        if let Some(name) = &service.container_name {
+            names.entry(name).or_default().push(service);
+        }
+    }
+
+    for (name, claiming) in names {
+        let mut claimants = claiming
+            .iter()
+            .map(|service| service.qualified())
+            .collect::<Vec<String>>();
+        // compose recreates containers of the claiming services, any other one is in the way
+        claimants.extend(
+            existing
+                .iter()
+                .filter(|container| container.name == name)
+                .filter(|container| {
+                    !claiming.iter().any(|service| {
+                        container.stack.as_deref() == Some(service.project.as_str())
+                            && container.service.as_deref() == Some(service.service.as_str())
+                    })
+                })
+                .map(|container| match &container.stack {
+                    Some(stack) => format!("container {} ({stack})", container.name),
+                    None => format!("container {}", container.name),
+                }),
+        );
+
+        if claimants.len() > 1 {
+            claimants.sort();
+            conflicts.push(Conflict::ContainerName {
+                name: name.to_string(),
+                claimants,
+            });
+        }
+    }
+
+    for (index, service) in services.iter().enumerate() {
+        for other in &services[index + 1..] {
+            if other.project == service.project {
+                continue;
+            }
+
+            for mount in &service.bind_mounts {
+                for other_mount in &other.bind_mounts {
+                    let overlaps = mount.source.starts_with(&other_mount.source)
+                        || other_mount.source.starts_with(&mount.source);
+                    // reading the same files is fine, writing them from two projects is not
+                    let writable = !mount.read_only || !other_mount.read_only;
+                    let shared = is_shared_host_path(&mount.source)
+                        || is_shared_host_path(&other_mount.source);
+
+                    if overlaps && writable && !shared {
+                        conflicts.push(Conflict::BindMount {
+                            first: (service.qualified(), mount.source.clone()),
+                            second: (other.qualified(), other_mount.source.clone()),
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    conflicts
+}
@@ -0,0 +1,190 @@
+use crate::commands::DOCKER;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
+/// Compose file names docker compose looks for, in order of preference
+pub const COMPOSE_FILE_NAMES: [&str; 4] = [
+    "compose.yaml",
+    "compose.yml",
+    "docker-compose.yaml",
+    "docker-compose.yml",
+];
+
+static CONTEXT_ENABLED: AtomicBool = AtomicBool::new(true);
+static COMPOSE_SELECTION: OnceLock<ComposeSelection> = OnceLock::new();
+
+/// Shape of the compose files, profiles and env file selected on the command line
+#[derive(Debug, Clone, Default)]
+pub struct ComposeSelection {
+    pub files: Vec<PathBuf>,
+    pub profiles: Vec<String>,
+    pub env_file: Option<PathBuf>,
+}
+
+/// Shape of the compose project found in the current directory
+#[derive(Debug, Clone)]
+pub struct WorkspaceContext {
+    pub project: String,
+    pub dir: PathBuf,
+    pub compose_files: Vec<PathBuf>,
+    /// Services active under the selected profiles, empty when compose could not resolve them
+    pub services: Vec<String>,
+}
+
+/// Enables or disables defaulting commands to the compose project of the current directory
+pub fn set_context_enabled(enabled: bool) {
+    CONTEXT_ENABLED.store(enabled, Ordering::Relaxed);
+}
+
+/// Sets the compose files, profiles and env file used to resolve the context
+pub fn set_compose_selection(selection: ComposeSelection) {
+    let _ = COMPOSE_SELECTION.set(selection);
+}
+
+/// Gets the compose files, profiles and env file used to resolve the context
+pub fn compose_selection() -> ComposeSelection {
+    COMPOSE_SELECTION.get().cloned().unwrap_or_default()
+}
+
+/// Finds the compose project of the current directory, if context is enabled
+pub fn current_context() -> Option<WorkspaceContext> {
+    if !CONTEXT_ENABLED.load(Ordering::Relaxed) {
+        return None;
+    }
+
+    let selection = compose_selection();
+
+    // explicitly selected compose files take precedence over searching the current directory
+    if let Some(first_file) = selection.files.first() {
+        let dir = first_file
+            .parent()
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| PathBuf::from("."));
+
+        return Some(resolve_context(&dir, selection.files.clone(), &selection));
+    }
+
+    let cwd = std::env::current_dir().ok()?;
+
+    find_workspace_context(&cwd)
+}
+
+/// Walks up from a directory looking for a compose file, like git does for its repo
+pub fn find_workspace_context(start: &Path) -> Option<WorkspaceContext> {
+    let selection = compose_selection();
+
+    for dir in start.ancestors() {
+        let Some(compose_file) = COMPOSE_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file())
+        else {
+            continue;
+        };
+
+        return Some(resolve_context(dir, vec![compose_file], &selection));
+    }
+
+    None
+}
+
+/// Resolves project name and active services, letting docker compose apply overrides,
+/// profiles and .env interpolation
+fn resolve_context(
+    dir: &Path,
+    compose_files: Vec<PathBuf>,
+    selection: &ComposeSelection,
+) -> WorkspaceContext {
+    let mut command = Command::new(DOCKER);
+    command.arg("compose").current_dir(dir);
+
+    // without explicit files compose picks up the override file and COMPOSE_FILE itself
+    for file in &selection.files {
+        command
+            .arg("-f")
+            .arg(file.canonicalize().unwrap_or_else(|_| file.to_path_buf()));
+    }
+    for profile in &selection.profiles {
+        command.args(["--profile", profile]);
+    }
+    // compose runs in the project directory, while the env file was given relative to ours
+    if let Some(env_file) = &selection.env_file {
+        command.arg("--env-file").arg(
+            env_file
+                .canonicalize()
+                .unwrap_or_else(|_| env_file.to_path_buf()),
+        );
+    }
+
+    let resolved = command
+        .args(["config", "--format", "json"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| serde_json::from_slice::<serde_json::Value>(&output.stdout).ok());
+
+    match resolved {
+        Some(config) => WorkspaceContext {
+            project: config["name"]
+                .as_str()
+                .map(normalize_project_name)
+                .unwrap_or_else(|| project_name(dir, &compose_files[0])),
+            dir: dir.to_path_buf(),
+            compose_files,
+            services: config["services"]
+                .as_object()
+                .map(|services| services.keys().cloned().collect())
+                .unwrap_or_default(),
+        },
+        None => WorkspaceContext {
+            project: project_name(dir, &compose_files[0]),
+            dir: dir.to_path_buf(),
+            compose_files,
+            services: vec![],
+        },
+    }
+}
+
+/// Determines the compose project name the same way docker compose does
+pub fn project_name(dir: &Path, compose_file: &Path) -> String {
+    if let Some(name) = std::env::var("COMPOSE_PROJECT_NAME")
+        .ok()
+        .filter(|name| !name.is_empty())
+    {
+        return normalize_project_name(&name);
+    }
+
+    // top-level `name:` in the compose file
+    if let Ok(contents) = std::fs::read_to_string(compose_file) {
+        let name = contents
+            .lines()
+            .find_map(|line| line.strip_prefix("name:"))
+            .map(|name| name.trim().trim_matches(|c| c == '"' || c == '\''));
+
+        if let Some(name) = name.filter(|name| !name.is_empty()) {
+            return normalize_project_name(name);
+        }
+    }
+
+    let dir_name = dir
+        .canonicalize()
+        .ok()
+        .and_then(|dir| {
+            dir.file_name()
+                .map(|name| name.to_string_lossy().to_string())
+        })
+        .unwrap_or_default();
+
+    normalize_project_name(&dir_name)
+}
+
+/// Normalizes a project name to lowercase alphanumerics, dashes and underscores
+fn normalize_project_name(name: &str) -> String {
+    name.to_lowercase()
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric() || *c == '-' || *c == '_')
+        .collect()
+}
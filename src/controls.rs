@@ -0,0 +1,201 @@
+use crate::selector::fuzzy_matches;
+use anyhow::Context;
+use std::io::Read;
+use std::os::fd::AsRawFd;
+use std::sync::mpsc::{channel, Receiver};
+
+const KEY_ENTER: u8 = b'\n';
+const KEY_RETURN: u8 = b'\r';
+const KEY_ESCAPE: u8 = 0x1b;
+const KEY_BACKSPACE: u8 = 0x7f;
+const KEY_CTRL_H: u8 = 0x08;
+
+/// Lines held back while paused, the oldest ones being dropped beyond that
+pub const MAX_HELD_LINES: usize = 10_000;
+
+/// Text being typed at the bottom of the terminal
+#[derive(Debug, Clone, PartialEq)]
+pub enum Prompt {
+    /// Live filter, started with /
+    Filter(String),
+    /// Name of a container to mute or unmute, started by typing it
+    Mute(String),
+}
+
+impl Prompt {
+    /// How the prompt is shown while typing
+    pub fn display(&self) -> String {
+        match self {
+            Prompt::Filter(text) => format!("/{text}"),
+            Prompt::Mute(text) => format!("mute: {text}"),
+        }
+    }
+}
+
+/// What a key press asks the log follower to do
+#[derive(Debug, Clone, PartialEq)]
+pub enum ControlAction {
+    /// Nothing to do, such as for keys without a meaning
+    None,
+    Pause,
+    /// Resume output, printing the lines buffered in the meantime
+    Resume,
+    /// Show the prompt being typed
+    Prompt(Prompt),
+    /// Replace the live filter, removing it when None
+    Filter(Option<String>),
+    /// Mute or unmute the container typed
+    ToggleMute(String),
+    /// Close the prompt without doing anything
+    Cancel,
+}
+
+/// Keyboard controls while following logs: space pauses and resumes, / sets a live filter, and
+/// typing a container name mutes or unmutes it
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct KeyControls {
+    paused: bool,
+    prompt: Option<Prompt>,
+}
+
+impl KeyControls {
+    /// Whether lines are held back, while paused or typing so the prompt is not overwritten
+    pub fn holds_output(&self) -> bool {
+        self.paused || self.prompt.is_some()
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    /// Handles a byte read from the terminal
+    pub fn key(&mut self, key: u8) -> ControlAction {
+        let Some(prompt) = &mut self.prompt else {
+            return match key {
+                b' ' => {
+                    self.paused = !self.paused;
+                    if self.paused {
+                        ControlAction::Pause
+                    } else {
+                        ControlAction::Resume
+                    }
+                }
+                b'/' => self.open(Prompt::Filter(String::new())),
+                // container names start with a letter or digit
+                key if key.is_ascii_alphanumeric() => {
+                    self.open(Prompt::Mute((key as char).to_string()))
+                }
+                _ => ControlAction::None,
+            };
+        };
+
+        let text = match prompt {
+            Prompt::Filter(text) | Prompt::Mute(text) => text,
+        };
+
+        match key {
+            KEY_ENTER | KEY_RETURN => match self.prompt.take() {
+                Some(Prompt::Filter(text)) if text.is_empty() => ControlAction::Filter(None),
+                Some(Prompt::Filter(text)) => ControlAction::Filter(Some(text)),
+                Some(Prompt::Mute(text)) => ControlAction::ToggleMute(text),
+                None => ControlAction::None,
+            },
+            KEY_ESCAPE => {
+                self.prompt = None;
+                ControlAction::Cancel
+            }
+            KEY_BACKSPACE | KEY_CTRL_H => {
+                text.pop();
+                ControlAction::Prompt(prompt.clone())
+            }
+            key if key.is_ascii_graphic() || key == b' ' => {
+                text.push(key as char);
+                ControlAction::Prompt(prompt.clone())
+            }
+            _ => ControlAction::None,
+        }
+    }
+
+    fn open(&mut self, prompt: Prompt) -> ControlAction {
+        self.prompt = Some(prompt.clone());
+        ControlAction::Prompt(prompt)
+    }
+}
+
+/// Finds the container a typed name means, its exact name or the only one it fuzzily matches
+pub fn typed_container<'a>(typed: &str, containers: &'a [String]) -> Option<&'a str> {
+    if let Some(container) = containers.iter().find(|container| *container == typed) {
+        return Some(container);
+    }
+
+    match fuzzy_matches(typed, containers).as_slice() {
+        [container] => Some(*container),
+        _ => None,
+    }
+}
+
+/// Puts the terminal on stdin into a mode passing on key presses right away without echoing
+/// them, restoring the previous mode when dropped. Ctrl-C keeps working.
+pub struct RawInput {
+    original: libc::termios,
+}
+
+impl RawInput {
+    pub fn enable() -> anyhow::Result<Self> {
+        let fd = std::io::stdin().as_raw_fd();
+        let mut termios = std::mem::MaybeUninit::<libc::termios>::zeroed();
+
+        // SAFETY: fd is stdin and tcgetattr only writes into the provided struct
+        if unsafe { libc::tcgetattr(fd, termios.as_mut_ptr()) } != 0 {
+            return Err(std::io::Error::last_os_error()).context("Failed to read terminal mode");
+        }
+
+        // SAFETY: tcgetattr succeeded and filled in the struct
+        let original = unsafe { termios.assume_init() };
+        let mut raw = original;
+        raw.c_lflag &= !(libc::ICANON | libc::ECHO);
+        raw.c_cc[libc::VMIN] = 1;
+        raw.c_cc[libc::VTIME] = 0;
+
+        // SAFETY: fd is stdin and raw is a valid termios struct
+        if unsafe { libc::tcsetattr(fd, libc::TCSANOW, &raw) } != 0 {
+            return Err(std::io::Error::last_os_error()).context("Failed to set terminal mode");
+        }
+
+        Ok(RawInput { original })
+    }
+}
+
+impl Drop for RawInput {
+    fn drop(&mut self) {
+        // SAFETY: fd is stdin and original is the mode read from it before
+        unsafe {
+            libc::tcsetattr(std::io::stdin().as_raw_fd(), libc::TCSANOW, &self.original);
+        }
+    }
+}
+
+/// Reads key presses from stdin on a background thread, which ends once stdin closes
+pub fn read_keys() -> Receiver<u8> {
+    let (sender, receiver) = channel();
+
+    std::thread::spawn(move || {
+        let mut stdin = std::io::stdin();
+        let mut buffer = [0u8; 64];
+
+        loop {
+            match stdin.read(&mut buffer) {
+                Ok(0) | Err(_) => return,
+                Ok(read) => {
+                    for key in &buffer[..read] {
+                        if sender.send(*key).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        }
+    });
+
+    receiver
+}
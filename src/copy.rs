@@ -0,0 +1,128 @@
+use crate::commands::DOCKER;
+use anyhow::Context;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Source or destination of a copy
+#[derive(Debug, Clone, PartialEq)]
+pub enum CopyLocation {
+    Host(PathBuf),
+    /// Path in a container given by name, as `container:path`
+    Container {
+        container: String,
+        path: String,
+    },
+    /// Path in the container of a compose service, as `stack/service:path`
+    Service {
+        stack: String,
+        service: String,
+        path: String,
+    },
+}
+
+impl CopyLocation {
+    /// Parses a location the way docker cp does, with services given as `stack/service:path`.
+    ///
+    /// Anything starting with `/` or `.` is a host path, so host paths containing a colon can
+    /// be given as `./file:name`.
+    pub fn parse(location: &str) -> Self {
+        if location.starts_with(['/', '.']) {
+            return CopyLocation::Host(PathBuf::from(location));
+        }
+
+        let Some((container, path)) = location.split_once(':') else {
+            return CopyLocation::Host(PathBuf::from(location));
+        };
+
+        match container.split_once('/') {
+            Some((stack, service)) => CopyLocation::Service {
+                stack: stack.to_string(),
+                service: service.to_string(),
+                path: path.to_string(),
+            },
+            None => CopyLocation::Container {
+                container: container.to_string(),
+                path: path.to_string(),
+            },
+        }
+    }
+
+    pub fn is_host(&self) -> bool {
+        matches!(self, CopyLocation::Host(_))
+    }
+}
+
+impl fmt::Display for CopyLocation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CopyLocation::Host(path) => write!(f, "{}", path.display()),
+            CopyLocation::Container { container, path } => write!(f, "{container}:{path}"),
+            CopyLocation::Service {
+                stack,
+                service,
+                path,
+            } => write!(f, "{stack}/{service}:{path}"),
+        }
+    }
+}
+
+/// Container of a compose service, from the containers of its stack and their services
+pub fn resolve_service(
+    stack: &str,
+    service: &str,
+    services: &[(String, String)],
+) -> anyhow::Result<String> {
+    let containers = services
+        .iter()
+        .filter(|(_, other)| other == service)
+        .map(|(container, _)| container.as_str())
+        .collect::<Vec<&str>>();
+
+    match containers.as_slice() {
+        [] => anyhow::bail!("No container of service {service} found in stack: {stack}"),
+        [container] => Ok(container.to_string()),
+        containers => anyhow::bail!(
+            "Service {service} of {stack} has several containers, address one of them by name: {}",
+            containers.join(", ")
+        ),
+    }
+}
+
+/// Directory a container's copy goes to with `--all`: the service name, or the container name
+/// when the service runs more than one container
+pub fn service_directory(
+    destination: &Path,
+    container: &str,
+    service: &str,
+    services: &[(String, String)],
+) -> PathBuf {
+    let replicas = services
+        .iter()
+        .filter(|(_, other)| other == service)
+        .count();
+
+    if replicas > 1 {
+        destination.join(container)
+    } else {
+        destination.join(service)
+    }
+}
+
+/// Copies with docker cp, showing its progress unless quiet
+pub fn docker_cp(source: &str, destination: &str, quiet: bool) -> anyhow::Result<()> {
+    let quiet_args: &[&str] = if quiet { &["--quiet"] } else { &[] };
+
+    let status = Command::new(DOCKER)
+        .arg("cp")
+        .args(quiet_args)
+        .args([source, destination])
+        .status()
+        .with_context(|| format!("Failed to copy {source} to {destination}"))?;
+
+    if !status.success() {
+        anyhow::bail!("Failed to copy {source} to {destination}");
+    }
+
+    Ok(())
+}
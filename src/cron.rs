@@ -0,0 +1,166 @@
+use anyhow::Context;
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike};
+use std::fmt;
+
+/// How far ahead to look for the next run, schedules such as `0 0 30 2 *` never run
+const SEARCH_DAYS: i64 = 5 * 366;
+
+/// Schedule in the five field cron syntax of minute, hour, day of month, month and day of week,
+/// or one of @hourly, @daily, @weekly, @monthly and @yearly
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronSchedule {
+    expression: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    /// Whether day of month and day of week were given, a day matching either is enough then
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+/// Parses a cron expression such as `0 4 * * *` or `*/15 8-18 * * 1-5`
+pub fn parse_cron(input: &str) -> anyhow::Result<CronSchedule> {
+    let expanded = match input.trim() {
+        "@hourly" => "0 * * * *",
+        "@daily" | "@midnight" => "0 0 * * *",
+        "@weekly" => "0 0 * * 0",
+        "@monthly" => "0 0 1 * *",
+        "@yearly" | "@annually" => "0 0 1 1 *",
+        expression => expression,
+    };
+
+    let fields = expanded.split_whitespace().collect::<Vec<&str>>();
+    let [minutes, hours, days, months, weekdays] = fields.as_slice() else {
+        anyhow::bail!(
+            "Invalid schedule: {input}, expected minute, hour, day of month, month and day of week"
+        );
+    };
+
+    let field = |field: &str, name: &str, min: u32, max: u32| {
+        parse_field(field, min, max)
+            .with_context(|| format!("Invalid {name} in schedule {input}: {field}"))
+    };
+
+    // both 0 and 7 are Sunday
+    let mut weekdays_mask = field(weekdays, "day of week", 0, 7)?;
+    if weekdays_mask & (1 << 7) != 0 {
+        weekdays_mask = (weekdays_mask & !(1 << 7)) | 1;
+    }
+
+    Ok(CronSchedule {
+        expression: input.trim().to_string(),
+        minutes: field(minutes, "minute", 0, 59)?,
+        hours: field(hours, "hour", 0, 23)?,
+        days: field(days, "day of month", 1, 31)?,
+        months: field(months, "month", 1, 12)?,
+        weekdays: weekdays_mask,
+        days_restricted: !days.starts_with('*'),
+        weekdays_restricted: !weekdays.starts_with('*'),
+    })
+}
+
+/// Parses a comma separated list of values, ranges and steps into a bit mask
+fn parse_field(field: &str, min: u32, max: u32) -> anyhow::Result<u64> {
+    let mut mask = 0;
+
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().context("Invalid step")?),
+            None => (item, 1),
+        };
+        if step == 0 {
+            anyhow::bail!("Step must not be zero");
+        }
+
+        let value = |value: &str| -> anyhow::Result<u32> {
+            let value = value
+                .parse::<u32>()
+                .with_context(|| format!("Invalid value: {value}"))?;
+            if !(min..=max).contains(&value) {
+                anyhow::bail!("{value} is out of range {min}-{max}");
+            }
+            Ok(value)
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            range => match range.split_once('-') {
+                Some((start, end)) => (value(start)?, value(end)?),
+                // a single value with a step runs from it to the end of the range
+                None if item.contains('/') => (value(range)?, max),
+                None => (value(range)?, value(range)?),
+            },
+        };
+        if start > end {
+            anyhow::bail!("Range {start}-{end} is reversed");
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+
+    Ok(mask)
+}
+
+impl CronSchedule {
+    fn matches_day(&self, date: NaiveDate) -> bool {
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    /// First time after the given one that the schedule runs, to the minute
+    pub fn next_after(&self, time: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut time = time.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = time + Duration::days(SEARCH_DAYS);
+
+        while time <= limit {
+            let date = time.date();
+
+            if self.months & (1 << date.month()) == 0 {
+                let (year, month) = if date.month() == 12 {
+                    (date.year() + 1, 1)
+                } else {
+                    (date.year(), date.month() + 1)
+                };
+                time = NaiveDate::from_ymd_opt(year, month, 1)?.and_hms_opt(0, 0, 0)?;
+            } else if !self.matches_day(date) {
+                time = date.succ_opt()?.and_hms_opt(0, 0, 0)?;
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = date.and_hms_opt(time.hour(), 0, 0)? + Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+
+        None
+    }
+
+    /// Next run in local time, skipping times that do not exist because clocks were set forward
+    pub fn next_local(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let mut time = now.naive_local();
+
+        loop {
+            time = self.next_after(time)?;
+
+            if let Some(local) = Local.from_local_datetime(&time).earliest() {
+                return Some(local);
+            }
+        }
+    }
+}
+
+impl fmt::Display for CronSchedule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expression)
+    }
+}
@@ -0,0 +1,1024 @@
+use crate::config::{DaemonConfig, HookConfig, ReportConfig, VerifyConfig, VerifyMode};
+use crate::exclusions::UpdateRules;
+use crate::hostinfo::LoadGuard;
+use crate::i18n::{tr, tr_args};
+use crate::image::same_image;
+use crate::prepull::{add_pulled, load_pulled, remove_applied, update_pulled};
+use crate::printer::{color_println, role_color, Color, Role};
+use crate::queue::{ActionQueue, Enqueued, QueueState, QueuedAction, Trigger};
+use crate::report::send_daily_report;
+use crate::utils::{
+    compose_command, get_compose_info, get_container_image, get_container_image_id,
+    get_container_labels, get_containers_from_stack, get_image_id, get_stack_services,
+    get_timestamp, is_terminal, ComposeInfo, UpdateResult,
+};
+use crate::verify::{restore_previous_image, should_verify, verify_image};
+use crate::window::{local_time, TimeWindow};
+use anyhow::Context;
+use hmac_sha256::HMAC;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::process::Command;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+const CURL: &str = "curl";
+const CLIENT_TIMEOUT: &str = "10";
+
+/// Environment variable holding the webhook secret, taking precedence over the config file
+pub const ENV_WEBHOOK_SECRET: &str = "DSD_UTIL_WEBHOOK_SECRET";
+
+/// Path prefix of webhook endpoints, followed by the stack name
+const HOOKS_PATH_PREFIX: &str = "/hooks/";
+
+/// Header carrying the signature of the request body, as sent by GitHub and Gitea
+const SIGNATURE_HEADER: &str = "x-hub-signature-256";
+const SIGNATURE_PREFIX: &str = "sha256=";
+
+/// Path reporting the state of the action queue
+const STATUS_PATH: &str = "/status";
+/// Header with the unix time a status request was signed at, and how far it may be off
+const TIMESTAMP_HEADER: &str = "x-dsd-timestamp";
+const MAX_TIMESTAMP_SKEW: u64 = 300;
+
+/// Largest request body accepted, webhook payloads only name a few services
+const MAX_BODY_SIZE: usize = 64 * 1024;
+/// Longest request or header line accepted, and the most headers a request may have
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+/// Connections handled at once, further ones are turned away until one of them is done
+const MAX_CONNECTIONS: usize = 64;
+
+/// How long a client may take to send its request or read the response, so a client that
+/// connects and goes quiet is let go
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What automatic deploys have to respect, just like `update` does
+#[derive(Debug, Clone)]
+pub struct DeployPolicy {
+    /// Exclusions, pins and opt-out labels, services they match are never pulled
+    pub rules: UpdateRules,
+    /// Signatures pulled images are verified against before their services are recreated
+    pub verify: Option<VerifyConfig>,
+}
+
+/// A received HTTP request
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    /// Headers by lowercase name
+    pub headers: BTreeMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+/// Shape of a webhook request body
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct HookPayload {
+    /// Services to pull and recreate
+    pub services: Vec<String>,
+    /// Set by `dsd-util daemon trigger`, webhooks from elsewhere leave it out
+    #[serde(default)]
+    pub trigger: Trigger,
+}
+
+/// Response to a webhook request
+#[derive(Debug, Clone, PartialEq)]
+pub struct HookResponse {
+    pub status: &'static str,
+    pub body: serde_json::Value,
+}
+
+impl HookResponse {
+    fn error(status: &'static str, message: impl Into<String>) -> Self {
+        HookResponse {
+            status,
+            body: serde_json::json!({ "error": message.into() }),
+        }
+    }
+}
+
+/// Reads a line of a request up to MAX_LINE_LENGTH, so a client cannot fill the memory with one
+fn read_line(reader: &mut impl BufRead, line: &mut String) -> anyhow::Result<usize> {
+    let read = Read::take(&mut *reader, MAX_LINE_LENGTH).read_line(line)?;
+    if read as u64 == MAX_LINE_LENGTH && !line.ends_with('\n') {
+        anyhow::bail!("Request line longer than {MAX_LINE_LENGTH} bytes");
+    }
+
+    Ok(read)
+}
+
+/// Reads an HTTP/1.1 request, including a body of up to MAX_BODY_SIZE given by Content-Length
+pub fn read_request(reader: &mut impl BufRead) -> anyhow::Result<HttpRequest> {
+    let mut request_line = String::new();
+    read_line(reader, &mut request_line).context("Failed to read request")?;
+
+    let mut parts = request_line.split_whitespace();
+    let (Some(method), Some(path)) = (parts.next(), parts.next()) else {
+        anyhow::bail!("Invalid request line: {}", request_line.trim());
+    };
+
+    let mut headers = BTreeMap::new();
+    for count in 0.. {
+        let mut line = String::new();
+        if read_line(reader, &mut line).context("Failed to read request headers")? == 0 {
+            break;
+        }
+
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+
+        if count == MAX_HEADERS {
+            anyhow::bail!("Request has more than {MAX_HEADERS} headers");
+        }
+        if let Some((name, value)) = line.split_once(':') {
+            headers.insert(name.trim().to_ascii_lowercase(), value.trim().to_string());
+        }
+    }
+
+    let length = match headers.get("content-length") {
+        Some(length) => length
+            .parse::<usize>()
+            .with_context(|| format!("Invalid Content-Length: {length}"))?,
+        None => 0,
+    };
+
+    if length > MAX_BODY_SIZE {
+        anyhow::bail!("Request body too large: {length} bytes");
+    }
+
+    let mut body = vec![0; length];
+    reader
+        .read_exact(&mut body)
+        .context("Failed to read request body")?;
+
+    Ok(HttpRequest {
+        method: method.to_string(),
+        path: path.to_string(),
+        headers,
+        body,
+    })
+}
+
+/// Checks a `sha256=<hex>` signature of the body against the secret in constant time
+pub fn verify_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+    let Some(hex) = signature.trim().strip_prefix(SIGNATURE_PREFIX) else {
+        return false;
+    };
+
+    let Some(expected) = decode_hex(hex) else {
+        return false;
+    };
+
+    HMAC::verify(body, secret.as_bytes(), &expected)
+}
+
+/// Signs a body the way webhook callers do, as `sha256=<hex>`
+pub fn sign(secret: &str, body: &[u8]) -> String {
+    let mac = HMAC::mac(body, secret.as_bytes());
+
+    let hex = mac
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect::<String>();
+
+    format!("{SIGNATURE_PREFIX}{hex}")
+}
+
+/// Decodes the 64 hex digits of a SHA-256 digest
+fn decode_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+
+    let mut digest = [0u8; 32];
+    for (index, byte) in digest.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[index * 2..index * 2 + 2], 16).ok()?;
+    }
+
+    Some(digest)
+}
+
+/// What a queued action does to the services of a stack
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    /// Pull new images and recreate the containers of the services
+    Deploy,
+    /// Only pull new images, leaving the recreate to the maintenance window
+    Pull,
+    /// Recreate the containers of the services from images pulled before
+    Apply,
+}
+
+/// Phase of an action with the given triggers.
+///
+/// Scheduled pulls of stacks with a maintenance window only pull, and the window applies what
+/// they pulled. A webhook or manual trigger merged into either deploys right away.
+pub fn action_phase(triggers: &BTreeSet<Trigger>, hook: Option<&HookConfig>) -> Phase {
+    let only = |trigger: Trigger| triggers.iter().all(|other| *other == trigger);
+
+    if only(Trigger::Maintenance) {
+        Phase::Apply
+    } else if only(Trigger::Schedule) && hook.is_some_and(|hook| hook.apply_window.is_some()) {
+        Phase::Pull
+    } else {
+        Phase::Deploy
+    }
+}
+
+/// Parses the pull and maintenance windows of a stack
+pub fn hook_windows(
+    stack: &str,
+    hook: &HookConfig,
+) -> anyhow::Result<(Option<TimeWindow>, Option<TimeWindow>)> {
+    let window = |window: &Option<String>, key: &str| {
+        window
+            .as_deref()
+            .map(TimeWindow::parse)
+            .transpose()
+            .with_context(|| format!("Invalid daemon.hooks.{stack}.{key}"))
+    };
+
+    Ok((
+        window(&hook.pull_window, "pull_window")?,
+        window(&hook.apply_window, "apply_window")?,
+    ))
+}
+
+/// Parses the window the daily report is sent in
+pub fn report_window(report: &ReportConfig) -> anyhow::Result<TimeWindow> {
+    TimeWindow::parse(&report.window).context("Invalid daemon.report.window")
+}
+
+/// Checks that all requested services of a stack are on its allow list, returning them sorted
+/// and without duplicates
+pub fn allowed_services(
+    config: &DaemonConfig,
+    stack: &str,
+    requested: &[String],
+) -> anyhow::Result<Vec<String>> {
+    let hook = config
+        .hooks
+        .get(stack)
+        .with_context(|| format!("Stack {stack} does not accept webhooks"))?;
+
+    if requested.is_empty() {
+        anyhow::bail!("No services given");
+    }
+
+    let mut services = requested.to_vec();
+    services.sort();
+    services.dedup();
+
+    let denied = services
+        .iter()
+        .filter(|service| !hook.services.contains(service))
+        .map(String::as_str)
+        .collect::<Vec<&str>>();
+
+    if !denied.is_empty() {
+        anyhow::bail!(
+            "Services not allowed for stack {stack}: {}",
+            denied.join(", ")
+        );
+    }
+
+    Ok(services)
+}
+
+/// Verifies and authorizes a webhook request, returning the stack, services and trigger of the
+/// action to queue or the response to send instead
+pub fn authorize(
+    request: &HttpRequest,
+    config: &DaemonConfig,
+    secret: &str,
+) -> Result<(String, Vec<String>, Trigger), HookResponse> {
+    let Some(stack) = request
+        .path
+        .strip_prefix(HOOKS_PATH_PREFIX)
+        .filter(|stack| !stack.is_empty() && !stack.contains('/'))
+    else {
+        return Err(HookResponse::error("404 Not Found", "Not Found"));
+    };
+
+    if request.method != "POST" {
+        return Err(HookResponse::error(
+            "405 Method Not Allowed",
+            "Only POST is allowed",
+        ));
+    }
+
+    // verify before parsing so unsigned bodies are never looked at
+    let signature = request
+        .headers
+        .get(SIGNATURE_HEADER)
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    if !verify_signature(secret, &request.body, signature) {
+        return Err(HookResponse::error("401 Unauthorized", "Invalid signature"));
+    }
+
+    let payload: HookPayload = serde_json::from_slice(&request.body)
+        .map_err(|e| HookResponse::error("400 Bad Request", format!("Invalid payload: {e}")))?;
+
+    let services = allowed_services(config, stack, &payload.services)
+        .map_err(|e| HookResponse::error("403 Forbidden", format!("{e:#}")))?;
+
+    Ok((stack.to_string(), services, payload.trigger))
+}
+
+/// Verifies a request for the queue state, which carries no body and is signed over its path
+/// instead, as it tells which stacks and services the daemon deploys
+pub fn authorize_status(request: &HttpRequest, secret: &str, now: u64) -> Result<(), HookResponse> {
+    // a signature over the path alone could be replayed forever once seen
+    let timestamp = request
+        .headers
+        .get(TIMESTAMP_HEADER)
+        .and_then(|timestamp| timestamp.parse::<u64>().ok());
+    let Some(timestamp) =
+        timestamp.filter(|timestamp| timestamp.abs_diff(now) <= MAX_TIMESTAMP_SKEW)
+    else {
+        return Err(HookResponse::error(
+            "401 Unauthorized",
+            "Missing or stale timestamp",
+        ));
+    };
+
+    let signature = request
+        .headers
+        .get(SIGNATURE_HEADER)
+        .map(String::as_str)
+        .unwrap_or_default();
+
+    if !verify_signature(secret, status_payload(timestamp).as_bytes(), signature) {
+        return Err(HookResponse::error("401 Unauthorized", "Invalid signature"));
+    }
+
+    Ok(())
+}
+
+/// What a status request signs, the time it was sent at and the path
+pub fn status_payload(timestamp: u64) -> String {
+    format!("{timestamp} {STATUS_PATH}")
+}
+
+fn unix_now() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default()
+}
+
+/// Whether reading a request failed because the client went quiet
+pub fn is_timeout(error: &anyhow::Error) -> bool {
+    error
+        .chain()
+        .filter_map(|cause| cause.downcast_ref::<std::io::Error>())
+        .any(|error| {
+            matches!(
+                error.kind(),
+                std::io::ErrorKind::WouldBlock | std::io::ErrorKind::TimedOut
+            )
+        })
+}
+
+/// Resolves the webhook secret, preferring DSD_UTIL_WEBHOOK_SECRET over the config file
+pub fn webhook_secret(config: &DaemonConfig) -> anyhow::Result<String> {
+    std::env::var(ENV_WEBHOOK_SECRET)
+        .ok()
+        .or_else(|| config.secret.clone())
+        .filter(|secret| !secret.is_empty())
+        .with_context(|| {
+            tr_args(
+                "No webhook secret configured, set daemon.secret in the config file or {env}",
+                &[("env", &ENV_WEBHOOK_SECRET)],
+            )
+        })
+}
+
+/// Receives webhooks and runs scheduled pulls, queueing the actions they trigger.
+///
+/// Scheduled pulls wait for the pull window of their stack, and with a maintenance window the
+/// containers are only recreated once it opens. Queued actions wait while the host is
+/// overloaded according to the guard.
+pub fn serve_webhooks(
+    listener: TcpListener,
+    config: DaemonConfig,
+    policy: DeployPolicy,
+    guard: LoadGuard,
+    secret: String,
+) -> anyhow::Result<()> {
+    let queue = Arc::new(ActionQueue::default());
+    let config = Arc::new(config);
+    let policy = Arc::new(policy);
+
+    for (stack, hook) in &config.hooks {
+        let (pull_window, apply_window) = hook_windows(stack, hook)?;
+
+        if let Some(interval) = hook.interval.filter(|_| !hook.services.is_empty()) {
+            let queue = Arc::clone(&queue);
+            let config = Arc::clone(&config);
+            let policy = Arc::clone(&policy);
+            let stack = stack.to_string();
+            let services = hook.services.clone();
+
+            std::thread::spawn(move || loop {
+                std::thread::sleep(Duration::from_secs(interval));
+                if let Some(window) = pull_window {
+                    std::thread::sleep(window.until_open(local_time()));
+                }
+
+                queue.enqueue(&stack, &services, Trigger::Schedule);
+                dispatch(&queue, &config, &policy, guard);
+            });
+        }
+
+        if let Some(window) = apply_window {
+            let queue = Arc::clone(&queue);
+            let config = Arc::clone(&config);
+            let policy = Arc::clone(&policy);
+            let stack = stack.to_string();
+
+            std::thread::spawn(move || loop {
+                std::thread::sleep(window.until_open(local_time()));
+
+                // pulls from before a restart of the daemon are applied too
+                let services = load_pulled()
+                    .ok()
+                    .and_then(|mut pulled| pulled.remove(&stack))
+                    .map(|pulled| pulled.services.into_iter().collect::<Vec<String>>())
+                    .unwrap_or_default();
+
+                if !services.is_empty() {
+                    queue.enqueue(&stack, &services, Trigger::Maintenance);
+                    dispatch(&queue, &config, &policy, guard);
+                }
+
+                // apply once per window, not again until it opens the next day
+                std::thread::sleep(window.until_close(local_time()));
+            });
+        }
+    }
+
+    if let Some(report) = &config.report {
+        let window = report_window(report)?;
+        let channels = report.channels.clone();
+
+        std::thread::spawn(move || loop {
+            std::thread::sleep(window.until_open(local_time()));
+
+            match send_daily_report(&channels) {
+                Ok(()) => log(Color::Green, &tr("Sent daily report")),
+                Err(error) => log(
+                    role_color(Role::Error),
+                    &tr_args(
+                        "Failed to send daily report: {error}",
+                        &[("error", &format!("{error:#}"))],
+                    ),
+                ),
+            }
+
+            // send once per window, not again until it opens the next day
+            std::thread::sleep(window.until_close(local_time()));
+        });
+    }
+
+    let secret: Arc<str> = Arc::from(secret);
+    let connections = Arc::new(AtomicUsize::new(0));
+
+    for stream in listener.incoming() {
+        let Ok(mut stream) = stream else {
+            continue;
+        };
+
+        if stream.set_read_timeout(Some(CONNECTION_TIMEOUT)).is_err()
+            || stream.set_write_timeout(Some(CONNECTION_TIMEOUT)).is_err()
+        {
+            continue;
+        }
+
+        // a flood of connections must not spawn threads without end
+        if connections.fetch_add(1, Ordering::SeqCst) >= MAX_CONNECTIONS {
+            connections.fetch_sub(1, Ordering::SeqCst);
+            let _ = write_response(
+                &mut stream,
+                &HookResponse::error("503 Service Unavailable", "Too many connections"),
+            );
+            continue;
+        }
+        let connection = ConnectionSlot(Arc::clone(&connections));
+
+        // a slow client only holds up its own request, not the ones after it
+        let config = Arc::clone(&config);
+        let policy = Arc::clone(&policy);
+        let secret = Arc::clone(&secret);
+        let queue = Arc::clone(&queue);
+        std::thread::spawn(move || {
+            // a broken connection should not bring the daemon down
+            let _ = handle_connection(stream, &config, &policy, &secret, &queue, guard);
+            drop(connection);
+        });
+    }
+
+    Ok(())
+}
+
+/// Answers a single request, queueing the action of an authorized webhook
+fn handle_connection(
+    mut stream: TcpStream,
+    config: &Arc<DaemonConfig>,
+    policy: &Arc<DeployPolicy>,
+    secret: &str,
+    queue: &Arc<ActionQueue>,
+    guard: LoadGuard,
+) -> anyhow::Result<()> {
+    let request = read_request(&mut BufReader::new(&stream));
+
+    let response = match request {
+        Ok(request) if request.method == "GET" && request.path == STATUS_PATH => {
+            match authorize_status(&request, secret, unix_now()) {
+                Ok(()) => HookResponse {
+                    status: "200 OK",
+                    body: serde_json::to_value(QueueState {
+                        pulled: load_pulled().unwrap_or_default(),
+                        ..queue.snapshot()
+                    })
+                    .context("Failed to serialize queue state")?,
+                },
+                Err(response) => response,
+            }
+        }
+        Ok(request) => match authorize(&request, config, secret) {
+            Ok((stack, services, trigger)) => {
+                let enqueued = queue.enqueue(&stack, &services, trigger);
+                dispatch(queue, config, policy, guard);
+
+                HookResponse {
+                    status: "202 Accepted",
+                    body: serde_json::json!({
+                        "stack": stack,
+                        "services": services,
+                        "status": match enqueued {
+                            Enqueued::Queued => "queued",
+                            Enqueued::Merged => "merged",
+                        },
+                    }),
+                }
+            }
+            Err(response) => {
+                log(
+                    role_color(Role::Error),
+                    &tr_args(
+                        "Rejected webhook for {path}: {reason}",
+                        &[
+                            ("path", &request.path),
+                            (
+                                "reason",
+                                &response.body["error"].as_str().unwrap_or_default(),
+                            ),
+                        ],
+                    ),
+                );
+                response
+            }
+        },
+        Err(e) if is_timeout(&e) => HookResponse::error("408 Request Timeout", "Request Timeout"),
+        Err(e) => HookResponse::error("400 Bad Request", format!("{e:#}")),
+    };
+
+    write_response(&mut stream, &response)
+}
+
+fn write_response(stream: &mut TcpStream, response: &HookResponse) -> anyhow::Result<()> {
+    let body = format!("{}\n", response.body);
+
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        response.status,
+        body.len()
+    )
+    .context("Failed to write response")?;
+
+    Ok(())
+}
+
+/// Counts a connection as handled until dropped, even when handling it panics
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Starts every queued action whose stack is idle, each on its own thread
+fn dispatch(
+    queue: &Arc<ActionQueue>,
+    config: &Arc<DaemonConfig>,
+    policy: &Arc<DeployPolicy>,
+    guard: LoadGuard,
+) {
+    while let Some(action) = queue.start_next() {
+        let queue = Arc::clone(queue);
+        let config = Arc::clone(config);
+        let policy = Arc::clone(policy);
+
+        std::thread::spawn(move || {
+            wait_for_host(&action.stack, &guard);
+
+            let error = run_action(&action, &config, &policy, &guard)
+                .err()
+                .map(|e| format!("{e:#}"));
+            queue.finish(&action.stack, error);
+
+            // the next action of the same stack may have been waiting for this one
+            dispatch(&queue, &config, &policy, guard);
+        });
+    }
+}
+
+/// Holds an action of a stack back while the host is overloaded, so a struggling host is not
+/// kicked with pulls and restarts while it is down
+fn wait_for_host(stack: &str, guard: &LoadGuard) {
+    let mut is_deferred = false;
+
+    while let Some(overload) = guard.check_host() {
+        if !is_deferred {
+            log(
+                role_color(Role::Warning),
+                &tr_args(
+                    "Deferring deploy of {stack}, host is overloaded: {reason}",
+                    &[("stack", &stack), ("reason", &overload)],
+                ),
+            );
+            is_deferred = true;
+        }
+
+        std::thread::sleep(guard.retry_interval);
+    }
+
+    if is_deferred {
+        log(
+            Color::Cyan,
+            &tr_args(
+                "Host recovered, resuming deploy of {stack}",
+                &[("stack", &stack)],
+            ),
+        );
+    }
+}
+
+/// Pulls and recreates the services of a queued action, or only one of both depending on its
+/// phase, logging the outcome
+fn run_action(
+    action: &QueuedAction,
+    config: &DaemonConfig,
+    policy: &DeployPolicy,
+    guard: &LoadGuard,
+) -> anyhow::Result<()> {
+    let services = action.services.iter().cloned().collect::<Vec<String>>();
+    let services_list = services.join(", ");
+    let stack = &action.stack;
+    let hook = config.hooks.get(stack);
+    let phase = action_phase(&action.triggers, hook);
+
+    let (starting, finished, failed) = match phase {
+        Phase::Deploy => (
+            "Deploying {services} of {stack}",
+            "Deployed {services} of {stack}",
+            "Failed to deploy {services} of {stack}: {error}",
+        ),
+        Phase::Pull => (
+            "Pre-pulling {services} of {stack}",
+            "Pulled {services} of {stack}, recreating them in the maintenance window {window}",
+            "Failed to pre-pull {services} of {stack}: {error}",
+        ),
+        Phase::Apply => (
+            "Applying pulled images of {services} of {stack}",
+            "Applied pulled images of {services} of {stack}",
+            "Failed to apply pulled images of {services} of {stack}: {error}",
+        ),
+    };
+
+    log(
+        Color::Cyan,
+        &tr_args(starting, &[("services", &services_list), ("stack", stack)]),
+    );
+
+    let result = stack_compose_info(stack).and_then(|compose_info| match phase {
+        Phase::Deploy => {
+            let services = pull(stack, &compose_info, &services, policy, guard)?;
+            recreate(stack, &compose_info, &services)?;
+
+            // services deployed in between need not wait for the maintenance window anymore
+            if hook.is_some_and(|hook| hook.apply_window.is_some()) {
+                update_pulled(|pulled| remove_applied(pulled, stack, &services))?;
+            }
+            Ok(())
+        }
+        Phase::Pull => {
+            let services = pull(stack, &compose_info, &services, policy, guard)?;
+            if services.is_empty() {
+                return Ok(());
+            }
+            update_pulled(|pulled| add_pulled(pulled, stack, &services, &get_timestamp()))
+        }
+        Phase::Apply => {
+            recreate(stack, &compose_info, &services)?;
+            update_pulled(|pulled| remove_applied(pulled, stack, &services))
+        }
+    });
+
+    match &result {
+        Ok(()) => log(
+            Color::Green,
+            &tr_args(
+                finished,
+                &[
+                    ("services", &services_list),
+                    ("stack", stack),
+                    (
+                        "window",
+                        &hook
+                            .and_then(|hook| hook.apply_window.as_deref())
+                            .unwrap_or_default(),
+                    ),
+                ],
+            ),
+        ),
+        Err(e) => log(
+            role_color(Role::Error),
+            &tr_args(
+                failed,
+                &[
+                    ("services", &services_list),
+                    ("stack", stack),
+                    ("error", &format!("{e:#}")),
+                ],
+            ),
+        ),
+    }
+
+    result
+}
+
+/// Compose project of a stack, read from one of its containers
+fn stack_compose_info(stack: &str) -> anyhow::Result<ComposeInfo> {
+    let container = get_containers_from_stack(stack)?
+        .into_iter()
+        .next()
+        .with_context(|| format!("No containers found in stack: {stack}"))?;
+
+    get_compose_info(&container)?
+        .with_context(|| format!("Stack {stack} is not managed by docker compose"))
+}
+
+/// Services automatic deploys may pull, leaving out those with a container `update` would
+/// skip, such as excluded services, pinned images and containers opting out by label
+fn updatable_services(
+    stack: &str,
+    services: &[String],
+    rules: &UpdateRules,
+) -> anyhow::Result<Vec<String>> {
+    let mut skipped: Vec<String> = vec![];
+    for (container, service) in get_stack_services(stack)?
+        .into_iter()
+        .filter(|(_, service)| services.contains(service))
+    {
+        let labels = get_container_labels(&container)?;
+        let image = get_container_image(&container)?;
+
+        if let Some(exclusion) = rules.check(&container, &labels, &image) {
+            log(
+                role_color(Role::Warning),
+                &tr_args(
+                    "Skipping {container}: {reason}",
+                    &[("container", &container), ("reason", &exclusion)],
+                ),
+            );
+            skipped.push(service);
+        }
+    }
+
+    Ok(services
+        .iter()
+        .filter(|service| !skipped.contains(service))
+        .cloned()
+        .collect())
+}
+
+/// Pulls new images of the services the policy allows updating, if the disk has room for them,
+/// returning the services pulled
+fn pull(
+    stack: &str,
+    compose_info: &ComposeInfo,
+    services: &[String],
+    policy: &DeployPolicy,
+    guard: &LoadGuard,
+) -> anyhow::Result<Vec<String>> {
+    let services = updatable_services(stack, services, &policy.rules)?;
+    // compose pulls every service of the project when none are named
+    if services.is_empty() {
+        return Ok(services);
+    }
+
+    // images with the id of the image their containers run, to tell whether the pull changed them
+    let mut images: Vec<(String, String)> = vec![];
+    for (container, _) in get_stack_services(stack)?
+        .into_iter()
+        .filter(|(_, service)| services.contains(service))
+    {
+        let image = get_container_image(&container)?;
+        if !images.iter().any(|(other, _)| same_image(other, &image)) {
+            images.push((image, get_container_image_id(&container)?));
+        }
+    }
+
+    // a pull failing halfway through can leave the host with a full disk
+    let names = images
+        .iter()
+        .map(|(image, _)| image.to_string())
+        .collect::<Vec<String>>();
+    if let Some(shortage) = guard.check_pull_space(&names) {
+        anyhow::bail!("Not enough disk space to pull images of {stack}: {shortage}");
+    }
+
+    let output = compose_command(compose_info)
+        .args(["pull", "--quiet"])
+        .args(&services)
+        .output()
+        .with_context(|| format!("Failed to pull images of {stack}"))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to pull images of {stack}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    if let Some(verify) = &policy.verify {
+        verify_pulled(&images, verify)?;
+    }
+
+    Ok(services)
+}
+
+/// Verifies the signatures of the images a pull changed, like `update` does, pointing the tags
+/// of refused images back at what the containers run
+fn verify_pulled(images: &[(String, String)], config: &VerifyConfig) -> anyhow::Result<()> {
+    let mut rejected: Vec<String> = vec![];
+
+    for (image, running_id) in images
+        .iter()
+        .filter(|(image, _)| should_verify(config, image))
+    {
+        let (current_id, digest) =
+            get_image_id(image)?.with_context(|| format!("No such image: {image}"))?;
+        let result = UpdateResult {
+            image: image.to_string(),
+            previous_id: Some(running_id.to_string()),
+            current_id,
+            digest,
+        };
+        if !result.is_updated() {
+            continue;
+        }
+
+        match verify_image(config, &result) {
+            Ok(()) => log(
+                Color::Green,
+                &tr_args("Signature verified: {image}", &[("image", image)]),
+            ),
+            Err(e) if config.mode == VerifyMode::Warn => log(
+                role_color(Role::Warning),
+                &tr_args("WARNING: {error}", &[("error", &e)]),
+            ),
+            Err(e) => {
+                log(role_color(Role::Error), &e.to_string());
+                if let Err(e) = restore_previous_image(&result) {
+                    log(role_color(Role::Error), &format!("{e:#}"));
+                }
+                rejected.push(image.to_string());
+            }
+        }
+    }
+
+    if !rejected.is_empty() {
+        anyhow::bail!(tr_args(
+            "Refused to deploy unverified images: {images}",
+            &[("images", &rejected.join(", "))]
+        ));
+    }
+
+    Ok(())
+}
+
+/// Recreates only the containers of the services, from the images on the host
+fn recreate(stack: &str, compose_info: &ComposeInfo, services: &[String]) -> anyhow::Result<()> {
+    // compose recreates every service of the project when none are named
+    if services.is_empty() {
+        return Ok(());
+    }
+
+    let output = compose_command(compose_info)
+        .args(["up", "-d", "--no-deps"])
+        .args(services)
+        .output()
+        .with_context(|| format!("Failed to recreate services of {stack}"))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to recreate services of {stack}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Gets the queue state of a running daemon, signing the request with the webhook secret
+pub fn fetch_status(address: &str, secret: &str) -> anyhow::Result<QueueState> {
+    let timestamp = unix_now();
+    let output = Command::new(CURL)
+        .args(["-sS", "-f", "--max-time", CLIENT_TIMEOUT])
+        .args(["-H", &format!("X-Dsd-Timestamp: {timestamp}")])
+        .args([
+            "-H",
+            &format!(
+                "X-Hub-Signature-256: {}",
+                sign(secret, status_payload(timestamp).as_bytes())
+            ),
+        ])
+        .arg(format!("http://{address}{STATUS_PATH}"))
+        .output()
+        .context("Failed to reach the daemon")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to reach the daemon at {address}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    serde_json::from_slice(&output.stdout).context("Failed to parse daemon status")
+}
+
+/// Asks a running daemon to pull and recreate services, signing the request like a webhook
+pub fn send_trigger(
+    address: &str,
+    secret: &str,
+    stack: &str,
+    services: Vec<String>,
+) -> anyhow::Result<serde_json::Value> {
+    let body = serde_json::to_string(&HookPayload {
+        services,
+        trigger: Trigger::Manual,
+    })?;
+
+    // without -f, so the error message of a rejected trigger can be shown
+    let output = Command::new(CURL)
+        .args(["-sS", "--max-time", CLIENT_TIMEOUT, "-X", "POST"])
+        .args(["-H", "Content-Type: application/json"])
+        .args([
+            "-H",
+            &format!("X-Hub-Signature-256: {}", sign(secret, body.as_bytes())),
+        ])
+        .args(["--data-binary", &body])
+        .arg(format!("http://{address}{HOOKS_PATH_PREFIX}{stack}"))
+        .output()
+        .context("Failed to reach the daemon")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to reach the daemon at {address}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let response: serde_json::Value =
+        serde_json::from_slice(&output.stdout).context("Failed to parse daemon response")?;
+
+    if let Some(error) = response["error"].as_str() {
+        anyhow::bail!("The daemon rejected the trigger: {error}");
+    }
+
+    Ok(response)
+}
+
+/// Prints a timestamped line about a webhook
+fn log(color: Color, message: &str) {
+    let line = format!("[{}] {message}", get_timestamp());
+
+    if is_terminal() {
+        color_println(color, &line);
+    } else {
+        println!("{line}");
+    }
+}
@@ -0,0 +1,97 @@
+use crate::ci::is_ci;
+use crate::config::state_dir;
+use anyhow::Context;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Image to deploy for a compose service
+#[derive(Debug, Clone, PartialEq)]
+pub struct ImageOverride {
+    pub service: String,
+    pub image: String,
+}
+
+/// Parses an image override given as service=image, e.g. web=ghcr.io/me/web:sha-abc
+pub fn parse_image_override(input: &str) -> anyhow::Result<ImageOverride> {
+    let (service, image) = input
+        .split_once('=')
+        .with_context(|| format!("Invalid image override: {input}, expected service=image"))?;
+
+    if service.is_empty() || image.is_empty() {
+        anyhow::bail!("Invalid image override: {input}, expected service=image");
+    }
+
+    Ok(ImageOverride {
+        service: service.to_string(),
+        image: image.to_string(),
+    })
+}
+
+/// Renders a compose override file replacing the images of services
+pub fn render_override(overrides: &[ImageOverride]) -> anyhow::Result<String> {
+    let services = overrides
+        .iter()
+        .map(|image_override| {
+            (
+                image_override.service.to_string(),
+                serde_json::json!({ "image": image_override.image }),
+            )
+        })
+        .collect::<serde_json::Map<String, serde_json::Value>>();
+
+    serde_yaml::to_string(&serde_json::json!({ "services": services }))
+        .context("Failed to render compose override")
+}
+
+/// Writes the compose override of a project to the state directory, returning its path.
+///
+/// The file is kept so later recreates of the project, which reuse its compose files, keep the
+/// deployed images.
+pub fn write_override(project: &str, overrides: &[ImageOverride]) -> anyhow::Result<PathBuf> {
+    let dir = state_dir()?.join("deploy");
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+    let path = dir.join(format!("{project}.override.yml"));
+    std::fs::write(&path, render_override(overrides)?)
+        .with_context(|| format!("Failed to write compose override: {}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Determine if GitHub Actions workflow annotations should be printed
+pub fn annotations_enabled() -> bool {
+    is_ci() || std::env::var("GITHUB_ACTIONS").is_ok_and(|value| value == "true")
+}
+
+/// Formats a GitHub Actions workflow annotation such as `::notice title=Deployed::web`
+pub fn github_annotation(level: &str, title: &str, message: &str) -> String {
+    let escape_data = |value: &str| {
+        value
+            .replace('%', "%25")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A")
+    };
+    let title = escape_data(title).replace(':', "%3A").replace(',', "%2C");
+
+    format!("::{level} title={title}::{}", escape_data(message))
+}
+
+/// Appends step outputs to the file named by GITHUB_OUTPUT, if running in a workflow
+pub fn write_github_outputs(outputs: &[(&str, String)]) -> anyhow::Result<()> {
+    let Some(path) = std::env::var_os("GITHUB_OUTPUT") else {
+        return Ok(());
+    };
+
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .context("Failed to open GITHUB_OUTPUT")?;
+
+    for (name, value) in outputs {
+        writeln!(file, "{name}={value}").context("Failed to write GITHUB_OUTPUT")?;
+    }
+
+    Ok(())
+}
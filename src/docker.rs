@@ -0,0 +1,273 @@
+//! Reusable docker helpers behind the dsd-util commands.
+//!
+//! Everything here shells out to the docker cli, so it works against whatever daemon the cli is
+//! configured for.
+//!
+//! ```no_run
+//! use dsd_util::docker::{list_containers, Logger, Stack};
+//!
+//! # fn main() -> anyhow::Result<()> {
+//! for container in list_containers()? {
+//!     println!("{} ({})", container.name, container.image);
+//! }
+//!
+//! let stack = Stack::find("nextcloud")?;
+//! for stats in stack.stats()? {
+//!     println!("{} {}% {}", stats.container_name, stats.cpu_percent, stats.memory_used);
+//! }
+//!
+//! for line in Logger::new(stack.container_names()).tail(10).follow()? {
+//!     println!("{line}");
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+use crate::commands::DOCKER;
+use crate::error::check_output;
+use crate::parsers::parse_container_list;
+use crate::printer::is_deterministic;
+use crate::ship::ShipTarget;
+use crate::utils::{
+    get_container_names, get_stats, inspect_containers, LABEL_COMPOSE_PROJECT,
+    LABEL_COMPOSE_SERVICE,
+};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+pub use crate::graph::{build_stack_graph, sort_by_dependencies, StackGraph};
+pub use crate::logger::{LogAlert, LogFormat, LogMultiplexer, LogWindow, TimestampMode};
+pub use crate::utils::{InspectData, StatsData};
+
+/// A running docker container
+#[derive(Debug, Clone, PartialEq)]
+pub struct Container {
+    pub id: String,
+    pub name: String,
+    pub image: String,
+    /// Compose project the container belongs to
+    pub stack: Option<String>,
+    /// Compose service the container was created for
+    pub service: Option<String>,
+}
+
+impl Container {
+    /// Gets current cpu and memory usage of the container
+    pub fn stats(&self) -> anyhow::Result<StatsData> {
+        get_stats(std::slice::from_ref(&self.name))?
+            .pop()
+            .with_context(|| format!("No stats for {}", self.name))
+    }
+
+    /// Gets status, health, uptime and restart count of the container
+    pub fn inspect(&self) -> anyhow::Result<InspectData> {
+        inspect_containers(std::slice::from_ref(&self.name))?
+            .pop()
+            .with_context(|| format!("Failed to inspect {}", self.name))
+    }
+}
+
+/// A compose project and its running containers
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stack {
+    pub name: String,
+    pub containers: Vec<Container>,
+}
+
+impl Stack {
+    /// Lists all stacks with running containers
+    pub fn list() -> anyhow::Result<Vec<Stack>> {
+        let mut stacks: BTreeMap<String, Vec<Container>> = BTreeMap::new();
+
+        for container in list_containers()? {
+            if let Some(stack) = container.stack.clone() {
+                stacks.entry(stack).or_default().push(container);
+            }
+        }
+
+        Ok(stacks
+            .into_iter()
+            .map(|(name, containers)| Stack { name, containers })
+            .collect())
+    }
+
+    /// Finds a stack by its compose project name
+    pub fn find(name: &str) -> anyhow::Result<Stack> {
+        let containers =
+            list_containers_filtered(&format!("label={LABEL_COMPOSE_PROJECT}={name}"))?;
+
+        if containers.is_empty() {
+            anyhow::bail!("No containers found in stack: {name}");
+        }
+
+        Ok(Stack {
+            name: name.to_string(),
+            containers,
+        })
+    }
+
+    /// Names of the containers in the stack
+    pub fn container_names(&self) -> Vec<String> {
+        self.containers
+            .iter()
+            .map(|container| container.name.to_string())
+            .collect()
+    }
+
+    /// Gets current cpu and memory usage of all containers in the stack
+    pub fn stats(&self) -> anyhow::Result<Vec<StatsData>> {
+        Ok(get_stats(&self.container_names())?)
+    }
+
+    /// Inspects all containers in the stack
+    pub fn inspect(&self) -> anyhow::Result<Vec<InspectData>> {
+        Ok(inspect_containers(&self.container_names())?)
+    }
+
+    /// Builds the container/network/volume/depends_on graph of the stack
+    pub fn graph(&self) -> anyhow::Result<StackGraph> {
+        build_stack_graph(&self.name)
+    }
+}
+
+/// Lists running docker containers
+pub fn list_containers() -> anyhow::Result<Vec<Container>> {
+    list_containers_filtered("status=running")
+}
+
+/// Lists docker containers, including stopped ones
+pub fn list_all_containers() -> anyhow::Result<Vec<Container>> {
+    query_containers(&["--all"])
+}
+
+/// Lists docker containers matching a `docker ps --filter`
+fn list_containers_filtered(filter: &str) -> anyhow::Result<Vec<Container>> {
+    query_containers(&["--filter", filter])
+}
+
+/// Lists docker containers with `docker ps` and the given arguments
+fn query_containers(args: &[&str]) -> anyhow::Result<Vec<Container>> {
+    let output = Command::new(DOCKER)
+        .arg("ps")
+        .args(args)
+        .arg("--format")
+        .arg(format!(
+            "{{{{.ID}}}}\t{{{{.Names}}}}\t{{{{.Image}}}}\t{{{{.Label \"{LABEL_COMPOSE_PROJECT}\"}}}}\t{{{{.Label \"{LABEL_COMPOSE_SERVICE}\"}}}}"
+        ))
+        .output();
+    // keeps the error typed, so a missing daemon or socket permission is told apart
+    let output = check_output("ps", output)?;
+
+    let containers = String::from_utf8(output.stdout).context("Failed to parse container list")?;
+    let mut containers = parse_container_list(&containers)?;
+
+    // docker lists the newest first, which changes whenever a container is recreated
+    if is_deterministic() {
+        containers.sort_by(|a, b| a.name.cmp(&b.name));
+    }
+
+    Ok(containers)
+}
+
+/// Follows the logs of several containers at once, merged into a single stream of lines
+#[derive(Debug, Clone)]
+pub struct Logger {
+    containers: Vec<String>,
+    are_ids: bool,
+    window: LogWindow,
+    grep: Option<String>,
+    alert: Option<LogAlert>,
+    ship: Vec<ShipTarget>,
+    color: bool,
+    format: LogFormat,
+}
+
+impl Logger {
+    /// Creates a logger for containers given by name or id
+    pub fn new(containers: Vec<String>) -> Self {
+        Logger {
+            containers,
+            are_ids: false,
+            window: LogWindow::default(),
+            grep: None,
+            alert: None,
+            ship: vec![],
+            color: false,
+            format: LogFormat::default(),
+        }
+    }
+
+    /// Looks up container names for the line prefix when containers are given by id
+    pub fn container_ids(mut self, are_ids: bool) -> Self {
+        self.are_ids = are_ids;
+        self
+    }
+
+    /// Sets the number of lines to show from the end of the logs before following
+    pub fn tail(mut self, tail: u32) -> Self {
+        self.window.tail = Some(tail);
+        self
+    }
+
+    /// Sets which lines to show, including since and until when and whether to keep following
+    pub fn window(mut self, window: LogWindow) -> Self {
+        self.window = window;
+        self
+    }
+
+    /// Only passes on lines containing this text, ignoring case
+    pub fn grep(mut self, grep: impl Into<String>) -> Self {
+        self.grep = Some(grep.into());
+        self
+    }
+
+    /// Rings the bell and raises a desktop notification for lines matching a pattern
+    pub fn alert(mut self, alert: LogAlert) -> Self {
+        self.alert = Some(alert);
+        self
+    }
+
+    /// Forwards lines to Loki or syslog endpoints besides returning them
+    pub fn ship(mut self, targets: Vec<ShipTarget>) -> Self {
+        self.ship = targets;
+        self
+    }
+
+    /// Colors the timestamp and container name prefix of each line
+    pub fn color(mut self, color: bool) -> Self {
+        self.color = color;
+        self
+    }
+
+    /// Gives each container name its own color, on by default, instead of all being green
+    pub fn container_colors(mut self, container_colors: bool) -> Self {
+        self.format.container_colors = container_colors;
+        self
+    }
+
+    /// Sets how lines are rendered, including whether container names get their own color
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Starts following the logs, returning the merged lines as they arrive
+    pub fn follow(self) -> anyhow::Result<LogMultiplexer> {
+        let containers = if self.are_ids {
+            get_container_names(&self.containers)?
+        } else {
+            self.containers
+        };
+
+        LogMultiplexer::spawn(
+            &containers,
+            &self.window,
+            self.color,
+            self.format,
+            self.grep,
+            self.alert,
+            self.ship,
+        )
+    }
+}
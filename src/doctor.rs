@@ -0,0 +1,240 @@
+use crate::commands::DOCKER;
+use crate::error::{check_output, DsdError};
+use crate::sbom::compare_versions;
+use std::cmp::Ordering;
+use std::fmt;
+use std::process::Command;
+
+/// Oldest Docker Engine known to work, earlier ones lack the compose plugin and label filters
+pub const MIN_DOCKER_VERSION: &str = "20.10";
+/// Oldest compose plugin known to work, compose v1 has no `config --format json`
+pub const MIN_COMPOSE_VERSION: &str = "2.0";
+
+/// Something about the docker setup that keeps commands from working
+#[derive(Debug, Clone, PartialEq)]
+pub enum Problem {
+    DockerMissing,
+    SocketPermission(String),
+    DaemonUnreachable(String),
+    DockerTooOld(String),
+    ComposeMissing,
+    ComposeTooOld(String),
+    /// The user the daemon switches to is missing or may not use docker
+    DaemonUser(String),
+    /// Docker failed in a way none of the other problems explain
+    Other(String),
+}
+
+impl Problem {
+    /// What to do about the problem
+    pub fn hint(&self) -> String {
+        match self {
+            Problem::DockerMissing => {
+                "install Docker Engine (https://docs.docker.com/engine/install/) and make sure docker is in PATH".to_string()
+            }
+            Problem::SocketPermission(_) => {
+                "add the user to the docker group with `sudo usermod -aG docker $USER`, then log out and back in".to_string()
+            }
+            Problem::DaemonUnreachable(_) => {
+                "start the daemon with `sudo systemctl start docker`, or check DOCKER_HOST and the current docker context".to_string()
+            }
+            Problem::DockerTooOld(_) => {
+                format!("upgrade Docker Engine to {MIN_DOCKER_VERSION} or newer")
+            }
+            Problem::ComposeMissing => {
+                "install the compose plugin, e.g. the docker-compose-plugin package (https://docs.docker.com/compose/install/linux/)".to_string()
+            }
+            Problem::ComposeTooOld(_) => {
+                format!("upgrade the compose plugin to {MIN_COMPOSE_VERSION} or newer")
+            }
+            Problem::DaemonUser(_) => {
+                "set daemon.user to an existing user in the docker group, e.g. with `sudo usermod -aG docker <user>`".to_string()
+            }
+            Problem::Other(_) => "check that `docker version` works for this user".to_string(),
+        }
+    }
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Problem::DockerMissing => write!(f, "docker is not installed or not in PATH"),
+            Problem::SocketPermission(message) => {
+                write!(f, "Permission denied on the docker socket: {message}")
+            }
+            Problem::DaemonUnreachable(message) => {
+                write!(
+                    f,
+                    "Docker daemon is not running or not reachable: {message}"
+                )
+            }
+            Problem::DockerTooOld(version) => write!(
+                f,
+                "Docker Engine {version} is too old, {MIN_DOCKER_VERSION} or newer is required"
+            ),
+            Problem::ComposeMissing => write!(f, "The docker compose plugin is not installed"),
+            Problem::ComposeTooOld(version) => write!(
+                f,
+                "docker compose {version} is too old, {MIN_COMPOSE_VERSION} or newer is required"
+            ),
+            Problem::DaemonUser(message) => write!(f, "{message}"),
+            Problem::Other(message) => write!(f, "{message}"),
+        }
+    }
+}
+
+impl std::error::Error for Problem {}
+
+impl From<DsdError> for Problem {
+    fn from(error: DsdError) -> Self {
+        match error {
+            DsdError::DockerNotFound => Problem::DockerMissing,
+            DsdError::PermissionDenied(message) => Problem::SocketPermission(message),
+            DsdError::DaemonUnavailable(message) => Problem::DaemonUnreachable(message),
+            error => Problem::Other(error.to_string()),
+        }
+    }
+}
+
+fn is_missing_compose(message: &str) -> bool {
+    let message = message.to_ascii_lowercase();
+    message.contains("'compose' is not a docker command") || message.contains("unknown command")
+}
+
+/// Whether a version reported by docker is at least the required one
+pub fn is_supported(version: &str, required: &str) -> bool {
+    compare_versions(version.trim().trim_start_matches('v'), required) != Ordering::Less
+}
+
+/// Outcome of one check of the docker setup
+#[derive(Debug, Clone, PartialEq)]
+pub enum CheckStatus {
+    /// Passed, with what was found, such as a version
+    Passed(String),
+    Failed(Problem),
+    /// Not run, as an earlier check failed
+    Skipped,
+}
+
+/// A check of the docker setup and its outcome
+#[derive(Debug, Clone, PartialEq)]
+pub struct Check {
+    pub name: &'static str,
+    pub status: CheckStatus,
+}
+
+/// Checks everything dsd-util needs from docker, in the order they depend on each other
+pub fn run_checks() -> Vec<Check> {
+    let mut checks = vec![];
+
+    let binary = client_version();
+    let binary_found = binary.is_ok();
+    checks.push(Check {
+        name: "docker binary",
+        status: status(binary),
+    });
+
+    if !binary_found {
+        for name in ["socket permissions", "docker daemon", "compose plugin"] {
+            checks.push(Check {
+                name,
+                status: CheckStatus::Skipped,
+            });
+        }
+        return checks;
+    }
+
+    checks.extend(daemon_checks(server_version()));
+    checks.push(Check {
+        name: "compose plugin",
+        status: status(compose_version()),
+    });
+
+    checks
+}
+
+/// Socket permission and daemon checks from the outcome of asking the daemon for its version
+pub fn daemon_checks(server: Result<String, Problem>) -> [Check; 2] {
+    let (socket, daemon) = match server {
+        Err(problem @ Problem::SocketPermission(_)) => {
+            (CheckStatus::Failed(problem), CheckStatus::Skipped)
+        }
+        server => (
+            CheckStatus::Passed(docker_host()),
+            status(server.map(|version| format!("Docker Engine {version}"))),
+        ),
+    };
+
+    [
+        Check {
+            name: "socket permissions",
+            status: socket,
+        },
+        Check {
+            name: "docker daemon",
+            status: daemon,
+        },
+    ]
+}
+
+fn status(result: Result<String, Problem>) -> CheckStatus {
+    match result {
+        Ok(found) => CheckStatus::Passed(found),
+        Err(problem) => CheckStatus::Failed(problem),
+    }
+}
+
+/// Fails with the first problem keeping commands from talking to docker and compose
+pub fn preflight() -> Result<(), Problem> {
+    server_version()?;
+    compose_version()?;
+
+    Ok(())
+}
+
+/// Where the docker client connects to
+fn docker_host() -> String {
+    std::env::var("DOCKER_HOST").unwrap_or_else(|_| "unix:///var/run/docker.sock".to_string())
+}
+
+fn docker_output(command: &str, args: &[&str]) -> Result<String, DsdError> {
+    let output = check_output(command, Command::new(DOCKER).args(args).output())?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Version of the docker client, which does not need the daemon
+fn client_version() -> Result<String, Problem> {
+    Ok(docker_output("--version", &["--version"])?)
+}
+
+/// Version of the Docker Engine, failing when the daemon cannot be reached or is too old
+fn server_version() -> Result<String, Problem> {
+    let version = docker_output("version", &["version", "--format", "{{.Server.Version}}"])?;
+
+    if !is_supported(&version, MIN_DOCKER_VERSION) {
+        return Err(Problem::DockerTooOld(version));
+    }
+
+    Ok(version)
+}
+
+/// Version of the compose plugin, failing when it is missing or too old
+fn compose_version() -> Result<String, Problem> {
+    let version =
+        docker_output("compose version", &["compose", "version", "--short"]).map_err(|error| {
+            match error {
+                // docker reports plugins it does not know as commands it does not know
+                DsdError::Docker { message, .. } if is_missing_compose(&message) => {
+                    Problem::ComposeMissing
+                }
+                error => Problem::from(error),
+            }
+        })?;
+
+    if !is_supported(&version, MIN_COMPOSE_VERSION) {
+        return Err(Problem::ComposeTooOld(version));
+    }
+
+    Ok(version)
+}
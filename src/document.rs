@@ -0,0 +1,172 @@
+use crate::table::{csv_table, markdown_table};
+use std::fmt::Write;
+use std::path::Path;
+
+/// File formats documents can be written in
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum DocumentFormat {
+    #[value(name = "md")]
+    Markdown,
+    /// Standalone HTML page
+    Html,
+    /// Only the tables, as comma separated values to open in a spreadsheet
+    Csv,
+}
+
+impl DocumentFormat {
+    /// Picks HTML for files ending in .html or .htm, CSV for .csv and Markdown otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("html" | "htm") => DocumentFormat::Html,
+            Some("csv") => DocumentFormat::Csv,
+            _ => DocumentFormat::Markdown,
+        }
+    }
+}
+
+/// Part of a document
+#[derive(Debug, Clone, PartialEq)]
+pub enum Block {
+    Heading(String),
+    Paragraph(String),
+    List(Vec<String>),
+    Table {
+        headers: Vec<String>,
+        rows: Vec<Vec<String>>,
+    },
+}
+
+/// Text meant to be read by people, such as a report, that renders to Markdown or HTML
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document {
+    pub title: String,
+    pub blocks: Vec<Block>,
+}
+
+impl Document {
+    pub fn new(title: impl Into<String>) -> Self {
+        Document {
+            title: title.into(),
+            blocks: vec![],
+        }
+    }
+
+    pub fn heading(&mut self, text: impl Into<String>) {
+        self.blocks.push(Block::Heading(text.into()));
+    }
+
+    pub fn paragraph(&mut self, text: impl Into<String>) {
+        self.blocks.push(Block::Paragraph(text.into()));
+    }
+
+    pub fn list(&mut self, items: Vec<String>) {
+        self.blocks.push(Block::List(items));
+    }
+
+    pub fn table(&mut self, headers: &[&str], rows: Vec<Vec<String>>) {
+        self.blocks.push(Block::Table {
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            rows,
+        });
+    }
+
+    pub fn render(&self, format: DocumentFormat) -> String {
+        match format {
+            DocumentFormat::Markdown => self.to_markdown(),
+            DocumentFormat::Html => self.to_html(),
+            DocumentFormat::Csv => self.to_csv(),
+        }
+    }
+
+    /// Tables of the document separated by blank lines, leaving out text CSV has no place for
+    pub fn to_csv(&self) -> String {
+        self.blocks
+            .iter()
+            .filter_map(|block| match block {
+                Block::Table { headers, rows } => Some(csv_table(headers, rows)),
+                _ => None,
+            })
+            .collect::<Vec<String>>()
+            .join("\n")
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let mut markdown = format!("# {}\n", self.title);
+
+        for block in &self.blocks {
+            markdown.push('\n');
+            match block {
+                Block::Heading(text) => {
+                    let _ = writeln!(markdown, "## {text}");
+                }
+                Block::Paragraph(text) => {
+                    let _ = writeln!(markdown, "{text}");
+                }
+                Block::List(items) => {
+                    for item in items {
+                        let _ = writeln!(markdown, "- {item}");
+                    }
+                }
+                Block::Table { headers, rows } => {
+                    markdown.push_str(&markdown_table(headers, rows));
+                }
+            }
+        }
+
+        markdown
+    }
+
+    pub fn to_html(&self) -> String {
+        let title = escape_html(&self.title);
+        let mut html = format!(
+            "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{title}</title>\n<style>{STYLE}</style>\n</head>\n<body>\n<h1>{title}</h1>\n"
+        );
+
+        for block in &self.blocks {
+            match block {
+                Block::Heading(text) => {
+                    let _ = writeln!(html, "<h2>{}</h2>", escape_html(text));
+                }
+                Block::Paragraph(text) => {
+                    let _ = writeln!(html, "<p>{}</p>", escape_html(text));
+                }
+                Block::List(items) => {
+                    html.push_str("<ul>\n");
+                    for item in items {
+                        let _ = writeln!(html, "<li>{}</li>", escape_html(item));
+                    }
+                    html.push_str("</ul>\n");
+                }
+                Block::Table { headers, rows } => {
+                    html.push_str("<table>\n<tr>");
+                    for header in headers {
+                        let _ = write!(html, "<th>{}</th>", escape_html(header));
+                    }
+                    html.push_str("</tr>\n");
+                    for cells in rows {
+                        html.push_str("<tr>");
+                        for cell in cells {
+                            let _ = write!(html, "<td>{}</td>", escape_html(cell));
+                        }
+                        html.push_str("</tr>\n");
+                    }
+                    html.push_str("</table>\n");
+                }
+            }
+        }
+
+        html.push_str("</body>\n</html>\n");
+        html
+    }
+}
+
+/// Keeps tables readable in mail clients that strip external stylesheets
+const STYLE: &str = "body{font-family:sans-serif;margin:2em}table{border-collapse:collapse}th,td{border:1px solid #ccc;padding:4px 8px;text-align:left}";
+
+/// Escapes text for HTML element content and attribute values
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
@@ -0,0 +1,298 @@
+use crate::plan::{
+    changed_env, inspect_image_config, inspect_stack_containers, load_compose_config,
+};
+use crate::utils::{LABEL_COMPOSE_CONFIG_FILES, LABEL_COMPOSE_WORKING_DIR};
+use anyhow::Context;
+use serde_json::Value;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Difference between what the compose file declares for a service and its container
+#[derive(Debug, Clone, PartialEq)]
+pub enum Drift {
+    /// The container runs another image than the file declares, such as an older tag
+    Image { declared: String, running: String },
+    /// Env vars set differently, or only on one side
+    Env { keys: Vec<String> },
+    /// Published ports, given as [host_ip:]host_port:container_port/protocol
+    Ports {
+        declared: Vec<String>,
+        running: Vec<String>,
+    },
+    /// The file declares the service but it has no container
+    Missing,
+    /// The container belongs to a service the file no longer declares
+    Undeclared,
+}
+
+impl fmt::Display for Drift {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Drift::Image { declared, running } => {
+                write!(f, "runs {running}, file declares {declared}")
+            }
+            Drift::Env { keys } => write!(f, "env differs ({})", keys.join(", ")),
+            Drift::Ports { declared, running } => write!(
+                f,
+                "ports differ (file: {}, running: {})",
+                list_or_none(declared),
+                list_or_none(running)
+            ),
+            Drift::Missing => write!(f, "no container exists"),
+            Drift::Undeclared => write!(f, "service is not in the compose file"),
+        }
+    }
+}
+
+fn list_or_none(values: &[String]) -> String {
+    if values.is_empty() {
+        "none".to_string()
+    } else {
+        values.join(", ")
+    }
+}
+
+/// Drift of a single service
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceDrift {
+    pub service: String,
+    pub container: Option<String>,
+    pub drifts: Vec<Drift>,
+}
+
+/// Drift of every service of a stack, sorted by service
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackDrift {
+    pub stack: String,
+    pub compose_files: Vec<PathBuf>,
+    pub services: Vec<ServiceDrift>,
+}
+
+/// Compares the compose files a stack was created from against its containers
+pub fn stack_drift(stack: &str) -> anyhow::Result<StackDrift> {
+    let containers = inspect_stack_containers(stack)?;
+
+    if containers.is_empty() {
+        anyhow::bail!("No containers found in stack: {stack}");
+    }
+
+    // compose records the files and directory it was run with on the containers it creates
+    let labels = containers
+        .values()
+        .map(|container| &container["Config"]["Labels"])
+        .find(|labels| labels[LABEL_COMPOSE_CONFIG_FILES].is_string())
+        .with_context(|| format!("Stack {stack} does not record its compose files"))?;
+    let compose_files = labels[LABEL_COMPOSE_CONFIG_FILES]
+        .as_str()
+        .unwrap_or_default()
+        .split(',')
+        .filter(|file| !file.is_empty())
+        .map(PathBuf::from)
+        .collect::<Vec<PathBuf>>();
+    let working_dir = PathBuf::from(labels[LABEL_COMPOSE_WORKING_DIR].as_str().unwrap_or("."));
+
+    let config = load_compose_config(&compose_files, &working_dir)?;
+    let declared = config["services"]
+        .as_object()
+        .context("Compose file has no services")?;
+
+    let mut services = declared
+        .iter()
+        .map(|(service, desired)| {
+            let Some(container) = containers.get(service) else {
+                return ServiceDrift {
+                    service: service.to_string(),
+                    container: None,
+                    drifts: vec![Drift::Missing],
+                };
+            };
+
+            let image_config =
+                inspect_image_config(container["Image"].as_str().unwrap_or_default());
+
+            ServiceDrift {
+                service: service.to_string(),
+                container: Some(container_name(container)),
+                drifts: service_drift(stack, service, desired, container, &image_config),
+            }
+        })
+        .collect::<Vec<ServiceDrift>>();
+
+    services.extend(
+        containers
+            .iter()
+            .filter(|(service, _)| !declared.contains_key(*service))
+            .map(|(service, container)| ServiceDrift {
+                service: service.to_string(),
+                container: Some(container_name(container)),
+                drifts: vec![Drift::Undeclared],
+            }),
+    );
+    services.sort_by(|a, b| a.service.cmp(&b.service));
+
+    Ok(StackDrift {
+        stack: stack.to_string(),
+        compose_files,
+        services,
+    })
+}
+
+/// Compares the image, env and ports a compose file declares for a service against its
+/// inspected container and the config of the image it runs
+pub fn service_drift(
+    project: &str,
+    service: &str,
+    desired: &Value,
+    container: &Value,
+    image_config: &Value,
+) -> Vec<Drift> {
+    let mut drifts = vec![];
+
+    // compose names images of services that are only built after the project and service
+    let declared = desired["image"]
+        .as_str()
+        .map(String::from)
+        .unwrap_or_else(|| format!("{project}-{service}"));
+    let running = container["Config"]["Image"].as_str().unwrap_or_default();
+    if running != declared {
+        drifts.push(Drift::Image {
+            declared,
+            running: running.to_string(),
+        });
+    }
+
+    let keys = changed_env(desired, container, image_config);
+    if !keys.is_empty() {
+        drifts.push(Drift::Env { keys });
+    }
+
+    let declared = compose_ports(&desired["ports"]);
+    let running = container_ports(container);
+    if declared != running {
+        drifts.push(Drift::Ports { declared, running });
+    }
+
+    drifts
+}
+
+/// Normalizes the ports of a compose service, in short or long syntax, sorted
+pub fn compose_ports(ports: &Value) -> Vec<String> {
+    let mut normalized = ports
+        .as_array()
+        .into_iter()
+        .flatten()
+        .flat_map(|port| match port {
+            Value::Object(port) => {
+                let value = |key: &str| match &port.get(key) {
+                    Some(Value::String(value)) => value.to_string(),
+                    Some(Value::Number(value)) => value.to_string(),
+                    _ => String::new(),
+                };
+                let protocol = Some(value("protocol")).filter(|protocol| !protocol.is_empty());
+
+                expand_ports(
+                    &value("host_ip"),
+                    &value("published"),
+                    &value("target"),
+                    protocol.as_deref().unwrap_or("tcp"),
+                )
+            }
+            Value::String(port) => short_syntax_ports(port),
+            Value::Number(port) => short_syntax_ports(&port.to_string()),
+            _ => vec![],
+        })
+        .collect::<Vec<String>>();
+
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Normalizes the published ports of an inspected container, sorted
+pub fn container_ports(container: &Value) -> Vec<String> {
+    let mut normalized = container["HostConfig"]["PortBindings"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .flat_map(|(container_port, bindings)| {
+            let (target, protocol) = container_port
+                .split_once('/')
+                .unwrap_or((container_port, "tcp"));
+
+            bindings
+                .as_array()
+                .into_iter()
+                .flatten()
+                .map(move |binding| {
+                    port_spec(
+                        binding["HostIp"].as_str().unwrap_or_default(),
+                        binding["HostPort"].as_str().unwrap_or_default(),
+                        target,
+                        protocol,
+                    )
+                })
+        })
+        .collect::<Vec<String>>();
+
+    normalized.sort();
+    normalized.dedup();
+    normalized
+}
+
+/// Parses [host_ip:][host_port:]container_port[/protocol], where ports may be ranges
+fn short_syntax_ports(port: &str) -> Vec<String> {
+    let (port, protocol) = port.split_once('/').unwrap_or((port, "tcp"));
+
+    let mut parts = port.rsplitn(3, ':');
+    let target = parts.next().unwrap_or_default();
+    let published = parts.next().unwrap_or_default();
+    let host_ip = parts.next().unwrap_or_default();
+
+    expand_ports(host_ip, published, target, protocol)
+}
+
+/// Expands port ranges such as 8000-8002:80-82 into one spec per port
+fn expand_ports(host_ip: &str, published: &str, target: &str, protocol: &str) -> Vec<String> {
+    let range = |ports: &str| -> Option<(u32, u32)> {
+        let (start, end) = ports.split_once('-').unwrap_or((ports, ports));
+        Some((start.parse().ok()?, end.parse().ok()?))
+    };
+
+    match (range(published), range(target)) {
+        (Some((host_start, host_end)), Some((start, end)))
+            if start < end && host_end.checked_sub(host_start) == Some(end - start) =>
+        {
+            (0..=end - start)
+                .map(|offset| {
+                    port_spec(
+                        host_ip,
+                        &(host_start + offset).to_string(),
+                        &(start + offset).to_string(),
+                        protocol,
+                    )
+                })
+                .collect()
+        }
+        _ => vec![port_spec(host_ip, published, target, protocol)],
+    }
+}
+
+/// Formats a port as [host_ip:]host_port:container_port/protocol, leaving out addresses that
+/// bind to every interface and host ports docker picks on start
+fn port_spec(host_ip: &str, published: &str, target: &str, protocol: &str) -> String {
+    let host_ip = host_ip.trim_start_matches('[').trim_end_matches(']');
+
+    match (host_ip, published) {
+        (_, "") => format!("{target}/{protocol}"),
+        ("" | "0.0.0.0" | "::", published) => format!("{published}:{target}/{protocol}"),
+        (host_ip, published) => format!("{host_ip}:{published}:{target}/{protocol}"),
+    }
+}
+
+fn container_name(container: &Value) -> String {
+    container["Name"]
+        .as_str()
+        .unwrap_or_default()
+        .trim_start_matches('/')
+        .to_string()
+}
@@ -0,0 +1,86 @@
+use crate::commands::DOCKER;
+use crate::error::check_output;
+use crate::parsers::parse_container_env;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+/// Shown instead of the value of a secret
+pub const MASK: &str = "********";
+/// Words in env var names that hold secrets, such as DB_PASSWORD or API_KEY
+const SECRET_WORDS: [&str; 5] = ["PASSWORD", "PASSWD", "TOKEN", "KEY", "SECRET"];
+
+/// Environment a container was created with, including vars set by its image
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerEnv {
+    pub container: String,
+    /// Compose project and service the container belongs to
+    pub stack: Option<String>,
+    pub service: Option<String>,
+    pub env: BTreeMap<String, String>,
+}
+
+impl ContainerEnv {
+    /// Whether both containers run the same compose service, such as two of its replicas
+    pub fn same_service(&self, other: &ContainerEnv) -> bool {
+        self.stack.is_some()
+            && self.service.is_some()
+            && self.stack == other.stack
+            && self.service == other.service
+    }
+}
+
+/// Env var whose value differs between two containers, None where a container does not set it
+#[derive(Debug, Clone, PartialEq)]
+pub struct EnvDifference {
+    pub key: String,
+    pub left: Option<String>,
+    pub right: Option<String>,
+}
+
+/// Whether an env var holds a secret, by its name
+pub fn is_secret_key(key: &str) -> bool {
+    let key = key.to_ascii_uppercase();
+
+    SECRET_WORDS.iter().any(|word| key.contains(word))
+}
+
+/// Value of an env var to print, masked when its name marks it as a secret
+pub fn display_value<'a>(key: &str, value: &'a str, show_secrets: bool) -> &'a str {
+    if !show_secrets && is_secret_key(key) {
+        MASK
+    } else {
+        value
+    }
+}
+
+/// Env vars that differ between two containers, sorted by name
+pub fn diff_env(
+    left: &BTreeMap<String, String>,
+    right: &BTreeMap<String, String>,
+) -> Vec<EnvDifference> {
+    let mut keys = left.keys().chain(right.keys()).collect::<Vec<&String>>();
+    keys.sort();
+    keys.dedup();
+
+    keys.into_iter()
+        .filter(|key| left.get(*key) != right.get(*key))
+        .map(|key| EnvDifference {
+            key: key.to_string(),
+            left: left.get(key).cloned(),
+            right: right.get(key).cloned(),
+        })
+        .collect()
+}
+
+/// Inspects the environment of containers, in the order given
+pub fn inspect_container_env(containers: &[String]) -> anyhow::Result<Vec<ContainerEnv>> {
+    let output = check_output(
+        "inspect",
+        Command::new(DOCKER)
+            .arg("inspect")
+            .args(containers)
+            .output(),
+    )?;
+
+    parse_container_env(&output.stdout)
+}
@@ -0,0 +1,165 @@
+use crate::registry::AuthenticationRequired;
+use std::process::Output;
+
+/// Exit code of `docker run` and `docker exec` when the command cannot be invoked
+const EXIT_NOT_EXECUTABLE: i32 = 126;
+/// Exit code of `docker run` and `docker exec` when the command does not exist
+const EXIT_NOT_FOUND: i32 = 127;
+
+/// What went wrong talking to docker, for library consumers to tell failures apart.
+///
+/// The functions of [`crate::utils`] return these, other modules add context with anyhow, which
+/// keeps them reachable through `downcast_ref::<DsdError>()`.
+#[derive(Debug, thiserror::Error)]
+pub enum DsdError {
+    #[error("docker is not installed or not in PATH")]
+    DockerNotFound,
+
+    #[error("Permission denied on the docker socket, add the user to the docker group: {0}")]
+    PermissionDenied(String),
+
+    #[error("Docker daemon is not running or not reachable: {0}")]
+    DaemonUnavailable(String),
+
+    #[error("No such container: {0}")]
+    ContainerNotFound(String),
+
+    #[error("No such image: {0}")]
+    ImageNotFound(String),
+
+    #[error(transparent)]
+    AuthenticationRequired(#[from] AuthenticationRequired),
+
+    /// The command given to `docker run` or `docker exec` does not exist in the container
+    #[error("Command not found in the container: {0}")]
+    CommandNotFound(String),
+
+    /// The command given to `docker run` or `docker exec` exists but cannot be executed
+    #[error("Command cannot be executed in the container: {0}")]
+    CommandNotExecutable(String),
+
+    #[error("Failed to recreate {container}{}", if *restored { ", restored previous container" } else { "" })]
+    RecreateFailed { container: String, restored: bool },
+
+    /// The container has a setting that recreating it with `docker run` would lose
+    #[error("Cannot recreate {container} without losing its {setting}")]
+    RecreateUnsupported { container: String, setting: String },
+
+    #[error("Failed to pull images: {}", images.join(", "))]
+    PullFailed {
+        images: Vec<String>,
+        /// Refusal of a registry, when a pull failed for lack of credentials
+        #[source]
+        authentication: Option<AuthenticationRequired>,
+    },
+
+    /// Any other failure of a docker command
+    #[error("docker {command} failed: {message}")]
+    Docker {
+        command: String,
+        code: Option<i32>,
+        message: String,
+    },
+
+    #[error("Failed to parse output of docker {command}: {message}")]
+    Parse { command: String, message: String },
+
+    #[error("Failed to run docker {command}")]
+    Io {
+        command: String,
+        #[source]
+        source: std::io::Error,
+    },
+}
+
+impl DsdError {
+    /// Error of a docker command that exited unsuccessfully, from its exit code and the message it
+    /// printed
+    pub fn from_docker(command: &str, code: Option<i32>, stderr: &str) -> Self {
+        let message = stderr.trim().trim_start_matches("Error: ").to_string();
+        let lowercase = message.to_ascii_lowercase();
+
+        let object = |prefix: &str| {
+            lowercase.find(prefix).map(|index| {
+                message[index + prefix.len()..]
+                    .lines()
+                    .next()
+                    .unwrap_or_default()
+                    .trim()
+                    .to_string()
+            })
+        };
+
+        if lowercase.contains("permission denied")
+            && (lowercase.contains("docker daemon socket") || lowercase.contains("docker.sock"))
+        {
+            return DsdError::PermissionDenied(message);
+        }
+        if lowercase.contains("cannot connect to the docker daemon")
+            || lowercase.contains("is the docker daemon running")
+        {
+            return DsdError::DaemonUnavailable(message);
+        }
+        // plain `docker inspect` only inspects containers here, it reports them as objects
+        if let Some(container) = object("no such container:").or_else(|| object("no such object:"))
+        {
+            return DsdError::ContainerNotFound(container);
+        }
+        if let Some(image) = object("no such image:") {
+            return DsdError::ImageNotFound(image);
+        }
+
+        match code {
+            Some(EXIT_NOT_FOUND) => DsdError::CommandNotFound(message),
+            Some(EXIT_NOT_EXECUTABLE) => DsdError::CommandNotExecutable(message),
+            _ => DsdError::Docker {
+                command: command.to_string(),
+                code,
+                message,
+            },
+        }
+    }
+
+    /// Error of a docker command that could not be started at all
+    pub fn from_spawn(command: &str, error: std::io::Error) -> Self {
+        if error.kind() == std::io::ErrorKind::NotFound {
+            return DsdError::DockerNotFound;
+        }
+
+        DsdError::Io {
+            command: command.to_string(),
+            source: error,
+        }
+    }
+
+    /// Error for output of a docker command that does not parse
+    pub fn parse(command: &str, error: impl std::fmt::Display) -> Self {
+        DsdError::Parse {
+            command: command.to_string(),
+            message: error.to_string(),
+        }
+    }
+
+    /// Whether the error only says a container or image does not exist
+    pub fn is_not_found(&self) -> bool {
+        matches!(
+            self,
+            DsdError::ContainerNotFound(_) | DsdError::ImageNotFound(_)
+        )
+    }
+}
+
+/// Output of a docker command, or the error it failed with
+pub fn check_output(command: &str, output: std::io::Result<Output>) -> Result<Output, DsdError> {
+    let output = output.map_err(|e| DsdError::from_spawn(command, e))?;
+
+    if !output.status.success() {
+        return Err(DsdError::from_docker(
+            command,
+            output.status.code(),
+            &String::from_utf8_lossy(&output.stderr),
+        ));
+    }
+
+    Ok(output)
+}
@@ -0,0 +1,116 @@
+use crate::config::UpdateConfig;
+use crate::image::{image_matches, ImageRef};
+use crate::utils::{
+    matches_pattern, LABEL_COMPOSE_PROJECT, LABEL_COMPOSE_SERVICE, LABEL_DSD_SKIP, LABEL_DSD_UPDATE,
+};
+use std::collections::HashMap;
+use std::fmt;
+
+/// Why `update` leaves a container alone
+#[derive(Debug, Clone, PartialEq)]
+pub enum Exclusion {
+    /// Opted out with dsd-util.update=false or dsd-util.skip=true
+    Label(&'static str),
+    /// Matched by a pattern of `--exclude` or `update.exclude`
+    Excluded(String),
+    /// Runs an image matched by a pattern of `update.pin`
+    Pinned(String),
+    /// Runs an image referenced by digest, such as one pinned by `pin-digest`
+    Digest(String),
+}
+
+impl fmt::Display for Exclusion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Exclusion::Label(label) => write!(f, "updates disabled by {label} label"),
+            Exclusion::Excluded(pattern) => write!(f, "excluded by {pattern}"),
+            Exclusion::Pinned(pattern) => write!(f, "image pinned by {pattern}"),
+            Exclusion::Digest(digest) => write!(
+                f,
+                "image pinned to digest {}, unpin to follow its tag",
+                short_digest(digest)
+            ),
+        }
+    }
+}
+
+/// Containers and images `update` must not touch
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct UpdateRules {
+    /// Patterns of containers, services as `service` or `stack/service`
+    pub exclude: Vec<String>,
+    /// Patterns of images, with or without their tag
+    pub pin: Vec<String>,
+}
+
+impl UpdateRules {
+    /// Rules of the config file, extended by the patterns given on the command line
+    pub fn new(config: &UpdateConfig, exclude: &[String]) -> Self {
+        UpdateRules {
+            exclude: config.exclude.iter().chain(exclude).cloned().collect(),
+            pin: config.pin.clone(),
+        }
+    }
+
+    /// First rule excluding a container running an image, labels coming first
+    pub fn check(
+        &self,
+        container: &str,
+        labels: &HashMap<String, String>,
+        image: &str,
+    ) -> Option<Exclusion> {
+        if labels
+            .get(LABEL_DSD_UPDATE)
+            .is_some_and(|policy| policy == "false")
+        {
+            return Some(Exclusion::Label(LABEL_DSD_UPDATE));
+        }
+
+        if labels
+            .get(LABEL_DSD_SKIP)
+            .is_some_and(|skip| skip == "true")
+        {
+            return Some(Exclusion::Label(LABEL_DSD_SKIP));
+        }
+
+        let service = labels.get(LABEL_COMPOSE_SERVICE);
+        let qualified = labels
+            .get(LABEL_COMPOSE_PROJECT)
+            .zip(service)
+            .map(|(stack, service)| format!("{stack}/{service}"));
+        let names = [
+            Some(container),
+            service.map(String::as_str),
+            qualified.as_deref(),
+        ];
+
+        if let Some(pattern) = self.exclude.iter().find(|pattern| {
+            names
+                .iter()
+                .flatten()
+                .any(|name| matches_pattern(pattern, name))
+        }) {
+            return Some(Exclusion::Excluded(pattern.to_string()));
+        }
+
+        if let Some(pattern) = self
+            .pin
+            .iter()
+            .find(|pattern| image_matches(pattern, image))
+        {
+            return Some(Exclusion::Pinned(pattern.to_string()));
+        }
+
+        // pulling a digest never brings anything new
+        ImageRef::parse(image)
+            .ok()
+            .and_then(|image| image.digest)
+            .map(Exclusion::Digest)
+    }
+}
+
+/// Digest shortened the way docker shortens ids, such as sha256:9f8e1a2b3c4d
+pub fn short_digest(digest: &str) -> &str {
+    let hex_start = digest.find(':').map_or(0, |index| index + 1);
+    &digest[..digest.len().min(hex_start + 12)]
+}
@@ -0,0 +1,217 @@
+use crate::utils::{
+    calc_uptime_seconds, get_stats, inspect_containers, list_container_ids, InspectData, StatsData,
+};
+use anyhow::Context;
+use std::fmt::Write as FmtWrite;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+
+const METRICS_PATH: &str = "/metrics";
+const HEALTH_STATES: [&str; 4] = ["healthy", "unhealthy", "starting", "none"];
+/// How long a client may take to send its request or read the response, so a client that
+/// connects and goes quiet does not hold up the scrapes after it
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Serves container metrics for Prometheus to scrape
+pub fn serve_metrics(listener: TcpListener) -> anyhow::Result<()> {
+    for stream in listener.incoming() {
+        let Ok(stream) = stream else {
+            continue;
+        };
+
+        if stream.set_read_timeout(Some(CONNECTION_TIMEOUT)).is_err()
+            || stream.set_write_timeout(Some(CONNECTION_TIMEOUT)).is_err()
+        {
+            continue;
+        }
+
+        // a failing scrape should not bring the exporter down
+        let _ = handle_connection(stream);
+    }
+
+    Ok(())
+}
+
+/// Answers a single HTTP request
+fn handle_connection(mut stream: TcpStream) -> anyhow::Result<()> {
+    let mut request_line = String::new();
+    BufReader::new(&stream)
+        .read_line(&mut request_line)
+        .context("Failed to read request")?;
+
+    let path = request_line.split_whitespace().nth(1).unwrap_or_default();
+
+    let (status, content_type, body) = if path == METRICS_PATH {
+        match render_metrics() {
+            Ok(metrics) => ("200 OK", "text/plain; version=0.0.4", metrics),
+            Err(e) => (
+                "500 Internal Server Error",
+                "text/plain",
+                format!("{e:#}\n"),
+            ),
+        }
+    } else {
+        ("404 Not Found", "text/plain", "Not Found\n".to_string())
+    };
+
+    write!(
+        stream,
+        "HTTP/1.1 {status}\r\nContent-Type: {content_type}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    )
+    .context("Failed to write response")?;
+
+    Ok(())
+}
+
+/// Collects stats and inspect data for all running containers in Prometheus text format
+pub fn render_metrics() -> anyhow::Result<String> {
+    let containers = list_container_ids()?;
+
+    let (stats, inspected) = if containers.is_empty() {
+        (vec![], vec![])
+    } else {
+        (get_stats(&containers)?, inspect_containers(&containers)?)
+    };
+
+    Ok(format_metrics(&stats, &inspected))
+}
+
+/// Formats stats and inspect data as Prometheus metrics
+pub fn format_metrics(stats: &[StatsData], inspected: &[InspectData]) -> String {
+    let mut metrics = String::new();
+
+    let _ = writeln!(
+        metrics,
+        "# HELP dsd_container_cpu_percent CPU usage of the container in percent"
+    );
+    let _ = writeln!(metrics, "# TYPE dsd_container_cpu_percent gauge");
+    for stats in stats {
+        let _ = writeln!(
+            metrics,
+            "dsd_container_cpu_percent{{name=\"{}\"}} {}",
+            escape_label(&stats.container_name),
+            stats.cpu_percent
+        );
+    }
+
+    let _ = writeln!(
+        metrics,
+        "# HELP dsd_container_memory_percent Memory usage of the container in percent of its limit"
+    );
+    let _ = writeln!(metrics, "# TYPE dsd_container_memory_percent gauge");
+    for stats in stats {
+        let _ = writeln!(
+            metrics,
+            "dsd_container_memory_percent{{name=\"{}\"}} {}",
+            escape_label(&stats.container_name),
+            stats.memory_percent
+        );
+    }
+
+    let _ = writeln!(
+        metrics,
+        "# HELP dsd_container_memory_bytes Memory used by the container in bytes"
+    );
+    let _ = writeln!(metrics, "# TYPE dsd_container_memory_bytes gauge");
+    for stats in stats {
+        let _ = writeln!(
+            metrics,
+            "dsd_container_memory_bytes{{name=\"{}\"}} {}",
+            escape_label(&stats.container_name),
+            stats.memory_used
+        );
+    }
+
+    let _ = writeln!(
+        metrics,
+        "# HELP dsd_container_pids Number of processes or threads in the container"
+    );
+    let _ = writeln!(metrics, "# TYPE dsd_container_pids gauge");
+    for stats in stats {
+        let _ = writeln!(
+            metrics,
+            "dsd_container_pids{{name=\"{}\"}} {}",
+            escape_label(&stats.container_name),
+            stats.pids
+        );
+    }
+
+    let _ = writeln!(
+        metrics,
+        "# HELP dsd_container_uptime_seconds Seconds since the container was started"
+    );
+    let _ = writeln!(metrics, "# TYPE dsd_container_uptime_seconds gauge");
+    for inspect in inspected {
+        if let Ok(uptime) = calc_uptime_seconds(&inspect.started_at) {
+            let _ = writeln!(
+                metrics,
+                "dsd_container_uptime_seconds{{name=\"{}\"}} {}",
+                escape_label(&inspect.container_name),
+                uptime
+            );
+        }
+    }
+
+    let _ = writeln!(
+        metrics,
+        "# HELP dsd_container_restart_count Number of times docker restarted the container"
+    );
+    let _ = writeln!(metrics, "# TYPE dsd_container_restart_count counter");
+    for inspect in inspected {
+        let _ = writeln!(
+            metrics,
+            "dsd_container_restart_count{{name=\"{}\"}} {}",
+            escape_label(&inspect.container_name),
+            inspect.restart_count
+        );
+    }
+
+    let _ = writeln!(
+        metrics,
+        "# HELP dsd_container_running Whether the container is running"
+    );
+    let _ = writeln!(metrics, "# TYPE dsd_container_running gauge");
+    for inspect in inspected {
+        let _ = writeln!(
+            metrics,
+            "dsd_container_running{{name=\"{}\"}} {}",
+            escape_label(&inspect.container_name),
+            (inspect.status == "running") as u8
+        );
+    }
+
+    let _ = writeln!(
+        metrics,
+        "# HELP dsd_container_health Health state of the container, 1 for the current state"
+    );
+    let _ = writeln!(metrics, "# TYPE dsd_container_health gauge");
+    for inspect in inspected {
+        let health = if inspect.health == "N/A" {
+            "none"
+        } else {
+            inspect.health.as_str()
+        };
+
+        for state in HEALTH_STATES {
+            let _ = writeln!(
+                metrics,
+                "dsd_container_health{{name=\"{}\",state=\"{}\"}} {}",
+                escape_label(&inspect.container_name),
+                state,
+                (health == state) as u8
+            );
+        }
+    }
+
+    metrics
+}
+
+/// Escapes a Prometheus label value
+fn escape_label(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
@@ -0,0 +1,530 @@
+use crate::commands::DOCKER;
+use crate::parsers::{parse_depends_on, parse_graph_containers};
+use crate::utils::{
+    get_container_labels, get_containers_from_stack, LABEL_COMPOSE_DEPENDS_ON,
+    LABEL_COMPOSE_SERVICE,
+};
+use anyhow::Context;
+use std::fmt::Write;
+use std::process::Command;
+
+/// Output formats for the stack graph
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum GraphFormat {
+    Text,
+    /// Services under the services they depend on or link to
+    Tree,
+    Dot,
+    Mermaid,
+}
+
+/// Characters used to draw boxes in the terminal
+struct BoxChars {
+    top_left: char,
+    top_right: char,
+    bottom_left: char,
+    bottom_right: char,
+    horizontal: char,
+    vertical: char,
+    branch: &'static str,
+    last_branch: &'static str,
+}
+
+const BOX_UNICODE: BoxChars = BoxChars {
+    top_left: '┌',
+    top_right: '┐',
+    bottom_left: '└',
+    bottom_right: '┘',
+    horizontal: '─',
+    vertical: '│',
+    branch: "├─",
+    last_branch: "└─",
+};
+
+const BOX_ASCII: BoxChars = BoxChars {
+    top_left: '+',
+    top_right: '+',
+    bottom_left: '+',
+    bottom_right: '+',
+    horizontal: '-',
+    vertical: '|',
+    branch: "|-",
+    last_branch: "`-",
+};
+
+/// Shape of a container node in the stack graph
+#[derive(Debug, Clone)]
+pub struct GraphContainer {
+    pub name: String,
+    pub service: String,
+    pub networks: Vec<String>,
+    pub volumes: Vec<String>,
+    pub depends_on: Vec<String>,
+    /// Containers linked with `links` or `--link`, as services once the graph is built when
+    /// they are part of the stack
+    pub links: Vec<String>,
+}
+
+/// Shape of the relationship graph of a stack
+#[derive(Debug, Clone)]
+pub struct StackGraph {
+    pub stack: String,
+    pub containers: Vec<GraphContainer>,
+}
+
+impl StackGraph {
+    /// All networks used in the stack
+    pub fn networks(&self) -> Vec<String> {
+        let mut networks = self
+            .containers
+            .iter()
+            .flat_map(|container| container.networks.clone())
+            .collect::<Vec<String>>();
+        networks.sort();
+        networks.dedup();
+        networks
+    }
+
+    /// All named volumes used in the stack
+    pub fn volumes(&self) -> Vec<String> {
+        let mut volumes = self
+            .containers
+            .iter()
+            .flat_map(|container| container.volumes.clone())
+            .collect::<Vec<String>>();
+        volumes.sort();
+        volumes.dedup();
+        volumes
+    }
+
+    /// Services a container depends on or is linked to, without duplicates
+    pub fn dependencies<'a>(&self, container: &'a GraphContainer) -> Vec<&'a str> {
+        let mut dependencies: Vec<&str> = vec![];
+
+        for dependency in container.depends_on.iter().chain(&container.links) {
+            if !dependencies.contains(&dependency.as_str()) {
+                dependencies.push(dependency);
+            }
+        }
+
+        dependencies
+    }
+}
+
+/// Builds the container/network/volume/depends_on graph of a stack
+pub fn build_stack_graph(stack: &str) -> anyhow::Result<StackGraph> {
+    let container_names = get_containers_from_stack(stack)?;
+
+    if container_names.is_empty() {
+        anyhow::bail!("No containers found in stack: {stack}");
+    }
+
+    let output = Command::new(DOCKER)
+        .arg("inspect")
+        .args(&container_names)
+        .output()
+        .context("Failed to inspect containers")?;
+
+    let mut containers = parse_graph_containers(&output.stdout)?;
+
+    containers.sort_by(|a, b| a.service.cmp(&b.service));
+    resolve_links(&mut containers);
+
+    Ok(StackGraph {
+        stack: stack.to_string(),
+        containers,
+    })
+}
+
+/// Replaces the container names of links with the services of the stack they run, so links
+/// show up alongside depends_on. Links to containers outside of the stack keep their name.
+pub fn resolve_links(containers: &mut [GraphContainer]) {
+    let services = containers
+        .iter()
+        .map(|container| (container.name.to_string(), container.service.to_string()))
+        .collect::<Vec<(String, String)>>();
+
+    for container in containers.iter_mut() {
+        for link in &mut container.links {
+            if let Some((_, service)) = services.iter().find(|(name, _)| name == link) {
+                *link = service.to_string();
+            }
+        }
+    }
+}
+
+/// Sorts containers so dependencies come before the containers depending on them.
+///
+/// Containers that are part of a dependency cycle keep their original order at the end.
+pub fn sort_by_dependencies(containers: &[String]) -> anyhow::Result<Vec<String>> {
+    let mut nodes: Vec<(String, String, Vec<String>)> = vec![];
+
+    for container in containers {
+        let labels = get_container_labels(container)?;
+        let service = labels
+            .get(LABEL_COMPOSE_SERVICE)
+            .cloned()
+            .unwrap_or_else(|| container.to_string());
+        let depends_on = parse_depends_on(labels.get(LABEL_COMPOSE_DEPENDS_ON).map(String::as_str));
+
+        nodes.push((container.to_string(), service, depends_on));
+    }
+
+    let services = nodes
+        .iter()
+        .map(|(_, service, _)| service.to_string())
+        .collect::<Vec<String>>();
+
+    let mut sorted: Vec<String> = vec![];
+    let mut started: Vec<String> = vec![];
+
+    while sorted.len() < nodes.len() {
+        // containers whose dependencies within the selection have all been handled
+        let ready = nodes
+            .iter()
+            .filter(|(container, _, _)| !sorted.contains(container))
+            .filter(|(_, _, depends_on)| {
+                depends_on.iter().all(|dependency| {
+                    !services.contains(dependency) || started.contains(dependency)
+                })
+            })
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if ready.is_empty() {
+            // dependency cycle, keep remaining containers in their original order
+            sorted.extend(
+                nodes
+                    .iter()
+                    .filter(|(container, _, _)| !sorted.contains(container))
+                    .map(|(container, _, _)| container.to_string())
+                    .collect::<Vec<String>>(),
+            );
+            break;
+        }
+
+        for (container, service, _) in ready {
+            sorted.push(container);
+            started.push(service);
+        }
+    }
+
+    Ok(sorted)
+}
+
+/// Renders the stack graph in Graphviz DOT format
+pub fn render_dot(graph: &StackGraph) -> String {
+    let mut dot = String::new();
+
+    let _ = writeln!(dot, "digraph \"{}\" {{", graph.stack);
+    let _ = writeln!(dot, "  rankdir=LR;");
+
+    for container in &graph.containers {
+        let _ = writeln!(
+            dot,
+            "  \"{}\" [shape=box, label=\"{}\\n({})\"];",
+            container.service, container.service, container.name
+        );
+    }
+
+    for network in graph.networks() {
+        let _ = writeln!(
+            dot,
+            "  \"network:{network}\" [shape=ellipse, label=\"{network}\"];"
+        );
+    }
+
+    for volume in graph.volumes() {
+        let _ = writeln!(
+            dot,
+            "  \"volume:{volume}\" [shape=cylinder, label=\"{volume}\"];"
+        );
+    }
+
+    for container in &graph.containers {
+        for dependency in &container.depends_on {
+            let _ = writeln!(
+                dot,
+                "  \"{}\" -> \"{}\" [label=\"depends_on\"];",
+                container.service, dependency
+            );
+        }
+        for link in &container.links {
+            let _ = writeln!(
+                dot,
+                "  \"{}\" -> \"{}\" [label=\"link\"];",
+                container.service, link
+            );
+        }
+        for network in &container.networks {
+            let _ = writeln!(
+                dot,
+                "  \"{}\" -> \"network:{}\" [style=dashed, arrowhead=none];",
+                container.service, network
+            );
+        }
+        for volume in &container.volumes {
+            let _ = writeln!(
+                dot,
+                "  \"{}\" -> \"volume:{}\" [style=dotted];",
+                container.service, volume
+            );
+        }
+    }
+
+    dot.push_str("}\n");
+
+    dot
+}
+
+/// Renders the stack graph as a Mermaid flowchart
+pub fn render_mermaid(graph: &StackGraph) -> String {
+    let mut mermaid = String::new();
+
+    let _ = writeln!(mermaid, "graph LR");
+
+    for container in &graph.containers {
+        let _ = writeln!(
+            mermaid,
+            "  c_{}[\"{}<br/>({})\"]",
+            mermaid_id(&container.service),
+            container.service,
+            container.name
+        );
+    }
+
+    for network in graph.networks() {
+        let _ = writeln!(mermaid, "  n_{}((\"{}\"))", mermaid_id(&network), network);
+    }
+
+    for volume in graph.volumes() {
+        let _ = writeln!(mermaid, "  v_{}[(\"{}\")]", mermaid_id(&volume), volume);
+    }
+
+    for container in &graph.containers {
+        let id = mermaid_id(&container.service);
+        for dependency in &container.depends_on {
+            let _ = writeln!(
+                mermaid,
+                "  c_{id} -->|depends_on| c_{}",
+                mermaid_id(dependency)
+            );
+        }
+        for link in &container.links {
+            let _ = writeln!(mermaid, "  c_{id} -->|link| c_{}", mermaid_id(link));
+        }
+        for network in &container.networks {
+            let _ = writeln!(mermaid, "  c_{id} -.- n_{}", mermaid_id(network));
+        }
+        for volume in &container.volumes {
+            let _ = writeln!(mermaid, "  c_{id} --- v_{}", mermaid_id(volume));
+        }
+    }
+
+    mermaid
+}
+
+/// Sanitizes a name so it can be used as a Mermaid node id
+fn mermaid_id(name: &str) -> String {
+    name.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Renders the stack topology as boxes of containers grouped by network
+pub fn render_text(graph: &StackGraph, ascii: bool) -> String {
+    let chars = if ascii { &BOX_ASCII } else { &BOX_UNICODE };
+    let mut text = String::new();
+
+    for network in graph.networks() {
+        let mut lines: Vec<String> = vec![];
+
+        for container in graph
+            .containers
+            .iter()
+            .filter(|container| container.networks.contains(&network))
+        {
+            lines.push(format!("{} ({})", container.service, container.name));
+
+            let mut details: Vec<String> = vec![];
+            if !container.depends_on.is_empty() {
+                details.push(format!("depends on: {}", container.depends_on.join(", ")));
+            }
+            if !container.links.is_empty() {
+                details.push(format!("links: {}", container.links.join(", ")));
+            }
+            if !container.volumes.is_empty() {
+                details.push(format!("volumes: {}", container.volumes.join(", ")));
+            }
+
+            for (i, detail) in details.iter().enumerate() {
+                let branch = if i + 1 == details.len() {
+                    chars.last_branch
+                } else {
+                    chars.branch
+                };
+                lines.push(format!("  {branch} {detail}"));
+            }
+        }
+
+        let title = format!(" network: {network} ");
+        let width = lines
+            .iter()
+            .map(|line| line.chars().count() + 2)
+            .chain(std::iter::once(title.chars().count() + 2))
+            .max()
+            .unwrap_or_default();
+
+        let _ = writeln!(
+            text,
+            "{}{}{}{}{}",
+            chars.top_left,
+            chars.horizontal,
+            title,
+            chars
+                .horizontal
+                .to_string()
+                .repeat(width - title.chars().count() - 1),
+            chars.top_right
+        );
+
+        for line in &lines {
+            let _ = writeln!(
+                text,
+                "{} {}{} {}",
+                chars.vertical,
+                line,
+                " ".repeat(width - line.chars().count() - 2),
+                chars.vertical
+            );
+        }
+
+        let _ = writeln!(
+            text,
+            "{}{}{}",
+            chars.bottom_left,
+            chars.horizontal.to_string().repeat(width),
+            chars.bottom_right
+        );
+    }
+
+    text
+}
+
+/// Renders the services of a stack as a tree, each service above the services it depends on or
+/// links to. Services nothing depends on are the roots, and a service depending on one of its
+/// own ancestors is marked as a cycle instead of repeating it forever.
+pub fn render_tree(graph: &StackGraph, ascii: bool) -> String {
+    let chars = if ascii { &BOX_ASCII } else { &BOX_UNICODE };
+    let mut text = format!("{}\n", graph.stack);
+
+    let is_dependency = |service: &str| {
+        graph
+            .containers
+            .iter()
+            .any(|container| graph.dependencies(container).contains(&service))
+    };
+    let mut roots = graph
+        .containers
+        .iter()
+        .filter(|container| !is_dependency(&container.service))
+        .collect::<Vec<&GraphContainer>>();
+
+    // every service is part of a cycle, so start anywhere
+    if roots.is_empty() {
+        roots.extend(graph.containers.first());
+    }
+
+    for (i, root) in roots.iter().enumerate() {
+        render_tree_node(
+            graph,
+            &root.service,
+            &mut TreePosition {
+                chars,
+                prefix: String::new(),
+                is_last: i + 1 == roots.len(),
+                ancestors: vec![],
+            },
+            &mut text,
+        );
+    }
+
+    text
+}
+
+/// Where a service is drawn in the dependency tree
+struct TreePosition<'a> {
+    chars: &'a BoxChars,
+    /// Continuation lines of the ancestors drawn before the branch
+    prefix: String,
+    is_last: bool,
+    ancestors: Vec<String>,
+}
+
+fn render_tree_node(
+    graph: &StackGraph,
+    service: &str,
+    position: &mut TreePosition,
+    text: &mut String,
+) {
+    let branch = if position.is_last {
+        position.chars.last_branch
+    } else {
+        position.chars.branch
+    };
+    let container = graph
+        .containers
+        .iter()
+        .find(|container| container.service == service);
+
+    let label = match container {
+        _ if position
+            .ancestors
+            .iter()
+            .any(|ancestor| ancestor == service) =>
+        {
+            format!("{service} (cycle)")
+        }
+        Some(container) => format!("{service} ({})", container.name),
+        // a link to a container outside of the stack, or a dependency that is not running
+        None => format!("{service} (not in stack)"),
+    };
+    let _ = writeln!(text, "{}{branch} {label}", position.prefix);
+
+    let Some(container) = container else {
+        return;
+    };
+    if position
+        .ancestors
+        .iter()
+        .any(|ancestor| ancestor == service)
+    {
+        return;
+    }
+
+    let dependencies = graph.dependencies(container);
+    let mut child = TreePosition {
+        chars: position.chars,
+        prefix: format!(
+            "{}{}  ",
+            position.prefix,
+            if position.is_last {
+                ' '
+            } else {
+                position.chars.vertical
+            }
+        ),
+        is_last: false,
+        ancestors: position
+            .ancestors
+            .iter()
+            .cloned()
+            .chain(std::iter::once(service.to_string()))
+            .collect(),
+    };
+
+    for (i, dependency) in dependencies.iter().enumerate() {
+        child.is_last = i + 1 == dependencies.len();
+        render_tree_node(graph, dependency, &mut child, text);
+    }
+}
@@ -0,0 +1,98 @@
+use crate::config::state_dir;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+/// Actions taken on containers, one JSON object per line
+const HISTORY_FILE: &str = "history.jsonl";
+
+/// Something dsd-util did to a container
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Action {
+    Restart,
+    /// Recreated with a newly pulled image
+    Update,
+    Rollback,
+    Stop,
+    Start,
+    Pause,
+    Unpause,
+}
+
+/// An action recorded in the history file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub at: DateTime<Utc>,
+    pub container: String,
+    pub action: Action,
+    /// What the action did, such as the image an update deployed
+    pub detail: Option<String>,
+    /// Error when the action failed
+    pub error: Option<String>,
+}
+
+impl HistoryEntry {
+    /// Entry for an action that just finished, with the error it failed with
+    pub fn new(container: &str, action: Action, error: Option<String>) -> Self {
+        HistoryEntry {
+            at: Utc::now(),
+            container: container.to_string(),
+            action,
+            detail: None,
+            error,
+        }
+    }
+
+    pub fn detail(mut self, detail: impl Into<String>) -> Self {
+        self.detail = Some(detail.into());
+        self
+    }
+}
+
+/// Gets the path of the history file
+pub fn history_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join(HISTORY_FILE))
+}
+
+/// Appends an action to the history file
+pub fn record_action(entry: &HistoryEntry) -> anyhow::Result<()> {
+    let path = history_path()?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open history: {}", path.display()))?;
+
+    // a single write per line, so actions recorded from parallel jobs do not interleave
+    let line = format!("{}\n", serde_json::to_string(entry)?);
+    file.write_all(line.as_bytes())
+        .context("Failed to write history")
+}
+
+/// Loads the actions recorded since the given time, skipping lines it cannot parse
+pub fn load_actions(since: DateTime<Utc>) -> anyhow::Result<Vec<HistoryEntry>> {
+    let path = history_path()?;
+
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read history: {}", path.display()))?;
+
+    Ok(contents
+        .lines()
+        .filter_map(|line| serde_json::from_str::<HistoryEntry>(line).ok())
+        .filter(|entry| entry.at >= since)
+        .collect())
+}
@@ -0,0 +1,225 @@
+use crate::config::GuardConfig;
+use crate::units::{format_size, parse_size};
+use crate::utils::{get_docker_root, get_image_download_size};
+use anyhow::Context;
+use std::ffi::CString;
+use std::fmt;
+use std::os::unix::ffi::OsStrExt;
+use std::path::Path;
+use std::time::Duration;
+
+const LOADAVG_PATH: &str = "/proc/loadavg";
+const MEMINFO_PATH: &str = "/proc/meminfo";
+const HOSTNAME_PATH: &str = "/proc/sys/kernel/hostname";
+
+/// Extracted layers take about this many times their compressed size on disk
+const EXTRACT_RATIO: u64 = 2;
+
+/// Load average and memory of the host
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct HostInfo {
+    /// Load average over the last minute
+    pub load: f64,
+    pub cpus: usize,
+    pub memory_total: u64,
+    /// Memory that can be used without swapping, including reclaimable caches
+    pub memory_available: u64,
+}
+
+impl HostInfo {
+    /// Load average over the last minute per CPU, 1.0 meaning every CPU is busy
+    pub fn load_per_cpu(&self) -> f64 {
+        self.load / self.cpus.max(1) as f64
+    }
+}
+
+/// Why the host is considered too busy for automatic actions
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Overload {
+    Load { load: f64, max: f64 },
+    Memory { available: u64, min: u64 },
+}
+
+impl fmt::Display for Overload {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Overload::Load { load, max } => {
+                write!(f, "load average per CPU is {load:.2}, above {max:.2}")
+            }
+            Overload::Memory { available, min } => write!(
+                f,
+                "{} of memory available, below {}",
+                format_size(*available),
+                format_size(*min)
+            ),
+        }
+    }
+}
+
+/// Not enough free disk space to pull images
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DiskShortage {
+    /// Estimated space the pull needs, including the headroom
+    pub required: u64,
+    pub free: u64,
+}
+
+impl fmt::Display for DiskShortage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} needed to download and extract images, only {} free",
+            format_size(self.required),
+            format_size(self.free)
+        )
+    }
+}
+
+/// Thresholds beyond which automatic restarts and updates are deferred
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LoadGuard {
+    pub max_load: Option<f64>,
+    pub min_free_memory: Option<u64>,
+    /// Time between checks while an action waits for the host to recover
+    pub retry_interval: Duration,
+    /// Disk space to keep free after pulling images
+    pub disk_headroom: u64,
+}
+
+impl LoadGuard {
+    pub fn from_config(config: &GuardConfig) -> anyhow::Result<Self> {
+        Ok(LoadGuard {
+            max_load: config.max_load,
+            min_free_memory: config
+                .min_free_memory
+                .as_deref()
+                .map(parse_size)
+                .transpose()
+                .context("Invalid guard.min_free_memory")?,
+            retry_interval: Duration::from_secs(config.retry_interval),
+            disk_headroom: parse_size(&config.disk_headroom)
+                .context("Invalid guard.disk_headroom")?,
+        })
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.max_load.is_some() || self.min_free_memory.is_some()
+    }
+
+    /// First threshold the host crosses, if any
+    pub fn check(&self, host: &HostInfo) -> Option<Overload> {
+        let load = host.load_per_cpu();
+
+        if let Some(max) = self.max_load.filter(|max| load > *max) {
+            return Some(Overload::Load { load, max });
+        }
+
+        self.min_free_memory
+            .filter(|min| host.memory_available < *min)
+            .map(|min| Overload::Memory {
+                available: host.memory_available,
+                min,
+            })
+    }
+
+    /// Checks the current state of the host, which is never considered overloaded when it
+    /// cannot be read, e.g. on a host without procfs
+    pub fn check_host(&self) -> Option<Overload> {
+        if !self.is_enabled() {
+            return None;
+        }
+
+        read_host_info().ok().and_then(|host| self.check(&host))
+    }
+
+    /// Checks free space against a download, which takes about as much again twice once
+    /// extracted
+    pub fn check_disk(&self, download: u64, free: u64) -> Option<DiskShortage> {
+        let required = download
+            .saturating_mul(1 + EXTRACT_RATIO)
+            .saturating_add(self.disk_headroom);
+
+        (free < required).then_some(DiskShortage { required, free })
+    }
+
+    /// Checks the docker root filesystem has room for pulling images.
+    ///
+    /// Layers already on disk are counted too, so the estimate errs on the safe side. Images the
+    /// registry does not report a size for are left out, and nothing is reported when docker or
+    /// the filesystem cannot be queried.
+    pub fn check_pull_space(&self, images: &[String]) -> Option<DiskShortage> {
+        let (root, platform) = get_docker_root().ok()?;
+        let free = free_disk_space(&root).ok()?;
+        let download = images
+            .iter()
+            .filter_map(|image| get_image_download_size(image, &platform))
+            .sum();
+
+        self.check_disk(download, free)
+    }
+}
+
+/// Space available to unprivileged users on the filesystem of a path
+pub fn free_disk_space(path: &Path) -> anyhow::Result<u64> {
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .with_context(|| format!("Invalid path: {}", path.display()))?;
+    let mut stats = std::mem::MaybeUninit::<libc::statvfs>::zeroed();
+
+    // SAFETY: the path is a valid C string and statvfs only writes into the provided struct
+    let result = unsafe { libc::statvfs(c_path.as_ptr(), stats.as_mut_ptr()) };
+    if result != 0 {
+        return Err(std::io::Error::last_os_error())
+            .with_context(|| format!("Failed to get free space of {}", path.display()));
+    }
+
+    // SAFETY: statvfs succeeded and filled in the struct
+    let stats = unsafe { stats.assume_init() };
+    Ok(stats.f_bavail.saturating_mul(stats.f_frsize))
+}
+
+/// Parses the load average over the last minute from /proc/loadavg
+pub fn parse_loadavg(contents: &str) -> anyhow::Result<f64> {
+    contents
+        .split_whitespace()
+        .next()
+        .and_then(|load| load.parse().ok())
+        .with_context(|| format!("Invalid load average: {}", contents.trim()))
+}
+
+/// Parses total and available memory in bytes from /proc/meminfo
+pub fn parse_meminfo(contents: &str) -> anyhow::Result<(u64, u64)> {
+    let field = |name: &str| {
+        contents
+            .lines()
+            .find_map(|line| line.strip_prefix(name)?.strip_prefix(':'))
+            .and_then(|value| value.trim().strip_suffix("kB"))
+            .and_then(|kilobytes| kilobytes.trim().parse::<u64>().ok())
+            .map(|kilobytes| kilobytes * 1024)
+            .with_context(|| format!("Missing {name} in meminfo"))
+    };
+
+    Ok((field("MemTotal")?, field("MemAvailable")?))
+}
+
+/// Reads load average, CPU count and memory of the host
+pub fn read_host_info() -> anyhow::Result<HostInfo> {
+    let loadavg = std::fs::read_to_string(LOADAVG_PATH)
+        .with_context(|| format!("Failed to read {LOADAVG_PATH}"))?;
+    let meminfo = std::fs::read_to_string(MEMINFO_PATH)
+        .with_context(|| format!("Failed to read {MEMINFO_PATH}"))?;
+    let (memory_total, memory_available) = parse_meminfo(&meminfo)?;
+
+    Ok(HostInfo {
+        load: parse_loadavg(&loadavg)?,
+        cpus: std::thread::available_parallelism().map_or(1, |cpus| cpus.get()),
+        memory_total,
+        memory_available,
+    })
+}
+
+/// Name of the host, empty when it cannot be read
+pub fn host_name() -> String {
+    std::fs::read_to_string(HOSTNAME_PATH)
+        .map(|name| name.trim().to_string())
+        .unwrap_or_default()
+}
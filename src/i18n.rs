@@ -0,0 +1,73 @@
+use crate::config::{config_dir, Config};
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::OnceLock;
+
+/// Translations shipped with dsd-util, by language code
+const BUILTIN_CATALOGS: [(&str, &str); 1] = [("de", include_str!("../locales/de.toml"))];
+
+static CATALOG: OnceLock<HashMap<String, String>> = OnceLock::new();
+
+/// Translates a user-facing message, returning it unchanged when there is no translation.
+///
+/// Messages are looked up by their English text, gettext style.
+pub fn tr(message: &str) -> String {
+    CATALOG
+        .get_or_init(load_catalog)
+        .get(message)
+        .cloned()
+        .unwrap_or_else(|| message.to_string())
+}
+
+/// Translates a message and fills in its `{name}` placeholders
+pub fn tr_args(message: &str, args: &[(&str, &dyn Display)]) -> String {
+    args.iter().fold(tr(message), |translated, (name, value)| {
+        translated.replace(&format!("{{{name}}}"), &value.to_string())
+    })
+}
+
+/// Determines the language from the config file, or else LC_ALL, LC_MESSAGES and LANG
+pub fn language() -> String {
+    let configured = Config::load().ok().and_then(|config| config.language);
+
+    let locale = configured
+        .or_else(|| {
+            ["LC_ALL", "LC_MESSAGES", "LANG"]
+                .iter()
+                .filter_map(|name| std::env::var(name).ok())
+                .find(|locale| !locale.is_empty())
+        })
+        .unwrap_or_default();
+
+    locale
+        .split(['_', '-', '.', '@'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase()
+}
+
+/// Loads the catalog of the current language, preferring a translation in the config directory
+fn load_catalog() -> HashMap<String, String> {
+    let language = language();
+
+    if matches!(language.as_str(), "" | "c" | "posix" | "en") {
+        return HashMap::new();
+    }
+
+    let custom = config_dir()
+        .ok()
+        .map(|dir| dir.join("locales").join(format!("{language}.toml")))
+        .and_then(|path| std::fs::read_to_string(path).ok());
+
+    let contents = custom.or_else(|| {
+        BUILTIN_CATALOGS
+            .iter()
+            .find(|(code, _)| *code == language)
+            .map(|(_, contents)| contents.to_string())
+    });
+
+    // a broken translation must never keep a command from running
+    contents
+        .and_then(|contents| toml::from_str(&contents).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,203 @@
+use crate::utils::matches_pattern;
+use std::fmt;
+use std::str::FromStr;
+
+/// Registry images without a registry in their reference are pulled from
+pub const DOCKER_HUB: &str = "docker.io";
+/// Older names of Docker Hub that show up in references
+const DOCKER_HUB_ALIASES: [&str; 2] = ["index.docker.io", "registry-1.docker.io"];
+/// Namespace of official Docker Hub images such as nginx
+const OFFICIAL_NAMESPACE: &str = "library";
+/// Tag docker pulls when a reference has neither tag nor digest
+const DEFAULT_TAG: &str = "latest";
+const MAX_TAG_LENGTH: usize = 128;
+
+/// A parsed image reference such as ghcr.io/acme/app:2 or nginx@sha256:..., following how
+/// docker tells a registry from a namespace and fills in what a reference leaves out
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ImageRef {
+    /// Registry host, with its port if any, docker.io when the reference names none
+    pub registry: String,
+    /// Repository within the registry, with library/ added for official Docker Hub images
+    pub repository: String,
+    /// Tag as given, which docker takes as latest when there is no digest either
+    pub tag: Option<String>,
+    /// Digest such as sha256:..., pinning the exact content
+    pub digest: Option<String>,
+}
+
+impl ImageRef {
+    /// Parses an image reference as docker accepts it
+    pub fn parse(reference: &str) -> anyhow::Result<Self> {
+        let reference = reference.trim();
+        if reference.is_empty() {
+            anyhow::bail!("Image reference is empty");
+        }
+
+        let (name, digest) = match reference.split_once('@') {
+            Some((name, digest)) => {
+                let valid = digest
+                    .split_once(':')
+                    .is_some_and(|(algorithm, hex)| !algorithm.is_empty() && !hex.is_empty());
+                if !valid {
+                    anyhow::bail!("Invalid digest in image reference: {reference}");
+                }
+                (name, Some(digest.to_string()))
+            }
+            None => (reference, None),
+        };
+
+        // a colon after the last slash starts the tag, before it is a registry port
+        let (name, tag) = match name.rsplit_once(':') {
+            Some((name, tag)) if !tag.contains('/') => (name, Some(tag.to_string())),
+            _ => (name, None),
+        };
+
+        let (registry, repository) = match name.split_once('/') {
+            Some((first, rest)) if first.contains(['.', ':']) || first == "localhost" => {
+                (first, rest)
+            }
+            _ => (DOCKER_HUB, name),
+        };
+        let registry = if DOCKER_HUB_ALIASES.contains(&registry) {
+            DOCKER_HUB
+        } else {
+            registry
+        };
+
+        let is_valid_repository = !repository.is_empty()
+            && repository.split('/').all(|component| {
+                !component.is_empty()
+                    && component.chars().all(|c| {
+                        c.is_ascii_lowercase() || c.is_ascii_digit() || matches!(c, '.' | '_' | '-')
+                    })
+            });
+        if !is_valid_repository {
+            anyhow::bail!("Invalid repository in image reference: {reference}");
+        }
+
+        if let Some(tag) = &tag {
+            let is_valid_tag = !tag.is_empty()
+                && tag.len() <= MAX_TAG_LENGTH
+                && tag
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+            if !is_valid_tag {
+                anyhow::bail!("Invalid tag in image reference: {reference}");
+            }
+        }
+
+        let repository = if registry == DOCKER_HUB && !repository.contains('/') {
+            format!("{OFFICIAL_NAMESPACE}/{repository}")
+        } else {
+            repository.to_string()
+        };
+
+        Ok(ImageRef {
+            registry: registry.to_string(),
+            repository,
+            tag,
+            digest,
+        })
+    }
+
+    /// Whether the image comes from Docker Hub
+    pub fn is_docker_hub(&self) -> bool {
+        self.registry == DOCKER_HUB
+    }
+
+    /// Repository the way docker shows it, such as nginx for docker.io/library/nginx
+    pub fn familiar_repository(&self) -> String {
+        if !self.is_docker_hub() {
+            return format!("{}/{}", self.registry, self.repository);
+        }
+
+        self.repository
+            .strip_prefix(&format!("{OFFICIAL_NAMESPACE}/"))
+            .unwrap_or(&self.repository)
+            .to_string()
+    }
+
+    /// Tag docker pulls, latest when the reference gives neither tag nor digest
+    pub fn effective_tag(&self) -> Option<&str> {
+        match (&self.tag, &self.digest) {
+            (Some(tag), _) => Some(tag),
+            (None, Some(_)) => None,
+            (None, None) => Some(DEFAULT_TAG),
+        }
+    }
+
+    /// Fully qualified reference, such as docker.io/library/nginx:latest for nginx, which is
+    /// the same for all spellings of an image
+    pub fn normalized(&self) -> String {
+        let mut normalized = format!("{}/{}", self.registry, self.repository);
+
+        if let Some(tag) = self.effective_tag() {
+            normalized.push(':');
+            normalized.push_str(tag);
+        }
+        if let Some(digest) = &self.digest {
+            normalized.push('@');
+            normalized.push_str(digest);
+        }
+
+        normalized
+    }
+
+    /// Whether a pattern with `*` wildcards matches the image, with or without its tag and
+    /// whether or not the pattern spells out the registry
+    pub fn matches(&self, pattern: &str) -> bool {
+        let familiar = self.familiar_repository();
+        let full = format!("{}/{}", self.registry, self.repository);
+        let mut candidates = vec![self.to_string(), self.normalized(), familiar.clone(), full];
+
+        if let Some(tag) = self.effective_tag() {
+            candidates.push(format!("{familiar}:{tag}"));
+        }
+
+        candidates
+            .iter()
+            .any(|candidate| matches_pattern(pattern, candidate))
+    }
+}
+
+impl fmt::Display for ImageRef {
+    /// Shortest form of the reference, such as nginx:1.27 or ghcr.io/acme/app@sha256:...
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.familiar_repository())?;
+
+        if let Some(tag) = &self.tag {
+            write!(f, ":{tag}")?;
+        }
+        if let Some(digest) = &self.digest {
+            write!(f, "@{digest}")?;
+        }
+
+        Ok(())
+    }
+}
+
+impl FromStr for ImageRef {
+    type Err = anyhow::Error;
+
+    fn from_str(reference: &str) -> anyhow::Result<Self> {
+        ImageRef::parse(reference)
+    }
+}
+
+/// Whether two references name the same image, such as nginx and docker.io/library/nginx:latest.
+/// References that do not parse are only the same when spelled the same.
+pub fn same_image(a: &str, b: &str) -> bool {
+    match (ImageRef::parse(a), ImageRef::parse(b)) {
+        (Ok(a), Ok(b)) => a.normalized() == b.normalized(),
+        _ => a == b,
+    }
+}
+
+/// Whether a pattern matches an image, see [`ImageRef::matches`]
+pub fn image_matches(pattern: &str, image: &str) -> bool {
+    match ImageRef::parse(image) {
+        Ok(reference) => reference.matches(pattern),
+        Err(_) => matches_pattern(pattern, image),
+    }
+}
@@ -0,0 +1,67 @@
+use crate::docker::Container;
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// Runs a task for each item on up to `jobs` threads, returning the results in the order of
+/// the items
+pub fn run_parallel<T: Sync, R: Send>(
+    items: &[T],
+    jobs: usize,
+    task: impl Fn(&T) -> R + Sync,
+) -> Vec<R> {
+    let next = AtomicUsize::new(0);
+
+    let mut results = std::thread::scope(|scope| {
+        let handles = (0..jobs.clamp(1, items.len().max(1)))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut results = vec![];
+
+                    loop {
+                        let index = next.fetch_add(1, Ordering::Relaxed);
+                        let Some(item) = items.get(index) else {
+                            break;
+                        };
+
+                        results.push((index, task(item)));
+                    }
+
+                    results
+                })
+            })
+            .collect::<Vec<_>>();
+
+        handles
+            .into_iter()
+            .flat_map(|handle| {
+                handle
+                    .join()
+                    .unwrap_or_else(|e| std::panic::resume_unwind(e))
+            })
+            .collect::<Vec<(usize, R)>>()
+    });
+
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Groups containers given by name or id by the stack they belong to, ordered by stack name and
+/// giving containers by name. Containers outside of a compose project form a stack of their own.
+pub fn group_by_stack(containers: &[String], known: &[Container]) -> Vec<(String, Vec<String>)> {
+    let mut stacks: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    for container in containers {
+        let found = known
+            .iter()
+            .find(|other| &other.name == container || &other.id == container);
+        let name = found.map_or(container, |found| &found.name);
+        let stack = found.and_then(|found| found.stack.as_ref()).unwrap_or(name);
+
+        stacks
+            .entry(stack.to_string())
+            .or_default()
+            .push(name.to_string());
+    }
+
+    stacks.into_iter().collect()
+}
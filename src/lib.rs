@@ -1,3 +1,68 @@
+pub mod activation;
+pub mod backup;
+pub mod batch;
+pub mod bump;
+pub mod bundle;
+pub mod changelog;
+pub mod ci;
 pub mod commands;
+pub mod config;
+pub mod conflicts;
+pub mod context;
+pub mod controls;
+pub mod copy;
+pub mod cron;
+pub mod daemon;
+pub mod deploy;
+pub mod docker;
+pub mod doctor;
+pub mod document;
+pub mod drift;
+pub mod environment;
+pub mod error;
+pub mod exclusions;
+pub mod exporter;
+pub mod graph;
+pub mod history;
+pub mod hostinfo;
+pub mod i18n;
+pub mod image;
+pub mod jobs;
+pub mod lifecycle;
+pub mod logaudit;
+pub mod logger;
+pub mod maintenance;
+pub mod mute;
+pub mod notify;
+pub mod parsers;
+pub mod pin;
+pub mod plan;
+pub mod ports;
+pub mod prepull;
 pub mod printer;
+pub mod privileges;
+pub mod probes;
+pub mod prune;
+pub mod queue;
+pub mod reboot;
+pub mod record;
+pub mod registry;
+pub mod report;
+pub mod rollback;
+pub mod sbom;
+pub mod scaffold;
+pub mod scale;
+pub mod secrets;
+pub mod security;
+pub mod selector;
+pub mod selfupdate;
+pub mod ship;
+pub mod shutdown;
+pub mod table;
+pub mod timeline;
+pub mod top;
+pub mod units;
 pub mod utils;
+pub mod verify;
+pub mod watch;
+pub mod window;
@@ -0,0 +1,146 @@
+use crate::commands::DOCKER;
+use crate::history::Action;
+use crate::i18n::tr_args;
+use crate::units::format_duration;
+use anyhow::Context;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+/// Extra time docker gets beyond the stop timeout to kill a container that ignored the signal
+const KILL_GRACE: Duration = Duration::from_secs(10);
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Changes of a container's state that keep the container itself
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Lifecycle {
+    Stop,
+    Start,
+    /// Freeze all processes of a container
+    Pause,
+    Unpause,
+}
+
+impl Lifecycle {
+    /// Arguments of the docker command, letting a stopped container take at most timeout to exit
+    pub fn docker_args(self, container: &str, timeout: Duration) -> Vec<String> {
+        let mut args = vec![self.docker_command().to_string()];
+        if self == Lifecycle::Stop {
+            args.extend(["--time".to_string(), timeout.as_secs().to_string()]);
+        }
+        args.push(container.to_string());
+        args
+    }
+
+    fn docker_command(self) -> &'static str {
+        match self {
+            Lifecycle::Stop => "stop",
+            Lifecycle::Start => "start",
+            Lifecycle::Pause => "pause",
+            Lifecycle::Unpause => "unpause",
+        }
+    }
+
+    /// Action recorded in the history
+    pub fn action(self) -> Action {
+        match self {
+            Lifecycle::Stop => Action::Stop,
+            Lifecycle::Start => Action::Start,
+            Lifecycle::Pause => Action::Pause,
+            Lifecycle::Unpause => Action::Unpause,
+        }
+    }
+
+    /// Whether a container must be running for the change, so stacks select running containers
+    pub fn needs_running(self) -> bool {
+        self != Lifecycle::Start
+    }
+
+    /// Title of the group of containers changed
+    pub fn title(self) -> &'static str {
+        match self {
+            Lifecycle::Stop => "Stopping containers",
+            Lifecycle::Start => "Starting containers",
+            Lifecycle::Pause => "Pausing containers",
+            Lifecycle::Unpause => "Unpausing containers",
+        }
+    }
+
+    /// Progress message for a single container, with {name} as placeholder
+    pub fn progress(self) -> &'static str {
+        match self {
+            Lifecycle::Stop => "Stopping container: {name}",
+            Lifecycle::Start => "Starting container: {name}",
+            Lifecycle::Pause => "Pausing container: {name}",
+            Lifecycle::Unpause => "Unpausing container: {name}",
+        }
+    }
+
+    /// Error listing the containers that failed, with {containers} as placeholder
+    pub fn failure(self) -> &'static str {
+        match self {
+            Lifecycle::Stop => "Failed to stop: {containers}",
+            Lifecycle::Start => "Failed to start: {containers}",
+            Lifecycle::Pause => "Failed to pause: {containers}",
+            Lifecycle::Unpause => "Failed to unpause: {containers}",
+        }
+    }
+
+    /// Key of the count of changed containers in CI outputs
+    pub fn output_key(self) -> &'static str {
+        match self {
+            Lifecycle::Stop => "containers_stopped",
+            Lifecycle::Start => "containers_started",
+            Lifecycle::Pause => "containers_paused",
+            Lifecycle::Unpause => "containers_unpaused",
+        }
+    }
+
+    /// Runs the docker command for a container, giving up once timeout has passed.
+    ///
+    /// Errors are returned when docker could not be run at all, while the inner error is what
+    /// docker reported for the container.
+    pub fn apply(self, container: &str, timeout: Duration) -> anyhow::Result<Result<(), String>> {
+        let mut child = Command::new(DOCKER)
+            .args(self.docker_args(container, timeout))
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to run docker {}", self.docker_command()))?;
+
+        // docker kills a container ignoring stop by itself, other commands have no such limit
+        let deadline = match self {
+            Lifecycle::Stop => timeout + KILL_GRACE,
+            _ => timeout,
+        };
+        let start = Instant::now();
+
+        let status = loop {
+            if let Some(status) = child.try_wait().context("Failed to wait for docker")? {
+                break status;
+            }
+
+            if start.elapsed() >= deadline {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Ok(Err(tr_args(
+                    "timed out after {timeout}",
+                    &[("timeout", &format_duration(deadline))],
+                )));
+            }
+
+            std::thread::sleep(POLL_INTERVAL);
+        };
+
+        if status.success() {
+            return Ok(Ok(()));
+        }
+
+        let mut error = String::new();
+        if let Some(mut stderr) = child.stderr.take() {
+            let _ = stderr.read_to_string(&mut error);
+        }
+
+        Ok(Err(error.trim().to_string()))
+    }
+}
@@ -0,0 +1,317 @@
+use crate::commands::DOCKER;
+use crate::config::state_dir;
+use crate::docker::list_all_containers;
+use crate::parsers::parse_log_configs;
+use crate::units::parse_size;
+use anyhow::Context;
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const LOGGING_OVERRIDE_DIR: &str = "logging";
+
+/// Where applications usually write log files
+const LOG_FILE_PATHS: [&str; 8] = [
+    "/var/log",
+    "/logs",
+    "/log",
+    "/app/logs",
+    "/app/log",
+    "/config/logs",
+    "/config/log",
+    "/data/logs",
+];
+/// Log files have to be written to within this many minutes to count
+const LOG_FILE_MAX_AGE_MINUTES: u32 = 60;
+/// Log files sampled per container, the most recently written ones are as good as any
+const LOG_FILES_SAMPLED: usize = 5;
+/// Lines sampled from the end of stdout and of each log file
+const SAMPLE_LINES: usize = 200;
+/// Lines of a log file that have to show up on stdout as well to call it double logging
+const MIN_SHARED_LINES: usize = 3;
+
+/// A log file in a volume receiving the same lines as stdout
+#[derive(Debug, Clone, PartialEq)]
+pub struct DoubleLogging {
+    pub container: String,
+    pub path: String,
+    /// Lines of the sample of the file also found on stdout
+    pub shared_lines: usize,
+    /// Lines written to stdout and stderr during the last hour
+    pub stdout_lines: usize,
+}
+
+/// Log driver settings of a container
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogConfig {
+    pub container: String,
+    /// Compose project the container belongs to
+    pub stack: Option<String>,
+    /// Compose service the container was created for
+    pub service: Option<String>,
+    pub driver: String,
+    pub max_size: Option<String>,
+    pub max_file: Option<String>,
+    /// Log file of the json-file and local log drivers, empty for other drivers
+    pub log_path: String,
+    pub running: bool,
+    /// Paths volumes and bind mounts are mounted at inside the container
+    pub mounts: Vec<String>,
+}
+
+impl LogConfig {
+    /// Whether docker keeps the logs from growing forever. Only json-file, the default driver,
+    /// keeps everything unless given a max-size, local rotates by default and the other drivers
+    /// hand logs off the host.
+    pub fn is_rotated(&self) -> bool {
+        self.driver != "json-file" || self.max_size.is_some()
+    }
+
+    /// Rotation settings for display, such as max-size=10m max-file=3
+    pub fn rotation(&self) -> Option<String> {
+        let options = [("max-size", &self.max_size), ("max-file", &self.max_file)]
+            .iter()
+            .filter_map(|(name, value)| value.as_ref().map(|value| format!("{name}={value}")))
+            .collect::<Vec<String>>();
+
+        (!options.is_empty()).then(|| options.join(" "))
+    }
+}
+
+/// Validates a max-size for json-file logs such as 10m, keeping it as docker expects it
+pub fn parse_max_size(input: &str) -> anyhow::Result<String> {
+    if parse_size(input)? == 0 {
+        anyhow::bail!("Log max-size must not be zero: {input}");
+    }
+
+    Ok(input.to_string())
+}
+
+/// Inspects the log settings of all containers, including stopped ones
+pub fn inspect_log_configs() -> anyhow::Result<Vec<LogConfig>> {
+    let ids = list_all_containers()?
+        .into_iter()
+        .map(|container| container.id)
+        .collect::<Vec<String>>();
+
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let output = Command::new(DOCKER)
+        .arg("inspect")
+        .args(&ids)
+        .output()
+        .context("Failed to inspect containers")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to inspect containers: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    parse_log_configs(&output.stdout)
+}
+
+/// Size of a log file and the files rotated out of it, such as container-json.log.1, or None
+/// when it cannot be read, as log files are usually only readable by root
+pub fn log_files_size(log_path: &str) -> Option<u64> {
+    let path = Path::new(log_path);
+    let (dir, file_name) = (path.parent()?, path.file_name()?.to_str()?);
+
+    let mut size = std::fs::metadata(path).ok()?.len();
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let name = entry.file_name();
+        let is_rotated = name
+            .to_str()
+            .and_then(|name| name.strip_prefix(file_name))
+            .is_some_and(|suffix| suffix.starts_with('.'));
+
+        if is_rotated {
+            size += entry.metadata().map_or(0, |metadata| metadata.len());
+        }
+    }
+
+    Some(size)
+}
+
+/// Adds json-file logging with rotation for services to a compose override, keeping the
+/// services an earlier override already covers
+pub fn render_logging_override(
+    existing: Option<&str>,
+    services: &[String],
+    max_size: &str,
+    max_file: u32,
+) -> anyhow::Result<String> {
+    let mut document = match existing {
+        Some(existing) => serde_yaml::from_str::<Value>(existing)
+            .context("Failed to parse existing logging override")?,
+        None => Value::Null,
+    };
+
+    if !document["services"].is_object() {
+        document = serde_json::json!({ "services": {} });
+    }
+
+    for service in services {
+        document["services"][service] = serde_json::json!({
+            "logging": {
+                "driver": "json-file",
+                "options": {
+                    "max-size": max_size,
+                    // compose expects option values as strings
+                    "max-file": max_file.to_string(),
+                },
+            },
+        });
+    }
+
+    serde_yaml::to_string(&document).context("Failed to render logging override")
+}
+
+/// Writes the logging override of a project to the state directory, returning its path.
+///
+/// Like deploy overrides, the file is kept so later recreates of the project keep rotating.
+pub fn write_logging_override(
+    project: &str,
+    services: &[String],
+    max_size: &str,
+    max_file: u32,
+) -> anyhow::Result<PathBuf> {
+    let dir = state_dir()?.join(LOGGING_OVERRIDE_DIR);
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+    let path = dir.join(format!("{project}.override.yml"));
+    let existing = std::fs::read_to_string(&path).ok();
+
+    std::fs::write(
+        &path,
+        render_logging_override(existing.as_deref(), services, max_size, max_file)?,
+    )
+    .with_context(|| format!("Failed to write compose override: {}", path.display()))?;
+
+    Ok(path)
+}
+
+/// Whether a path inside a container is stored on a volume or bind mount rather than in the
+/// container layer
+pub fn is_on_mount(path: &str, mounts: &[String]) -> bool {
+    mounts.iter().any(|mount| {
+        let mount = mount.trim_end_matches('/');
+        mount.is_empty()
+            || path
+                .strip_prefix(mount)
+                .is_some_and(|rest| rest.starts_with('/'))
+    })
+}
+
+/// Number of distinct non-empty lines of a log file sample also found on stdout
+pub fn shared_lines(stdout: &[String], file: &[String]) -> usize {
+    let stdout = stdout
+        .iter()
+        .map(|line| line.trim())
+        .collect::<BTreeSet<&str>>();
+
+    file.iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<BTreeSet<&str>>()
+        .into_iter()
+        .filter(|line| stdout.contains(line))
+        .count()
+}
+
+/// Looks for log files in volumes of a running container that repeat what it writes to stdout.
+///
+/// This runs `find` and `tail` inside the container, so images without a shell are skipped.
+pub fn find_double_logging(config: &LogConfig) -> anyhow::Result<Vec<DoubleLogging>> {
+    // stdout volume, which quiet containers do not need their files checked for
+    let stdout = docker_logs(&config.container, &["--since", "1h"])?;
+    if stdout.is_empty() || config.mounts.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let script = format!(
+        "find {} -type f -mmin -{LOG_FILE_MAX_AGE_MINUTES} -size +0 2>/dev/null",
+        LOG_FILE_PATHS.join(" ")
+    );
+    let Some(files) = exec_lines(&config.container, &["sh", "-c", &script]) else {
+        return Ok(vec![]);
+    };
+
+    let recent = docker_logs(&config.container, &["--tail", &SAMPLE_LINES.to_string()])?;
+    let mut found = vec![];
+
+    for path in files
+        .iter()
+        .filter(|path| is_on_mount(path, &config.mounts))
+        .take(LOG_FILES_SAMPLED)
+    {
+        let Some(sample) = exec_lines(
+            &config.container,
+            &["tail", "-n", &SAMPLE_LINES.to_string(), path],
+        ) else {
+            continue;
+        };
+
+        let shared = shared_lines(&recent, &sample);
+        if shared >= MIN_SHARED_LINES {
+            found.push(DoubleLogging {
+                container: config.container.to_string(),
+                path: path.to_string(),
+                shared_lines: shared,
+                stdout_lines: stdout.len(),
+            });
+        }
+    }
+
+    Ok(found)
+}
+
+/// Lines a container logged to stdout and stderr
+fn docker_logs(container: &str, args: &[&str]) -> anyhow::Result<Vec<String>> {
+    let output = Command::new(DOCKER)
+        .arg("logs")
+        .args(args)
+        .arg(container)
+        .output()
+        .with_context(|| format!("Failed to read logs of {container}"))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to read logs of {container}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .chain(String::from_utf8_lossy(&output.stderr).lines())
+        .map(String::from)
+        .collect())
+}
+
+/// Output lines of a command run inside a container, or None when it cannot run there
+fn exec_lines(container: &str, command: &[&str]) -> Option<Vec<String>> {
+    let output = Command::new(DOCKER)
+        .arg("exec")
+        .arg(container)
+        .args(command)
+        .output()
+        .ok()?;
+
+    // find exits with an error when some of the paths do not exist, which most will not
+    if output.stdout.is_empty() && !output.status.success() {
+        return None;
+    }
+
+    Some(
+        String::from_utf8_lossy(&output.stdout)
+            .lines()
+            .map(String::from)
+            .collect(),
+    )
+}
@@ -0,0 +1,1138 @@
+use crate::commands::DOCKER;
+use crate::i18n::tr_args;
+use crate::printer::{color_println_fmt, container_color, role_color, Role};
+use crate::ship::{LogShipper, ShipTarget};
+use crate::shutdown::{shutdown_requested, SHUTDOWN_POLL_INTERVAL};
+use crate::units::{format_duration, parse_duration};
+use crate::utils::{get_timestamp, line_matches};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::io::Read;
+use std::os::fd::{AsRawFd, RawFd};
+use std::process::{Child, Command, Stdio};
+use std::task::Poll;
+use std::time::{Duration, Instant};
+
+/// Bytes read from a log stream at once
+const READ_BUFFER_SIZE: usize = 8192;
+
+/// Template of merged log lines unless another one is given
+pub const DEFAULT_LOG_TEMPLATE: &str = "[{time} | {container}] {line}";
+
+/// Which lines of the container logs to show
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogWindow {
+    /// Number of lines to show from the end of the logs, all lines when None
+    pub tail: Option<u32>,
+    /// Only show lines since this time, as accepted by `docker logs --since`
+    pub since: Option<String>,
+    /// Only show lines until this time, as accepted by `docker logs --until`
+    pub until: Option<String>,
+    /// Keep following new lines instead of exiting after the existing ones
+    pub follow: bool,
+    /// Containers read at once when not following, all of them when None
+    pub jobs: Option<usize>,
+    /// Only show lines of at least this severity, dropping lines without a known one
+    pub min_level: Option<LogLevel>,
+    /// Collapse repeated lines or limit the lines of each container
+    pub throttle: Option<LogThrottle>,
+}
+
+impl Default for LogWindow {
+    fn default() -> Self {
+        LogWindow {
+            tail: Some(100),
+            since: None,
+            until: None,
+            follow: true,
+            jobs: None,
+            min_level: None,
+            throttle: None,
+        }
+    }
+}
+
+impl LogWindow {
+    /// Arguments for `docker logs`
+    fn docker_args(&self) -> Vec<String> {
+        let mut args = vec![
+            "--tail".to_string(),
+            self.tail
+                .map(|tail| tail.to_string())
+                .unwrap_or_else(|| "all".to_string()),
+        ];
+
+        if let Some(since) = &self.since {
+            args.extend(["--since".to_string(), since.to_string()]);
+        }
+
+        if let Some(until) = &self.until {
+            args.extend(["--until".to_string(), until.to_string()]);
+        }
+
+        if self.follow {
+            args.push("--follow".to_string());
+        }
+
+        args
+    }
+
+    /// Whether a line is severe enough to show
+    pub fn shows_level(&self, line: &str) -> bool {
+        match self.min_level {
+            Some(min_level) => LogLevel::detect(line).is_some_and(|level| level >= min_level),
+            None => true,
+        }
+    }
+}
+
+/// Words of a line searched for a level such as ERROR, as later ones are usually the message
+const LEVEL_WORDS: usize = 6;
+
+/// Severity of a log line, from the least to the most severe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+    Fatal,
+}
+
+impl LogLevel {
+    /// Parses a level name as logging libraries write it, ignoring case
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "trace" | "trc" => Some(LogLevel::Trace),
+            "debug" | "dbg" => Some(LogLevel::Debug),
+            "info" | "inf" | "information" | "notice" => Some(LogLevel::Info),
+            "warn" | "wrn" | "warning" => Some(LogLevel::Warn),
+            "error" | "err" | "eror" => Some(LogLevel::Error),
+            "fatal" | "ftl" | "critical" | "crit" | "panic" | "emerg" | "alert" => {
+                Some(LogLevel::Fatal)
+            }
+            _ => None,
+        }
+    }
+
+    /// Maps the numeric levels of pino and bunyan
+    fn from_number(number: u64) -> Option<Self> {
+        match number {
+            10 => Some(LogLevel::Trace),
+            20 => Some(LogLevel::Debug),
+            30 => Some(LogLevel::Info),
+            40 => Some(LogLevel::Warn),
+            50 => Some(LogLevel::Error),
+            60 => Some(LogLevel::Fatal),
+            _ => None,
+        }
+    }
+
+    /// Finds the level of a line, from a `level` field of JSON lines, a logfmt `level=` pair or a
+    /// word such as ERROR or [warn] near the start of the line
+    pub fn detect(line: &str) -> Option<Self> {
+        let trimmed = line.trim();
+
+        let json = trimmed
+            .starts_with('{')
+            .then(|| serde_json::from_str::<serde_json::Value>(trimmed).ok())
+            .flatten();
+
+        if let Some(serde_json::Value::Object(fields)) = json {
+            return ["level", "lvl", "severity", "log.level"]
+                .iter()
+                .filter_map(|key| fields.get(*key))
+                .find_map(|value| match value {
+                    serde_json::Value::String(name) => LogLevel::from_name(name),
+                    serde_json::Value::Number(number) => {
+                        number.as_u64().and_then(LogLevel::from_number)
+                    }
+                    _ => None,
+                });
+        }
+
+        let words = trimmed.split_whitespace().collect::<Vec<&str>>();
+
+        let logfmt = words.iter().find_map(|word| {
+            let value = word
+                .strip_prefix("level=")
+                .or_else(|| word.strip_prefix("lvl="))?;
+            LogLevel::from_name(value.trim_matches('"'))
+        });
+        if logfmt.is_some() {
+            return logfmt;
+        }
+
+        words.iter().take(LEVEL_WORDS).find_map(|word| {
+            let bracketed = word.starts_with('[') || word.starts_with('<');
+            let name = word.trim_matches(|c: char| !c.is_ascii_alphanumeric());
+
+            // lowercase words only count in brackets, so messages such as "no error" do not
+            let shouted = name.chars().any(|c| c.is_ascii_uppercase())
+                && !name.chars().any(|c| c.is_ascii_lowercase());
+            if shouted || bracketed {
+                LogLevel::from_name(name)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// Tag of the level shown by the {level} placeholder
+    pub fn tag(self) -> &'static str {
+        match self {
+            LogLevel::Trace => "TRACE",
+            LogLevel::Debug => "DEBUG",
+            LogLevel::Info => "INFO",
+            LogLevel::Warn => "WARN",
+            LogLevel::Error => "ERROR",
+            LogLevel::Fatal => "FATAL",
+        }
+    }
+
+    /// Color lines of this level are shown in, None for levels that are not a problem
+    fn role(self) -> Option<Role> {
+        match self {
+            LogLevel::Warn => Some(Role::Warning),
+            LogLevel::Error | LogLevel::Fatal => Some(Role::Error),
+            _ => None,
+        }
+    }
+}
+
+/// Where lines go besides stdout
+#[derive(Debug, Clone, Default)]
+pub struct LogOutputs {
+    /// Ring the bell and raise a desktop notification for matching lines
+    pub bell: Option<LogAlert>,
+    /// Endpoints every line shown is forwarded to, labeled with its container and stack
+    pub ship: Vec<ShipTarget>,
+}
+
+/// Collapses repeated lines and limits the lines of each container, so a container in a crash
+/// loop does not drown out the others
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogThrottle {
+    /// Collapse lines repeating the line before them into a count
+    pub dedupe: bool,
+    /// Lines passed on per container and window, further ones only being counted
+    pub max_lines: Option<usize>,
+    /// Window of the line limit, and how often counts of repeated lines are shown
+    pub window: Duration,
+}
+
+/// Lines of a container held back so far
+#[derive(Debug, Default)]
+struct ThrottleState {
+    last_line: Option<String>,
+    repeats: usize,
+    /// Start of the current window, None before the first line
+    window_start: Option<Instant>,
+    /// Lines passed on in the current window
+    lines: usize,
+    suppressed: usize,
+}
+
+/// Applies a throttle to the lines of each container, telling how many it held back
+#[derive(Debug)]
+pub struct Throttler {
+    throttle: LogThrottle,
+    containers: BTreeMap<String, ThrottleState>,
+}
+
+impl Throttler {
+    pub fn new(throttle: LogThrottle) -> Self {
+        Throttler {
+            throttle,
+            containers: BTreeMap::new(),
+        }
+    }
+
+    /// Whether to pass on a line, along with notices of lines held back before it
+    pub fn admit(&mut self, container: &str, line: &str, now: Instant) -> (bool, Vec<String>) {
+        let throttle = &self.throttle;
+        let state = self.containers.entry(container.to_string()).or_default();
+        let mut notices = vec![];
+
+        if state
+            .window_start
+            .is_none_or(|start| now.duration_since(start) >= throttle.window)
+        {
+            notices.extend(state.report(throttle));
+            state.window_start = Some(now);
+            state.lines = 0;
+        }
+
+        if throttle.dedupe && state.last_line.as_deref() == Some(line) {
+            state.repeats += 1;
+            return (false, notices);
+        }
+
+        notices.extend(state.take_repeats());
+        state.last_line = throttle.dedupe.then(|| line.to_string());
+
+        if throttle
+            .max_lines
+            .is_some_and(|max_lines| state.lines >= max_lines)
+        {
+            state.suppressed += 1;
+            return (false, notices);
+        }
+        state.lines += 1;
+
+        (true, notices)
+    }
+
+    /// Notices of containers whose window ended with lines held back, or of all containers
+    /// holding back lines when flushing at the end
+    pub fn due(&mut self, now: Instant, flush: bool) -> Vec<(String, String)> {
+        let mut due = vec![];
+
+        for (container, state) in &mut self.containers {
+            let ended = state
+                .window_start
+                .is_some_and(|start| now.duration_since(start) >= self.throttle.window);
+            if !ended && !flush {
+                continue;
+            }
+
+            let notices = state.report(&self.throttle);
+            if !notices.is_empty() {
+                // the next line starts a new window
+                state.window_start = None;
+            }
+            due.extend(
+                notices
+                    .into_iter()
+                    .map(|notice| (container.to_string(), notice)),
+            );
+        }
+
+        due
+    }
+}
+
+impl ThrottleState {
+    /// Notices of the lines held back in the window so far, starting to count again
+    fn report(&mut self, throttle: &LogThrottle) -> Vec<String> {
+        let mut notices = vec![];
+
+        if self.suppressed > 0 {
+            notices.push(tr_args(
+                "{count} lines suppressed, more than {limit} per {window}",
+                &[
+                    ("count", &self.suppressed),
+                    ("limit", &throttle.max_lines.unwrap_or_default()),
+                    ("window", &format_duration(throttle.window)),
+                ],
+            ));
+            self.suppressed = 0;
+        }
+        // repeats go on being collapsed, as the last line stays
+        notices.extend(self.take_repeats());
+
+        notices
+    }
+
+    fn take_repeats(&mut self) -> Option<String> {
+        let repeats = std::mem::take(&mut self.repeats);
+
+        (repeats > 0).then(|| {
+            tr_args(
+                "last message repeated {count} times",
+                &[("count", &repeats)],
+            )
+        })
+    }
+}
+
+/// Parses a level for --min-level, such as warn or error
+pub fn parse_log_level(input: &str) -> anyhow::Result<LogLevel> {
+    LogLevel::from_name(input).with_context(|| {
+        format!("Invalid log level: {input}, expected trace, debug, info, warn, error or fatal")
+    })
+}
+
+/// Parses a time for --since or --until, either a duration ago such as 2h or an RFC3339 timestamp,
+/// into a form docker logs understands
+pub fn parse_log_time(input: &str) -> anyhow::Result<String> {
+    if DateTime::parse_from_rfc3339(input).is_ok() {
+        return Ok(input.to_string());
+    }
+
+    // docker does not understand days, so pass on seconds
+    let duration = parse_duration(input).with_context(|| {
+        format!("Invalid time: {input}, expected a duration such as 2h or an RFC3339 timestamp")
+    })?;
+
+    Ok(format!("{}s", duration.as_secs()))
+}
+
+/// How the time of a log line is shown
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum TimestampMode {
+    /// Date and time the line arrived
+    #[default]
+    Absolute,
+    /// Time since following started, e.g. +00:01:02.345
+    Relative,
+}
+
+/// How merged log lines are rendered
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFormat {
+    /// Template with {time}, {level}, {container} and {line} placeholders
+    pub template: String,
+    pub timestamps: TimestampMode,
+    /// Show absolute timestamps in UTC instead of local time
+    pub utc: bool,
+    /// Pad container names to the same width so lines align in columns
+    pub align: bool,
+    /// Give each container name its own color instead of all being green
+    pub container_colors: bool,
+    /// Show JSON lines indented or only some of their fields, other lines staying as they are
+    pub json: Option<JsonMode>,
+    /// Join lines not starting a record, such as the frames of a stack trace, to the one before
+    pub multiline: Option<RecordStart>,
+}
+
+/// Start of a record spanning several lines, such as leading whitespace
+pub const DEFAULT_RECORD_START: &str = r"^\S";
+/// Time a record waits for more continuation lines before it is passed on
+const RECORD_IDLE_TIMEOUT: Duration = Duration::from_millis(100);
+/// Lines after which a record is passed on even when more continuation lines follow
+const MAX_RECORD_LINES: usize = 1000;
+
+/// Pattern of the lines starting a log record, any other line continuing the record before it
+#[derive(Debug, Clone)]
+pub struct RecordStart(Regex);
+
+impl PartialEq for RecordStart {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl RecordStart {
+    /// Parses a regex such as `^\d{4}-\d{2}-\d{2}` for records starting with a date
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        Ok(RecordStart(Regex::new(input).with_context(|| {
+            format!("Invalid record start: {input}")
+        })?))
+    }
+
+    pub fn matches(&self, line: &str) -> bool {
+        self.0.is_match(line)
+    }
+}
+
+/// Lines of a log record read so far, passed on once the next record starts
+#[derive(Debug, Default)]
+pub struct RecordBuffer {
+    lines: Vec<String>,
+    /// When the last line arrived
+    updated: Option<Instant>,
+}
+
+impl RecordBuffer {
+    /// Adds a line, returning the previous record when the line starts a new one
+    pub fn push(&mut self, line: String, start: &RecordStart, now: Instant) -> Option<String> {
+        let done = if start.matches(&line) || self.lines.len() >= MAX_RECORD_LINES {
+            self.flush()
+        } else {
+            None
+        };
+
+        self.lines.push(line);
+        self.updated = Some(now);
+
+        done
+    }
+
+    /// Whether the record waited long enough for more lines to be passed on as it is
+    pub fn is_idle(&self, now: Instant) -> bool {
+        self.updated
+            .is_some_and(|updated| now.duration_since(updated) >= RECORD_IDLE_TIMEOUT)
+    }
+
+    /// Takes the record read so far, its lines joined by newlines
+    pub fn flush(&mut self) -> Option<String> {
+        self.updated = None;
+        if self.lines.is_empty() {
+            return None;
+        }
+
+        Some(std::mem::take(&mut self.lines).join("\n"))
+    }
+}
+
+/// How JSON log lines are shown
+#[derive(Debug, Clone, PartialEq)]
+pub enum JsonMode {
+    /// Indented over several lines
+    Pretty,
+    /// Only the values of these fields, such as ts, level and msg, with dots reaching into
+    /// nested objects as in http.status
+    Fields(Vec<String>),
+}
+
+impl JsonMode {
+    /// Renders a JSON line, or None when the line is not a JSON object or has none of the fields
+    pub fn render(&self, line: &str) -> Option<String> {
+        let trimmed = line.trim();
+        if !trimmed.starts_with('{') {
+            return None;
+        }
+        let value = serde_json::from_str::<serde_json::Value>(trimmed).ok()?;
+        if !value.is_object() {
+            return None;
+        }
+
+        match self {
+            JsonMode::Pretty => serde_json::to_string_pretty(&value).ok(),
+            JsonMode::Fields(fields) => {
+                let values = fields
+                    .iter()
+                    .filter_map(|field| {
+                        field
+                            .split('.')
+                            .try_fold(&value, |value, key| value.get(key))
+                    })
+                    .map(|value| match value {
+                        // strings are shown without quotes
+                        serde_json::Value::String(text) => text.to_string(),
+                        value => value.to_string(),
+                    })
+                    .collect::<Vec<String>>();
+
+                (!values.is_empty()).then(|| values.join(" "))
+            }
+        }
+    }
+}
+
+impl Default for LogFormat {
+    fn default() -> Self {
+        LogFormat {
+            template: DEFAULT_LOG_TEMPLATE.to_string(),
+            timestamps: TimestampMode::default(),
+            utc: false,
+            align: false,
+            container_colors: true,
+            json: None,
+            multiline: None,
+        }
+    }
+}
+
+impl LogFormat {
+    /// Renders a log line, given the time since following started and the width of container names
+    pub fn render(
+        &self,
+        container: &str,
+        line: &str,
+        elapsed: Duration,
+        name_width: usize,
+        use_color: bool,
+    ) -> String {
+        let time = match self.timestamps {
+            TimestampMode::Absolute if self.utc => {
+                Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+            }
+            TimestampMode::Absolute => get_timestamp(),
+            TimestampMode::Relative => format_elapsed(elapsed),
+        };
+
+        // pad before coloring, as escape codes do not take up space
+        let container_name = if self.align {
+            format!("{container:<name_width$}")
+        } else {
+            container.to_string()
+        };
+
+        let level = LogLevel::detect(line);
+        let line = match &self.json {
+            Some(json) => json.render(line).unwrap_or_else(|| line.to_string()),
+            None => line.to_string(),
+        };
+        // pad to the longest tag, so lines without a level keep the columns as well
+        let tag = match (level, self.align) {
+            (Some(level), true) => format!("{:<5}", level.tag()),
+            (Some(level), false) => level.tag().to_string(),
+            (None, true) => " ".repeat(5),
+            (None, false) => String::new(),
+        };
+
+        // warnings and errors stand out from the rest of the stream
+        let (tag, line) = match level.and_then(LogLevel::role) {
+            Some(role) if use_color => (
+                color_println_fmt(role_color(role), &tag),
+                color_println_fmt(role_color(role), &line),
+            ),
+            _ => (tag, line),
+        };
+
+        let (time, container_name) = if use_color {
+            let color = if self.container_colors {
+                container_color(container)
+            } else {
+                role_color(Role::Container)
+            };
+            (
+                color_println_fmt(role_color(Role::Timestamp), &time),
+                color_println_fmt(color, &container_name),
+            )
+        } else {
+            (time, container_name)
+        };
+
+        // substitute in a single pass so placeholders inside the line itself stay untouched
+        let mut rendered = String::new();
+        let mut rest = self.template.as_str();
+
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            rest = &rest[start..];
+
+            let value = [
+                ("{time}", time.as_str()),
+                ("{level}", tag.as_str()),
+                ("{container}", container_name.as_str()),
+                ("{line}", line.as_str()),
+            ]
+            .into_iter()
+            .find(|(placeholder, _)| rest.starts_with(placeholder));
+
+            match value {
+                Some((placeholder, value)) => {
+                    rendered.push_str(value);
+                    rest = &rest[placeholder.len()..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+
+        rendered
+    }
+}
+
+/// Formats time since following started as +HH:MM:SS.mmm
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+
+    format!(
+        "+{:02}:{:02}:{:02}.{:03}",
+        secs / 3600,
+        secs / 60 % 60,
+        secs % 60,
+        elapsed.subsec_millis()
+    )
+}
+
+/// Rings the terminal bell and raises a desktop notification for log lines matching a pattern
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogAlert {
+    /// Alternatives of the pattern, matched ignoring case like --grep
+    patterns: Vec<String>,
+    /// When each container last raised an alert, so a burst of matches only raises one
+    last_alerts: HashMap<String, Instant>,
+}
+
+/// Time a container has to stay quiet after an alert before raising another
+const ALERT_COOLDOWN: Duration = Duration::from_secs(10);
+/// Characters of the line shown in a desktop notification
+const ALERT_BODY_LENGTH: usize = 200;
+
+impl LogAlert {
+    /// Parses alternatives separated by `|`, such as PANIC|FATAL
+    pub fn parse(input: &str) -> anyhow::Result<Self> {
+        let patterns = input
+            .split('|')
+            .filter(|pattern| !pattern.is_empty())
+            .map(String::from)
+            .collect::<Vec<String>>();
+
+        if patterns.is_empty() {
+            anyhow::bail!("Invalid bell pattern: {input}");
+        }
+
+        Ok(LogAlert {
+            patterns,
+            last_alerts: HashMap::new(),
+        })
+    }
+
+    pub fn matches(&self, line: &str) -> bool {
+        self.patterns
+            .iter()
+            .any(|pattern| line_matches(line, Some(pattern)))
+    }
+
+    /// Escape sequences alerting of a line, or None when it does not match or the container
+    /// alerted moments ago
+    pub fn check(&mut self, container: &str, line: &str, now: Instant) -> Option<String> {
+        if !self.matches(line) {
+            return None;
+        }
+
+        if self
+            .last_alerts
+            .get(container)
+            .is_some_and(|last| now.duration_since(*last) < ALERT_COOLDOWN)
+        {
+            return None;
+        }
+
+        self.last_alerts.insert(container.to_string(), now);
+        Some(alert_sequence(container, line))
+    }
+}
+
+/// Bell followed by OSC 9 and OSC 777 desktop notifications, which terminals without support
+/// for them ignore
+pub fn alert_sequence(container: &str, line: &str) -> String {
+    // control characters would end the sequence early and ; separates OSC 777 fields
+    let clean = |text: &str| {
+        text.chars()
+            .filter(|c| !c.is_control())
+            .map(|c| if c == ';' { ',' } else { c })
+            .take(ALERT_BODY_LENGTH)
+            .collect::<String>()
+    };
+    let (container, line) = (clean(container), clean(line));
+
+    format!("\x07\x1b]9;{container}: {line}\x1b\\\x1b]777;notify;{container};{line}\x1b\\")
+}
+
+/// A stdout or stderr pipe of a `docker logs --follow` process
+struct LogSource {
+    container: String,
+    reader: Box<dyn Read + Send>,
+    fd: RawFd,
+    /// Bytes read after the last complete line
+    partial: Vec<u8>,
+    /// Lines of a record waiting for its continuation lines
+    record: RecordBuffer,
+}
+
+/// Follows the logs of many containers from a single thread.
+///
+/// Instead of a thread per stream, one poll loop waits on the output pipes of all
+/// `docker logs --follow` processes, so resource usage stays flat with the number of containers.
+/// Following ends once all processes exit or shutdown is requested, killing the processes.
+pub struct LogMultiplexer {
+    containers: Vec<String>,
+    children: Vec<Child>,
+    sources: Vec<LogSource>,
+    /// Containers waiting for a slot when the number of running processes is limited
+    waiting: VecDeque<String>,
+    window: LogWindow,
+    pending: VecDeque<String>,
+    /// Lines passed on per container
+    line_counts: HashMap<String, usize>,
+    use_color: bool,
+    format: LogFormat,
+    grep: Option<String>,
+    /// Filter set while following, on top of grep
+    live_filter: Option<String>,
+    /// Containers whose lines are dropped while following
+    muted: HashSet<String>,
+    alert: Option<LogAlert>,
+    throttle: Option<Throttler>,
+    shipper: Option<LogShipper>,
+    started: Instant,
+    name_width: usize,
+}
+
+impl LogMultiplexer {
+    /// Starts following the logs of the given containers, which must be names, not ids.
+    ///
+    /// When the window limits the jobs of logs that are not followed, at most that many
+    /// `docker logs` processes run at once, the next container starting once one is done.
+    /// Lines passed on are also shipped to the given endpoints.
+    pub fn spawn(
+        containers: &[String],
+        window: &LogWindow,
+        use_color: bool,
+        format: LogFormat,
+        grep: Option<String>,
+        alert: Option<LogAlert>,
+        ship: Vec<ShipTarget>,
+    ) -> anyhow::Result<Self> {
+        let mut multiplexer = LogMultiplexer {
+            containers: containers.to_vec(),
+            children: vec![],
+            sources: vec![],
+            waiting: containers.iter().cloned().collect(),
+            window: window.clone(),
+            pending: VecDeque::new(),
+            line_counts: HashMap::new(),
+            use_color,
+            format,
+            grep,
+            live_filter: None,
+            muted: HashSet::new(),
+            alert,
+            throttle: window.throttle.clone().map(Throttler::new),
+            shipper: (!ship.is_empty())
+                .then(|| LogShipper::start(ship))
+                .transpose()?,
+            started: Instant::now(),
+            name_width: containers
+                .iter()
+                .map(|container| container.chars().count())
+                .max()
+                .unwrap_or_default(),
+        };
+
+        multiplexer.start_waiting();
+
+        Ok(multiplexer)
+    }
+
+    /// Lines passed on so far per container, after filtering
+    pub fn line_counts(&self) -> &HashMap<String, usize> {
+        &self.line_counts
+    }
+
+    /// Names of the containers followed
+    pub fn containers(&self) -> &[String] {
+        &self.containers
+    }
+
+    /// Only passes on lines containing this text from now on, ignoring case, or all lines
+    /// passing grep again when None
+    pub fn set_filter(&mut self, filter: Option<String>) {
+        self.live_filter = filter;
+    }
+
+    /// Drops the lines of a container from now on, or passes them on again, returning whether
+    /// it is muted now
+    pub fn toggle_mute(&mut self, container: &str) -> bool {
+        if self.muted.remove(container) {
+            return false;
+        }
+
+        self.muted.insert(container.to_string());
+        true
+    }
+
+    /// Starts `docker logs` for waiting containers while the job limit allows
+    fn start_waiting(&mut self) {
+        // reap processes that are done, so limited runs over many containers leave no zombies
+        self.children
+            .retain_mut(|child| !matches!(child.try_wait(), Ok(Some(_))));
+
+        loop {
+            let running = self
+                .sources
+                .iter()
+                .map(|source| source.container.as_str())
+                .collect::<HashSet<&str>>()
+                .len();
+
+            // followed logs never finish, so every container needs its process right away
+            let limit = self.window.jobs.filter(|_| !self.window.follow);
+            if limit.is_some_and(|jobs| running >= jobs.max(1)) {
+                return;
+            }
+
+            let Some(container) = self.waiting.pop_front() else {
+                return;
+            };
+
+            self.start(&container);
+        }
+    }
+
+    fn start(&mut self, container: &str) {
+        let child = Command::new(DOCKER)
+            .args(["logs", container])
+            .args(self.window.docker_args())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+
+        let mut child = match child {
+            Ok(child) => child,
+            Err(_) => {
+                let message = format!(
+                    "[ERROR] - {}",
+                    tr_args("Failed to log {container}", &[("container", &container)])
+                );
+                self.pending.push_back(if self.use_color {
+                    color_println_fmt(role_color(Role::Error), &message)
+                } else {
+                    message
+                });
+                return;
+            }
+        };
+
+        if let Some(stdout) = child.stdout.take() {
+            self.sources.push(LogSource {
+                container: container.to_string(),
+                fd: stdout.as_raw_fd(),
+                reader: Box::new(stdout),
+                partial: vec![],
+                record: RecordBuffer::default(),
+            });
+        }
+
+        if let Some(stderr) = child.stderr.take() {
+            self.sources.push(LogSource {
+                container: container.to_string(),
+                fd: stderr.as_raw_fd(),
+                reader: Box::new(stderr),
+                partial: vec![],
+                record: RecordBuffer::default(),
+            });
+        }
+
+        self.children.push(child);
+    }
+
+    /// Waits until at least one stream has output or closed, queueing complete lines
+    fn poll_sources(&mut self) -> anyhow::Result<()> {
+        let mut poll_fds = self
+            .sources
+            .iter()
+            .map(|source| libc::pollfd {
+                fd: source.fd,
+                events: libc::POLLIN,
+                revents: 0,
+            })
+            .collect::<Vec<libc::pollfd>>();
+
+        // wake up regularly to notice a requested shutdown, and sooner to pass on waiting records
+        let waiting_records = self
+            .sources
+            .iter()
+            .any(|source| source.record.updated.is_some());
+        let timeout = if waiting_records {
+            RECORD_IDLE_TIMEOUT
+        } else {
+            SHUTDOWN_POLL_INTERVAL
+        }
+        .as_millis() as libc::c_int;
+
+        // SAFETY: poll_fds is a valid, exclusively borrowed array of pollfd structs
+        // for the given length, and all fds stay open while their sources exist
+        let ready = unsafe { libc::poll(poll_fds.as_mut_ptr(), poll_fds.len() as _, timeout) };
+
+        if ready < 0 {
+            let error = std::io::Error::last_os_error();
+            if error.kind() == std::io::ErrorKind::Interrupted {
+                return Ok(());
+            }
+            return Err(error).context("Failed to wait for container logs");
+        }
+
+        let mut buffer = [0u8; READ_BUFFER_SIZE];
+        let mut closed = vec![];
+        let mut records = vec![];
+        let now = Instant::now();
+
+        for (index, poll_fd) in poll_fds.iter().enumerate() {
+            let source = &mut self.sources[index];
+
+            if poll_fd.revents == 0 {
+                if source.record.is_idle(now) {
+                    records.extend(source.record.flush().map(|record| (index, record)));
+                }
+                continue;
+            }
+
+            // a single read after poll never blocks, and returns 0 once the pipe is closed
+            let read = source.reader.read(&mut buffer).unwrap_or(0);
+
+            if read == 0 {
+                if !source.partial.is_empty() {
+                    source.partial.push(b'\n');
+                }
+                closed.push(index);
+            } else {
+                source.partial.extend_from_slice(&buffer[..read]);
+            }
+
+            while let Some(end) = source.partial.iter().position(|byte| *byte == b'\n') {
+                let line = source.partial.drain(..=end).collect::<Vec<u8>>();
+                let line = String::from_utf8_lossy(&line[..line.len() - 1])
+                    .trim_end_matches('\r')
+                    .to_string();
+
+                match &self.format.multiline {
+                    Some(start) => {
+                        records.extend(source.record.push(line, start, now).map(|r| (index, r)));
+                    }
+                    None => records.push((index, line)),
+                }
+            }
+
+            if read == 0 {
+                records.extend(source.record.flush().map(|record| (index, record)));
+            }
+        }
+
+        for (index, record) in records {
+            let container = self.sources[index].container.to_string();
+            self.pass_on(&container, &record);
+        }
+
+        self.pass_on_notices(false);
+
+        for index in closed.into_iter().rev() {
+            self.sources.remove(index);
+        }
+
+        self.start_waiting();
+
+        Ok(())
+    }
+
+    /// Queues a line or record that passes the filters, rendered
+    fn pass_on(&mut self, container: &str, line: &str) {
+        if !line_matches(line, self.grep.as_ref())
+            || !line_matches(line, self.live_filter.as_ref())
+            || !self.window.shows_level(line)
+            || self.muted.contains(container)
+        {
+            return;
+        }
+
+        if let Some(throttle) = &mut self.throttle {
+            let (admitted, notices) = throttle.admit(container, line, Instant::now());
+            for notice in notices {
+                self.pass_on_notice(container, &notice);
+            }
+            if !admitted {
+                return;
+            }
+        }
+
+        if let Some(shipper) = &mut self.shipper {
+            shipper.ship(container, line);
+        }
+
+        let rendered = self.format.render(
+            container,
+            line,
+            self.started.elapsed(),
+            self.name_width,
+            self.use_color,
+        );
+
+        // the alert goes out along with the line that raised it
+        let alert = self
+            .alert
+            .as_mut()
+            .and_then(|alert| alert.check(container, line, Instant::now()));
+        self.pending.push_back(match alert {
+            Some(alert) => format!("{alert}{rendered}"),
+            None => rendered,
+        });
+        *self.line_counts.entry(container.to_string()).or_default() += 1;
+    }
+
+    /// Queues notices of lines the throttle held back, of all containers when flushing at the end
+    fn pass_on_notices(&mut self, flush: bool) {
+        let due = match &mut self.throttle {
+            Some(throttle) => throttle.due(Instant::now(), flush),
+            None => return,
+        };
+
+        for (container, notice) in due {
+            self.pass_on_notice(&container, &notice);
+        }
+    }
+
+    /// Queues a notice rendered like a line of the container, bypassing filters and alerts
+    fn pass_on_notice(&mut self, container: &str, notice: &str) {
+        let rendered = self.format.render(
+            container,
+            &format!("[{notice}]"),
+            self.started.elapsed(),
+            self.name_width,
+            self.use_color,
+        );
+        self.pending.push_back(rendered);
+    }
+
+    /// Stops all `docker logs` processes, sending the lines still waiting to be shipped
+    pub fn shutdown(&mut self) {
+        self.sources.clear();
+        self.waiting.clear();
+
+        if let Some(shipper) = &mut self.shipper {
+            shipper.finish();
+        }
+
+        for child in &mut self.children {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+
+        self.children.clear();
+    }
+}
+
+impl LogMultiplexer {
+    /// Like `next`, but waits for output at most once, returning Pending when no line arrived
+    /// in the meantime so the caller can do other work such as reading keys
+    pub fn poll_next(&mut self) -> Poll<Option<String>> {
+        if let Some(line) = self.pending.pop_front() {
+            return Poll::Ready(Some(line));
+        }
+
+        if (self.sources.is_empty() && self.waiting.is_empty()) || shutdown_requested() {
+            // counts of lines held back go out before the end
+            self.pass_on_notices(true);
+            self.throttle = None;
+            if let Some(line) = self.pending.pop_front() {
+                return Poll::Ready(Some(line));
+            }
+
+            self.shutdown();
+            return Poll::Ready(None);
+        }
+
+        if let Err(e) = self.poll_sources() {
+            self.shutdown();
+            return Poll::Ready(Some(if self.use_color {
+                color_println_fmt(role_color(Role::Error), &format!("[ERROR] - {e:#}"))
+            } else {
+                format!("[ERROR] - {e:#}")
+            }));
+        }
+
+        match self.pending.pop_front() {
+            Some(line) => Poll::Ready(Some(line)),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl Iterator for LogMultiplexer {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        loop {
+            if let Poll::Ready(line) = self.poll_next() {
+                return line;
+            }
+        }
+    }
+}
+
+impl Drop for LogMultiplexer {
+    fn drop(&mut self) {
+        self.shutdown();
+    }
+}
@@ -1,18 +1,233 @@
-use clap::{Parser, Subcommand};
-use dsd_util::commands::{init, logs, nuke, restart, stats, update};
+use clap::{CommandFactory, FromArgMatches, Parser, Subcommand};
+use dsd_util::batch::exit_code;
+use dsd_util::ci::{set_ci, summary_line};
+use dsd_util::commands::{
+    bundle_export, bundle_import, check_docker, conflicts, cp, daemon_run, daemon_status,
+    daemon_trigger, daily_report, deploy, doctor, drift, env_vars, exec, exporter, graph, init,
+    licenses, lifecycle, log_audit, logs, maintenance_end, maintenance_start, maintenance_status,
+    mute_container, new, nuke, pin_digest, pkg_search, ports, probes_check, probes_history,
+    probes_schedule, prune, reboot, record_stats, report_stats, restart, rollback, sbom, scale,
+    secret_get, secret_list, secret_remove, secret_set, security_profiles, security_users,
+    self_update, stack_backup, stack_bump, stack_plan, stack_restore, stats, timeline, top,
+    unmute_container, unpin, update, view, wait_healthy, watch, UpdateOptions,
+};
+use dsd_util::config::set_state_dir;
+use dsd_util::context::{set_compose_selection, set_context_enabled, ComposeSelection};
+use dsd_util::cron::{parse_cron, CronSchedule};
+use dsd_util::deploy::{parse_image_override, ImageOverride};
+use dsd_util::document::DocumentFormat;
+use dsd_util::graph::GraphFormat;
+use dsd_util::lifecycle::Lifecycle;
+use dsd_util::logaudit::parse_max_size;
+use dsd_util::logger::{
+    parse_log_level, parse_log_time, JsonMode, LogAlert, LogFormat, LogLevel, LogOutputs,
+    LogThrottle, LogWindow, RecordStart, TimestampMode, DEFAULT_LOG_TEMPLATE, DEFAULT_RECORD_START,
+};
+use dsd_util::printer::{set_accessible, set_color_choice, set_deterministic, ColorChoice};
+use dsd_util::registry::{read_password_stdin, RegistryLogin};
+use dsd_util::scaffold::Template;
+use dsd_util::scale::{parse_service_scale, ServiceScale};
+use dsd_util::secrets::secret_value;
+use dsd_util::selector::set_fuzzy;
+use dsd_util::ship::{parse_ship_target, ShipTarget};
+use dsd_util::top::TopSort;
+use dsd_util::units::parse_duration;
+use dsd_util::utils::StatsSort;
+use std::path::PathBuf;
+use std::time::Instant;
 
 const DEFAULT_ARG_PROJECT_DIR: &str = "/var/lib/docker-stack-deploy";
-const DEFAULT_ARG_TAIL: &str = "100";
+const DEFAULT_TAIL: u32 = 100;
+const DEFAULT_ARG_JOBS: &str = "4";
+const DEFAULT_ARG_SINCE: &str = "24h";
+const DEFAULT_ARG_TIMELINE_SINCE: &str = "7d";
+const DEFAULT_ARG_THROTTLE_WINDOW: &str = "10s";
+const DEFAULT_ARG_TIMEOUT: &str = "5m";
+const DEFAULT_ARG_LIFECYCLE_TIMEOUT: &str = "30s";
+const DEFAULT_ARG_INTERVAL: &str = "2s";
+const DEFAULT_ARG_LISTEN: &str = "127.0.0.1:9333";
+const DEFAULT_ARG_DAEMON_LISTEN: &str = "127.0.0.1:9334";
+const DEFAULT_ARG_RECORD_INTERVAL: &str = "30s";
+const DEFAULT_ARG_MUTE_FOR: &str = "1h";
+const DEFAULT_ARG_LOG_MAX_SIZE: &str = "10m";
+const DEFAULT_ARG_LOG_MAX_FILE: &str = "3";
 
 #[derive(Debug, Parser)]
 #[command(version, about = "A simple helper for managing your docker-stack-deploy containers.", long_about = None)]
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    /// Do not default to the compose project found in the current directory
+    #[arg(long, global = true)]
+    no_context: bool,
+
+    /// Compose files used to resolve the project context, like docker compose -f
+    #[arg(long = "compose-file", global = true)]
+    compose_files: Vec<PathBuf>,
+
+    /// Compose profiles used to resolve the project context
+    #[arg(long = "profile", global = true)]
+    profiles: Vec<String>,
+
+    /// Env file used to interpolate the compose files of the project context
+    #[arg(long, global = true)]
+    env_file: Option<PathBuf>,
+
+    /// Screen reader friendly output without color, box drawing or tables
+    #[arg(long, global = true)]
+    a11y: bool,
+
+    /// Output for CI pipelines: no color or prompts, grouped output and a final JSON summary line
+    #[arg(long, global = true)]
+    ci: bool,
+
+    /// When to color output; NO_COLOR and CLICOLOR_FORCE are respected with auto
+    #[arg(long, global = true, value_enum, default_value_t = ColorChoice::Auto)]
+    color: ColorChoice,
+
+    /// Directory for history, snapshots and caches, such as one per remote docker host
+    #[arg(long, global = true)]
+    state_dir: Option<PathBuf>,
+
+    /// Use the closest container name for a misspelled one instead of only suggesting it
+    #[arg(long, global = true)]
+    fuzzy: bool,
+
+    /// Output to commit and diff: listings sorted by name, times in UTC and values changing from
+    /// run to run, such as usage and uptime, left out
+    #[arg(long, global = true)]
+    deterministic: bool,
 }
 
 #[derive(Debug, Subcommand)]
 enum Commands {
+    /// Find ports, container names and bind mounts that compose projects on the host would
+    /// fight over
+    Conflicts {
+        /// Also check the compose project in these directories, such as one not started yet
+        #[arg(short, long)]
+        dir: Vec<PathBuf>,
+    },
+
+    /// Copy files between the host and a container, given as stack/service:path or container:path
+    Cp {
+        /// Where to copy from, such as web/app:/etc/app.conf or ./backup
+        source: String,
+
+        /// Where to copy to, such as ./app.conf or web/app:/data
+        destination: String,
+
+        /// Copy the path from every container of the stack given as stack:path into a
+        /// directory per service
+        #[arg(long)]
+        all: bool,
+    },
+
+    /// Receive signed webhooks that pull and recreate allowed services
+    Daemon {
+        #[command(subcommand)]
+        action: DaemonAction,
+    },
+
+    /// Deploy new images for services of a stack and wait until it is healthy
+    Deploy {
+        /// Stack to deploy
+        #[arg(short, long)]
+        stack: String,
+
+        /// Image to deploy for a service, given as service=image
+        #[arg(short, long = "image", required = true, value_parser = parse_image_override)]
+        images: Vec<ImageOverride>,
+
+        /// How long to wait for the stack to become healthy, e.g. 90s, 5m
+        #[arg(short, long, default_value = DEFAULT_ARG_TIMEOUT, value_parser = parse_duration)]
+        timeout: std::time::Duration,
+
+        /// How often to check container health
+        #[arg(long, default_value = DEFAULT_ARG_INTERVAL, value_parser = parse_duration)]
+        interval: std::time::Duration,
+    },
+
+    /// Check that docker, its daemon and the compose plugin are usable and explain how to fix
+    /// what is not
+    Doctor,
+
+    /// List the environment of a container or stack with secrets masked, or compare two
+    /// containers of a service
+    Env {
+        /// Stack, or full or partial container name
+        target: String,
+
+        /// Only list the vars that differ from this container of the same service
+        #[arg(long, value_name = "CONTAINER")]
+        diff: Option<String>,
+
+        /// Show the values of vars named like secrets, such as DB_PASSWORD or API_TOKEN
+        #[arg(long)]
+        show_secrets: bool,
+    },
+
+    /// Run a command or an interactive shell in a running container, matched by partial name
+    Exec {
+        /// Full or partial container name, such as app for web-app-1
+        container: String,
+
+        /// Command to run, given after --, a shell when left out
+        #[arg(last = true)]
+        command: Vec<String>,
+    },
+
+    /// Report where the containers of a stack differ from its compose files
+    Drift {
+        /// Stack to compare against the compose files it was created from
+        stack: String,
+    },
+
+    /// Export config and state, such as rollback records and pins, to a bundle with secrets
+    /// redacted
+    ExportBundle {
+        /// Bundle to write, such as host.dspb
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+
+    /// Serve container stats as Prometheus metrics
+    Exporter {
+        /// Address to listen on
+        #[arg(short, long, default_value = DEFAULT_ARG_LISTEN)]
+        listen: String,
+    },
+
+    /// Show or export the container/network/volume graph of a stack
+    Graph {
+        /// Stack to graph
+        #[arg(required_unless_present = "stack_flag")]
+        stack: Option<String>,
+
+        /// Stack to graph
+        #[arg(short, long = "stack", conflicts_with = "stack")]
+        stack_flag: Option<String>,
+
+        /// Output format
+        #[arg(short, long, value_enum, default_value = "text")]
+        output: GraphFormat,
+
+        /// Draw the text graph and tree using plain ASCII instead of box-drawing characters
+        #[arg(long)]
+        ascii: bool,
+    },
+
+    /// Import config and state from a bundle written by export-bundle
+    ImportBundle {
+        /// Bundle to read
+        bundle: PathBuf,
+
+        /// Overwrite files that differ from the bundle
+        #[arg(short, long)]
+        force: bool,
+    },
+
     /// Initialize and bootstrap a new instance of docker-stack-deploy
     Init {
         /// Path where docker-stack-deploy compose file will be located
@@ -23,7 +238,24 @@ enum Commands {
         git_url: String,
     },
 
-    // TODO: Add more arg options for logs - since, filter, follow ?
+    /// Summarize licenses of packages in container images, flagging disallowed licenses
+    Licenses {
+        /// Report on specified containers
+        containers: Option<Vec<String>>,
+
+        /// Report on specified stacks
+        #[arg(short, long)]
+        stacks: Option<Vec<String>>,
+
+        /// Report on all containers
+        #[arg(short, long)]
+        all: bool,
+
+        /// Regenerate cached SBOMs
+        #[arg(short, long)]
+        refresh: bool,
+    },
+
     /// View container logs
     Logs {
         /// View logs for specified containers
@@ -33,21 +265,298 @@ enum Commands {
         #[arg(short, long)]
         stacks: Option<Vec<String>>,
 
-        /// Set the number of lines to show from end of logs
-        #[arg(short, long, default_value = DEFAULT_ARG_TAIL)]
-        tail: u32,
+        /// Set the number of lines to show from end of logs [default: 100, all with --since or --until]
+        #[arg(short, long)]
+        tail: Option<u32>,
+
+        /// Only show lines since a duration ago such as 2h, or an RFC3339 timestamp
+        #[arg(long, value_parser = parse_log_time)]
+        since: Option<String>,
+
+        /// Only show lines until a duration ago such as 30m, or an RFC3339 timestamp
+        #[arg(long, value_parser = parse_log_time)]
+        until: Option<String>,
+
+        /// Exit after showing the existing lines instead of following new ones
+        #[arg(long)]
+        no_follow: bool,
+
+        /// Number of containers to read logs of concurrently [default: all]
+        #[arg(short, long, requires = "no_follow")]
+        jobs: Option<usize>,
 
         /// View logs for all containers
         #[arg(short, long)]
         all: bool,
+
+        /// Only show lines containing this text, ignoring case
+        #[arg(short, long)]
+        grep: Option<String>,
+
+        /// Only show lines of at least this level, such as warn, detected from JSON level fields
+        /// and words such as ERROR
+        #[arg(long, value_parser = parse_log_level)]
+        min_level: Option<LogLevel>,
+
+        /// Collapse lines repeating the line before them into a count
+        #[arg(long)]
+        dedupe: bool,
+
+        /// Show at most this many lines per container every --throttle-window, counting the rest
+        #[arg(long)]
+        rate_limit: Option<usize>,
+
+        /// Window of --rate-limit, and how often counts of repeated lines are shown
+        #[arg(long, default_value = DEFAULT_ARG_THROTTLE_WINDOW, value_parser = parse_duration)]
+        throttle_window: std::time::Duration,
+
+        /// Show all container names in the same color
+        #[arg(long)]
+        no_container_colors: bool,
+
+        /// Show JSON lines indented, other lines as they are
+        #[arg(long)]
+        parse_json: bool,
+
+        /// Only show these fields of JSON lines, such as ts,level,msg, with dots reaching into
+        /// nested objects
+        #[arg(long, value_delimiter = ',', requires = "parse_json")]
+        fields: Vec<String>,
+
+        /// Join lines such as stack trace frames to the record they continue, records starting with
+        /// lines matching a regex given as --multiline='^\d{4}-' [default: '^\S', not indented]
+        #[arg(long, value_name = "REGEX", num_args = 0..=1, require_equals = true, default_missing_value = DEFAULT_RECORD_START, value_parser = RecordStart::parse)]
+        multiline: Option<RecordStart>,
+
+        /// Template of each line, using {time}, {level}, {container} and {line}
+        #[arg(long, default_value = DEFAULT_LOG_TEMPLATE)]
+        log_format: String,
+
+        /// Show the time each line arrived or the time since following started
+        #[arg(long, value_enum, default_value = "absolute")]
+        timestamps: TimestampMode,
+
+        /// Show absolute timestamps in UTC instead of local time
+        #[arg(long)]
+        utc: bool,
+
+        /// Pad container names to the same width so lines align in columns
+        #[arg(long)]
+        align: bool,
+
+        /// Ring the bell and raise a desktop notification for lines matching any alternative of
+        /// a pattern such as 'PANIC|FATAL', ignoring case
+        #[arg(long, value_parser = LogAlert::parse)]
+        bell: Option<LogAlert>,
+
+        /// Also forward each line to loki=http://host:3100 or syslog=udp://host:514, labeled
+        /// with its container and stack
+        #[arg(long, value_parser = parse_ship_target)]
+        ship: Vec<ShipTarget>,
+    },
+
+    /// Report the log size of containers and cap logs that are never rotated
+    LogAudit {
+        /// Only audit containers of these stacks
+        #[arg(short, long)]
+        stacks: Vec<String>,
+
+        /// Look for log files in volumes repeating what running containers write to stdout
+        #[arg(short, long)]
+        double_logging: bool,
+
+        /// Recreate compose services without log rotation so their logs are rotated
+        #[arg(long)]
+        fix: bool,
+
+        /// Size at which logs are rotated, e.g. 10m
+        #[arg(long, default_value = DEFAULT_ARG_LOG_MAX_SIZE, value_parser = parse_max_size, requires = "fix")]
+        max_size: String,
+
+        /// Number of log files to keep, including the current one
+        #[arg(long, default_value = DEFAULT_ARG_LOG_MAX_FILE, value_parser = clap::value_parser!(u32).range(1..), requires = "fix")]
+        max_file: u32,
+
+        /// Recreate without asking for confirmation
+        #[arg(short, long, requires = "fix")]
+        yes: bool,
+    },
+
+    /// Stop stacks for host maintenance and start exactly what was running afterwards
+    Maintenance {
+        #[command(subcommand)]
+        action: MaintenanceAction,
+    },
+
+    /// Silence watch alerts of a container for a while, or list active mutes
+    Mute {
+        /// Container name or pattern, supporting `*` wildcards
+        container: Option<String>,
+
+        /// How long to mute for, e.g. 30m, 2h
+        #[arg(long = "for", default_value = DEFAULT_ARG_MUTE_FOR, value_parser = parse_duration)]
+        duration: std::time::Duration,
+
+        /// Why alerts are muted, shown wherever the mute is
+        #[arg(short, long)]
+        reason: Option<String>,
+    },
+
+    /// Scaffold a new compose project with labels recognized by dsd-util
+    New {
+        /// Name of the project
+        name: String,
+
+        /// Template to scaffold from
+        #[arg(short, long, value_enum, default_value = "webservice")]
+        template: Template,
+
+        /// Directory to create the project in, defaults to the project name
+        #[arg(short, long)]
+        dir: Option<PathBuf>,
+    },
+
+    /// Pin containers to the digest of the image they run, until unpinned
+    PinDigest {
+        /// Containers or stacks to pin
+        #[arg(required = true)]
+        containers: Vec<String>,
+    },
+
+    /// Search container images for a package, e.g. after a CVE is published
+    PkgSearch {
+        /// Package name, supporting `*` wildcards
+        package: String,
+
+        /// Only show packages older than this version
+        #[arg(short, long)]
+        below: Option<String>,
+
+        /// Search specified containers
+        #[arg(short, long)]
+        containers: Option<Vec<String>>,
+
+        /// Search specified stacks
+        #[arg(short, long)]
+        stacks: Option<Vec<String>>,
+
+        /// Regenerate cached SBOMs
+        #[arg(short, long)]
+        refresh: bool,
     },
 
     /// Kill all docker containers and redeploy docker-stack-deploy
     Nuke,
 
+    /// Pause containers, freezing their processes until unpaused
+    Pause {
+        /// Pause specified containers, or all containers of a stack given by name
+        containers: Option<Vec<String>>,
+
+        /// Pause specified stacks
+        #[arg(short, long)]
+        stacks: Option<Vec<String>>,
+
+        /// Pause all containers
+        #[arg(short, long)]
+        all: bool,
+
+        /// How long to wait for each container, e.g. 30s
+        #[arg(short, long, default_value = DEFAULT_ARG_LIFECYCLE_TIMEOUT, value_parser = parse_duration)]
+        timeout: std::time::Duration,
+    },
+
+    /// Show host ports published by containers and flag ports claimed by more than one
+    Ports {
+        /// Only show ports of this stack
+        stack: Option<String>,
+    },
+
+    /// Report disk usage per stack and remove stopped containers and dangling images of a stack
+    Prune {
+        /// Stack to clean up, only reports disk usage of all stacks when not given
+        stack: Option<String>,
+
+        /// List what would be removed without removing it
+        #[arg(long, requires = "stack")]
+        dry_run: bool,
+
+        /// Remove without asking for confirmation
+        #[arg(short, long, requires = "stack")]
+        yes: bool,
+    },
+
+    /// Run HTTP/TCP probes defined in the config file
+    Probes {
+        #[command(subcommand)]
+        action: ProbesAction,
+    },
+
+    /// Report whether the host needs a reboot, or reboot it with the stacks stopped cleanly
+    Reboot {
+        /// Stop the stacks and reboot, starting the stacks again after the boot
+        #[arg(long)]
+        safe: bool,
+
+        /// Stacks or groups to stop, all stacks when left out
+        #[arg(requires = "safe")]
+        stacks: Vec<String>,
+
+        /// Only reboot when the host asked for it
+        #[arg(long, requires = "safe")]
+        if_required: bool,
+
+        /// Reboot without asking for confirmation
+        #[arg(short, long, requires = "safe")]
+        yes: bool,
+    },
+
+    /// Sample stats of stacks periodically and append them to a CSV file or SQLite database
+    Record {
+        /// Stacks to record
+        stacks: Option<Vec<String>>,
+
+        /// Record all running containers
+        #[arg(short, long)]
+        all: bool,
+
+        /// Time between samples, e.g. 30s, 5m
+        #[arg(short, long, default_value = DEFAULT_ARG_RECORD_INTERVAL, value_parser = parse_duration)]
+        interval: std::time::Duration,
+
+        /// File to append samples to, a SQLite database when ending in .db, .sqlite or .sqlite3
+        #[arg(short, long)]
+        out: PathBuf,
+    },
+
+    /// Summarize recorded stats with min, average and max usage per container, or compile a
+    /// daily report
+    Report {
+        /// File written by record, used for resource highlights of the daily report when given
+        #[arg(required_unless_present = "daily")]
+        input: Option<PathBuf>,
+
+        /// Compile uptime, restarts, updates, resource highlights, disk trends and open warnings
+        /// of the last day into a report
+        #[arg(long)]
+        daily: bool,
+
+        /// Write the daily report to a .md or .html file instead of printing it as Markdown
+        #[arg(short, long, requires = "daily")]
+        output: Option<PathBuf>,
+
+        /// Start of the time range, as a duration ago such as 30m, 24h, 7d
+        #[arg(long, default_value = DEFAULT_ARG_SINCE, value_parser = parse_duration)]
+        since: std::time::Duration,
+
+        /// End of the time range, as a duration ago, defaults to now
+        #[arg(long, value_parser = parse_duration)]
+        until: Option<std::time::Duration>,
+    },
+
     /// Restart containers
     Restart {
-        /// Restart specified container
+        /// Restart specified containers, or all containers of a stack given by name
         containers: Option<Vec<String>>,
 
         /// Restart specified stacks
@@ -57,6 +566,85 @@ enum Commands {
         /// Restart all containers
         #[arg(short, long)]
         all: bool,
+
+        /// Restart in dependency order derived from compose depends_on
+        #[arg(short, long)]
+        ordered: bool,
+    },
+
+    /// Roll containers back to the image they ran before their last update
+    Rollback {
+        /// Containers or stacks to roll back, lists what can be rolled back when not given
+        targets: Vec<String>,
+    },
+
+    /// Generate or load SBOMs of images and list their packages
+    Sbom {
+        /// Images to list packages for
+        #[arg(required_unless_present = "all")]
+        images: Option<Vec<String>>,
+
+        /// Use images of all running containers
+        #[arg(short, long)]
+        all: bool,
+
+        /// Regenerate cached SBOMs
+        #[arg(short, long)]
+        refresh: bool,
+    },
+
+    /// Scale services of a stack, such as to add workers for a while, without changing its
+    /// compose files
+    Scale {
+        /// Stack the services belong to
+        stack: String,
+
+        /// Number of replicas for a service, given as service=replicas
+        #[arg(required = true, value_parser = parse_service_scale)]
+        scales: Vec<ServiceScale>,
+    },
+
+    /// Manage secrets encrypted with age, referenced as ${secret:name} in the config file
+    Secret {
+        #[command(subcommand)]
+        action: SecretAction,
+    },
+
+    /// Audit the privileges containers run with
+    Security {
+        #[command(subcommand)]
+        action: SecurityAction,
+    },
+
+    /// Update dsd-util to its latest release
+    SelfUpdate {
+        /// Only report whether a newer release is available
+        #[arg(long)]
+        check: bool,
+    },
+
+    /// Inspect compose projects
+    Stack {
+        #[command(subcommand)]
+        action: StackAction,
+    },
+
+    /// Start stopped containers
+    Start {
+        /// Start specified containers, or all containers of a stack given by name
+        containers: Option<Vec<String>>,
+
+        /// Start specified stacks
+        #[arg(short, long)]
+        stacks: Option<Vec<String>>,
+
+        /// Start all containers
+        #[arg(short, long)]
+        all: bool,
+
+        /// How long to wait for each container, e.g. 30s
+        #[arg(short, long, default_value = DEFAULT_ARG_LIFECYCLE_TIMEOUT, value_parser = parse_duration)]
+        timeout: std::time::Duration,
     },
 
     /// View basic stats for docker containers
@@ -71,6 +659,106 @@ enum Commands {
         /// View stats for all containers
         #[arg(short, long)]
         all: bool,
+
+        /// Order of containers in the table
+        #[arg(long, value_enum, default_value_t = StatsSort::Name)]
+        sort: StatsSort,
+
+        /// Number of probes to run concurrently
+        #[arg(short, long, default_value = DEFAULT_ARG_JOBS)]
+        jobs: usize,
+
+        /// Keep redrawing the stats until interrupted
+        #[arg(short, long)]
+        watch: bool,
+
+        /// How often to redraw the stats when watching
+        #[arg(long, default_value = DEFAULT_ARG_INTERVAL, value_parser = parse_duration, requires = "watch")]
+        interval: std::time::Duration,
+
+        /// Print the table as Markdown or HTML to paste into wikis and tickets, or as CSV for
+        /// spreadsheets
+        #[arg(short, long, value_enum, conflicts_with = "watch")]
+        output: Option<DocumentFormat>,
+    },
+
+    /// Stop containers, keeping them to start again
+    Stop {
+        /// Stop specified containers, or all containers of a stack given by name
+        containers: Option<Vec<String>>,
+
+        /// Stop specified stacks
+        #[arg(short, long)]
+        stacks: Option<Vec<String>>,
+
+        /// Stop all containers
+        #[arg(short, long)]
+        all: bool,
+
+        /// How long a container gets to exit before it is killed, e.g. 30s
+        #[arg(short, long, default_value = DEFAULT_ARG_LIFECYCLE_TIMEOUT, value_parser = parse_duration)]
+        timeout: std::time::Duration,
+    },
+
+    /// Show what happened to a container: actions taken, docker events, health transitions,
+    /// updates and probe results, oldest first
+    Timeline {
+        /// Container given by name, partial name or id
+        container: String,
+
+        /// How far back to look, e.g. 24h or 7d
+        #[arg(long, default_value = DEFAULT_ARG_TIMELINE_SINCE, value_parser = parse_duration)]
+        since: std::time::Duration,
+    },
+
+    /// List the processes of all containers of a stack in one table, such as to find which
+    /// service spawned a runaway process
+    Top {
+        /// Stack whose containers to list the processes of
+        stack: String,
+
+        /// Order of processes in the table
+        #[arg(long, value_enum, default_value_t = TopSort::Cpu)]
+        sort: TopSort,
+
+        /// Keep redrawing the table until interrupted
+        #[arg(short, long)]
+        watch: bool,
+
+        /// How often to redraw the table when watching
+        #[arg(long, default_value = DEFAULT_ARG_INTERVAL, value_parser = parse_duration, requires = "watch")]
+        interval: std::time::Duration,
+    },
+
+    /// Remove the mute of a container before it expires
+    Unmute {
+        /// Container name or pattern, as given to mute
+        container: String,
+    },
+
+    /// Unpause paused containers
+    Unpause {
+        /// Unpause specified containers, or all containers of a stack given by name
+        containers: Option<Vec<String>>,
+
+        /// Unpause specified stacks
+        #[arg(short, long)]
+        stacks: Option<Vec<String>>,
+
+        /// Unpause all containers
+        #[arg(short, long)]
+        all: bool,
+
+        /// How long to wait for each container, e.g. 30s
+        #[arg(short, long, default_value = DEFAULT_ARG_LIFECYCLE_TIMEOUT, value_parser = parse_duration)]
+        timeout: std::time::Duration,
+    },
+
+    /// Return containers pinned by pin-digest to following their tag
+    Unpin {
+        /// Containers or stacks to unpin
+        #[arg(required = true)]
+        containers: Vec<String>,
     },
 
     /// Update container images
@@ -85,39 +773,695 @@ enum Commands {
         /// Update all containers
         #[arg(short, long)]
         all: bool,
+
+        /// Number of images to pull and stacks to recreate concurrently
+        #[arg(short, long, default_value = DEFAULT_ARG_JOBS)]
+        jobs: usize,
+
+        /// Recreate updated containers instead of restarting docker-stack-deploy
+        #[arg(short, long)]
+        recreate: bool,
+
+        /// Update even when the host is overloaded according to the guard settings
+        #[arg(long)]
+        ignore_load: bool,
+
+        /// Pull even when the disk looks too full for the new images
+        #[arg(long)]
+        ignore_disk: bool,
+
+        /// Leave containers or services matching a pattern alone, such as db or web/*
+        #[arg(long)]
+        exclude: Vec<String>,
+
+        /// Roll recreated containers back to their previous image unless healthy within this time
+        #[arg(long, value_parser = parse_duration, requires = "recreate")]
+        auto_rollback: Option<std::time::Duration>,
+
+        /// Append the version, revision, build date and size changes of updated images to a file
+        /// of JSON lines
+        #[arg(long)]
+        changelog: Option<PathBuf>,
+
+        /// Log in to the registry of the images as this user before pulling
+        #[arg(long, requires = "registry_password")]
+        registry_user: Option<String>,
+
+        /// Read the registry password or token from stdin
+        #[arg(long, requires = "registry_user", group = "registry_password")]
+        registry_password_stdin: bool,
+
+        /// Take the registry password or token from a secret stored with `secret set`
+        #[arg(long, requires = "registry_user", group = "registry_password")]
+        registry_password_secret: Option<String>,
+
+        /// Registry to log in to, needed when the images come from several registries
+        #[arg(long, requires = "registry_user")]
+        registry: Option<String>,
+
+        /// Keep running and update on a cron schedule such as "0 4 * * *" or @daily
+        #[arg(long, value_parser = parse_cron)]
+        schedule: Option<CronSchedule>,
+
+        /// Send a summary of the images each scheduled update changed to the notify channels
+        #[arg(long, requires = "schedule")]
+        notify: bool,
+    },
+
+    /// View container logs using a named view from the config file
+    View {
+        /// Name of the view
+        name: String,
+    },
+
+    /// Watch containers and notify a webhook when they exit, turn unhealthy or keep restarting
+    Watch {
+        /// Watch specified containers
+        containers: Option<Vec<String>>,
+
+        /// Watch specified stacks
+        #[arg(short, long)]
+        stacks: Option<Vec<String>>,
+
+        /// Watch all containers
+        #[arg(short, long)]
+        all: bool,
+    },
+
+    /// Wait until all containers of a stack are healthy, exiting non-zero on failure
+    WaitHealthy {
+        /// Stacks to wait for
+        #[arg(required = true)]
+        stacks: Vec<String>,
+
+        /// How long to wait before failing, e.g. 90s, 5m
+        #[arg(short, long, default_value = DEFAULT_ARG_TIMEOUT, value_parser = parse_duration)]
+        timeout: std::time::Duration,
+
+        /// How often to check container health
+        #[arg(short, long, default_value = DEFAULT_ARG_INTERVAL, value_parser = parse_duration)]
+        interval: std::time::Duration,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum StackAction {
+    /// Explain what compose up would create, recreate or leave alone, without invoking compose
+    Plan {
+        /// Directory containing the compose file
+        #[arg(default_value = ".")]
+        dir: PathBuf,
+    },
+    /// Set the image tag of a service in its compose file and recreate the service with it
+    Bump {
+        /// Service whose image is bumped
+        service: String,
+        /// Tag to set, such as 1.25.3
+        tag: String,
+        /// Directory containing the compose file
+        #[arg(short, long, default_value = ".")]
+        dir: PathBuf,
+        /// Commit the compose file to git with a conventional commit message
+        #[arg(short, long)]
+        commit: bool,
+        /// Only edit the compose file, without recreating the service
+        #[arg(long)]
+        no_apply: bool,
+    },
+    /// Archive the named volumes of a stack into timestamped tarballs
+    Backup {
+        /// Stack whose volumes are backed up
+        stack: String,
+        /// Directory the archives are written to
+        #[arg(short, long, default_value = ".")]
+        out: PathBuf,
+        /// Stop running containers using the volumes while they are archived
+        #[arg(long)]
+        stop: bool,
+    },
+    /// Replace the named volumes of a stack with their latest or a given backup
+    Restore {
+        /// Stack whose volumes are restored
+        stack: String,
+        /// Directory containing the archives
+        #[arg(short, long, default_value = ".")]
+        from: PathBuf,
+        /// Restore the backup taken at this time, such as 20250601T120000Z
+        #[arg(long)]
+        at: Option<String>,
+        /// Restore without asking for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum DaemonAction {
+    /// Receive webhooks and run queued actions until interrupted
+    Run {
+        /// Address to listen on, unless started by a systemd socket unit
+        #[arg(short, long, default_value = DEFAULT_ARG_DAEMON_LISTEN)]
+        listen: String,
+    },
+
+    /// Show running, pending and recently finished actions of a running daemon
+    Status {
+        /// Address the daemon listens on
+        #[arg(long, default_value = DEFAULT_ARG_DAEMON_LISTEN)]
+        address: String,
+    },
+
+    /// Ask a running daemon to pull and recreate services of a stack
+    Trigger {
+        /// Stack of the services
+        #[arg(short, long)]
+        stack: String,
+
+        /// Services to pull and recreate, which must be allowed for the stack
+        #[arg(required = true)]
+        services: Vec<String>,
+
+        /// Address the daemon listens on
+        #[arg(long, default_value = DEFAULT_ARG_DAEMON_LISTEN)]
+        address: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum MaintenanceAction {
+    /// Record the running containers of stacks and stop them, dependents first
+    Start {
+        /// Stacks or groups to stop
+        stacks: Vec<String>,
+
+        /// Stop every stack with running containers
+        #[arg(short, long, conflicts_with = "stacks")]
+        all: bool,
+    },
+
+    /// Start the containers maintenance start stopped and wait until they are healthy
+    End {
+        /// How long to wait for the stacks to become healthy, e.g. 90s, 5m
+        #[arg(short, long, default_value = DEFAULT_ARG_TIMEOUT, value_parser = parse_duration)]
+        timeout: std::time::Duration,
+
+        /// How often to check container health
+        #[arg(short, long, default_value = DEFAULT_ARG_INTERVAL, value_parser = parse_duration)]
+        interval: std::time::Duration,
+
+        /// Send whether the containers came back to the notify channels
+        #[arg(long)]
+        notify: bool,
+    },
+
+    /// Show which containers a maintenance in progress stopped
+    Status,
+}
+
+#[derive(Debug, Subcommand)]
+enum ProbesAction {
+    /// Run probes once and print the results
+    Check {
+        /// Only run probes for specified containers
+        containers: Option<Vec<String>>,
+    },
+
+    /// Run probes continuously on their configured interval and record the results
+    Schedule {
+        /// Only run probes for specified containers
+        containers: Option<Vec<String>>,
+    },
+
+    /// Show availability and latency of recorded probe results
+    History {
+        /// Only show history for specified probes or containers
+        names: Option<Vec<String>>,
+
+        /// How far back to look, e.g. 30m, 24h, 7d
+        #[arg(long, default_value = DEFAULT_ARG_SINCE, value_parser = parse_duration)]
+        since: std::time::Duration,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SecretAction {
+    /// Store a secret read from stdin, replacing its previous value
+    Set {
+        /// Name of the secret, such as slack-webhook
+        name: String,
+    },
+
+    /// Print a secret
+    Get {
+        /// Name of the secret
+        name: String,
+    },
+
+    /// List the names of stored secrets
+    List,
+
+    /// Remove a secret
+    Remove {
+        /// Name of the secret
+        name: String,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum SecurityAction {
+    /// List which containers run as root and which mount the docker socket
+    Users {
+        /// Only audit containers of these stacks
+        #[arg(short, long)]
+        stacks: Vec<String>,
+    },
+
+    /// Show the AppArmor, seccomp and SELinux profiles of containers, failing when any runs
+    /// unconfined
+    Profiles {
+        /// Only audit containers of these stacks
+        #[arg(short, long)]
+        stacks: Vec<String>,
     },
 }
 
 fn main() -> anyhow::Result<()> {
-    let cli = Cli::parse();
+    let matches = Cli::command().get_matches();
+    let cli = Cli::from_arg_matches(&matches)?;
+
+    // full name of nested subcommands, e.g. probes check
+    let mut command_names = vec![];
+    let mut subcommand = matches.subcommand();
+    while let Some((name, sub_matches)) = subcommand {
+        command_names.push(name);
+        subcommand = sub_matches.subcommand();
+    }
+    let command_name = command_names.join(" ");
 
-    match cli.command {
+    set_context_enabled(!cli.no_context);
+    set_accessible(cli.a11y);
+    set_deterministic(cli.deterministic);
+    set_color_choice(cli.color);
+    set_fuzzy(cli.fuzzy);
+    set_ci(cli.ci);
+    if let Some(state_dir) = cli.state_dir {
+        set_state_dir(state_dir);
+    }
+    set_compose_selection(ComposeSelection {
+        files: cli.compose_files,
+        profiles: cli.profiles,
+        env_file: cli.env_file,
+    });
+
+    if !cli.ci {
+        let result = run(cli.command);
+
+        // batches failing only in part exit with their own code, so scripts can tell them apart
+        if let Err(e) = &result {
+            let code = exit_code(e);
+            if code != 1 {
+                eprintln!("Error: {e:#}");
+                std::process::exit(code);
+            }
+        }
+
+        return result;
+    }
+
+    let start = Instant::now();
+    let result = run(cli.command);
+
+    if let Err(e) = &result {
+        eprintln!("Error: {e:#}");
+    }
+
+    println!("{}", summary_line(&command_name, &result, start.elapsed()));
+
+    std::process::exit(match &result {
+        Ok(()) => 0,
+        Err(e) => exit_code(e),
+    });
+}
+
+/// Whether a command talks to docker, rather than only to state files, the network or a daemon
+/// of dsd-util
+fn needs_docker(command: &Commands) -> bool {
+    !matches!(
+        command,
+        Commands::Daemon {
+            action: DaemonAction::Status { .. } | DaemonAction::Trigger { .. }
+        } | Commands::Doctor
+            | Commands::ExportBundle { .. }
+            | Commands::ImportBundle { .. }
+            | Commands::Maintenance {
+                action: MaintenanceAction::Status
+            }
+            | Commands::Mute { .. }
+            | Commands::New { .. }
+            | Commands::Probes {
+                action: ProbesAction::History { .. }
+            }
+            | Commands::Report { daily: false, .. }
+            | Commands::Secret { .. }
+            | Commands::SelfUpdate { .. }
+            | Commands::Stack {
+                action: StackAction::Bump { no_apply: true, .. }
+            }
+            | Commands::Unmute { .. }
+    )
+}
+
+fn run(command: Commands) -> anyhow::Result<()> {
+    if needs_docker(&command) {
+        check_docker()?;
+    }
+
+    match command {
+        Commands::Conflicts { dir } => conflicts(dir)?,
+        Commands::Cp {
+            source,
+            destination,
+            all,
+        } => cp(source, destination, all)?,
+        Commands::Daemon { action } => match action {
+            DaemonAction::Run { listen } => daemon_run(listen)?,
+            DaemonAction::Status { address } => daemon_status(address)?,
+            DaemonAction::Trigger {
+                stack,
+                services,
+                address,
+            } => daemon_trigger(stack, services, address)?,
+        },
+        Commands::Deploy {
+            stack,
+            images,
+            timeout,
+            interval,
+        } => deploy(stack, images, timeout, interval)?,
+        Commands::Doctor => doctor()?,
+        Commands::Drift { stack } => drift(stack)?,
+        Commands::Env {
+            target,
+            diff,
+            show_secrets,
+        } => env_vars(target, diff, show_secrets)?,
+        Commands::Exec { container, command } => exec(container, command)?,
+        Commands::ExportBundle { output } => bundle_export(output)?,
+        Commands::Exporter { listen } => exporter(listen)?,
+        Commands::Graph {
+            stack,
+            stack_flag,
+            output,
+            ascii,
+        } => graph(stack.or(stack_flag).unwrap_or_default(), output, ascii)?,
+        Commands::ImportBundle { bundle, force } => bundle_import(bundle, force)?,
         Commands::Init {
             project_dir,
             git_url,
         } => init(project_dir, git_url)?,
+        Commands::Licenses {
+            containers,
+            stacks,
+            all,
+            refresh,
+        } => licenses(containers, stacks, all, refresh)?,
+        Commands::LogAudit {
+            stacks,
+            double_logging,
+            fix,
+            max_size,
+            max_file,
+            yes,
+        } => log_audit(stacks, double_logging, fix, max_size, max_file, yes)?,
         Commands::Logs {
             containers,
             stacks,
             tail,
+            since,
+            until,
+            no_follow,
+            jobs,
             all,
-        } => logs(containers, stacks, tail, all)?,
+            grep,
+            min_level,
+            dedupe,
+            rate_limit,
+            throttle_window,
+            no_container_colors,
+            parse_json,
+            fields,
+            multiline,
+            log_format,
+            timestamps,
+            utc,
+            align,
+            bell,
+            ship,
+        } => logs(
+            containers,
+            stacks,
+            LogWindow {
+                // a time range is usually wanted in full
+                tail: tail.or((since.is_none() && until.is_none()).then_some(DEFAULT_TAIL)),
+                since,
+                until,
+                follow: !no_follow,
+                jobs,
+                min_level,
+                throttle: (dedupe || rate_limit.is_some()).then_some(LogThrottle {
+                    dedupe,
+                    max_lines: rate_limit,
+                    window: throttle_window,
+                }),
+            },
+            all,
+            grep,
+            LogOutputs { bell, ship },
+            LogFormat {
+                template: log_format,
+                timestamps,
+                utc,
+                align,
+                container_colors: !no_container_colors,
+                json: parse_json.then_some(if fields.is_empty() {
+                    JsonMode::Pretty
+                } else {
+                    JsonMode::Fields(fields)
+                }),
+                multiline,
+            },
+        )?,
+        Commands::New {
+            name,
+            template,
+            dir,
+        } => new(name, template, dir)?,
         Commands::Nuke => nuke()?,
+        Commands::Maintenance { action } => match action {
+            MaintenanceAction::Start { stacks, all } => maintenance_start(stacks, all)?,
+            MaintenanceAction::End {
+                timeout,
+                interval,
+                notify,
+            } => maintenance_end(timeout, interval, notify)?,
+            MaintenanceAction::Status => maintenance_status()?,
+        },
+        Commands::Mute {
+            container,
+            duration,
+            reason,
+        } => mute_container(container, duration, reason)?,
+        Commands::Pause {
+            containers,
+            stacks,
+            all,
+            timeout,
+        } => lifecycle(Lifecycle::Pause, containers, stacks, all, timeout)?,
+        Commands::Ports { stack } => ports(stack)?,
+        Commands::Prune {
+            stack,
+            dry_run,
+            yes,
+        } => prune(stack, dry_run, yes)?,
+        Commands::Probes { action } => match action {
+            ProbesAction::Check { containers } => probes_check(containers)?,
+            ProbesAction::Schedule { containers } => probes_schedule(containers)?,
+            ProbesAction::History { names, since } => probes_history(names, since)?,
+        },
+        Commands::PinDigest { containers } => pin_digest(containers)?,
+        Commands::PkgSearch {
+            package,
+            below,
+            containers,
+            stacks,
+            refresh,
+        } => pkg_search(package, below, containers, stacks, refresh)?,
+        Commands::Reboot {
+            safe,
+            stacks,
+            if_required,
+            yes,
+        } => reboot(safe, stacks, if_required, yes)?,
+        Commands::Record {
+            stacks,
+            all,
+            interval,
+            out,
+        } => record_stats(stacks, all, interval, out)?,
+        Commands::Report {
+            input: Some(input),
+            daily: false,
+            since,
+            until,
+            ..
+        } => report_stats(input, since, until)?,
+        Commands::Report { input, output, .. } => daily_report(input, output)?,
         Commands::Restart {
             containers,
             stacks,
             all,
-        } => restart(containers, stacks, all)?,
+            ordered,
+        } => restart(containers, stacks, all, ordered)?,
+        Commands::Rollback { targets } => rollback(targets)?,
+        Commands::Scale { stack, scales } => scale(stack, scales)?,
+        Commands::Sbom {
+            images,
+            all,
+            refresh,
+        } => sbom(images, all, refresh)?,
+        Commands::Secret { action } => match action {
+            SecretAction::Set { name } => secret_set(name)?,
+            SecretAction::Get { name } => secret_get(name)?,
+            SecretAction::List => secret_list()?,
+            SecretAction::Remove { name } => secret_remove(name)?,
+        },
+        Commands::Security { action } => match action {
+            SecurityAction::Users { stacks } => security_users(stacks)?,
+            SecurityAction::Profiles { stacks } => security_profiles(stacks)?,
+        },
+        Commands::SelfUpdate { check } => self_update(check)?,
+        Commands::Stack { action } => match action {
+            StackAction::Plan { dir } => stack_plan(dir)?,
+            StackAction::Bump {
+                service,
+                tag,
+                dir,
+                commit,
+                no_apply,
+            } => stack_bump(service, tag, dir, commit, !no_apply)?,
+            StackAction::Backup { stack, out, stop } => stack_backup(stack, out, stop)?,
+            StackAction::Restore {
+                stack,
+                from,
+                at,
+                yes,
+            } => stack_restore(stack, from, at, yes)?,
+        },
+        Commands::Start {
+            containers,
+            stacks,
+            all,
+            timeout,
+        } => lifecycle(Lifecycle::Start, containers, stacks, all, timeout)?,
         Commands::Stats {
             containers,
             stacks,
             all,
-        } => stats(containers, stacks, all)?,
+            sort,
+            jobs,
+            watch,
+            interval,
+            output,
+        } => stats(
+            containers,
+            stacks,
+            all,
+            sort,
+            jobs,
+            watch.then_some(interval),
+            output,
+        )?,
+        Commands::Stop {
+            containers,
+            stacks,
+            all,
+            timeout,
+        } => lifecycle(Lifecycle::Stop, containers, stacks, all, timeout)?,
+        Commands::Timeline { container, since } => timeline(container, since)?,
+        Commands::Top {
+            stack,
+            sort,
+            watch,
+            interval,
+        } => top(stack, sort, watch.then_some(interval))?,
+        Commands::Unmute { container } => unmute_container(container)?,
+        Commands::Unpause {
+            containers,
+            stacks,
+            all,
+            timeout,
+        } => lifecycle(Lifecycle::Unpause, containers, stacks, all, timeout)?,
+        Commands::Unpin { containers } => unpin(containers)?,
         Commands::Update {
             containers,
             stacks,
             all,
-        } => update(containers, stacks, all)?,
+            jobs,
+            recreate,
+            ignore_load,
+            ignore_disk,
+            exclude,
+            auto_rollback,
+            changelog,
+            registry_user,
+            registry_password_stdin: _,
+            registry_password_secret,
+            registry,
+            schedule,
+            notify,
+        } => {
+            // read before anything else touches stdin
+            let registry_login = match registry_user {
+                Some(user) => Some(RegistryLogin {
+                    registry,
+                    user,
+                    password: match registry_password_secret {
+                        Some(name) => secret_value(&name)?,
+                        None => read_password_stdin()?,
+                    },
+                }),
+                None => None,
+            };
+
+            update(
+                containers,
+                stacks,
+                all,
+                UpdateOptions {
+                    jobs,
+                    recreate,
+                    ignore_load,
+                    ignore_disk,
+                    exclude,
+                    auto_rollback,
+                    changelog,
+                    registry_login,
+                },
+                schedule,
+                notify,
+            )?
+        }
+        Commands::View { name } => view(name)?,
+        Commands::Watch {
+            containers,
+            stacks,
+            all,
+        } => watch(containers, stacks, all)?,
+        Commands::WaitHealthy {
+            stacks,
+            timeout,
+            interval,
+        } => wait_healthy(stacks, timeout, interval)?,
     }
 
     Ok(())
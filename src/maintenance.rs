@@ -0,0 +1,99 @@
+use crate::config::state_dir;
+use crate::docker::Container;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+const MAINTENANCE_FILE: &str = "maintenance.json";
+
+/// Containers of a stack that were running when maintenance started
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FrozenStack {
+    pub stack: String,
+    /// Containers in the order they are started, dependencies first
+    pub containers: Vec<String>,
+}
+
+/// What `maintenance start` stopped, for `maintenance end` to start again
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceState {
+    pub started_at: String,
+    pub stacks: Vec<FrozenStack>,
+}
+
+impl MaintenanceState {
+    pub fn container_count(&self) -> usize {
+        self.stacks.iter().map(|stack| stack.containers.len()).sum()
+    }
+
+    pub fn stack_names(&self) -> Vec<String> {
+        self.stacks
+            .iter()
+            .map(|stack| stack.stack.to_string())
+            .collect()
+    }
+}
+
+/// Stacks of running containers, in the order they first appear
+pub fn running_stacks(containers: &[Container]) -> Vec<String> {
+    let mut stacks: Vec<String> = vec![];
+
+    for stack in containers
+        .iter()
+        .filter_map(|container| container.stack.as_ref())
+    {
+        if !stacks.contains(stack) {
+            stacks.push(stack.to_string());
+        }
+    }
+
+    stacks
+}
+
+/// Path of the state of a maintenance in progress, which only exists during one
+pub fn maintenance_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join(MAINTENANCE_FILE))
+}
+
+/// Loads the state of the maintenance in progress, if any
+pub fn load_maintenance() -> anyhow::Result<Option<MaintenanceState>> {
+    let path = maintenance_path()?;
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read maintenance state: {}", path.display()))?;
+
+    serde_json::from_str(&contents)
+        .map(Some)
+        .with_context(|| format!("Failed to parse maintenance state: {}", path.display()))
+}
+
+/// Records the state of a maintenance that is starting
+pub fn save_maintenance(state: &MaintenanceState) -> anyhow::Result<()> {
+    let path = maintenance_path()?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let contents =
+        serde_json::to_string_pretty(state).context("Failed to serialize maintenance state")?;
+    std::fs::write(&path, contents)
+        .with_context(|| format!("Failed to write maintenance state: {}", path.display()))
+}
+
+/// Forgets the maintenance once everything it stopped is running again
+pub fn clear_maintenance() -> anyhow::Result<()> {
+    let path = maintenance_path()?;
+
+    if path.exists() {
+        std::fs::remove_file(&path)
+            .with_context(|| format!("Failed to remove maintenance state: {}", path.display()))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,109 @@
+use crate::config::state_dir;
+use crate::utils::matches_pattern;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+const MUTES_FILE: &str = "mutes.json";
+
+/// Silenced alerts of containers matching a pattern, until the mute expires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Mute {
+    /// Container name or pattern, supporting `*` wildcards
+    pub container: String,
+    pub until: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+impl Mute {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.until > now
+    }
+}
+
+/// Active mute covering a container, if any
+pub fn find_mute<'a>(mutes: &'a [Mute], container: &str, now: DateTime<Utc>) -> Option<&'a Mute> {
+    let active = || mutes.iter().filter(move |mute| mute.is_active(now));
+
+    // a mute of the container itself says more than one of a pattern covering it
+    active()
+        .find(|mute| mute.container == container)
+        .or_else(|| active().find(|mute| matches_pattern(&mute.container, container)))
+}
+
+/// Adds a mute, replacing an earlier one for the same container and dropping expired ones
+pub fn add_mute(mutes: &mut Vec<Mute>, mute: Mute, now: DateTime<Utc>) {
+    mutes.retain(|existing| existing.is_active(now) && existing.container != mute.container);
+    mutes.push(mute);
+    mutes.sort_by(|a, b| a.container.cmp(&b.container));
+}
+
+fn mutes_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join(MUTES_FILE))
+}
+
+/// Loads the mutes that have not expired yet
+pub fn load_mutes() -> anyhow::Result<Vec<Mute>> {
+    let path = mutes_path()?;
+
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read mutes: {}", path.display()))?;
+    let mutes: Vec<Mute> = serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse mutes: {}", path.display()))?;
+
+    let now = Utc::now();
+    Ok(mutes
+        .into_iter()
+        .filter(|mute| mute.is_active(now))
+        .collect())
+}
+
+/// Replaces the stored mutes
+pub fn save_mutes(mutes: &[Mute]) -> anyhow::Result<()> {
+    let path = mutes_path()?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let contents = serde_json::to_string_pretty(mutes).context("Failed to serialize mutes")?;
+    std::fs::write(&path, contents)
+        .with_context(|| format!("Failed to write mutes: {}", path.display()))
+}
+
+/// Mutes alerts of a container for a while, returning the stored mute
+pub fn mute(container: &str, duration: Duration, reason: Option<String>) -> anyhow::Result<Mute> {
+    let now = Utc::now();
+    let until = now
+        + chrono::Duration::from_std(duration)
+            .with_context(|| format!("Mute duration too long: {duration:?}"))?;
+    let mute = Mute {
+        container: container.to_string(),
+        until,
+        reason,
+    };
+
+    let mut mutes = load_mutes()?;
+    add_mute(&mut mutes, mute.clone(), now);
+    save_mutes(&mutes)?;
+
+    Ok(mute)
+}
+
+/// Removes the mute of a container, returning whether there was one
+pub fn unmute(container: &str) -> anyhow::Result<bool> {
+    let mut mutes = load_mutes()?;
+    let count = mutes.len();
+
+    mutes.retain(|mute| mute.container != container);
+    save_mutes(&mutes)?;
+
+    Ok(mutes.len() != count)
+}
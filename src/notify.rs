@@ -0,0 +1,373 @@
+use crate::config::{ChannelConfig, Config, RouteConfig, WebhookFormat};
+use crate::i18n::tr_args;
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+const CURL: &str = "curl";
+const NOTIFY_TIMEOUT: &str = "10";
+
+/// Name of the channel for the webhook given directly in the watch settings
+const WATCH_CHANNEL: &str = "watch";
+
+/// How urgent a notification is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    /// Only printed unless routed to channels
+    Info,
+    Warning,
+    /// Sent right away, even to channels that collect digests
+    Critical,
+}
+
+/// Subsystem an event comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EventSource {
+    /// Container failures found by `watch`
+    Watch,
+    /// Images pulled or rejected by `update`
+    Update,
+    /// Findings of audits such as `licenses`
+    Audit,
+    /// Stacks started again by `maintenance end`, such as after `reboot --safe`
+    Maintenance,
+}
+
+/// Something worth notifying about
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub source: EventSource,
+    pub severity: Severity,
+    pub message: String,
+}
+
+impl Event {
+    pub fn new(source: EventSource, severity: Severity, message: impl Into<String>) -> Self {
+        Event {
+            source,
+            severity,
+            message: message.into(),
+        }
+    }
+}
+
+/// Channels an event is routed to by the first matching rule, or None when no rule matches
+pub fn route<'a>(routes: &'a [RouteConfig], event: &Event) -> Option<&'a [String]> {
+    routes
+        .iter()
+        .find(|route| {
+            route
+                .severity
+                .is_none_or(|severity| severity == event.severity)
+                && route.source.is_none_or(|source| source == event.source)
+        })
+        .map(|route| route.channels.as_slice())
+}
+
+/// Notifications collected for a channel until its digest interval has passed
+#[derive(Debug)]
+pub struct Digest {
+    interval: Duration,
+    /// When the first message of the pending digest was collected
+    started: Option<Instant>,
+    messages: Vec<String>,
+}
+
+impl Digest {
+    pub fn new(interval: Duration) -> Self {
+        Digest {
+            interval,
+            started: None,
+            messages: vec![],
+        }
+    }
+
+    /// Collects a message, starting a new digest interval if none is pending
+    pub fn push(&mut self, message: &str, now: Instant) {
+        self.started.get_or_insert(now);
+        self.messages.push(message.to_string());
+    }
+
+    /// Takes the digest once its interval has passed since the first collected message
+    pub fn take_due(&mut self, now: Instant) -> Option<String> {
+        let started = self.started?;
+
+        if now.duration_since(started) < self.interval {
+            return None;
+        }
+
+        self.take()
+    }
+
+    /// Takes the digest of all collected messages, if any
+    pub fn take(&mut self) -> Option<String> {
+        self.started = None;
+
+        if self.messages.is_empty() {
+            return None;
+        }
+
+        let messages = std::mem::take(&mut self.messages);
+        let header = tr_args(
+            "{count} notification(s) in the last {minutes} minutes:",
+            &[
+                ("count", &messages.len()),
+                ("minutes", &(self.interval.as_secs() / 60)),
+            ],
+        );
+
+        Some(
+            std::iter::once(header)
+                .chain(messages.iter().map(|message| format!("- {message}")))
+                .collect::<Vec<String>>()
+                .join("\n"),
+        )
+    }
+}
+
+/// Sends events to the channels they are routed to, collecting digests where configured
+#[derive(Debug)]
+pub struct Notifier {
+    channels: BTreeMap<String, (ChannelConfig, Option<Digest>)>,
+    routes: Vec<RouteConfig>,
+}
+
+impl Notifier {
+    /// Checks that channels are complete and routes only name existing channels
+    pub fn new(
+        channels: BTreeMap<String, ChannelConfig>,
+        routes: Vec<RouteConfig>,
+    ) -> anyhow::Result<Self> {
+        for (name, channel) in &channels {
+            match channel.format {
+                WebhookFormat::Telegram if channel.chat_id.is_none() => {
+                    anyhow::bail!("Notification channel {name} is missing a chat_id")
+                }
+                WebhookFormat::Email if channel.from.is_none() || channel.to.is_empty() => {
+                    anyhow::bail!("Notification channel {name} is missing a sender or recipients")
+                }
+                _ => {}
+            }
+        }
+
+        for name in routes.iter().flat_map(|route| &route.channels) {
+            if !channels.contains_key(name) {
+                anyhow::bail!("Unknown notification channel in routes: {name}");
+            }
+        }
+
+        Ok(Notifier {
+            channels: channels
+                .into_iter()
+                .map(|(name, channel)| {
+                    let digest = channel
+                        .digest_interval
+                        .map(|minutes| Digest::new(Duration::from_secs(minutes * 60)));
+                    (name, (channel, digest))
+                })
+                .collect(),
+            routes,
+        })
+    }
+
+    /// Notifier for the channels and routes of the config file, including the watch webhook
+    pub fn from_config(config: &Config) -> anyhow::Result<Self> {
+        let mut channels = config.notify.channels.clone();
+
+        if let Some(channel) = config.watch.channel() {
+            channels.insert(WATCH_CHANNEL.to_string(), channel);
+        }
+
+        Notifier::new(channels, config.notify.routes.clone())
+    }
+
+    /// Whether there are no channels to notify
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+
+    /// Names of the channels an event goes to. Without a matching rule, warnings and critical
+    /// events go to all channels while info is only printed
+    pub fn channels_for(&self, event: &Event) -> Vec<&str> {
+        match route(&self.routes, event) {
+            Some(channels) => channels.iter().map(String::as_str).collect(),
+            None if event.severity >= Severity::Warning => {
+                self.channels.keys().map(String::as_str).collect()
+            }
+            None => vec![],
+        }
+    }
+
+    /// Sends an event right away, or adds it to the digest of channels that collect one unless
+    /// it is critical
+    pub fn notify(&mut self, event: &Event) -> anyhow::Result<()> {
+        let now = Instant::now();
+        let routed = self
+            .channels_for(event)
+            .into_iter()
+            .map(str::to_string)
+            .collect::<Vec<String>>();
+
+        self.send_each(|name, digest| {
+            if !routed.iter().any(|routed| routed == name) {
+                return None;
+            }
+
+            match digest {
+                Some(digest) if event.severity < Severity::Critical => {
+                    digest.push(&event.message, now);
+                    None
+                }
+                _ => Some(event.message.to_string()),
+            }
+        })
+    }
+
+    /// Sends the digests whose interval has passed
+    pub fn flush_due(&mut self) -> anyhow::Result<()> {
+        let now = Instant::now();
+
+        self.send_each(|_, digest| digest.as_mut()?.take_due(now))
+    }
+
+    /// Sends all pending digests, e.g. before exiting
+    pub fn flush_all(&mut self) -> anyhow::Result<()> {
+        self.send_each(|_, digest| digest.as_mut()?.take())
+    }
+
+    /// Sends the message picked for each channel, trying every channel even when one fails
+    fn send_each(
+        &mut self,
+        mut pick: impl FnMut(&str, &mut Option<Digest>) -> Option<String>,
+    ) -> anyhow::Result<()> {
+        let errors = self
+            .channels
+            .iter_mut()
+            .filter_map(|(name, (channel, digest))| {
+                let message = pick(name, digest)?;
+                send_message(channel, &message)
+                    .err()
+                    .map(|e| format!("{name}: {e:#}"))
+            })
+            .collect::<Vec<String>>();
+
+        if !errors.is_empty() {
+            anyhow::bail!(errors.join("\n"));
+        }
+
+        Ok(())
+    }
+}
+
+/// Sends events to the channels of the config file, trying every event even when one fails.
+///
+/// For one-off commands, so digests are sent right away instead of being collected.
+pub fn notify_events(events: &[Event]) -> anyhow::Result<()> {
+    let mut notifier = Notifier::from_config(&Config::load_with_secrets()?)?;
+
+    let mut results = events
+        .iter()
+        .map(|event| notifier.notify(event))
+        .collect::<Vec<anyhow::Result<()>>>();
+    results.push(notifier.flush_all());
+
+    let errors = results
+        .into_iter()
+        .filter_map(|result| result.err().map(|e| format!("{e:#}")))
+        .collect::<Vec<String>>();
+
+    if !errors.is_empty() {
+        anyhow::bail!(errors.join("\n"));
+    }
+
+    Ok(())
+}
+
+/// Sends a message to the webhook or mail server of a channel
+pub fn send_message(channel: &ChannelConfig, message: &str) -> anyhow::Result<()> {
+    let (content_type, body) = match channel.format {
+        WebhookFormat::Slack => (
+            "application/json",
+            serde_json::json!({ "text": message }).to_string(),
+        ),
+        WebhookFormat::Discord => (
+            "application/json",
+            serde_json::json!({ "content": message }).to_string(),
+        ),
+        WebhookFormat::Ntfy => ("text/plain", message.to_string()),
+        WebhookFormat::Telegram => (
+            "application/json",
+            serde_json::json!({ "chat_id": channel.chat_id, "text": message }).to_string(),
+        ),
+        WebhookFormat::Email => return send_email(channel, message),
+    };
+
+    let output = Command::new(CURL)
+        .args(["-sS", "-f", "--max-time", NOTIFY_TIMEOUT, "-X", "POST"])
+        .args(["-H", &format!("Content-Type: {content_type}")])
+        .args(["--data-binary", &body])
+        .arg(&channel.url)
+        .output()
+        .context("Failed to send notification")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to send notification: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Sends a message as mail over SMTP with curl, using its first line as subject
+fn send_email(channel: &ChannelConfig, message: &str) -> anyhow::Result<()> {
+    let from = channel.from.as_deref().unwrap_or_default();
+    let subject = message.lines().next().unwrap_or_default();
+    let mail = format!(
+        "From: {from}\r\nTo: {}\r\nSubject: dsd-util: {subject}\r\n\r\n{}\r\n",
+        channel.to.join(", "),
+        message.replace('\n', "\r\n")
+    );
+
+    let mut command = Command::new(CURL);
+    command
+        .args(["-sS", "--max-time", NOTIFY_TIMEOUT, "--url", &channel.url])
+        .args(["--mail-from", from]);
+    for recipient in &channel.to {
+        command.args(["--mail-rcpt", recipient]);
+    }
+
+    let mut child = command
+        .args(["--upload-file", "-"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to send notification")?;
+
+    child
+        .stdin
+        .take()
+        .context("Failed to send notification")?
+        .write_all(mail.as_bytes())
+        .context("Failed to send notification")?;
+
+    let output = child
+        .wait_with_output()
+        .context("Failed to send notification")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to send notification: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
@@ -0,0 +1,871 @@
+use crate::changelog::{ImageMetadata, LABEL_OCI_REVISION, LABEL_OCI_VERSION};
+use crate::docker::Container;
+use crate::environment::ContainerEnv;
+use crate::graph::GraphContainer;
+use crate::logaudit::LogConfig;
+use crate::prune::{DiskContainer, DiskImage};
+use crate::security::{
+    is_docker_socket, security_option, ContainerUser, SecurityProfiles, UNCONFINED,
+};
+use crate::units::parse_size;
+use crate::utils::{
+    InspectData, PortBinding, StatsData, LABEL_COMPOSE_DEPENDS_ON, LABEL_COMPOSE_PROJECT,
+    LABEL_COMPOSE_SERVICE,
+};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Shape of a line of `docker stats --format "{{json .}}"` output
+#[derive(Debug, Deserialize)]
+struct DockerStats {
+    #[serde(rename = "Name")]
+    name: String,
+    #[serde(rename = "CPUPerc", default)]
+    cpu_percent: String,
+    #[serde(rename = "MemPerc", default)]
+    memory_percent: String,
+    #[serde(rename = "MemUsage", default)]
+    memory_usage: String,
+    #[serde(rename = "NetIO", default)]
+    net_io: String,
+    #[serde(rename = "BlockIO", default)]
+    block_io: String,
+    #[serde(rename = "PIDs", default)]
+    pids: String,
+}
+
+/// Parses the output of `docker stats --format "{{json .}}"`, one container per line
+pub fn parse_stats_output(output: &str) -> anyhow::Result<Vec<StatsData>> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(parse_stats_data)
+        .collect()
+}
+
+/// Parses a single line of `docker stats --format "{{json .}}"` output
+pub fn parse_stats_data(stats: &str) -> anyhow::Result<StatsData> {
+    let parsed: DockerStats =
+        serde_json::from_str(stats).with_context(|| format!("Failed to parse stats: {stats}"))?;
+
+    let (memory_used, memory_limit) = parse_size_pair(&parsed.memory_usage)?;
+    let (net_rx, net_tx) = parse_size_pair(&parsed.net_io)?;
+    let (block_read, block_write) = parse_size_pair(&parsed.block_io)?;
+
+    Ok(StatsData {
+        container_name: parsed.name.trim_start_matches('/').to_string(),
+        cpu_percent: parse_percent(&parsed.cpu_percent)?,
+        memory_percent: parse_percent(&parsed.memory_percent)?,
+        memory_used,
+        memory_limit,
+        net_rx,
+        net_tx,
+        block_read,
+        block_write,
+        pids: match parsed.pids.trim() {
+            "" | "--" => 0,
+            pids => pids
+                .parse()
+                .with_context(|| format!("Invalid number of pids: {pids}"))?,
+        },
+    })
+}
+
+/// Parses a percentage such as 12.34% into a number, where -- means unavailable
+fn parse_percent(value: &str) -> anyhow::Result<f64> {
+    match value.trim() {
+        "" | "--" => Ok(0.0),
+        value => value
+            .trim_end_matches('%')
+            .parse()
+            .with_context(|| format!("Invalid percentage: {value}")),
+    }
+}
+
+/// Parses a pair of sizes such as 12.5MiB / 1.9GiB into bytes, where -- means unavailable
+fn parse_size_pair(value: &str) -> anyhow::Result<(u64, u64)> {
+    let value = value.trim();
+
+    if value.is_empty() || value == "--" {
+        return Ok((0, 0));
+    }
+
+    let (first, second) = value
+        .split_once(" / ")
+        .with_context(|| format!("Invalid size pair: {value}"))?;
+
+    let size = |size: &str| match size.trim() {
+        "--" => Ok(0),
+        size => parse_size(size),
+    };
+
+    Ok((size(first)?, size(second)?))
+}
+
+/// Shape of a container in `docker inspect` output, keeping only what InspectData needs
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerInspect {
+    name: String,
+    state: DockerInspectState,
+    #[serde(default)]
+    host_config: Option<DockerHostConfig>,
+    #[serde(default)]
+    restart_count: u64,
+    #[serde(default)]
+    network_settings: Option<DockerNetworkSettings>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerInspectState {
+    status: String,
+    /// Absent for containers without a healthcheck
+    #[serde(default)]
+    health: Option<DockerHealth>,
+    started_at: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerHealth {
+    status: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerHostConfig {
+    #[serde(default)]
+    restart_policy: Option<DockerRestartPolicy>,
+    /// Configured host bindings, which are kept while the container is stopped
+    #[serde(default)]
+    port_bindings: Option<BTreeMap<String, Option<Vec<DockerPortBinding>>>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerRestartPolicy {
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerNetworkSettings {
+    /// Exposed ports with their host bindings, which are null when not published
+    #[serde(default)]
+    ports: Option<BTreeMap<String, Option<Vec<DockerPortBinding>>>>,
+    /// Attached networks by name
+    #[serde(default)]
+    networks: Option<BTreeMap<String, serde_json::Value>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct DockerPortBinding {
+    #[serde(default)]
+    host_ip: String,
+    #[serde(default)]
+    host_port: String,
+}
+
+/// Parses the JSON array printed by `docker inspect` of containers
+pub fn parse_inspect_output(output: &str, now: DateTime<Utc>) -> anyhow::Result<Vec<InspectData>> {
+    // docker prints nothing at all when given no containers
+    if output.trim().is_empty() {
+        return Ok(vec![]);
+    }
+
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_str(output).context("Failed to parse inspect output")?;
+
+    inspected
+        .iter()
+        .map(|container| parse_inspect_data(container, now))
+        .collect()
+}
+
+/// Parses a single inspected container, using N/A for a missing healthcheck or published ports
+pub fn parse_inspect_data(
+    container: &serde_json::Value,
+    now: DateTime<Utc>,
+) -> anyhow::Result<InspectData> {
+    let parsed = DockerInspect::deserialize(container)
+        .with_context(|| format!("Failed to parse inspected container: {container}"))?;
+
+    let (restart_policy, configured_bindings) = match parsed.host_config {
+        Some(host_config) => (host_config.restart_policy, host_config.port_bindings),
+        None => (None, None),
+    };
+    let restart_policy = restart_policy
+        .map(|restart_policy| restart_policy.name)
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "no".to_string());
+
+    let (published, networks) = match parsed.network_settings {
+        Some(network_settings) => (
+            network_settings.ports.unwrap_or_default(),
+            network_settings.networks.unwrap_or_default(),
+        ),
+        None => (BTreeMap::new(), BTreeMap::new()),
+    };
+
+    let ports = published
+        .iter()
+        .map(
+            |(port, bindings)| match bindings.as_deref().and_then(|bindings| bindings.first()) {
+                Some(binding) => format!("{port}:{}", binding.host_port),
+                None => port.to_string(),
+            },
+        )
+        .collect::<Vec<String>>();
+
+    // stopped containers only have the bindings they were configured with
+    let bindings = if published.values().any(Option::is_some) {
+        published
+    } else {
+        configured_bindings.unwrap_or_default()
+    };
+
+    let port_bindings = bindings
+        .into_iter()
+        .flat_map(|(container_port, bindings)| {
+            bindings
+                .unwrap_or_default()
+                .into_iter()
+                .map(move |binding| PortBinding {
+                    container_port: container_port.to_string(),
+                    host_ip: binding.host_ip,
+                    host_port: binding.host_port,
+                })
+        })
+        .collect();
+
+    Ok(InspectData {
+        container_name: parsed.name.trim_start_matches('/').to_string(),
+        status: parsed.state.status,
+        restart_policy,
+        health: parsed
+            .state
+            .health
+            .map(|health| health.status)
+            .unwrap_or_else(|| "N/A".to_string()),
+        uptime: format_uptime(&parsed.state.started_at, now)?,
+        started_at: parsed.state.started_at,
+        restart_count: parsed.restart_count,
+        ports: if ports.is_empty() {
+            "N/A".to_string()
+        } else {
+            ports.join(" ")
+        },
+        port_bindings,
+        networks: networks.into_keys().collect(),
+    })
+}
+
+/// Gets a field of a split line, failing instead of panicking when the line is too short
+fn field<'a>(parsed: &[&'a str], index: usize, name: &str, line: &str) -> anyhow::Result<&'a str> {
+    parsed
+        .get(index)
+        .copied()
+        .with_context(|| format!("Missing {name} in: {line}"))
+}
+
+/// Calculate the number of seconds between a container start time and now
+pub fn uptime_seconds(start_time: &str, now: DateTime<Utc>) -> anyhow::Result<i64> {
+    let start_time =
+        DateTime::parse_from_rfc3339(start_time).context("Failed to parse start_time")?;
+
+    Ok(now
+        .signed_duration_since(start_time.with_timezone(&Utc))
+        .num_seconds())
+}
+
+/// Formats the uptime of a container as days, hours and minutes
+pub fn format_uptime(start_time: &str, now: DateTime<Utc>) -> anyhow::Result<String> {
+    let start_time =
+        DateTime::parse_from_rfc3339(start_time).context("Failed to parse start_time")?;
+    let duration = now.signed_duration_since(start_time.with_timezone(&Utc));
+
+    let days = duration.num_days();
+    let hours = duration.num_hours() % 24;
+    let minutes = duration.num_minutes() % 60;
+
+    let uptime = if days > 0 {
+        format!("{days}D {hours}H {minutes}m")
+    } else if hours > 0 {
+        format!("{hours}H {minutes}m")
+    } else {
+        format!("{minutes}m")
+    };
+
+    Ok(uptime)
+}
+
+/// Parses the output of `docker image inspect --format "{{.Id}} {{range .RepoDigests}}{{.}} {{end}}"`
+/// into the image id and first repo digest
+pub fn parse_image_id(output: &str) -> Option<(String, Option<String>)> {
+    let mut parsed = output.split_whitespace();
+    let id = parsed.next()?;
+
+    Some((id.to_string(), parsed.next().map(String::from)))
+}
+
+/// Parses `docker ps --format "{{.Names}}\t{{.Label ...}}"` output into name and label pairs
+pub fn parse_name_label_pairs(output: &str) -> Vec<(String, String)> {
+    output
+        .lines()
+        .filter_map(|line| line.split_once('\t'))
+        .map(|(name, label)| (name.to_string(), label.to_string()))
+        .collect()
+}
+
+/// Parses `docker ps` output formatted as id, name, image, compose project and compose service
+/// separated by tabs
+pub fn parse_container_list(output: &str) -> anyhow::Result<Vec<Container>> {
+    output
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let parsed = line.split('\t').collect::<Vec<&str>>();
+            let label = |index: usize| {
+                parsed
+                    .get(index)
+                    .filter(|label| !label.is_empty())
+                    .map(|label| label.to_string())
+            };
+
+            Ok(Container {
+                id: field(&parsed, 0, "container id", line)?.to_string(),
+                name: field(&parsed, 1, "container name", line)?.to_string(),
+                image: field(&parsed, 2, "image", line)?.to_string(),
+                stack: label(3),
+                service: label(4),
+            })
+        })
+        .collect()
+}
+
+/// Progress reported by `docker pull`, one per output line
+#[derive(Debug, Clone, PartialEq)]
+pub enum PullProgress {
+    /// Start of the pull, e.g. `latest: Pulling from library/nginx`
+    Pulling { tag: String, repository: String },
+    /// Progress of a single layer, e.g. `a2abf6c4d29d: Pull complete`
+    Layer { id: String, status: String },
+    /// Digest of the pulled image
+    Digest(String),
+    /// Final status, telling whether a newer image was downloaded
+    Status { image: String, updated: bool },
+    /// Fully qualified reference printed by newer docker versions after pulling
+    Reference(String),
+    /// Any other line
+    Other(String),
+}
+
+/// Parses a single line of `docker pull` output
+pub fn parse_pull_progress(line: &str) -> PullProgress {
+    let line = line.trim();
+
+    if let Some(digest) = line.strip_prefix("Digest: ") {
+        return PullProgress::Digest(digest.to_string());
+    }
+
+    if let Some(status) = line.strip_prefix("Status: ") {
+        if let Some(image) = status.strip_prefix("Downloaded newer image for ") {
+            return PullProgress::Status {
+                image: image.to_string(),
+                updated: true,
+            };
+        }
+        if let Some(image) = status.strip_prefix("Image is up to date for ") {
+            return PullProgress::Status {
+                image: image.to_string(),
+                updated: false,
+            };
+        }
+    }
+
+    if let Some((tag, repository)) = line.split_once(": Pulling from ") {
+        return PullProgress::Pulling {
+            tag: tag.to_string(),
+            repository: repository.to_string(),
+        };
+    }
+
+    if let Some((id, status)) = line.split_once(": ") {
+        let is_layer = id.len() >= 12 && id.chars().all(|c| c.is_ascii_hexdigit());
+        if is_layer {
+            return PullProgress::Layer {
+                id: id.to_string(),
+                status: status.to_string(),
+            };
+        }
+    }
+
+    if !line.is_empty() && !line.contains(' ') {
+        return PullProgress::Reference(line.to_string());
+    }
+
+    PullProgress::Other(line.to_string())
+}
+
+/// Shape of an event reported by `docker events --format "{{json .}}"`
+#[derive(Debug, Clone, PartialEq)]
+pub struct DockerEvent {
+    /// Object type, such as container, image or network
+    pub kind: String,
+    pub action: String,
+    pub id: String,
+    /// Name attribute of the actor, such as the container name
+    pub name: Option<String>,
+    pub time: i64,
+}
+
+/// Parses a single line of `docker events --format "{{json .}}"` output.
+///
+/// Docker 25 dropped the legacy `status`, `id` and `from` fields, so only the `Actor` is used.
+pub fn parse_event(line: &str) -> anyhow::Result<DockerEvent> {
+    let event: serde_json::Value =
+        serde_json::from_str(line).context("Failed to parse docker event")?;
+
+    Ok(DockerEvent {
+        kind: event["Type"]
+            .as_str()
+            .context("Docker event has no type")?
+            .to_string(),
+        action: event["Action"]
+            .as_str()
+            .context("Docker event has no action")?
+            .to_string(),
+        id: event["Actor"]["ID"]
+            .as_str()
+            .context("Docker event has no actor")?
+            .to_string(),
+        name: event["Actor"]["Attributes"]["name"]
+            .as_str()
+            .map(String::from),
+        time: event["time"].as_i64().unwrap_or_default(),
+    })
+}
+
+/// Parses the output of `docker inspect` of containers into graph nodes
+pub fn parse_graph_containers(output: &[u8]) -> anyhow::Result<Vec<GraphContainer>> {
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_slice(output).context("Failed to parse inspect output")?;
+
+    Ok(inspected.iter().map(parse_graph_container).collect())
+}
+
+/// Parses the networks, volumes and dependencies of an inspected container
+pub fn parse_graph_container(container: &serde_json::Value) -> GraphContainer {
+    let name = container["Name"]
+        .as_str()
+        .unwrap_or_default()
+        .trim_start_matches('/')
+        .to_string();
+    let labels = &container["Config"]["Labels"];
+
+    GraphContainer {
+        service: labels[LABEL_COMPOSE_SERVICE]
+            .as_str()
+            .unwrap_or(&name)
+            .to_string(),
+        networks: container["NetworkSettings"]["Networks"]
+            .as_object()
+            .map(|networks| networks.keys().cloned().collect())
+            .unwrap_or_default(),
+        volumes: container["Mounts"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter(|mount| mount["Type"] == "volume")
+            .filter_map(|mount| mount["Name"].as_str().map(String::from))
+            .collect(),
+        depends_on: parse_depends_on(labels[LABEL_COMPOSE_DEPENDS_ON].as_str()),
+        links: parse_links(container),
+        name,
+    }
+}
+
+/// Parses the containers a container is linked to, from `--link` on the default bridge, given
+/// as /target:/container/alias, and from compose links on user networks, given as target:alias
+pub fn parse_links(container: &serde_json::Value) -> Vec<String> {
+    let legacy = container["HostConfig"]["Links"]
+        .as_array()
+        .into_iter()
+        .flatten();
+    let networks = container["NetworkSettings"]["Networks"]
+        .as_object()
+        .into_iter()
+        .flat_map(|networks| networks.values())
+        .flat_map(|network| network["Links"].as_array().into_iter().flatten());
+
+    let mut links: Vec<String> = vec![];
+    for link in legacy.chain(networks).filter_map(|link| link.as_str()) {
+        let target = link
+            .split(':')
+            .next()
+            .unwrap_or_default()
+            .trim_start_matches('/');
+
+        if !target.is_empty() && !links.iter().any(|other| other == target) {
+            links.push(target.to_string());
+        }
+    }
+
+    links
+}
+
+/// Parses the compose depends_on label, formatted as service:condition:restart,...
+pub fn parse_depends_on(label: Option<&str>) -> Vec<String> {
+    label
+        .unwrap_or_default()
+        .split(',')
+        .filter_map(|dependency| dependency.split(':').next())
+        .filter(|service| !service.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+/// Parses the output of `docker inspect` of containers into what they take up on disk
+pub fn parse_prune_containers(output: &[u8]) -> anyhow::Result<Vec<DiskContainer>> {
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_slice(output).context("Failed to parse inspect output")?;
+
+    Ok(inspected
+        .iter()
+        .map(|container| DiskContainer {
+            id: container["Id"].as_str().unwrap_or_default().to_string(),
+            name: container["Name"]
+                .as_str()
+                .unwrap_or_default()
+                .trim_start_matches('/')
+                .to_string(),
+            stack: container["Config"]["Labels"][LABEL_COMPOSE_PROJECT]
+                .as_str()
+                .map(String::from),
+            image: container["Config"]["Image"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            image_id: container["Image"].as_str().unwrap_or_default().to_string(),
+            status: container["State"]["Status"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            log_path: container["LogPath"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            volumes: container["Mounts"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter(|mount| mount["Type"] == "volume")
+                .filter_map(|mount| mount["Name"].as_str().map(String::from))
+                .collect(),
+        })
+        .collect())
+}
+
+/// Parses the log driver settings out of `docker inspect` output
+pub fn parse_log_configs(output: &[u8]) -> anyhow::Result<Vec<LogConfig>> {
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_slice(output).context("Failed to parse inspect output")?;
+
+    Ok(inspected
+        .iter()
+        .map(|container| {
+            let labels = &container["Config"]["Labels"];
+            let log_config = &container["HostConfig"]["LogConfig"];
+            let option = |name: &str| {
+                log_config["Config"][name]
+                    .as_str()
+                    .filter(|value| !value.is_empty())
+                    .map(String::from)
+            };
+
+            LogConfig {
+                container: container["Name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .trim_start_matches('/')
+                    .to_string(),
+                stack: labels[LABEL_COMPOSE_PROJECT].as_str().map(String::from),
+                service: labels[LABEL_COMPOSE_SERVICE].as_str().map(String::from),
+                driver: log_config["Type"]
+                    .as_str()
+                    .unwrap_or("json-file")
+                    .to_string(),
+                max_size: option("max-size"),
+                max_file: option("max-file"),
+                log_path: container["LogPath"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .to_string(),
+                running: container["State"]["Running"].as_bool().unwrap_or(false),
+                mounts: container["Mounts"]
+                    .as_array()
+                    .map(|mounts| {
+                        mounts
+                            .iter()
+                            .filter_map(|mount| mount["Destination"].as_str().map(String::from))
+                            .collect()
+                    })
+                    .unwrap_or_default(),
+            }
+        })
+        .collect())
+}
+
+/// Parses `docker inspect` output of containers into their environment. Vars given without a
+/// value, which docker passes on unset, are kept with an empty one.
+pub fn parse_container_env(output: &[u8]) -> anyhow::Result<Vec<ContainerEnv>> {
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_slice(output).context("Failed to parse inspect output")?;
+
+    Ok(inspected
+        .iter()
+        .map(|container| {
+            let labels = &container["Config"]["Labels"];
+
+            ContainerEnv {
+                container: container["Name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .trim_start_matches('/')
+                    .to_string(),
+                stack: labels[LABEL_COMPOSE_PROJECT].as_str().map(String::from),
+                service: labels[LABEL_COMPOSE_SERVICE].as_str().map(String::from),
+                env: container["Config"]["Env"]
+                    .as_array()
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|env| env.as_str())
+                    .map(|env| {
+                        let (key, value) = env.split_once('=').unwrap_or((env, ""));
+                        (key.to_string(), value.to_string())
+                    })
+                    .collect(),
+            }
+        })
+        .collect())
+}
+
+/// Parses `docker inspect` output of containers into the security profiles they run under
+pub fn parse_security_profiles(output: &[u8]) -> anyhow::Result<Vec<SecurityProfiles>> {
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_slice(output).context("Failed to parse inspect output")?;
+
+    Ok(inspected
+        .iter()
+        .map(|container| {
+            let options = container["HostConfig"]["SecurityOpt"]
+                .as_array()
+                .into_iter()
+                .flatten()
+                .filter_map(|option| option.as_str().map(String::from))
+                .collect::<Vec<String>>();
+            let privileged = container["HostConfig"]["Privileged"]
+                .as_bool()
+                .unwrap_or_default();
+
+            let apparmor = match (
+                security_option(&options, "apparmor").last(),
+                container["AppArmorProfile"].as_str().unwrap_or_default(),
+            ) {
+                (Some(profile), _) => profile.to_string(),
+                (None, "") => "-".to_string(),
+                (None, profile) => profile.to_string(),
+            };
+
+            // docker keeps the contents of a seccomp profile file rather than its path
+            let seccomp = match security_option(&options, "seccomp").last() {
+                _ if privileged => UNCONFINED.to_string(),
+                Some(&UNCONFINED) => UNCONFINED.to_string(),
+                Some(_) => "custom".to_string(),
+                None => "default".to_string(),
+            };
+
+            let labels = security_option(&options, "label");
+            let selinux = match container["ProcessLabel"].as_str().unwrap_or_default() {
+                _ if labels.contains(&"disable") => "disable".to_string(),
+                _ if !labels.is_empty() => labels.join(","),
+                "" => "-".to_string(),
+                label => label.to_string(),
+            };
+
+            SecurityProfiles {
+                container: container["Name"]
+                    .as_str()
+                    .unwrap_or_default()
+                    .trim_start_matches('/')
+                    .to_string(),
+                stack: container["Config"]["Labels"][LABEL_COMPOSE_PROJECT]
+                    .as_str()
+                    .map(String::from),
+                apparmor,
+                seccomp,
+                selinux,
+                privileged,
+            }
+        })
+        .collect())
+}
+
+/// Parses `docker inspect` output of containers into the users they run as
+pub fn parse_container_users(output: &[u8]) -> anyhow::Result<Vec<ContainerUser>> {
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_slice(output).context("Failed to parse inspect output")?;
+
+    Ok(inspected
+        .iter()
+        .map(|container| ContainerUser {
+            container: container["Name"]
+                .as_str()
+                .unwrap_or_default()
+                .trim_start_matches('/')
+                .to_string(),
+            stack: container["Config"]["Labels"][LABEL_COMPOSE_PROJECT]
+                .as_str()
+                .map(String::from),
+            image: container["Config"]["Image"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            user: container["Config"]["User"]
+                .as_str()
+                .unwrap_or_default()
+                .to_string(),
+            docker_socket: container["Mounts"].as_array().is_some_and(|mounts| {
+                mounts
+                    .iter()
+                    .filter_map(|mount| mount["Source"].as_str())
+                    .any(is_docker_socket)
+            }),
+        })
+        .collect())
+}
+
+/// Parses the output of `docker image inspect`
+pub fn parse_image_inspect(output: &[u8]) -> anyhow::Result<Vec<DiskImage>> {
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_slice(output).context("Failed to parse image inspect output")?;
+
+    let strings = |value: &serde_json::Value| {
+        value
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|value| value.as_str().map(String::from))
+            .collect::<Vec<String>>()
+    };
+
+    Ok(inspected
+        .iter()
+        .map(|image| DiskImage {
+            id: image["Id"].as_str().unwrap_or_default().to_string(),
+            size: image["Size"].as_u64().unwrap_or_default(),
+            repo_tags: strings(&image["RepoTags"]),
+            repo_digests: strings(&image["RepoDigests"]),
+        })
+        .collect())
+}
+
+/// Parses the build date, version labels and size out of `docker image inspect` output, which is
+/// empty when none of the images were found
+pub fn parse_image_metadata(output: &[u8]) -> anyhow::Result<Vec<ImageMetadata>> {
+    if output.iter().all(u8::is_ascii_whitespace) {
+        return Ok(vec![]);
+    }
+
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_slice(output).context("Failed to parse image inspect output")?;
+
+    Ok(inspected
+        .iter()
+        .map(|image| {
+            let label = |name: &str| {
+                image["Config"]["Labels"][name]
+                    .as_str()
+                    .filter(|value| !value.is_empty())
+                    .map(String::from)
+            };
+
+            ImageMetadata {
+                id: image["Id"].as_str().unwrap_or_default().to_string(),
+                created: image["Created"].as_str().map(String::from),
+                version: label(LABEL_OCI_VERSION),
+                revision: label(LABEL_OCI_REVISION),
+                size: image["Size"].as_u64().unwrap_or_default(),
+            }
+        })
+        .collect())
+}
+
+/// Parses volume sizes from `docker system df --verbose --format "{{json .}}"`, leaving out
+/// volumes docker did not size
+pub fn parse_volume_sizes(output: &[u8]) -> anyhow::Result<BTreeMap<String, u64>> {
+    let usage: serde_json::Value =
+        serde_json::from_slice(output).context("Failed to parse disk usage")?;
+
+    Ok(usage["Volumes"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|volume| {
+            let name = volume["Name"].as_str()?;
+            let size = parse_size(volume["Size"].as_str()?).ok()?;
+            Some((name.to_string(), size))
+        })
+        .collect())
+}
+
+/// Parses the download size of an image for a platform such as linux/amd64 from
+/// `docker manifest inspect --verbose`, summing its config and compressed layers.
+///
+/// Multi-platform images list a manifest per platform, single-platform images only their own,
+/// which is used regardless of the platform. None when no manifest matches the platform.
+pub fn parse_manifest_size(output: &[u8], platform: &str) -> anyhow::Result<Option<u64>> {
+    let manifests: serde_json::Value =
+        serde_json::from_slice(output).context("Failed to parse image manifest")?;
+
+    let manifest = match manifests.as_array() {
+        Some(manifests) => manifests.iter().find(|manifest| {
+            let descriptor = &manifest["Descriptor"]["platform"];
+            format!(
+                "{}/{}",
+                descriptor["os"].as_str().unwrap_or_default(),
+                descriptor["architecture"].as_str().unwrap_or_default()
+            ) == platform
+        }),
+        None => Some(&manifests),
+    };
+
+    let Some(manifest) = manifest else {
+        return Ok(None);
+    };
+
+    let image = match &manifest["OCIManifest"] {
+        serde_json::Value::Null => &manifest["SchemaV2Manifest"],
+        image => image,
+    };
+
+    if image.is_null() {
+        return Ok(None);
+    }
+
+    let layers: u64 = image["layers"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter_map(|layer| layer["size"].as_u64())
+        .sum();
+
+    Ok(Some(
+        image["config"]["size"].as_u64().unwrap_or_default() + layers,
+    ))
+}
@@ -0,0 +1,142 @@
+use crate::commands::DOCKER;
+use crate::config::state_dir;
+use crate::image::ImageRef;
+use anyhow::Context;
+use serde_json::Value;
+use std::path::PathBuf;
+use std::process::Command;
+
+const PIN_OVERRIDE_DIR: &str = "pins";
+
+/// Reference pinning an image to a digest, keeping the tag so it still tells the version, such
+/// as nginx:1.27@sha256:...
+pub fn pinned_reference(image: &ImageRef, digest: &str) -> String {
+    ImageRef {
+        digest: Some(digest.to_string()),
+        ..image.clone()
+    }
+    .to_string()
+}
+
+/// Reference following the tag of a pinned image again
+pub fn unpinned_reference(image: &ImageRef) -> String {
+    ImageRef {
+        digest: None,
+        ..image.clone()
+    }
+    .to_string()
+}
+
+/// Digest of the repo digests of an image that belongs to the repository of a reference, as an
+/// image pulled from several repositories has a digest for each
+pub fn select_repo_digest(image: &ImageRef, repo_digests: &[String]) -> Option<String> {
+    repo_digests
+        .iter()
+        .filter_map(|repo_digest| ImageRef::parse(repo_digest).ok())
+        .find(|repo_digest| {
+            repo_digest.registry == image.registry && repo_digest.repository == image.repository
+        })
+        .and_then(|repo_digest| repo_digest.digest)
+}
+
+/// Repo digests of an image, which only images pulled from or pushed to a registry have
+pub fn repo_digests(image_id: &str) -> anyhow::Result<Vec<String>> {
+    let output = Command::new(DOCKER)
+        .args([
+            "image",
+            "inspect",
+            "--format",
+            "{{json .RepoDigests}}",
+            image_id,
+        ])
+        .output()
+        .with_context(|| format!("Failed to inspect image: {image_id}"))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to inspect image {image_id}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let digests: Option<Vec<String>> = serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("Failed to parse repo digests of {image_id}"))?;
+
+    Ok(digests.unwrap_or_default())
+}
+
+/// Sets or, given no image, removes the image of a service in a compose override, keeping the
+/// services an earlier override already covers
+pub fn render_pin_override(
+    existing: Option<&str>,
+    service: &str,
+    image: Option<&str>,
+) -> anyhow::Result<String> {
+    let mut document = match existing {
+        Some(existing) => serde_yaml::from_str::<Value>(existing)
+            .context("Failed to parse existing pin override")?,
+        None => Value::Null,
+    };
+
+    if !document["services"].is_object() {
+        document = serde_json::json!({ "services": {} });
+    }
+
+    match image {
+        Some(image) => document["services"][service] = serde_json::json!({ "image": image }),
+        None => {
+            if let Some(services) = document["services"].as_object_mut() {
+                services.remove(service);
+            }
+        }
+    }
+
+    serde_yaml::to_string(&document).context("Failed to render pin override")
+}
+
+/// Path of the compose override pinning services of a project
+pub fn pin_override_path(project: &str) -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?
+        .join(PIN_OVERRIDE_DIR)
+        .join(format!("{project}.override.yml")))
+}
+
+/// Image the pin override of a project sets for a service, if it pins the service
+pub fn pinned_service_image(project: &str, service: &str) -> anyhow::Result<Option<String>> {
+    let Ok(contents) = std::fs::read_to_string(pin_override_path(project)?) else {
+        return Ok(None);
+    };
+
+    let document = serde_yaml::from_str::<Value>(&contents)
+        .with_context(|| format!("Failed to parse pin override of {project}"))?;
+
+    Ok(document["services"][service]["image"]
+        .as_str()
+        .map(String::from))
+}
+
+/// Writes the pin override of a project to the state directory, returning its path.
+///
+/// The file stays when its last service is unpinned, as containers recreated with it keep
+/// listing it among their compose files.
+pub fn write_pin_override(
+    project: &str,
+    service: &str,
+    image: Option<&str>,
+) -> anyhow::Result<PathBuf> {
+    let path = pin_override_path(project)?;
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir)
+            .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+    }
+
+    let existing = std::fs::read_to_string(&path).ok();
+
+    std::fs::write(
+        &path,
+        render_pin_override(existing.as_deref(), service, image)?,
+    )
+    .with_context(|| format!("Failed to write compose override: {}", path.display()))?;
+
+    Ok(path)
+}
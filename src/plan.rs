@@ -0,0 +1,461 @@
+use crate::commands::DOCKER;
+use crate::context::{project_name, COMPOSE_FILE_NAMES};
+use crate::image::same_image;
+use crate::utils::{get_image_id, LABEL_COMPOSE_PROJECT, LABEL_COMPOSE_SERVICE};
+use anyhow::Context;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// Override files docker compose merges into the main compose file
+const COMPOSE_OVERRIDE_FILE_NAMES: [&str; 4] = [
+    "compose.override.yaml",
+    "compose.override.yml",
+    "docker-compose.override.yaml",
+    "docker-compose.override.yml",
+];
+
+const ENV_FILE_NAME: &str = ".env";
+
+/// What compose up would do with a service
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlanAction {
+    Create,
+    Recreate,
+    Start,
+    Noop,
+}
+
+impl PlanAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanAction::Create => "create",
+            PlanAction::Recreate => "recreate",
+            PlanAction::Start => "start",
+            PlanAction::Noop => "no-op",
+        }
+    }
+}
+
+/// Shape of the planned action for a single service
+#[derive(Debug, Clone)]
+pub struct ServicePlan {
+    pub service: String,
+    pub container: Option<String>,
+    pub action: PlanAction,
+    pub reasons: Vec<String>,
+}
+
+/// Shape of the plan for a compose project
+#[derive(Debug, Clone)]
+pub struct StackPlan {
+    pub project: String,
+    pub compose_files: Vec<PathBuf>,
+    pub services: Vec<ServicePlan>,
+}
+
+/// Explains what compose up would do for the project in a directory, without invoking compose
+pub fn plan_stack(dir: &Path) -> anyhow::Result<StackPlan> {
+    let compose_files = find_compose_files(dir)?;
+    let config = load_compose_config(&compose_files, dir)?;
+
+    let project = project_name(dir, &compose_files[0]);
+    let containers = inspect_stack_containers(&project)?;
+
+    let services = config["services"]
+        .as_object()
+        .context("Compose file has no services")?
+        .iter()
+        .map(|(service, service_config)| {
+            plan_service(&project, service, service_config, containers.get(service))
+        })
+        .collect::<anyhow::Result<Vec<ServicePlan>>>()?;
+
+    Ok(StackPlan {
+        project,
+        compose_files,
+        services,
+    })
+}
+
+/// Compose file of the project in a directory, followed by its override file if it has one
+pub fn find_compose_files(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let compose_file = COMPOSE_FILE_NAMES
+        .iter()
+        .map(|name| dir.join(name))
+        .find(|path| path.is_file())
+        .with_context(|| format!("No compose file found in {}", dir.display()))?;
+
+    let mut compose_files = vec![compose_file];
+    compose_files.extend(
+        COMPOSE_OVERRIDE_FILE_NAMES
+            .iter()
+            .map(|name| dir.join(name))
+            .find(|path| path.is_file()),
+    );
+
+    Ok(compose_files)
+}
+
+/// Reads and merges compose files, interpolating variables from the .env file in a directory
+pub fn load_compose_config(compose_files: &[PathBuf], dir: &Path) -> anyhow::Result<Value> {
+    let variables = load_variables(dir);
+    let mut config = Value::Null;
+
+    for file in compose_files {
+        let contents = std::fs::read_to_string(file)
+            .with_context(|| format!("Failed to read compose file: {}", file.display()))?;
+
+        let parsed: Value = serde_yaml::from_str(&contents)
+            .with_context(|| format!("Failed to parse compose file: {}", file.display()))?;
+
+        merge(&mut config, interpolate_value(parsed, &variables));
+    }
+
+    Ok(config)
+}
+
+/// Compares the desired config of a service against its current container
+fn plan_service(
+    project: &str,
+    service: &str,
+    desired: &Value,
+    container: Option<&Value>,
+) -> anyhow::Result<ServicePlan> {
+    let Some(container) = container else {
+        return Ok(ServicePlan {
+            service: service.to_string(),
+            container: None,
+            action: PlanAction::Create,
+            reasons: vec!["no container exists".to_string()],
+        });
+    };
+
+    let container_name = container["Name"]
+        .as_str()
+        .unwrap_or_default()
+        .trim_start_matches('/')
+        .to_string();
+
+    // compose names images of services that are only built after the project and service
+    let image = desired["image"]
+        .as_str()
+        .map(String::from)
+        .unwrap_or_else(|| format!("{project}-{service}"));
+
+    let mut reasons = vec![];
+
+    let current_image = container["Config"]["Image"].as_str().unwrap_or_default();
+    // compose passes the image on as written, so nginx and nginx:latest are the same image
+    if !same_image(current_image, &image) {
+        reasons.push(format!("image changed ({current_image} -> {image})"));
+    } else {
+        match get_image_id(&image)? {
+            Some((image_id, _)) if container["Image"].as_str() != Some(image_id.as_str()) => {
+                reasons.push("newer image pulled".to_string())
+            }
+            None => reasons.push("image not pulled yet".to_string()),
+            _ => (),
+        }
+    }
+
+    let image_config = inspect_image_config(container["Image"].as_str().unwrap_or(current_image));
+
+    let changed_env = changed_env(desired, container, &image_config);
+    if !changed_env.is_empty() {
+        reasons.push(format!("env changed ({})", changed_env.join(", ")));
+    }
+
+    // compose adds its own labels, so only labels from the compose file are compared
+    let desired_labels = key_values(&desired["labels"]);
+    let current_labels = container["Config"]["Labels"]
+        .as_object()
+        .into_iter()
+        .flatten()
+        .map(|(key, value)| {
+            (
+                key.to_string(),
+                value.as_str().unwrap_or_default().to_string(),
+            )
+        })
+        .collect::<BTreeMap<String, String>>();
+
+    let changed_labels = changed_keys(&desired_labels, &current_labels, false);
+    if !changed_labels.is_empty() {
+        reasons.push(format!("labels changed ({})", changed_labels.join(", ")));
+    }
+
+    if let Some(command) = command_args(&desired["command"]) {
+        let current_command = command_args(&container["Config"]["Cmd"]).unwrap_or_default();
+        if command != current_command {
+            reasons.push("command changed".to_string());
+        }
+    }
+
+    let status = container["State"]["Status"].as_str().unwrap_or_default();
+
+    let action = if !reasons.is_empty() {
+        PlanAction::Recreate
+    } else if status != "running" {
+        reasons.push(format!("container is {status}"));
+        PlanAction::Start
+    } else {
+        reasons.push("unchanged".to_string());
+        PlanAction::Noop
+    };
+
+    Ok(ServicePlan {
+        service: service.to_string(),
+        container: Some(container_name),
+        action,
+        reasons,
+    })
+}
+
+/// Env vars whose values differ between the compose file and a container, leaving out vars the
+/// container only has from its image
+pub fn changed_env(desired: &Value, container: &Value, image_config: &Value) -> Vec<String> {
+    let desired_env = key_values(&desired["environment"]);
+    let current_env = container["Config"]["Env"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|env| {
+            !image_config["Env"]
+                .as_array()
+                .is_some_and(|image_env| image_env.contains(env))
+        })
+        .filter_map(|env| env.as_str()?.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect::<BTreeMap<String, String>>();
+
+    changed_keys(&desired_env, &current_env, true)
+}
+
+/// Inspects all containers of a compose project, including stopped ones, keyed by service
+pub fn inspect_stack_containers(project: &str) -> anyhow::Result<HashMap<String, Value>> {
+    let output = Command::new(DOCKER)
+        .args([
+            "ps",
+            "-aq",
+            "--filter",
+            &format!("label={LABEL_COMPOSE_PROJECT}={project}"),
+        ])
+        .output()
+        .context(format!("Failed to list containers in stack: {project}"))?;
+
+    let ids = String::from_utf8(output.stdout)
+        .context("Failed to parse container ids from output")?
+        .split_whitespace()
+        .map(String::from)
+        .collect::<Vec<String>>();
+
+    if ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let output = Command::new(DOCKER)
+        .arg("inspect")
+        .args(&ids)
+        .output()
+        .context("Failed to inspect containers")?;
+
+    let inspected: Vec<Value> =
+        serde_json::from_slice(&output.stdout).context("Failed to parse inspect output")?;
+
+    let containers = inspected
+        .into_iter()
+        .filter_map(|container| {
+            let service = container["Config"]["Labels"][LABEL_COMPOSE_SERVICE]
+                .as_str()?
+                .to_string();
+            Some((service, container))
+        })
+        .collect();
+
+    Ok(containers)
+}
+
+/// Gets the config of an image, such as the env vars it sets by default
+pub fn inspect_image_config(image: &str) -> Value {
+    Command::new(DOCKER)
+        .args(["image", "inspect", "--format", "{{json .Config}}", image])
+        .output()
+        .ok()
+        .and_then(|output| serde_json::from_slice(&output.stdout).ok())
+        .unwrap_or_default()
+}
+
+/// Lists keys whose values differ, optionally including keys only present in the current state
+fn changed_keys(
+    desired: &BTreeMap<String, String>,
+    current: &BTreeMap<String, String>,
+    include_removed: bool,
+) -> Vec<String> {
+    let mut changed = desired
+        .iter()
+        .filter(|(key, value)| current.get(*key) != Some(*value))
+        .map(|(key, _)| key.to_string())
+        .collect::<Vec<String>>();
+
+    if include_removed {
+        changed.extend(
+            current
+                .keys()
+                .filter(|key| !desired.contains_key(*key))
+                .cloned(),
+        );
+    }
+
+    changed
+}
+
+/// Normalizes compose `environment` or `labels`, given either as a map or a list of KEY=VALUE
+fn key_values(value: &Value) -> BTreeMap<String, String> {
+    match value {
+        Value::Object(map) => map
+            .iter()
+            .map(|(key, value)| {
+                let value = match value {
+                    Value::String(value) => value.to_string(),
+                    Value::Null => String::new(),
+                    value => value.to_string(),
+                };
+                (key.to_string(), value)
+            })
+            .collect(),
+        Value::Array(list) => list
+            .iter()
+            .filter_map(Value::as_str)
+            .map(|entry| match entry.split_once('=') {
+                Some((key, value)) => (key.to_string(), value.to_string()),
+                None => (entry.to_string(), std::env::var(entry).unwrap_or_default()),
+            })
+            .collect(),
+        _ => BTreeMap::new(),
+    }
+}
+
+/// Normalizes a command given either as a string or a list of arguments
+fn command_args(value: &Value) -> Option<Vec<String>> {
+    match value {
+        Value::String(command) => Some(command.split_whitespace().map(String::from).collect()),
+        Value::Array(args) => Some(
+            args.iter()
+                .map(|arg| arg.as_str().unwrap_or_default().to_string())
+                .collect(),
+        ),
+        _ => None,
+    }
+}
+
+/// Merges an override compose file into the config, replacing everything but maps
+fn merge(base: &mut Value, overrides: Value) {
+    match (base, overrides) {
+        (Value::Object(base), Value::Object(overrides)) => {
+            for (key, value) in overrides {
+                merge(base.entry(key).or_insert(Value::Null), value);
+            }
+        }
+        (base, overrides) => *base = overrides,
+    }
+}
+
+/// Loads variables for interpolation from the .env file, overridden by the environment
+fn load_variables(dir: &Path) -> HashMap<String, String> {
+    let mut variables = std::fs::read_to_string(dir.join(ENV_FILE_NAME))
+        .unwrap_or_default()
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| {
+            let value = value.trim().trim_matches(|c| c == '"' || c == '\'');
+            (key.trim().to_string(), value.to_string())
+        })
+        .collect::<HashMap<String, String>>();
+
+    variables.extend(std::env::vars());
+
+    variables
+}
+
+/// Interpolates variables in all strings of a parsed compose file
+fn interpolate_value(value: Value, variables: &HashMap<String, String>) -> Value {
+    match value {
+        Value::String(text) => Value::String(interpolate(&text, variables)),
+        Value::Array(list) => Value::Array(
+            list.into_iter()
+                .map(|value| interpolate_value(value, variables))
+                .collect(),
+        ),
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, interpolate_value(value, variables)))
+                .collect(),
+        ),
+        value => value,
+    }
+}
+
+/// Interpolates `$VAR`, `${VAR}`, `${VAR:-default}` and `${VAR-default}` like docker compose
+fn interpolate(text: &str, variables: &HashMap<String, String>) -> String {
+    let mut result = String::new();
+    let mut rest = text;
+
+    while let Some(index) = rest.find('$') {
+        result.push_str(&rest[..index]);
+        rest = &rest[index + 1..];
+
+        if let Some(after) = rest.strip_prefix('$') {
+            result.push('$');
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('{') {
+            let Some(end) = after.find('}') else {
+                result.push('$');
+                continue;
+            };
+
+            let expression = &after[..end];
+            rest = &after[end + 1..];
+
+            let (name, default, use_default_if_empty) =
+                if let Some((name, default)) = expression.split_once(":-") {
+                    (name, default, true)
+                } else if let Some((name, default)) = expression.split_once('-') {
+                    (name, default, false)
+                } else {
+                    // errors from ${VAR:?message} are left to compose
+                    (
+                        expression.split([':', '?']).next().unwrap_or_default(),
+                        "",
+                        false,
+                    )
+                };
+
+            match variables.get(name) {
+                Some(value) if !(use_default_if_empty && value.is_empty()) => {
+                    result.push_str(value)
+                }
+                _ => result.push_str(default),
+            }
+        } else {
+            let end = rest
+                .find(|c: char| !c.is_ascii_alphanumeric() && c != '_')
+                .unwrap_or(rest.len());
+
+            if end == 0 {
+                result.push('$');
+                continue;
+            }
+
+            result.push_str(variables.get(&rest[..end]).map_or("", String::as_str));
+            rest = &rest[end..];
+        }
+    }
+
+    result.push_str(rest);
+
+    result
+}
@@ -0,0 +1,152 @@
+use crate::docker::Container;
+use crate::utils::InspectData;
+use std::collections::BTreeMap;
+
+/// Host port published by a container
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortMapping {
+    /// Host address the port is bound to, None when bound to all addresses
+    pub host_ip: Option<String>,
+    pub host_port: String,
+    /// Port and protocol inside the container, such as 80/tcp
+    pub container_port: String,
+    pub container: String,
+    /// Compose service the container was created for
+    pub service: Option<String>,
+    /// Compose project the container belongs to
+    pub stack: Option<String>,
+    pub networks: Vec<String>,
+}
+
+impl PortMapping {
+    /// Host side of the mapping, such as 8080 or 127.0.0.1:8080
+    pub fn host(&self) -> String {
+        match &self.host_ip {
+            Some(host_ip) if host_ip.contains(':') => format!("[{host_ip}]:{}", self.host_port),
+            Some(host_ip) => format!("{host_ip}:{}", self.host_port),
+            None => self.host_port.to_string(),
+        }
+    }
+
+    /// Address a browser reaches the port at, for tcp ports only. Ports published on all
+    /// interfaces are reached through the given host.
+    pub fn url(&self, host: &str) -> Option<String> {
+        if self.protocol() != "tcp" {
+            return None;
+        }
+
+        let host = match &self.host_ip {
+            Some(host_ip) if host_ip.contains(':') => format!("[{host_ip}]"),
+            Some(host_ip) => host_ip.to_string(),
+            None => host.to_string(),
+        };
+        let scheme = if matches!(self.host_port.as_str(), "443" | "8443") {
+            "https"
+        } else {
+            "http"
+        };
+
+        Some(format!("{scheme}://{host}:{}", self.host_port))
+    }
+
+    /// Protocol of the port, tcp unless given
+    pub fn protocol(&self) -> &str {
+        self.container_port
+            .split_once('/')
+            .map_or("tcp", |(_, protocol)| protocol)
+    }
+
+    /// Whether both mappings claim the same port on the same host address
+    pub fn overlaps(&self, other: &PortMapping) -> bool {
+        self.host_port == other.host_port
+            && self.protocol() == other.protocol()
+            && match (&self.host_ip, &other.host_ip) {
+                (Some(a), Some(b)) => a == b,
+                _ => true,
+            }
+    }
+}
+
+/// Host port published by more than one container
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortConflict {
+    /// Port and protocol on the host, such as 8080/tcp
+    pub port: String,
+    /// Conflicting containers with their stacks, sorted by name
+    pub containers: Vec<(String, Option<String>)>,
+}
+
+/// Maps published host ports of inspected containers to their containers, services and stacks
+///
+/// Bindings to every IPv4 and IPv6 address are merged into a single mapping, and ports docker
+/// picks on start are left out until they are bound.
+pub fn port_mappings(containers: &[Container], inspected: &[InspectData]) -> Vec<PortMapping> {
+    let mut mappings = inspected
+        .iter()
+        .flat_map(|data| {
+            let container = containers
+                .iter()
+                .find(|container| container.name == data.container_name);
+
+            data.port_bindings
+                .iter()
+                .filter(|binding| !binding.host_port.is_empty())
+                .map(move |binding| PortMapping {
+                    host_ip: Some(binding.host_ip.to_string())
+                        .filter(|host_ip| !matches!(host_ip.as_str(), "" | "0.0.0.0" | "::")),
+                    host_port: binding.host_port.to_string(),
+                    container_port: binding.container_port.to_string(),
+                    container: data.container_name.to_string(),
+                    service: container.and_then(|container| container.service.clone()),
+                    stack: container.and_then(|container| container.stack.clone()),
+                    networks: data.networks.clone(),
+                })
+        })
+        .collect::<Vec<PortMapping>>();
+
+    mappings.sort_by(|a, b| {
+        let port = |mapping: &PortMapping| mapping.host_port.parse::<u16>().unwrap_or(u16::MAX);
+        port(a)
+            .cmp(&port(b))
+            .then_with(|| a.host_ip.cmp(&b.host_ip))
+            .then_with(|| a.container.cmp(&b.container))
+            .then_with(|| a.container_port.cmp(&b.container_port))
+    });
+    mappings.dedup();
+
+    mappings
+}
+
+/// Finds host ports that more than one container publishes on overlapping addresses
+pub fn find_conflicts(mappings: &[PortMapping]) -> Vec<PortConflict> {
+    // keyed by port number first, so 443 comes before 8080
+    let mut conflicts: BTreeMap<(u16, String), Vec<&PortMapping>> = BTreeMap::new();
+
+    for (index, mapping) in mappings.iter().enumerate() {
+        for other in &mappings[index + 1..] {
+            if mapping.container != other.container && mapping.overlaps(other) {
+                conflicts
+                    .entry((
+                        mapping.host_port.parse().unwrap_or(u16::MAX),
+                        format!("{}/{}", mapping.host_port, mapping.protocol()),
+                    ))
+                    .or_default()
+                    .extend([mapping, other]);
+            }
+        }
+    }
+
+    conflicts
+        .into_iter()
+        .map(|((_, port), conflicting)| {
+            let mut containers = conflicting
+                .into_iter()
+                .map(|mapping| (mapping.container.to_string(), mapping.stack.clone()))
+                .collect::<Vec<_>>();
+            containers.sort();
+            containers.dedup();
+
+            PortConflict { port, containers }
+        })
+        .collect()
+}
@@ -0,0 +1,87 @@
+use crate::config::state_dir;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+const PULLED_FILE: &str = "pulled.json";
+
+/// Serializes changes to the state file, as actions of different stacks run side by side
+static LOCK: Mutex<()> = Mutex::new(());
+
+/// Services of a stack whose new images were pulled but not applied yet
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PulledServices {
+    pub services: BTreeSet<String>,
+    /// Time of the first pull not applied yet
+    pub pulled_at: String,
+}
+
+/// Pulled services by stack, waiting for the maintenance window of their stack
+pub type PulledState = BTreeMap<String, PulledServices>;
+
+/// Records services as pulled, keeping the time of an earlier pull still waiting
+pub fn add_pulled(state: &mut PulledState, stack: &str, services: &[String], pulled_at: &str) {
+    let pulled = state
+        .entry(stack.to_string())
+        .or_insert_with(|| PulledServices {
+            services: BTreeSet::new(),
+            pulled_at: pulled_at.to_string(),
+        });
+
+    pulled.services.extend(services.iter().cloned());
+}
+
+/// Forgets services whose containers were recreated, dropping stacks with none left
+pub fn remove_applied(state: &mut PulledState, stack: &str, services: &[String]) {
+    let Some(pulled) = state.get_mut(stack) else {
+        return;
+    };
+
+    pulled
+        .services
+        .retain(|service| !services.contains(service));
+
+    if pulled.services.is_empty() {
+        state.remove(stack);
+    }
+}
+
+fn pulled_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join(PULLED_FILE))
+}
+
+/// Loads the services waiting to be applied
+pub fn load_pulled() -> anyhow::Result<PulledState> {
+    let path = pulled_path()?;
+
+    if !path.exists() {
+        return Ok(PulledState::new());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read pulled services: {}", path.display()))?;
+
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse pulled services: {}", path.display()))
+}
+
+/// Changes the stored services waiting to be applied
+pub fn update_pulled(change: impl FnOnce(&mut PulledState)) -> anyhow::Result<()> {
+    let _lock = LOCK.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    let mut state = load_pulled()?;
+    change(&mut state);
+
+    let path = pulled_path()?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let contents =
+        serde_json::to_string_pretty(&state).context("Failed to serialize pulled services")?;
+    std::fs::write(&path, contents)
+        .with_context(|| format!("Failed to write pulled services: {}", path.display()))
+}
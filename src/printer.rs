@@ -1,7 +1,26 @@
+use crate::ci::is_ci;
+use crate::config::{Config, ThemeConfig};
+use crate::i18n::tr_args;
+use std::io::IsTerminal;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::OnceLock;
+
 const ANSI_RESET: &str = "\x1b[0m"; // ANSI reset code
 
+static ACCESSIBLE: AtomicBool = AtomicBool::new(false);
+static DETERMINISTIC: AtomicBool = AtomicBool::new(false);
+static HYPERLINKS: OnceLock<bool> = OnceLock::new();
+static COLOR_CHOICE: OnceLock<ColorChoice> = OnceLock::new();
+
+/// Terminals known to render OSC 8 hyperlinks, by TERM_PROGRAM
+const HYPERLINK_TERM_PROGRAMS: [&str; 5] = ["iTerm.app", "WezTerm", "vscode", "ghostty", "Hyper"];
+/// Terminals known to render OSC 8 hyperlinks, by TERM
+const HYPERLINK_TERMS: [&str; 4] = ["xterm-kitty", "foot", "alacritty", "xterm-ghostty"];
+/// First VTE version rendering hyperlinks, as in GNOME Terminal 3.26
+const MIN_VTE_VERSION: u32 = 5000;
+
 /// Color options for printing to the terminal
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Color {
     Red,
     Green,
@@ -10,30 +29,430 @@ pub enum Color {
     Magenta,
     Cyan,
     White,
+    /// Color of the 256 color palette
+    Ansi256(u8),
+    /// 24-bit color, downgraded on terminals without truecolor support
+    Rgb(u8, u8, u8),
+}
+
+/// Colors a terminal can render
+#[derive(Debug, Clone, Copy, PartialEq, PartialOrd)]
+pub enum ColorDepth {
+    /// The 8 basic colors
+    Basic,
+    Ansi256,
+    TrueColor,
+}
+
+/// Semantic role of colored text, which the theme in the config file maps to a color
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Role {
+    Timestamp,
+    Container,
+    Error,
+    Warning,
 }
 
+/// When to color output, as chosen with `--color`
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum ColorChoice {
+    /// Color terminals, unless NO_COLOR is set or CLICOLOR_FORCE forces it
+    #[default]
+    Auto,
+    /// Always color, such as when piping into `less -R`
+    Always,
+    /// Never color
+    Never,
+}
+
+/// Colors assigned to container names in merged logs, in the order docker compose uses them
+const CONTAINER_PALETTE: [Color; 5] = [
+    Color::Cyan,
+    Color::Yellow,
+    Color::Green,
+    Color::Magenta,
+    Color::Blue,
+];
+
+/// Basic colors with the RGB values of xterm, used to pick the closest one on basic terminals
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 7] = [
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::White, (229, 229, 229)),
+];
+/// The first 16 colors of the 256 color palette, as xterm renders them
+const ANSI16_RGB: [(u8, u8, u8); 16] = [
+    (0, 0, 0),
+    (205, 0, 0),
+    (0, 205, 0),
+    (205, 205, 0),
+    (0, 0, 238),
+    (205, 0, 205),
+    (0, 205, 205),
+    (229, 229, 229),
+    (127, 127, 127),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (92, 92, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+/// Levels of each channel in the 6x6x6 color cube of the 256 color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+static COLOR_DEPTH: OnceLock<ColorDepth> = OnceLock::new();
+static THEME: OnceLock<[Color; 4]> = OnceLock::new();
+
 /// Implement Color to match on proper ANSI code
 impl Color {
-    /// Get ANSI code for color
-    fn code(&self) -> &str {
-        match self {
-            Color::Red => "\x1b[1;31m",
-            Color::Green => "\x1b[1;32m",
-            Color::Blue => "\x1b[1;34m",
-            Color::Yellow => "\x1b[1;33m",
-            Color::Magenta => "\x1b[1;35m",
-            Color::Cyan => "\x1b[1;36m",
-            Color::White => "\x1b[1;37m",
+    /// Parses a color name such as cyan, a 256 color palette index such as 208 or an RGB color
+    /// such as #ff8700
+    pub fn parse(color: &str) -> anyhow::Result<Color> {
+        let color = color.trim();
+
+        if let Some(hex) = color.strip_prefix('#') {
+            let channel = |index: usize| {
+                hex.get(index..index + 2)
+                    .and_then(|channel| u8::from_str_radix(channel, 16).ok())
+            };
+            return match (hex.len(), channel(0), channel(2), channel(4)) {
+                (6, Some(r), Some(g), Some(b)) => Ok(Color::Rgb(r, g, b)),
+                _ => anyhow::bail!("Invalid RGB color {color}, expected #rrggbb"),
+            };
+        }
+
+        if let Ok(index) = color.parse::<u8>() {
+            return Ok(Color::Ansi256(index));
+        }
+
+        match color.to_ascii_lowercase().as_str() {
+            "red" => Ok(Color::Red),
+            "green" => Ok(Color::Green),
+            "blue" => Ok(Color::Blue),
+            "yellow" => Ok(Color::Yellow),
+            "magenta" => Ok(Color::Magenta),
+            "cyan" => Ok(Color::Cyan),
+            "white" => Ok(Color::White),
+            _ => anyhow::bail!(
+                "Unknown color {color}, expected a color name, a number up to 255 or #rrggbb"
+            ),
+        }
+    }
+
+    /// Get ANSI code for color, downgraded to what the terminal renders
+    pub fn code(&self, depth: ColorDepth) -> String {
+        match (*self, depth) {
+            (Color::Red, _) => "\x1b[1;31m".to_string(),
+            (Color::Green, _) => "\x1b[1;32m".to_string(),
+            (Color::Blue, _) => "\x1b[1;34m".to_string(),
+            (Color::Yellow, _) => "\x1b[1;33m".to_string(),
+            (Color::Magenta, _) => "\x1b[1;35m".to_string(),
+            (Color::Cyan, _) => "\x1b[1;36m".to_string(),
+            (Color::White, _) => "\x1b[1;37m".to_string(),
+            (Color::Rgb(r, g, b), ColorDepth::TrueColor) => format!("\x1b[1;38;2;{r};{g};{b}m"),
+            (Color::Rgb(r, g, b), ColorDepth::Ansi256) => {
+                format!("\x1b[1;38;5;{}m", rgb_to_ansi256(r, g, b))
+            }
+            (Color::Ansi256(index), ColorDepth::Ansi256 | ColorDepth::TrueColor) => {
+                format!("\x1b[1;38;5;{index}m")
+            }
+            (color, ColorDepth::Basic) => closest_basic(color.rgb()).code(depth),
+        }
+    }
+
+    /// RGB value of the color, as xterm renders it
+    fn rgb(&self) -> (u8, u8, u8) {
+        match *self {
+            Color::Rgb(r, g, b) => (r, g, b),
+            Color::Ansi256(index) => ansi256_to_rgb(index),
+            color => BASIC_COLORS
+                .iter()
+                .find(|(basic, _)| *basic == color)
+                .map(|(_, rgb)| *rgb)
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Closest color of the 256 color palette to an RGB color
+pub fn rgb_to_ansi256(r: u8, g: u8, b: u8) -> u8 {
+    // grays get the finer grayscale ramp, except its ends which the cube covers
+    if r == g && g == b {
+        return match r {
+            0..8 => 16,
+            249.. => 231,
+            gray => 232 + (gray - 8) / 10,
+        };
+    }
+
+    let level = |channel: u8| {
+        CUBE_LEVELS
+            .iter()
+            .enumerate()
+            .min_by_key(|(_, level)| level.abs_diff(channel))
+            .map(|(index, _)| index as u8)
+            .unwrap_or_default()
+    };
+
+    16 + 36 * level(r) + 6 * level(g) + level(b)
+}
+
+/// RGB value of a color of the 256 color palette
+pub fn ansi256_to_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..16 => ANSI16_RGB[index as usize],
+        16..232 => {
+            let cube = index - 16;
+            (
+                CUBE_LEVELS[(cube / 36) as usize],
+                CUBE_LEVELS[(cube / 6 % 6) as usize],
+                CUBE_LEVELS[(cube % 6) as usize],
+            )
+        }
+        gray => {
+            let level = 8 + (gray - 232) * 10;
+            (level, level, level)
         }
     }
 }
 
+/// Basic color closest to an RGB color
+fn closest_basic((r, g, b): (u8, u8, u8)) -> Color {
+    let distance = |(other_r, other_g, other_b): (u8, u8, u8)| {
+        [(r, other_r), (g, other_g), (b, other_b)]
+            .iter()
+            .map(|(a, b)| (*a as i32 - *b as i32).pow(2))
+            .sum::<i32>()
+    };
+
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|(_, rgb)| distance(*rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::White)
+}
+
+/// Colors the terminal renders, judging by its environment. COLORTERM is set to truecolor or
+/// 24bit by terminals rendering RGB colors, TERM ends in 256color on those with the palette.
+pub fn color_depth_from_env(var: impl Fn(&str) -> Option<String>) -> ColorDepth {
+    if var("COLORTERM").is_some_and(|value| matches!(value.as_str(), "truecolor" | "24bit"))
+        || var("WT_SESSION").is_some()
+    {
+        return ColorDepth::TrueColor;
+    }
+
+    if var("TERM").is_some_and(|term| term.contains("256color")) {
+        return ColorDepth::Ansi256;
+    }
+
+    ColorDepth::Basic
+}
+
+/// Colors the terminal renders
+pub fn color_depth() -> ColorDepth {
+    *COLOR_DEPTH.get_or_init(|| color_depth_from_env(|name| std::env::var(name).ok()))
+}
+
+/// Colors of the roles from the theme of the config file, with the built-in colors for roles it
+/// leaves out or sets to colors that do not parse
+pub fn theme_colors(theme: &ThemeConfig) -> [Color; 4] {
+    let defaults = [Color::Cyan, Color::Green, Color::Red, Color::Yellow];
+    let configured = [
+        &theme.timestamp,
+        &theme.container,
+        &theme.error,
+        &theme.warning,
+    ];
+
+    let mut colors = defaults;
+    for (color, configured) in colors.iter_mut().zip(configured) {
+        let Some(configured) = configured else {
+            continue;
+        };
+
+        match Color::parse(configured) {
+            Ok(parsed) => *color = parsed,
+            Err(e) => eprintln!(
+                "{}",
+                tr_args(
+                    "Ignoring theme color: {error}",
+                    &[("error", &format!("{e:#}"))]
+                )
+            ),
+        }
+    }
+
+    colors
+}
+
+/// Color of a role, as set by the theme of the config file
+pub fn role_color(role: Role) -> Color {
+    let colors = THEME.get_or_init(|| {
+        let theme = Config::load()
+            .map(|config| config.theme)
+            .unwrap_or_default();
+        theme_colors(&theme)
+    });
+
+    colors[role as usize]
+}
+
 /// Print line function that uses ANSI code to display colored text on terminal
 pub fn color_println(color: Color, text: &str) {
-    println!("{}{}{}", color.code(), text, ANSI_RESET);
+    println!("{}", color_println_fmt(color, text));
 }
 
 /// Format string function that uses ANSI code to return string formatted for color
 pub fn color_println_fmt(color: Color, text: &str) -> String {
-    format!("{}{}{}", color.code(), text, ANSI_RESET)
+    if !use_color() {
+        return text.to_string();
+    }
+
+    format!("{}{}{}", color.code(color_depth()), text, ANSI_RESET)
+}
+
+/// Picks a color for a container name, always the same one for the same name
+pub fn container_color(name: &str) -> Color {
+    // FNV-1a, as the std hasher may change between releases
+    let hash = name.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+
+    CONTAINER_PALETTE[(hash % CONTAINER_PALETTE.len() as u64) as usize]
+}
+
+/// Enables screen reader friendly output: no color, no box drawing and sentences instead of tables
+pub fn set_accessible(accessible: bool) {
+    ACCESSIBLE.store(accessible, Ordering::SeqCst);
+}
+
+/// Determine if screen reader friendly output was requested
+pub fn is_accessible() -> bool {
+    ACCESSIBLE.load(Ordering::SeqCst)
+}
+
+/// Enables output meant to be committed and diffed: listings sorted by name, times in UTC and
+/// values changing from run to run, such as usage and uptime, left out
+pub fn set_deterministic(deterministic: bool) {
+    DETERMINISTIC.store(deterministic, Ordering::SeqCst);
+}
+
+/// Determine if deterministic output was requested
+pub fn is_deterministic() -> bool {
+    DETERMINISTIC.load(Ordering::SeqCst)
+}
+
+/// Replaces a value changing from run to run with a dash in deterministic output
+pub fn volatile(value: String) -> String {
+    if is_deterministic() {
+        "-".to_string()
+    } else {
+        value
+    }
+}
+
+/// Sets when to color output
+pub fn set_color_choice(choice: ColorChoice) {
+    let _ = COLOR_CHOICE.set(choice);
+}
+
+/// Whether output is colored under a color choice. Without an explicit choice, a non-empty
+/// NO_COLOR disables color and a CLICOLOR_FORCE other than 0 enables it even when the output is
+/// not a terminal, see https://no-color.org and https://bixense.com/clicolors.
+pub fn color_from_env(
+    choice: ColorChoice,
+    is_terminal: bool,
+    var: impl Fn(&str) -> Option<String>,
+) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            if var("NO_COLOR").is_some_and(|value| !value.is_empty()) {
+                return false;
+            }
+            if var("CLICOLOR_FORCE").is_some_and(|value| !value.is_empty() && value != "0") {
+                return true;
+            }
+
+            is_terminal
+        }
+    }
+}
+
+/// Whether output is colored, which is never the case for screen readers, and in CI logs only
+/// when asked for with `--color always`
+pub fn use_color() -> bool {
+    let choice = COLOR_CHOICE.get().copied().unwrap_or_default();
+
+    if is_accessible() || (is_ci() && choice != ColorChoice::Always) {
+        return false;
+    }
+
+    color_from_env(choice, std::io::stdout().is_terminal(), |name| {
+        std::env::var(name).ok()
+    })
+}
+
+/// Whether the terminal renders OSC 8 hyperlinks, judging by its environment. FORCE_HYPERLINK=1
+/// or 0 overrides the guess.
+pub fn hyperlinks_from_env(var: impl Fn(&str) -> Option<String>) -> bool {
+    if let Some(force) = var("FORCE_HYPERLINK") {
+        return force != "0";
+    }
+
+    let vte_version = var("VTE_VERSION")
+        .and_then(|version| version.parse::<u32>().ok())
+        .unwrap_or_default();
+
+    var("TERM_PROGRAM").is_some_and(|program| HYPERLINK_TERM_PROGRAMS.contains(&program.as_str()))
+        || var("TERM").is_some_and(|term| {
+            HYPERLINK_TERMS
+                .iter()
+                .any(|supported| term.starts_with(supported))
+        })
+        || var("WT_SESSION").is_some()
+        || var("KITTY_WINDOW_ID").is_some()
+        || vte_version >= MIN_VTE_VERSION
+}
+
+/// Whether links are rendered as OSC 8 hyperlinks, which is never the case for screen readers, CI
+/// logs or output that is not a terminal
+pub fn supports_hyperlinks() -> bool {
+    *HYPERLINKS.get_or_init(|| {
+        !is_accessible()
+            && !is_ci()
+            && std::io::stdout().is_terminal()
+            && hyperlinks_from_env(|name| std::env::var(name).ok())
+    })
+}
+
+/// Wraps text in an OSC 8 hyperlink, leaving it plain where hyperlinks are not supported
+pub fn hyperlink(url: &str, text: &str) -> String {
+    if !supports_hyperlinks() {
+        return text.to_string();
+    }
+
+    osc8_link(url, text)
+}
+
+/// OSC 8 sequence of a hyperlink
+pub fn osc8_link(url: &str, text: &str) -> String {
+    format!("\x1b]8;;{url}\x1b\\{text}\x1b]8;;\x1b\\")
+}
+
+/// Text padded to a width, with only the text itself linked, as escape codes take up no space
+pub fn link_padded(url: Option<&str>, text: &str, width: usize) -> String {
+    let padding = " ".repeat(width.saturating_sub(text.chars().count()));
+
+    match url {
+        Some(url) => format!("{}{padding}", hyperlink(url, text)),
+        None => format!("{text}{padding}"),
+    }
 }
@@ -0,0 +1,161 @@
+//! Privilege boundary of the daemon.
+//!
+//! The daemon may be started as root so it can take a privileged port. Once the listener is open,
+//! it switches to the unprivileged user from `daemon.user` for good, before any request is
+//! handled, and from then on reaches docker through the socket permissions of that user.
+
+use crate::doctor::{Check, CheckStatus, Problem};
+use anyhow::Context;
+use std::ffi::{CStr, CString};
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+
+const DEFAULT_DOCKER_SOCKET: &str = "/var/run/docker.sock";
+/// Supplementary groups looked up for a user at most
+const MAX_GROUPS: usize = 256;
+
+/// Account the daemon switches to
+#[derive(Debug, Clone, PartialEq)]
+pub struct DaemonUser {
+    pub name: String,
+    pub uid: u32,
+    pub gid: u32,
+    pub home: PathBuf,
+    /// Primary and supplementary groups, such as docker
+    pub groups: Vec<u32>,
+}
+
+impl DaemonUser {
+    /// Looks up a user by name in the system's user database
+    pub fn lookup(name: &str) -> anyhow::Result<Self> {
+        let c_name = CString::new(name).with_context(|| format!("Invalid user name: {name}"))?;
+
+        // SAFETY: c_name is a valid C string; the returned entry is copied before any other
+        // lookup could overwrite it, which only happens during startup
+        let entry = unsafe { libc::getpwnam(c_name.as_ptr()) };
+        if entry.is_null() {
+            anyhow::bail!("User {name} does not exist");
+        }
+        // SAFETY: getpwnam returned a valid entry
+        let (uid, gid, home) = unsafe {
+            (
+                (*entry).pw_uid,
+                (*entry).pw_gid,
+                CStr::from_ptr((*entry).pw_dir)
+                    .to_string_lossy()
+                    .to_string(),
+            )
+        };
+
+        let mut groups = vec![0 as libc::gid_t; MAX_GROUPS];
+        let mut count = MAX_GROUPS as libc::c_int;
+        // SAFETY: groups has room for count entries, getgrouplist writes at most that many
+        let found =
+            unsafe { libc::getgrouplist(c_name.as_ptr(), gid, groups.as_mut_ptr(), &mut count) };
+        if found < 0 {
+            anyhow::bail!("User {name} is in more than {MAX_GROUPS} groups");
+        }
+        groups.truncate(count.max(0) as usize);
+
+        Ok(DaemonUser {
+            name: name.to_string(),
+            uid,
+            gid,
+            home: PathBuf::from(home),
+            groups,
+        })
+    }
+}
+
+/// Path of the docker socket the client uses, None when it connects over the network
+pub fn docker_socket() -> Option<PathBuf> {
+    match std::env::var("DOCKER_HOST") {
+        Ok(host) if !host.is_empty() => host.strip_prefix("unix://").map(PathBuf::from),
+        _ => Some(PathBuf::from(DEFAULT_DOCKER_SOCKET)),
+    }
+}
+
+/// Whether a user may read and write a socket owned by owner and group with the given mode
+pub fn can_use_socket(user: &DaemonUser, owner: u32, group: u32, mode: u32) -> bool {
+    let read_write = |shift: u32| (mode >> shift) & 0o6 == 0o6;
+
+    user.uid == 0
+        || (user.uid == owner && read_write(6))
+        || (user.groups.contains(&group) && read_write(3))
+        || read_write(0)
+}
+
+/// Switches the process to the user for good: groups first, as they can no longer be changed
+/// once the user id is given up, and verifies root cannot be regained.
+///
+/// Must be called before any other thread is started, as it updates HOME and USER for the
+/// state directory and the commands run later.
+pub fn drop_privileges(user: &DaemonUser) -> anyhow::Result<()> {
+    // SAFETY: geteuid has no preconditions
+    let euid = unsafe { libc::geteuid() };
+    if euid == user.uid {
+        return Ok(());
+    }
+    if euid != 0 {
+        anyhow::bail!(
+            "Cannot switch to user {} without being started as root",
+            user.name
+        );
+    }
+
+    // SAFETY: groups holds user.groups.len() valid group ids
+    if unsafe { libc::setgroups(user.groups.len() as _, user.groups.as_ptr()) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to set groups");
+    }
+    // SAFETY: setgid and setuid have no memory preconditions
+    if unsafe { libc::setgid(user.gid) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to set group id");
+    }
+    // SAFETY: as above
+    if unsafe { libc::setuid(user.uid) } != 0 {
+        return Err(std::io::Error::last_os_error()).context("Failed to set user id");
+    }
+
+    // SAFETY: as above, succeeding would mean the switch did not stick
+    if unsafe { libc::setuid(0) } == 0 {
+        anyhow::bail!("Regained root after switching to user {}", user.name);
+    }
+
+    // SAFETY: called during startup, before any other thread reads the environment
+    unsafe {
+        std::env::set_var("HOME", &user.home);
+        std::env::set_var("USER", &user.name);
+    }
+
+    Ok(())
+}
+
+/// Doctor check of the user the daemon switches to: that it exists and may use the docker socket
+pub fn daemon_user_check(name: &str) -> Check {
+    let status = match DaemonUser::lookup(name) {
+        Err(error) => CheckStatus::Failed(Problem::DaemonUser(format!("{error:#}"))),
+        Ok(user) => match docker_socket() {
+            None => CheckStatus::Passed(format!("{name}, docker over the network")),
+            Some(socket) => match std::fs::metadata(&socket) {
+                Err(error) => CheckStatus::Failed(Problem::DaemonUser(format!(
+                    "Failed to read {}: {error}",
+                    socket.display()
+                ))),
+                Ok(metadata)
+                    if can_use_socket(&user, metadata.uid(), metadata.gid(), metadata.mode()) =>
+                {
+                    CheckStatus::Passed(format!("{name} can use {}", socket.display()))
+                }
+                Ok(_) => CheckStatus::Failed(Problem::DaemonUser(format!(
+                    "{name} may not use {}",
+                    socket.display()
+                ))),
+            },
+        },
+    };
+
+    Check {
+        name: "daemon user",
+        status,
+    }
+}
@@ -0,0 +1,254 @@
+use crate::commands::DOCKER;
+use crate::config::{state_dir, ProbeConfig};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs};
+use std::path::PathBuf;
+use std::process::Command;
+use std::time::{Duration, Instant};
+
+const PROBE_HISTORY_FILE: &str = "probes.csv";
+
+const CURL: &str = "curl";
+const IMAGE_CURL: &str = "curlimages/curl";
+const IMAGE_BUSYBOX: &str = "busybox";
+
+/// Shape of a probe result
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub name: String,
+    pub container: String,
+    pub target: String,
+    pub ok: bool,
+    pub detail: String,
+    pub latency: Duration,
+}
+
+/// Runs a single probe and reports the outcome
+pub fn run_probe(probe: &ProbeConfig) -> anyhow::Result<ProbeResult> {
+    let start = Instant::now();
+
+    let (target, ok, detail) = if let Some(url) = &probe.url {
+        let status = http_status(probe, url)?;
+        let detail = if status == 0 {
+            "connection failed".to_string()
+        } else {
+            format!("HTTP {status}")
+        };
+
+        (url.to_string(), status == probe.expected_status, detail)
+    } else if let Some(addr) = &probe.tcp {
+        let result = tcp_connect(probe, addr);
+        let detail = match &result {
+            Ok(()) => "connected".to_string(),
+            Err(e) => e.to_string(),
+        };
+
+        (format!("tcp://{addr}"), result.is_ok(), detail)
+    } else {
+        anyhow::bail!("Probe {} must define either url or tcp", probe.name())
+    };
+
+    Ok(ProbeResult {
+        name: probe.name().to_string(),
+        container: probe.container.to_string(),
+        target,
+        ok,
+        detail,
+        latency: start.elapsed(),
+    })
+}
+
+impl ProbeResult {
+    /// Result of a probe that could not be run at all, counted as failed
+    pub fn failed(probe: &ProbeConfig, error: &anyhow::Error, latency: Duration) -> Self {
+        let target = match (&probe.url, &probe.tcp) {
+            (Some(url), _) => url.to_string(),
+            (None, Some(addr)) => format!("tcp://{addr}"),
+            (None, None) => String::new(),
+        };
+
+        ProbeResult {
+            name: probe.name().to_string(),
+            container: probe.container.to_string(),
+            target,
+            ok: false,
+            detail: format!("{error:#}"),
+            latency,
+        }
+    }
+}
+
+/// Requests a url and returns the HTTP status code, 0 when no response was received
+fn http_status(probe: &ProbeConfig, url: &str) -> anyhow::Result<u16> {
+    let timeout = probe.timeout.to_string();
+    let curl_args = [
+        "-s",
+        "-o",
+        "/dev/null",
+        "-w",
+        "%{http_code}",
+        "--max-time",
+        &timeout,
+        url,
+    ];
+
+    let output = if probe.in_network {
+        Command::new(DOCKER)
+            .args(["run", "--rm"])
+            .args(["--network", &format!("container:{}", probe.container)])
+            .arg(IMAGE_CURL)
+            .args(curl_args)
+            .output()
+            .context(format!("Failed to probe {url} from {}", probe.container))?
+    } else {
+        Command::new(CURL)
+            .args(curl_args)
+            .output()
+            .context(format!("Failed to probe {url}"))?
+    };
+
+    let status = String::from_utf8(output.stdout)
+        .context("Failed to parse probe output")?
+        .trim()
+        .parse::<u16>()
+        .unwrap_or(0);
+
+    Ok(status)
+}
+
+/// Opens a TCP connection to an address
+fn tcp_connect(probe: &ProbeConfig, addr: &str) -> anyhow::Result<()> {
+    if probe.in_network {
+        let (host, port) = addr
+            .rsplit_once(':')
+            .with_context(|| format!("Invalid tcp address: {addr}"))?;
+
+        let status = Command::new(DOCKER)
+            .args(["run", "--rm"])
+            .args(["--network", &format!("container:{}", probe.container)])
+            .args([IMAGE_BUSYBOX, "nc", "-z", "-w", &probe.timeout.to_string()])
+            .args([host, port])
+            .output()
+            .context(format!("Failed to probe {addr} from {}", probe.container))?
+            .status;
+
+        if !status.success() {
+            anyhow::bail!("connection failed");
+        }
+
+        return Ok(());
+    }
+
+    let socket_addr = addr
+        .to_socket_addrs()
+        .with_context(|| format!("Failed to resolve {addr}"))?
+        .next()
+        .with_context(|| format!("No address found for {addr}"))?;
+
+    TcpStream::connect_timeout(&socket_addr, Duration::from_secs(probe.timeout))?;
+
+    Ok(())
+}
+
+/// Shape of a recorded probe result
+#[derive(Debug, Clone)]
+pub struct ProbeHistoryEntry {
+    pub timestamp: DateTime<Utc>,
+    pub name: String,
+    pub container: String,
+    pub ok: bool,
+    pub latency_ms: u64,
+    pub detail: String,
+}
+
+/// Gets the path of the probe history file
+pub fn history_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join(PROBE_HISTORY_FILE))
+}
+
+/// Appends a probe result to the probe history file
+pub fn record_result(result: &ProbeResult) -> anyhow::Result<()> {
+    let path = history_path()?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("Failed to open probe history: {}", path.display()))?;
+
+    writeln!(
+        file,
+        "{},{},{},{},{},{}",
+        Utc::now().to_rfc3339(),
+        result.name,
+        result.container,
+        result.ok,
+        result.latency.as_millis(),
+        result.detail.replace(',', ";")
+    )
+    .context("Failed to write probe history")?;
+
+    Ok(())
+}
+
+/// Loads recorded probe results newer than the given time
+pub fn load_history(since: DateTime<Utc>) -> anyhow::Result<Vec<ProbeHistoryEntry>> {
+    let path = history_path()?;
+
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read probe history: {}", path.display()))?;
+
+    let entries = contents
+        .lines()
+        .filter_map(|line| parse_history_line(line).ok())
+        .filter(|entry| entry.timestamp >= since)
+        .collect();
+
+    Ok(entries)
+}
+
+/// Parses a single line of the probe history file
+fn parse_history_line(line: &str) -> anyhow::Result<ProbeHistoryEntry> {
+    let parsed = line.splitn(6, ',').collect::<Vec<&str>>();
+
+    if parsed.len() < 6 {
+        anyhow::bail!("Malformed probe history line: {line}");
+    }
+
+    Ok(ProbeHistoryEntry {
+        timestamp: DateTime::parse_from_rfc3339(parsed[0])
+            .context("Failed to parse probe timestamp")?
+            .with_timezone(&Utc),
+        name: parsed[1].to_string(),
+        container: parsed[2].to_string(),
+        ok: parsed[3] == "true",
+        latency_ms: parsed[4].parse().context("Failed to parse probe latency")?,
+        detail: parsed[5].to_string(),
+    })
+}
+
+/// Calculates the given percentile of a list of latencies
+pub fn percentile(latencies: &[u64], percentile: f64) -> u64 {
+    if latencies.is_empty() {
+        return 0;
+    }
+
+    let mut sorted = latencies.to_vec();
+    sorted.sort_unstable();
+
+    let rank = ((percentile / 100.0) * sorted.len() as f64).ceil() as usize;
+
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
@@ -0,0 +1,283 @@
+use crate::commands::DOCKER;
+use crate::docker::list_all_containers;
+use crate::image::ImageRef;
+use crate::parsers::{parse_image_inspect, parse_prune_containers, parse_volume_sizes};
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::process::Command;
+
+/// Container with what it takes up on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskContainer {
+    pub id: String,
+    pub name: String,
+    /// Compose project the container belongs to
+    pub stack: Option<String>,
+    /// Image reference the container was created from, such as nginx:1.27
+    pub image: String,
+    pub image_id: String,
+    pub status: String,
+    /// Log file of the json-file and local log drivers, empty for other drivers
+    pub log_path: String,
+    /// Named volumes mounted into the container
+    pub volumes: Vec<String>,
+}
+
+impl DiskContainer {
+    /// Whether the container is not running and can be removed
+    pub fn is_stopped(&self) -> bool {
+        matches!(self.status.as_str(), "exited" | "created" | "dead")
+    }
+}
+
+/// Image with its size on disk
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskImage {
+    pub id: String,
+    pub size: u64,
+    pub repo_tags: Vec<String>,
+    /// Digests the image was pulled by, kept after its tag moved to a newer image
+    pub repo_digests: Vec<String>,
+}
+
+/// Disk usage of a stack
+#[derive(Debug, Clone, PartialEq)]
+pub struct StackUsage {
+    pub stack: String,
+    pub containers: usize,
+    /// Size of the images used by the stack, including images shared with other stacks
+    pub images: u64,
+    /// Size of the named volumes, None when docker did not report it
+    pub volumes: Option<u64>,
+    /// Size of the container logs, None when a log file could not be read
+    pub logs: Option<u64>,
+}
+
+/// What `prune` removes from a stack
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct PrunePlan {
+    pub containers: Vec<DiskContainer>,
+    pub images: Vec<DiskImage>,
+}
+
+impl PrunePlan {
+    pub fn is_empty(&self) -> bool {
+        self.containers.is_empty() && self.images.is_empty()
+    }
+
+    /// Size of the images that are removed
+    pub fn reclaimable(&self) -> u64 {
+        self.images.iter().map(|image| image.size).sum()
+    }
+}
+
+/// Repository of an image reference or digest, such as nginx for docker.io/library/nginx:1.27
+pub fn repository(reference: &str) -> String {
+    ImageRef::parse(reference)
+        .map(|reference| reference.familiar_repository())
+        .unwrap_or_else(|_| reference.to_string())
+}
+
+/// Sums up the disk usage of a stack
+pub fn stack_usage(
+    stack: &str,
+    containers: &[DiskContainer],
+    images: &[DiskImage],
+    volume_sizes: &BTreeMap<String, u64>,
+    log_sizes: &BTreeMap<String, Option<u64>>,
+) -> StackUsage {
+    let containers = containers
+        .iter()
+        .filter(|container| container.stack.as_deref() == Some(stack))
+        .collect::<Vec<&DiskContainer>>();
+
+    let images = images
+        .iter()
+        .filter(|image| {
+            containers
+                .iter()
+                .any(|container| container.image_id == image.id)
+        })
+        .map(|image| image.size)
+        .sum();
+
+    let mut volumes = containers
+        .iter()
+        .flat_map(|container| &container.volumes)
+        .collect::<Vec<&String>>();
+    volumes.sort();
+    volumes.dedup();
+
+    StackUsage {
+        stack: stack.to_string(),
+        containers: containers.len(),
+        images,
+        volumes: volumes
+            .iter()
+            .map(|volume| volume_sizes.get(*volume).copied())
+            .sum(),
+        logs: containers
+            .iter()
+            .map(|container| log_sizes.get(&container.id).copied().flatten())
+            .sum(),
+    }
+}
+
+/// Picks the stopped containers of a stack and the dangling images only it used to run.
+///
+/// Dangling images are matched by the repository of their digests, and kept while any
+/// container still uses them.
+pub fn plan_prune(stack: &str, containers: &[DiskContainer], dangling: &[DiskImage]) -> PrunePlan {
+    let stopped = containers
+        .iter()
+        .filter(|container| container.stack.as_deref() == Some(stack) && container.is_stopped())
+        .cloned()
+        .collect::<Vec<DiskContainer>>();
+
+    let repositories = containers
+        .iter()
+        .filter(|container| container.stack.as_deref() == Some(stack))
+        .map(|container| repository(&container.image))
+        .collect::<Vec<String>>();
+
+    let images = dangling
+        .iter()
+        .filter(|image| {
+            image
+                .repo_digests
+                .iter()
+                .any(|digest| repositories.contains(&repository(digest)))
+        })
+        .filter(|image| {
+            // containers removed with the plan no longer hold on to their image
+            !containers
+                .iter()
+                .any(|container| container.image_id == image.id && !stopped.contains(container))
+        })
+        .cloned()
+        .collect();
+
+    PrunePlan {
+        containers: stopped,
+        images,
+    }
+}
+
+/// Inspects all containers, including stopped ones
+pub fn inspect_disk_containers() -> anyhow::Result<Vec<DiskContainer>> {
+    let ids = list_all_containers()?
+        .into_iter()
+        .map(|container| container.id)
+        .collect::<Vec<String>>();
+
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    parse_prune_containers(&docker_output(
+        &["inspect"],
+        &ids,
+        "Failed to inspect containers",
+    )?)
+}
+
+/// Inspects images by id
+pub fn inspect_images(ids: &[String]) -> anyhow::Result<Vec<DiskImage>> {
+    if ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    parse_image_inspect(&docker_output(
+        &["image", "inspect"],
+        ids,
+        "Failed to inspect images",
+    )?)
+}
+
+/// Ids of images that lost their tag to a newer image
+pub fn dangling_image_ids() -> anyhow::Result<Vec<String>> {
+    let output = docker_output(
+        &[
+            "images",
+            "--filter",
+            "dangling=true",
+            "--quiet",
+            "--no-trunc",
+        ],
+        &[],
+        "Failed to list dangling images",
+    )?;
+
+    let mut ids = String::from_utf8_lossy(&output)
+        .lines()
+        .map(|line| line.trim().to_string())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<String>>();
+    ids.dedup();
+
+    Ok(ids)
+}
+
+/// Sizes of named volumes as reported by `docker system df`, which can take a while
+pub fn volume_sizes() -> anyhow::Result<BTreeMap<String, u64>> {
+    parse_volume_sizes(&docker_output(
+        &["system", "df", "--verbose", "--format", "{{json .}}"],
+        &[],
+        "Failed to get disk usage",
+    )?)
+}
+
+/// Sizes of container log files by container id, None when a file could not be read
+pub fn log_sizes(containers: &[DiskContainer]) -> BTreeMap<String, Option<u64>> {
+    containers
+        .iter()
+        .map(|container| {
+            // log files are usually only readable by root
+            let size = std::fs::metadata(&container.log_path)
+                .ok()
+                .map(|metadata| metadata.len());
+            (container.id.to_string(), size)
+        })
+        .collect()
+}
+
+/// Removes the containers and then the images of a plan
+pub fn remove(plan: &PrunePlan) -> anyhow::Result<()> {
+    if !plan.containers.is_empty() {
+        let ids = plan
+            .containers
+            .iter()
+            .map(|container| container.id.to_string())
+            .collect::<Vec<String>>();
+        docker_output(&["rm"], &ids, "Failed to remove containers")?;
+    }
+
+    if !plan.images.is_empty() {
+        let ids = plan
+            .images
+            .iter()
+            .map(|image| image.id.to_string())
+            .collect::<Vec<String>>();
+        docker_output(&["image", "rm"], &ids, "Failed to remove images")?;
+    }
+
+    Ok(())
+}
+
+/// Runs docker, failing with its error output when it does not succeed
+fn docker_output(args: &[&str], ids: &[String], error: &str) -> anyhow::Result<Vec<u8>> {
+    let output = Command::new(DOCKER)
+        .args(args)
+        .args(ids)
+        .output()
+        .context(error.to_string())?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "{error}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(output.stdout)
+}
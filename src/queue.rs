@@ -0,0 +1,150 @@
+use crate::prepull::PulledState;
+use crate::utils::get_timestamp;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, VecDeque};
+use std::sync::Mutex;
+
+/// Number of finished actions kept for `daemon status`
+const FINISHED_HISTORY: usize = 20;
+
+/// What asked for an action to run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Trigger {
+    /// A signed request from a registry or CI
+    #[default]
+    Webhook,
+    /// The interval configured for the stack
+    Schedule,
+    /// `dsd-util daemon trigger`
+    Manual,
+    /// The maintenance window of the stack, applying images pulled on schedule
+    Maintenance,
+}
+
+/// Pull and recreate of services of a stack, waiting for or during its run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedAction {
+    pub stack: String,
+    pub services: BTreeSet<String>,
+    /// Triggers of all requests merged into this action
+    pub triggers: BTreeSet<Trigger>,
+    pub queued_at: String,
+}
+
+/// An action that ran, with its outcome
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FinishedAction {
+    #[serde(flatten)]
+    pub action: QueuedAction,
+    pub finished_at: String,
+    /// Error when the action failed
+    pub error: Option<String>,
+}
+
+/// How a request was added to the queue
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Enqueued {
+    /// Added as a new action
+    Queued,
+    /// Merged into an action of the same stack that has not started yet
+    Merged,
+}
+
+/// Snapshot of the queue, as reported by `daemon status`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct QueueState {
+    pub running: Vec<QueuedAction>,
+    pub pending: Vec<QueuedAction>,
+    /// Most recently finished actions, newest first
+    pub finished: VecDeque<FinishedAction>,
+    /// Services pulled on schedule, waiting for the maintenance window of their stack
+    #[serde(default)]
+    pub pulled: PulledState,
+}
+
+/// Queue of triggered actions that runs at most one action per stack at a time.
+///
+/// Requests for a stack that already has a pending action are merged into it, so a burst of
+/// webhooks results in a single pull and recreate. Actions of different stacks do not conflict
+/// and may run side by side.
+#[derive(Debug, Default)]
+pub struct ActionQueue {
+    state: Mutex<QueueState>,
+}
+
+impl ActionQueue {
+    /// Adds a request, merging it into a pending action of the same stack if there is one
+    pub fn enqueue(&self, stack: &str, services: &[String], trigger: Trigger) -> Enqueued {
+        let mut state = self.lock();
+
+        if let Some(pending) = state
+            .pending
+            .iter_mut()
+            .find(|action| action.stack == stack)
+        {
+            pending.services.extend(services.iter().cloned());
+            pending.triggers.insert(trigger);
+            return Enqueued::Merged;
+        }
+
+        state.pending.push(QueuedAction {
+            stack: stack.to_string(),
+            services: services.iter().cloned().collect(),
+            triggers: BTreeSet::from([trigger]),
+            queued_at: get_timestamp(),
+        });
+
+        Enqueued::Queued
+    }
+
+    /// Takes the oldest pending action whose stack is not running and marks it running
+    pub fn start_next(&self) -> Option<QueuedAction> {
+        let mut state = self.lock();
+
+        let index = state.pending.iter().position(|pending| {
+            !state
+                .running
+                .iter()
+                .any(|running| running.stack == pending.stack)
+        })?;
+
+        let action = state.pending.remove(index);
+        state.running.push(action.clone());
+
+        Some(action)
+    }
+
+    /// Records the outcome of a running action, letting the next action of its stack start
+    pub fn finish(&self, stack: &str, error: Option<String>) {
+        let mut state = self.lock();
+
+        let Some(index) = state
+            .running
+            .iter()
+            .position(|running| running.stack == stack)
+        else {
+            return;
+        };
+
+        let action = state.running.remove(index);
+        state.finished.push_front(FinishedAction {
+            action,
+            finished_at: get_timestamp(),
+            error,
+        });
+        state.finished.truncate(FINISHED_HISTORY);
+    }
+
+    /// Current state of the queue
+    pub fn snapshot(&self) -> QueueState {
+        self.lock().clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, QueueState> {
+        // a panicking action must not take the whole daemon down with it
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
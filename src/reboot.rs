@@ -0,0 +1,130 @@
+use crate::config::{state_dir, ENV_STATE_DIR};
+use crate::maintenance::maintenance_path;
+use anyhow::Context;
+use std::path::Path;
+use std::process::Command;
+
+/// Created by Debian and Ubuntu when an update needs a reboot to take effect
+const REBOOT_REQUIRED_PATH: &str = "/var/run/reboot-required";
+/// Packages that asked for the reboot, one per line
+const REBOOT_REQUIRED_PKGS_PATH: &str = "/var/run/reboot-required.pkgs";
+
+const SYSTEMCTL: &str = "systemctl";
+
+/// Unit starting the stacks stopped before a reboot once docker is up again
+pub const THAW_UNIT: &str = "dsd-util-thaw.service";
+const THAW_UNIT_DIR: &str = "/etc/systemd/system";
+
+/// Environment the thaw unit passes on, so it finds the maintenance state and config file
+const THAW_UNIT_ENV: [&str; 3] = ["HOME", "XDG_STATE_HOME", "XDG_CONFIG_HOME"];
+
+/// Checks whether the host asked for a reboot, returning the packages that asked for it, which
+/// may be none
+pub fn reboot_required() -> Option<Vec<String>> {
+    if !Path::new(REBOOT_REQUIRED_PATH).exists() {
+        return None;
+    }
+
+    Some(
+        std::fs::read_to_string(REBOOT_REQUIRED_PKGS_PATH)
+            .map(|contents| parse_reboot_packages(&contents))
+            .unwrap_or_default(),
+    )
+}
+
+/// Parses the packages listed in reboot-required.pkgs, without duplicates
+pub fn parse_reboot_packages(contents: &str) -> Vec<String> {
+    let mut packages: Vec<String> = vec![];
+
+    for package in contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+    {
+        if !packages.iter().any(|other| other == package) {
+            packages.push(package.to_string());
+        }
+    }
+
+    packages
+}
+
+/// Renders the oneshot unit running `maintenance end` after the next boot, disabling itself once
+/// the stacks are back. It is skipped while no maintenance is recorded in the state file.
+pub fn render_thaw_unit(executable: &Path, state_file: &Path, env: &[(String, String)]) -> String {
+    let environment = env
+        .iter()
+        .map(|(name, value)| format!("Environment=\"{name}={value}\"\n"))
+        .collect::<String>();
+
+    format!(
+        "[Unit]
+Description=Start the stacks stopped by dsd-util reboot --safe
+Requires=docker.service
+After=docker.service network-online.target
+Wants=network-online.target
+ConditionPathExists={state_file}
+
+[Service]
+Type=oneshot
+{environment}ExecStart={executable} maintenance end --notify
+ExecStartPost={SYSTEMCTL} disable {THAW_UNIT}
+
+[Install]
+WantedBy=multi-user.target
+",
+        executable = executable.display(),
+        state_file = state_file.display()
+    )
+}
+
+/// Installs and enables the thaw unit for the next boot
+pub fn install_thaw_unit() -> anyhow::Result<()> {
+    let executable = std::env::current_exe().context("Failed to determine the path of dsd-util")?;
+    let mut env = THAW_UNIT_ENV
+        .iter()
+        .filter_map(|name| {
+            std::env::var(name)
+                .ok()
+                .filter(|value| !value.is_empty())
+                .map(|value| (name.to_string(), value))
+        })
+        .collect::<Vec<(String, String)>>();
+    // the state directory may come from --state-dir, which the unit would not see otherwise
+    env.push((
+        ENV_STATE_DIR.to_string(),
+        state_dir()?.display().to_string(),
+    ));
+
+    let path = Path::new(THAW_UNIT_DIR).join(THAW_UNIT);
+    std::fs::write(
+        &path,
+        render_thaw_unit(&executable, &maintenance_path()?, &env),
+    )
+    .with_context(|| format!("Failed to write {}", path.display()))?;
+
+    systemctl(&["daemon-reload"])?;
+    systemctl(&["enable", THAW_UNIT])
+}
+
+/// Reboots the host through systemd
+pub fn reboot_host() -> anyhow::Result<()> {
+    systemctl(&["reboot"])
+}
+
+fn systemctl(args: &[&str]) -> anyhow::Result<()> {
+    let output = Command::new(SYSTEMCTL)
+        .args(args)
+        .output()
+        .with_context(|| format!("Failed to run {SYSTEMCTL} {}", args.join(" ")))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to run {SYSTEMCTL} {}: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
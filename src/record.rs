@@ -0,0 +1,279 @@
+use crate::utils::StatsData;
+use anyhow::Context;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::BTreeMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::Path;
+use std::process::Command;
+
+const SQLITE: &str = "sqlite3";
+
+/// Columns of recorded stats, in CSV and SQLite alike
+pub const RECORD_COLUMNS: [&str; 11] = [
+    "timestamp",
+    "container",
+    "cpu_percent",
+    "memory_percent",
+    "memory_used",
+    "memory_limit",
+    "net_rx",
+    "net_tx",
+    "block_read",
+    "block_write",
+    "pids",
+];
+
+/// File formats stats can be recorded to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordFormat {
+    Csv,
+    /// SQLite database, written with the sqlite3 cli
+    Sqlite,
+}
+
+impl RecordFormat {
+    /// Picks SQLite for files ending in .db, .sqlite or .sqlite3 and CSV otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("db" | "sqlite" | "sqlite3") => RecordFormat::Sqlite,
+            _ => RecordFormat::Csv,
+        }
+    }
+}
+
+/// Stats of a container at a point in time
+#[derive(Debug, Clone)]
+pub struct StatsSample {
+    pub timestamp: DateTime<Utc>,
+    pub stats: StatsData,
+}
+
+/// Min, average and max usage of a container over a time range
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageSummary {
+    pub container: String,
+    pub samples: usize,
+    pub cpu_min: f64,
+    pub cpu_avg: f64,
+    pub cpu_max: f64,
+    /// Memory used in bytes
+    pub memory_min: u64,
+    pub memory_avg: u64,
+    pub memory_max: u64,
+}
+
+/// Formats a sample as a line of comma separated values in the order of RECORD_COLUMNS
+pub fn format_sample(sample: &StatsSample) -> String {
+    let stats = &sample.stats;
+
+    format!(
+        "{},{},{},{},{},{},{},{},{},{},{}",
+        sample.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+        stats.container_name,
+        stats.cpu_percent,
+        stats.memory_percent,
+        stats.memory_used,
+        stats.memory_limit,
+        stats.net_rx,
+        stats.net_tx,
+        stats.block_read,
+        stats.block_write,
+        stats.pids
+    )
+}
+
+/// Parses a line of comma separated values in the order of RECORD_COLUMNS
+pub fn parse_sample(line: &str) -> anyhow::Result<StatsSample> {
+    let parsed = line.trim().split(',').collect::<Vec<&str>>();
+
+    if parsed.len() != RECORD_COLUMNS.len() {
+        anyhow::bail!("Invalid recorded stats: {line}");
+    }
+
+    let number = |index: usize| {
+        parsed[index]
+            .parse::<u64>()
+            .with_context(|| format!("Invalid {} in: {line}", RECORD_COLUMNS[index]))
+    };
+    let percent = |index: usize| {
+        parsed[index]
+            .parse::<f64>()
+            .with_context(|| format!("Invalid {} in: {line}", RECORD_COLUMNS[index]))
+    };
+
+    Ok(StatsSample {
+        timestamp: DateTime::parse_from_rfc3339(parsed[0])
+            .with_context(|| format!("Invalid timestamp in: {line}"))?
+            .with_timezone(&Utc),
+        stats: StatsData {
+            container_name: parsed[1].to_string(),
+            cpu_percent: percent(2)?,
+            memory_percent: percent(3)?,
+            memory_used: number(4)?,
+            memory_limit: number(5)?,
+            net_rx: number(6)?,
+            net_tx: number(7)?,
+            block_read: number(8)?,
+            block_write: number(9)?,
+            pids: number(10)?,
+        },
+    })
+}
+
+/// Appends samples to a CSV file or SQLite database, creating it when needed
+pub fn append_samples(path: &Path, samples: &[StatsSample]) -> anyhow::Result<()> {
+    if samples.is_empty() {
+        return Ok(());
+    }
+
+    match RecordFormat::from_path(path) {
+        RecordFormat::Csv => {
+            let is_new = std::fs::metadata(path).map_or(true, |metadata| metadata.len() == 0);
+
+            let mut file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .with_context(|| format!("Failed to open {}", path.display()))?;
+
+            let mut contents = String::new();
+            if is_new {
+                contents.push_str(&RECORD_COLUMNS.join(","));
+                contents.push('\n');
+            }
+            for sample in samples {
+                contents.push_str(&format_sample(sample));
+                contents.push('\n');
+            }
+
+            file.write_all(contents.as_bytes())
+                .with_context(|| format!("Failed to write {}", path.display()))
+        }
+        RecordFormat::Sqlite => {
+            let values = samples
+                .iter()
+                .map(|sample| {
+                    let stats = &sample.stats;
+                    format!(
+                        "('{}','{}',{},{},{},{},{},{},{},{},{})",
+                        sample.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+                        stats.container_name.replace('\'', "''"),
+                        stats.cpu_percent,
+                        stats.memory_percent,
+                        stats.memory_used,
+                        stats.memory_limit,
+                        stats.net_rx,
+                        stats.net_tx,
+                        stats.block_read,
+                        stats.block_write,
+                        stats.pids
+                    )
+                })
+                .collect::<Vec<String>>()
+                .join(",");
+
+            run_sqlite(
+                path,
+                &[],
+                &format!(
+                    "CREATE TABLE IF NOT EXISTS stats (timestamp TEXT NOT NULL, container TEXT NOT NULL, \
+                     cpu_percent REAL, memory_percent REAL, memory_used INTEGER, memory_limit INTEGER, \
+                     net_rx INTEGER, net_tx INTEGER, block_read INTEGER, block_write INTEGER, pids INTEGER); \
+                     INSERT INTO stats ({}) VALUES {values};",
+                    RECORD_COLUMNS.join(",")
+                ),
+            )
+            .map(|_| ())
+        }
+    }
+}
+
+/// Loads all samples from a CSV file or SQLite database
+pub fn load_samples(path: &Path) -> anyhow::Result<Vec<StatsSample>> {
+    let contents = match RecordFormat::from_path(path) {
+        RecordFormat::Csv => std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?,
+        RecordFormat::Sqlite => {
+            if !path.exists() {
+                anyhow::bail!("No such database: {}", path.display());
+            }
+
+            run_sqlite(
+                path,
+                &["-csv"],
+                &format!(
+                    "SELECT {} FROM stats ORDER BY timestamp;",
+                    RECORD_COLUMNS.join(",")
+                ),
+            )?
+        }
+    };
+
+    let header = RECORD_COLUMNS.join(",");
+
+    contents
+        .lines()
+        .filter(|line| !line.trim().is_empty() && line.trim() != header)
+        .map(parse_sample)
+        .collect()
+}
+
+/// Runs SQL against a database with the sqlite3 cli, returning its output
+fn run_sqlite(path: &Path, args: &[&str], sql: &str) -> anyhow::Result<String> {
+    let output = Command::new(SQLITE)
+        .args(args)
+        .arg(path)
+        .arg(sql)
+        .output()
+        .context("Failed to run sqlite3, is it installed?")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to query {}: {}",
+            path.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    String::from_utf8(output.stdout).context("Failed to parse sqlite3 output")
+}
+
+/// Summarizes CPU and memory usage per container of samples taken between since and until
+pub fn summarize(
+    samples: &[StatsSample],
+    since: DateTime<Utc>,
+    until: DateTime<Utc>,
+) -> Vec<UsageSummary> {
+    let mut by_container: BTreeMap<&str, Vec<&StatsData>> = BTreeMap::new();
+
+    for sample in samples
+        .iter()
+        .filter(|sample| sample.timestamp >= since && sample.timestamp <= until)
+    {
+        by_container
+            .entry(&sample.stats.container_name)
+            .or_default()
+            .push(&sample.stats);
+    }
+
+    by_container
+        .into_iter()
+        .map(|(container, stats)| {
+            let count = stats.len();
+            let cpu = stats.iter().map(|stats| stats.cpu_percent);
+            let memory = stats.iter().map(|stats| stats.memory_used);
+
+            UsageSummary {
+                container: container.to_string(),
+                samples: count,
+                cpu_min: cpu.clone().fold(f64::INFINITY, f64::min),
+                cpu_avg: cpu.clone().sum::<f64>() / count as f64,
+                cpu_max: cpu.fold(0.0, f64::max),
+                memory_min: memory.clone().min().unwrap_or_default(),
+                memory_avg: (memory.clone().map(u128::from).sum::<u128>() / count as u128) as u64,
+                memory_max: memory.max().unwrap_or_default(),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,225 @@
+use crate::commands::DOCKER;
+use crate::image::{ImageRef, DOCKER_HUB};
+use anyhow::Context;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+/// Key docker stores Docker Hub credentials under
+const DOCKER_HUB_AUTH_KEY: &str = "https://index.docker.io/v1/";
+
+/// Messages registries and docker answer a pull without valid credentials with
+const AUTH_ERRORS: [&str; 7] = [
+    "unauthorized",
+    "authentication required",
+    "no basic auth credentials",
+    "pull access denied",
+    "denied: requested access",
+    "denied: access forbidden",
+    "403 forbidden",
+];
+
+/// A registry refused a pull for lack of valid credentials
+#[derive(Debug, Clone, PartialEq)]
+pub struct AuthenticationRequired {
+    pub registry: String,
+    /// Whether docker has credentials for the registry, which were then rejected
+    pub has_credentials: bool,
+}
+
+impl fmt::Display for AuthenticationRequired {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.has_credentials {
+            write!(
+                f,
+                "Authentication required for {}, the stored credentials were rejected",
+                self.registry
+            )
+        } else {
+            write!(
+                f,
+                "Authentication required for {}, run docker login {} or pass --registry-user and --registry-password-stdin",
+                self.registry, self.registry
+            )
+        }
+    }
+}
+
+impl std::error::Error for AuthenticationRequired {}
+
+/// Credentials to log in to a registry with before pulling
+#[derive(Clone, PartialEq)]
+pub struct RegistryLogin {
+    /// Registry to log in to, inferred from the images when not given
+    pub registry: Option<String>,
+    pub user: String,
+    pub password: String,
+}
+
+// the password must not end up in logs
+impl fmt::Debug for RegistryLogin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RegistryLogin")
+            .field("registry", &self.registry)
+            .field("user", &self.user)
+            .field("password", &"<redacted>")
+            .finish()
+    }
+}
+
+/// Parts of the docker cli config telling which registries docker has credentials for
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+pub struct DockerConfig {
+    pub auths: HashMap<String, serde_json::Value>,
+    /// Credential helper storing the credentials of all registries
+    pub creds_store: Option<String>,
+    /// Credential helpers by registry
+    pub cred_helpers: HashMap<String, String>,
+}
+
+impl DockerConfig {
+    /// Whether docker may have credentials for a registry. Credentials in a credential store
+    /// cannot be listed without asking the helper, so a store counts for every registry.
+    pub fn has_credentials(&self, registry: &str) -> bool {
+        let keys = if registry == DOCKER_HUB {
+            vec![DOCKER_HUB, DOCKER_HUB_AUTH_KEY, "index.docker.io"]
+        } else {
+            vec![registry]
+        };
+
+        self.creds_store.is_some()
+            || keys.iter().any(|key| {
+                self.cred_helpers.contains_key(*key)
+                    || self.auths.keys().any(|auth| {
+                        auth == key
+                            || auth
+                                .trim_start_matches("https://")
+                                .trim_start_matches("http://")
+                                .split('/')
+                                .next()
+                                == Some(key)
+                    })
+            })
+    }
+}
+
+/// Path of the docker cli config, following DOCKER_CONFIG like docker does
+pub fn docker_config_path() -> anyhow::Result<PathBuf> {
+    if let Some(dir) = std::env::var_os("DOCKER_CONFIG").filter(|dir| !dir.is_empty()) {
+        return Ok(PathBuf::from(dir).join("config.json"));
+    }
+
+    let home = std::env::var_os("HOME").context("Failed to determine home directory")?;
+
+    Ok(PathBuf::from(home).join(".docker").join("config.json"))
+}
+
+/// Loads the docker cli config, which is empty when docker never logged in anywhere
+pub fn load_docker_config() -> anyhow::Result<DockerConfig> {
+    let path = docker_config_path()?;
+
+    if !path.exists() {
+        return Ok(DockerConfig::default());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read docker config: {}", path.display()))?;
+
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse docker config: {}", path.display()))
+}
+
+/// Registry an image is pulled from, following how docker tells a registry from a namespace
+pub fn registry_host(image: &str) -> String {
+    ImageRef::parse(image).map_or_else(|_| DOCKER_HUB.to_string(), |reference| reference.registry)
+}
+
+/// Whether the error output of a pull says the registry wants credentials
+pub fn is_auth_error(stderr: &str) -> bool {
+    let stderr = stderr.to_lowercase();
+    AUTH_ERRORS.iter().any(|error| stderr.contains(error))
+}
+
+/// The error for a pull a registry refused, telling whether docker had credentials for it
+pub fn authentication_required(image: &str) -> AuthenticationRequired {
+    let registry = registry_host(image);
+
+    AuthenticationRequired {
+        has_credentials: load_docker_config()
+            .map(|config| config.has_credentials(&registry))
+            .unwrap_or(false),
+        registry,
+    }
+}
+
+/// Logs docker in to a registry, passing the password on stdin so it stays out of the process list
+pub fn docker_login(registry: &str, user: &str, password: &str) -> anyhow::Result<()> {
+    let mut child = Command::new(DOCKER)
+        .args(["login", registry, "--username", user, "--password-stdin"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to log in to {registry}"))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(password.as_bytes())
+            .with_context(|| format!("Failed to log in to {registry}"))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to log in to {registry}"))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to log in to {registry}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Reads a password piped to stdin, without its trailing newline
+pub fn read_password_stdin() -> anyhow::Result<String> {
+    let mut password = String::new();
+    std::io::stdin()
+        .read_to_string(&mut password)
+        .context("Failed to read password from stdin")?;
+
+    let password = password.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        anyhow::bail!("No password given on stdin");
+    }
+
+    Ok(password)
+}
+
+/// Registry credentials apply to, which has to be given when images come from several
+pub fn login_registry(login: &RegistryLogin, images: &[String]) -> anyhow::Result<String> {
+    if let Some(registry) = &login.registry {
+        return Ok(registry.to_string());
+    }
+
+    let mut registries = images
+        .iter()
+        .map(|image| registry_host(image))
+        .collect::<Vec<String>>();
+    registries.sort();
+    registries.dedup();
+
+    match registries.as_slice() {
+        [registry] => Ok(registry.to_string()),
+        [] => anyhow::bail!("No images to log in to a registry for"),
+        registries => anyhow::bail!(
+            "Images come from several registries ({}), pass --registry to pick the one to log in to",
+            registries.join(", ")
+        ),
+    }
+}
@@ -0,0 +1,532 @@
+use crate::commands::DOCKER;
+use crate::config::{state_dir, Config};
+use crate::docker::list_all_containers;
+use crate::document::Document;
+use crate::error::check_output;
+use crate::history::{load_actions, Action, HistoryEntry};
+use crate::hostinfo::{free_disk_space, host_name};
+use crate::i18n::{tr, tr_args};
+use crate::notify::send_message;
+use crate::probes::{load_history, ProbeHistoryEntry};
+use crate::prune::{inspect_disk_containers, inspect_images, log_sizes, stack_usage, volume_sizes};
+use crate::reboot::reboot_required;
+use crate::record::{load_samples, summarize, StatsSample, UsageSummary};
+use crate::timeline::action_events;
+use crate::units::NumberFormat;
+use crate::utils::{get_docker_root, get_stats, inspect_containers, InspectData};
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+/// Disk usage per stack at the last daily report, to show how it changed since
+const DISK_SNAPSHOT_FILE: &str = "report-disk.json";
+/// Containers with the highest CPU and memory usage shown each
+const HIGHLIGHTS: usize = 5;
+
+/// Disk usage of the host at a point in time
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DiskSnapshot {
+    pub at: DateTime<Utc>,
+    /// Space left on the filesystem of the docker root, None when it could not be read
+    pub free: Option<u64>,
+    /// Images, volumes and logs per stack in bytes
+    pub stacks: BTreeMap<String, u64>,
+}
+
+/// Disk usage of a stack and how it changed since the previous report
+#[derive(Debug, Clone, PartialEq)]
+pub struct DiskTrend {
+    pub stack: String,
+    pub size: u64,
+    /// Bytes more than at the previous report, None for stacks it did not know
+    pub change: Option<i64>,
+}
+
+/// Everything a daily report tells about the last day
+#[derive(Debug, Clone)]
+pub struct DailyReport {
+    pub host: String,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    /// All containers, including stopped ones
+    pub containers: Vec<InspectData>,
+    /// Exits with a non-zero code per container
+    pub crashes: BTreeMap<String, usize>,
+    pub actions: Vec<HistoryEntry>,
+    pub usage: Vec<UsageSummary>,
+    pub disk: DiskSnapshot,
+    pub previous_disk: Option<DiskSnapshot>,
+    pub warnings: Vec<String>,
+}
+
+/// Turns a line of `docker events --format '{{json .}}'` about a container exiting into its
+/// name and exit code
+pub fn parse_exit_event(line: &str) -> Option<(String, String)> {
+    let event = serde_json::from_str::<serde_json::Value>(line).ok()?;
+    if event.get("Action")?.as_str()? != "die" {
+        return None;
+    }
+
+    let attribute = |name: &str| {
+        event
+            .pointer(&format!("/Actor/Attributes/{name}"))
+            .and_then(|value| value.as_str())
+            .map(str::to_string)
+    };
+
+    Some((
+        attribute("name")?,
+        attribute("exitCode").unwrap_or_default(),
+    ))
+}
+
+/// Counts exits with a non-zero code per container in lines of `docker events`
+pub fn count_crashes(events: &str) -> BTreeMap<String, usize> {
+    let mut crashes = BTreeMap::new();
+
+    for (container, code) in events.lines().filter_map(parse_exit_event) {
+        if code != "0" {
+            *crashes.entry(container).or_default() += 1;
+        }
+    }
+
+    crashes
+}
+
+/// Compares disk usage per stack to the previous report, largest stacks first
+pub fn disk_trends(previous: Option<&DiskSnapshot>, current: &DiskSnapshot) -> Vec<DiskTrend> {
+    let mut trends = current
+        .stacks
+        .iter()
+        .map(|(stack, size)| DiskTrend {
+            stack: stack.to_string(),
+            size: *size,
+            change: previous
+                .and_then(|previous| previous.stacks.get(stack))
+                .map(|previous| *size as i64 - *previous as i64),
+        })
+        .collect::<Vec<DiskTrend>>();
+
+    trends.sort_by(|a, b| b.size.cmp(&a.size).then_with(|| a.stack.cmp(&b.stack)));
+    trends
+}
+
+/// Containers with the highest peak CPU or memory usage, highest CPU first
+pub fn resource_highlights(usage: &[UsageSummary], count: usize) -> Vec<&UsageSummary> {
+    let mut by_cpu = usage.iter().collect::<Vec<&UsageSummary>>();
+    by_cpu.sort_by(|a, b| b.cpu_max.total_cmp(&a.cpu_max));
+    let mut by_memory = usage.iter().collect::<Vec<&UsageSummary>>();
+    by_memory.sort_by_key(|summary| std::cmp::Reverse(summary.memory_max));
+
+    let mut highlights = by_cpu
+        .iter()
+        .take(count)
+        .copied()
+        .collect::<Vec<&UsageSummary>>();
+    for summary in by_memory.into_iter().take(count) {
+        if !highlights
+            .iter()
+            .any(|highlight| highlight.container == summary.container)
+        {
+            highlights.push(summary);
+        }
+    }
+
+    highlights.sort_by(|a, b| b.cpu_max.total_cmp(&a.cpu_max));
+    highlights
+}
+
+/// Problems that still need attention: unhealthy or crashed containers, probes failing on their
+/// last run and a pending reboot
+pub fn open_warnings(
+    containers: &[InspectData],
+    probes: &[ProbeHistoryEntry],
+    reboot: Option<&[String]>,
+) -> Vec<String> {
+    let mut warnings = vec![];
+
+    for container in containers {
+        if container.health == "unhealthy" {
+            warnings.push(tr_args(
+                "{name} is unhealthy",
+                &[("name", &container.container_name)],
+            ));
+        }
+        if matches!(container.status.as_str(), "restarting" | "dead") {
+            warnings.push(tr_args(
+                "{name} is {status}",
+                &[
+                    ("name", &container.container_name),
+                    ("status", &container.status),
+                ],
+            ));
+        }
+    }
+
+    let mut latest: BTreeMap<(&str, &str), &ProbeHistoryEntry> = BTreeMap::new();
+    for probe in probes {
+        let entry = latest
+            .entry((&probe.container, &probe.name))
+            .or_insert(probe);
+        if probe.timestamp >= entry.timestamp {
+            *entry = probe;
+        }
+    }
+    for probe in latest.values().filter(|probe| !probe.ok) {
+        warnings.push(tr_args(
+            "Probe {name} of {container} is failing: {detail}",
+            &[
+                ("name", &probe.name),
+                ("container", &probe.container),
+                ("detail", &probe.detail),
+            ],
+        ));
+    }
+
+    if let Some(packages) = reboot {
+        warnings.push(if packages.is_empty() {
+            tr("The host needs a reboot")
+        } else {
+            tr_args(
+                "The host needs a reboot for {packages}",
+                &[("packages", &packages.join(", "))],
+            )
+        });
+    }
+
+    warnings
+}
+
+/// Formats a change in size with its sign, such as +1.5 GiB
+fn format_change(change: i64, number_format: &NumberFormat) -> String {
+    let size = number_format.size(change.unsigned_abs());
+    if change < 0 {
+        format!("-{size}")
+    } else {
+        format!("+{size}")
+    }
+}
+
+/// Lays out a daily report as a document
+pub fn render_report(report: &DailyReport, number_format: &NumberFormat) -> Document {
+    let mut document = Document::new(tr_args(
+        "Daily report for {host}",
+        &[("host", &report.host)],
+    ));
+
+    let running = report
+        .containers
+        .iter()
+        .filter(|container| container.status == "running")
+        .count();
+    document.paragraph(tr_args(
+        "{since} to {until}: {running} of {total} container(s) running, {warnings} open warning(s).",
+        &[
+            ("since", &report.since.format("%Y-%m-%d %H:%M UTC")),
+            ("until", &report.until.format("%Y-%m-%d %H:%M UTC")),
+            ("running", &running),
+            ("total", &report.containers.len()),
+            ("warnings", &report.warnings.len()),
+        ],
+    ));
+
+    document.heading(tr("Open warnings"));
+    if report.warnings.is_empty() {
+        document.paragraph(tr("Nothing needs attention."));
+    } else {
+        document.list(report.warnings.clone());
+    }
+
+    document.heading(tr("Uptime"));
+    document.table(
+        &[
+            "CONTAINER",
+            "STATUS",
+            "HEALTH",
+            "UPTIME",
+            "RESTARTS",
+            "CRASHES",
+        ],
+        report
+            .containers
+            .iter()
+            .map(|container| {
+                vec![
+                    container.container_name.to_string(),
+                    container.status.to_string(),
+                    container.health.to_string(),
+                    container.uptime.to_string(),
+                    container.restart_count.to_string(),
+                    report
+                        .crashes
+                        .get(&container.container_name)
+                        .copied()
+                        .unwrap_or_default()
+                        .to_string(),
+                ]
+            })
+            .collect(),
+    );
+
+    document.heading(tr("Restarts and updates"));
+    let count = |action: Action| {
+        report
+            .actions
+            .iter()
+            .filter(|entry| entry.action == action)
+            .count()
+    };
+    document.paragraph(tr_args(
+        "{restarts} restart(s), {updates} update(s) and {rollbacks} rollback(s) by dsd-util.",
+        &[
+            ("restarts", &count(Action::Restart)),
+            ("updates", &count(Action::Update)),
+            ("rollbacks", &count(Action::Rollback)),
+        ],
+    ));
+    if !report.actions.is_empty() {
+        document.list(
+            report
+                .actions
+                .iter()
+                .filter_map(|entry| {
+                    let event =
+                        action_events(std::slice::from_ref(entry), &entry.container).pop()?;
+                    Some(format!(
+                        "{} {}: {}",
+                        event.at.format("%H:%M"),
+                        entry.container,
+                        event.summary
+                    ))
+                })
+                .collect(),
+        );
+    }
+
+    document.heading(tr("Resource highlights"));
+    let highlights = resource_highlights(&report.usage, HIGHLIGHTS);
+    if highlights.is_empty() {
+        document.paragraph(tr("No stats available."));
+    } else {
+        document.table(
+            &["CONTAINER", "CPU AVG", "CPU MAX", "MEM AVG", "MEM MAX"],
+            highlights
+                .iter()
+                .map(|summary| {
+                    vec![
+                        summary.container.to_string(),
+                        number_format.percent(summary.cpu_avg),
+                        number_format.percent(summary.cpu_max),
+                        number_format.size(summary.memory_avg),
+                        number_format.size(summary.memory_max),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    document.heading(tr("Disk"));
+    if let Some(free) = report.disk.free {
+        document.paragraph(tr_args(
+            "{free} free for docker.",
+            &[("free", &number_format.size(free))],
+        ));
+    }
+    let trends = disk_trends(report.previous_disk.as_ref(), &report.disk);
+    if !trends.is_empty() {
+        document.table(
+            &["STACK", "SIZE", "CHANGE"],
+            trends
+                .iter()
+                .map(|trend| {
+                    vec![
+                        trend.stack.to_string(),
+                        number_format.size(trend.size),
+                        trend.change.map_or_else(
+                            || "N/A".to_string(),
+                            |change| format_change(change, number_format),
+                        ),
+                    ]
+                })
+                .collect(),
+        );
+    }
+
+    document
+}
+
+/// Exits of containers docker still knows of, which are only its last few hundred events
+fn crashes_since(since: DateTime<Utc>) -> anyhow::Result<BTreeMap<String, usize>> {
+    let output = check_output(
+        "events",
+        Command::new(DOCKER)
+            .args([
+                "events",
+                "--since",
+                &since.timestamp().to_string(),
+                "--until",
+                &Utc::now().timestamp().to_string(),
+                "--filter",
+                "type=container",
+                "--filter",
+                "event=die",
+                "--format",
+                "{{json .}}",
+            ])
+            .output(),
+    )?;
+
+    Ok(count_crashes(&String::from_utf8_lossy(&output.stdout)))
+}
+
+/// Usage of containers over the last day from recorded stats, or right now without a recording
+fn usage_since(input: Option<&Path>, since: DateTime<Utc>) -> anyhow::Result<Vec<UsageSummary>> {
+    let now = Utc::now();
+
+    let samples = match input {
+        Some(input) => load_samples(input)?,
+        None => get_stats(&[])?
+            .into_iter()
+            .map(|stats| StatsSample {
+                timestamp: now,
+                stats,
+            })
+            .collect(),
+    };
+
+    Ok(summarize(&samples, since, now))
+}
+
+/// Disk usage per stack right now
+fn disk_snapshot() -> anyhow::Result<DiskSnapshot> {
+    let containers = inspect_disk_containers()?;
+
+    let mut image_ids = containers
+        .iter()
+        .map(|container| container.image_id.to_string())
+        .collect::<Vec<String>>();
+    image_ids.sort();
+    image_ids.dedup();
+    let images = inspect_images(&image_ids)?;
+    // volumes are left unsized when docker cannot report them
+    let volume_sizes = volume_sizes().unwrap_or_default();
+    let log_sizes = log_sizes(&containers);
+
+    let mut stacks = BTreeMap::new();
+    for stack in containers
+        .iter()
+        .filter_map(|container| container.stack.as_ref())
+    {
+        if stacks.contains_key(stack) {
+            continue;
+        }
+
+        let usage = stack_usage(stack, &containers, &images, &volume_sizes, &log_sizes);
+        stacks.insert(
+            stack.to_string(),
+            usage.images + usage.volumes.unwrap_or_default() + usage.logs.unwrap_or_default(),
+        );
+    }
+
+    Ok(DiskSnapshot {
+        at: Utc::now(),
+        free: get_docker_root()
+            .ok()
+            .and_then(|(root, _)| free_disk_space(&root).ok()),
+        stacks,
+    })
+}
+
+/// Reads the disk usage stored by the previous daily report
+pub fn load_disk_snapshot() -> anyhow::Result<Option<DiskSnapshot>> {
+    let path = state_dir()?.join(DISK_SNAPSHOT_FILE);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .map(Some)
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Stores disk usage for the next daily report to compare against
+pub fn save_disk_snapshot(snapshot: &DiskSnapshot) -> anyhow::Result<()> {
+    let dir = state_dir()?;
+    std::fs::create_dir_all(&dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+    let path = dir.join(DISK_SNAPSHOT_FILE);
+    std::fs::write(&path, serde_json::to_string(snapshot)?)
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Compiles the report of the last day, storing disk usage for the next one to compare against.
+///
+/// Resource usage comes from stats recorded to `input` when given, and is a single sample taken
+/// right now otherwise.
+pub fn compile_daily_report(input: Option<&Path>) -> anyhow::Result<DailyReport> {
+    let until = Utc::now();
+    let since = until - chrono::Duration::days(1);
+
+    let names = list_all_containers()?
+        .into_iter()
+        .map(|container| container.name)
+        .collect::<Vec<String>>();
+    let containers = if names.is_empty() {
+        vec![]
+    } else {
+        inspect_containers(&names)?
+    };
+
+    let warnings = open_warnings(
+        &containers,
+        &load_history(since)?,
+        reboot_required().as_deref(),
+    );
+
+    let disk = disk_snapshot()?;
+    let previous_disk = load_disk_snapshot()?;
+    save_disk_snapshot(&disk)?;
+
+    Ok(DailyReport {
+        host: host_name(),
+        since,
+        until,
+        containers,
+        crashes: crashes_since(since)?,
+        actions: load_actions(since)?,
+        usage: usage_since(input, since)?,
+        disk,
+        previous_disk,
+        warnings,
+    })
+}
+
+/// Compiles the daily report and sends it as Markdown to notification channels by name
+pub fn send_daily_report(channels: &[String]) -> anyhow::Result<()> {
+    let config = Config::load_with_secrets()?;
+    let report =
+        render_report(&compile_daily_report(None)?, &config.units.number_format()).to_markdown();
+
+    let mut errors = vec![];
+    for name in channels {
+        let result = match config.notify.channels.get(name) {
+            Some(channel) => send_message(channel, &report),
+            None => Err(anyhow::anyhow!("Unknown notification channel: {name}")),
+        };
+        if let Err(error) = result {
+            errors.push(format!("{name}: {error:#}"));
+        }
+    }
+
+    if !errors.is_empty() {
+        anyhow::bail!(errors.join("\n"));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,145 @@
+use crate::commands::DOCKER;
+use crate::config::state_dir;
+use crate::image::ImageRef;
+use crate::utils::recreate_container;
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::process::Command;
+
+const ROLLBACK_FILE: &str = "rollback.json";
+
+/// Image a container ran before an update replaced it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RollbackTarget {
+    pub container: String,
+    pub stack: Option<String>,
+    /// Image reference the container was created from, such as ghcr.io/acme/app:2
+    pub image: String,
+    /// ID of the image the reference pointed to before the update
+    pub image_id: String,
+    pub recorded_at: DateTime<Utc>,
+}
+
+impl RollbackTarget {
+    /// Image ID as docker shows it, without the algorithm and shortened to 12 characters
+    pub fn short_image_id(&self) -> &str {
+        let id = self.image_id.trim_start_matches("sha256:");
+        &id[..id.len().min(12)]
+    }
+}
+
+/// Previous images by container name
+pub type RollbackState = BTreeMap<String, RollbackTarget>;
+
+/// Targets of containers, or of every container of stacks, given by name
+pub fn select_targets(
+    state: &RollbackState,
+    names: &[String],
+) -> anyhow::Result<Vec<RollbackTarget>> {
+    let mut targets: Vec<RollbackTarget> = vec![];
+
+    for name in names {
+        let matching = state
+            .values()
+            .filter(|target| target.container == *name || target.stack.as_ref() == Some(name))
+            .collect::<Vec<&RollbackTarget>>();
+
+        if matching.is_empty() {
+            anyhow::bail!("No previous image recorded for {name}");
+        }
+
+        for target in matching {
+            if !targets.contains(target) {
+                targets.push(target.clone());
+            }
+        }
+    }
+
+    Ok(targets)
+}
+
+fn rollback_path() -> anyhow::Result<PathBuf> {
+    Ok(state_dir()?.join(ROLLBACK_FILE))
+}
+
+/// Loads the previous images recorded by updates
+pub fn load_rollbacks() -> anyhow::Result<RollbackState> {
+    let path = rollback_path()?;
+
+    if !path.exists() {
+        return Ok(RollbackState::new());
+    }
+
+    let contents = std::fs::read_to_string(&path)
+        .with_context(|| format!("Failed to read rollback state: {}", path.display()))?;
+
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse rollback state: {}", path.display()))
+}
+
+/// Replaces the stored previous images
+pub fn save_rollbacks(state: &RollbackState) -> anyhow::Result<()> {
+    let path = rollback_path()?;
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    let contents =
+        serde_json::to_string_pretty(state).context("Failed to serialize rollback state")?;
+    std::fs::write(&path, contents)
+        .with_context(|| format!("Failed to write rollback state: {}", path.display()))
+}
+
+/// Records the images containers ran before an update, replacing earlier records of them
+pub fn record_rollbacks(targets: &[RollbackTarget]) -> anyhow::Result<()> {
+    let mut state = load_rollbacks()?;
+
+    for target in targets {
+        state.insert(target.container.to_string(), target.clone());
+    }
+
+    save_rollbacks(&state)
+}
+
+/// Points the image reference of a container back at its previous image and recreates it.
+///
+/// The previous image is usually dangling once the container stopped using it, so pruning
+/// dangling images removes it.
+pub fn roll_back(target: &RollbackTarget) -> anyhow::Result<()> {
+    let output = Command::new(DOCKER)
+        .args(["image", "inspect", "--format", "{{.Id}}", &target.image_id])
+        .output()
+        .with_context(|| format!("Failed to inspect image: {}", target.image_id))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Previous image of {} was removed: {}",
+            target.container,
+            target.image_id
+        );
+    }
+
+    // references pinned by digest cannot be tagged, and already name the previous image
+    let is_pinned = ImageRef::parse(&target.image).is_ok_and(|image| image.digest.is_some());
+    if !is_pinned {
+        let output = Command::new(DOCKER)
+            .args(["tag", &target.image_id, &target.image])
+            .output()
+            .with_context(|| format!("Failed to tag {}", target.image))?;
+
+        if !output.status.success() {
+            anyhow::bail!(
+                "Failed to tag {}: {}",
+                target.image,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+        }
+    }
+
+    Ok(recreate_container(&target.container)?)
+}
@@ -0,0 +1,210 @@
+use crate::commands::DOCKER;
+use crate::config::cache_dir;
+use crate::utils::{get_image_id, matches_pattern};
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::path::PathBuf;
+use std::process::Command;
+
+const SBOM_DIR: &str = "sbom";
+const SYFT: &str = "syft";
+const SPDX_NOASSERTION: &str = "NOASSERTION";
+
+/// Where an SBOM was obtained from
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SbomSource {
+    /// SBOM attestation attached to the image in the registry
+    Attached,
+    /// SBOM generated locally by syft
+    Syft,
+}
+
+/// Shape of a package listed in an SBOM
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Package {
+    pub name: String,
+    pub version: String,
+    pub licenses: Vec<String>,
+}
+
+/// Shape of the SBOM of an image, as cached in the state directory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sbom {
+    pub image: String,
+    pub image_id: String,
+    pub source: SbomSource,
+    pub packages: Vec<Package>,
+}
+
+/// Loads the SBOM of an image from the cache, generating it when missing or when refreshing.
+///
+/// Cached SBOMs are keyed by image id so a pulled update gets a new SBOM.
+pub fn load_sbom(image: &str, refresh: bool) -> anyhow::Result<Sbom> {
+    let (image_id, _) =
+        get_image_id(image)?.with_context(|| format!("Image not found locally: {image}"))?;
+
+    let path = sbom_path(&image_id)?;
+
+    if !refresh && path.exists() {
+        let contents = std::fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read SBOM: {}", path.display()))?;
+
+        if let Ok(sbom) = serde_json::from_str::<Sbom>(&contents) {
+            return Ok(sbom);
+        }
+    }
+
+    let (source, spdx) = match attached_sbom(image) {
+        Some(spdx) => (SbomSource::Attached, spdx),
+        None => (SbomSource::Syft, syft_sbom(image)?),
+    };
+
+    let sbom = Sbom {
+        image: image.to_string(),
+        image_id,
+        source,
+        packages: parse_spdx_packages(&spdx),
+    };
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create directory: {}", parent.display()))?;
+    }
+
+    std::fs::write(&path, serde_json::to_string(&sbom)?)
+        .with_context(|| format!("Failed to write SBOM: {}", path.display()))?;
+
+    Ok(sbom)
+}
+
+/// Gets the path of the cached SBOM of an image id
+fn sbom_path(image_id: &str) -> anyhow::Result<PathBuf> {
+    let file_name = format!("{}.json", image_id.trim_start_matches("sha256:"));
+
+    Ok(cache_dir()?.join(SBOM_DIR).join(file_name))
+}
+
+/// Gets the SPDX document attached to an image by buildx, if the registry has one
+fn attached_sbom(image: &str) -> Option<serde_json::Value> {
+    let output = Command::new(DOCKER)
+        .args(["buildx", "imagetools", "inspect", image])
+        .args(["--format", "{{ json .SBOM }}"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    let sbom = serde_json::from_slice::<serde_json::Value>(&output.stdout).ok()?;
+
+    // multi-platform images map each platform to its own SBOM
+    let spdx = match sbom.get("SPDX") {
+        Some(spdx) => spdx.clone(),
+        None => sbom
+            .as_object()?
+            .values()
+            .find_map(|platform| platform.get("SPDX"))?
+            .clone(),
+    };
+
+    spdx.get("packages").is_some().then_some(spdx)
+}
+
+/// Generates an SPDX document for a local image using syft
+fn syft_sbom(image: &str) -> anyhow::Result<serde_json::Value> {
+    let output = Command::new(SYFT)
+        .args(["scan", "-q", "-o", "spdx-json"])
+        .arg(format!("docker:{image}"))
+        .output()
+        .context("Failed to run syft, is it installed?")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to generate SBOM for {image}: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    serde_json::from_slice(&output.stdout).context("Failed to parse syft output")
+}
+
+/// Extracts packages from an SPDX JSON document
+fn parse_spdx_packages(spdx: &serde_json::Value) -> Vec<Package> {
+    let Some(packages) = spdx["packages"].as_array() else {
+        return vec![];
+    };
+
+    packages
+        .iter()
+        .filter_map(|package| {
+            let name = package["name"].as_str()?;
+            let version = package["versionInfo"].as_str().unwrap_or_default();
+
+            // prefer the concluded license, falling back to what the package declares
+            let licenses = ["licenseConcluded", "licenseDeclared"]
+                .iter()
+                .filter_map(|field| package[field].as_str())
+                .find(|license| !license.is_empty() && *license != SPDX_NOASSERTION)
+                .map(|license| vec![license.to_string()])
+                .unwrap_or_default();
+
+            Some(Package {
+                name: name.to_string(),
+                version: version.to_string(),
+                licenses,
+            })
+        })
+        .collect()
+}
+
+/// Compares package versions segment by segment, comparing numeric segments as numbers
+pub fn compare_versions(a: &str, b: &str) -> Ordering {
+    let segments = |version: &str| {
+        version
+            .split(|c: char| !c.is_ascii_alphanumeric())
+            .filter(|segment| !segment.is_empty())
+            .map(String::from)
+            .collect::<Vec<String>>()
+    };
+
+    let (a, b) = (segments(a), segments(b));
+
+    for (a, b) in a.iter().zip(&b) {
+        let ordering = match (a.parse::<u64>(), b.parse::<u64>()) {
+            (Ok(a), Ok(b)) => a.cmp(&b),
+            _ => a.cmp(b),
+        };
+
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+
+    a.len().cmp(&b.len())
+}
+
+/// Splits an SPDX license expression into the license ids it mentions
+pub fn license_ids(expression: &str) -> Vec<String> {
+    expression
+        .split(|c: char| c.is_whitespace() || c == '(' || c == ')')
+        .filter(|id| !id.is_empty() && !matches!(*id, "AND" | "OR" | "WITH"))
+        .map(String::from)
+        .collect()
+}
+
+/// Determine if an SPDX license expression can only be satisfied by disallowed licenses.
+///
+/// Any alternative of an `OR` that avoids disallowed licenses makes the expression allowed.
+pub fn is_license_disallowed(expression: &str, disallowed: &[String]) -> bool {
+    if disallowed.is_empty() {
+        return false;
+    }
+
+    expression.split(" OR ").all(|alternative| {
+        license_ids(alternative).iter().any(|id| {
+            disallowed
+                .iter()
+                .any(|pattern| matches_pattern(pattern, id))
+        })
+    })
+}
@@ -0,0 +1,111 @@
+use crate::utils::{LABEL_DSD_GROUP, LABEL_DSD_UPDATE};
+use anyhow::Context;
+use std::path::Path;
+
+/// Templates available for scaffolding a new project
+#[derive(Debug, Clone, Copy, clap::ValueEnum)]
+pub enum Template {
+    /// HTTP service with a published port and HTTP healthcheck
+    Webservice,
+    /// Background worker without published ports
+    Worker,
+}
+
+const COMPOSE_WEBSERVICE: &str = r#"name: {name}
+
+services:
+  web:
+    image: nginx:stable-alpine
+    restart: unless-stopped
+    ports:
+      - "8080:80"
+    healthcheck:
+      test: ["CMD", "wget", "-q", "--spider", "http://localhost/"]
+      interval: 30s
+      timeout: 5s
+      retries: 3
+      start_period: 10s
+    labels:
+      {label_update}: "true"
+      {label_group}: "{name}"
+"#;
+
+const COMPOSE_WORKER: &str = r#"name: {name}
+
+services:
+  worker:
+    image: alpine:latest
+    restart: unless-stopped
+    command: ["sh", "-c", "while true; do date; sleep 60; done"]
+    healthcheck:
+      test: ["CMD-SHELL", "pgrep sleep || exit 1"]
+      interval: 30s
+      timeout: 5s
+      retries: 3
+    labels:
+      {label_update}: "true"
+      {label_group}: "{name}"
+"#;
+
+const MAKEFILE: &str = r#"STACK := {name}
+
+.PHONY: up down restart logs stats update wait graph
+
+up:
+	docker compose up -d
+	dsd-util wait-healthy $(STACK)
+
+down:
+	docker compose down
+
+restart:
+	dsd-util restart $(STACK) --ordered
+
+logs:
+	dsd-util logs --stacks $(STACK)
+
+stats:
+	dsd-util stats --stacks $(STACK)
+
+update:
+	dsd-util update --stacks $(STACK) --recreate
+
+wait:
+	dsd-util wait-healthy $(STACK)
+
+graph:
+	dsd-util graph $(STACK)
+"#;
+
+/// Scaffolds a new compose project directory from a template
+pub fn scaffold_project(dir: &Path, name: &str, template: Template) -> anyhow::Result<()> {
+    if dir
+        .read_dir()
+        .is_ok_and(|mut entries| entries.next().is_some())
+    {
+        anyhow::bail!("Directory is not empty: {}", dir.display());
+    }
+
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create directory: {}", dir.display()))?;
+
+    let compose = match template {
+        Template::Webservice => COMPOSE_WEBSERVICE,
+        Template::Worker => COMPOSE_WORKER,
+    };
+
+    let files = [("compose.yaml", compose), ("Makefile", MAKEFILE)];
+
+    for (file_name, contents) in files {
+        let path = dir.join(file_name);
+        let contents = contents
+            .replace("{name}", name)
+            .replace("{label_update}", LABEL_DSD_UPDATE)
+            .replace("{label_group}", LABEL_DSD_GROUP);
+
+        std::fs::write(&path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use anyhow::Context;
+
+/// Number of containers to run for a compose service
+#[derive(Debug, Clone, PartialEq)]
+pub struct ServiceScale {
+    pub service: String,
+    pub replicas: usize,
+}
+
+/// Parses a scale given as service=replicas, e.g. worker=3
+pub fn parse_service_scale(input: &str) -> anyhow::Result<ServiceScale> {
+    let (service, replicas) = input
+        .split_once('=')
+        .with_context(|| format!("Invalid scale: {input}, expected service=replicas"))?;
+
+    if service.is_empty() {
+        anyhow::bail!("Invalid scale: {input}, expected service=replicas");
+    }
+
+    let replicas = replicas
+        .trim()
+        .parse::<usize>()
+        .with_context(|| format!("Invalid number of replicas: {replicas}"))?;
+
+    Ok(ServiceScale {
+        service: service.to_string(),
+        replicas,
+    })
+}
+
+/// Names of the containers running a service, given container names paired with their compose
+/// service label, sorted by name
+pub fn service_replicas(services: &[(String, String)], service: &str) -> Vec<String> {
+    let mut replicas = services
+        .iter()
+        .filter(|(_, label)| label == service)
+        .map(|(name, _)| name.to_string())
+        .collect::<Vec<String>>();
+    replicas.sort();
+
+    replicas
+}
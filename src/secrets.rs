@@ -0,0 +1,194 @@
+use crate::config::config_dir;
+use anyhow::Context;
+use std::collections::BTreeMap;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+
+const AGE: &str = "age";
+const AGE_KEYGEN: &str = "age-keygen";
+/// Secrets encrypted with age, as a JSON object of names and values
+const SECRETS_FILE: &str = "secrets.age";
+/// Identity created for the secrets file when no other one is set
+pub const IDENTITY_FILE: &str = "secrets.key";
+/// Environment variable naming another age identity, such as one of age-plugin-yubikey
+pub const ENV_AGE_IDENTITY: &str = "DSD_UTIL_AGE_IDENTITY";
+const REFERENCE_PREFIX: &str = "${secret:";
+
+/// Whether a name can be used for a secret and referenced from the config file
+pub fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.'))
+}
+
+/// Whether text references secrets, such as `${secret:slack}`
+pub fn has_references(text: &str) -> bool {
+    text.contains(REFERENCE_PREFIX)
+}
+
+/// Replaces `${secret:name}` references in text by the secrets `lookup` finds
+pub fn resolve_references(
+    text: &str,
+    lookup: &dyn Fn(&str) -> Option<String>,
+) -> anyhow::Result<String> {
+    let mut resolved = String::new();
+    let mut rest = text;
+
+    while let Some(start) = rest.find(REFERENCE_PREFIX) {
+        resolved.push_str(&rest[..start]);
+        let reference = &rest[start + REFERENCE_PREFIX.len()..];
+        let end = reference
+            .find('}')
+            .with_context(|| format!("Unterminated secret reference in {text}"))?;
+        let name = &reference[..end];
+
+        let secret = lookup(name).with_context(|| {
+            format!("No secret named {name}, set it with dsd-util secret set {name}")
+        })?;
+        resolved.push_str(&secret);
+        rest = &reference[end + 1..];
+    }
+    resolved.push_str(rest);
+
+    Ok(resolved)
+}
+
+fn secrets_path() -> anyhow::Result<PathBuf> {
+    Ok(config_dir()?.join(SECRETS_FILE))
+}
+
+/// Path of the age identity decrypting the secrets file
+pub fn identity_path() -> anyhow::Result<PathBuf> {
+    match std::env::var_os(ENV_AGE_IDENTITY).filter(|path| !path.is_empty()) {
+        Some(path) => Ok(PathBuf::from(path)),
+        None => Ok(config_dir()?.join(IDENTITY_FILE)),
+    }
+}
+
+/// Runs age or age-keygen, feeding it input on stdin
+fn run_age(program: &str, args: &[&std::ffi::OsStr], input: &[u8]) -> anyhow::Result<Vec<u8>> {
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {program}, is age installed?"))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(input)
+            .with_context(|| format!("Failed to write to {program}"))?;
+    }
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Failed to run {program}"))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "{program} failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(output.stdout)
+}
+
+/// Creates the identity of the secrets file unless it exists, readable only by the user
+fn ensure_identity() -> anyhow::Result<PathBuf> {
+    let path = identity_path()?;
+    if path.exists() {
+        return Ok(path);
+    }
+
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    // age-keygen creates the file with permissions only for the user
+    run_age(AGE_KEYGEN, &["-o".as_ref(), path.as_os_str()], &[])?;
+
+    Ok(path)
+}
+
+/// Reads all secrets, none when no secret was set yet
+pub fn load_secrets() -> anyhow::Result<BTreeMap<String, String>> {
+    let path = secrets_path()?;
+    if !path.exists() {
+        return Ok(BTreeMap::new());
+    }
+
+    let identity = identity_path()?;
+    let decrypted = run_age(
+        AGE,
+        &[
+            "--decrypt".as_ref(),
+            "-i".as_ref(),
+            identity.as_os_str(),
+            path.as_os_str(),
+        ],
+        &[],
+    )
+    .with_context(|| format!("Failed to decrypt {}", path.display()))?;
+
+    serde_json::from_slice(&decrypted)
+        .with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Encrypts and writes all secrets, replacing the secrets file at once
+pub fn save_secrets(secrets: &BTreeMap<String, String>) -> anyhow::Result<()> {
+    let identity = ensure_identity()?;
+
+    // age encrypts to the recipient of the identity itself, which also works for plugin
+    // identities that age-keygen -y cannot read
+    let path = secrets_path()?;
+    let partial = path.with_extension("age.partial");
+    run_age(
+        AGE,
+        &[
+            "--encrypt".as_ref(),
+            "-i".as_ref(),
+            identity.as_os_str(),
+            "-o".as_ref(),
+            partial.as_os_str(),
+        ],
+        &serde_json::to_vec(secrets)?,
+    )?;
+
+    std::fs::rename(&partial, &path).with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Reads a single secret
+pub fn secret_value(name: &str) -> anyhow::Result<String> {
+    load_secrets()?
+        .remove(name)
+        .with_context(|| format!("No secret named {name}, set it with dsd-util secret set {name}"))
+}
+
+/// Sets a secret, replacing its previous value
+pub fn set_secret(name: &str, value: &str) -> anyhow::Result<()> {
+    if !is_valid_name(name) {
+        anyhow::bail!(
+            "Invalid secret name {name}, use letters, digits, dashes, dots and underscores"
+        );
+    }
+
+    let mut secrets = load_secrets()?;
+    secrets.insert(name.to_string(), value.to_string());
+
+    save_secrets(&secrets)
+}
+
+/// Removes a secret, returning whether it was set
+pub fn remove_secret(name: &str) -> anyhow::Result<bool> {
+    let mut secrets = load_secrets()?;
+    if secrets.remove(name).is_none() {
+        return Ok(false);
+    }
+
+    save_secrets(&secrets)?;
+    Ok(true)
+}
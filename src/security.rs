@@ -0,0 +1,168 @@
+use crate::commands::DOCKER;
+use crate::docker::list_all_containers;
+use crate::image::image_matches;
+use crate::parsers::{parse_container_users, parse_security_profiles};
+use anyhow::Context;
+use std::process::Command;
+
+/// Host paths of the docker socket, which gives whoever can write to it root on the host
+pub const DOCKER_SOCKETS: [&str; 2] = ["/var/run/docker.sock", "/run/docker.sock"];
+
+/// Profile value docker uses for AppArmor and seccomp when confinement is turned off
+pub const UNCONFINED: &str = "unconfined";
+
+/// Images known to work as a non-root user, by image pattern, with how to run them that way
+const NON_ROOT_SUGGESTIONS: [(&str, &str); 9] = [
+    (
+        "nginx",
+        "use nginxinc/nginx-unprivileged, which listens on 8080",
+    ),
+    ("redis", "user: \"999:999\""),
+    (
+        "postgres",
+        "user: \"999:999\", with the data directory owned by it",
+    ),
+    (
+        "mariadb",
+        "user: \"999:999\", with the data directory owned by it",
+    ),
+    (
+        "mysql",
+        "user: \"999:999\", with the data directory owned by it",
+    ),
+    ("node", "user: node"),
+    (
+        "traefik",
+        "user: \"65534:65534\", reaching docker through a socket proxy",
+    ),
+    (
+        "*linuxserver/*",
+        "set PUID and PGID, the image switches to that user itself",
+    ),
+    ("eclipse-mosquitto", "user: \"1883:1883\""),
+];
+
+/// User a container runs as and whether it can control docker
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerUser {
+    pub container: String,
+    /// Compose project the container belongs to
+    pub stack: Option<String>,
+    pub image: String,
+    /// User as configured, such as 1000:1000 or node, empty for the image's default of root
+    pub user: String,
+    pub docker_socket: bool,
+}
+
+impl ContainerUser {
+    pub fn is_root(&self) -> bool {
+        is_root_user(&self.user)
+    }
+}
+
+/// Confinement a container runs under, from the security options it was created with
+#[derive(Debug, Clone, PartialEq)]
+pub struct SecurityProfiles {
+    pub container: String,
+    /// Compose project the container belongs to
+    pub stack: Option<String>,
+    /// AppArmor profile, such as docker-default, or - on hosts without AppArmor
+    pub apparmor: String,
+    /// Seccomp profile: default, custom for a profile file, or unconfined
+    pub seccomp: String,
+    /// SELinux labels, disable when labeling is turned off, or - when none are set
+    pub selinux: String,
+    /// Privileged containers bypass all of the above
+    pub privileged: bool,
+}
+
+impl SecurityProfiles {
+    /// Confinements turned off for the container, such as seccomp
+    pub fn unconfined(&self) -> Vec<&'static str> {
+        let mut unconfined = vec![];
+        if self.privileged {
+            unconfined.push("privileged");
+        }
+        if self.apparmor == UNCONFINED {
+            unconfined.push("apparmor");
+        }
+        if self.seccomp == UNCONFINED {
+            unconfined.push("seccomp");
+        }
+        if self.selinux == "disable" {
+            unconfined.push("selinux");
+        }
+
+        unconfined
+    }
+}
+
+/// Value of a security option such as seccomp=unconfined, also given as seccomp:unconfined by
+/// older docker versions
+pub fn security_option<'a>(options: &'a [String], name: &str) -> Vec<&'a str> {
+    options
+        .iter()
+        .filter_map(|option| {
+            option
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('=').or_else(|| rest.strip_prefix(':')))
+        })
+        .collect()
+}
+
+/// Whether a user given as user[:group] is root, which docker also runs as when none is given
+pub fn is_root_user(user: &str) -> bool {
+    let user = user.split(':').next().unwrap_or_default().trim();
+
+    user.is_empty() || user == "0" || user == "root"
+}
+
+/// Whether a host path is the docker socket
+pub fn is_docker_socket(path: &str) -> bool {
+    DOCKER_SOCKETS.contains(&path)
+}
+
+/// How to run an image as non-root, for images known to work that way
+pub fn non_root_suggestion(image: &str) -> Option<&'static str> {
+    NON_ROOT_SUGGESTIONS
+        .iter()
+        .find(|(pattern, _)| image_matches(pattern, image))
+        .map(|(_, suggestion)| *suggestion)
+}
+
+/// `docker inspect` output of all containers, including stopped ones
+fn inspect_all_containers() -> anyhow::Result<Vec<u8>> {
+    let ids = list_all_containers()?
+        .into_iter()
+        .map(|container| container.id)
+        .collect::<Vec<String>>();
+
+    if ids.is_empty() {
+        return Ok(b"[]".to_vec());
+    }
+
+    let output = Command::new(DOCKER)
+        .arg("inspect")
+        .args(&ids)
+        .output()
+        .context("Failed to inspect containers")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to inspect containers: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(output.stdout)
+}
+
+/// Inspects the users of all containers, including stopped ones
+pub fn inspect_container_users() -> anyhow::Result<Vec<ContainerUser>> {
+    parse_container_users(&inspect_all_containers()?)
+}
+
+/// Inspects the security profiles of all containers, including stopped ones
+pub fn inspect_security_profiles() -> anyhow::Result<Vec<SecurityProfiles>> {
+    parse_security_profiles(&inspect_all_containers()?)
+}
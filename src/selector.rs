@@ -0,0 +1,462 @@
+use crate::config::Config;
+use crate::context::current_context;
+use crate::docker::{list_all_containers, Container};
+use crate::i18n::{tr, tr_args};
+use crate::printer::{color_println, is_deterministic, Color};
+use crate::utils::{
+    get_containers_from_group, get_containers_from_stack, get_stack_services, is_terminal,
+    list_containers,
+};
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static FUZZY: AtomicBool = AtomicBool::new(false);
+
+/// Accept the closest container name for names matching none instead of only suggesting it
+pub fn set_fuzzy(fuzzy: bool) {
+    FUZZY.store(fuzzy, Ordering::SeqCst);
+}
+
+pub fn is_fuzzy() -> bool {
+    FUZZY.load(Ordering::SeqCst)
+}
+
+/// Resolves the containers a command should act on.
+///
+/// Falls back to the compose project of the current directory when nothing is specified.
+pub fn resolve_containers(
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    all: bool,
+) -> anyhow::Result<Vec<String>> {
+    let mut resolved = select_containers(containers, stacks, all)?;
+
+    // docker lists the newest first, which changes whenever a container is recreated
+    if is_deterministic() {
+        resolved.sort();
+    }
+
+    Ok(resolved)
+}
+
+fn select_containers(
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    all: bool,
+) -> anyhow::Result<Vec<String>> {
+    if all {
+        return Ok(list_containers()?);
+    }
+
+    if let Some(containers) = containers {
+        return complete_containers(containers);
+    }
+
+    let Some(stacks) = stacks else {
+        let Some(context) = current_context() else {
+            anyhow::bail!(tr(
+                "Must specify containers, use --stacks (-s) or use --all (-a)"
+            ))
+        };
+
+        if is_terminal() {
+            color_println(
+                Color::Magenta,
+                &tr_args(
+                    "Using stack {stack} from {dir}",
+                    &[("stack", &context.project), ("dir", &context.dir.display())],
+                ),
+            );
+        }
+
+        // only include services active under the selected compose files and profiles
+        let containers = get_stack_services(&context.project)?
+            .into_iter()
+            .filter(|(_, service)| {
+                context.services.is_empty() || context.services.contains(service)
+            })
+            .map(|(name, _)| name)
+            .collect();
+
+        return Ok(containers);
+    };
+
+    let mut containers = vec![];
+
+    for stack in expand_groups(&stacks)? {
+        let container_names = get_containers_from_stack(&stack)?;
+        containers.extend(container_names);
+    }
+
+    // containers may also join a group through the dsd-util.group label
+    for stack in &stacks {
+        containers.extend(get_containers_from_group(stack)?);
+    }
+
+    let mut seen = HashSet::new();
+    containers.retain(|container| seen.insert(container.to_string()));
+
+    Ok(containers)
+}
+
+/// Resolves containers like [`resolve_containers`], but including stopped containers of stacks,
+/// such as to start them again
+pub fn resolve_containers_including_stopped(
+    containers: Option<Vec<String>>,
+    stacks: Option<Vec<String>>,
+    all: bool,
+) -> anyhow::Result<Vec<String>> {
+    if containers.is_none() && stacks.is_none() && !all {
+        return resolve_containers(None, None, false);
+    }
+
+    let all_containers = list_all_containers()?;
+    if all {
+        return Ok(all_containers
+            .into_iter()
+            .map(|container| container.name)
+            .collect());
+    }
+
+    let mut names = vec![];
+    if let Some(stacks) = stacks {
+        names.extend(stack_members(&expand_groups(&stacks)?, &all_containers));
+    }
+
+    for name in containers.unwrap_or_default() {
+        let exact = match_exact(&name, &all_containers);
+        if exact != ContainerMatch::NotFound {
+            names.push(completion(name, exact, &all_containers)?);
+            continue;
+        }
+
+        let members = stack_members(
+            &expand_groups(std::slice::from_ref(&name))?,
+            &all_containers,
+        );
+        if members.is_empty() {
+            let found = match_container(&name, &all_containers);
+            names.push(completion(name, found, &all_containers)?);
+        } else {
+            names.extend(members);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    names.retain(|container| seen.insert(container.to_string()));
+
+    Ok(names)
+}
+
+/// Names of the containers belonging to any of the stacks, running or not
+pub fn stack_members(stacks: &[String], containers: &[Container]) -> Vec<String> {
+    containers
+        .iter()
+        .filter(|container| {
+            container
+                .stack
+                .as_ref()
+                .is_some_and(|stack| stacks.contains(stack))
+        })
+        .map(|container| container.name.to_string())
+        .collect()
+}
+
+/// Expands group names from the config file into the stacks they contain
+pub fn expand_groups(stacks: &[String]) -> anyhow::Result<Vec<String>> {
+    let groups = Config::load()?.groups;
+    let mut expanded = vec![];
+
+    for stack in stacks {
+        match groups.get(stack) {
+            Some(group) => expanded.extend(group.iter().cloned()),
+            None => expanded.push(stack.to_string()),
+        }
+    }
+
+    Ok(expanded)
+}
+
+/// Expands names that are not containers but compose projects or groups into their containers
+pub fn expand_stack_names(names: Vec<String>) -> anyhow::Result<Vec<String>> {
+    let all_containers = list_all_containers()?;
+    let mut containers = vec![];
+
+    for name in names {
+        // like docker, exact names and id prefixes refer to containers before anything else
+        let exact = match_exact(&name, &all_containers);
+        if exact != ContainerMatch::NotFound {
+            containers.push(completion(name, exact, &all_containers)?);
+            continue;
+        }
+
+        let mut stack_containers = vec![];
+        for stack in expand_groups(std::slice::from_ref(&name))? {
+            stack_containers.extend(get_containers_from_stack(&stack)?);
+        }
+        stack_containers.extend(get_containers_from_group(&name)?);
+
+        let mut seen = HashSet::new();
+        stack_containers.retain(|container| seen.insert(container.to_string()));
+
+        if stack_containers.is_empty() {
+            let found = match_container(&name, &all_containers);
+            containers.push(completion(name, found, &all_containers)?);
+        } else {
+            containers.extend(stack_containers);
+        }
+    }
+
+    Ok(containers)
+}
+
+/// Characters of container ids shown along with names, as `docker ps` shows them
+const SHORT_ID_LENGTH: usize = 12;
+
+/// What a container given on the command line refers to
+#[derive(Debug, Clone, PartialEq)]
+pub enum ContainerMatch {
+    /// Name of the only container it refers to
+    Found(String),
+    /// Containers it could refer to, as names followed by their short id
+    Ambiguous(Vec<String>),
+    NotFound,
+}
+
+impl ContainerMatch {
+    fn from_matches(matches: &[&Container]) -> Self {
+        match matches {
+            [] => ContainerMatch::NotFound,
+            [container] => ContainerMatch::Found(container.name.to_string()),
+            matches => ContainerMatch::Ambiguous(
+                matches
+                    .iter()
+                    .map(|container| {
+                        let id = container
+                            .id
+                            .chars()
+                            .take(SHORT_ID_LENGTH)
+                            .collect::<String>();
+                        format!("{} ({id})", container.name)
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+/// Containers whose id starts with a query, as docker accepts any unique prefix of an id
+fn id_matches<'a>(query: &str, containers: &'a [Container]) -> Vec<&'a Container> {
+    if query.is_empty() || !query.chars().all(|c| c.is_ascii_hexdigit()) {
+        return vec![];
+    }
+
+    let query = query.to_ascii_lowercase();
+    containers
+        .iter()
+        // docker ps shows short ids, which full ids start with
+        .filter(|container| container.id.starts_with(&query) || query.starts_with(&container.id))
+        .collect()
+}
+
+/// Finds the container an exact name or a prefix of an id refers to, the way docker does
+pub fn match_exact(query: &str, containers: &[Container]) -> ContainerMatch {
+    if let Some(container) = containers.iter().find(|container| container.name == query) {
+        return ContainerMatch::Found(container.name.to_string());
+    }
+
+    ContainerMatch::from_matches(&id_matches(query, containers))
+}
+
+/// Finds the container a query refers to, by exact name, unique prefix of an id or partial name
+/// as matched by [`fuzzy_matches`]
+pub fn match_container(query: &str, containers: &[Container]) -> ContainerMatch {
+    if let Some(container) = containers.iter().find(|container| container.name == query) {
+        return ContainerMatch::Found(container.name.to_string());
+    }
+
+    let names = containers
+        .iter()
+        .map(|container| container.name.to_string())
+        .collect::<Vec<String>>();
+    let partial = fuzzy_matches(query, &names);
+
+    // a short query such as db can be a prefix of an id and part of a name at once
+    let mut matches = id_matches(query, containers);
+    matches.extend(
+        containers
+            .iter()
+            .filter(|container| partial.contains(&container.name.as_str())),
+    );
+    let mut seen = HashSet::new();
+    matches.retain(|container| seen.insert(container.id.to_string()));
+
+    ContainerMatch::from_matches(&matches)
+}
+
+/// Number of single character edits turning one text into another, ignoring case, where
+/// swapping two adjacent characters such as mial for mail counts as one edit
+pub fn edit_distance(a: &str, b: &str) -> usize {
+    let a = a.to_lowercase().chars().collect::<Vec<char>>();
+    let b = b.to_lowercase().chars().collect::<Vec<char>>();
+
+    // distances between each prefix of a and each prefix of b, as optimal string alignment
+    let mut distances = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in distances.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, distance) in distances[0].iter_mut().enumerate() {
+        *distance = j;
+    }
+
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let substitution = distances[i - 1][j - 1] + usize::from(a[i - 1] != b[j - 1]);
+            let mut distance = substitution
+                .min(distances[i - 1][j] + 1)
+                .min(distances[i][j - 1] + 1);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(distances[i - 2][j - 2] + 1);
+            }
+            distances[i][j] = distance;
+        }
+    }
+
+    distances[a.len()][b.len()]
+}
+
+/// Names a query that matches none may be a typo of, the closest ones first.
+///
+/// Besides whole names, parts such as nextcloud of nextcloud-app-1 are compared, as partial
+/// names are accepted as well. Longer queries may be further off.
+pub fn close_matches<'a>(query: &str, names: &'a [String]) -> Vec<&'a str> {
+    let max_distance = (query.chars().count() / 3).clamp(1, 3);
+
+    let mut close = names
+        .iter()
+        .filter_map(|name| {
+            let distance = name
+                .split(['-', '_', '.'])
+                .chain([name.as_str()])
+                .map(|part| edit_distance(query, part))
+                .min()?;
+            (distance <= max_distance).then_some((distance, name.as_str()))
+        })
+        .collect::<Vec<(usize, &str)>>();
+    close.sort();
+
+    let Some((closest, _)) = close.first().copied() else {
+        return vec![];
+    };
+    close
+        .into_iter()
+        .take_while(|(distance, _)| *distance == closest)
+        .map(|(_, name)| name)
+        .collect()
+}
+
+/// Container a query matching none is a typo of, accepted when fuzzy, as with --fuzzy, and a
+/// single one is the closest, and otherwise suggested in the error. None when no name is close.
+pub fn correct_typo(
+    query: &str,
+    containers: &[Container],
+    fuzzy: bool,
+) -> anyhow::Result<Option<String>> {
+    let names = containers
+        .iter()
+        .map(|container| container.name.to_string())
+        .collect::<Vec<String>>();
+
+    match close_matches(query, &names).as_slice() {
+        [] => Ok(None),
+        [name] if fuzzy => {
+            eprintln!(
+                "{}",
+                tr_args(
+                    "Using {container} for {query}",
+                    &[("container", name), ("query", &query)]
+                )
+            );
+            Ok(Some(name.to_string()))
+        }
+        close => anyhow::bail!(tr_args(
+            "No container named {query}, did you mean {suggestions}?",
+            &[
+                ("query", &query),
+                (
+                    "suggestions",
+                    &close
+                        .iter()
+                        .map(|name| format!("`{name}`"))
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                )
+            ]
+        )),
+    }
+}
+
+/// Name a query completes to, failing with the candidates when it is ambiguous or with close
+/// names when it looks like a typo, and keeping the query for docker to report otherwise
+fn completion(
+    query: String,
+    found: ContainerMatch,
+    containers: &[Container],
+) -> anyhow::Result<String> {
+    match found {
+        ContainerMatch::Found(name) => Ok(name),
+        ContainerMatch::Ambiguous(candidates) => anyhow::bail!(tr_args(
+            "{query} matches several containers: {containers}",
+            &[("query", &query), ("containers", &candidates.join(", "))]
+        )),
+        ContainerMatch::NotFound => {
+            Ok(correct_typo(&query, containers, is_fuzzy())?.unwrap_or(query))
+        }
+    }
+}
+
+/// Completes containers given by partial name or unique prefix of an id to their names
+pub fn complete_containers(queries: Vec<String>) -> anyhow::Result<Vec<String>> {
+    let containers = list_all_containers()?;
+
+    queries
+        .into_iter()
+        .map(|query| {
+            let found = match_container(&query, &containers);
+            completion(query, found, &containers)
+        })
+        .collect()
+}
+
+/// Finds the containers a partial name refers to, ignoring case.
+///
+/// An exact name wins over names starting with the query, which win over names containing it,
+/// which in turn win over names containing its characters in order, such as wa1 for web-app-1.
+pub fn fuzzy_matches<'a>(query: &str, names: &'a [String]) -> Vec<&'a str> {
+    let query = query.to_lowercase();
+    let is_subsequence = |name: &str| {
+        let mut chars = name.chars();
+        query.chars().all(|c| chars.any(|other| other == c))
+    };
+
+    let tiers: [&dyn Fn(&str) -> bool; 4] = [
+        &|name| name == query,
+        &|name| name.starts_with(&query),
+        &|name| name.contains(&query),
+        &is_subsequence,
+    ];
+
+    for tier in tiers {
+        let matches = names
+            .iter()
+            .filter(|name| tier(&name.to_lowercase()))
+            .map(String::as_str)
+            .collect::<Vec<&str>>();
+
+        if !matches.is_empty() {
+            return matches;
+        }
+    }
+
+    vec![]
+}
@@ -0,0 +1,279 @@
+use crate::sbom::compare_versions;
+use anyhow::Context;
+use serde::Deserialize;
+use std::cmp::Ordering;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const CURL: &str = "curl";
+const TAR: &str = "tar";
+const COSIGN: &str = "cosign";
+const BINARY_NAME: &str = "dsd-util";
+const GITHUB_API: &str = "https://api.github.com";
+/// Seconds to wait for the release API, downloads may take longer
+const API_TIMEOUT: &str = "15";
+const DOWNLOAD_TIMEOUT: &str = "300";
+/// Issuer of the certificates GitHub Actions signs releases with
+const SIGNATURE_OIDC_ISSUER: &str = "https://token.actions.githubusercontent.com";
+
+/// A published release of dsd-util
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Release {
+    #[serde(rename = "tag_name")]
+    pub tag: String,
+    #[serde(default)]
+    pub assets: Vec<ReleaseAsset>,
+}
+
+/// A file attached to a release
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ReleaseAsset {
+    pub name: String,
+    #[serde(rename = "browser_download_url")]
+    pub url: String,
+}
+
+impl Release {
+    /// Version of the release, without the v its tag starts with
+    pub fn version(&self) -> &str {
+        self.tag.strip_prefix('v').unwrap_or(&self.tag)
+    }
+
+    /// Whether the release is newer than a version
+    pub fn is_newer_than(&self, version: &str) -> bool {
+        compare_versions(self.version(), version) == Ordering::Greater
+    }
+
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
+
+    /// Published sha256 checksum of an archive, named after the archive with or without its
+    /// extension
+    pub fn checksum_asset(&self, archive_name: &str) -> Option<&ReleaseAsset> {
+        let stem = archive_name.strip_suffix(".tar.gz").unwrap_or(archive_name);
+
+        self.asset(&format!("{archive_name}.sha256"))
+            .or_else(|| self.asset(&format!("{stem}.sha256")))
+    }
+}
+
+/// Target triple of the running binary, as release archives are named after it
+pub fn release_target() -> String {
+    let env = if cfg!(target_env = "musl") {
+        "musl"
+    } else {
+        "gnu"
+    };
+
+    match std::env::consts::OS {
+        "linux" => format!("{}-unknown-linux-{env}", std::env::consts::ARCH),
+        "macos" => format!("{}-apple-darwin", std::env::consts::ARCH),
+        os => format!("{}-unknown-{os}", std::env::consts::ARCH),
+    }
+}
+
+/// Name of the release archive of a target, as uploaded by the release workflow
+pub fn archive_name(target: &str) -> String {
+    format!("{BINARY_NAME}-{target}.tar.gz")
+}
+
+/// GitHub API path of the repository, taken from the repository of the package
+pub fn repository_path(repository: &str) -> anyhow::Result<&str> {
+    repository
+        .trim_end_matches('/')
+        .strip_prefix("https://github.com/")
+        .filter(|path| path.split('/').count() == 2)
+        .with_context(|| format!("Not a GitHub repository: {repository}"))
+}
+
+/// Parses a release as the GitHub API returns it
+pub fn parse_release(json: &[u8]) -> anyhow::Result<Release> {
+    serde_json::from_slice(json).context("Failed to parse release")
+}
+
+/// Fetches the latest release, which leaves out drafts and pre-releases
+pub fn latest_release() -> anyhow::Result<Release> {
+    let repository = repository_path(env!("CARGO_PKG_REPOSITORY"))?;
+
+    let output = Command::new(CURL)
+        .args(["-sS", "-f", "-L", "--max-time", API_TIMEOUT])
+        .args(["-H", "Accept: application/vnd.github+json"])
+        .arg(format!("{GITHUB_API}/repos/{repository}/releases/latest"))
+        .output()
+        .context("Failed to check for a new release")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to check for a new release: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    parse_release(&output.stdout)
+}
+
+/// Finds the checksum of a file in the output of sha256sum, which may list several files, or
+/// is a bare checksum for a single one
+pub fn parse_checksum(contents: &str, file_name: &str) -> Option<String> {
+    let mut lines = contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty());
+    let is_checksum =
+        |value: &str| value.len() == 64 && value.chars().all(|c| c.is_ascii_hexdigit());
+
+    lines
+        .clone()
+        .find_map(|line| {
+            let (checksum, name) = line.split_once(char::is_whitespace)?;
+            // sha256sum marks files read in binary mode with *
+            let name = name.trim().trim_start_matches('*');
+            (name == file_name && is_checksum(checksum)).then(|| checksum.to_lowercase())
+        })
+        .or_else(|| {
+            let line = lines.next()?;
+            (lines.next().is_none() && is_checksum(line)).then(|| line.to_lowercase())
+        })
+}
+
+/// Hex encoded SHA-256 of some bytes
+pub fn sha256_hex(bytes: &[u8]) -> String {
+    hmac_sha256::Hash::hash(bytes)
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+/// Downloads a release asset into a directory
+pub fn download(asset: &ReleaseAsset, dir: &Path) -> anyhow::Result<PathBuf> {
+    let path = dir.join(&asset.name);
+
+    let output = Command::new(CURL)
+        .args(["-sS", "-f", "-L", "--max-time", DOWNLOAD_TIMEOUT, "-o"])
+        .arg(&path)
+        .arg(&asset.url)
+        .output()
+        .with_context(|| format!("Failed to download {}", asset.name))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to download {}: {}",
+            asset.name,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(path)
+}
+
+/// Checks a downloaded archive against its published checksum
+pub fn verify_checksum(archive: &Path, checksums: &Path) -> anyhow::Result<()> {
+    let file_name = archive
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or_default();
+    let contents = std::fs::read_to_string(checksums)
+        .with_context(|| format!("Failed to read {}", checksums.display()))?;
+    let expected = parse_checksum(&contents, file_name)
+        .with_context(|| format!("No checksum for {file_name} in {}", checksums.display()))?;
+
+    let bytes =
+        std::fs::read(archive).with_context(|| format!("Failed to read {}", archive.display()))?;
+    let actual = sha256_hex(&bytes);
+
+    if actual != expected {
+        anyhow::bail!("Checksum mismatch for {file_name}: expected {expected}, got {actual}");
+    }
+
+    Ok(())
+}
+
+/// Checks the keyless cosign signature of an archive, made by a workflow of the repository
+pub fn verify_signature(archive: &Path, bundle: &Path) -> anyhow::Result<()> {
+    let identity = format!(
+        "^{}/.github/workflows/",
+        env!("CARGO_PKG_REPOSITORY").trim_end_matches('/')
+    );
+
+    let output = Command::new(COSIGN)
+        .args(["verify-blob", "--bundle"])
+        .arg(bundle)
+        .args(["--certificate-identity-regexp", &identity])
+        .args(["--certificate-oidc-issuer", SIGNATURE_OIDC_ISSUER])
+        .arg(archive)
+        .output()
+        .context("Failed to run cosign")?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Signature verification failed for {}: {}",
+            archive.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
+
+/// Whether cosign is installed to check signatures with
+pub fn has_cosign() -> bool {
+    Command::new(COSIGN)
+        .arg("version")
+        .output()
+        .is_ok_and(|output| output.status.success())
+}
+
+/// Unpacks the binary from a release archive into the directory of the archive
+pub fn extract_binary(archive: &Path) -> anyhow::Result<PathBuf> {
+    let dir = archive.parent().unwrap_or(Path::new("."));
+
+    let output = Command::new(TAR)
+        .arg("-xzf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dir)
+        .output()
+        .with_context(|| format!("Failed to unpack {}", archive.display()))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to unpack {}: {}",
+            archive.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let binary = dir.join(BINARY_NAME);
+    if !binary.is_file() {
+        anyhow::bail!("{} does not contain {BINARY_NAME}", archive.display());
+    }
+
+    Ok(binary)
+}
+
+/// Replaces an executable with a new binary in a single rename, so the executable is never
+/// missing or half written, even when it is running.
+///
+/// The binary is copied next to the executable first, as renames cannot cross filesystems.
+pub fn replace_executable(binary: &Path, executable: &Path) -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = executable
+        .parent()
+        .with_context(|| format!("No directory for {}", executable.display()))?;
+    let staged = dir.join(format!(".{BINARY_NAME}.new-{}", std::process::id()));
+
+    std::fs::copy(binary, &staged)
+        .with_context(|| format!("Failed to write {}", staged.display()))?;
+
+    let result = std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))
+        .and_then(|()| std::fs::rename(&staged, executable));
+
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&staged);
+        return Err(e).with_context(|| format!("Failed to replace {}", executable.display()));
+    }
+
+    Ok(())
+}
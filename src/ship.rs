@@ -0,0 +1,458 @@
+use crate::docker::list_all_containers;
+use crate::hostinfo::host_name;
+use crate::i18n::tr_args;
+use crate::logger::LogLevel;
+use anyhow::Context;
+use chrono::{DateTime, SecondsFormat, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::io::Write;
+use std::net::{TcpStream, ToSocketAddrs, UdpSocket};
+use std::process::{Command, Stdio};
+use std::sync::mpsc::{sync_channel, RecvTimeoutError, SyncSender, TrySendError};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+const CURL: &str = "curl";
+const SHIP_TIMEOUT: &str = "10";
+/// How long connecting to and writing to a syslog server may take, so an unreachable server
+/// cannot hold up shutting down
+const TCP_TIMEOUT: Duration = Duration::from_secs(10);
+const LOKI_PUSH_PATH: &str = "/loki/api/v1/push";
+const DEFAULT_SYSLOG_PORT: u16 = 514;
+/// Facility of shipped syslog messages, user-level messages
+const SYSLOG_FACILITY: u8 = 1;
+/// Lines waiting to be shipped, further lines being dropped while an endpoint is slow
+const SHIP_QUEUE_SIZE: usize = 10_000;
+/// Lines sent in a single push at most
+const MAX_BATCH_LINES: usize = 500;
+/// How long lines are collected into a batch before they are sent
+const BATCH_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Transport of syslog messages
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SyslogTransport {
+    Udp,
+    /// Newline delimited messages over a TCP connection
+    Tcp,
+}
+
+/// Endpoint log lines are forwarded to
+#[derive(Debug, Clone, PartialEq)]
+pub enum ShipTarget {
+    /// Push API of Grafana Loki, given by its base url such as http://host:3100
+    Loki(String),
+    /// Remote syslog server, given as host:port
+    Syslog {
+        transport: SyslogTransport,
+        address: String,
+    },
+}
+
+impl fmt::Display for ShipTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ShipTarget::Loki(url) => write!(f, "loki={url}"),
+            ShipTarget::Syslog {
+                transport: SyslogTransport::Udp,
+                address,
+            } => write!(f, "syslog=udp://{address}"),
+            ShipTarget::Syslog {
+                transport: SyslogTransport::Tcp,
+                address,
+            } => write!(f, "syslog=tcp://{address}"),
+        }
+    }
+}
+
+/// Parses a target given as loki=http://host:3100 or syslog=udp://host:514
+pub fn parse_ship_target(input: &str) -> anyhow::Result<ShipTarget> {
+    let (kind, url) = input.split_once('=').with_context(|| {
+        format!("Invalid target {input}, expected loki=http://host:3100 or syslog=udp://host:514")
+    })?;
+
+    match kind {
+        "loki" => {
+            if !url.starts_with("http://") && !url.starts_with("https://") {
+                anyhow::bail!("Invalid Loki url {url}, expected http:// or https://");
+            }
+            Ok(ShipTarget::Loki(url.trim_end_matches('/').to_string()))
+        }
+        "syslog" => {
+            let (transport, address) = match url.split_once("://") {
+                Some(("udp", address)) => (SyslogTransport::Udp, address),
+                Some(("tcp", address)) => (SyslogTransport::Tcp, address),
+                Some((scheme, _)) => {
+                    anyhow::bail!("Unsupported syslog transport {scheme}, expected udp or tcp")
+                }
+                None => (SyslogTransport::Udp, url),
+            };
+
+            if address.is_empty() {
+                anyhow::bail!("Missing syslog host in {input}");
+            }
+            let address = if address
+                .rsplit_once(':')
+                .is_some_and(|(_, port)| port.parse::<u16>().is_ok())
+            {
+                address.to_string()
+            } else {
+                format!("{address}:{DEFAULT_SYSLOG_PORT}")
+            };
+
+            Ok(ShipTarget::Syslog { transport, address })
+        }
+        _ => anyhow::bail!("Unknown target {kind}, expected loki or syslog"),
+    }
+}
+
+/// Line of a container on its way to the endpoints
+#[derive(Debug, Clone, PartialEq)]
+pub struct ShippedLine {
+    pub at: DateTime<Utc>,
+    pub container: String,
+    /// Compose project of the container
+    pub stack: Option<String>,
+    pub line: String,
+}
+
+/// Url of the push API of a Loki server
+pub fn loki_push_url(base: &str) -> String {
+    if base.ends_with(LOKI_PUSH_PATH) {
+        base.to_string()
+    } else {
+        format!("{base}{LOKI_PUSH_PATH}")
+    }
+}
+
+/// Body of a Loki push, with a stream per container labeled with its name, stack and the host
+pub fn loki_push_body(lines: &[ShippedLine], host: &str) -> serde_json::Value {
+    let mut streams: BTreeMap<(&str, Option<&str>), Vec<[String; 2]>> = BTreeMap::new();
+    for line in lines {
+        streams
+            .entry((&line.container, line.stack.as_deref()))
+            .or_default()
+            .push([
+                line.at
+                    .timestamp_nanos_opt()
+                    .unwrap_or_default()
+                    .to_string(),
+                line.line.to_string(),
+            ]);
+    }
+
+    let streams = streams
+        .into_iter()
+        .map(|((container, stack), values)| {
+            let mut labels = serde_json::json!({
+                "job": "dsd-util",
+                "host": host,
+                "container": container,
+            });
+            if let Some(stack) = stack {
+                labels["stack"] = stack.into();
+            }
+
+            serde_json::json!({ "stream": labels, "values": values })
+        })
+        .collect::<Vec<serde_json::Value>>();
+
+    serde_json::json!({ "streams": streams })
+}
+
+/// Severity of a syslog message for the level detected in a line, informational without one
+fn syslog_severity(line: &str) -> u8 {
+    match LogLevel::detect(line) {
+        Some(LogLevel::Fatal) => 2,
+        Some(LogLevel::Error) => 3,
+        Some(LogLevel::Warn) => 4,
+        Some(LogLevel::Debug | LogLevel::Trace) => 7,
+        Some(LogLevel::Info) | None => 6,
+    }
+}
+
+/// Keeps a header field of a syslog message to printable ASCII of at most max characters
+fn syslog_field(value: &str, max: usize) -> String {
+    let field = value
+        .chars()
+        .filter(|c| c.is_ascii_graphic())
+        .take(max)
+        .collect::<String>();
+
+    if field.is_empty() {
+        "-".to_string()
+    } else {
+        field
+    }
+}
+
+/// Formats a line as RFC 5424 syslog message, with the container as app name and its stack as
+/// message id
+pub fn syslog_message(line: &ShippedLine, host: &str) -> String {
+    format!(
+        "<{}>1 {} {} {} - {} - {}",
+        SYSLOG_FACILITY * 8 + syslog_severity(&line.line),
+        line.at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        syslog_field(host, 255),
+        syslog_field(&line.container, 48),
+        syslog_field(line.stack.as_deref().unwrap_or_default(), 32),
+        line.line
+    )
+}
+
+/// Pushes lines to a Loki server with curl
+fn push_to_loki(url: &str, lines: &[ShippedLine], host: &str) -> anyhow::Result<()> {
+    let mut child = Command::new(CURL)
+        .args(["-sS", "-f", "--max-time", SHIP_TIMEOUT, "-X", "POST"])
+        .args(["-H", "Content-Type: application/json"])
+        .args(["--data-binary", "@-"])
+        .arg(loki_push_url(url))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .context("Failed to run curl")?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin
+            .write_all(loki_push_body(lines, host).to_string().as_bytes())
+            .context("Failed to write to curl")?;
+    }
+
+    let output = child.wait_with_output().context("Failed to run curl")?;
+    if !output.status.success() {
+        anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+
+    Ok(())
+}
+
+/// Connection to an endpoint, kept open between batches
+enum Sink {
+    Loki(String),
+    Udp {
+        socket: Option<UdpSocket>,
+        address: String,
+    },
+    Tcp {
+        stream: Option<TcpStream>,
+        address: String,
+    },
+}
+
+impl Sink {
+    fn new(target: &ShipTarget) -> Self {
+        match target {
+            ShipTarget::Loki(url) => Sink::Loki(url.to_string()),
+            ShipTarget::Syslog {
+                transport: SyslogTransport::Udp,
+                address,
+            } => Sink::Udp {
+                socket: None,
+                address: address.to_string(),
+            },
+            ShipTarget::Syslog {
+                transport: SyslogTransport::Tcp,
+                address,
+            } => Sink::Tcp {
+                stream: None,
+                address: address.to_string(),
+            },
+        }
+    }
+
+    fn send(&mut self, lines: &[ShippedLine], host: &str) -> anyhow::Result<()> {
+        match self {
+            Sink::Loki(url) => push_to_loki(url, lines, host),
+            Sink::Udp { socket, address } => {
+                if socket.is_none() {
+                    *socket = Some(UdpSocket::bind("0.0.0.0:0").context("Failed to open socket")?);
+                }
+                let Some(udp) = socket else {
+                    return Ok(());
+                };
+
+                for line in lines {
+                    udp.send_to(syslog_message(line, host).as_bytes(), address.as_str())
+                        .with_context(|| format!("Failed to send to {address}"))?;
+                }
+                Ok(())
+            }
+            Sink::Tcp { stream, address } => {
+                if stream.is_none() {
+                    *stream = Some(
+                        connect_tcp(address)
+                            .with_context(|| format!("Failed to connect to {address}"))?,
+                    );
+                }
+                let Some(tcp) = stream else {
+                    return Ok(());
+                };
+
+                let mut messages = String::new();
+                for line in lines {
+                    messages.push_str(&syslog_message(line, host).replace('\n', " "));
+                    messages.push('\n');
+                }
+
+                let result = tcp
+                    .write_all(messages.as_bytes())
+                    .with_context(|| format!("Failed to send to {address}"));
+                // reconnect with the next batch, the server may have restarted
+                if result.is_err() {
+                    *stream = None;
+                }
+                result
+            }
+        }
+    }
+}
+
+/// Connects to the first address a host:port resolves to that accepts in time, with writes
+/// timing out as well
+fn connect_tcp(address: &str) -> anyhow::Result<TcpStream> {
+    let mut error = None;
+    for socket_address in address.to_socket_addrs()? {
+        match TcpStream::connect_timeout(&socket_address, TCP_TIMEOUT) {
+            Ok(stream) => {
+                stream.set_write_timeout(Some(TCP_TIMEOUT))?;
+                return Ok(stream);
+            }
+            Err(e) => error = Some(e),
+        }
+    }
+
+    Err(error.map_or_else(
+        || anyhow::anyhow!("{address} did not resolve to any address"),
+        anyhow::Error::from,
+    ))
+}
+
+/// Forwards log lines to Loki or syslog endpoints from a background thread, so a slow endpoint
+/// never holds up the lines printed to stdout.
+///
+/// Lines are sent in batches about once a second. While an endpoint cannot keep up, lines beyond
+/// the queue size are dropped and counted. Failures are printed to stderr once until the
+/// endpoint works again.
+pub struct LogShipper {
+    sender: Option<SyncSender<ShippedLine>>,
+    worker: Option<JoinHandle<()>>,
+    /// Compose project per container name
+    stacks: HashMap<String, String>,
+    dropped: usize,
+}
+
+impl LogShipper {
+    /// Starts shipping to the given endpoints, looking up the stacks of all containers
+    pub fn start(targets: Vec<ShipTarget>) -> anyhow::Result<Self> {
+        let stacks = list_all_containers()?
+            .into_iter()
+            .filter_map(|container| Some((container.name, container.stack?)))
+            .collect();
+
+        let (sender, receiver) = sync_channel::<ShippedLine>(SHIP_QUEUE_SIZE);
+        let host = host_name();
+
+        let worker = std::thread::spawn(move || {
+            let mut sinks = targets
+                .iter()
+                .map(|target| (target, Sink::new(target), false))
+                .collect::<Vec<(&ShipTarget, Sink, bool)>>();
+            let mut batch = vec![];
+            let mut started = Instant::now();
+
+            loop {
+                let wait = BATCH_INTERVAL.saturating_sub(started.elapsed());
+                let done = match receiver.recv_timeout(wait) {
+                    Ok(line) => {
+                        if batch.is_empty() {
+                            started = Instant::now();
+                        }
+                        batch.push(line);
+                        false
+                    }
+                    Err(RecvTimeoutError::Timeout) => false,
+                    Err(RecvTimeoutError::Disconnected) => true,
+                };
+
+                let due = batch.len() >= MAX_BATCH_LINES || started.elapsed() >= BATCH_INTERVAL;
+                if !batch.is_empty() && (due || done) {
+                    for (target, sink, failing) in &mut sinks {
+                        match sink.send(&batch, &host) {
+                            Ok(()) => *failing = false,
+                            Err(error) if !*failing => {
+                                *failing = true;
+                                eprintln!(
+                                    "{}",
+                                    tr_args(
+                                        "Failed to ship logs to {target}: {error}",
+                                        &[("target", target), ("error", &format!("{error:#}"))]
+                                    )
+                                );
+                            }
+                            Err(_) => {}
+                        }
+                    }
+                    batch.clear();
+                }
+                if batch.is_empty() {
+                    started = Instant::now();
+                }
+
+                if done {
+                    return;
+                }
+            }
+        });
+
+        Ok(LogShipper {
+            sender: Some(sender),
+            worker: Some(worker),
+            stacks,
+            dropped: 0,
+        })
+    }
+
+    /// Queues a line of a container to be shipped
+    pub fn ship(&mut self, container: &str, line: &str) {
+        let Some(sender) = &self.sender else {
+            return;
+        };
+
+        let shipped = ShippedLine {
+            at: Utc::now(),
+            container: container.to_string(),
+            stack: self.stacks.get(container).cloned(),
+            line: line.to_string(),
+        };
+
+        if let Err(TrySendError::Full(_)) = sender.try_send(shipped) {
+            self.dropped += 1;
+        }
+    }
+
+    /// Sends the lines still queued and stops the background thread
+    pub fn finish(&mut self) {
+        self.sender = None;
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+
+        if self.dropped > 0 {
+            eprintln!(
+                "{}",
+                tr_args(
+                    "Dropped {count} line(s) the log endpoints could not keep up with",
+                    &[("count", &self.dropped)]
+                )
+            );
+            self.dropped = 0;
+        }
+    }
+}
+
+impl Drop for LogShipper {
+    fn drop(&mut self) {
+        self.finish();
+    }
+}
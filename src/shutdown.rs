@@ -0,0 +1,47 @@
+use anyhow::Context;
+use std::io::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+/// How often long running loops check whether shutdown was requested
+pub const SHUTDOWN_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Exit code of a process interrupted by Ctrl-C
+const EXIT_CODE_INTERRUPTED: i32 = 130;
+
+static SHUTDOWN_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+/// Handles Ctrl-C by requesting a graceful shutdown, exiting right away on a second Ctrl-C.
+///
+/// Only long running modes that check shutdown_requested should install this.
+pub fn handle_ctrl_c() -> anyhow::Result<()> {
+    ctrlc::set_handler(|| {
+        if SHUTDOWN_REQUESTED.swap(true, Ordering::SeqCst) {
+            let _ = std::io::stdout().flush();
+            std::process::exit(EXIT_CODE_INTERRUPTED);
+        }
+    })
+    .context("Failed to set Ctrl-C handler")
+}
+
+/// Determine if a graceful shutdown was requested
+pub fn shutdown_requested() -> bool {
+    SHUTDOWN_REQUESTED.load(Ordering::SeqCst)
+}
+
+/// Sleeps for the given duration, waking up early when shutdown is requested.
+///
+/// Returns false when shutdown was requested.
+pub fn sleep_unless_shutdown(duration: Duration) -> bool {
+    let start = Instant::now();
+
+    while start.elapsed() < duration {
+        if shutdown_requested() {
+            return false;
+        }
+
+        std::thread::sleep(SHUTDOWN_POLL_INTERVAL.min(duration - start.elapsed()));
+    }
+
+    !shutdown_requested()
+}
@@ -0,0 +1,104 @@
+use std::fmt::Write;
+
+/// Rows of cells under a header, rendered the same way by every reporting command
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    pub fn new(headers: &[&str]) -> Self {
+        Table {
+            headers: headers.iter().map(|header| header.to_string()).collect(),
+            rows: vec![],
+        }
+    }
+
+    pub fn push(&mut self, row: Vec<String>) {
+        self.rows.push(row);
+    }
+
+    /// Columns padded to their widest cell, the last one left unpadded
+    pub fn to_text(&self) -> String {
+        let mut widths = self
+            .headers
+            .iter()
+            .map(|header| header.chars().count())
+            .collect::<Vec<usize>>();
+        for row in &self.rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+
+        let mut text = String::new();
+        for cells in std::iter::once(&self.headers).chain(&self.rows) {
+            let line = cells
+                .iter()
+                .zip(&widths)
+                .map(|(cell, width)| format!("{cell:<width$}"))
+                .collect::<Vec<String>>()
+                .join("  ");
+            let _ = writeln!(text, "{}", line.trim_end());
+        }
+
+        text
+    }
+
+    pub fn to_csv(&self) -> String {
+        csv_table(&self.headers, &self.rows)
+    }
+
+    pub fn to_markdown(&self) -> String {
+        markdown_table(&self.headers, &self.rows)
+    }
+}
+
+/// Renders CSV as RFC 4180 describes it, quoting cells with commas, quotes or line breaks
+pub fn csv_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut csv = String::new();
+    for cells in std::iter::once(headers).chain(rows.iter().map(Vec::as_slice)) {
+        let line = cells
+            .iter()
+            .map(|cell| escape_csv_cell(cell))
+            .collect::<Vec<String>>()
+            .join(",");
+        let _ = writeln!(csv, "{line}");
+    }
+
+    csv
+}
+
+/// Renders a Markdown table, with a separator line under the header
+pub fn markdown_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    let row = |cells: &[String]| {
+        let cells = cells
+            .iter()
+            .map(|cell| escape_markdown_cell(cell))
+            .collect::<Vec<String>>();
+        format!("| {} |\n", cells.join(" | "))
+    };
+
+    let mut markdown = row(headers);
+    let _ = writeln!(markdown, "|{}", " --- |".repeat(headers.len()));
+    for cells in rows {
+        markdown.push_str(&row(cells));
+    }
+
+    markdown
+}
+
+/// Escapes pipes, which would end a Markdown table cell, and keeps a cell on a single line
+pub fn escape_markdown_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// Quotes a CSV cell when it holds a separator, quote or line break, doubling its quotes
+pub fn escape_csv_cell(text: &str) -> String {
+    if text.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", text.replace('"', "\"\""))
+    } else {
+        text.to_string()
+    }
+}
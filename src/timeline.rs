@@ -0,0 +1,259 @@
+use crate::commands::DOCKER;
+use crate::error::check_output;
+use crate::history::{load_actions, Action, HistoryEntry};
+use crate::i18n::{tr, tr_args};
+use crate::logger::LogLevel;
+use crate::probes::{load_history, ProbeHistoryEntry};
+use crate::rollback::{load_rollbacks, RollbackState};
+use chrono::{DateTime, Utc};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::process::Command;
+
+/// Where an event of a timeline was found
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimelineSource {
+    /// Actions dsd-util took, from the history file
+    Action,
+    /// `docker events`, including health transitions
+    Docker,
+    /// Previous images recorded by updates for rolling back
+    Update,
+    /// Probe results turning from passing to failing or back
+    Probe,
+}
+
+impl fmt::Display for TimelineSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TimelineSource::Action => write!(f, "action"),
+            TimelineSource::Docker => write!(f, "docker"),
+            TimelineSource::Update => write!(f, "update"),
+            TimelineSource::Probe => write!(f, "probe"),
+        }
+    }
+}
+
+/// Something that happened to a container
+#[derive(Debug, Clone, PartialEq)]
+pub struct TimelineEvent {
+    pub at: DateTime<Utc>,
+    pub source: TimelineSource,
+    pub summary: String,
+    /// How much of a problem the event is, info for expected ones such as a start
+    pub level: LogLevel,
+}
+
+impl TimelineEvent {
+    fn new(at: DateTime<Utc>, source: TimelineSource, level: LogLevel, summary: String) -> Self {
+        TimelineEvent {
+            at,
+            source,
+            summary,
+            level,
+        }
+    }
+}
+
+/// Turns a line of `docker events --format '{{json .}}'` into an event, None for events that
+/// say little about the container, such as exec sessions
+pub fn parse_docker_event(line: &str) -> Option<TimelineEvent> {
+    let event = serde_json::from_str::<serde_json::Value>(line).ok()?;
+    let action = event.get("Action")?.as_str()?;
+    let at = DateTime::from_timestamp(event.get("time")?.as_i64()?, 0)?;
+    let attribute = |name: &str| {
+        event
+            .pointer(&format!("/Actor/Attributes/{name}"))
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+
+    let (level, summary) = match action {
+        "create" => (
+            LogLevel::Info,
+            tr_args("Created from {image}", &[("image", &attribute("image"))]),
+        ),
+        "start" => (LogLevel::Info, tr("Started")),
+        "restart" => (LogLevel::Info, tr("Restarted")),
+        "stop" => (LogLevel::Info, tr("Stopped")),
+        "kill" => (
+            LogLevel::Warn,
+            tr_args(
+                "Killed with signal {signal}",
+                &[("signal", &attribute("signal"))],
+            ),
+        ),
+        "die" => {
+            let code = attribute("exitCode");
+            let level = if code == "0" {
+                LogLevel::Info
+            } else {
+                LogLevel::Error
+            };
+            (
+                level,
+                tr_args("Exited with code {code}", &[("code", &code)]),
+            )
+        }
+        "oom" => (LogLevel::Error, tr("Ran out of memory")),
+        "pause" => (LogLevel::Info, tr("Paused")),
+        "unpause" => (LogLevel::Info, tr("Unpaused")),
+        "destroy" => (LogLevel::Info, tr("Removed")),
+        "health_status: healthy" => (LogLevel::Info, tr("Turned healthy")),
+        "health_status: unhealthy" => (LogLevel::Error, tr("Turned unhealthy")),
+        _ => return None,
+    };
+
+    Some(TimelineEvent::new(
+        at,
+        TimelineSource::Docker,
+        level,
+        summary,
+    ))
+}
+
+/// Events of the actions recorded for a container
+pub fn action_events(entries: &[HistoryEntry], container: &str) -> Vec<TimelineEvent> {
+    entries
+        .iter()
+        .filter(|entry| entry.container == container)
+        .map(|entry| {
+            let action = match entry.action {
+                Action::Restart => tr("Restarted by dsd-util"),
+                Action::Update => tr("Updated by dsd-util"),
+                Action::Rollback => tr("Rolled back by dsd-util"),
+                Action::Stop => tr("Stopped by dsd-util"),
+                Action::Start => tr("Started by dsd-util"),
+                Action::Pause => tr("Paused by dsd-util"),
+                Action::Unpause => tr("Unpaused by dsd-util"),
+            };
+            let action = match &entry.detail {
+                Some(detail) => format!("{action} ({detail})"),
+                None => action,
+            };
+
+            match &entry.error {
+                Some(error) => TimelineEvent::new(
+                    entry.at,
+                    TimelineSource::Action,
+                    LogLevel::Error,
+                    tr_args(
+                        "{action} failed: {error}",
+                        &[("action", &action), ("error", error)],
+                    ),
+                ),
+                None => {
+                    TimelineEvent::new(entry.at, TimelineSource::Action, LogLevel::Info, action)
+                }
+            }
+        })
+        .collect()
+}
+
+/// Event of the last update of a container recorded for rolling back, if it falls in the window
+pub fn update_events(
+    state: &RollbackState,
+    container: &str,
+    since: DateTime<Utc>,
+) -> Vec<TimelineEvent> {
+    state
+        .get(container)
+        .filter(|target| target.recorded_at >= since)
+        .map(|target| {
+            TimelineEvent::new(
+                target.recorded_at,
+                TimelineSource::Update,
+                LogLevel::Info,
+                tr_args(
+                    "Update replaced {image} image {id}",
+                    &[("image", &target.image), ("id", &target.short_image_id())],
+                ),
+            )
+        })
+        .into_iter()
+        .collect()
+}
+
+/// Events of probes of a container starting out passing or failing and turning to the other
+pub fn probe_events(entries: &[ProbeHistoryEntry], container: &str) -> Vec<TimelineEvent> {
+    let mut probes: BTreeMap<&str, Vec<&ProbeHistoryEntry>> = BTreeMap::new();
+    for entry in entries.iter().filter(|entry| entry.container == container) {
+        probes.entry(&entry.name).or_default().push(entry);
+    }
+
+    let mut events = vec![];
+    for (name, mut results) in probes {
+        results.sort_by_key(|result| result.timestamp);
+
+        let mut last = None;
+        for result in results {
+            if last == Some(result.ok) {
+                continue;
+            }
+            last = Some(result.ok);
+
+            events.push(if result.ok {
+                TimelineEvent::new(
+                    result.timestamp,
+                    TimelineSource::Probe,
+                    LogLevel::Info,
+                    tr_args("Probe {name} passing", &[("name", &name)]),
+                )
+            } else {
+                TimelineEvent::new(
+                    result.timestamp,
+                    TimelineSource::Probe,
+                    LogLevel::Error,
+                    tr_args(
+                        "Probe {name} failing: {detail}",
+                        &[("name", &name), ("detail", &result.detail)],
+                    ),
+                )
+            });
+        }
+    }
+
+    events
+}
+
+/// Container events docker still knows of, which are only its last few hundred and none from
+/// before the daemon started
+fn docker_events(container: &str, since: DateTime<Utc>) -> anyhow::Result<Vec<TimelineEvent>> {
+    let output = check_output(
+        "events",
+        Command::new(DOCKER)
+            .args([
+                "events",
+                "--since",
+                &since.timestamp().to_string(),
+                "--until",
+                &Utc::now().timestamp().to_string(),
+                "--filter",
+                "type=container",
+                "--filter",
+                &format!("container={container}"),
+                "--format",
+                "{{json .}}",
+            ])
+            .output(),
+    )?;
+
+    Ok(String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_docker_event)
+        .collect())
+}
+
+/// Everything known to have happened to a container since a time, oldest first
+pub fn build_timeline(container: &str, since: DateTime<Utc>) -> anyhow::Result<Vec<TimelineEvent>> {
+    let mut events = action_events(&load_actions(since)?, container);
+    events.extend(docker_events(container, since)?);
+    events.extend(update_events(&load_rollbacks()?, container, since));
+    events.extend(probe_events(&load_history(since)?, container));
+
+    // stable, so events of the same second keep the order of their source
+    events.sort_by_key(|event| event.at);
+
+    Ok(events)
+}
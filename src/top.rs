@@ -0,0 +1,85 @@
+use crate::commands::DOCKER;
+use crate::error::check_output;
+use anyhow::Context;
+use std::process::Command;
+
+/// Options docker top passes to ps: all processes, which docker narrows to the container's, with
+/// the pid, %CPU and command columns
+const PS_ARGS: [&str; 2] = ["-eo", "pid,pcpu,args"];
+
+/// Process running in a container
+#[derive(Debug, Clone, PartialEq)]
+pub struct ContainerProcess {
+    pub container: String,
+    /// Process id on the host, as kill and other tools there expect it
+    pub pid: u32,
+    pub cpu_percent: f64,
+    pub command: String,
+}
+
+/// Order of processes in the top table
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum TopSort {
+    /// Highest CPU usage first
+    #[default]
+    Cpu,
+    /// Lowest process id first
+    Pid,
+    /// Alphabetically by container name
+    Container,
+}
+
+/// Parses `docker top` output with the pid, %CPU and command columns
+pub fn parse_top_output(container: &str, output: &str) -> anyhow::Result<Vec<ContainerProcess>> {
+    output
+        .lines()
+        // the first line holds the column headers
+        .skip(1)
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| {
+            let mut columns = line.split_whitespace();
+            let (Some(pid), Some(cpu)) = (columns.next(), columns.next()) else {
+                anyhow::bail!("Invalid process line of {container}: {line}");
+            };
+
+            Ok(ContainerProcess {
+                container: container.to_string(),
+                pid: pid
+                    .parse()
+                    .with_context(|| format!("Invalid process id of {container}: {pid}"))?,
+                cpu_percent: cpu
+                    .parse()
+                    .with_context(|| format!("Invalid CPU usage of {container}: {cpu}"))?,
+                command: columns.collect::<Vec<&str>>().join(" "),
+            })
+        })
+        .collect()
+}
+
+/// Sorts processes in place, breaking ties by container name and process id
+pub fn sort_processes(processes: &mut [ContainerProcess], sort: TopSort) {
+    processes.sort_by(|a, b| {
+        let order = match sort {
+            TopSort::Cpu => b.cpu_percent.total_cmp(&a.cpu_percent),
+            TopSort::Pid => a.pid.cmp(&b.pid),
+            TopSort::Container => std::cmp::Ordering::Equal,
+        };
+
+        order
+            .then_with(|| a.container.cmp(&b.container))
+            .then_with(|| a.pid.cmp(&b.pid))
+    });
+}
+
+/// Lists the processes running in a container
+pub fn container_processes(container: &str) -> anyhow::Result<Vec<ContainerProcess>> {
+    let output = check_output(
+        "top",
+        Command::new(DOCKER)
+            .args(["top", container])
+            .args(PS_ARGS)
+            .output(),
+    )?;
+
+    parse_top_output(container, &String::from_utf8_lossy(&output.stdout))
+}
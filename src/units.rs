@@ -0,0 +1,308 @@
+use anyhow::Context;
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Duration units from largest to smallest, with their length in seconds
+const DURATION_UNITS: [(char, u64); 4] = [('d', 86_400), ('h', 3_600), ('m', 60), ('s', 1)];
+
+/// Binary size units from largest to smallest, used when formatting
+const BINARY_SIZE_UNITS: [(&str, u64); 5] = [
+    ("TiB", 1 << 40),
+    ("GiB", 1 << 30),
+    ("MiB", 1 << 20),
+    ("KiB", 1 << 10),
+    ("B", 1),
+];
+
+/// Decimal size units from largest to smallest, used when formatting
+const DECIMAL_SIZE_UNITS: [(&str, u64); 5] = [
+    ("TB", 1_000_000_000_000),
+    ("GB", 1_000_000_000),
+    ("MB", 1_000_000),
+    ("kB", 1_000),
+    ("B", 1),
+];
+
+/// Unit system used when formatting sizes
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum SizeUnits {
+    /// Powers of 1024, e.g. MiB, like docker stats
+    #[default]
+    Binary,
+    /// Powers of 1000, e.g. MB, like docker images
+    Decimal,
+}
+
+/// Parses a human friendly duration such as 30s, 15m or 2h30m.
+///
+/// Units must be given from largest to smallest and at most once. A plain number is seconds.
+pub fn parse_duration(input: &str) -> anyhow::Result<Duration> {
+    if input.is_empty() {
+        anyhow::bail!("Duration must not be empty");
+    }
+
+    if let Ok(secs) = input.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+
+    let mut total_secs: u64 = 0;
+    let mut digits = String::new();
+    let mut last_unit_index: Option<usize> = None;
+
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+
+        let unit_index = DURATION_UNITS
+            .iter()
+            .position(|(unit, _)| *unit == c)
+            .with_context(|| format!("Invalid duration unit '{c}' in: {input}"))?;
+
+        if last_unit_index.is_some_and(|last| unit_index <= last) {
+            anyhow::bail!("Duration units must go from largest to smallest in: {input}");
+        }
+        last_unit_index = Some(unit_index);
+
+        let value = digits
+            .parse::<u64>()
+            .with_context(|| format!("Invalid duration: {input}"))?;
+        digits.clear();
+
+        total_secs = value
+            .checked_mul(DURATION_UNITS[unit_index].1)
+            .and_then(|secs| total_secs.checked_add(secs))
+            .with_context(|| format!("Duration too long: {input}"))?;
+    }
+
+    if !digits.is_empty() {
+        anyhow::bail!("Missing unit after {digits} in: {input}");
+    }
+
+    Ok(Duration::from_secs(total_secs))
+}
+
+/// Formats a duration the way parse_duration reads it, e.g. 2h30m, dropping sub-second precision
+pub fn format_duration(duration: Duration) -> String {
+    let mut remaining = duration.as_secs();
+
+    if remaining == 0 {
+        return "0s".to_string();
+    }
+
+    let mut formatted = String::new();
+
+    for (unit, secs) in DURATION_UNITS {
+        if remaining >= secs {
+            formatted.push_str(&format!("{}{unit}", remaining / secs));
+            remaining %= secs;
+        }
+    }
+
+    formatted
+}
+
+/// Parses a size such as 512MiB, 1.5G or 100KB into bytes.
+///
+/// Like docker, single letter units (K, M, G, T) are binary, while KB, MB, GB and TB are decimal.
+/// Units are case insensitive and a plain number is bytes.
+pub fn parse_size(input: &str) -> anyhow::Result<u64> {
+    let unit_start = input
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(input.len());
+    let (number, unit) = input.split_at(unit_start);
+
+    let is_valid_number = !number.is_empty()
+        && !number.starts_with('.')
+        && !number.ends_with('.')
+        && number.matches('.').count() <= 1;
+
+    if !is_valid_number {
+        anyhow::bail!("Invalid size: {input}");
+    }
+
+    let multiplier: u64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "k" | "kib" => 1 << 10,
+        "m" | "mib" => 1 << 20,
+        "g" | "gib" => 1 << 30,
+        "t" | "tib" => 1 << 40,
+        "kb" => 1_000,
+        "mb" => 1_000_000,
+        "gb" => 1_000_000_000,
+        "tb" => 1_000_000_000_000,
+        _ => anyhow::bail!("Invalid size unit '{unit}' in: {input}"),
+    };
+
+    // whole numbers are computed exactly, fractions are rounded to the nearest byte
+    if let Ok(value) = number.parse::<u64>() {
+        return value
+            .checked_mul(multiplier)
+            .with_context(|| format!("Size too large: {input}"));
+    }
+
+    let value = number
+        .parse::<f64>()
+        .with_context(|| format!("Invalid size: {input}"))?;
+    let bytes = (value * multiplier as f64).round();
+
+    if bytes >= u64::MAX as f64 {
+        anyhow::bail!("Size too large: {input}");
+    }
+
+    Ok(bytes as u64)
+}
+
+/// Formats bytes using the largest binary unit, with up to two decimals, e.g. 1.5GiB
+pub fn format_size(bytes: u64) -> String {
+    let (value, unit) = scale_size(bytes, SizeUnits::Binary);
+
+    format!("{value}{unit}")
+}
+
+/// Scales bytes to the largest fitting unit, returning the value with up to two decimals
+fn scale_size(bytes: u64, units: SizeUnits) -> (String, &'static str) {
+    let table = match units {
+        SizeUnits::Binary => &BINARY_SIZE_UNITS,
+        SizeUnits::Decimal => &DECIMAL_SIZE_UNITS,
+    };
+
+    let (unit, unit_bytes) = table
+        .iter()
+        .find(|(_, unit_bytes)| bytes >= *unit_bytes)
+        .copied()
+        .unwrap_or(("B", 1));
+
+    if bytes.is_multiple_of(unit_bytes) {
+        return ((bytes / unit_bytes).to_string(), unit);
+    }
+
+    let value = format!("{:.2}", bytes as f64 / unit_bytes as f64);
+
+    (
+        value
+            .trim_end_matches('0')
+            .trim_end_matches('.')
+            .to_string(),
+        unit,
+    )
+}
+
+/// Separators used when formatting numbers for humans, following the locale
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NumberFormat {
+    pub thousands_separator: char,
+    pub decimal_separator: char,
+    pub size_units: SizeUnits,
+}
+
+impl Default for NumberFormat {
+    fn default() -> Self {
+        NumberFormat {
+            thousands_separator: ',',
+            decimal_separator: '.',
+            size_units: SizeUnits::default(),
+        }
+    }
+}
+
+impl NumberFormat {
+    /// Picks separators for a locale such as de_DE.UTF-8, falling back to 1,234.5
+    pub fn from_locale(locale: &str, size_units: SizeUnits) -> Self {
+        let locale = locale.split(['.', '@']).next().unwrap_or_default();
+        let language = locale.split(['_', '-']).next().unwrap_or_default();
+
+        let (thousands_separator, decimal_separator) = match (language, locale) {
+            (_, "de_CH" | "de_LI" | "it_CH") => ('\'', '.'),
+            (
+                "bg" | "cs" | "et" | "fi" | "fr" | "hu" | "lt" | "lv" | "nb" | "nn" | "no" | "pl"
+                | "ru" | "sk" | "sv" | "uk",
+                _,
+            ) => ('\u{a0}', ','),
+            (
+                "da" | "de" | "el" | "es" | "hr" | "id" | "it" | "nl" | "pt" | "ro" | "sl" | "sr"
+                | "tr",
+                _,
+            ) => ('.', ','),
+            _ => (',', '.'),
+        };
+
+        NumberFormat {
+            thousands_separator,
+            decimal_separator,
+            size_units,
+        }
+    }
+
+    /// Picks separators for the locale of the environment, honoring LC_ALL, LC_NUMERIC and LANG
+    pub fn from_env(size_units: SizeUnits) -> Self {
+        let locale = ["LC_ALL", "LC_NUMERIC", "LANG"]
+            .iter()
+            .filter_map(|name| std::env::var(name).ok())
+            .find(|locale| !locale.is_empty())
+            .unwrap_or_default();
+
+        NumberFormat::from_locale(&locale, size_units)
+    }
+
+    /// Formats a whole number with thousands separators, e.g. 1,234,567
+    pub fn number(&self, value: u64) -> String {
+        group_thousands(&value.to_string(), self.thousands_separator)
+    }
+
+    /// Formats a number with the given decimals, e.g. 1,234.50
+    pub fn decimal(&self, value: f64, decimals: usize) -> String {
+        let formatted = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = formatted
+            .split_once('.')
+            .unwrap_or((formatted.as_str(), ""));
+
+        let mut result = if value < 0.0 {
+            "-".to_string()
+        } else {
+            String::new()
+        };
+        result.push_str(&group_thousands(whole, self.thousands_separator));
+        if !fraction.is_empty() {
+            result.push(self.decimal_separator);
+            result.push_str(fraction);
+        }
+
+        result
+    }
+
+    /// Formats a percentage with two decimals, e.g. 12.34%
+    pub fn percent(&self, value: f64) -> String {
+        format!("{}%", self.decimal(value, 2))
+    }
+
+    /// Formats bytes in the configured unit system with up to two decimals, e.g. 1.5 GiB
+    pub fn size(&self, bytes: u64) -> String {
+        let (value, unit) = scale_size(bytes, self.size_units);
+        let (whole, fraction) = value.split_once('.').unwrap_or((value.as_str(), ""));
+
+        let mut result = group_thousands(whole, self.thousands_separator);
+        if !fraction.is_empty() {
+            result.push(self.decimal_separator);
+            result.push_str(fraction);
+        }
+
+        format!("{result} {unit}")
+    }
+}
+
+/// Inserts a separator between groups of three digits
+fn group_thousands(digits: &str, separator: char) -> String {
+    let mut grouped = String::new();
+
+    for (index, digit) in digits.chars().enumerate() {
+        if index > 0 && (digits.len() - index).is_multiple_of(3) {
+            grouped.push(separator);
+        }
+        grouped.push(digit);
+    }
+
+    grouped
+}
@@ -1,36 +1,89 @@
+use crate::batch::BatchResults;
 use crate::commands::DOCKER;
-use crate::printer::{color_println, color_println_fmt, Color};
-use anyhow::Context;
+use crate::error::{check_output, DsdError};
+use crate::i18n::{tr, tr_args};
+use crate::parsers::{
+    parse_image_id, parse_inspect_output, parse_manifest_size, parse_name_label_pairs,
+    parse_stats_output, uptime_seconds,
+};
+use crate::printer::{
+    color_println, color_println_fmt, is_deterministic, role_color, use_color, Color, Role,
+};
+use crate::registry::{authentication_required, is_auth_error, AuthenticationRequired};
 use chrono::{DateTime, Local, Utc};
-use std::io::{BufRead, BufReader, IsTerminal};
+use std::collections::{HashMap, VecDeque};
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
 use std::process::{Command, Stdio};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
-/// Determine if stdout is going to terminal
+/// Determine if output is colored and decorated like on a terminal, following `--color`,
+/// NO_COLOR and CLICOLOR_FORCE, unless accessible output or CI mode was requested
 pub fn is_terminal() -> bool {
-    std::io::stdout().is_terminal()
+    use_color()
 }
 
-/// Gets the current time on the system in readable format
+/// Gets the current time on the system in readable format, in UTC for deterministic output
 pub fn get_timestamp() -> String {
-    Local::now().format("%Y-%m-%dT%H:%M:%S").to_string()
+    if is_deterministic() {
+        Utc::now().format("%Y-%m-%dT%H:%M:%SZ").to_string()
+    } else {
+        Local::now().format("%Y-%m-%dT%H:%M:%S").to_string()
+    }
+}
+
+/// Formats a time in the local timezone, or in UTC marked as such for deterministic output
+pub fn format_time(at: DateTime<Utc>, format: &str) -> String {
+    if is_deterministic() {
+        format!("{} UTC", at.format(format))
+    } else {
+        at.with_timezone(&Local).format(format).to_string()
+    }
+}
+
+/// Determine if text matches a pattern where `*` matches any number of characters
+pub fn matches_pattern(pattern: &str, text: &str) -> bool {
+    let parts = pattern.split('*').collect::<Vec<&str>>();
+
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+
+    if !text.starts_with(first) || !text[first.len()..].ends_with(last) {
+        return false;
+    }
+
+    let mut remaining = &text[first.len()..text.len() - last.len()];
+
+    for part in &parts[1..parts.len() - 1] {
+        match remaining.find(part) {
+            Some(index) => remaining = &remaining[index + part.len()..],
+            None => return false,
+        }
+    }
+
+    true
 }
 
 /// Lists currently running docker containers
-pub fn list_containers() -> anyhow::Result<Vec<String>> {
+pub fn list_containers() -> Result<Vec<String>, DsdError> {
     if is_terminal() {
-        color_println(Color::Magenta, "Listing docker containers...");
+        color_println(Color::Magenta, &tr("Listing docker containers..."));
     }
 
+    list_container_ids()
+}
+
+/// Lists ids of currently running docker containers without printing progress
+pub fn list_container_ids() -> Result<Vec<String>, DsdError> {
     // Use docker to list container_ids
-    let container_ids = Command::new(DOCKER)
-        .args(["ps", "-q"])
-        .output()
-        .context("Failed to list docker containers")?;
+    let container_ids = check_output("ps", Command::new(DOCKER).args(["ps", "-q"]).output())?;
 
     // Turn Output into String
-    let container_id_list = String::from_utf8(container_ids.stdout)
-        .context("Failed to create string of container id's")?;
+    let container_id_list =
+        String::from_utf8(container_ids.stdout).map_err(|e| DsdError::parse("ps", e))?;
 
     // Parse/sanitize container ids and collecto into Vec
     let ids = container_id_list
@@ -41,37 +94,53 @@ pub fn list_containers() -> anyhow::Result<Vec<String>> {
     Ok(ids)
 }
 
-/// Force removes all docker containers provided in argument
-pub fn kill_containers(container_ids: Vec<String>) -> anyhow::Result<()> {
+/// Force removes the docker containers provided in argument one by one, so a container that
+/// cannot be removed does not keep the others running
+pub fn kill_containers(containers: &[String]) -> Result<BatchResults, DsdError> {
     if is_terminal() {
-        color_println(Color::Yellow, "Killing docker containers...");
+        color_println(
+            role_color(Role::Warning),
+            &tr("Killing docker containers..."),
+        );
     } else {
-        println!("Killing docker containers...")
+        println!("{}", tr("Killing docker containers..."))
     }
 
-    Command::new(DOCKER)
-        .args(["rm", "-f"])
-        .args(&container_ids)
-        .status()
-        .context("Failed to remove containers")?;
+    let mut results = BatchResults::default();
+    for container in containers {
+        let output = Command::new(DOCKER)
+            .args(["rm", "-f", container])
+            .output()
+            .map_err(|e| DsdError::from_spawn("rm", e))?;
 
-    Ok(())
+        if output.status.success() {
+            results.succeeded(container);
+        } else {
+            results.failed(
+                container,
+                String::from_utf8_lossy(&output.stderr).trim().to_string(),
+            );
+        }
+    }
+
+    Ok(results)
 }
 
 /// Gets container names from a given stack
-pub fn get_containers_from_stack(stack: &str) -> anyhow::Result<Vec<String>> {
-    let output = Command::new(DOCKER)
-        .args([
-            "ps",
-            "-q",
-            "--filter",
-            &format!("label=com.docker.compose.project={}", &stack),
-        ])
-        .output()
-        .context(format!("Failed to containers in stack: {}", &stack))?;
+pub fn get_containers_from_stack(stack: &str) -> Result<Vec<String>, DsdError> {
+    let output = check_output(
+        "ps",
+        Command::new(DOCKER)
+            .args([
+                "ps",
+                "-q",
+                "--filter",
+                &format!("label={LABEL_COMPOSE_PROJECT}={}", &stack),
+            ])
+            .output(),
+    )?;
 
-    let container_ids =
-        String::from_utf8(output.stdout).expect("Failed to parse container name from output");
+    let container_ids = String::from_utf8(output.stdout).map_err(|e| DsdError::parse("ps", e))?;
 
     let container_ids_vec = container_ids.split_whitespace().map(String::from);
 
@@ -82,17 +151,96 @@ pub fn get_containers_from_stack(stack: &str) -> anyhow::Result<Vec<String>> {
     Ok(containers)
 }
 
+/// Gets names of containers labelled as belonging to a group
+pub fn get_containers_from_group(group: &str) -> Result<Vec<String>, DsdError> {
+    let output = check_output(
+        "ps",
+        Command::new(DOCKER)
+            .args([
+                "ps",
+                "--filter",
+                &format!("label={LABEL_DSD_GROUP}={group}"),
+                "--format",
+                "{{.Names}}",
+            ])
+            .output(),
+    )?;
+
+    let containers = String::from_utf8(output.stdout)
+        .map_err(|e| DsdError::parse("ps", e))?
+        .split_whitespace()
+        .map(String::from)
+        .collect();
+
+    Ok(containers)
+}
+
+/// Determine if a container exists by name or id
+pub fn container_exists(container: &str) -> bool {
+    Command::new(DOCKER)
+        .args(["inspect", "--type", "container", container])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success())
+}
+
+/// Gets container names from a given stack, along with the compose service of each container
+pub fn get_stack_services(stack: &str) -> Result<Vec<(String, String)>, DsdError> {
+    let output = check_output(
+        "ps",
+        Command::new(DOCKER)
+            .args([
+                "ps",
+                "--filter",
+                &format!("label={LABEL_COMPOSE_PROJECT}={}", &stack),
+                "--format",
+                &format!("{{{{.Names}}}}\t{{{{.Label \"{LABEL_COMPOSE_SERVICE}\"}}}}"),
+            ])
+            .output(),
+    )?;
+
+    let services = String::from_utf8(output.stdout).map_err(|e| DsdError::parse("ps", e))?;
+
+    Ok(parse_name_label_pairs(&services))
+}
+
+/// Gets the names of docker containers by id, in a single docker call
+pub fn get_container_names(container_ids: &[String]) -> Result<Vec<String>, DsdError> {
+    if container_ids.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let output = check_output(
+        "inspect",
+        Command::new(DOCKER)
+            .args(["inspect", "--format", "{{.Name}}"])
+            .args(container_ids)
+            .output(),
+    )?;
+
+    let names = String::from_utf8(output.stdout)
+        .map_err(|e| DsdError::parse("inspect", e))?
+        .lines()
+        .map(|name| name.trim().trim_start_matches('/').to_string())
+        .collect();
+
+    Ok(names)
+}
+
 /// Gets the name of a docker container by the container_id passed as argument
-pub fn get_container_name(container_id: &str) -> anyhow::Result<String> {
+pub fn get_container_name(container_id: &str) -> Result<String, DsdError> {
     // get container name by referencing id
-    let output = Command::new(DOCKER)
-        .args(["inspect", "--format", "{{.Name}}", container_id])
-        .output()
-        .context("Failed to inspect container")?;
+    let output = check_output(
+        "inspect",
+        Command::new(DOCKER)
+            .args(["inspect", "--format", "{{.Name}}", container_id])
+            .output(),
+    )?;
 
     // parse output into clean String
     let name = String::from_utf8(output.stdout)
-        .context("Failed to parse container name from output")?
+        .map_err(|e| DsdError::parse("inspect", e))?
         .trim()
         .trim_start_matches('/') // Docker names start with '/'
         .to_string();
@@ -100,198 +248,813 @@ pub fn get_container_name(container_id: &str) -> anyhow::Result<String> {
     Ok(name)
 }
 
-/// Updates a container by the container_name provided as argument
-pub fn update_container_by_name(container_name: &str) -> anyhow::Result<u8> {
-    let mut is_updated: u8 = 0;
-    // get container image string by referenciing the container_name
-    let image_output = Command::new(DOCKER)
-        .args(["inspect", "--format", "{{.Config.Image}}", container_name])
-        .output()
-        .context("Failed to inspect container")?;
+/// Shell to open in a container, bash when it has one and sh otherwise
+pub fn detect_shell(container: &str) -> &'static str {
+    let has_bash = Command::new(DOCKER)
+        .args(["exec", container, "sh", "-c", "command -v bash"])
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .is_ok_and(|status| status.success());
+
+    if has_bash {
+        "bash"
+    } else {
+        "sh"
+    }
+}
+
+/// Gets the image of a docker container by the container_name passed as argument
+pub fn get_container_image(container_name: &str) -> Result<String, DsdError> {
+    // get container image string by referencing the container_name
+    let image_output = check_output(
+        "inspect",
+        Command::new(DOCKER)
+            .args(["inspect", "--format", "{{.Config.Image}}", container_name])
+            .output(),
+    )?;
 
     // parse output into clean String
     let image_name = String::from_utf8(image_output.stdout)
-        .context("Failed to parse image name from output")?
+        .map_err(|e| DsdError::parse("inspect", e))?
         .trim()
         .to_string();
 
+    Ok(image_name)
+}
+
+/// Gets the ID of the image a container was created from, which the image reference may no longer
+/// point to after a pull
+pub fn get_container_image_id(container_name: &str) -> Result<String, DsdError> {
+    let output = check_output(
+        "inspect",
+        Command::new(DOCKER)
+            .args(["inspect", "--format", "{{.Image}}", container_name])
+            .output(),
+    )?;
+
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Shape of the result of pulling an image
+#[derive(Debug, Clone)]
+pub struct UpdateResult {
+    pub image: String,
+    pub previous_id: Option<String>,
+    pub current_id: String,
+    pub digest: Option<String>,
+}
+
+impl UpdateResult {
+    /// Whether the pull resulted in a different image than was present before
+    pub fn is_updated(&self) -> bool {
+        self.previous_id.as_deref() != Some(self.current_id.as_str())
+    }
+}
+
+/// Gets the local image id and repo digest of an image, if the image is present
+pub fn get_image_id(image_name: &str) -> Result<Option<(String, Option<String>)>, DsdError> {
+    let output = check_output(
+        "image inspect",
+        Command::new(DOCKER)
+            .args([
+                "image",
+                "inspect",
+                "--format",
+                "{{.Id}} {{range .RepoDigests}}{{.}} {{end}}",
+                image_name,
+            ])
+            .output(),
+    );
+
+    let output = match output {
+        Ok(output) => output,
+        Err(DsdError::ImageNotFound(_)) => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let inspected =
+        String::from_utf8(output.stdout).map_err(|e| DsdError::parse("image inspect", e))?;
+
+    Ok(parse_image_id(&inspected))
+}
+
+/// Gets the size of a local image in bytes
+pub fn get_image_size(image_name: &str) -> Result<u64, DsdError> {
+    let output = check_output(
+        "image inspect",
+        Command::new(DOCKER)
+            .args(["image", "inspect", "--format", "{{.Size}}", image_name])
+            .output(),
+    )?;
+
+    String::from_utf8_lossy(&output.stdout)
+        .trim()
+        .parse()
+        .map_err(|e| DsdError::parse("image inspect", format!("size of {image_name}: {e}")))
+}
+
+/// Size of an image in the registry for a platform such as linux/amd64, None when the registry
+/// cannot tell, e.g. without credentials
+pub fn get_image_download_size(image_name: &str, platform: &str) -> Option<u64> {
+    let output = Command::new(DOCKER)
+        .args(["manifest", "inspect", "--verbose", image_name])
+        .stderr(Stdio::null())
+        .output()
+        .ok()
+        .filter(|output| output.status.success())?;
+
+    parse_manifest_size(&output.stdout, platform).ok().flatten()
+}
+
+/// Root directory of the docker daemon and the platform it pulls images for, such as
+/// /var/lib/docker and linux/amd64
+pub fn get_docker_root() -> Result<(PathBuf, String), DsdError> {
+    let output = check_output(
+        "info",
+        Command::new(DOCKER)
+            .args([
+                "info",
+                "--format",
+                "{{.DockerRootDir}}\t{{.OSType}}\t{{.Architecture}}",
+            ])
+            .output(),
+    )?;
+
+    let info = String::from_utf8_lossy(&output.stdout);
+    let fields = info.trim().split('\t').collect::<Vec<&str>>();
+    let [root, os, architecture] = fields.as_slice() else {
+        return Err(DsdError::parse("info", info.trim()));
+    };
+
+    Ok((
+        PathBuf::from(root),
+        format!("{os}/{}", platform_architecture(architecture)),
+    ))
+}
+
+/// Architecture name used by image platforms for the one reported by the kernel, such as amd64
+/// for x86_64
+pub fn platform_architecture(architecture: &str) -> &str {
+    match architecture {
+        "x86_64" => "amd64",
+        "aarch64" => "arm64",
+        "armv7l" | "armv6l" => "arm",
+        "i386" | "i686" => "386",
+        architecture => architecture,
+    }
+}
+
+/// Updates a container by the container_name provided as argument
+pub fn update_container_by_name(container_name: &str) -> Result<UpdateResult, DsdError> {
+    let image_name = get_container_image(container_name)?;
+
     if is_terminal() {
         color_println(
             Color::Cyan,
-            &format!("Pulling image for {}: {}", &container_name, &image_name),
+            &tr_args(
+                "Pulling image for {container}: {image}",
+                &[("container", &container_name), ("image", &image_name)],
+            ),
         );
     } else {
-        println!("Pulling image for {}: {}", &container_name, &image_name)
+        println!(
+            "{}",
+            tr_args(
+                "Pulling image for {container}: {image}",
+                &[("container", &container_name), ("image", &image_name)]
+            )
+        )
     }
 
-    // pull new image for container
+    pull_image(&image_name, false)
+}
+
+/// Pulls an image, comparing the image id before and after the pull to detect updates
+pub fn pull_image(image_name: &str, prefix_output: bool) -> Result<UpdateResult, DsdError> {
+    let use_color = is_terminal();
+    let previous_id = get_image_id(image_name)?.map(|(id, _)| id);
+
+    // pull new image
     let mut logs_process = Command::new(DOCKER)
-        .args(["pull", &image_name])
+        .args(["pull", image_name])
         .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
         .spawn()
-        .context(format!("Failed to pull image: {}", &image_name))?;
+        .map_err(|e| DsdError::from_spawn("pull", e))?;
 
     if let Some(stdout) = logs_process.stdout.take() {
         let reader = BufReader::new(stdout);
         for line in reader.lines().map_while(Result::ok) {
-            println!("{line}");
-            if line.contains("Status: Downloaded newer image") {
-                is_updated = 1
+            if !prefix_output {
+                println!("{line}");
+            } else if use_color {
+                println!("[{}] {}", color_println_fmt(Color::Cyan, image_name), line);
+            } else {
+                println!("[{image_name}] {line}");
             }
         }
     }
 
-    let _ = logs_process.kill();
-    let _ = logs_process.wait();
+    // kept to tell registries refusing the pull apart from other failures
+    let mut stderr = String::new();
+    if let Some(mut pipe) = logs_process.stderr.take() {
+        let _ = pipe.read_to_string(&mut stderr);
+    }
+
+    // both pipes are closed, so the pull has finished and its exit status is the real one
+    let status = logs_process
+        .wait()
+        .map_err(|e| DsdError::from_spawn("pull", e))?;
+
+    if !status.success() {
+        if is_auth_error(&stderr) {
+            return Err(authentication_required(image_name).into());
+        }
 
-    Ok(is_updated)
+        return Err(DsdError::from_docker("pull", status.code(), &stderr));
+    }
+
+    let (current_id, digest) =
+        get_image_id(image_name)?.ok_or_else(|| DsdError::ImageNotFound(image_name.to_string()))?;
+
+    Ok(UpdateResult {
+        image: image_name.to_string(),
+        previous_id,
+        current_id,
+        digest,
+    })
 }
 
-/// Spawns threads to handle container logs
-pub fn spawn_container_logger(
-    container: &str,
-    is_container_id: bool,
-    use_color: bool,
-    tail: u32,
-    tx: std::sync::mpsc::Sender<String>,
-) -> anyhow::Result<std::thread::JoinHandle<()>> {
-    let container_identifier = Arc::new(container.to_string());
+/// Pulls images concurrently on a bounded pool of threads, returning the result for each image
+pub fn pull_images(
+    images: &[String],
+    jobs: usize,
+) -> Result<HashMap<String, UpdateResult>, DsdError> {
+    let use_color = is_terminal();
+    let queue = Arc::new(Mutex::new(images.iter().cloned().collect::<VecDeque<_>>()));
+    let (tx, rx) = std::sync::mpsc::channel::<(String, Result<UpdateResult, DsdError>)>();
+    let mut handles: Vec<std::thread::JoinHandle<()>> = vec![];
 
-    let handle = std::thread::spawn(move || {
-        let container_name = if is_container_id {
-            match get_container_name(&container_identifier) {
-                Ok(name) => Arc::new(name),
-                Err(_) => Arc::clone(&container_identifier),
+    for _ in 0..jobs.clamp(1, images.len().max(1)) {
+        let queue = Arc::clone(&queue);
+        let tx = tx.clone();
+        let handle = std::thread::spawn(move || {
+            loop {
+                let image = match queue.lock() {
+                    Ok(mut queue) => queue.pop_front(),
+                    Err(_) => None,
+                };
+
+                let Some(image) = image else {
+                    break;
+                };
+
+                let result = pull_image(&image, true);
+                if tx.send((image, result)).is_err() {
+                    break; // Receiver closed
+                }
             }
-        } else {
-            Arc::clone(&container_identifier)
-        };
+        });
+
+        handles.push(handle);
+    }
+
+    drop(tx);
+
+    let mut results: HashMap<String, UpdateResult> = HashMap::new();
+    let mut failed: Vec<String> = vec![];
+    let mut refused: Option<AuthenticationRequired> = None;
 
-        let mut logs_process = match Command::new(DOCKER)
+    for (image, result) in rx {
+        match result {
+            Ok(result) => {
+                let status = if result.is_updated() {
+                    tr("pulled newer image")
+                } else {
+                    tr("up to date")
+                };
+
+                if use_color {
+                    println!(
+                        "{} {}",
+                        color_println_fmt(Color::Cyan, &format!("[{image}]")),
+                        color_println_fmt(Color::Green, &status)
+                    );
+                } else {
+                    println!("[{image}] {status}");
+                }
+
+                results.insert(image, result);
+            }
+            Err(e) => {
+                if use_color {
+                    color_println(role_color(Role::Error), &format!("[{image}] {e:#}"));
+                } else {
+                    println!("[{image}] {e:#}");
+                }
+
+                if let DsdError::AuthenticationRequired(auth) = &e {
+                    refused.get_or_insert_with(|| auth.clone());
+                }
+                failed.push(image);
+            }
+        }
+    }
+
+    for handle in handles {
+        let _ = handle.join();
+    }
+
+    if !failed.is_empty() {
+        return Err(DsdError::PullFailed {
+            images: failed,
+            authentication: refused,
+        });
+    }
+
+    Ok(results)
+}
+
+pub const LABEL_COMPOSE_PROJECT: &str = "com.docker.compose.project";
+pub const LABEL_COMPOSE_SERVICE: &str = "com.docker.compose.service";
+pub const LABEL_COMPOSE_DEPENDS_ON: &str = "com.docker.compose.depends_on";
+pub const LABEL_COMPOSE_WORKING_DIR: &str = "com.docker.compose.project.working_dir";
+pub const LABEL_COMPOSE_CONFIG_FILES: &str = "com.docker.compose.project.config_files";
+pub const LABEL_DSD_UPDATE: &str = "dsd-util.update";
+pub const LABEL_DSD_SKIP: &str = "dsd-util.skip";
+pub const LABEL_DSD_GROUP: &str = "dsd-util.group";
+const SUFFIX_OLD_CONTAINER: &str = "dsd-old";
+
+/// Shape of the compose project a container belongs to
+#[derive(Debug, Clone)]
+pub struct ComposeInfo {
+    pub project: String,
+    pub service: String,
+    pub working_dir: String,
+    pub config_files: Vec<String>,
+}
+
+/// Gets the labels of a docker container
+pub fn get_container_labels(container_name: &str) -> Result<HashMap<String, String>, DsdError> {
+    let output = check_output(
+        "inspect",
+        Command::new(DOCKER)
             .args([
-                "logs",
-                &container_name,
-                "--tail",
-                &tail.to_string(),
-                "--follow",
+                "inspect",
+                "--format",
+                "{{json .Config.Labels}}",
+                container_name,
             ])
-            .stdout(Stdio::piped())
-            .stderr(Stdio::piped())
-            .spawn()
-        {
-            Ok(proc) => proc,
-            Err(_) => {
-                let _ = tx.send(if use_color {
-                    color_println_fmt(
-                        Color::Red,
-                        &format!("[ERROR] - Failed to log {container_name}"),
-                    )
+            .output(),
+    )?;
+
+    let labels: Option<HashMap<String, String>> = serde_json::from_slice(&output.stdout)
+        .map_err(|e| DsdError::parse("inspect", format!("labels of {container_name}: {e}")))?;
+
+    Ok(labels.unwrap_or_default())
+}
+
+/// Gets the compose project info of a container, if it is managed by docker compose
+pub fn get_compose_info(container_name: &str) -> Result<Option<ComposeInfo>, DsdError> {
+    let labels = get_container_labels(container_name)?;
+
+    let (Some(project), Some(service)) = (
+        labels.get(LABEL_COMPOSE_PROJECT),
+        labels.get(LABEL_COMPOSE_SERVICE),
+    ) else {
+        return Ok(None);
+    };
+
+    Ok(Some(ComposeInfo {
+        project: project.to_string(),
+        service: service.to_string(),
+        working_dir: labels
+            .get(LABEL_COMPOSE_WORKING_DIR)
+            .cloned()
+            .unwrap_or_default(),
+        config_files: labels
+            .get(LABEL_COMPOSE_CONFIG_FILES)
+            .map(|files| files.split(',').map(String::from).collect())
+            .unwrap_or_default(),
+    }))
+}
+
+/// Builds a docker compose command targeting the project of a container
+pub fn compose_command(compose_info: &ComposeInfo) -> Command {
+    let mut command = Command::new(DOCKER);
+    command.args(["compose", "--project-name", &compose_info.project]);
+
+    if !compose_info.working_dir.is_empty() {
+        command.args(["--project-directory", &compose_info.working_dir]);
+    }
+
+    for file in &compose_info.config_files {
+        command.args(["-f", file]);
+    }
+
+    command
+}
+
+/// Changes the restart policy of a container without recreating it
+pub fn set_restart_policy(container_name: &str, policy: &str) -> Result<(), DsdError> {
+    check_output(
+        "update",
+        Command::new(DOCKER)
+            .args(["update", "--restart", policy, container_name])
+            .output(),
+    )?;
+
+    Ok(())
+}
+
+/// Recreates a container so it runs the latest pulled image
+pub fn recreate_container(container_name: &str) -> Result<(), DsdError> {
+    if let Some(compose_info) = get_compose_info(container_name)? {
+        let status = compose_command(&compose_info)
+            .args(["up", "-d", "--no-deps", &compose_info.service])
+            .status()
+            .map_err(|e| DsdError::from_spawn("compose up", e))?;
+
+        if !status.success() {
+            return Err(DsdError::RecreateFailed {
+                container: container_name.to_string(),
+                restored: false,
+            });
+        }
+
+        return Ok(());
+    }
+
+    recreate_standalone_container(container_name, None)
+}
+
+/// Recreates a container that is not managed by docker compose, preserving its config, running
+/// another image reference when given one.
+///
+/// Env vars and labels inherited from the old image are dropped so the new image can provide its own.
+pub fn recreate_standalone_container(
+    container_name: &str,
+    image: Option<&str>,
+) -> Result<(), DsdError> {
+    let output = check_output(
+        "inspect",
+        Command::new(DOCKER)
+            .args(["inspect", container_name])
+            .output(),
+    )?;
+
+    let inspected: Vec<serde_json::Value> =
+        serde_json::from_slice(&output.stdout).map_err(|e| DsdError::parse("inspect", e))?;
+    let container = inspected
+        .first()
+        .ok_or_else(|| DsdError::ContainerNotFound(container_name.to_string()))?;
+
+    let current_image = container["Config"]["Image"]
+        .as_str()
+        .ok_or_else(|| DsdError::parse("inspect", format!("no image for {container_name}")))?;
+    let image_name = image.unwrap_or(current_image);
+
+    // env vars and labels of the old image, which should not be pinned on the new container
+    let old_image_output = Command::new(DOCKER)
+        .args(["image", "inspect", "--format", "{{json .Config}}"])
+        .arg(container["Image"].as_str().unwrap_or(current_image))
+        .output()
+        .map_err(|e| DsdError::from_spawn("image inspect", e))?;
+    let old_image_config: serde_json::Value =
+        serde_json::from_slice(&old_image_output.stdout).unwrap_or_default();
+
+    let run_args = standalone_run_args(container_name, container, &old_image_config, image_name)?;
+
+    // keep the old container around until the new one is running
+    let old_container_name = format!("{container_name}-{SUFFIX_OLD_CONTAINER}");
+    let was_running = container["State"]["Running"].as_bool().unwrap_or(true);
+
+    // puts the old container back under its name, running again if it was, and tells whether
+    // that worked
+    let restore = |renamed: bool| {
+        let named = !renamed
+            || check_output(
+                "rename",
+                Command::new(DOCKER)
+                    .args(["rename", &old_container_name, container_name])
+                    .output(),
+            )
+            .is_ok();
+
+        named
+            && (!was_running
+                || check_output(
+                    "start",
+                    Command::new(DOCKER)
+                        .args(["start", container_name])
+                        .output(),
+                )
+                .is_ok())
+    };
+
+    if let Err(e) = check_output(
+        "stop",
+        Command::new(DOCKER).args(["stop", container_name]).output(),
+    ) {
+        // a stop that timed out may still have killed the container
+        restore(false);
+        return Err(e);
+    }
+
+    if let Err(e) = check_output(
+        "rename",
+        Command::new(DOCKER)
+            .args(["rename", container_name, &old_container_name])
+            .output(),
+    ) {
+        restore(false);
+        return Err(e);
+    }
+
+    let status = Command::new(DOCKER)
+        .args(&run_args)
+        .stdout(Stdio::null())
+        .status()
+        .map_err(|e| DsdError::from_spawn("run", e));
+
+    if !status.as_ref().is_ok_and(|status| status.success()) {
+        // the failed run may have left a container behind under the name
+        let _ = Command::new(DOCKER)
+            .args(["rm", "-f", container_name])
+            .output();
+
+        return Err(DsdError::RecreateFailed {
+            container: container_name.to_string(),
+            restored: restore(true),
+        });
+    }
+
+    Command::new(DOCKER)
+        .args(["rm", &old_container_name])
+        .output()
+        .map_err(|e| DsdError::from_spawn("rm", e))?;
+
+    Ok(())
+}
+
+/// Arguments of the `docker run` recreating a standalone container from its `docker inspect`
+/// output, on top of the config of the image it was created from.
+///
+/// Fails when the container has settings that would not survive the recreation.
+pub fn standalone_run_args(
+    container_name: &str,
+    container: &serde_json::Value,
+    old_image_config: &serde_json::Value,
+    image_name: &str,
+) -> Result<Vec<String>, DsdError> {
+    let unsupported = |setting: &str| DsdError::RecreateUnsupported {
+        container: container_name.to_string(),
+        setting: setting.to_string(),
+    };
+
+    let mut run_args: Vec<String> = vec!["run".into(), "-d".into()];
+    run_args.extend(["--name".into(), container_name.to_string()]);
+
+    for env in container["Config"]["Env"].as_array().into_iter().flatten() {
+        let is_image_env = old_image_config["Env"]
+            .as_array()
+            .is_some_and(|image_env| image_env.contains(env));
+
+        if let (Some(env), false) = (env.as_str(), is_image_env) {
+            run_args.extend(["-e".into(), env.to_string()]);
+        }
+    }
+
+    if let Some(labels) = container["Config"]["Labels"].as_object() {
+        for (key, value) in labels {
+            if old_image_config["Labels"][key] == *value {
+                continue;
+            }
+            if let Some(value) = value.as_str() {
+                run_args.extend(["--label".into(), format!("{key}={value}")]);
+            }
+        }
+    }
+
+    if let Some(port_bindings) = container["HostConfig"]["PortBindings"].as_object() {
+        for (container_port, bindings) in port_bindings {
+            for binding in bindings.as_array().into_iter().flatten() {
+                let host_ip = binding["HostIp"].as_str().unwrap_or_default();
+                let host_port = binding["HostPort"].as_str().unwrap_or_default();
+                let port = if host_ip.is_empty() {
+                    format!("{host_port}:{container_port}")
                 } else {
-                    format!("[ERROR] - Failed to log {container_name}")
-                });
-                return;
+                    format!("{host_ip}:{host_port}:{container_port}")
+                };
+                run_args.extend(["-p".into(), port]);
             }
+        }
+    }
+
+    for bind in container["HostConfig"]["Binds"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        if let Some(bind) = bind.as_str() {
+            run_args.extend(["-v".into(), bind.to_string()]);
+        }
+    }
+
+    for mount in container["Mounts"].as_array().into_iter().flatten() {
+        if mount["Type"] != "volume" {
+            continue;
+        }
+        let name = mount["Name"].as_str().unwrap_or_default();
+        let destination = mount["Destination"].as_str().unwrap_or_default();
+        let volume = if mount["RW"] == false {
+            format!("{name}:{destination}:ro")
+        } else {
+            format!("{name}:{destination}")
         };
+        run_args.extend(["-v".into(), volume]);
+    }
 
-        let mut handles: Vec<std::thread::JoinHandle<()>> = vec![];
-
-        // handle stdout
-        if let Some(stdout) = logs_process.stdout.take() {
-            let tx_stdout = tx.clone();
-            let container_name_stdout = Arc::clone(&container_name);
-            let handle_stdout = std::thread::spawn(move || {
-                let reader = BufReader::new(stdout);
-                for line in reader.lines().map_while(Result::ok) {
-                    if tx_stdout
-                        .send(if use_color {
-                            format!(
-                                "[{} | {}] {}",
-                                color_println_fmt(Color::Cyan, &get_timestamp()),
-                                color_println_fmt(Color::Green, &container_name_stdout),
-                                line
-                            )
-                        } else {
-                            format!(
-                                "[{} | {}] {}",
-                                &get_timestamp(),
-                                &container_name_stdout,
-                                line
-                            )
-                        })
-                        .is_err()
-                    {
-                        break; // Receiver closed
-                    }
-                }
-            });
+    let restart_policy = container["HostConfig"]["RestartPolicy"]["Name"]
+        .as_str()
+        .unwrap_or_default();
+    if !restart_policy.is_empty() && restart_policy != "no" {
+        run_args.extend(["--restart".into(), restart_policy.to_string()]);
+    }
+
+    let network_mode = container["HostConfig"]["NetworkMode"]
+        .as_str()
+        .unwrap_or_default();
+    if !network_mode.is_empty() && network_mode != "default" {
+        run_args.extend(["--network".into(), network_mode.to_string()]);
+    }
 
-            handles.push(handle_stdout);
+    if container["HostConfig"]["Privileged"] == true {
+        run_args.push("--privileged".into());
+    }
+    for (setting, flag) in [("CapAdd", "--cap-add"), ("CapDrop", "--cap-drop")] {
+        for capability in container["HostConfig"][setting]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|capability| capability.as_str())
+        {
+            run_args.extend([flag.into(), capability.to_string()]);
         }
+    }
+    for device in container["HostConfig"]["Devices"]
+        .as_array()
+        .into_iter()
+        .flatten()
+    {
+        let (Some(host), Some(path)) = (
+            device["PathOnHost"].as_str(),
+            device["PathInContainer"].as_str(),
+        ) else {
+            return Err(unsupported("HostConfig.Devices"));
+        };
+        let permissions = device["CgroupPermissions"].as_str().unwrap_or("rwm");
+        run_args.extend(["--device".into(), format!("{host}:{path}:{permissions}")]);
+    }
 
-        // handle stderr
-        if let Some(stderr) = logs_process.stderr.take() {
-            let tx_stderr = tx.clone();
-            let container_name_stderr = Arc::clone(&container_name);
-            let handle_stderr = std::thread::spawn(move || {
-                let reader = BufReader::new(stderr);
-                for line in reader.lines().map_while(Result::ok) {
-                    if tx_stderr
-                        .send(if use_color {
-                            format!(
-                                "[{} | {}] {}",
-                                color_println_fmt(Color::Cyan, &get_timestamp()),
-                                color_println_fmt(Color::Green, &container_name_stderr),
-                                line
-                            )
-                        } else {
-                            format!(
-                                "[{} | {}] {}",
-                                &get_timestamp(),
-                                &container_name_stderr,
-                                line
-                            )
-                        })
-                        .is_err()
-                    {
-                        break; // Receiver closed
-                    }
-                }
-            });
+    // settings given to `docker run`, compared to the image so its defaults are not pinned
+    for (setting, flag) in [("User", "--user"), ("WorkingDir", "--workdir")] {
+        let value = container["Config"][setting].as_str().unwrap_or_default();
+        if container["Config"][setting] != old_image_config[setting] && !value.is_empty() {
+            run_args.extend([flag.into(), value.to_string()]);
+        }
+    }
 
-            handles.push(handle_stderr);
+    // --entrypoint takes a single program, its arguments go in front of the command. Docker drops
+    // the command of the image once the entrypoint is overridden, so it is passed along as well.
+    let command = strings(&container["Config"]["Cmd"]);
+    let mut command_args = vec![];
+    if container["Config"]["Entrypoint"] != old_image_config["Entrypoint"] {
+        let entrypoint = strings(&container["Config"]["Entrypoint"]).unwrap_or_default();
+        match entrypoint.split_first() {
+            Some((program, args)) => {
+                run_args.extend(["--entrypoint".into(), program.clone()]);
+                command_args.extend_from_slice(args);
+            }
+            None => run_args.extend(["--entrypoint".into(), String::new()]),
+        }
+        command_args.extend(command.unwrap_or_default());
+    } else if container["Config"]["Cmd"] != old_image_config["Cmd"] {
+        // an image command cannot be removed with `docker run` without touching the entrypoint
+        match command {
+            Some(command) if !command.is_empty() => command_args.extend(command),
+            _ => return Err(unsupported("Config.Cmd")),
         }
+    }
 
-        for handle in handles {
-            let _ = handle.join();
+    // settings not carried over, losing them silently could break the container
+    for setting in [
+        "Mounts",
+        "Tmpfs",
+        "SecurityOpt",
+        "Sysctls",
+        "ExtraHosts",
+        "Dns",
+        "Ulimits",
+    ] {
+        let value = &container["HostConfig"][setting];
+        let is_set = match value {
+            serde_json::Value::Array(values) => !values.is_empty(),
+            serde_json::Value::Object(values) => !values.is_empty(),
+            _ => !value.is_null(),
+        };
+        if is_set {
+            return Err(unsupported(&format!("HostConfig.{setting}")));
         }
+    }
 
-        let _ = logs_process.kill();
-        let _ = logs_process.wait();
-    });
+    run_args.push(image_name.to_string());
+    run_args.extend(command_args);
 
-    Ok(handle)
+    Ok(run_args)
+}
+
+/// Strings of a JSON array, None when the value is not one
+fn strings(value: &serde_json::Value) -> Option<Vec<String>> {
+    value.as_array().map(|values| {
+        values
+            .iter()
+            .filter_map(|value| value.as_str().map(str::to_string))
+            .collect()
+    })
+}
+
+/// Determine if a log line contains the filter, ignoring case
+pub fn line_matches(line: &str, grep: Option<&String>) -> bool {
+    match grep {
+        Some(grep) => line.to_lowercase().contains(&grep.to_lowercase()),
+        None => true,
+    }
 }
 
 /// Shape of stats data
 #[derive(Debug, Clone)]
 pub struct StatsData {
     pub container_name: String,
-    pub cpu: String,
-    pub memory: String,
+    pub cpu_percent: f64,
+    /// Memory used in percent of the limit
+    pub memory_percent: f64,
+    /// Memory used in bytes
+    pub memory_used: u64,
+    /// Memory limit in bytes
+    pub memory_limit: u64,
+    /// Bytes received over the network
+    pub net_rx: u64,
+    /// Bytes sent over the network
+    pub net_tx: u64,
+    /// Bytes read from block devices
+    pub block_read: u64,
+    /// Bytes written to block devices
+    pub block_write: u64,
+    /// Number of processes or threads in the container
+    pub pids: u64,
 }
 
-/// Parse stats data
-pub fn parse_stats_data(stats: &str) -> anyhow::Result<StatsData> {
-    let parsed = stats
-        .trim_start_matches("/")
-        .split_whitespace()
-        .collect::<Vec<&str>>();
+/// Order of containers in the stats table
+#[derive(Debug, Clone, Copy, Default, PartialEq, clap::ValueEnum)]
+pub enum StatsSort {
+    /// Alphabetically by container name
+    #[default]
+    Name,
+    /// Highest CPU usage first
+    Cpu,
+    /// Highest memory usage first
+    Memory,
+}
 
-    Ok(StatsData {
-        container_name: parsed[0].to_string(),
-        cpu: parsed[1].to_string(),
-        memory: parsed[2].to_string(),
-    })
+/// Sorts stats in place, breaking ties by container name
+pub fn sort_stats(stats: &mut [StatsData], sort: StatsSort) {
+    stats.sort_by(|a, b| {
+        let order = match sort {
+            StatsSort::Name => std::cmp::Ordering::Equal,
+            StatsSort::Cpu => b.cpu_percent.total_cmp(&a.cpu_percent),
+            StatsSort::Memory => b.memory_used.cmp(&a.memory_used),
+        };
+
+        order.then_with(|| a.container_name.cmp(&b.container_name))
+    });
+}
+
+/// Gets a single sample of stats for containers
+pub fn get_stats(containers: &[String]) -> Result<Vec<StatsData>, DsdError> {
+    // stats format from docker cli
+    let stats_output = skip_missing_containers(
+        "stats",
+        Command::new(DOCKER)
+            .args(["stats", "--no-stream", "--format", "{{json .}}"])
+            .args(containers)
+            .output(),
+    )?;
+
+    let stats_string = String::from_utf8(stats_output).map_err(|e| DsdError::parse("stats", e))?;
+
+    parse_stats_output(&stats_string).map_err(|e| DsdError::parse("stats", format!("{e:#}")))
 }
 
 /// Shape of inspected data
@@ -302,44 +1065,66 @@ pub struct InspectData {
     pub restart_policy: String,
     pub health: String,
     pub uptime: String,
+    pub started_at: String,
+    pub restart_count: u64,
     pub ports: String,
+    /// Host bindings of published ports, as configured when the container is stopped
+    pub port_bindings: Vec<PortBinding>,
+    /// Networks the container is attached to
+    pub networks: Vec<String>,
 }
 
-/// Parses inspected data
-pub fn parse_inspect_data(stats: &str) -> anyhow::Result<InspectData> {
-    let parsed = stats
-        .trim_start_matches("/")
-        .split(",")
-        .collect::<Vec<&str>>();
-
-    Ok(InspectData {
-        container_name: parsed[0].to_string(),
-        status: parsed[1].to_string(),
-        restart_policy: parsed[2].to_string(),
-        health: parsed[3].to_string(),
-        uptime: calc_uptime(parsed[4])?,
-        ports: parsed[5].to_string(),
-    })
+/// Container port published on the host
+#[derive(Debug, Clone, PartialEq)]
+pub struct PortBinding {
+    /// Port and protocol inside the container, such as 80/tcp
+    pub container_port: String,
+    /// Host address the port is bound to, empty or a wildcard for all addresses
+    pub host_ip: String,
+    /// Port on the host, empty when docker picks one
+    pub host_port: String,
 }
 
-/// Calculate uptime for a container
-fn calc_uptime(start_time: &str) -> anyhow::Result<String> {
-    let start_time =
-        DateTime::parse_from_rfc3339(start_time).context("Failed to parse start_time")?;
-    let now = Utc::now();
-    let duration = now.signed_duration_since(start_time.with_timezone(&Utc));
+/// Inspects containers and parses the results
+pub fn inspect_containers(containers: &[String]) -> Result<Vec<InspectData>, DsdError> {
+    let inspect_output = skip_missing_containers(
+        "inspect",
+        Command::new(DOCKER)
+            .arg("inspect")
+            .args(containers)
+            .output(),
+    )?;
 
-    let days = duration.num_days();
-    let hours = duration.num_hours() % 24;
-    let minutes = duration.num_minutes() % 60;
+    let inspect_string =
+        String::from_utf8(inspect_output).map_err(|e| DsdError::parse("inspect", e))?;
 
-    let uptime = if days > 0 {
-        format!("{days}D {hours}H {minutes}m")
-    } else if hours > 0 {
-        format!("{hours}H {minutes}m")
-    } else {
-        format!("{minutes}m")
-    };
+    parse_inspect_output(&inspect_string, Utc::now())
+        .map_err(|e| DsdError::parse("inspect", format!("{e:#}")))
+}
+
+/// Output of a docker command about several containers, which still reports on the others when
+/// some of them are gone
+fn skip_missing_containers(
+    command: &str,
+    output: std::io::Result<std::process::Output>,
+) -> Result<Vec<u8>, DsdError> {
+    let output = output.map_err(|e| DsdError::from_spawn(command, e))?;
+
+    if !output.status.success() {
+        let error = DsdError::from_docker(
+            command,
+            output.status.code(),
+            &String::from_utf8_lossy(&output.stderr),
+        );
+        if !matches!(error, DsdError::ContainerNotFound(_)) {
+            return Err(error);
+        }
+    }
+
+    Ok(output.stdout)
+}
 
-    Ok(uptime)
+/// Calculate the number of seconds a container has been up
+pub fn calc_uptime_seconds(start_time: &str) -> Result<i64, DsdError> {
+    uptime_seconds(start_time, Utc::now()).map_err(|e| DsdError::parse("inspect", format!("{e:#}")))
 }
@@ -0,0 +1,87 @@
+use crate::commands::DOCKER;
+use crate::config::{VerifyConfig, VerifyTool};
+use crate::image::image_matches;
+use crate::utils::UpdateResult;
+use anyhow::Context;
+use std::process::Command;
+
+const COSIGN: &str = "cosign";
+const NOTATION: &str = "notation";
+
+/// Determine if an image should be verified according to the config
+pub fn should_verify(config: &VerifyConfig, image: &str) -> bool {
+    config.images.is_empty()
+        || config
+            .images
+            .iter()
+            .any(|pattern| image_matches(pattern, image))
+}
+
+/// Verifies the signature of a pulled image by its digest
+pub fn verify_image(config: &VerifyConfig, result: &UpdateResult) -> anyhow::Result<()> {
+    // verify the exact pulled content rather than a tag that can move
+    let reference = result
+        .digest
+        .as_deref()
+        .with_context(|| format!("No repo digest to verify for {}", result.image))?;
+
+    let mut command = match config.tool {
+        VerifyTool::Cosign => {
+            let mut command = Command::new(COSIGN);
+            command.arg("verify");
+
+            if let Some(key) = &config.key {
+                command.args(["--key", key]);
+            }
+            if let Some(identity) = &config.certificate_identity {
+                command.args(["--certificate-identity", identity]);
+            }
+            if let Some(issuer) = &config.certificate_oidc_issuer {
+                command.args(["--certificate-oidc-issuer", issuer]);
+            }
+
+            command
+        }
+        VerifyTool::Notation => {
+            let mut command = Command::new(NOTATION);
+            command.arg("verify");
+            command
+        }
+    };
+
+    let output = command.arg(reference).output().context(format!(
+        "Failed to run signature verification for {reference}"
+    ))?;
+
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        let error = error.lines().last().unwrap_or_default().trim();
+        anyhow::bail!("Signature verification failed for {reference}: {error}");
+    }
+
+    Ok(())
+}
+
+/// Points the reference of a rejected image back at the image it replaced, or removes the pulled
+/// image when there was none, so nothing created from the reference later runs it
+pub fn restore_previous_image(result: &UpdateResult) -> anyhow::Result<()> {
+    let mut command = Command::new(DOCKER);
+    match &result.previous_id {
+        Some(previous_id) => command.args(["tag", previous_id, &result.image]),
+        None => command.args(["rmi", &result.image]),
+    };
+
+    let output = command
+        .output()
+        .with_context(|| format!("Failed to restore the previous image of {}", result.image))?;
+
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to restore the previous image of {}: {}",
+            result.image,
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    Ok(())
+}
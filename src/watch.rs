@@ -0,0 +1,226 @@
+use crate::notify::Severity;
+use crate::utils::InspectData;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant};
+
+/// Container failures detected while watching
+#[derive(Debug, Clone, PartialEq)]
+pub enum WatchEvent {
+    /// Container stopped without dsd-util stopping it
+    Exited { container: String, status: String },
+    /// Container healthcheck started failing
+    Unhealthy { container: String },
+    /// Container restarted too often within the restart window
+    Restarting {
+        container: String,
+        restarts: usize,
+        window: Duration,
+    },
+    /// Container keeps changing state, so its other events are held back until it stabilizes
+    Flapping {
+        container: String,
+        changes: usize,
+        window: Duration,
+    },
+    /// Flapping container kept its state for a whole flap window
+    Stabilized { container: String },
+}
+
+impl WatchEvent {
+    /// Container the event belongs to
+    pub fn container(&self) -> &str {
+        match self {
+            WatchEvent::Exited { container, .. }
+            | WatchEvent::Unhealthy { container }
+            | WatchEvent::Restarting { container, .. }
+            | WatchEvent::Flapping { container, .. }
+            | WatchEvent::Stabilized { container } => container,
+        }
+    }
+
+    /// Severity of the notification sent for the event
+    pub fn severity(&self) -> Severity {
+        match self {
+            WatchEvent::Exited { .. } | WatchEvent::Restarting { .. } => Severity::Critical,
+            WatchEvent::Unhealthy { .. } | WatchEvent::Flapping { .. } => Severity::Warning,
+            WatchEvent::Stabilized { .. } => Severity::Info,
+        }
+    }
+}
+
+impl fmt::Display for WatchEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WatchEvent::Exited { container, status } => {
+                write!(f, "{container} stopped unexpectedly ({status})")
+            }
+            WatchEvent::Unhealthy { container } => write!(f, "{container} is unhealthy"),
+            WatchEvent::Restarting {
+                container,
+                restarts,
+                window,
+            } => write!(
+                f,
+                "{container} restarted {restarts} times in {} minutes",
+                window.as_secs() / 60
+            ),
+            WatchEvent::Flapping {
+                container,
+                changes,
+                window,
+            } => write!(
+                f,
+                "{container} is flapping, it changed state {changes} times in {} minutes",
+                window.as_secs() / 60
+            ),
+            WatchEvent::Stabilized { container } => {
+                write!(f, "{container} stopped flapping")
+            }
+        }
+    }
+}
+
+/// Last seen state of a watched container
+#[derive(Debug)]
+struct ContainerState {
+    status: String,
+    health: String,
+    restart_count: u64,
+    restarts: VecDeque<Instant>,
+    /// Status or health changes within the flap window
+    changes: VecDeque<Instant>,
+    flapping: bool,
+}
+
+/// Tracks container state between checks and reports failures
+#[derive(Debug)]
+pub struct Watcher {
+    max_restarts: usize,
+    restart_window: Duration,
+    /// Number of state changes within the flap window that make a container flap, 0 to never
+    flap_threshold: usize,
+    flap_window: Duration,
+    containers: HashMap<String, ContainerState>,
+}
+
+impl Watcher {
+    pub fn new(max_restarts: usize, restart_window: Duration) -> Self {
+        Watcher {
+            max_restarts,
+            restart_window,
+            flap_threshold: 0,
+            flap_window: Duration::ZERO,
+            containers: HashMap::new(),
+        }
+    }
+
+    /// Reports containers changing state `threshold` times within `window` as flapping once,
+    /// instead of every change
+    pub fn flap_detection(mut self, threshold: usize, window: Duration) -> Self {
+        self.flap_threshold = threshold;
+        self.flap_window = window;
+        self
+    }
+
+    /// Compares freshly inspected containers against the last check.
+    ///
+    /// Containers seen for the first time only establish a baseline.
+    pub fn check(&mut self, inspected: &[InspectData]) -> Vec<WatchEvent> {
+        let now = Instant::now();
+        let mut events = vec![];
+
+        for inspect in inspected {
+            let Some(state) = self.containers.get_mut(&inspect.container_name) else {
+                self.containers.insert(
+                    inspect.container_name.to_string(),
+                    ContainerState {
+                        status: inspect.status.to_string(),
+                        health: inspect.health.to_string(),
+                        restart_count: inspect.restart_count,
+                        restarts: VecDeque::new(),
+                        changes: VecDeque::new(),
+                        flapping: false,
+                    },
+                );
+                continue;
+            };
+
+            // a recreated container starts counting from zero again
+            let new_restarts = inspect.restart_count.saturating_sub(state.restart_count);
+            for _ in 0..new_restarts {
+                state.restarts.push_back(now);
+            }
+            while state
+                .restarts
+                .front()
+                .is_some_and(|restart| now.duration_since(*restart) > self.restart_window)
+            {
+                state.restarts.pop_front();
+            }
+
+            // a restart changes state even when it happened between two checks
+            let is_changed = state.status != inspect.status || state.health != inspect.health;
+            for _ in 0..new_restarts.max(u64::from(is_changed)) {
+                state.changes.push_back(now);
+            }
+            while state
+                .changes
+                .front()
+                .is_some_and(|change| now.duration_since(*change) > self.flap_window)
+            {
+                state.changes.pop_front();
+            }
+
+            if self.flap_threshold > 0 {
+                if !state.flapping && state.changes.len() >= self.flap_threshold {
+                    state.flapping = true;
+                    events.push(WatchEvent::Flapping {
+                        container: inspect.container_name.to_string(),
+                        changes: state.changes.len(),
+                        window: self.flap_window,
+                    });
+                } else if state.flapping && state.changes.is_empty() {
+                    state.flapping = false;
+                    events.push(WatchEvent::Stabilized {
+                        container: inspect.container_name.to_string(),
+                    });
+                }
+            }
+
+            if !state.flapping {
+                let is_stopped = matches!(inspect.status.as_str(), "exited" | "dead");
+                if is_stopped && state.status != inspect.status {
+                    events.push(WatchEvent::Exited {
+                        container: inspect.container_name.to_string(),
+                        status: inspect.status.to_string(),
+                    });
+                }
+
+                if inspect.health == "unhealthy" && state.health != "unhealthy" {
+                    events.push(WatchEvent::Unhealthy {
+                        container: inspect.container_name.to_string(),
+                    });
+                }
+            }
+
+            if state.restarts.len() >= self.max_restarts && new_restarts > 0 {
+                if !state.flapping {
+                    events.push(WatchEvent::Restarting {
+                        container: inspect.container_name.to_string(),
+                        restarts: state.restarts.len(),
+                        window: self.restart_window,
+                    });
+                }
+                // only notify again once the threshold is reached anew
+                state.restarts.clear();
+            }
+
+            state.status = inspect.status.to_string();
+            state.health = inspect.health.to_string();
+            state.restart_count = inspect.restart_count;
+        }
+
+        events
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::Context;
+use chrono::{NaiveTime, Timelike};
+use std::fmt;
+use std::time::Duration;
+
+const SECONDS_PER_DAY: u32 = 24 * 60 * 60;
+
+/// Daily window of local time such as 01:00-05:00, which may run past midnight
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimeWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl TimeWindow {
+    /// Parses a window given as HH:MM-HH:MM
+    pub fn parse(window: &str) -> anyhow::Result<Self> {
+        let (start, end) = window
+            .split_once('-')
+            .with_context(|| format!("Invalid time window, expected HH:MM-HH:MM: {window}"))?;
+        let time = |time: &str| {
+            NaiveTime::parse_from_str(time.trim(), "%H:%M")
+                .with_context(|| format!("Invalid time in window {window}: {}", time.trim()))
+        };
+
+        let (start, end) = (time(start)?, time(end)?);
+        if start == end {
+            anyhow::bail!("Time window is empty: {window}");
+        }
+
+        Ok(TimeWindow { start, end })
+    }
+
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            self.start <= time || time < self.end
+        }
+    }
+
+    /// Time until the window opens next, zero while it is open
+    pub fn until_open(&self, time: NaiveTime) -> Duration {
+        if self.contains(time) {
+            return Duration::ZERO;
+        }
+
+        seconds_between(time, self.start)
+    }
+
+    /// Time until the window closes, zero while it is closed
+    pub fn until_close(&self, time: NaiveTime) -> Duration {
+        if !self.contains(time) {
+            return Duration::ZERO;
+        }
+
+        seconds_between(time, self.end)
+    }
+}
+
+impl fmt::Display for TimeWindow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{}",
+            self.start.format("%H:%M"),
+            self.end.format("%H:%M")
+        )
+    }
+}
+
+/// Time from one time of day to the next occurrence of another, to the second
+fn seconds_between(from: NaiveTime, to: NaiveTime) -> Duration {
+    let seconds = (to.num_seconds_from_midnight() + SECONDS_PER_DAY
+        - from.num_seconds_from_midnight())
+        % SECONDS_PER_DAY;
+
+    Duration::from_secs(seconds.into())
+}
+
+/// Current local time of day
+pub fn local_time() -> NaiveTime {
+    chrono::Local::now().time()
+}
@@ -0,0 +1,37 @@
+use dsd_util::activation::listen_fds;
+use std::collections::HashMap;
+
+fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars = vars
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect::<HashMap<String, String>>();
+
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn sockets_passed_by_systemd() {
+    assert_eq!(
+        listen_fds(env(&[("LISTEN_PID", "4242"), ("LISTEN_FDS", "1")]), 4242),
+        1
+    );
+    assert_eq!(
+        listen_fds(env(&[("LISTEN_PID", "4242"), ("LISTEN_FDS", "2")]), 4242),
+        2
+    );
+}
+
+#[test]
+fn sockets_for_other_processes_are_ignored() {
+    assert_eq!(
+        listen_fds(env(&[("LISTEN_PID", "17"), ("LISTEN_FDS", "1")]), 4242),
+        0
+    );
+    assert_eq!(listen_fds(env(&[("LISTEN_FDS", "1")]), 4242), 0);
+    assert_eq!(listen_fds(env(&[]), 4242), 0);
+    assert_eq!(
+        listen_fds(env(&[("LISTEN_PID", "4242"), ("LISTEN_FDS", "x")]), 4242),
+        0
+    );
+}
@@ -0,0 +1,87 @@
+use chrono::{DateTime, Utc};
+use dsd_util::backup::{
+    archive_name, containers_using, parse_archive_name, pick_archives, stack_volumes,
+};
+use dsd_util::prune::DiskContainer;
+
+fn time(timestamp: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .expect("timestamp is valid")
+        .with_timezone(&Utc)
+}
+
+fn container(name: &str, stack: &str, status: &str, volumes: &[&str]) -> DiskContainer {
+    DiskContainer {
+        id: format!("{name}-id"),
+        name: name.to_string(),
+        stack: Some(stack.to_string()),
+        image: "postgres:16".to_string(),
+        image_id: "sha256:postgres".to_string(),
+        status: status.to_string(),
+        log_path: String::new(),
+        volumes: volumes.iter().map(|volume| volume.to_string()).collect(),
+    }
+}
+
+fn strings(values: &[&str]) -> Vec<String> {
+    values.iter().map(|value| value.to_string()).collect()
+}
+
+#[test]
+fn archive_names_round_trip() {
+    let name = archive_name("web_db-data", time("2025-06-01T12:00:00Z"));
+    assert_eq!(name, "web_db-data-20250601T120000Z.tar.gz");
+    assert_eq!(
+        parse_archive_name(&name),
+        Some(("web_db-data".to_string(), time("2025-06-01T12:00:00Z")))
+    );
+
+    assert_eq!(parse_archive_name("web_db-data.tar.gz"), None);
+    assert_eq!(parse_archive_name("web_db-data-yesterday.tar.gz"), None);
+    assert_eq!(parse_archive_name("notes.txt"), None);
+}
+
+#[test]
+fn volumes_of_a_stack() {
+    let containers = [
+        container("web-db-1", "web", "running", &["web_db", "web_shared"]),
+        container("web-app-1", "web", "exited", &["web_shared", "web_uploads"]),
+        container("mail-1", "mail", "running", &["mail_data"]),
+    ];
+
+    assert_eq!(
+        stack_volumes("web", &containers),
+        strings(&["web_db", "web_shared", "web_uploads"])
+    );
+    assert!(stack_volumes("blog", &containers).is_empty());
+
+    // stopped containers are left alone
+    assert_eq!(
+        containers_using("web", &containers, &strings(&["web_shared"])),
+        strings(&["web-db-1"])
+    );
+    assert!(containers_using("web", &containers, &strings(&["web_uploads"])).is_empty());
+}
+
+#[test]
+fn picking_archives() {
+    let file_names = strings(&[
+        "web_db-20250601T120000Z.tar.gz",
+        "web_db-20250602T120000Z.tar.gz",
+        "web_uploads-20250601T120000Z.tar.gz",
+        "mail_data-20250603T120000Z.tar.gz",
+        "README.md",
+    ]);
+    let volumes = strings(&["web_db", "web_uploads"]);
+
+    let latest = pick_archives(&file_names, &volumes, None);
+    assert_eq!(latest.len(), 2);
+    assert_eq!(latest["web_db"], "web_db-20250602T120000Z.tar.gz");
+    assert_eq!(latest["web_uploads"], "web_uploads-20250601T120000Z.tar.gz");
+
+    let earlier = pick_archives(&file_names, &volumes, Some(time("2025-06-01T12:00:00Z")));
+    assert_eq!(earlier["web_db"], "web_db-20250601T120000Z.tar.gz");
+
+    let missing = pick_archives(&file_names, &volumes, Some(time("2025-06-02T12:00:00Z")));
+    assert_eq!(missing.keys().collect::<Vec<_>>(), ["web_db"]);
+}
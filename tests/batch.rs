@@ -0,0 +1,64 @@
+use dsd_util::batch::{exit_code, BatchResults, BatchStatus};
+
+fn results(outcomes: &[(&str, bool)]) -> BatchResults {
+    let mut results = BatchResults::default();
+    for (container, ok) in outcomes {
+        if *ok {
+            results.succeeded(container);
+        } else {
+            results.failed(container, "No such container");
+        }
+    }
+    results
+}
+
+#[test]
+fn status_of_batch() {
+    assert_eq!(results(&[]).status(), BatchStatus::Succeeded);
+    assert_eq!(
+        results(&[("web-app-1", true), ("web-db-1", true)]).status(),
+        BatchStatus::Succeeded
+    );
+    assert_eq!(
+        results(&[("web-app-1", true), ("web-db-1", false)]).status(),
+        BatchStatus::Partial
+    );
+    assert_eq!(
+        results(&[("web-app-1", false), ("web-db-1", false)]).status(),
+        BatchStatus::Failed
+    );
+}
+
+#[test]
+fn lists_failed_containers_in_order() {
+    let results = results(&[("b", false), ("a", true), ("c", false)]);
+
+    assert_eq!(results.failed_containers(), vec!["b", "c"]);
+    assert_eq!(results.succeeded_count(), 1);
+}
+
+#[test]
+fn exit_codes_tell_partial_failures_apart() {
+    assert!(results(&[("a", true)])
+        .into_result("Failed".to_string())
+        .is_ok());
+
+    let partial = anyhow::Error::new(
+        results(&[("a", true), ("b", false)])
+            .into_result("Failed to restart: b".to_string())
+            .unwrap_err(),
+    );
+    assert_eq!(exit_code(&partial), 2);
+    assert_eq!(partial.to_string(), "Failed to restart: b");
+
+    let all = anyhow::Error::new(
+        results(&[("a", false)])
+            .into_result("Failed to restart: a".to_string())
+            .unwrap_err(),
+    );
+    assert_eq!(exit_code(&all), 1);
+
+    assert_eq!(exit_code(&anyhow::anyhow!("No containers running")), 1);
+    // context added on the way up keeps the exit code
+    assert_eq!(exit_code(&partial.context("Update failed")), 2);
+}
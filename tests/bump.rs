@@ -0,0 +1,69 @@
+use dsd_util::bump::{bump_image, commit_message, retag};
+
+const COMPOSE: &str = "\
+# web stack
+name: web
+
+services:
+  # reverse proxy
+  proxy:
+    image: nginx:1.25.2   # pinned until the config migrates
+    ports:
+      - \"80:80\"
+
+  app:
+    image: \"registry.local:5000/acme/app:2.1@sha256:0123\"
+    depends_on:
+      - proxy
+    environment:
+      image: not-this-one
+
+  db:
+    image: ${DB_IMAGE:-postgres:16}
+";
+
+#[test]
+fn only_the_image_line_changes() {
+    let (edited, bump) = bump_image(COMPOSE, "proxy", "1.25.3").unwrap().unwrap();
+
+    assert_eq!(
+        edited,
+        COMPOSE.replace(
+            "image: nginx:1.25.2   # pinned",
+            "image: nginx:1.25.3   # pinned"
+        )
+    );
+    assert_eq!(bump.old_image, "nginx:1.25.2");
+    assert_eq!(bump.new_image, "nginx:1.25.3");
+    assert_eq!(bump.line, 7);
+    assert_eq!(
+        commit_message("web", &bump),
+        "chore(web): bump proxy to nginx:1.25.3\n\nUpdate the image of proxy from nginx:1.25.2 to nginx:1.25.3."
+    );
+}
+
+#[test]
+fn quotes_and_registry_ports_are_kept() {
+    let (edited, bump) = bump_image(COMPOSE, "app", "2.2").unwrap().unwrap();
+
+    assert!(edited.contains("    image: \"registry.local:5000/acme/app:2.2\"\n"));
+    assert!(edited.contains("      image: not-this-one\n"));
+    assert_eq!(bump.new_image, "registry.local:5000/acme/app:2.2");
+}
+
+#[test]
+fn images_that_cannot_be_bumped() {
+    assert!(bump_image(COMPOSE, "db", "17").is_err());
+    assert!(bump_image(COMPOSE, "proxy", "not a tag").is_err());
+    assert_eq!(bump_image(COMPOSE, "cache", "7").unwrap(), None);
+    assert_eq!(bump_image("services: {}\n", "proxy", "1").unwrap(), None);
+}
+
+#[test]
+fn retagging() {
+    assert_eq!(retag("nginx", "1.27").unwrap(), "nginx:1.27");
+    assert_eq!(
+        retag("localhost:5000/app@sha256:abc", "2").unwrap(),
+        "localhost:5000/app:2"
+    );
+}
@@ -0,0 +1,57 @@
+use chrono::{TimeZone, Utc};
+use dsd_util::changelog::{append_changelog, ChangelogEntry, ImageChange, ImageMetadata};
+
+fn metadata(id: &str, version: &str, size: u64) -> ImageMetadata {
+    ImageMetadata {
+        id: id.to_string(),
+        created: Some("2026-10-15T12:00:00Z".to_string()),
+        version: Some(version.to_string()),
+        revision: None,
+        size,
+    }
+}
+
+#[test]
+fn size_delta() {
+    let mut change = ImageChange {
+        image: "ghcr.io/acme/app:2".to_string(),
+        previous: Some(metadata("sha256:old", "1.4.2", 1000)),
+        current: metadata("sha256:new", "1.5.0", 800),
+    };
+
+    assert_eq!(change.size_delta(), Some(-200));
+
+    change.previous = None;
+    assert_eq!(change.size_delta(), None);
+}
+
+#[test]
+fn appends_json_lines() {
+    let path =
+        std::env::temp_dir().join(format!("dsd-util-changelog-{}.jsonl", std::process::id()));
+    let entry = ChangelogEntry {
+        updated_at: Utc.with_ymd_and_hms(2026, 10, 16, 4, 0, 0).unwrap(),
+        changes: vec![ImageChange {
+            image: "ghcr.io/acme/app:2".to_string(),
+            previous: Some(metadata("sha256:old", "1.4.2", 1000)),
+            current: metadata("sha256:new", "1.5.0", 1200),
+        }],
+    };
+
+    append_changelog(&path, &entry).unwrap();
+    append_changelog(&path, &entry).unwrap();
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let lines = contents.lines().collect::<Vec<&str>>();
+    assert_eq!(lines.len(), 2);
+
+    let line: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+    assert_eq!(line["updated_at"], "2026-10-16T04:00:00Z");
+    assert_eq!(line["changes"][0]["previous"]["version"], "1.4.2");
+    assert_eq!(line["changes"][0]["current"]["version"], "1.5.0");
+    assert_eq!(
+        line["changes"][0]["current"]["revision"],
+        serde_json::Value::Null
+    );
+}
@@ -0,0 +1,27 @@
+use dsd_util::config::{cache_dir, create_private_dir, set_state_dir, state_dir};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+
+#[test]
+fn state_dir_override_holds_state_and_cache() {
+    set_state_dir(PathBuf::from("/srv/dsd-util/nas"));
+
+    assert_eq!(state_dir().unwrap(), PathBuf::from("/srv/dsd-util/nas"));
+    assert_eq!(
+        cache_dir().unwrap(),
+        PathBuf::from("/srv/dsd-util/nas/cache")
+    );
+}
+
+#[test]
+fn private_dirs_are_new_and_closed_to_others() {
+    let first = create_private_dir("test").unwrap();
+    let second = create_private_dir("test").unwrap();
+
+    assert_ne!(first, second);
+    let mode = std::fs::metadata(&first).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o700);
+
+    std::fs::remove_dir(first).unwrap();
+    std::fs::remove_dir(second).unwrap();
+}
@@ -0,0 +1,141 @@
+use dsd_util::conflicts::{bind_mounts, declared_services, find_project_conflicts, Conflict};
+use dsd_util::docker::Container;
+use dsd_util::ports::PortMapping;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+fn container(name: &str, stack: Option<&str>, service: Option<&str>) -> Container {
+    Container {
+        id: name.to_string(),
+        name: name.to_string(),
+        image: "nginx:latest".to_string(),
+        stack: stack.map(String::from),
+        service: service.map(String::from),
+    }
+}
+
+#[test]
+fn bind_mounts_are_resolved() {
+    let mounts = bind_mounts(
+        &json!([
+            "./data:/data",
+            "../shared/config:/config:ro,z",
+            "media:/media",
+            "/srv/backups:/backups",
+            { "type": "bind", "source": "/srv/www", "target": "/var/www", "read_only": true },
+            { "type": "volume", "source": "cache", "target": "/cache" }
+        ]),
+        Path::new("/opt/stacks/web"),
+    );
+
+    let summary = mounts
+        .iter()
+        .map(|mount| (mount.source.clone(), mount.read_only))
+        .collect::<Vec<(PathBuf, bool)>>();
+    assert_eq!(
+        summary,
+        vec![
+            (PathBuf::from("/opt/stacks/web/data"), false),
+            (PathBuf::from("/opt/stacks/shared/config"), true),
+            (PathBuf::from("/srv/backups"), false),
+            (PathBuf::from("/srv/www"), true),
+        ]
+    );
+}
+
+#[test]
+fn conflicts_across_projects() {
+    let mut services = declared_services(
+        "web",
+        Path::new("/opt/web"),
+        &json!({ "services": {
+            "proxy": {
+                "container_name": "proxy",
+                "ports": ["80:80", "127.0.0.1:8080:8080"],
+                "volumes": ["/srv/data:/data", "/var/run/docker.sock:/var/run/docker.sock"]
+            },
+            "app": { "ports": ["3000"], "volumes": ["/srv/www:/www:ro"] }
+        }}),
+    );
+    services.extend(declared_services(
+        "blog",
+        Path::new("/opt/blog"),
+        &json!({ "services": {
+            "ghost": {
+                "container_name": "proxy",
+                "ports": [{ "published": 80, "target": 2368 }, "8080:80", "9000:9000"],
+                "volumes": [
+                    "/srv/data/ghost:/var/lib/ghost",
+                    "/srv/www:/www:ro",
+                    "/var/run/docker.sock:/var/run/docker.sock"
+                ]
+            }
+        }}),
+    ));
+
+    let existing = [
+        container("proxy", Some("web"), Some("proxy")),
+        container("portainer", None, None),
+    ];
+    let existing_ports = [PortMapping {
+        host_ip: None,
+        host_port: "9000".to_string(),
+        container_port: "9000/tcp".to_string(),
+        container: "portainer".to_string(),
+        service: None,
+        stack: None,
+        networks: vec![],
+    }];
+
+    assert_eq!(
+        find_project_conflicts(&services, &existing, &existing_ports),
+        vec![
+            Conflict::Port {
+                port: "80/tcp".to_string(),
+                containers: vec!["blog/ghost".to_string(), "web/proxy".to_string()],
+            },
+            Conflict::Port {
+                port: "8080/tcp".to_string(),
+                containers: vec!["blog/ghost".to_string(), "web/proxy".to_string()],
+            },
+            Conflict::Port {
+                port: "9000/tcp".to_string(),
+                containers: vec!["blog/ghost".to_string(), "container portainer".to_string()],
+            },
+            Conflict::ContainerName {
+                name: "proxy".to_string(),
+                claimants: vec!["blog/ghost".to_string(), "web/proxy".to_string()],
+            },
+            Conflict::BindMount {
+                first: ("web/proxy".to_string(), PathBuf::from("/srv/data")),
+                second: ("blog/ghost".to_string(), PathBuf::from("/srv/data/ghost")),
+            },
+        ]
+    );
+}
+
+#[test]
+fn containers_in_the_way() {
+    let services = declared_services(
+        "tools",
+        Path::new("/opt/tools"),
+        &json!({ "services": { "portainer": { "container_name": "portainer" } } }),
+    );
+
+    let conflicts = find_project_conflicts(&services, &[container("portainer", None, None)], &[]);
+    assert_eq!(
+        conflicts
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<String>>(),
+        vec!["container name portainer is claimed by container portainer, tools/portainer"]
+    );
+
+    // the container compose created for the service itself is fine
+    let conflicts = find_project_conflicts(
+        &services,
+        &[container("portainer", Some("tools"), Some("portainer"))],
+        &[],
+    );
+    assert!(conflicts.is_empty());
+}
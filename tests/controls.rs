@@ -0,0 +1,96 @@
+use dsd_util::controls::{typed_container, ControlAction, KeyControls, Prompt};
+
+fn type_keys(controls: &mut KeyControls, keys: &str) -> ControlAction {
+    let mut action = ControlAction::None;
+    for key in keys.bytes() {
+        action = controls.key(key);
+    }
+    action
+}
+
+#[test]
+fn pause_and_resume() {
+    let mut controls = KeyControls::default();
+    assert!(!controls.holds_output());
+
+    assert_eq!(controls.key(b' '), ControlAction::Pause);
+    assert!(controls.is_paused());
+    assert!(controls.holds_output());
+
+    assert_eq!(controls.key(b' '), ControlAction::Resume);
+    assert!(!controls.holds_output());
+    assert_eq!(controls.key(b'-'), ControlAction::None);
+}
+
+#[test]
+fn live_filter() {
+    let mut controls = KeyControls::default();
+    assert_eq!(
+        controls.key(b'/'),
+        ControlAction::Prompt(Prompt::Filter(String::new()))
+    );
+    // lines wait while typing, so they do not overwrite the prompt
+    assert!(controls.holds_output());
+
+    assert_eq!(
+        type_keys(&mut controls, "error x"),
+        ControlAction::Prompt(Prompt::Filter("error x".to_string()))
+    );
+    assert_eq!(
+        controls.key(0x7f),
+        ControlAction::Prompt(Prompt::Filter("error ".to_string()))
+    );
+    assert_eq!(Prompt::Filter("error".to_string()).display(), "/error");
+    assert_eq!(
+        controls.key(b'\n'),
+        ControlAction::Filter(Some("error ".to_string()))
+    );
+    assert!(!controls.holds_output());
+
+    assert_eq!(type_keys(&mut controls, "/\r"), ControlAction::Filter(None));
+}
+
+#[test]
+fn mute_prompt() {
+    let mut controls = KeyControls::default();
+    assert_eq!(
+        type_keys(&mut controls, "web"),
+        ControlAction::Prompt(Prompt::Mute("web".to_string()))
+    );
+    assert_eq!(
+        controls.key(b'\r'),
+        ControlAction::ToggleMute("web".to_string())
+    );
+
+    type_keys(&mut controls, "db");
+    assert_eq!(controls.key(0x1b), ControlAction::Cancel);
+    assert!(!controls.holds_output());
+}
+
+#[test]
+fn prompt_keeps_pause() {
+    let mut controls = KeyControls::default();
+    controls.key(b' ');
+    // space is part of the typed text rather than resuming
+    assert_eq!(
+        type_keys(&mut controls, "/a b"),
+        ControlAction::Prompt(Prompt::Filter("a b".to_string()))
+    );
+    controls.key(b'\n');
+    assert!(controls.is_paused());
+}
+
+#[test]
+fn typed_containers() {
+    let containers = vec![
+        "web".to_string(),
+        "web-worker".to_string(),
+        "postgres".to_string(),
+    ];
+
+    assert_eq!(typed_container("web", &containers), Some("web"));
+    assert_eq!(typed_container("pg", &containers), Some("postgres"));
+    assert_eq!(typed_container("WORK", &containers), Some("web-worker"));
+    assert_eq!(typed_container("w", &containers), None);
+    assert_eq!(typed_container("redis", &containers), None);
+}
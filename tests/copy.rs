@@ -0,0 +1,74 @@
+use dsd_util::copy::{resolve_service, service_directory, CopyLocation};
+use std::path::{Path, PathBuf};
+
+fn services() -> Vec<(String, String)> {
+    [
+        ("web-app-1", "app"),
+        ("web-worker-1", "worker"),
+        ("web-worker-2", "worker"),
+    ]
+    .iter()
+    .map(|(container, service)| (container.to_string(), service.to_string()))
+    .collect()
+}
+
+#[test]
+fn locations() {
+    assert_eq!(
+        CopyLocation::parse("web/app:/etc/app.conf"),
+        CopyLocation::Service {
+            stack: "web".to_string(),
+            service: "app".to_string(),
+            path: "/etc/app.conf".to_string(),
+        }
+    );
+    assert_eq!(
+        CopyLocation::parse("web-app-1:/data"),
+        CopyLocation::Container {
+            container: "web-app-1".to_string(),
+            path: "/data".to_string(),
+        }
+    );
+    assert_eq!(
+        CopyLocation::parse("backup/app.conf"),
+        CopyLocation::Host(PathBuf::from("backup/app.conf"))
+    );
+
+    // host paths containing a colon
+    assert!(CopyLocation::parse("./notes:old").is_host());
+    assert!(CopyLocation::parse("/tmp/a:b").is_host());
+
+    assert_eq!(
+        CopyLocation::parse("web/app:/data").to_string(),
+        "web/app:/data"
+    );
+}
+
+#[test]
+fn services_resolve_to_containers() {
+    let services = services();
+
+    assert_eq!(
+        resolve_service("web", "app", &services).unwrap(),
+        "web-app-1"
+    );
+    assert!(resolve_service("web", "db", &services).is_err());
+
+    let error = resolve_service("web", "worker", &services).unwrap_err();
+    assert!(error.to_string().contains("web-worker-1, web-worker-2"));
+}
+
+#[test]
+fn directories_per_service() {
+    let services = services();
+    let destination = Path::new("configs");
+
+    assert_eq!(
+        service_directory(destination, "web-app-1", "app", &services),
+        Path::new("configs/app")
+    );
+    assert_eq!(
+        service_directory(destination, "web-worker-2", "worker", &services),
+        Path::new("configs/web-worker-2")
+    );
+}
@@ -0,0 +1,73 @@
+use chrono::NaiveDateTime;
+use dsd_util::cron::parse_cron;
+
+fn time(time: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap()
+}
+
+fn next(schedule: &str, after: &str) -> Option<NaiveDateTime> {
+    parse_cron(schedule).unwrap().next_after(time(after))
+}
+
+#[test]
+fn parsing() {
+    assert_eq!(parse_cron(" 0 4 * * * ").unwrap().to_string(), "0 4 * * *");
+    assert!(parse_cron("*/15 8-18 * * 1-5").is_ok());
+    assert!(parse_cron("0 0 1,15 * 7").is_ok());
+    assert!(parse_cron("@daily").is_ok());
+
+    assert!(parse_cron("0 4 * *").is_err());
+    assert!(parse_cron("60 4 * * *").is_err());
+    assert!(parse_cron("0 4 0 * *").is_err());
+    assert!(parse_cron("0 18-8 * * *").is_err());
+    assert!(parse_cron("*/0 * * * *").is_err());
+    assert!(parse_cron("@sometimes").is_err());
+}
+
+#[test]
+fn next_runs() {
+    assert_eq!(
+        next("0 4 * * *", "2026-10-16 03:59"),
+        Some(time("2026-10-16 04:00"))
+    );
+    // never the time it is asked at, so a run that finished within its minute does not repeat
+    assert_eq!(
+        next("0 4 * * *", "2026-10-16 04:00"),
+        Some(time("2026-10-17 04:00"))
+    );
+    assert_eq!(
+        next("*/15 8-18 * * 1-5", "2026-10-16 18:50"),
+        Some(time("2026-10-19 08:00"))
+    );
+    assert_eq!(
+        next("30 2 * * *", "2026-12-31 23:00"),
+        Some(time("2027-01-01 02:30"))
+    );
+    assert_eq!(
+        next("0 0 29 2 *", "2026-03-01 00:00"),
+        Some(time("2028-02-29 00:00"))
+    );
+    assert_eq!(
+        next("@weekly", "2026-10-16 12:00"),
+        Some(time("2026-10-18 00:00"))
+    );
+    assert_eq!(next("0 0 30 2 *", "2026-01-01 00:00"), None);
+}
+
+#[test]
+fn days_of_month_and_week() {
+    // with both given, either one matching is enough, as in cron
+    assert_eq!(
+        next("0 0 1 * 1", "2026-10-16 12:00"),
+        Some(time("2026-10-19 00:00"))
+    );
+    assert_eq!(
+        next("0 0 1 * 1", "2026-10-26 12:00"),
+        Some(time("2026-11-01 00:00"))
+    );
+    // Sunday may be given as 7
+    assert_eq!(
+        next("0 12 * * 7", "2026-10-16 12:00"),
+        Some(time("2026-10-18 12:00"))
+    );
+}
@@ -0,0 +1,350 @@
+use dsd_util::config::{DaemonConfig, HookConfig, ReportConfig};
+use dsd_util::daemon::{
+    action_phase, allowed_services, authorize, authorize_status, hook_windows, is_timeout,
+    read_request, report_window, sign, status_payload, verify_signature, Phase,
+};
+use dsd_util::prepull::{add_pulled, remove_applied, PulledState};
+use dsd_util::queue::{ActionQueue, Enqueued, Trigger};
+use std::collections::{BTreeMap, BTreeSet};
+
+const SECRET: &str = "It's a Secret to Everybody";
+
+fn config() -> DaemonConfig {
+    DaemonConfig {
+        secret: Some(SECRET.to_string()),
+        hooks: BTreeMap::from([(
+            "web".to_string(),
+            HookConfig {
+                services: vec!["app".to_string(), "worker".to_string()],
+                interval: None,
+                ..Default::default()
+            },
+        )]),
+        report: None,
+        user: None,
+    }
+}
+
+fn request(path: &str, body: &str, signature: &str) -> String {
+    format!(
+        "POST {path} HTTP/1.1\r\nHost: localhost\r\nX-Hub-Signature-256: {signature}\r\nContent-Length: {}\r\n\r\n{body}",
+        body.len()
+    )
+}
+
+#[test]
+fn signatures() {
+    // example from the GitHub webhook documentation
+    let signature = "sha256=757107ea0eb2509fc211221cce984b8a37570b6d7586c22c46f4379c8b043e17";
+    assert_eq!(sign(SECRET, b"Hello, World!"), signature);
+    assert!(verify_signature(SECRET, b"Hello, World!", signature));
+
+    assert!(!verify_signature(SECRET, b"Hello, World?", signature));
+    assert!(!verify_signature(
+        "other secret",
+        b"Hello, World!",
+        signature
+    ));
+    assert!(!verify_signature(SECRET, b"Hello, World!", &signature[7..]));
+    assert!(!verify_signature(SECRET, b"Hello, World!", "sha256=zz"));
+    assert!(!verify_signature(SECRET, b"Hello, World!", ""));
+}
+
+#[test]
+fn allow_lists() {
+    let config = config();
+    let services = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(
+        allowed_services(&config, "web", &services(&["worker", "app", "worker"])).unwrap(),
+        services(&["app", "worker"])
+    );
+    assert!(allowed_services(&config, "web", &services(&["app", "db"])).is_err());
+    assert!(allowed_services(&config, "web", &[]).is_err());
+    assert!(allowed_services(&config, "media", &services(&["app"])).is_err());
+}
+
+#[test]
+fn authorization() {
+    let config = config();
+    let body = r#"{"services":["app"]}"#;
+
+    let signed = request("/hooks/web", body, &sign(SECRET, body.as_bytes()));
+    let parsed = read_request(&mut signed.as_bytes()).unwrap();
+    assert_eq!(parsed.body, body.as_bytes());
+    assert_eq!(
+        authorize(&parsed, &config, SECRET).unwrap(),
+        ("web".to_string(), vec!["app".to_string()], Trigger::Webhook)
+    );
+
+    let status = |raw: String| {
+        let parsed = read_request(&mut raw.as_bytes()).unwrap();
+        authorize(&parsed, &config, SECRET).unwrap_err().status
+    };
+
+    assert_eq!(
+        status(request("/hooks/web", body, "sha256=00")),
+        "401 Unauthorized"
+    );
+    assert_eq!(
+        status(request("/metrics", body, &sign(SECRET, body.as_bytes()))),
+        "404 Not Found"
+    );
+
+    let denied = r#"{"services":["db"]}"#;
+    assert_eq!(
+        status(request(
+            "/hooks/web",
+            denied,
+            &sign(SECRET, denied.as_bytes())
+        )),
+        "403 Forbidden"
+    );
+
+    let invalid = r#"{"service":"app"}"#;
+    assert_eq!(
+        status(request(
+            "/hooks/web",
+            invalid,
+            &sign(SECRET, invalid.as_bytes())
+        )),
+        "400 Bad Request"
+    );
+
+    assert!(read_request(
+        &mut "POST /hooks/web HTTP/1.1\r\nContent-Length: 999999\r\n\r\n".as_bytes()
+    )
+    .is_err());
+
+    let long_header = format!(
+        "GET /status HTTP/1.1\r\nX-Long: {}\r\n\r\n",
+        "a".repeat(10_000)
+    );
+    assert!(read_request(&mut long_header.as_bytes()).is_err());
+
+    let many_headers = format!(
+        "GET /status HTTP/1.1\r\n{}\r\n",
+        "X-Header: a\r\n".repeat(101)
+    );
+    assert!(read_request(&mut many_headers.as_bytes()).is_err());
+    let enough_headers = format!(
+        "GET /status HTTP/1.1\r\n{}\r\n",
+        "X-Header: a\r\n".repeat(100)
+    );
+    assert!(read_request(&mut enough_headers.as_bytes()).is_ok());
+}
+
+#[test]
+fn status_requires_signature() {
+    const NOW: u64 = 1_700_000_000;
+    let status = |timestamp: u64, signature: &str| {
+        let raw = format!(
+            "GET /status HTTP/1.1\r\nHost: localhost\r\nX-Dsd-Timestamp: {timestamp}\r\nX-Hub-Signature-256: {signature}\r\n\r\n"
+        );
+        authorize_status(&read_request(&mut raw.as_bytes()).unwrap(), SECRET, NOW)
+    };
+    let signed = |timestamp: u64| sign(SECRET, status_payload(timestamp).as_bytes());
+
+    assert!(status(NOW, &signed(NOW)).is_ok());
+    assert!(status(NOW - 60, &signed(NOW - 60)).is_ok());
+    assert_eq!(status(NOW, "").unwrap_err().status, "401 Unauthorized");
+    assert_eq!(
+        status(NOW, &sign("other secret", status_payload(NOW).as_bytes()))
+            .unwrap_err()
+            .status,
+        "401 Unauthorized"
+    );
+
+    // neither the bare path, an old timestamp nor a signature of another time gets in
+    assert!(status(NOW, &sign(SECRET, b"/status")).is_err());
+    assert!(status(NOW - 3600, &signed(NOW - 3600)).is_err());
+    assert!(status(NOW, &signed(NOW - 3600)).is_err());
+
+    let unsigned_time = format!(
+        "GET /status HTTP/1.1\r\nX-Hub-Signature-256: {}\r\n\r\n",
+        signed(NOW)
+    );
+    assert!(authorize_status(
+        &read_request(&mut unsigned_time.as_bytes()).unwrap(),
+        SECRET,
+        NOW
+    )
+    .is_err());
+}
+
+#[test]
+fn quiet_clients_time_out() {
+    let timed_out = anyhow::Error::new(std::io::Error::from(std::io::ErrorKind::WouldBlock))
+        .context("Failed to read request");
+    assert!(is_timeout(&timed_out));
+
+    let truncated =
+        read_request(&mut "POST /hooks/web HTTP/1.1\r\nContent-Length: 10\r\n\r\n{}".as_bytes())
+            .unwrap_err();
+    assert!(!is_timeout(&truncated));
+}
+
+#[test]
+fn queue() {
+    let queue = ActionQueue::default();
+    let services = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(
+        queue.enqueue("web", &services(&["app"]), Trigger::Webhook),
+        Enqueued::Queued
+    );
+    assert_eq!(
+        queue.enqueue("web", &services(&["worker", "app"]), Trigger::Manual),
+        Enqueued::Merged
+    );
+    assert_eq!(
+        queue.enqueue("media", &services(&["jellyfin"]), Trigger::Schedule),
+        Enqueued::Queued
+    );
+
+    let web = queue.start_next().unwrap();
+    assert_eq!(web.stack, "web");
+    assert_eq!(web.services.len(), 2);
+    assert_eq!(web.triggers.len(), 2);
+
+    // a request for a running stack waits for it instead of merging into it
+    assert_eq!(
+        queue.enqueue("web", &services(&["app"]), Trigger::Webhook),
+        Enqueued::Queued
+    );
+
+    // other stacks do not conflict, but web has to wait
+    assert_eq!(queue.start_next().unwrap().stack, "media");
+    assert!(queue.start_next().is_none());
+
+    queue.finish("web", Some("pull failed".to_string()));
+    assert_eq!(queue.start_next().unwrap().stack, "web");
+
+    let state = queue.snapshot();
+    assert_eq!(state.running.len(), 2);
+    assert!(state.pending.is_empty());
+    assert_eq!(state.finished[0].error.as_deref(), Some("pull failed"));
+}
+
+#[test]
+fn phases() {
+    let scheduled = HookConfig {
+        services: vec!["app".to_string()],
+        interval: Some(3600),
+        ..Default::default()
+    };
+    let windowed = HookConfig {
+        pull_window: Some("01:00-03:00".to_string()),
+        apply_window: Some("03:00-04:00".to_string()),
+        ..scheduled.clone()
+    };
+    let triggers = |triggers: &[Trigger]| triggers.iter().copied().collect::<BTreeSet<Trigger>>();
+
+    assert_eq!(
+        action_phase(&triggers(&[Trigger::Schedule]), Some(&scheduled)),
+        Phase::Deploy
+    );
+    assert_eq!(
+        action_phase(&triggers(&[Trigger::Schedule]), Some(&windowed)),
+        Phase::Pull
+    );
+    assert_eq!(
+        action_phase(&triggers(&[Trigger::Maintenance]), Some(&windowed)),
+        Phase::Apply
+    );
+
+    // webhooks never wait for the maintenance window
+    assert_eq!(
+        action_phase(&triggers(&[Trigger::Webhook]), Some(&windowed)),
+        Phase::Deploy
+    );
+    assert_eq!(
+        action_phase(
+            &triggers(&[Trigger::Schedule, Trigger::Manual]),
+            Some(&windowed)
+        ),
+        Phase::Deploy
+    );
+    assert_eq!(
+        action_phase(
+            &triggers(&[Trigger::Maintenance, Trigger::Webhook]),
+            Some(&windowed)
+        ),
+        Phase::Deploy
+    );
+
+    let (pull_window, apply_window) = hook_windows("web", &windowed).unwrap();
+    assert_eq!(pull_window.unwrap().to_string(), "01:00-03:00");
+    assert_eq!(apply_window.unwrap().to_string(), "03:00-04:00");
+
+    let invalid = HookConfig {
+        apply_window: Some("3am".to_string()),
+        ..scheduled
+    };
+    let error = format!("{:#}", hook_windows("web", &invalid).unwrap_err());
+    assert!(error.contains("daemon.hooks.web.apply_window"), "{error}");
+}
+
+#[test]
+fn report_schedule() {
+    let report = ReportConfig {
+        window: "07:00-08:00".to_string(),
+        channels: vec!["ops".to_string()],
+    };
+    assert_eq!(report_window(&report).unwrap().to_string(), "07:00-08:00");
+
+    let invalid = ReportConfig {
+        window: "7am".to_string(),
+        ..report
+    };
+    let error = format!("{:#}", report_window(&invalid).unwrap_err());
+    assert!(error.contains("daemon.report.window"), "{error}");
+}
+
+#[test]
+fn pulled_services() {
+    let mut state = PulledState::new();
+    let services = |names: &[&str]| {
+        names
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<String>>()
+    };
+
+    add_pulled(
+        &mut state,
+        "web",
+        &services(&["app"]),
+        "2026-10-16 02:00:00",
+    );
+    add_pulled(
+        &mut state,
+        "web",
+        &services(&["worker", "app"]),
+        "2026-10-16 08:00:00",
+    );
+
+    // the oldest pull waiting is kept
+    assert_eq!(state["web"].pulled_at, "2026-10-16 02:00:00");
+    assert_eq!(state["web"].services.len(), 2);
+
+    remove_applied(&mut state, "web", &services(&["app"]));
+    assert_eq!(
+        state["web"].services,
+        BTreeSet::from(["worker".to_string()])
+    );
+
+    remove_applied(&mut state, "media", &services(&["jellyfin"]));
+    remove_applied(&mut state, "web", &services(&["worker"]));
+    assert!(state.is_empty());
+}
@@ -0,0 +1,32 @@
+use dsd_util::deploy::{github_annotation, parse_image_override, render_override};
+
+#[test]
+fn image_overrides() {
+    let web = parse_image_override("web=ghcr.io/me/web:sha-abc").unwrap();
+    assert_eq!(web.service, "web");
+    assert_eq!(web.image, "ghcr.io/me/web:sha-abc");
+
+    // registries with ports keep their colon, only the first = separates
+    let api = parse_image_override("api=registry:5000/api@sha256:abc=").unwrap();
+    assert_eq!(api.image, "registry:5000/api@sha256:abc=");
+
+    for invalid in ["", "web", "=image", "web="] {
+        assert!(
+            parse_image_override(invalid).is_err(),
+            "{invalid:?} should not parse"
+        );
+    }
+
+    assert_eq!(
+        render_override(&[web, api]).unwrap(),
+        "services:\n  api:\n    image: registry:5000/api@sha256:abc=\n  web:\n    image: ghcr.io/me/web:sha-abc\n"
+    );
+}
+
+#[test]
+fn annotations() {
+    assert_eq!(
+        github_annotation("error", "Deploy of web: failed", "line 1\nline 2 100%"),
+        "::error title=Deploy of web%3A failed::line 1%0Aline 2 100%25"
+    );
+}
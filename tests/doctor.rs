@@ -0,0 +1,54 @@
+use dsd_util::doctor::{
+    daemon_checks, is_supported, CheckStatus, Problem, MIN_COMPOSE_VERSION, MIN_DOCKER_VERSION,
+};
+use dsd_util::error::DsdError;
+
+#[test]
+fn docker_errors_become_problems() {
+    assert_eq!(
+        Problem::from(DsdError::DockerNotFound),
+        Problem::DockerMissing
+    );
+
+    let problem = Problem::from(DsdError::PermissionDenied("denied".to_string()));
+    assert_eq!(problem, Problem::SocketPermission("denied".to_string()));
+    assert!(problem.hint().contains("usermod -aG docker"));
+
+    let problem = Problem::from(DsdError::DaemonUnavailable("down".to_string()));
+    assert_eq!(problem, Problem::DaemonUnreachable("down".to_string()));
+    assert!(problem.hint().contains("systemctl start docker"));
+
+    let problem = Problem::from(DsdError::ImageNotFound("nginx".to_string()));
+    assert!(matches!(problem, Problem::Other(_)));
+}
+
+#[test]
+fn versions_are_checked_against_the_minimum() {
+    assert!(is_supported("27.3.1", MIN_DOCKER_VERSION));
+    assert!(is_supported("20.10.24+dfsg1", MIN_DOCKER_VERSION));
+    assert!(!is_supported("19.03.15", MIN_DOCKER_VERSION));
+
+    assert!(is_supported("2.29.7", MIN_COMPOSE_VERSION));
+    assert!(is_supported("v2.0.1", MIN_COMPOSE_VERSION));
+    assert!(!is_supported("1.29.2", MIN_COMPOSE_VERSION));
+}
+
+#[test]
+fn socket_permission_skips_the_daemon_check() {
+    let [socket, daemon] = daemon_checks(Err(Problem::SocketPermission("denied".to_string())));
+    assert!(matches!(socket.status, CheckStatus::Failed(_)));
+    assert_eq!(daemon.status, CheckStatus::Skipped);
+
+    let [socket, daemon] = daemon_checks(Err(Problem::DaemonUnreachable("down".to_string())));
+    assert!(matches!(socket.status, CheckStatus::Passed(_)));
+    assert!(matches!(
+        daemon.status,
+        CheckStatus::Failed(Problem::DaemonUnreachable(_))
+    ));
+
+    let [_, daemon] = daemon_checks(Ok("27.3.1".to_string()));
+    assert_eq!(
+        daemon.status,
+        CheckStatus::Passed("Docker Engine 27.3.1".to_string())
+    );
+}
@@ -0,0 +1,79 @@
+use dsd_util::document::{Document, DocumentFormat};
+use std::path::Path;
+
+fn document() -> Document {
+    let mut document = Document::new("Daily report for host");
+    document.heading("Open warnings");
+    document.list(vec!["web-app-1 is <unhealthy>".to_string()]);
+    document.paragraph("1 of 2 container(s) running.");
+    document.table(
+        &["CONTAINER", "STATUS"],
+        vec![vec!["web|app".to_string(), "running".to_string()]],
+    );
+    document
+}
+
+#[test]
+fn format_from_path() {
+    assert_eq!(
+        DocumentFormat::from_path(Path::new("report.html")),
+        DocumentFormat::Html
+    );
+    assert_eq!(
+        DocumentFormat::from_path(Path::new("report.htm")),
+        DocumentFormat::Html
+    );
+    assert_eq!(
+        DocumentFormat::from_path(Path::new("report.md")),
+        DocumentFormat::Markdown
+    );
+    assert_eq!(
+        DocumentFormat::from_path(Path::new("report.csv")),
+        DocumentFormat::Csv
+    );
+    assert_eq!(
+        DocumentFormat::from_path(Path::new("report")),
+        DocumentFormat::Markdown
+    );
+}
+
+#[test]
+fn markdown() {
+    assert_eq!(
+        document().render(DocumentFormat::Markdown),
+        "# Daily report for host\n\
+         \n## Open warnings\n\
+         \n- web-app-1 is <unhealthy>\n\
+         \n1 of 2 container(s) running.\n\
+         \n| CONTAINER | STATUS |\n\
+         | --- | --- |\n\
+         | web\\|app | running |\n"
+    );
+}
+
+#[test]
+fn html_escapes_text() {
+    let html = document().render(DocumentFormat::Html);
+
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    assert!(html.contains("<title>Daily report for host</title>"));
+    assert!(html.contains("<li>web-app-1 is &lt;unhealthy&gt;</li>"));
+    assert!(html.contains("<tr><th>CONTAINER</th><th>STATUS</th></tr>"));
+    assert!(html.contains("<tr><td>web|app</td><td>running</td></tr>"));
+    assert!(html.trim_end().ends_with("</html>"));
+}
+
+#[test]
+fn csv_keeps_only_tables() {
+    let mut document = document();
+    document.table(&["STACK"], vec![vec!["web, media".to_string()]]);
+
+    assert_eq!(
+        document.render(DocumentFormat::Csv),
+        "CONTAINER,STATUS\n\
+         web|app,running\n\
+         \n\
+         STACK\n\
+         \"web, media\"\n"
+    );
+}
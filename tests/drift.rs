@@ -0,0 +1,109 @@
+use dsd_util::drift::{compose_ports, container_ports, service_drift, Drift};
+use serde_json::{json, Value};
+
+fn compose_service(yaml: &str) -> Value {
+    serde_yaml::from_str(yaml).expect("compose service is valid yaml")
+}
+
+#[test]
+fn compose_port_syntaxes() {
+    let service = compose_service(
+        r#"
+ports:
+  - "8080:80"
+  - "127.0.0.1:5432:5432"
+  - "[::1]:9000:9000/udp"
+  - "3000"
+  - 4000
+  - "8000-8001:7000-7001"
+  - target: 443
+    published: "8443"
+    protocol: tcp
+"#,
+    );
+
+    assert_eq!(
+        compose_ports(&service["ports"]),
+        [
+            "127.0.0.1:5432:5432/tcp",
+            "3000/tcp",
+            "4000/tcp",
+            "8000:7000/tcp",
+            "8001:7001/tcp",
+            "8080:80/tcp",
+            "8443:443/tcp",
+            "::1:9000:9000/udp",
+        ]
+    );
+    assert!(compose_ports(&Value::Null).is_empty());
+}
+
+#[test]
+fn container_port_bindings() {
+    let container = json!({
+        "HostConfig": {
+            "PortBindings": {
+                "80/tcp": [{ "HostIp": "", "HostPort": "8080" }],
+                "5432/tcp": [{ "HostIp": "127.0.0.1", "HostPort": "5432" }],
+                "3000/tcp": [{ "HostIp": "", "HostPort": "" }]
+            }
+        }
+    });
+
+    assert_eq!(
+        container_ports(&container),
+        ["127.0.0.1:5432:5432/tcp", "3000/tcp", "8080:80/tcp"]
+    );
+}
+
+#[test]
+fn drift_of_a_service() {
+    let desired = compose_service(
+        r#"
+image: ghcr.io/acme/app:2.1
+environment:
+  LOG_LEVEL: info
+ports:
+  - "8080:80"
+"#,
+    );
+    let container = json!({
+        "Config": {
+            "Image": "ghcr.io/acme/app:2.0",
+            "Env": ["LOG_LEVEL=debug", "PATH=/usr/bin"]
+        },
+        "HostConfig": {
+            "PortBindings": { "80/tcp": [{ "HostIp": "0.0.0.0", "HostPort": "8081" }] }
+        }
+    });
+    let image_config = json!({ "Env": ["PATH=/usr/bin"] });
+
+    let drifts = service_drift("web", "app", &desired, &container, &image_config);
+    assert_eq!(
+        drifts,
+        [
+            Drift::Image {
+                declared: "ghcr.io/acme/app:2.1".to_string(),
+                running: "ghcr.io/acme/app:2.0".to_string(),
+            },
+            Drift::Env {
+                keys: vec!["LOG_LEVEL".to_string()],
+            },
+            Drift::Ports {
+                declared: vec!["8080:80/tcp".to_string()],
+                running: vec!["8081:80/tcp".to_string()],
+            },
+        ]
+    );
+    assert_eq!(
+        drifts[0].to_string(),
+        "runs ghcr.io/acme/app:2.0, file declares ghcr.io/acme/app:2.1"
+    );
+
+    // env vars from the image do not count as drift
+    let matching = json!({
+        "Config": { "Image": "ghcr.io/acme/app:2.1", "Env": ["LOG_LEVEL=info", "PATH=/usr/bin"] },
+        "HostConfig": { "PortBindings": { "80/tcp": [{ "HostIp": "", "HostPort": "8080" }] } }
+    });
+    assert!(service_drift("web", "app", &desired, &matching, &image_config).is_empty());
+}
@@ -0,0 +1,117 @@
+use dsd_util::environment::{
+    diff_env, display_value, is_secret_key, ContainerEnv, EnvDifference, MASK,
+};
+use dsd_util::parsers::parse_container_env;
+use std::collections::BTreeMap;
+
+const INSPECT_OUTPUT: &str = r#"[
+  {
+    "Name": "/app-worker-1",
+    "Config": {
+      "Env": ["QUEUE=default", "DB_PASSWORD=hunter2", "DEBUG", "PATH=/usr/bin:/bin"],
+      "Labels": {"com.docker.compose.project": "app", "com.docker.compose.service": "worker"}
+    }
+  },
+  {
+    "Name": "/scratch",
+    "Config": {"Env": null, "Labels": null}
+  }
+]"#;
+
+fn env(vars: &[(&str, &str)]) -> BTreeMap<String, String> {
+    vars.iter()
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect()
+}
+
+#[test]
+fn detects_secret_keys() {
+    assert!(is_secret_key("DB_PASSWORD"));
+    assert!(is_secret_key("api_token"));
+    assert!(is_secret_key("AWS_SECRET_ACCESS_KEY"));
+    assert!(is_secret_key("MYSQL_ROOT_PASSWD"));
+
+    assert!(!is_secret_key("QUEUE"));
+    assert!(!is_secret_key("PATH"));
+}
+
+#[test]
+fn masks_secret_values_unless_shown() {
+    assert_eq!(display_value("DB_PASSWORD", "hunter2", false), MASK);
+    assert_eq!(display_value("DB_PASSWORD", "hunter2", true), "hunter2");
+    assert_eq!(display_value("QUEUE", "default", false), "default");
+}
+
+#[test]
+fn parses_container_env() {
+    let containers = parse_container_env(INSPECT_OUTPUT.as_bytes()).unwrap();
+
+    assert_eq!(
+        containers,
+        vec![
+            ContainerEnv {
+                container: "app-worker-1".to_string(),
+                stack: Some("app".to_string()),
+                service: Some("worker".to_string()),
+                env: env(&[
+                    ("DB_PASSWORD", "hunter2"),
+                    ("DEBUG", ""),
+                    ("PATH", "/usr/bin:/bin"),
+                    ("QUEUE", "default"),
+                ]),
+            },
+            ContainerEnv {
+                container: "scratch".to_string(),
+                stack: None,
+                service: None,
+                env: BTreeMap::new(),
+            },
+        ]
+    );
+}
+
+#[test]
+fn diffs_env_of_two_containers() {
+    let left = env(&[("QUEUE", "default"), ("DEBUG", "1"), ("PATH", "/bin")]);
+    let right = env(&[("QUEUE", "mail"), ("PATH", "/bin"), ("LOCALE", "de")]);
+
+    assert_eq!(
+        diff_env(&left, &right),
+        vec![
+            EnvDifference {
+                key: "DEBUG".to_string(),
+                left: Some("1".to_string()),
+                right: None,
+            },
+            EnvDifference {
+                key: "LOCALE".to_string(),
+                left: None,
+                right: Some("de".to_string()),
+            },
+            EnvDifference {
+                key: "QUEUE".to_string(),
+                left: Some("default".to_string()),
+                right: Some("mail".to_string()),
+            },
+        ]
+    );
+
+    assert!(diff_env(&left, &left).is_empty());
+}
+
+#[test]
+fn compares_only_containers_of_the_same_service() {
+    let container = |name: &str, stack: Option<&str>, service: Option<&str>| ContainerEnv {
+        container: name.to_string(),
+        stack: stack.map(String::from),
+        service: service.map(String::from),
+        env: BTreeMap::new(),
+    };
+
+    let worker = container("app-worker-1", Some("app"), Some("worker"));
+
+    assert!(worker.same_service(&container("app-worker-2", Some("app"), Some("worker"))));
+    assert!(!worker.same_service(&container("app-web-1", Some("app"), Some("web"))));
+    assert!(!worker.same_service(&container("mail-worker-1", Some("mail"), Some("worker"))));
+    assert!(!container("a", None, None).same_service(&container("b", None, None)));
+}
@@ -0,0 +1,67 @@
+use dsd_util::error::{check_output, DsdError};
+use std::process::Command;
+
+#[test]
+fn docker_messages_are_classified() {
+    let error = DsdError::from_docker(
+        "ps",
+        Some(1),
+        "permission denied while trying to connect to the Docker daemon socket at unix:///var/run/docker.sock: Get \"http://%2Fvar%2Frun%2Fdocker.sock/v1.47/containers/json\": dial unix /var/run/docker.sock: connect: permission denied\n",
+    );
+    assert!(matches!(error, DsdError::PermissionDenied(_)));
+
+    let error = DsdError::from_docker(
+        "ps",
+        Some(1),
+        "Cannot connect to the Docker daemon at unix:///var/run/docker.sock. Is the docker daemon running?\n",
+    );
+    assert!(matches!(error, DsdError::DaemonUnavailable(_)));
+
+    let error = DsdError::from_docker("inspect", Some(1), "Error: No such object: web-app-1\n");
+    assert!(matches!(&error, DsdError::ContainerNotFound(name) if name == "web-app-1"));
+    assert!(error.is_not_found());
+
+    let error = DsdError::from_docker(
+        "stats",
+        Some(1),
+        "Error response from daemon: No such container: gone\n",
+    );
+    assert!(matches!(&error, DsdError::ContainerNotFound(name) if name == "gone"));
+
+    let error = DsdError::from_docker(
+        "image inspect",
+        Some(1),
+        "Error response from daemon: No such image: nginx:9\n",
+    );
+    assert!(matches!(&error, DsdError::ImageNotFound(name) if name == "nginx:9"));
+}
+
+#[test]
+fn exit_codes_of_container_commands() {
+    let error = DsdError::from_docker(
+        "exec",
+        Some(127),
+        "OCI runtime exec failed: exec: \"bash\": executable file not found in $PATH",
+    );
+    assert!(matches!(error, DsdError::CommandNotFound(_)));
+
+    let error = DsdError::from_docker("exec", Some(126), "permission denied: unknown");
+    assert!(matches!(error, DsdError::CommandNotExecutable(_)));
+
+    let error = DsdError::from_docker("update", Some(1), "invalid restart policy \"sometimes\"");
+    assert_eq!(
+        error.to_string(),
+        "docker update failed: invalid restart policy \"sometimes\""
+    );
+}
+
+#[test]
+fn missing_docker_binary() {
+    let error =
+        check_output("ps", Command::new("dsd-util-test-no-such-binary").output()).unwrap_err();
+    assert!(matches!(error, DsdError::DockerNotFound));
+
+    let error = check_output("ps", Command::new("false").output()).unwrap_err();
+    assert!(matches!(error, DsdError::Docker { code: Some(1), .. }));
+    assert!(check_output("ps", Command::new("true").output()).is_ok());
+}
@@ -0,0 +1,133 @@
+use dsd_util::config::UpdateConfig;
+use dsd_util::exclusions::{Exclusion, UpdateRules};
+use std::collections::HashMap;
+
+fn labels(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+    pairs
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect()
+}
+
+fn compose_labels(stack: &str, service: &str) -> HashMap<String, String> {
+    labels(&[
+        ("com.docker.compose.project", stack),
+        ("com.docker.compose.service", service),
+    ])
+}
+
+#[test]
+fn labels_opt_out() {
+    let rules = UpdateRules::default();
+
+    assert_eq!(
+        rules.check(
+            "db",
+            &labels(&[("dsd-util.update", "false")]),
+            "postgres:16"
+        ),
+        Some(Exclusion::Label("dsd-util.update"))
+    );
+    assert_eq!(
+        rules.check("db", &labels(&[("dsd-util.skip", "true")]), "postgres:16"),
+        Some(Exclusion::Label("dsd-util.skip"))
+    );
+    assert_eq!(
+        rules.check("db", &labels(&[("dsd-util.skip", "false")]), "postgres:16"),
+        None
+    );
+}
+
+#[test]
+fn exclusions_and_pins() {
+    let rules = UpdateRules::new(
+        &UpdateConfig {
+            exclude: vec!["media/*".to_string()],
+            pin: vec!["postgres".to_string(), "mariadb:10.*".to_string()],
+        },
+        &["db".to_string()],
+    );
+
+    // services by name or as stack/service, and containers by name
+    assert_eq!(
+        rules.check("web-db-1", &compose_labels("web", "db"), "redis:7"),
+        Some(Exclusion::Excluded("db".to_string()))
+    );
+    assert_eq!(
+        rules.check(
+            "media-jellyfin-1",
+            &compose_labels("media", "jellyfin"),
+            "jellyfin/jellyfin"
+        ),
+        Some(Exclusion::Excluded("media/*".to_string()))
+    );
+    assert_eq!(
+        rules.check("db", &HashMap::new(), "redis:7"),
+        Some(Exclusion::Excluded("db".to_string()))
+    );
+
+    assert_eq!(
+        rules.check("web-pg-1", &compose_labels("web", "pg"), "postgres:16"),
+        Some(Exclusion::Pinned("postgres".to_string()))
+    );
+    assert_eq!(
+        rules.check(
+            "web-maria-1",
+            &compose_labels("web", "maria"),
+            "mariadb:10.11"
+        ),
+        Some(Exclusion::Pinned("mariadb:10.*".to_string()))
+    );
+    assert_eq!(
+        rules.check(
+            "web-maria-1",
+            &compose_labels("web", "maria"),
+            "mariadb:11.4"
+        ),
+        None
+    );
+
+    // pins hold for every spelling of an image
+    assert_eq!(
+        rules.check(
+            "web-pg-1",
+            &compose_labels("web", "pg"),
+            "docker.io/library/postgres:16@sha256:9f8e"
+        ),
+        Some(Exclusion::Pinned("postgres".to_string()))
+    );
+    assert_eq!(
+        rules.check(
+            "web-maria-1",
+            &compose_labels("web", "maria"),
+            "index.docker.io/library/mariadb:10.6"
+        ),
+        Some(Exclusion::Pinned("mariadb:10.*".to_string()))
+    );
+    assert_eq!(
+        rules.check(
+            "web-app-1",
+            &compose_labels("web", "app"),
+            "ghcr.io/acme/app:2"
+        ),
+        None
+    );
+}
+
+#[test]
+fn digests_pin_images() {
+    let exclusion = UpdateRules::default().check(
+        "web-app-1",
+        &compose_labels("web", "app"),
+        "ghcr.io/acme/app:2@sha256:9f8e1a2b3c4d5e6f",
+    );
+
+    assert_eq!(
+        exclusion,
+        Some(Exclusion::Digest("sha256:9f8e1a2b3c4d5e6f".to_string()))
+    );
+    assert_eq!(
+        exclusion.unwrap().to_string(),
+        "image pinned to digest sha256:9f8e1a2b3c4d, unpin to follow its tag"
+    );
+}
@@ -0,0 +1,78 @@
+use dsd_util::graph::{render_dot, render_tree, resolve_links, GraphContainer, StackGraph};
+
+fn container(service: &str, depends_on: &[&str], links: &[&str]) -> GraphContainer {
+    GraphContainer {
+        name: format!("web-{service}-1"),
+        service: service.to_string(),
+        networks: vec!["web_default".to_string()],
+        volumes: vec![],
+        depends_on: depends_on
+            .iter()
+            .map(|service| service.to_string())
+            .collect(),
+        links: links.iter().map(|link| link.to_string()).collect(),
+    }
+}
+
+fn stack(mut containers: Vec<GraphContainer>) -> StackGraph {
+    resolve_links(&mut containers);
+
+    StackGraph {
+        stack: "web".to_string(),
+        containers,
+    }
+}
+
+#[test]
+fn links_resolve_to_services() {
+    let graph = stack(vec![
+        container("app", &["db"], &["web-cache-1", "legacy"]),
+        container("cache", &[], &[]),
+        container("db", &[], &[]),
+    ]);
+
+    assert_eq!(graph.containers[0].links, vec!["cache", "legacy"]);
+    assert_eq!(
+        graph.dependencies(&graph.containers[0]),
+        vec!["db", "cache", "legacy"]
+    );
+    assert!(render_dot(&graph).contains("\"app\" -> \"cache\" [label=\"link\"];"));
+}
+
+#[test]
+fn dependency_tree() {
+    let graph = stack(vec![
+        container("app", &["db", "cache"], &[]),
+        container("cache", &[], &[]),
+        container("db", &[], &[]),
+        container("proxy", &["app"], &["legacy"]),
+    ]);
+
+    assert_eq!(
+        render_tree(&graph, true),
+        "web
+`- proxy (web-proxy-1)
+   |- app (web-app-1)
+   |  |- db (web-db-1)
+   |  `- cache (web-cache-1)
+   `- legacy (not in stack)
+"
+    );
+}
+
+#[test]
+fn cycles_are_marked() {
+    let graph = stack(vec![
+        container("a", &["b"], &[]),
+        container("b", &["a"], &[]),
+    ]);
+
+    assert_eq!(
+        render_tree(&graph, false),
+        "web
+└─ a (web-a-1)
+   └─ b (web-b-1)
+      └─ a (cycle)
+"
+    );
+}
@@ -0,0 +1,112 @@
+use dsd_util::config::GuardConfig;
+use dsd_util::hostinfo::{
+    free_disk_space, parse_loadavg, parse_meminfo, DiskShortage, HostInfo, LoadGuard, Overload,
+};
+use std::path::Path;
+use std::time::Duration;
+
+const MEMINFO: &str = "MemTotal:        8041964 kB
+MemFree:          412340 kB
+MemAvailable:    2097152 kB
+Buffers:          120536 kB
+";
+
+fn host(load: f64, memory_available: u64) -> HostInfo {
+    HostInfo {
+        load,
+        cpus: 4,
+        memory_total: 8 << 30,
+        memory_available,
+    }
+}
+
+#[test]
+fn proc_files() {
+    assert_eq!(parse_loadavg("3.52 2.10 1.05 3/512 12345\n").unwrap(), 3.52);
+    assert!(parse_loadavg("").is_err());
+
+    assert_eq!(parse_meminfo(MEMINFO).unwrap(), (8_041_964 * 1024, 2 << 30));
+    assert!(parse_meminfo("MemTotal: 8041964 kB\n").is_err());
+}
+
+#[test]
+fn guard_thresholds() {
+    let guard = LoadGuard {
+        max_load: Some(2.0),
+        min_free_memory: Some(1 << 30),
+        retry_interval: Duration::from_secs(60),
+        disk_headroom: 0,
+    };
+
+    assert_eq!(guard.check(&host(4.0, 2 << 30)), None);
+    assert_eq!(
+        guard.check(&host(10.0, 2 << 30)),
+        Some(Overload::Load {
+            load: 2.5,
+            max: 2.0
+        })
+    );
+    assert_eq!(
+        guard.check(&host(1.0, 512 << 20)),
+        Some(Overload::Memory {
+            available: 512 << 20,
+            min: 1 << 30
+        })
+    );
+    assert_eq!(
+        Overload::Memory {
+            available: 512 << 20,
+            min: 1 << 30
+        }
+        .to_string(),
+        "512MiB of memory available, below 1GiB"
+    );
+}
+
+#[test]
+fn guard_config() {
+    let guard = LoadGuard::from_config(&GuardConfig::default()).unwrap();
+    assert!(!guard.is_enabled());
+    assert_eq!(guard.check_host(), None);
+
+    let guard = LoadGuard::from_config(&GuardConfig {
+        min_free_memory: Some("512MiB".to_string()),
+        ..GuardConfig::default()
+    })
+    .unwrap();
+    assert!(guard.is_enabled());
+    assert_eq!(guard.min_free_memory, Some(512 << 20));
+
+    assert!(LoadGuard::from_config(&GuardConfig {
+        min_free_memory: Some("lots".to_string()),
+        ..GuardConfig::default()
+    })
+    .is_err());
+}
+
+#[test]
+fn disk_space() {
+    let guard = LoadGuard::from_config(&GuardConfig::default()).unwrap();
+    assert_eq!(guard.disk_headroom, 1 << 30);
+
+    // downloads take as much again twice once extracted
+    assert_eq!(guard.check_disk(1 << 30, 4 << 30), None);
+    assert_eq!(
+        guard.check_disk(1 << 30, 3 << 30),
+        Some(DiskShortage {
+            required: 4 << 30,
+            free: 3 << 30
+        })
+    );
+    assert_eq!(
+        DiskShortage {
+            required: 4 << 30,
+            free: 3 << 30
+        }
+        .to_string(),
+        "4GiB needed to download and extract images, only 3GiB free"
+    );
+
+    assert!(free_disk_space(Path::new("/")).unwrap() > 0);
+    assert!(free_disk_space(Path::new("/nonexistent")).is_err());
+}
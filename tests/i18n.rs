@@ -0,0 +1,30 @@
+use std::collections::BTreeSet;
+
+/// Names of the `{placeholders}` in a message
+fn placeholders(message: &str) -> BTreeSet<&str> {
+    message
+        .split('{')
+        .skip(1)
+        .filter_map(|part| part.split_once('}').map(|(name, _)| name))
+        .collect()
+}
+
+#[test]
+fn translations_keep_placeholders() {
+    for entry in std::fs::read_dir("locales").unwrap() {
+        let path = entry.unwrap().path();
+        let contents = std::fs::read_to_string(&path).unwrap();
+        let catalog: toml::Table = toml::from_str(&contents)
+            .unwrap_or_else(|e| panic!("{} does not parse: {e}", path.display()));
+
+        for (message, translation) in &catalog {
+            let translation = translation.as_str().unwrap();
+            assert_eq!(
+                placeholders(message),
+                placeholders(translation),
+                "{}: {message:?}",
+                path.display()
+            );
+        }
+    }
+}
@@ -0,0 +1,104 @@
+use dsd_util::image::{image_matches, same_image, ImageRef};
+
+fn parse(reference: &str) -> ImageRef {
+    ImageRef::parse(reference).expect("image reference parses")
+}
+
+#[test]
+fn references() {
+    assert_eq!(
+        parse("nginx"),
+        ImageRef {
+            registry: "docker.io".to_string(),
+            repository: "library/nginx".to_string(),
+            tag: None,
+            digest: None,
+        }
+    );
+    assert_eq!(
+        parse("registry.local:5000/acme/app:1.2@sha256:9f8e"),
+        ImageRef {
+            registry: "registry.local:5000".to_string(),
+            repository: "acme/app".to_string(),
+            tag: Some("1.2".to_string()),
+            digest: Some("sha256:9f8e".to_string()),
+        }
+    );
+    assert_eq!(parse("localhost/app").registry, "localhost");
+    assert_eq!(
+        parse("index.docker.io/grafana/grafana").registry,
+        "docker.io"
+    );
+
+    for invalid in [
+        "",
+        "Nginx",
+        "nginx:",
+        "nginx@sha256",
+        "ghcr.io//app",
+        "app:bad/tag!",
+    ] {
+        assert!(
+            ImageRef::parse(invalid).is_err(),
+            "{invalid} should not parse"
+        );
+    }
+}
+
+#[test]
+fn repositories() {
+    assert_eq!(parse("postgres:16").familiar_repository(), "postgres");
+    assert_eq!(
+        parse("docker.io/library/postgres").familiar_repository(),
+        "postgres"
+    );
+    assert_eq!(
+        parse("ghcr.io/acme/app:2@sha256:9f8e").familiar_repository(),
+        "ghcr.io/acme/app"
+    );
+    assert_eq!(
+        parse("registry:5000/acme/app:1.2").familiar_repository(),
+        "registry:5000/acme/app"
+    );
+}
+
+#[test]
+fn display_and_normalization() {
+    assert_eq!(
+        parse("docker.io/library/nginx:1.27").to_string(),
+        "nginx:1.27"
+    );
+    assert_eq!(
+        parse("nginx").normalized(),
+        "docker.io/library/nginx:latest"
+    );
+    assert_eq!(
+        parse("nginx@sha256:9f8e").normalized(),
+        "docker.io/library/nginx@sha256:9f8e"
+    );
+    assert_eq!(
+        "ghcr.io/acme/app:2"
+            .parse::<ImageRef>()
+            .unwrap()
+            .to_string(),
+        "ghcr.io/acme/app:2"
+    );
+
+    assert!(same_image("nginx", "docker.io/library/nginx:latest"));
+    assert!(same_image(
+        "grafana/grafana:11",
+        "index.docker.io/grafana/grafana:11"
+    ));
+    assert!(!same_image("nginx", "nginx:1.27"));
+    assert!(!same_image("nginx:1.27", "nginx:1.27@sha256:9f8e"));
+}
+
+#[test]
+fn patterns() {
+    assert!(image_matches("postgres", "docker.io/library/postgres:16"));
+    assert!(image_matches("docker.io/library/postgres", "postgres:16"));
+    assert!(image_matches("postgres:latest", "postgres"));
+    assert!(image_matches("ghcr.io/acme/*", "ghcr.io/acme/app:2"));
+    assert!(!image_matches("acme/*", "ghcr.io/acme/app:2"));
+    assert!(!image_matches("postgres:15", "postgres:16"));
+}
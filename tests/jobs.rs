@@ -0,0 +1,83 @@
+use dsd_util::docker::Container;
+use dsd_util::jobs::{group_by_stack, run_parallel};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+fn container(id: &str, name: &str, stack: Option<&str>) -> Container {
+    Container {
+        id: id.to_string(),
+        name: name.to_string(),
+        image: "nginx:latest".to_string(),
+        stack: stack.map(String::from),
+        service: None,
+    }
+}
+
+#[test]
+fn results_keep_the_order_of_items() {
+    let items = (0..20).collect::<Vec<u64>>();
+
+    // later items finish first
+    let results = run_parallel(&items, 4, |item| {
+        std::thread::sleep(Duration::from_millis(20 - item));
+        item * 2
+    });
+
+    assert_eq!(
+        results,
+        items.iter().map(|item| item * 2).collect::<Vec<u64>>()
+    );
+    assert!(run_parallel(&[] as &[u64], 4, |item| *item).is_empty());
+}
+
+#[test]
+fn jobs_bound_concurrency() {
+    let running = AtomicUsize::new(0);
+    let most = AtomicUsize::new(0);
+
+    run_parallel(&[(); 12], 3, |_| {
+        let now = running.fetch_add(1, Ordering::SeqCst) + 1;
+        most.fetch_max(now, Ordering::SeqCst);
+        std::thread::sleep(Duration::from_millis(10));
+        running.fetch_sub(1, Ordering::SeqCst);
+    });
+
+    assert_eq!(most.load(Ordering::SeqCst), 3);
+
+    // zero jobs still gets the work done
+    assert_eq!(run_parallel(&[1, 2], 0, |item| item + 1), vec![2, 3]);
+}
+
+#[test]
+fn containers_grouped_by_stack() {
+    let known = [
+        container("a1", "web-app-1", Some("web")),
+        container("b2", "media-jellyfin-1", Some("media")),
+        container("c3", "web-db-1", Some("web")),
+        container("d4", "portainer", None),
+    ];
+
+    let groups = group_by_stack(
+        &[
+            "web-app-1".to_string(),
+            "portainer".to_string(),
+            "b2".to_string(),
+            "web-db-1".to_string(),
+            "gone".to_string(),
+        ],
+        &known,
+    );
+
+    assert_eq!(
+        groups,
+        vec![
+            ("gone".to_string(), vec!["gone".to_string()]),
+            ("media".to_string(), vec!["media-jellyfin-1".to_string()]),
+            ("portainer".to_string(), vec!["portainer".to_string()]),
+            (
+                "web".to_string(),
+                vec!["web-app-1".to_string(), "web-db-1".to_string()]
+            ),
+        ]
+    );
+}
@@ -0,0 +1,41 @@
+use dsd_util::history::Action;
+use dsd_util::lifecycle::Lifecycle;
+use std::time::Duration;
+
+#[test]
+fn docker_args() {
+    let timeout = Duration::from_secs(90);
+
+    assert_eq!(
+        Lifecycle::Stop.docker_args("web-app-1", timeout),
+        vec!["stop", "--time", "90", "web-app-1"]
+    );
+    assert_eq!(
+        Lifecycle::Start.docker_args("web-app-1", timeout),
+        vec!["start", "web-app-1"]
+    );
+    assert_eq!(
+        Lifecycle::Pause.docker_args("web-app-1", timeout),
+        vec!["pause", "web-app-1"]
+    );
+    assert_eq!(
+        Lifecycle::Unpause.docker_args("web-app-1", timeout),
+        vec!["unpause", "web-app-1"]
+    );
+}
+
+#[test]
+fn only_start_selects_stopped_containers() {
+    assert!(Lifecycle::Stop.needs_running());
+    assert!(Lifecycle::Pause.needs_running());
+    assert!(Lifecycle::Unpause.needs_running());
+    assert!(!Lifecycle::Start.needs_running());
+}
+
+#[test]
+fn recorded_actions() {
+    assert_eq!(Lifecycle::Stop.action(), Action::Stop);
+    assert_eq!(Lifecycle::Start.action(), Action::Start);
+    assert_eq!(Lifecycle::Pause.action(), Action::Pause);
+    assert_eq!(Lifecycle::Unpause.action(), Action::Unpause);
+}
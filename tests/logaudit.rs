@@ -0,0 +1,126 @@
+use dsd_util::logaudit::{
+    is_on_mount, log_files_size, parse_max_size, render_logging_override, shared_lines, LogConfig,
+};
+
+fn log_config(driver: &str, max_size: Option<&str>, max_file: Option<&str>) -> LogConfig {
+    LogConfig {
+        container: "web-app-1".to_string(),
+        stack: Some("web".to_string()),
+        service: Some("app".to_string()),
+        driver: driver.to_string(),
+        max_size: max_size.map(String::from),
+        max_file: max_file.map(String::from),
+        log_path: String::new(),
+        running: true,
+        mounts: vec![],
+    }
+}
+
+#[test]
+fn rotation() {
+    assert!(!log_config("json-file", None, None).is_rotated());
+    assert!(!log_config("json-file", None, Some("3")).is_rotated());
+    assert!(log_config("json-file", Some("10m"), None).is_rotated());
+    assert!(log_config("local", None, None).is_rotated());
+    assert!(log_config("journald", None, None).is_rotated());
+
+    assert_eq!(log_config("json-file", None, None).rotation(), None);
+    assert_eq!(
+        log_config("json-file", Some("10m"), Some("3"))
+            .rotation()
+            .as_deref(),
+        Some("max-size=10m max-file=3")
+    );
+}
+
+#[test]
+fn max_size() {
+    assert_eq!(parse_max_size("10m").unwrap(), "10m");
+    assert_eq!(parse_max_size("1g").unwrap(), "1g");
+    assert!(parse_max_size("0").is_err());
+    assert!(parse_max_size("ten").is_err());
+}
+
+#[test]
+fn logging_override() {
+    let rendered = render_logging_override(None, &["app".to_string()], "10m", 3).unwrap();
+    let document: serde_json::Value = serde_yaml::from_str(&rendered).unwrap();
+    let logging = &document["services"]["app"]["logging"];
+
+    assert_eq!(logging["driver"], "json-file");
+    assert_eq!(logging["options"]["max-size"], "10m");
+    assert_eq!(logging["options"]["max-file"], "3");
+
+    // services of an earlier fix are kept
+    let rendered = render_logging_override(Some(&rendered), &["db".to_string()], "20m", 5).unwrap();
+    let document: serde_json::Value = serde_yaml::from_str(&rendered).unwrap();
+
+    assert_eq!(
+        document["services"]["app"]["logging"]["options"]["max-size"],
+        "10m"
+    );
+    assert_eq!(
+        document["services"]["db"]["logging"]["options"]["max-file"],
+        "5"
+    );
+}
+
+#[test]
+fn rotated_files_are_counted() {
+    let dir = std::env::temp_dir().join(format!("dsd-util-logaudit-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+
+    let log_path = dir.join("abc-json.log");
+    std::fs::write(&log_path, [0; 100]).unwrap();
+    std::fs::write(dir.join("abc-json.log.1"), [0; 50]).unwrap();
+    std::fs::write(dir.join("abc-json.log.2.gz"), [0; 25]).unwrap();
+    std::fs::write(dir.join("abc-json.logs"), [0; 10]).unwrap();
+
+    let size = log_files_size(log_path.to_str().unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+
+    assert_eq!(size, Some(175));
+    assert_eq!(log_files_size("/nonexistent/abc-json.log"), None);
+}
+
+#[test]
+fn files_on_mounts() {
+    let mounts = ["/config".to_string(), "/app/logs/".to_string()];
+
+    assert!(is_on_mount("/config/logs/app.log", &mounts));
+    assert!(is_on_mount("/app/logs/access.log", &mounts));
+    assert!(!is_on_mount("/configs/app.log", &mounts));
+    assert!(!is_on_mount("/var/log/app.log", &mounts));
+    assert!(is_on_mount("/var/log/app.log", &["/".to_string()]));
+}
+
+#[test]
+fn lines_shared_with_stdout() {
+    let lines = |lines: &[&str]| {
+        lines
+            .iter()
+            .map(|line| line.to_string())
+            .collect::<Vec<String>>()
+    };
+    let stdout = lines(&[
+        "2026-10-16 04:00:01 INFO started",
+        "2026-10-16 04:00:02 INFO listening on :8080",
+        "2026-10-16 04:00:03 WARN slow request",
+    ]);
+
+    assert_eq!(
+        shared_lines(
+            &stdout,
+            &lines(&[
+                "2026-10-16 04:00:01 INFO started",
+                "2026-10-16 04:00:02 INFO listening on :8080  ",
+                "",
+                "",
+                "2026-10-16 04:00:03 WARN slow request",
+                "2026-10-16 04:00:03 WARN slow request",
+            ])
+        ),
+        3
+    );
+    assert_eq!(shared_lines(&stdout, &lines(&["GET /health 200", ""])), 0);
+}
@@ -0,0 +1,309 @@
+use dsd_util::logger::{
+    alert_sequence, parse_log_level, JsonMode, LogAlert, LogFormat, LogLevel, LogThrottle,
+    LogWindow, RecordBuffer, RecordStart, Throttler, DEFAULT_RECORD_START,
+};
+use std::time::{Duration, Instant};
+
+#[test]
+fn alternatives_ignore_case() {
+    let alert = LogAlert::parse("PANIC|FATAL").unwrap();
+
+    assert!(alert.matches("thread 'main' panicked: PANIC in handler"));
+    assert!(alert.matches("level=fatal msg=\"database unreachable\""));
+    assert!(!alert.matches("level=error msg=\"retrying\""));
+
+    assert!(LogAlert::parse("").is_err());
+    assert!(LogAlert::parse("||").is_err());
+}
+
+#[test]
+fn bursts_raise_one_alert_per_container() {
+    let mut alert = LogAlert::parse("FATAL").unwrap();
+    let now = Instant::now();
+
+    assert!(alert
+        .check("web-app-1", "FATAL: out of memory", now)
+        .is_some());
+    assert!(alert
+        .check(
+            "web-app-1",
+            "FATAL: out of memory",
+            now + Duration::from_secs(1)
+        )
+        .is_none());
+    assert!(alert
+        .check(
+            "web-db-1",
+            "FATAL: out of memory",
+            now + Duration::from_secs(1)
+        )
+        .is_some());
+    assert!(alert
+        .check("web-app-1", "all good", now + Duration::from_secs(30))
+        .is_none());
+    assert!(alert
+        .check("web-app-1", "FATAL: again", now + Duration::from_secs(30))
+        .is_some());
+}
+
+#[test]
+fn alert_sequences() {
+    assert_eq!(
+        alert_sequence("web-app-1", "FATAL; giving up\x1b[31m"),
+        "\x07\x1b]9;web-app-1: FATAL, giving up[31m\x1b\\\x1b]777;notify;web-app-1;FATAL, giving up[31m\x1b\\"
+    );
+}
+
+#[test]
+fn levels_are_detected() {
+    assert_eq!(
+        LogLevel::detect(r#"{"level":"warn","msg":"disk almost full"}"#),
+        Some(LogLevel::Warn)
+    );
+    assert_eq!(
+        LogLevel::detect(r#"{"level":50,"msg":"request failed"}"#),
+        Some(LogLevel::Error)
+    );
+    assert_eq!(
+        LogLevel::detect(r#"time=2025-06-01T12:00:00Z level=info msg="started""#),
+        Some(LogLevel::Info)
+    );
+    assert_eq!(
+        LogLevel::detect("2025-06-01 12:00:00,123 ERROR [main] connection refused"),
+        Some(LogLevel::Error)
+    );
+    assert_eq!(
+        LogLevel::detect("2025/06/01 12:00:00 [crit] 29#29: worker exited"),
+        Some(LogLevel::Fatal)
+    );
+    assert_eq!(
+        LogLevel::detect("WARNING: no config found"),
+        Some(LogLevel::Warn)
+    );
+
+    assert_eq!(LogLevel::detect("finished without error"), None);
+    assert_eq!(LogLevel::detect(r#"{"msg":"no level"}"#), None);
+}
+
+#[test]
+fn min_level_drops_less_severe_lines() {
+    let window = LogWindow {
+        min_level: Some(parse_log_level("warning").unwrap()),
+        ..LogWindow::default()
+    };
+
+    assert!(window.shows_level("ERROR connection refused"));
+    assert!(window.shows_level("level=warn msg=slow"));
+    assert!(!window.shows_level("INFO listening on :8080"));
+    assert!(!window.shows_level("listening on :8080"));
+
+    assert!(LogWindow::default().shows_level("listening on :8080"));
+    assert!(parse_log_level("loud").is_err());
+}
+
+#[test]
+fn level_placeholder() {
+    let format = LogFormat {
+        template: "{level} {container}: {line}".to_string(),
+        ..LogFormat::default()
+    };
+
+    assert_eq!(
+        format.render("web", "ERROR boom", Duration::ZERO, 3, false),
+        "ERROR web: ERROR boom"
+    );
+    assert_eq!(
+        format.render("web", "started", Duration::ZERO, 3, false),
+        " web: started"
+    );
+
+    let aligned = LogFormat {
+        align: true,
+        ..format
+    };
+    assert_eq!(
+        aligned.render("web", "level=warn slow", Duration::ZERO, 3, false),
+        "WARN  web: level=warn slow"
+    );
+}
+
+#[test]
+fn json_fields_are_extracted() {
+    let fields = JsonMode::Fields(vec![
+        "ts".to_string(),
+        "level".to_string(),
+        "http.status".to_string(),
+        "msg".to_string(),
+    ]);
+    let line = r#"{"ts":"12:00:01","level":"info","msg":"GET /","http":{"status":200}}"#;
+
+    assert_eq!(
+        fields.render(line),
+        Some("12:00:01 info 200 GET /".to_string())
+    );
+    assert_eq!(fields.render(r#"{"other":1}"#), None);
+    assert_eq!(fields.render("plain text"), None);
+    assert_eq!(fields.render("[1, 2]"), None);
+
+    assert_eq!(
+        JsonMode::Pretty.render(r#"{"msg":"hi"}"#),
+        Some("{\n  \"msg\": \"hi\"\n}".to_string())
+    );
+}
+
+#[test]
+fn non_json_lines_stay_raw() {
+    let format = LogFormat {
+        template: "{line}".to_string(),
+        json: Some(JsonMode::Fields(vec!["msg".to_string()])),
+        ..LogFormat::default()
+    };
+
+    assert_eq!(
+        format.render(
+            "web",
+            r#"{"level":"error","msg":"boom"}"#,
+            Duration::ZERO,
+            3,
+            false
+        ),
+        "boom"
+    );
+    assert_eq!(
+        format.render("web", "not json {", Duration::ZERO, 3, false),
+        "not json {"
+    );
+}
+
+#[test]
+fn continuation_lines_join_their_record() {
+    let start = RecordStart::parse(DEFAULT_RECORD_START).unwrap();
+    let mut record = RecordBuffer::default();
+    let now = Instant::now();
+
+    assert_eq!(
+        record.push(
+            "Exception in thread \"main\" java.lang.IllegalStateException".to_string(),
+            &start,
+            now
+        ),
+        None
+    );
+    assert_eq!(
+        record.push(
+            "\tat com.example.App.main(App.java:12)".to_string(),
+            &start,
+            now
+        ),
+        None
+    );
+    assert_eq!(
+        record.push("started worker".to_string(), &start, now),
+        Some(
+            "Exception in thread \"main\" java.lang.IllegalStateException\n\tat com.example.App.main(App.java:12)"
+                .to_string()
+        )
+    );
+
+    assert!(!record.is_idle(now));
+    assert!(record.is_idle(now + Duration::from_secs(1)));
+    assert_eq!(record.flush(), Some("started worker".to_string()));
+    assert_eq!(record.flush(), None);
+    assert!(!record.is_idle(now + Duration::from_secs(1)));
+}
+
+#[test]
+fn records_start_at_timestamps() {
+    let start = RecordStart::parse(r"^\d{4}-\d{2}-\d{2}").unwrap();
+    let mut record = RecordBuffer::default();
+    let now = Instant::now();
+
+    record.push("2025-06-01 12:00:00 ERROR failed".to_string(), &start, now);
+    // not indented, yet a continuation of the record
+    record.push("Caused by: java.io.IOException".to_string(), &start, now);
+    assert_eq!(
+        record.push("2025-06-01 12:00:01 INFO retrying".to_string(), &start, now),
+        Some("2025-06-01 12:00:00 ERROR failed\nCaused by: java.io.IOException".to_string())
+    );
+
+    assert!(RecordStart::parse("(").is_err());
+}
+
+#[test]
+fn repeated_lines_collapse_into_a_count() {
+    let mut throttler = Throttler::new(LogThrottle {
+        dedupe: true,
+        max_lines: None,
+        window: Duration::from_secs(10),
+    });
+    let now = Instant::now();
+
+    assert_eq!(throttler.admit("web", "crashed", now), (true, vec![]));
+    assert_eq!(throttler.admit("web", "crashed", now), (false, vec![]));
+    assert_eq!(throttler.admit("web", "crashed", now), (false, vec![]));
+    // other containers are counted on their own
+    assert_eq!(throttler.admit("db", "crashed", now), (true, vec![]));
+
+    assert_eq!(
+        throttler.admit("web", "restarting", now),
+        (true, vec!["last message repeated 2 times".to_string()])
+    );
+
+    // repeats still going on are reported once the window ends
+    throttler.admit("web", "restarting", now);
+    assert!(throttler.due(now, false).is_empty());
+    assert_eq!(
+        throttler.due(now + Duration::from_secs(10), false),
+        [(
+            "web".to_string(),
+            "last message repeated 1 times".to_string()
+        )]
+    );
+    assert_eq!(
+        throttler.admit("web", "restarting", now + Duration::from_secs(11)),
+        (false, vec![])
+    );
+}
+
+#[test]
+fn lines_over_the_limit_are_counted() {
+    let mut throttler = Throttler::new(LogThrottle {
+        dedupe: false,
+        max_lines: Some(2),
+        window: Duration::from_secs(10),
+    });
+    let now = Instant::now();
+
+    assert!(throttler.admit("web", "one", now).0);
+    assert!(throttler.admit("web", "two", now).0);
+    assert!(!throttler.admit("web", "three", now).0);
+    assert!(!throttler.admit("web", "four", now).0);
+    assert!(throttler.admit("db", "one", now).0);
+
+    // a new window passes lines again, after telling how many were held back
+    assert_eq!(
+        throttler.admit("web", "five", now + Duration::from_secs(10)),
+        (
+            true,
+            vec!["2 lines suppressed, more than 2 per 10s".to_string()]
+        )
+    );
+
+    assert!(
+        throttler
+            .admit("web", "six", now + Duration::from_secs(10))
+            .0
+    );
+    assert!(
+        !throttler
+            .admit("web", "seven", now + Duration::from_secs(10))
+            .0
+    );
+    assert_eq!(
+        throttler.due(now + Duration::from_secs(11), true),
+        [(
+            "web".to_string(),
+            "1 lines suppressed, more than 2 per 10s".to_string()
+        )]
+    );
+}
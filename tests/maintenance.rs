@@ -0,0 +1,52 @@
+use dsd_util::docker::Container;
+use dsd_util::maintenance::{running_stacks, FrozenStack, MaintenanceState};
+
+fn container(name: &str, stack: Option<&str>) -> Container {
+    Container {
+        id: format!("{name}-id"),
+        name: name.to_string(),
+        image: "alpine".to_string(),
+        stack: stack.map(String::from),
+        service: None,
+    }
+}
+
+#[test]
+fn stacks_of_running_containers() {
+    let containers = [
+        container("web-app-1", Some("web")),
+        container("media-jellyfin-1", Some("media")),
+        container("web-db-1", Some("web")),
+        container("portainer", None),
+    ];
+
+    assert_eq!(running_stacks(&containers), ["web", "media"]);
+    assert!(running_stacks(&[]).is_empty());
+}
+
+#[test]
+fn state() {
+    let state = MaintenanceState {
+        started_at: "2026-10-16T04:00:00".to_string(),
+        stacks: vec![
+            FrozenStack {
+                stack: "web".to_string(),
+                containers: vec!["web-db-1".to_string(), "web-app-1".to_string()],
+            },
+            FrozenStack {
+                stack: "media".to_string(),
+                containers: vec!["media-jellyfin-1".to_string()],
+            },
+        ],
+    };
+
+    assert_eq!(state.container_count(), 3);
+    assert_eq!(state.stack_names(), ["web", "media"]);
+
+    // the start order survives being written to disk
+    let json = serde_json::to_string(&state).unwrap();
+    assert_eq!(
+        serde_json::from_str::<MaintenanceState>(&json).unwrap(),
+        state
+    );
+}
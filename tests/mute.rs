@@ -0,0 +1,59 @@
+use chrono::{DateTime, Utc};
+use dsd_util::mute::{add_mute, find_mute, Mute};
+
+fn time(timestamp: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .expect("timestamp is valid")
+        .with_timezone(&Utc)
+}
+
+fn mute(container: &str, until: &str) -> Mute {
+    Mute {
+        container: container.to_string(),
+        until: time(until),
+        reason: Some("migrating".to_string()),
+    }
+}
+
+#[test]
+fn mutes_expire() {
+    let mutes = [mute("web-app-1", "2025-06-01T14:00:00Z")];
+
+    assert!(find_mute(&mutes, "web-app-1", time("2025-06-01T13:59:59Z")).is_some());
+    assert!(find_mute(&mutes, "web-app-1", time("2025-06-01T14:00:00Z")).is_none());
+    assert!(find_mute(&mutes, "web-db-1", time("2025-06-01T12:00:00Z")).is_none());
+}
+
+#[test]
+fn patterns() {
+    let mutes = [mute("web-*", "2025-06-01T14:00:00Z")];
+    let now = time("2025-06-01T12:00:00Z");
+
+    assert!(find_mute(&mutes, "web-app-1", now).is_some());
+    assert!(find_mute(&mutes, "web-db-1", now).is_some());
+    assert!(find_mute(&mutes, "blog-app-1", now).is_none());
+}
+
+#[test]
+fn adding_replaces_and_drops_expired() {
+    let now = time("2025-06-01T12:00:00Z");
+    let mut mutes = vec![
+        mute("web-app-1", "2025-06-01T13:00:00Z"),
+        mute("web-db-1", "2025-06-01T11:00:00Z"),
+    ];
+
+    add_mute(&mut mutes, mute("web-app-1", "2025-06-01T18:00:00Z"), now);
+
+    assert_eq!(mutes, [mute("web-app-1", "2025-06-01T18:00:00Z")]);
+}
+
+#[test]
+fn exact_mutes_come_first() {
+    let mutes = [
+        mute("web-*", "2025-06-01T13:00:00Z"),
+        mute("web-app-1", "2025-06-01T18:00:00Z"),
+    ];
+
+    let found = find_mute(&mutes, "web-app-1", time("2025-06-01T12:00:00Z"));
+    assert_eq!(found.map(|mute| mute.container.as_str()), Some("web-app-1"));
+}
@@ -0,0 +1,135 @@
+use dsd_util::config::{ChannelConfig, RouteConfig, WebhookFormat};
+use dsd_util::notify::{route, Digest, Event, EventSource, Notifier, Severity};
+use dsd_util::watch::WatchEvent;
+use std::collections::BTreeMap;
+use std::time::{Duration, Instant};
+
+#[test]
+fn digests() {
+    let start = Instant::now();
+    let mut digest = Digest::new(Duration::from_secs(3_600));
+
+    assert_eq!(digest.take_due(start), None);
+
+    digest.push("web-app-1 is unhealthy", start);
+    digest.push("web-db-1 is unhealthy", start + Duration::from_secs(1_800));
+
+    // the interval starts with the first collected message
+    assert_eq!(digest.take_due(start + Duration::from_secs(3_599)), None);
+    assert_eq!(
+        digest.take_due(start + Duration::from_secs(3_600)).as_deref(),
+        Some(
+            "2 notification(s) in the last 60 minutes:\n- web-app-1 is unhealthy\n- web-db-1 is unhealthy"
+        )
+    );
+
+    // nothing is left after a digest is taken
+    assert_eq!(digest.take_due(start + Duration::from_secs(7_200)), None);
+    assert_eq!(digest.take(), None);
+
+    digest.push("web-app-1 is unhealthy", start + Duration::from_secs(7_200));
+    assert!(digest.take().is_some());
+}
+
+#[test]
+fn severities() {
+    let exited = WatchEvent::Exited {
+        container: "web-app-1".to_string(),
+        status: "exited".to_string(),
+    };
+    let unhealthy = WatchEvent::Unhealthy {
+        container: "web-app-1".to_string(),
+    };
+
+    assert_eq!(exited.severity(), Severity::Critical);
+    assert_eq!(unhealthy.severity(), Severity::Warning);
+    assert!(Severity::Warning < Severity::Critical);
+}
+
+fn channel(url: &str) -> ChannelConfig {
+    ChannelConfig {
+        url: url.to_string(),
+        format: WebhookFormat::Ntfy,
+        digest_interval: None,
+        chat_id: None,
+        from: None,
+        to: vec![],
+    }
+}
+
+fn rule(severity: Option<Severity>, source: Option<EventSource>, channels: &[&str]) -> RouteConfig {
+    RouteConfig {
+        severity,
+        source,
+        channels: channels.iter().map(|channel| channel.to_string()).collect(),
+    }
+}
+
+#[test]
+fn routing() {
+    let routes = vec![
+        rule(Some(Severity::Info), Some(EventSource::Update), &["chat"]),
+        rule(Some(Severity::Critical), None, &["chat", "pager"]),
+        rule(Some(Severity::Info), None, &[]),
+    ];
+    let notifier = Notifier::new(
+        BTreeMap::from([
+            ("chat".to_string(), channel("https://ntfy.sh/chat")),
+            ("pager".to_string(), channel("https://ntfy.sh/pager")),
+        ]),
+        routes.clone(),
+    )
+    .unwrap();
+
+    let update = Event::new(EventSource::Update, Severity::Info, "pulled web-app-1");
+    let audit = Event::new(EventSource::Audit, Severity::Info, "audit passed");
+    let exited = Event::new(EventSource::Watch, Severity::Critical, "web-app-1 exited");
+    let unhealthy = Event::new(EventSource::Watch, Severity::Warning, "web-app-1 unhealthy");
+
+    // the first matching rule wins
+    assert_eq!(
+        route(&routes, &update),
+        Some(["chat".to_string()].as_slice())
+    );
+    assert_eq!(notifier.channels_for(&update), ["chat"]);
+    assert_eq!(notifier.channels_for(&audit), Vec::<&str>::new());
+    assert_eq!(notifier.channels_for(&exited), ["chat", "pager"]);
+
+    // without a matching rule, warnings go to every channel
+    assert_eq!(route(&routes, &unhealthy), None);
+    assert_eq!(notifier.channels_for(&unhealthy), ["chat", "pager"]);
+}
+
+#[test]
+fn routes_without_rules() {
+    let notifier = Notifier::new(
+        BTreeMap::from([("chat".to_string(), channel("https://ntfy.sh/chat"))]),
+        vec![],
+    )
+    .unwrap();
+
+    let info = Event::new(EventSource::Update, Severity::Info, "pulled web-app-1");
+    assert!(notifier.channels_for(&info).is_empty());
+}
+
+#[test]
+fn invalid_channels() {
+    let unknown = Notifier::new(
+        BTreeMap::new(),
+        vec![rule(Some(Severity::Critical), None, &["telegram"])],
+    );
+    assert!(unknown.is_err());
+
+    let telegram = ChannelConfig {
+        format: WebhookFormat::Telegram,
+        ..channel("https://api.telegram.org/bot123:abc/sendMessage")
+    };
+    assert!(Notifier::new(BTreeMap::from([("telegram".to_string(), telegram)]), vec![]).is_err());
+
+    let email = ChannelConfig {
+        format: WebhookFormat::Email,
+        from: Some("dsd-util@example.com".to_string()),
+        ..channel("smtps://smtp.example.com")
+    };
+    assert!(Notifier::new(BTreeMap::from([("email".to_string(), email)]), vec![]).is_err());
+}
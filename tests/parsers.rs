@@ -0,0 +1,295 @@
+use chrono::{DateTime, Utc};
+use dsd_util::parsers::{
+    parse_container_list, parse_event, parse_graph_containers, parse_image_inspect,
+    parse_image_metadata, parse_inspect_output, parse_log_configs, parse_manifest_size,
+    parse_prune_containers, parse_pull_progress, parse_stats_output, parse_volume_sizes,
+};
+use dsd_util::units::parse_duration;
+use std::path::{Path, PathBuf};
+
+/// Time used as "now" when parsing uptimes, so golden files do not change over time
+const FIXTURE_NOW: &str = "2025-06-01T12:00:00Z";
+
+/// Set to regenerate golden files after an intentional change in parser output
+const ENV_UPDATE_GOLDEN: &str = "UPDATE_GOLDEN";
+
+fn fixtures_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests")
+        .join("fixtures")
+}
+
+/// Captured output of every docker version fixtures exist for
+fn docker_versions() -> Vec<PathBuf> {
+    let mut versions = std::fs::read_dir(fixtures_dir())
+        .expect("fixtures directory exists")
+        .map(|entry| entry.expect("fixture entry is readable").path())
+        .filter(|path| path.is_dir())
+        .collect::<Vec<PathBuf>>();
+    versions.sort();
+    versions
+}
+
+/// Compares parser output against the golden file next to the fixture
+fn assert_golden(fixture: &Path, actual: String) {
+    let golden = fixture.with_extension("golden");
+
+    if std::env::var_os(ENV_UPDATE_GOLDEN).is_some() {
+        std::fs::write(&golden, &actual).expect("golden file is writable");
+        return;
+    }
+
+    let expected = std::fs::read_to_string(&golden).unwrap_or_else(|_| {
+        panic!(
+            "missing golden file {}, run with {ENV_UPDATE_GOLDEN}=1 to create it",
+            golden.display()
+        )
+    });
+
+    assert_eq!(expected, actual, "output differs from {}", golden.display());
+}
+
+/// Runs a parser over the named fixture of every docker version that has it
+fn check_fixtures(file_name: &str, parse: impl Fn(&str) -> String) {
+    let mut checked = 0;
+
+    for version in docker_versions() {
+        let fixture = version.join(file_name);
+
+        let Ok(contents) = std::fs::read_to_string(&fixture) else {
+            continue;
+        };
+
+        assert_golden(&fixture, parse(&contents));
+        checked += 1;
+    }
+
+    assert!(checked > 0, "no fixtures found for {file_name}");
+}
+
+fn fixture_now() -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(FIXTURE_NOW)
+        .expect("fixture time is valid")
+        .with_timezone(&Utc)
+}
+
+#[test]
+fn stats_output() {
+    check_fixtures("stats.jsonl", |contents| {
+        format!(
+            "{:#?}\n",
+            parse_stats_output(contents).expect("stats parse")
+        )
+    });
+}
+
+#[test]
+fn inspect_output() {
+    check_fixtures("inspect.json", |contents| {
+        let inspected = parse_inspect_output(contents, fixture_now()).expect("inspect parse");
+        format!("{inspected:#?}\n")
+    });
+}
+
+#[test]
+fn pull_progress() {
+    check_fixtures("pull.txt", |contents| {
+        let progress = contents
+            .lines()
+            .map(parse_pull_progress)
+            .collect::<Vec<_>>();
+        format!("{progress:#?}\n")
+    });
+}
+
+#[test]
+fn events() {
+    check_fixtures("events.jsonl", |contents| {
+        let events = contents
+            .lines()
+            .map(|line| parse_event(line).expect("event parse"))
+            .collect::<Vec<_>>();
+        format!("{events:#?}\n")
+    });
+}
+
+#[test]
+fn container_inspect() {
+    check_fixtures("container-inspect.json", |contents| {
+        let containers =
+            parse_graph_containers(contents.as_bytes()).expect("container inspect parse");
+        format!("{containers:#?}\n")
+    });
+}
+
+#[test]
+fn container_list() {
+    check_fixtures("ps.txt", |contents| {
+        let containers = parse_container_list(contents).expect("container list parse");
+        format!("{containers:#?}\n")
+    });
+}
+
+#[test]
+fn volume_sizes() {
+    check_fixtures("system-df.json", |contents| {
+        let sizes = parse_volume_sizes(contents.as_bytes()).expect("disk usage parse");
+        format!("{sizes:#?}\n")
+    });
+}
+
+#[test]
+fn manifest_sizes() {
+    check_fixtures("manifest-inspect.json", |contents| {
+        let sizes = ["linux/amd64", "linux/arm64", "windows/amd64"].map(|platform| {
+            let size = parse_manifest_size(contents.as_bytes(), platform).expect("manifest parse");
+            (platform, size)
+        });
+        format!("{sizes:#?}\n")
+    });
+
+    // single-platform images only list their own manifest
+    let size = parse_manifest_size(
+        br#"{"Descriptor":{"size":528},"SchemaV2Manifest":{"config":{"size":1000},"layers":[{"size":2000},{"size":3000}]}}"#,
+        "linux/arm64",
+    )
+    .expect("manifest parse");
+    assert_eq!(size, Some(6_000));
+}
+
+#[test]
+fn disk_usage_inspect() {
+    let containers = parse_prune_containers(
+        br#"[{"Id":"abc","Name":"/web-app-1","Image":"sha256:1a2b","LogPath":"/var/lib/docker/containers/abc/abc-json.log","State":{"Status":"exited"},"Config":{"Image":"ghcr.io/acme/app:2","Labels":{"com.docker.compose.project":"web"}},"Mounts":[{"Type":"volume","Name":"web_data"},{"Type":"bind","Source":"/srv"}]}]"#,
+    )
+    .expect("container inspect parse");
+
+    assert_eq!(containers[0].name, "web-app-1");
+    assert_eq!(containers[0].stack.as_deref(), Some("web"));
+    assert_eq!(containers[0].image, "ghcr.io/acme/app:2");
+    assert_eq!(containers[0].volumes, ["web_data"]);
+    assert!(containers[0].is_stopped());
+
+    let images = parse_image_inspect(
+        br#"[{"Id":"sha256:1a2b","Size":52428800,"RepoTags":[],"RepoDigests":["ghcr.io/acme/app@sha256:9f8e"]}]"#,
+    )
+    .expect("image inspect parse");
+
+    assert_eq!(images[0].size, 52_428_800);
+    assert!(images[0].repo_tags.is_empty());
+    assert_eq!(images[0].repo_digests, ["ghcr.io/acme/app@sha256:9f8e"]);
+}
+
+#[test]
+fn log_config_inspect() {
+    let configs = parse_log_configs(
+        br#"[{"Name":"/web-app-1","LogPath":"/var/lib/docker/containers/abc/abc-json.log","State":{"Running":true},"Mounts":[{"Type":"volume","Destination":"/config"}],"Config":{"Labels":{"com.docker.compose.project":"web","com.docker.compose.service":"app"}},"HostConfig":{"LogConfig":{"Type":"json-file","Config":{}}}},{"Name":"/portainer","LogPath":"","Config":{"Labels":{}},"HostConfig":{"LogConfig":{"Type":"json-file","Config":{"max-size":"10m","max-file":"3"}}}}]"#,
+    )
+    .expect("log config inspect parse");
+
+    assert_eq!(configs[0].container, "web-app-1");
+    assert_eq!(configs[0].stack.as_deref(), Some("web"));
+    assert_eq!(configs[0].service.as_deref(), Some("app"));
+    assert_eq!(configs[0].max_size, None);
+    assert!(!configs[0].is_rotated());
+    assert!(configs[0].running);
+    assert_eq!(configs[0].mounts, ["/config"]);
+
+    assert_eq!(configs[1].stack, None);
+    assert!(!configs[1].running);
+    assert!(configs[1].mounts.is_empty());
+    assert_eq!(configs[1].max_size.as_deref(), Some("10m"));
+    assert_eq!(configs[1].max_file.as_deref(), Some("3"));
+    assert!(configs[1].is_rotated());
+}
+
+#[test]
+fn truncated_output_does_not_panic() {
+    for version in docker_versions() {
+        for entry in std::fs::read_dir(&version).expect("fixture directory is readable") {
+            let path = entry.expect("fixture entry is readable").path();
+
+            if path
+                .extension()
+                .is_some_and(|extension| extension == "golden")
+            {
+                continue;
+            }
+
+            let contents = std::fs::read_to_string(&path).expect("fixture is readable");
+
+            for (index, _) in contents.char_indices() {
+                let truncated = &contents[..index];
+
+                let _ = parse_stats_output(truncated);
+                let _ = parse_inspect_output(truncated, fixture_now());
+                let _ = parse_graph_containers(truncated.as_bytes());
+                let _ = parse_prune_containers(truncated.as_bytes());
+                let _ = parse_image_inspect(truncated.as_bytes());
+                let _ = parse_volume_sizes(truncated.as_bytes());
+                let _ = parse_container_list(truncated);
+                for line in truncated.lines() {
+                    let _ = parse_pull_progress(line);
+                    let _ = parse_event(line);
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn inspect_without_optional_fields() {
+    let inspected = parse_inspect_output(
+        "[{\"Name\":\"/web-1\",\"State\":{\"Status\":\"created\",\"StartedAt\":\"2025-06-01T11:00:00Z\"},\"HostConfig\":null,\"NetworkSettings\":{\"Ports\":null}}]",
+        fixture_now(),
+    )
+    .expect("inspect parse");
+
+    assert_eq!(inspected[0].health, "N/A");
+    assert_eq!(inspected[0].restart_policy, "no");
+    assert_eq!(inspected[0].ports, "N/A");
+    assert_eq!(inspected[0].restart_count, 0);
+    assert!(parse_inspect_output("", fixture_now())
+        .expect("empty output")
+        .is_empty());
+}
+
+#[test]
+fn malformed_lines_are_errors() {
+    assert!(parse_stats_output("NAME CPU % MEM %\nweb-1 0.5%").is_err());
+    assert!(parse_stats_output("{\"CPUPerc\":\"0.5%\"}").is_err());
+    assert!(parse_stats_output("{\"Name\":\"web-1\",\"CPUPerc\":\"half\"}").is_err());
+    assert!(parse_stats_output("{\"Name\":\"web-1\",\"MemUsage\":\"12MiB\"}").is_err());
+    assert!(parse_inspect_output("/web-1,running,no", fixture_now()).is_err());
+    assert!(parse_inspect_output("[{\"Name\":\"/web-1\"}]", fixture_now()).is_err());
+    assert!(parse_inspect_output(
+        "[{\"Name\":\"/web-1\",\"State\":{\"Status\":\"running\",\"StartedAt\":\"yesterday\"}}]",
+        fixture_now()
+    )
+    .is_err());
+    assert!(parse_event("{\"Type\":\"container\"}").is_err());
+    assert!(parse_duration("99999999999999999999d").is_err());
+    assert!(parse_duration("999999999999999d").is_err());
+}
+
+#[test]
+fn image_metadata_inspect() {
+    let images = parse_image_metadata(
+        br#"[{"Id":"sha256:1a2b","Created":"2026-10-15T12:00:00.123Z","Size":52428800,"Config":{"Labels":{"org.opencontainers.image.version":"1.5.0","org.opencontainers.image.revision":""}}},{"Id":"sha256:3c4d","Created":"2026-09-01T08:00:00Z","Size":1024,"Config":{"Labels":null}}]"#,
+    )
+    .expect("image metadata parse");
+
+    assert_eq!(images[0].id, "sha256:1a2b");
+    assert_eq!(
+        images[0].created.as_deref(),
+        Some("2026-10-15T12:00:00.123Z")
+    );
+    assert_eq!(images[0].version.as_deref(), Some("1.5.0"));
+    assert_eq!(images[0].revision, None);
+    assert_eq!(images[0].size, 52_428_800);
+    assert_eq!(images[1].version, None);
+
+    // docker prints nothing on stdout when none of the images exist
+    assert!(parse_image_metadata(b"[]\n").unwrap().is_empty());
+    assert!(parse_image_metadata(b"").unwrap().is_empty());
+}
@@ -0,0 +1,129 @@
+use dsd_util::error::DsdError;
+use dsd_util::image::ImageRef;
+use dsd_util::pin::{
+    pinned_reference, render_pin_override, select_repo_digest, unpinned_reference,
+};
+use dsd_util::utils::standalone_run_args;
+use serde_json::json;
+
+fn parse(reference: &str) -> ImageRef {
+    ImageRef::parse(reference).expect("image reference parses")
+}
+
+#[test]
+fn references() {
+    let image = parse("nginx:1.27");
+    let pinned = pinned_reference(&image, "sha256:9f8e");
+
+    assert_eq!(pinned, "nginx:1.27@sha256:9f8e");
+    assert_eq!(unpinned_reference(&parse(&pinned)), "nginx:1.27");
+    assert_eq!(
+        pinned_reference(&parse("ghcr.io/acme/app"), "sha256:9f8e"),
+        "ghcr.io/acme/app@sha256:9f8e"
+    );
+}
+
+#[test]
+fn repo_digest_of_the_repository() {
+    let repo_digests = [
+        "ghcr.io/acme/nginx@sha256:1111".to_string(),
+        "nginx@sha256:2222".to_string(),
+    ];
+
+    assert_eq!(
+        select_repo_digest(&parse("docker.io/library/nginx:1.27"), &repo_digests).as_deref(),
+        Some("sha256:2222")
+    );
+    assert_eq!(
+        select_repo_digest(&parse("ghcr.io/acme/nginx:1"), &repo_digests).as_deref(),
+        Some("sha256:1111")
+    );
+    assert_eq!(select_repo_digest(&parse("caddy:2"), &repo_digests), None);
+}
+
+#[test]
+fn overrides_keep_other_services() {
+    let pinned = render_pin_override(None, "app", Some("nginx:1.27@sha256:9f8e")).unwrap();
+    let both = render_pin_override(Some(&pinned), "db", Some("postgres:16@sha256:1111")).unwrap();
+    let unpinned = render_pin_override(Some(&both), "app", None).unwrap();
+
+    let document: serde_json::Value = serde_yaml::from_str(&unpinned).unwrap();
+    assert_eq!(
+        document,
+        serde_json::json!({ "services": { "db": { "image": "postgres:16@sha256:1111" } } })
+    );
+
+    // the last service unpinned still leaves a valid override
+    let empty: serde_json::Value =
+        serde_yaml::from_str(&render_pin_override(Some(&unpinned), "db", None).unwrap()).unwrap();
+    assert_eq!(empty, serde_json::json!({ "services": {} }));
+}
+
+#[test]
+fn standalone_containers_keep_their_settings() {
+    let image = json!({
+        "Entrypoint": ["/docker-entrypoint.sh"],
+        "Cmd": ["nginx", "-g", "daemon off;"],
+        "User": "",
+        "WorkingDir": "/",
+    });
+    let container = json!({
+        "Config": {
+            "Entrypoint": ["/docker-entrypoint.sh"],
+            "Cmd": ["nginx", "-g", "daemon off;"],
+            "User": "101",
+            "WorkingDir": "/",
+        },
+        "HostConfig": {
+            "Privileged": true,
+            "CapAdd": ["NET_ADMIN"],
+            "Devices": [{"PathOnHost": "/dev/ttyUSB0", "PathInContainer": "/dev/ttyUSB0", "CgroupPermissions": "rwm"}],
+            "Dns": [],
+        },
+    });
+
+    let args = standalone_run_args("web", &container, &image, "nginx:1.28").unwrap();
+    assert_eq!(
+        args,
+        [
+            "run",
+            "-d",
+            "--name",
+            "web",
+            "--privileged",
+            "--cap-add",
+            "NET_ADMIN",
+            "--device",
+            "/dev/ttyUSB0:/dev/ttyUSB0:rwm",
+            "--user",
+            "101",
+            "nginx:1.28",
+        ]
+    );
+
+    // a command of its own is passed, and an entrypoint is split into program and arguments
+    let mut custom = container.clone();
+    custom["Config"]["Cmd"] = json!(["nginx", "-T"]);
+    let args = standalone_run_args("web", &custom, &image, "nginx:1.28").unwrap();
+    assert!(args.ends_with(&["nginx:1.28".into(), "nginx".into(), "-T".into()]));
+
+    custom["Config"]["Entrypoint"] = json!(["/bin/sh", "-c"]);
+    custom["Config"]["Cmd"] = json!(["exec nginx"]);
+    let args = standalone_run_args("web", &custom, &image, "nginx:1.28").unwrap();
+    assert!(args
+        .windows(2)
+        .any(|pair| pair == ["--entrypoint", "/bin/sh"]));
+    assert!(args.ends_with(&["nginx:1.28".into(), "-c".into(), "exec nginx".into()]));
+
+    // settings that cannot be carried over stop the recreation
+    let mut tmpfs = container.clone();
+    tmpfs["HostConfig"]["Tmpfs"] = json!({"/run": ""});
+    assert!(matches!(
+        standalone_run_args("web", &tmpfs, &image, "nginx:1.28"),
+        Err(DsdError::RecreateUnsupported { setting, .. }) if setting == "HostConfig.Tmpfs"
+    ));
+
+    let mut no_command = container.clone();
+    no_command["Config"]["Cmd"] = json!(null);
+    assert!(standalone_run_args("web", &no_command, &image, "nginx:1.28").is_err());
+}
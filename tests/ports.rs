@@ -0,0 +1,123 @@
+use dsd_util::docker::Container;
+use dsd_util::ports::{find_conflicts, port_mappings};
+use dsd_util::utils::{InspectData, PortBinding};
+
+fn container(name: &str, stack: &str, service: &str) -> Container {
+    Container {
+        id: format!("{name}-id"),
+        name: name.to_string(),
+        image: "nginx:1.27".to_string(),
+        stack: Some(stack.to_string()),
+        service: Some(service.to_string()),
+    }
+}
+
+fn inspected(name: &str, bindings: &[(&str, &str, &str)]) -> InspectData {
+    InspectData {
+        container_name: name.to_string(),
+        status: "running".to_string(),
+        restart_policy: "unless-stopped".to_string(),
+        health: "N/A".to_string(),
+        uptime: "1h".to_string(),
+        started_at: "2025-06-01T12:00:00Z".to_string(),
+        restart_count: 0,
+        ports: "N/A".to_string(),
+        port_bindings: bindings
+            .iter()
+            .map(|(container_port, host_ip, host_port)| PortBinding {
+                container_port: container_port.to_string(),
+                host_ip: host_ip.to_string(),
+                host_port: host_port.to_string(),
+            })
+            .collect(),
+        networks: vec!["proxy".to_string()],
+    }
+}
+
+#[test]
+fn mappings() {
+    let containers = [container("web-nginx-1", "web", "nginx")];
+    let mappings = port_mappings(
+        &containers,
+        &[inspected(
+            "web-nginx-1",
+            &[
+                ("80/tcp", "0.0.0.0", "8080"),
+                ("80/tcp", "::", "8080"),
+                ("443/tcp", "127.0.0.1", "443"),
+                ("53/udp", "", ""),
+            ],
+        )],
+    );
+
+    // wildcard bindings are merged and ports picked by docker are left out
+    assert_eq!(mappings.len(), 2);
+    assert_eq!(mappings[0].host(), "127.0.0.1:443");
+    assert_eq!(mappings[1].host(), "8080");
+    assert_eq!(mappings[1].service.as_deref(), Some("nginx"));
+    assert_eq!(mappings[1].stack.as_deref(), Some("web"));
+    assert_eq!(mappings[1].protocol(), "tcp");
+
+    assert_eq!(
+        mappings[0].url("nas.local").as_deref(),
+        Some("https://127.0.0.1:443")
+    );
+    assert_eq!(
+        mappings[1].url("nas.local").as_deref(),
+        Some("http://nas.local:8080")
+    );
+}
+
+#[test]
+fn urls_of_tcp_ports_only() {
+    let containers = [container("dns-pihole-1", "dns", "pihole")];
+    let mappings = port_mappings(
+        &containers,
+        &[inspected(
+            "dns-pihole-1",
+            &[("53/udp", "", "53"), ("80/tcp", "fd00::2", "8081")],
+        )],
+    );
+
+    assert_eq!(mappings[0].url("localhost"), None);
+    assert_eq!(
+        mappings[1].url("localhost").as_deref(),
+        Some("http://[fd00::2]:8081")
+    );
+}
+
+#[test]
+fn conflicts() {
+    let containers = [
+        container("web-nginx-1", "web", "nginx"),
+        container("media-jellyfin-1", "media", "jellyfin"),
+        container("dns-pihole-1", "dns", "pihole"),
+    ];
+    let mappings = port_mappings(
+        &containers,
+        &[
+            inspected("web-nginx-1", &[("80/tcp", "0.0.0.0", "8080")]),
+            inspected(
+                "media-jellyfin-1",
+                &[("8096/tcp", "127.0.0.1", "8080"), ("53/udp", "", "53")],
+            ),
+            inspected(
+                "dns-pihole-1",
+                &[("53/tcp", "", "53"), ("80/tcp", "192.168.1.2", "8081")],
+            ),
+        ],
+    );
+
+    let conflicts = find_conflicts(&mappings);
+
+    // a wildcard binding overlaps every address, while tcp and udp never conflict
+    assert_eq!(conflicts.len(), 1);
+    assert_eq!(conflicts[0].port, "8080/tcp");
+    assert_eq!(
+        conflicts[0].containers,
+        [
+            ("media-jellyfin-1".to_string(), Some("media".to_string())),
+            ("web-nginx-1".to_string(), Some("web".to_string())),
+        ]
+    );
+}
@@ -0,0 +1,204 @@
+use dsd_util::config::{LinksConfig, ThemeConfig};
+use dsd_util::printer::{
+    ansi256_to_rgb, color_depth_from_env, color_from_env, hyperlinks_from_env, link_padded,
+    osc8_link, rgb_to_ansi256, set_deterministic, theme_colors, volatile, Color, ColorChoice,
+    ColorDepth,
+};
+use dsd_util::utils::format_time;
+use std::collections::HashMap;
+
+fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+    let vars = vars
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.to_string()))
+        .collect::<HashMap<String, String>>();
+
+    move |name| vars.get(name).cloned()
+}
+
+#[test]
+fn hyperlink_support() {
+    assert!(hyperlinks_from_env(env(&[("TERM_PROGRAM", "WezTerm")])));
+    assert!(hyperlinks_from_env(env(&[("TERM", "xterm-kitty")])));
+    assert!(hyperlinks_from_env(env(&[("TERM", "foot-extra")])));
+    assert!(hyperlinks_from_env(env(&[("VTE_VERSION", "7200")])));
+    assert!(hyperlinks_from_env(env(&[("WT_SESSION", "1")])));
+
+    assert!(!hyperlinks_from_env(env(&[])));
+    assert!(!hyperlinks_from_env(env(&[("TERM", "xterm-256color")])));
+    assert!(!hyperlinks_from_env(env(&[("VTE_VERSION", "4600")])));
+
+    assert!(hyperlinks_from_env(env(&[("FORCE_HYPERLINK", "1")])));
+    assert!(!hyperlinks_from_env(env(&[
+        ("FORCE_HYPERLINK", "0"),
+        ("TERM_PROGRAM", "iTerm.app")
+    ])));
+}
+
+#[test]
+fn links() {
+    assert_eq!(
+        osc8_link("http://localhost:8080", "8080"),
+        "\x1b]8;;http://localhost:8080\x1b\\8080\x1b]8;;\x1b\\"
+    );
+
+    // tests do not run in a terminal, so links fall back to padded text
+    assert_eq!(
+        link_padded(Some("http://localhost:8080"), "8080", 6),
+        "8080  "
+    );
+    assert_eq!(link_padded(None, "web-app-1", 4), "web-app-1");
+}
+
+#[test]
+fn container_links() {
+    let links = LinksConfig {
+        container: Some("http://nas.local:9334/status#{stack}/{container}".to_string()),
+        host: None,
+    };
+
+    assert_eq!(
+        links.container_url("web-app-1", Some("web")).as_deref(),
+        Some("http://nas.local:9334/status#web/web-app-1")
+    );
+    assert_eq!(
+        LinksConfig::default().container_url("web-app-1", None),
+        None
+    );
+}
+
+#[test]
+fn color_policy() {
+    assert!(color_from_env(ColorChoice::Auto, true, env(&[])));
+    assert!(!color_from_env(ColorChoice::Auto, false, env(&[])));
+
+    assert!(!color_from_env(
+        ColorChoice::Auto,
+        true,
+        env(&[("NO_COLOR", "1")])
+    ));
+    assert!(color_from_env(
+        ColorChoice::Auto,
+        true,
+        env(&[("NO_COLOR", "")])
+    ));
+    assert!(color_from_env(
+        ColorChoice::Auto,
+        false,
+        env(&[("CLICOLOR_FORCE", "1")])
+    ));
+    assert!(!color_from_env(
+        ColorChoice::Auto,
+        false,
+        env(&[("CLICOLOR_FORCE", "0")])
+    ));
+    assert!(!color_from_env(
+        ColorChoice::Auto,
+        true,
+        env(&[("NO_COLOR", "1"), ("CLICOLOR_FORCE", "1")])
+    ));
+
+    assert!(color_from_env(
+        ColorChoice::Always,
+        false,
+        env(&[("NO_COLOR", "1")])
+    ));
+    assert!(!color_from_env(
+        ColorChoice::Never,
+        true,
+        env(&[("CLICOLOR_FORCE", "1")])
+    ));
+}
+
+#[test]
+fn colors_parse() {
+    assert_eq!(Color::parse("cyan").unwrap(), Color::Cyan);
+    assert_eq!(Color::parse("Red").unwrap(), Color::Red);
+    assert_eq!(Color::parse("208").unwrap(), Color::Ansi256(208));
+    assert_eq!(Color::parse("#ff8700").unwrap(), Color::Rgb(255, 135, 0));
+
+    assert!(Color::parse("256").is_err());
+    assert!(Color::parse("#ff87").is_err());
+    assert!(Color::parse("orange").is_err());
+}
+
+#[test]
+fn colors_downgrade_to_terminal_depth() {
+    let orange = Color::Rgb(255, 135, 0);
+    assert_eq!(orange.code(ColorDepth::TrueColor), "\x1b[1;38;2;255;135;0m");
+    assert_eq!(orange.code(ColorDepth::Ansi256), "\x1b[1;38;5;208m");
+    assert_eq!(
+        orange.code(ColorDepth::Basic),
+        Color::Yellow.code(ColorDepth::Basic)
+    );
+
+    assert_eq!(
+        Color::Ansi256(21).code(ColorDepth::TrueColor),
+        "\x1b[1;38;5;21m"
+    );
+    assert_eq!(
+        Color::Ansi256(21).code(ColorDepth::Basic),
+        Color::Blue.code(ColorDepth::Basic)
+    );
+    assert_eq!(Color::Cyan.code(ColorDepth::TrueColor), "\x1b[1;36m");
+}
+
+#[test]
+fn palette_conversion() {
+    assert_eq!(rgb_to_ansi256(255, 135, 0), 208);
+    assert_eq!(rgb_to_ansi256(0, 0, 0), 16);
+    assert_eq!(rgb_to_ansi256(128, 128, 128), 244);
+    assert_eq!(rgb_to_ansi256(255, 255, 255), 231);
+
+    assert_eq!(ansi256_to_rgb(208), (255, 135, 0));
+    assert_eq!(ansi256_to_rgb(244), (128, 128, 128));
+    assert_eq!(ansi256_to_rgb(9), (255, 0, 0));
+}
+
+#[test]
+fn color_depth_detection() {
+    assert_eq!(
+        color_depth_from_env(env(&[("COLORTERM", "truecolor")])),
+        ColorDepth::TrueColor
+    );
+    assert_eq!(
+        color_depth_from_env(env(&[("TERM", "xterm-256color")])),
+        ColorDepth::Ansi256
+    );
+    assert_eq!(
+        color_depth_from_env(env(&[("TERM", "xterm")])),
+        ColorDepth::Basic
+    );
+}
+
+#[test]
+fn theme_overrides_role_colors() {
+    let theme = ThemeConfig {
+        timestamp: Some("#5f87af".to_string()),
+        error: Some("not a color".to_string()),
+        ..ThemeConfig::default()
+    };
+
+    assert_eq!(
+        theme_colors(&theme),
+        [
+            Color::Rgb(95, 135, 175),
+            Color::Green,
+            Color::Red,
+            Color::Yellow
+        ]
+    );
+}
+
+#[test]
+fn deterministic_output() {
+    let at = chrono::DateTime::from_timestamp(1_750_000_000, 0).unwrap();
+
+    set_deterministic(true);
+    assert_eq!(volatile("3.5%".to_string()), "-");
+    assert_eq!(format_time(at, "%Y-%m-%d %H:%M"), "2025-06-15 15:06 UTC");
+
+    set_deterministic(false);
+    assert_eq!(volatile("3.5%".to_string()), "3.5%");
+    assert!(!format_time(at, "%Y-%m-%d %H:%M").ends_with("UTC"));
+}
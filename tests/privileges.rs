@@ -0,0 +1,51 @@
+use dsd_util::privileges::{can_use_socket, DaemonUser};
+use std::path::PathBuf;
+
+fn user(uid: u32, groups: &[u32]) -> DaemonUser {
+    DaemonUser {
+        name: "dsd".to_string(),
+        uid,
+        gid: 1000,
+        home: PathBuf::from("/home/dsd"),
+        groups: groups.to_vec(),
+    }
+}
+
+#[test]
+fn socket_access() {
+    // root:docker srw-rw----, as docker creates it
+    let (owner, docker, mode) = (0, 998, 0o140660);
+
+    assert!(can_use_socket(
+        &user(1000, &[1000, 998]),
+        owner,
+        docker,
+        mode
+    ));
+    assert!(!can_use_socket(&user(1000, &[1000]), owner, docker, mode));
+    assert!(can_use_socket(&user(0, &[0]), owner, docker, mode));
+
+    // read access alone is not enough to talk to the daemon
+    assert!(!can_use_socket(
+        &user(1000, &[1000, 998]),
+        owner,
+        docker,
+        0o140640
+    ));
+    assert!(can_use_socket(&user(1000, &[1000]), 1000, docker, 0o140600));
+    assert!(can_use_socket(
+        &user(1000, &[1000]),
+        owner,
+        docker,
+        0o140666
+    ));
+}
+
+#[test]
+fn looks_up_users() {
+    let root = DaemonUser::lookup("root").unwrap();
+    assert_eq!(root.uid, 0);
+    assert!(root.groups.contains(&root.gid));
+
+    assert!(DaemonUser::lookup("no-such-user-dsd").is_err());
+}
@@ -0,0 +1,159 @@
+use dsd_util::prune::{plan_prune, repository, stack_usage, DiskContainer, DiskImage};
+use std::collections::BTreeMap;
+
+fn container(name: &str, stack: &str, image: &str, image_id: &str, status: &str) -> DiskContainer {
+    DiskContainer {
+        id: format!("{name}-id"),
+        name: name.to_string(),
+        stack: Some(stack.to_string()),
+        image: image.to_string(),
+        image_id: image_id.to_string(),
+        status: status.to_string(),
+        log_path: String::new(),
+        volumes: vec![format!("{stack}_data")],
+    }
+}
+
+fn image(id: &str, size: u64, repo_digests: &[&str]) -> DiskImage {
+    DiskImage {
+        id: id.to_string(),
+        size,
+        repo_tags: vec![],
+        repo_digests: repo_digests
+            .iter()
+            .map(|digest| digest.to_string())
+            .collect(),
+    }
+}
+
+#[test]
+fn repositories() {
+    assert_eq!(repository("nginx:1.27"), "nginx");
+    assert_eq!(repository("docker.io/library/nginx:1.27"), "nginx");
+    assert_eq!(repository("nginx@sha256:9f8e"), "nginx");
+    assert_eq!(repository("ghcr.io/acme/app"), "ghcr.io/acme/app");
+    assert_eq!(
+        repository("registry.local:5000/acme/app:2@sha256:9f8e"),
+        "registry.local:5000/acme/app"
+    );
+}
+
+#[test]
+fn plans() {
+    let containers = [
+        container(
+            "web-app-1",
+            "web",
+            "ghcr.io/acme/app:2",
+            "sha256:new",
+            "running",
+        ),
+        container(
+            "web-migrate-1",
+            "web",
+            "ghcr.io/acme/app:2",
+            "sha256:old",
+            "exited",
+        ),
+        container(
+            "web-nginx-1",
+            "web",
+            "nginx:1.27",
+            "sha256:nginx",
+            "running",
+        ),
+        container(
+            "blog-nginx-1",
+            "blog",
+            "nginx:1.26",
+            "sha256:nginx-old",
+            "running",
+        ),
+        container(
+            "blog-db-1",
+            "blog",
+            "postgres:16",
+            "sha256:postgres",
+            "exited",
+        ),
+    ];
+    let dangling = [
+        // only used by a stopped container of the stack
+        image("sha256:old", 100, &["ghcr.io/acme/app@sha256:1"]),
+        image("sha256:older", 200, &["ghcr.io/acme/app@sha256:2"]),
+        // still used by another stack
+        image("sha256:nginx-old", 300, &["nginx@sha256:3"]),
+        // not an image of the stack at all
+        image("sha256:redis", 400, &["redis@sha256:4"]),
+    ];
+
+    let plan = plan_prune("web", &containers, &dangling);
+
+    assert_eq!(
+        plan.containers
+            .iter()
+            .map(|container| container.name.as_str())
+            .collect::<Vec<&str>>(),
+        ["web-migrate-1"]
+    );
+    assert_eq!(
+        plan.images
+            .iter()
+            .map(|image| image.id.as_str())
+            .collect::<Vec<&str>>(),
+        ["sha256:old", "sha256:older"]
+    );
+    assert_eq!(plan.reclaimable(), 300);
+
+    assert!(plan_prune("shop", &containers, &dangling).is_empty());
+}
+
+#[test]
+fn usage() {
+    let containers = [
+        container(
+            "web-app-1",
+            "web",
+            "ghcr.io/acme/app:2",
+            "sha256:app",
+            "running",
+        ),
+        container(
+            "web-nginx-1",
+            "web",
+            "nginx:1.27",
+            "sha256:nginx",
+            "running",
+        ),
+        container(
+            "blog-nginx-1",
+            "blog",
+            "nginx:1.27",
+            "sha256:nginx",
+            "running",
+        ),
+    ];
+    let images = [
+        image("sha256:app", 1_000, &[]),
+        image("sha256:nginx", 500, &[]),
+    ];
+    let volume_sizes = BTreeMap::from([("web_data".to_string(), 2_000)]);
+    let log_sizes = BTreeMap::from([
+        ("web-app-1-id".to_string(), Some(10)),
+        ("web-nginx-1-id".to_string(), Some(20)),
+        ("blog-nginx-1-id".to_string(), None),
+    ]);
+
+    let web = stack_usage("web", &containers, &images, &volume_sizes, &log_sizes);
+    assert_eq!(web.containers, 2);
+    assert_eq!(web.images, 1_500);
+    // the volume is shared by both containers but only counted once
+    assert_eq!(web.volumes, Some(2_000));
+    assert_eq!(web.logs, Some(30));
+
+    // unknown sizes are not reported as zero
+    let blog = stack_usage("blog", &containers, &images, &volume_sizes, &log_sizes);
+    assert_eq!(blog.images, 500);
+    assert_eq!(blog.volumes, None);
+    assert_eq!(blog.logs, None);
+}
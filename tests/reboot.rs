@@ -0,0 +1,27 @@
+use dsd_util::reboot::{parse_reboot_packages, render_thaw_unit};
+use std::path::Path;
+
+#[test]
+fn reboot_packages() {
+    assert_eq!(
+        parse_reboot_packages("linux-image-6.8.0-45-generic\nlibc6\n\nlibc6\n"),
+        ["linux-image-6.8.0-45-generic", "libc6"]
+    );
+    assert!(parse_reboot_packages("").is_empty());
+}
+
+#[test]
+fn thaw_unit() {
+    let unit = render_thaw_unit(
+        Path::new("/usr/local/bin/dsd-util"),
+        Path::new("/root/.local/state/dsd-util/maintenance.json"),
+        &[("HOME".to_string(), "/root".to_string())],
+    );
+
+    assert!(unit.contains("After=docker.service"));
+    assert!(unit.contains("ConditionPathExists=/root/.local/state/dsd-util/maintenance.json\n"));
+    assert!(unit.contains("Environment=\"HOME=/root\"\n"));
+    assert!(unit.contains("ExecStart=/usr/local/bin/dsd-util maintenance end --notify\n"));
+    assert!(unit.contains("ExecStartPost=systemctl disable dsd-util-thaw.service\n"));
+    assert!(unit.contains("WantedBy=multi-user.target"));
+}
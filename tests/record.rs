@@ -0,0 +1,127 @@
+use chrono::{DateTime, Utc};
+use dsd_util::record::{
+    append_samples, format_sample, load_samples, parse_sample, summarize, RecordFormat, StatsSample,
+};
+use dsd_util::utils::StatsData;
+use std::path::Path;
+
+fn time(timestamp: &str) -> DateTime<Utc> {
+    DateTime::parse_from_rfc3339(timestamp)
+        .expect("timestamp is valid")
+        .with_timezone(&Utc)
+}
+
+fn sample(timestamp: &str, container: &str, cpu_percent: f64, memory_used: u64) -> StatsSample {
+    StatsSample {
+        timestamp: time(timestamp),
+        stats: StatsData {
+            container_name: container.to_string(),
+            cpu_percent,
+            memory_percent: 12.5,
+            memory_used,
+            memory_limit: 1 << 30,
+            net_rx: 1_000,
+            net_tx: 2_000,
+            block_read: 0,
+            block_write: 4_096,
+            pids: 7,
+        },
+    }
+}
+
+#[test]
+fn samples_round_trip() {
+    let line = format_sample(&sample(
+        "2025-06-01T12:00:00Z",
+        "web-app-1",
+        0.25,
+        1_048_576,
+    ));
+    assert_eq!(
+        line,
+        "2025-06-01T12:00:00Z,web-app-1,0.25,12.5,1048576,1073741824,1000,2000,0,4096,7"
+    );
+    assert_eq!(format_sample(&parse_sample(&line).unwrap()), line);
+
+    assert!(parse_sample("2025-06-01T12:00:00+00:00,web-app-1,0.25").is_err());
+    assert!(parse_sample("yesterday,web-app-1,0.25,12.5,1,1,1,1,1,1,1").is_err());
+}
+
+#[test]
+fn formats() {
+    assert_eq!(
+        RecordFormat::from_path(Path::new("stats.db")),
+        RecordFormat::Sqlite
+    );
+    assert_eq!(
+        RecordFormat::from_path(Path::new("stats.sqlite3")),
+        RecordFormat::Sqlite
+    );
+    assert_eq!(
+        RecordFormat::from_path(Path::new("stats.csv")),
+        RecordFormat::Csv
+    );
+    assert_eq!(
+        RecordFormat::from_path(Path::new("stats")),
+        RecordFormat::Csv
+    );
+}
+
+#[test]
+fn csv_files() {
+    let path = std::env::temp_dir().join(format!("dsd-util-record-{}.csv", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+
+    append_samples(
+        &path,
+        &[sample("2025-06-01T12:00:00Z", "web-app-1", 1.0, 100)],
+    )
+    .unwrap();
+    append_samples(
+        &path,
+        &[sample("2025-06-01T12:00:30Z", "web-app-1", 3.0, 300)],
+    )
+    .unwrap();
+
+    let contents = std::fs::read_to_string(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    // the header is only written once
+    assert_eq!(contents.matches("timestamp,container").count(), 1);
+    assert_eq!(contents.lines().count(), 3);
+}
+
+#[test]
+fn summaries() {
+    let samples = [
+        sample("2025-06-01T11:00:00Z", "web-db-1", 50.0, 5_000),
+        sample("2025-06-01T12:00:00Z", "web-app-1", 1.0, 100),
+        sample("2025-06-01T12:00:30Z", "web-app-1", 3.0, 300),
+        sample("2025-06-01T12:01:00Z", "web-app-1", 2.0, 201),
+        sample("2025-06-01T12:01:00Z", "web-db-1", 10.0, 1_000),
+    ];
+
+    let summaries = summarize(
+        &samples,
+        time("2025-06-01T11:30:00Z"),
+        time("2025-06-01T12:30:00Z"),
+    );
+
+    assert_eq!(summaries.len(), 2);
+
+    let app = &summaries[0];
+    assert_eq!(app.container, "web-app-1");
+    assert_eq!(app.samples, 3);
+    assert_eq!((app.cpu_min, app.cpu_avg, app.cpu_max), (1.0, 2.0, 3.0));
+    assert_eq!(
+        (app.memory_min, app.memory_avg, app.memory_max),
+        (100, 200, 300)
+    );
+
+    // samples outside of the range are left out
+    let db = &summaries[1];
+    assert_eq!(db.samples, 1);
+    assert_eq!(db.cpu_max, 10.0);
+
+    assert!(load_samples(Path::new("/nonexistent/stats.csv")).is_err());
+}
@@ -0,0 +1,97 @@
+use dsd_util::registry::{
+    is_auth_error, login_registry, registry_host, AuthenticationRequired, DockerConfig,
+    RegistryLogin,
+};
+
+#[test]
+fn registry_of_images() {
+    assert_eq!(registry_host("alpine"), "docker.io");
+    assert_eq!(registry_host("library/alpine:3"), "docker.io");
+    assert_eq!(registry_host("ghcr.io/acme/app:2"), "ghcr.io");
+    assert_eq!(
+        registry_host("registry.local:5000/app"),
+        "registry.local:5000"
+    );
+    assert_eq!(registry_host("localhost/app"), "localhost");
+}
+
+#[test]
+fn auth_errors() {
+    assert!(is_auth_error(
+        "Error response from daemon: Head \"https://ghcr.io/v2/acme/app/manifests/2\": unauthorized"
+    ));
+    assert!(is_auth_error(
+        "Error response from daemon: pull access denied for acme/app, repository does not exist or may require 'docker login'"
+    ));
+    assert!(is_auth_error("no basic auth credentials"));
+    assert!(!is_auth_error(
+        "Error response from daemon: manifest for alpine:99 not found: manifest unknown"
+    ));
+}
+
+#[test]
+fn stored_credentials() {
+    let config: DockerConfig = serde_json::from_str(
+        r#"{"auths":{"https://index.docker.io/v1/":{"auth":"dXNlcjpwYXNz"},"ghcr.io":{}},"credHelpers":{"123.dkr.ecr.eu-west-1.amazonaws.com":"ecr-login"}}"#,
+    )
+    .unwrap();
+
+    assert!(config.has_credentials("docker.io"));
+    assert!(config.has_credentials("ghcr.io"));
+    assert!(config.has_credentials("123.dkr.ecr.eu-west-1.amazonaws.com"));
+    assert!(!config.has_credentials("registry.gitlab.com"));
+
+    let store: DockerConfig = serde_json::from_str(r#"{"credsStore":"pass"}"#).unwrap();
+    assert!(store.has_credentials("registry.gitlab.com"));
+    assert!(!DockerConfig::default().has_credentials("docker.io"));
+}
+
+#[test]
+fn registry_to_log_in_to() {
+    let login = RegistryLogin {
+        registry: None,
+        user: "bot".to_string(),
+        password: "secret".to_string(),
+    };
+    let images = |images: &[&str]| {
+        images
+            .iter()
+            .map(|image| image.to_string())
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(
+        login_registry(
+            &login,
+            &images(&["ghcr.io/acme/app:2", "ghcr.io/acme/worker:2"])
+        )
+        .unwrap(),
+        "ghcr.io"
+    );
+    assert!(login_registry(&login, &images(&["ghcr.io/acme/app:2", "postgres:16"])).is_err());
+
+    let login = RegistryLogin {
+        registry: Some("ghcr.io".to_string()),
+        ..login
+    };
+    assert_eq!(
+        login_registry(&login, &images(&["ghcr.io/acme/app:2", "postgres:16"])).unwrap(),
+        "ghcr.io"
+    );
+    assert!(!format!("{login:?}").contains("secret"));
+}
+
+#[test]
+fn authentication_required_message() {
+    let error = AuthenticationRequired {
+        registry: "ghcr.io".to_string(),
+        has_credentials: false,
+    };
+    assert!(error.to_string().contains("docker login ghcr.io"));
+
+    let error = AuthenticationRequired {
+        has_credentials: true,
+        ..error
+    };
+    assert!(error.to_string().contains("rejected"));
+}
@@ -0,0 +1,185 @@
+use chrono::{DateTime, Utc};
+use dsd_util::history::{Action, HistoryEntry};
+use dsd_util::probes::ProbeHistoryEntry;
+use dsd_util::record::UsageSummary;
+use dsd_util::report::{
+    count_crashes, disk_trends, open_warnings, parse_exit_event, render_report,
+    resource_highlights, DailyReport, DiskSnapshot,
+};
+use dsd_util::units::NumberFormat;
+use dsd_util::utils::InspectData;
+use std::collections::BTreeMap;
+
+fn at(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_750_000_000 + secs, 0).unwrap()
+}
+
+fn inspected(name: &str, status: &str, health: &str) -> InspectData {
+    InspectData {
+        container_name: name.to_string(),
+        status: status.to_string(),
+        restart_policy: "unless-stopped".to_string(),
+        health: health.to_string(),
+        uptime: "1h".to_string(),
+        started_at: "2025-06-15T14:00:00Z".to_string(),
+        restart_count: 2,
+        ports: "N/A".to_string(),
+        port_bindings: vec![],
+        networks: vec![],
+    }
+}
+
+fn usage(container: &str, cpu_max: f64, memory_max: u64) -> UsageSummary {
+    UsageSummary {
+        container: container.to_string(),
+        samples: 1,
+        cpu_min: cpu_max,
+        cpu_avg: cpu_max,
+        cpu_max,
+        memory_min: memory_max,
+        memory_avg: memory_max,
+        memory_max,
+    }
+}
+
+fn probe(name: &str, secs: i64, ok: bool) -> ProbeHistoryEntry {
+    ProbeHistoryEntry {
+        timestamp: at(secs),
+        name: name.to_string(),
+        container: "web-app-1".to_string(),
+        ok,
+        latency_ms: 5,
+        detail: if ok { "200" } else { "503" }.to_string(),
+    }
+}
+
+fn snapshot(stacks: &[(&str, u64)]) -> DiskSnapshot {
+    DiskSnapshot {
+        at: at(0),
+        free: Some(10_000_000_000),
+        stacks: stacks
+            .iter()
+            .map(|(stack, size)| (stack.to_string(), *size))
+            .collect(),
+    }
+}
+
+#[test]
+fn exit_events() {
+    assert_eq!(
+        parse_exit_event(
+            r#"{"Type":"container","Action":"die","Actor":{"Attributes":{"exitCode":"137","name":"web-app-1"}},"time":1750000000}"#
+        ),
+        Some(("web-app-1".to_string(), "137".to_string()))
+    );
+    assert_eq!(
+        parse_exit_event(
+            r#"{"Type":"container","Action":"start","Actor":{"Attributes":{"name":"web-app-1"}},"time":1750000000}"#
+        ),
+        None
+    );
+
+    let crashes = count_crashes(
+        r#"{"Action":"die","Actor":{"Attributes":{"exitCode":"1","name":"web-app-1"}}}
+{"Action":"die","Actor":{"Attributes":{"exitCode":"0","name":"web-app-1"}}}
+{"Action":"die","Actor":{"Attributes":{"exitCode":"139","name":"web-app-1"}}}
+{"Action":"die","Actor":{"Attributes":{"exitCode":"0","name":"web-db-1"}}}
+not json"#,
+    );
+    assert_eq!(crashes, BTreeMap::from([("web-app-1".to_string(), 2)]));
+}
+
+#[test]
+fn disk_changes_since_previous_report() {
+    let previous = snapshot(&[("web", 1_000), ("gone", 50)]);
+    let current = snapshot(&[("web", 1_500), ("media", 4_000)]);
+
+    let trends = disk_trends(Some(&previous), &current);
+    assert_eq!(trends.len(), 2);
+    assert_eq!(trends[0].stack, "media");
+    assert_eq!(trends[0].change, None);
+    assert_eq!(trends[1].stack, "web");
+    assert_eq!(trends[1].change, Some(500));
+
+    assert!(disk_trends(None, &current)
+        .iter()
+        .all(|trend| trend.change.is_none()));
+}
+
+#[test]
+fn highlights_top_cpu_and_memory() {
+    let usage = [
+        usage("idle", 0.1, 10),
+        usage("busy", 90.0, 100),
+        usage("hungry", 5.0, 9_000),
+        usage("warm", 40.0, 50),
+    ];
+
+    let highlights = resource_highlights(&usage, 1)
+        .iter()
+        .map(|summary| summary.container.as_str())
+        .collect::<Vec<&str>>();
+    assert_eq!(highlights, ["busy", "hungry"]);
+
+    assert_eq!(resource_highlights(&usage, 10).len(), 4);
+}
+
+#[test]
+fn warnings_that_need_attention() {
+    let containers = [
+        inspected("web-app-1", "running", "unhealthy"),
+        inspected("web-db-1", "restarting", "N/A"),
+        inspected("web-cache-1", "running", "healthy"),
+    ];
+    // only the last result of a probe counts
+    let probes = [
+        probe("http", 0, false),
+        probe("http", 60, true),
+        probe("tcp", 0, true),
+        probe("tcp", 60, false),
+    ];
+
+    let warnings = open_warnings(&containers, &probes, Some(&["linux-image".to_string()]));
+    assert_eq!(
+        warnings,
+        [
+            "web-app-1 is unhealthy",
+            "web-db-1 is restarting",
+            "Probe tcp of web-app-1 is failing: 503",
+            "The host needs a reboot for linux-image",
+        ]
+    );
+
+    assert!(open_warnings(&containers[2..], &[], None).is_empty());
+}
+
+#[test]
+fn renders_report() {
+    let mut restart = HistoryEntry::new("web-app-1", Action::Restart, None);
+    restart.at = at(0);
+    let report = DailyReport {
+        host: "nas".to_string(),
+        since: at(-86_400),
+        until: at(0),
+        containers: vec![
+            inspected("web-app-1", "running", "unhealthy"),
+            inspected("web-db-1", "exited", "N/A"),
+        ],
+        crashes: BTreeMap::from([("web-app-1".to_string(), 3)]),
+        actions: vec![restart],
+        usage: vec![usage("web-app-1", 12.5, 1024)],
+        disk: snapshot(&[("web", 2_048)]),
+        previous_disk: Some(snapshot(&[("web", 3_072)])),
+        warnings: vec!["web-app-1 is unhealthy".to_string()],
+    };
+
+    let markdown = render_report(&report, &NumberFormat::default()).to_markdown();
+    assert!(markdown.starts_with("# Daily report for nas\n"));
+    assert!(markdown.contains("1 of 2 container(s) running, 1 open warning(s)."));
+    assert!(markdown.contains("- web-app-1 is unhealthy\n"));
+    assert!(markdown.contains("| web-app-1 | running | unhealthy | 1h | 2 | 3 |\n"));
+    assert!(markdown.contains("1 restart(s), 0 update(s) and 0 rollback(s) by dsd-util."));
+    assert!(markdown.contains("web-app-1: Restarted by dsd-util\n"));
+    assert!(markdown.contains("| web-app-1 | 12.50% | 12.50% |"));
+    assert!(markdown.contains("| web | 2 KiB | -1 KiB |\n"));
+}
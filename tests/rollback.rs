@@ -0,0 +1,63 @@
+use chrono::{TimeZone, Utc};
+use dsd_util::rollback::{select_targets, RollbackState, RollbackTarget};
+
+fn target(container: &str, stack: Option<&str>) -> RollbackTarget {
+    RollbackTarget {
+        container: container.to_string(),
+        stack: stack.map(String::from),
+        image: "ghcr.io/acme/app:2".to_string(),
+        image_id: "sha256:1a2b3c4d5e6f7a8b9c0d".to_string(),
+        recorded_at: Utc.with_ymd_and_hms(2026, 10, 16, 4, 0, 0).unwrap(),
+    }
+}
+
+fn state() -> RollbackState {
+    [
+        target("web-app-1", Some("web")),
+        target("web-db-1", Some("web")),
+        target("portainer", None),
+    ]
+    .into_iter()
+    .map(|target| (target.container.to_string(), target))
+    .collect()
+}
+
+#[test]
+fn targets_by_container_or_stack() {
+    let state = state();
+
+    let names = |targets: Vec<RollbackTarget>| {
+        targets
+            .into_iter()
+            .map(|target| target.container)
+            .collect::<Vec<String>>()
+    };
+
+    assert_eq!(
+        names(select_targets(&state, &["portainer".to_string()]).unwrap()),
+        ["portainer"]
+    );
+    assert_eq!(
+        names(select_targets(&state, &["web".to_string()]).unwrap()),
+        ["web-app-1", "web-db-1"]
+    );
+    // a container of a stack given as well is only rolled back once
+    assert_eq!(
+        names(select_targets(&state, &["web-db-1".to_string(), "web".to_string()]).unwrap()),
+        ["web-db-1", "web-app-1"]
+    );
+    assert!(select_targets(&state, &["media".to_string()]).is_err());
+}
+
+#[test]
+fn short_image_id() {
+    assert_eq!(target("web-app-1", None).short_image_id(), "1a2b3c4d5e6f");
+}
+
+#[test]
+fn state_round_trip() {
+    let state = state();
+    let json = serde_json::to_string(&state).unwrap();
+
+    assert_eq!(serde_json::from_str::<RollbackState>(&json).unwrap(), state);
+}
@@ -0,0 +1,37 @@
+use dsd_util::scale::{parse_service_scale, service_replicas, ServiceScale};
+
+#[test]
+fn scales() {
+    assert_eq!(
+        parse_service_scale("worker=3").unwrap(),
+        ServiceScale {
+            service: "worker".to_string(),
+            replicas: 3
+        }
+    );
+    assert_eq!(parse_service_scale("worker=0").unwrap().replicas, 0);
+
+    assert!(parse_service_scale("worker").is_err());
+    assert!(parse_service_scale("=3").is_err());
+    assert!(parse_service_scale("worker=-1").is_err());
+    assert!(parse_service_scale("worker=many").is_err());
+}
+
+#[test]
+fn replicas_of_a_service() {
+    let services = [
+        ("media-worker-2", "worker"),
+        ("media-web-1", "web"),
+        ("media-worker-1", "worker"),
+    ]
+    .iter()
+    .map(|(name, service)| (name.to_string(), service.to_string()))
+    .collect::<Vec<(String, String)>>();
+
+    assert_eq!(
+        service_replicas(&services, "worker"),
+        vec!["media-worker-1", "media-worker-2"]
+    );
+    assert_eq!(service_replicas(&services, "web"), vec!["media-web-1"]);
+    assert!(service_replicas(&services, "db").is_empty());
+}
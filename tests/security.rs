@@ -0,0 +1,181 @@
+use dsd_util::parsers::{parse_container_users, parse_security_profiles};
+use dsd_util::security::{
+    is_root_user, non_root_suggestion, security_option, ContainerUser, SecurityProfiles,
+};
+
+const INSPECT_OUTPUT: &str = r#"[
+  {
+    "Name": "/proxy-traefik-1",
+    "Config": {
+      "User": "",
+      "Image": "traefik:v3.1",
+      "Labels": {"com.docker.compose.project": "proxy"}
+    },
+    "Mounts": [
+      {"Type": "bind", "Source": "/var/run/docker.sock", "Destination": "/var/run/docker.sock"}
+    ]
+  },
+  {
+    "Name": "/app-web-1",
+    "Config": {
+      "User": "node:node",
+      "Image": "node:20",
+      "Labels": {"com.docker.compose.project": "app"}
+    },
+    "Mounts": [
+      {"Type": "volume", "Source": "/var/lib/docker/volumes/app_data/_data", "Destination": "/data"}
+    ]
+  },
+  {
+    "Name": "/scratch",
+    "Config": {"User": "0:0", "Image": "alpine", "Labels": null},
+    "Mounts": null
+  }
+]"#;
+
+#[test]
+fn detects_root_users() {
+    assert!(is_root_user(""));
+    assert!(is_root_user("0"));
+    assert!(is_root_user("root"));
+    assert!(is_root_user("0:1000"));
+    assert!(is_root_user("root:docker"));
+
+    assert!(!is_root_user("1000"));
+    assert!(!is_root_user("1000:0"));
+    assert!(!is_root_user("node"));
+    assert!(!is_root_user("rootless"));
+}
+
+#[test]
+fn suggests_non_root_users_for_known_images() {
+    assert_eq!(non_root_suggestion("redis:7"), Some("user: \"999:999\""));
+    assert_eq!(
+        non_root_suggestion("docker.io/library/postgres:16"),
+        non_root_suggestion("postgres")
+    );
+    assert!(non_root_suggestion("lscr.io/linuxserver/sonarr:latest")
+        .unwrap()
+        .contains("PUID"));
+
+    assert_eq!(non_root_suggestion("ghcr.io/example/app:1.0"), None);
+}
+
+#[test]
+fn parses_container_users() {
+    let users = parse_container_users(INSPECT_OUTPUT.as_bytes()).unwrap();
+
+    assert_eq!(
+        users,
+        vec![
+            ContainerUser {
+                container: "proxy-traefik-1".to_string(),
+                stack: Some("proxy".to_string()),
+                image: "traefik:v3.1".to_string(),
+                user: String::new(),
+                docker_socket: true,
+            },
+            ContainerUser {
+                container: "app-web-1".to_string(),
+                stack: Some("app".to_string()),
+                image: "node:20".to_string(),
+                user: "node:node".to_string(),
+                docker_socket: false,
+            },
+            ContainerUser {
+                container: "scratch".to_string(),
+                stack: None,
+                image: "alpine".to_string(),
+                user: "0:0".to_string(),
+                docker_socket: false,
+            },
+        ]
+    );
+
+    assert!(users[0].is_root());
+    assert!(!users[1].is_root());
+    assert!(users[2].is_root());
+}
+
+const PROFILES_OUTPUT: &str = r#"[
+  {
+    "Name": "/proxy-traefik-1",
+    "AppArmorProfile": "docker-default",
+    "ProcessLabel": "",
+    "Config": {"Labels": {"com.docker.compose.project": "proxy"}},
+    "HostConfig": {"Privileged": false, "SecurityOpt": null}
+  },
+  {
+    "Name": "/media-jellyfin-1",
+    "AppArmorProfile": "unconfined",
+    "ProcessLabel": "",
+    "Config": {"Labels": {"com.docker.compose.project": "media"}},
+    "HostConfig": {
+      "Privileged": false,
+      "SecurityOpt": ["apparmor=unconfined", "seccomp={\"defaultAction\": \"SCMP_ACT_ERRNO\"}", "label:disable"]
+    }
+  },
+  {
+    "Name": "/monitor-cadvisor-1",
+    "AppArmorProfile": "",
+    "ProcessLabel": "system_u:system_r:container_t:s0:c1,c2",
+    "Config": {"Labels": null},
+    "HostConfig": {"Privileged": true, "SecurityOpt": ["label=type:container_runtime_t"]}
+  }
+]"#;
+
+#[test]
+fn reads_security_options() {
+    let options = vec![
+        "seccomp=unconfined".to_string(),
+        "label:disable".to_string(),
+        "label=level:s0:c100".to_string(),
+        "no-new-privileges".to_string(),
+    ];
+
+    assert_eq!(security_option(&options, "seccomp"), vec!["unconfined"]);
+    assert_eq!(
+        security_option(&options, "label"),
+        vec!["disable", "level:s0:c100"]
+    );
+    assert!(security_option(&options, "apparmor").is_empty());
+}
+
+#[test]
+fn parses_security_profiles() {
+    let profiles = parse_security_profiles(PROFILES_OUTPUT.as_bytes()).unwrap();
+
+    assert_eq!(
+        profiles,
+        vec![
+            SecurityProfiles {
+                container: "proxy-traefik-1".to_string(),
+                stack: Some("proxy".to_string()),
+                apparmor: "docker-default".to_string(),
+                seccomp: "default".to_string(),
+                selinux: "-".to_string(),
+                privileged: false,
+            },
+            SecurityProfiles {
+                container: "media-jellyfin-1".to_string(),
+                stack: Some("media".to_string()),
+                apparmor: "unconfined".to_string(),
+                seccomp: "custom".to_string(),
+                selinux: "disable".to_string(),
+                privileged: false,
+            },
+            SecurityProfiles {
+                container: "monitor-cadvisor-1".to_string(),
+                stack: None,
+                apparmor: "-".to_string(),
+                seccomp: "unconfined".to_string(),
+                selinux: "type:container_runtime_t".to_string(),
+                privileged: true,
+            },
+        ]
+    );
+
+    assert!(profiles[0].unconfined().is_empty());
+    assert_eq!(profiles[1].unconfined(), vec!["apparmor", "selinux"]);
+    assert_eq!(profiles[2].unconfined(), vec!["privileged", "seccomp"]);
+}
@@ -0,0 +1,174 @@
+use dsd_util::docker::Container;
+use dsd_util::selector::{
+    close_matches, correct_typo, edit_distance, fuzzy_matches, match_container, match_exact,
+    stack_members, ContainerMatch,
+};
+
+fn names() -> Vec<String> {
+    ["web-app-1", "web-app-worker-1", "web-db-1", "mail-1"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+#[test]
+fn fuzzy_matching() {
+    let names = names();
+
+    // exact names win even when they are part of other names
+    assert_eq!(fuzzy_matches("web-app-1", &names), ["web-app-1"]);
+    assert_eq!(fuzzy_matches("mail", &names), ["mail-1"]);
+    assert_eq!(fuzzy_matches("DB", &names), ["web-db-1"]);
+    assert_eq!(fuzzy_matches("worker", &names), ["web-app-worker-1"]);
+    assert_eq!(fuzzy_matches("wdb", &names), ["web-db-1"]);
+
+    assert_eq!(
+        fuzzy_matches("app", &names),
+        ["web-app-1", "web-app-worker-1"]
+    );
+    assert!(fuzzy_matches("redis", &names).is_empty());
+}
+
+fn containers() -> Vec<Container> {
+    [
+        ("3f2a1b9c0d1e", "web-app-1"),
+        ("3f9e8d7c6b5a", "web-app-worker-1"),
+        ("db01a2b3c4d5", "web-db-1"),
+        ("7c1d2e3f4a5b", "mail-1"),
+    ]
+    .iter()
+    .map(|(id, name)| Container {
+        id: id.to_string(),
+        name: name.to_string(),
+        image: "nginx:latest".to_string(),
+        stack: None,
+        service: None,
+    })
+    .collect()
+}
+
+#[test]
+fn containers_complete_from_id_prefixes() {
+    let containers = containers();
+    let found = |name: &str| ContainerMatch::Found(name.to_string());
+
+    assert_eq!(match_container("7c1", &containers), found("mail-1"));
+    assert_eq!(match_container("3F2A", &containers), found("web-app-1"));
+    assert_eq!(
+        match_container(
+            "3f2a1b9c0d1e4c5b6a79887766554433221100ffeeddccbbaa99887766554433",
+            &containers
+        ),
+        found("web-app-1")
+    );
+    assert_eq!(
+        match_container("3f", &containers),
+        ContainerMatch::Ambiguous(vec![
+            "web-app-1 (3f2a1b9c0d1e)".to_string(),
+            "web-app-worker-1 (3f9e8d7c6b5a)".to_string(),
+        ])
+    );
+}
+
+#[test]
+fn containers_complete_from_partial_names() {
+    let containers = containers();
+
+    assert_eq!(
+        match_container("worker", &containers),
+        ContainerMatch::Found("web-app-worker-1".to_string())
+    );
+    assert_eq!(
+        match_container("web-app-1", &containers),
+        ContainerMatch::Found("web-app-1".to_string())
+    );
+    // db is both a prefix of the id and part of the name of the same container
+    assert_eq!(
+        match_container("db", &containers),
+        ContainerMatch::Found("web-db-1".to_string())
+    );
+    assert!(matches!(
+        match_container("app", &containers),
+        ContainerMatch::Ambiguous(_)
+    ));
+    assert_eq!(
+        match_container("redis", &containers),
+        ContainerMatch::NotFound
+    );
+}
+
+#[test]
+fn exact_matches_skip_partial_names() {
+    let containers = containers();
+
+    assert_eq!(
+        match_exact("mail-1", &containers),
+        ContainerMatch::Found("mail-1".to_string())
+    );
+    assert_eq!(
+        match_exact("7c1d", &containers),
+        ContainerMatch::Found("mail-1".to_string())
+    );
+    assert_eq!(match_exact("mail", &containers), ContainerMatch::NotFound);
+}
+
+#[test]
+fn edit_distances() {
+    assert_eq!(edit_distance("nextcloud", "nextcloud"), 0);
+    assert_eq!(edit_distance("nextclod", "nextcloud"), 1);
+    assert_eq!(edit_distance("NextCloud", "nextcloud"), 0);
+    assert_eq!(edit_distance("kitten", "sitting"), 3);
+    assert_eq!(edit_distance("", "db"), 2);
+    assert_eq!(edit_distance("mial", "mail"), 1);
+    assert_eq!(edit_distance("ca", "abc"), 3);
+}
+
+#[test]
+fn typos_suggest_close_names() {
+    let names = ["nextcloud-app-1", "nextcloud-db-1", "mail-1"]
+        .iter()
+        .map(|name| name.to_string())
+        .collect::<Vec<String>>();
+
+    assert_eq!(close_matches("nextclod-app-1", &names), ["nextcloud-app-1"]);
+    // both nextcloud containers have a part as close to nextclod, so neither stands out
+    assert_eq!(
+        close_matches("nextclod", &names),
+        ["nextcloud-app-1", "nextcloud-db-1"]
+    );
+    assert_eq!(close_matches("mial", &names), ["mail-1"]);
+    assert!(close_matches("postgres", &names).is_empty());
+}
+
+#[test]
+fn fuzzy_accepts_the_closest_name() {
+    let containers = containers();
+
+    let error = correct_typo("web-dp-1", &containers, false).unwrap_err();
+    assert!(error.to_string().contains("did you mean `web-db-1`"));
+    assert_eq!(correct_typo("redis", &containers, false).unwrap(), None);
+
+    assert_eq!(
+        correct_typo("web-dp-1", &containers, true).unwrap(),
+        Some("web-db-1".to_string())
+    );
+    assert_eq!(correct_typo("redis", &containers, true).unwrap(), None);
+}
+
+#[test]
+fn stack_members_include_any_listed_stack() {
+    let mut containers = containers();
+    containers[0].stack = Some("web".to_string());
+    containers[2].stack = Some("web".to_string());
+    containers[3].stack = Some("mail".to_string());
+
+    assert_eq!(
+        stack_members(&["web".to_string()], &containers),
+        vec!["web-app-1", "web-db-1"]
+    );
+    assert_eq!(
+        stack_members(&["mail".to_string(), "web".to_string()], &containers),
+        vec!["web-app-1", "web-db-1", "mail-1"]
+    );
+    assert!(stack_members(&["other".to_string()], &containers).is_empty());
+}
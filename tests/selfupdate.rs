@@ -0,0 +1,104 @@
+use dsd_util::selfupdate::{
+    archive_name, parse_checksum, parse_release, replace_executable, repository_path, sha256_hex,
+};
+
+const RELEASE: &str = r#"{
+    "tag_name": "v0.3.0",
+    "name": "v0.3.0",
+    "assets": [
+        {
+            "name": "dsd-util-x86_64-unknown-linux-gnu.tar.gz",
+            "browser_download_url": "https://github.com/eldyl/dsd-util/releases/download/v0.3.0/dsd-util-x86_64-unknown-linux-gnu.tar.gz"
+        },
+        {
+            "name": "dsd-util-x86_64-unknown-linux-gnu.sha256",
+            "browser_download_url": "https://github.com/eldyl/dsd-util/releases/download/v0.3.0/dsd-util-x86_64-unknown-linux-gnu.sha256"
+        }
+    ]
+}"#;
+
+#[test]
+fn releases_compare_by_version() {
+    let release = parse_release(RELEASE.as_bytes()).unwrap();
+
+    assert_eq!(release.version(), "0.3.0");
+    assert!(release.is_newer_than("0.2.9"));
+    assert!(release.is_newer_than("0.1.0"));
+    assert!(!release.is_newer_than("0.3.0"));
+    assert!(!release.is_newer_than("0.10.0"));
+
+    let archive = archive_name("x86_64-unknown-linux-gnu");
+    assert_eq!(archive, "dsd-util-x86_64-unknown-linux-gnu.tar.gz");
+    assert!(release.asset(&archive).is_some());
+    assert_eq!(
+        release.checksum_asset(&archive).unwrap().name,
+        "dsd-util-x86_64-unknown-linux-gnu.sha256"
+    );
+    assert!(release
+        .asset(&archive_name("aarch64-apple-darwin"))
+        .is_none());
+}
+
+#[test]
+fn checksums_are_found_by_file_name() {
+    let a = "a".repeat(64);
+    let b = "B".repeat(64);
+    let listing = format!("{a}  dsd-util-x86_64-unknown-linux-gnu.tar.gz\n{b} *dsd-util.tar.gz\n");
+
+    assert_eq!(
+        parse_checksum(&listing, "dsd-util-x86_64-unknown-linux-gnu.tar.gz"),
+        Some(a.clone())
+    );
+    assert_eq!(
+        parse_checksum(&listing, "dsd-util.tar.gz"),
+        Some(b.to_lowercase())
+    );
+    assert_eq!(parse_checksum(&listing, "other.tar.gz"), None);
+
+    // a file with only a checksum covers whatever file it was published for
+    assert_eq!(parse_checksum(&format!("{a}\n"), "any.tar.gz"), Some(a));
+    assert_eq!(parse_checksum("not a checksum", "any.tar.gz"), None);
+}
+
+#[test]
+fn sha256_of_bytes() {
+    assert_eq!(
+        sha256_hex(b"abc"),
+        "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+    );
+}
+
+#[test]
+fn repository_paths() {
+    assert_eq!(
+        repository_path("https://github.com/eldyl/dsd-util").unwrap(),
+        "eldyl/dsd-util"
+    );
+    assert_eq!(
+        repository_path("https://github.com/eldyl/dsd-util/").unwrap(),
+        "eldyl/dsd-util"
+    );
+    assert!(repository_path("https://gitlab.com/eldyl/dsd-util").is_err());
+}
+
+#[test]
+fn executables_are_replaced() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = std::env::temp_dir().join(format!("dsd-util-selfupdate-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let binary = dir.join("new");
+    let executable = dir.join("dsd-util");
+    std::fs::write(&binary, "new").unwrap();
+    std::fs::write(&executable, "old").unwrap();
+
+    replace_executable(&binary, &executable).unwrap();
+
+    assert_eq!(std::fs::read_to_string(&executable).unwrap(), "new");
+    let mode = std::fs::metadata(&executable).unwrap().permissions().mode();
+    assert_eq!(mode & 0o777, 0o755);
+    // nothing staged is left behind
+    assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 2);
+
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,104 @@
+use chrono::DateTime;
+use dsd_util::ship::{
+    loki_push_body, loki_push_url, parse_ship_target, syslog_message, ShipTarget, ShippedLine,
+    SyslogTransport,
+};
+
+fn shipped(container: &str, stack: Option<&str>, line: &str) -> ShippedLine {
+    ShippedLine {
+        at: DateTime::from_timestamp(1_750_000_000, 500_000_000).unwrap(),
+        container: container.to_string(),
+        stack: stack.map(str::to_string),
+        line: line.to_string(),
+    }
+}
+
+#[test]
+fn targets() {
+    assert_eq!(
+        parse_ship_target("loki=http://loki:3100/").unwrap(),
+        ShipTarget::Loki("http://loki:3100".to_string())
+    );
+    assert_eq!(
+        parse_ship_target("syslog=udp://logs.lan:514").unwrap(),
+        ShipTarget::Syslog {
+            transport: SyslogTransport::Udp,
+            address: "logs.lan:514".to_string()
+        }
+    );
+    assert_eq!(
+        parse_ship_target("syslog=tcp://logs.lan").unwrap(),
+        ShipTarget::Syslog {
+            transport: SyslogTransport::Tcp,
+            address: "logs.lan:514".to_string()
+        }
+    );
+    assert_eq!(
+        parse_ship_target("syslog=logs.lan:1514")
+            .unwrap()
+            .to_string(),
+        "syslog=udp://logs.lan:1514"
+    );
+
+    assert!(parse_ship_target("loki=loki:3100").is_err());
+    assert!(parse_ship_target("syslog=quic://logs.lan").is_err());
+    assert!(parse_ship_target("elastic=http://es:9200").is_err());
+    assert!(parse_ship_target("http://loki:3100").is_err());
+}
+
+#[test]
+fn loki_push() {
+    assert_eq!(
+        loki_push_url("http://loki:3100"),
+        "http://loki:3100/loki/api/v1/push"
+    );
+    assert_eq!(
+        loki_push_url("http://loki:3100/loki/api/v1/push"),
+        "http://loki:3100/loki/api/v1/push"
+    );
+
+    let body = loki_push_body(
+        &[
+            shipped("web-app-1", Some("web"), "GET /"),
+            shipped("backup", None, "done"),
+            shipped("web-app-1", Some("web"), "GET /health"),
+        ],
+        "nas",
+    );
+
+    assert_eq!(
+        body,
+        serde_json::json!({
+            "streams": [
+                {
+                    "stream": { "job": "dsd-util", "host": "nas", "container": "backup" },
+                    "values": [["1750000000500000000", "done"]],
+                },
+                {
+                    "stream": {
+                        "job": "dsd-util",
+                        "host": "nas",
+                        "container": "web-app-1",
+                        "stack": "web",
+                    },
+                    "values": [
+                        ["1750000000500000000", "GET /"],
+                        ["1750000000500000000", "GET /health"],
+                    ],
+                },
+            ]
+        })
+    );
+}
+
+#[test]
+fn syslog_messages() {
+    assert_eq!(
+        syslog_message(&shipped("web-app-1", Some("web"), "ERROR boom"), "nas"),
+        "<11>1 2025-06-15T15:06:40.500Z nas web-app-1 - web - ERROR boom"
+    );
+    assert_eq!(
+        syslog_message(&shipped("backup", None, "done"), ""),
+        "<14>1 2025-06-15T15:06:40.500Z - backup - - - done"
+    );
+}
@@ -0,0 +1,46 @@
+use dsd_util::table::Table;
+
+fn table() -> Table {
+    let mut table = Table::new(&["NAME", "PORTS"]);
+    table.push(vec![
+        "web-app-1".to_string(),
+        "80->8080/tcp, 443->8443/tcp".to_string(),
+    ]);
+    table.push(vec!["db|primary".to_string(), "-".to_string()]);
+    table
+}
+
+#[test]
+fn text_aligns_columns() {
+    assert_eq!(
+        table().to_text(),
+        "NAME        PORTS\n\
+         web-app-1   80->8080/tcp, 443->8443/tcp\n\
+         db|primary  -\n"
+    );
+}
+
+#[test]
+fn csv_quotes_separators() {
+    let mut table = table();
+    table.push(vec!["say \"hi\"".to_string(), String::new()]);
+
+    assert_eq!(
+        table.to_csv(),
+        "NAME,PORTS\n\
+         web-app-1,\"80->8080/tcp, 443->8443/tcp\"\n\
+         db|primary,-\n\
+         \"say \"\"hi\"\"\",\n"
+    );
+}
+
+#[test]
+fn markdown_escapes_pipes() {
+    assert_eq!(
+        table().to_markdown(),
+        "| NAME | PORTS |\n\
+         | --- | --- |\n\
+         | web-app-1 | 80->8080/tcp, 443->8443/tcp |\n\
+         | db\\|primary | - |\n"
+    );
+}
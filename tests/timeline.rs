@@ -0,0 +1,114 @@
+use chrono::{DateTime, Duration, Utc};
+use dsd_util::history::{Action, HistoryEntry};
+use dsd_util::logger::LogLevel;
+use dsd_util::probes::ProbeHistoryEntry;
+use dsd_util::rollback::{RollbackState, RollbackTarget};
+use dsd_util::timeline::{
+    action_events, parse_docker_event, probe_events, update_events, TimelineSource,
+};
+
+fn at(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(1_750_000_000 + secs, 0).unwrap()
+}
+
+#[test]
+fn docker_events() {
+    let die = parse_docker_event(
+        r#"{"status":"die","id":"3f2a","Type":"container","Action":"die","Actor":{"ID":"3f2a","Attributes":{"exitCode":"137","name":"web-app-1"}},"time":1750000000,"timeNano":1750000000000000000}"#,
+    )
+    .unwrap();
+    assert_eq!(die.at, at(0));
+    assert_eq!(die.source, TimelineSource::Docker);
+    assert_eq!(die.level, LogLevel::Error);
+    assert_eq!(die.summary, "Exited with code 137");
+
+    let healthy = parse_docker_event(
+        r#"{"Type":"container","Action":"health_status: healthy","Actor":{"Attributes":{}},"time":1750000060}"#,
+    )
+    .unwrap();
+    assert_eq!(healthy.level, LogLevel::Info);
+    assert_eq!(healthy.summary, "Turned healthy");
+
+    assert!(parse_docker_event(
+        r#"{"Type":"container","Action":"exec_start: sh","Actor":{"Attributes":{}},"time":1750000060}"#
+    )
+    .is_none());
+    assert!(parse_docker_event("not json").is_none());
+}
+
+#[test]
+fn recorded_actions() {
+    let mut update = HistoryEntry::new("web-app-1", Action::Update, None).detail("nginx:1.27");
+    update.at = at(0);
+    let mut failed = HistoryEntry::new("web-app-1", Action::Restart, Some("timeout".to_string()));
+    failed.at = at(60);
+    let other = HistoryEntry::new("web-db-1", Action::Restart, None);
+
+    let events = action_events(&[update, failed, other], "web-app-1");
+    assert_eq!(events.len(), 2);
+    assert_eq!(events[0].summary, "Updated by dsd-util (nginx:1.27)");
+    assert_eq!(events[0].level, LogLevel::Info);
+    assert_eq!(events[1].summary, "Restarted by dsd-util failed: timeout");
+    assert_eq!(events[1].level, LogLevel::Error);
+}
+
+#[test]
+fn updates_within_the_window() {
+    let mut state = RollbackState::new();
+    state.insert(
+        "web-app-1".to_string(),
+        RollbackTarget {
+            container: "web-app-1".to_string(),
+            stack: Some("web".to_string()),
+            image: "nginx:latest".to_string(),
+            image_id: "sha256:0123456789abcdef".to_string(),
+            recorded_at: at(0),
+        },
+    );
+
+    let events = update_events(&state, "web-app-1", at(-60));
+    assert_eq!(events.len(), 1);
+    assert_eq!(
+        events[0].summary,
+        "Update replaced nginx:latest image 0123456789ab"
+    );
+
+    assert!(update_events(&state, "web-app-1", at(60)).is_empty());
+    assert!(update_events(&state, "web-db-1", at(-60)).is_empty());
+}
+
+#[test]
+fn probe_transitions() {
+    let result = |secs: i64, ok: bool| ProbeHistoryEntry {
+        timestamp: at(secs),
+        name: "web-http".to_string(),
+        container: "web-app-1".to_string(),
+        ok,
+        latency_ms: 12,
+        detail: if ok { "HTTP 200" } else { "HTTP 502" }.to_string(),
+    };
+
+    // out of order, as results of several probes interleave in the history file
+    let entries = [
+        result(120, false),
+        result(0, true),
+        result(60, true),
+        result(180, false),
+        result(240, true),
+    ];
+
+    let events = probe_events(&entries, "web-app-1");
+    let summaries = events
+        .iter()
+        .map(|event| (event.at - at(0), event.summary.as_str()))
+        .collect::<Vec<(Duration, &str)>>();
+
+    assert_eq!(
+        summaries,
+        [
+            (Duration::seconds(0), "Probe web-http passing"),
+            (Duration::seconds(120), "Probe web-http failing: HTTP 502"),
+            (Duration::seconds(240), "Probe web-http passing"),
+        ]
+    );
+}
@@ -0,0 +1,67 @@
+use dsd_util::top::{parse_top_output, sort_processes, ContainerProcess, TopSort};
+
+const TOP_OUTPUT: &str = "PID                 %CPU                COMMAND
+21840               0.0                 nginx: master process nginx -g daemon off;
+21905               12.5                nginx: worker process
+";
+
+fn process(container: &str, pid: u32, cpu_percent: f64) -> ContainerProcess {
+    ContainerProcess {
+        container: container.to_string(),
+        pid,
+        cpu_percent,
+        command: "sh".to_string(),
+    }
+}
+
+#[test]
+fn parses_docker_top() {
+    let processes = parse_top_output("web-app-1", TOP_OUTPUT).unwrap();
+
+    assert_eq!(
+        processes,
+        vec![
+            ContainerProcess {
+                container: "web-app-1".to_string(),
+                pid: 21840,
+                cpu_percent: 0.0,
+                command: "nginx: master process nginx -g daemon off;".to_string(),
+            },
+            ContainerProcess {
+                container: "web-app-1".to_string(),
+                pid: 21905,
+                cpu_percent: 12.5,
+                command: "nginx: worker process".to_string(),
+            },
+        ]
+    );
+
+    assert!(parse_top_output("web-app-1", "PID %CPU COMMAND\n")
+        .unwrap()
+        .is_empty());
+    assert!(parse_top_output("web-app-1", "PID %CPU COMMAND\nabc 1.0 sh\n").is_err());
+}
+
+#[test]
+fn sorts_processes() {
+    let mut processes = vec![
+        process("web-worker-1", 300, 1.0),
+        process("web-app-1", 200, 80.0),
+        process("web-app-1", 100, 1.0),
+    ];
+
+    sort_processes(&mut processes, TopSort::Cpu);
+    let order = |processes: &[ContainerProcess]| {
+        processes
+            .iter()
+            .map(|process| process.pid)
+            .collect::<Vec<u32>>()
+    };
+    assert_eq!(order(&processes), vec![200, 100, 300]);
+
+    sort_processes(&mut processes, TopSort::Pid);
+    assert_eq!(order(&processes), vec![100, 200, 300]);
+
+    sort_processes(&mut processes, TopSort::Container);
+    assert_eq!(order(&processes), vec![100, 200, 300]);
+}
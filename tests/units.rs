@@ -0,0 +1,116 @@
+use dsd_util::units::{
+    format_duration, format_size, parse_duration, parse_size, NumberFormat, SizeUnits,
+};
+use proptest::prelude::*;
+use std::time::Duration;
+
+#[test]
+fn durations() {
+    assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+    assert_eq!(parse_duration("15m").unwrap(), Duration::from_secs(15 * 60));
+    assert_eq!(
+        parse_duration("2h30m").unwrap(),
+        Duration::from_secs(2 * 3600 + 30 * 60)
+    );
+    assert_eq!(
+        parse_duration("1d2h3m4s").unwrap(),
+        Duration::from_secs(86_400 + 2 * 3600 + 3 * 60 + 4)
+    );
+
+    for invalid in [
+        "", "m", "5x", "5m2h", "1m1m", "2h30", " 5m", "5m ", "-5m", "1.5h",
+    ] {
+        assert!(
+            parse_duration(invalid).is_err(),
+            "{invalid:?} should not parse"
+        );
+    }
+}
+
+#[test]
+fn sizes() {
+    assert_eq!(parse_size("100").unwrap(), 100);
+    assert_eq!(parse_size("512MiB").unwrap(), 512 << 20);
+    assert_eq!(parse_size("512m").unwrap(), 512 << 20);
+    assert_eq!(parse_size("1.5G").unwrap(), 3 << 29);
+    assert_eq!(parse_size("100KB").unwrap(), 100_000);
+    assert_eq!(parse_size("2tb").unwrap(), 2_000_000_000_000);
+
+    for invalid in [
+        "",
+        "MiB",
+        "1.2.3G",
+        ".5G",
+        "5.G",
+        "5 G",
+        "5XB",
+        "-1K",
+        "99999999999T",
+    ] {
+        assert!(parse_size(invalid).is_err(), "{invalid:?} should not parse");
+    }
+
+    assert_eq!(format_size(0), "0B");
+    assert_eq!(format_size(1023), "1023B");
+    assert_eq!(format_size(3 << 29), "1.5GiB");
+    assert_eq!(format_size(512 << 20), "512MiB");
+}
+
+#[test]
+fn number_formats() {
+    let english = NumberFormat::from_locale("en_US.UTF-8", SizeUnits::Binary);
+    assert_eq!(english.number(0), "0");
+    assert_eq!(english.number(999), "999");
+    assert_eq!(english.number(1_234_567), "1,234,567");
+    assert_eq!(english.percent(1234.5), "1,234.50%");
+    assert_eq!(english.size(3 << 29), "1.5 GiB");
+    assert_eq!(english.size(2048 << 30), "2 TiB");
+
+    let german = NumberFormat::from_locale("de_DE.UTF-8", SizeUnits::Decimal);
+    assert_eq!(german.number(1_234_567), "1.234.567");
+    assert_eq!(german.percent(12.4), "12,40%");
+    assert_eq!(german.size(1_500_000_000), "1,5 GB");
+    assert_eq!(german.size(999), "999 B");
+
+    let swiss = NumberFormat::from_locale("de_CH", SizeUnits::Binary);
+    assert_eq!(swiss.number(1_000_000), "1'000'000");
+
+    let french = NumberFormat::from_locale("fr_FR", SizeUnits::Binary);
+    assert_eq!(french.decimal(1234.567, 1), "1\u{a0}234,6");
+
+    assert_eq!(
+        NumberFormat::from_locale("C", SizeUnits::Binary),
+        NumberFormat::default()
+    );
+}
+
+proptest! {
+    #[test]
+    fn duration_round_trips(secs in 0u64..=u64::MAX / 2) {
+        let duration = Duration::from_secs(secs);
+        prop_assert_eq!(parse_duration(&format_duration(duration)).unwrap(), duration);
+    }
+
+    #[test]
+    fn duration_parsing_never_panics(input in "\\PC*") {
+        let _ = parse_duration(&input);
+    }
+
+    #[test]
+    fn exact_sizes_round_trip(value in 0u64..1024, shift in prop::sample::select(vec![0u32, 10, 20, 30, 40])) {
+        let bytes = value << shift;
+        prop_assert_eq!(parse_size(&format_size(bytes)).unwrap(), bytes);
+    }
+
+    #[test]
+    fn sizes_round_trip_within_rounding(bytes in 0u64..u64::MAX >> 1) {
+        let parsed = parse_size(&format_size(bytes)).unwrap();
+        let error = parsed.abs_diff(bytes) as f64;
+        prop_assert!(error <= bytes as f64 * 0.005, "{} parsed back as {}", bytes, parsed);
+    }
+
+    #[test]
+    fn size_parsing_never_panics(input in "\\PC*") {
+        let _ = parse_size(&input);
+    }
+}
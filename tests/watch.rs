@@ -0,0 +1,93 @@
+use dsd_util::utils::InspectData;
+use dsd_util::watch::{WatchEvent, Watcher};
+use std::time::Duration;
+
+fn inspected(status: &str, health: &str, restart_count: u64) -> InspectData {
+    InspectData {
+        container_name: "web-app-1".to_string(),
+        status: status.to_string(),
+        restart_policy: "unless-stopped".to_string(),
+        health: health.to_string(),
+        uptime: "1h".to_string(),
+        started_at: "2025-06-01T12:00:00Z".to_string(),
+        restart_count,
+        ports: "N/A".to_string(),
+        port_bindings: vec![],
+        networks: vec![],
+    }
+}
+
+#[test]
+fn failures() {
+    let mut watcher = Watcher::new(2, Duration::from_secs(600));
+
+    // the first check only establishes a baseline
+    assert!(watcher
+        .check(&[inspected("running", "unhealthy", 0)])
+        .is_empty());
+    assert!(watcher
+        .check(&[inspected("running", "healthy", 0)])
+        .is_empty());
+    assert!(matches!(
+        watcher.check(&[inspected("running", "unhealthy", 0)])[..],
+        [WatchEvent::Unhealthy { .. }]
+    ));
+    assert!(matches!(
+        watcher.check(&[inspected("exited", "unhealthy", 2)])[..],
+        [
+            WatchEvent::Exited { .. },
+            WatchEvent::Restarting { restarts: 2, .. }
+        ]
+    ));
+}
+
+#[test]
+fn flapping() {
+    let window = Duration::from_millis(200);
+    let mut watcher = Watcher::new(100, Duration::from_secs(600)).flap_detection(4, window);
+
+    watcher.check(&[inspected("running", "healthy", 0)]);
+
+    let mut events = vec![];
+    for health in [
+        "unhealthy",
+        "healthy",
+        "unhealthy",
+        "healthy",
+        "unhealthy",
+        "healthy",
+    ] {
+        events.extend(watcher.check(&[inspected("running", health, 0)]));
+    }
+
+    // changes before the threshold are still reported, after it only the flapping is
+    assert_eq!(
+        events
+            .iter()
+            .filter(|event| matches!(event, WatchEvent::Unhealthy { .. }))
+            .count(),
+        2
+    );
+    assert!(matches!(
+        events.last(),
+        Some(WatchEvent::Flapping { changes: 4, .. })
+    ));
+    assert!(watcher
+        .check(&[inspected("running", "unhealthy", 0)])
+        .is_empty());
+
+    // a whole window without changes stabilizes the container
+    std::thread::sleep(window + Duration::from_millis(50));
+    assert!(matches!(
+        watcher.check(&[inspected("running", "unhealthy", 0)])[..],
+        [WatchEvent::Stabilized { .. }]
+    ));
+    assert!(matches!(
+        watcher.check(&[inspected("running", "healthy", 0)])[..],
+        []
+    ));
+    assert!(matches!(
+        watcher.check(&[inspected("running", "unhealthy", 0)])[..],
+        [WatchEvent::Unhealthy { .. }]
+    ));
+}
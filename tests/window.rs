@@ -0,0 +1,73 @@
+use chrono::NaiveTime;
+use dsd_util::window::TimeWindow;
+use std::time::Duration;
+
+fn time(time: &str) -> NaiveTime {
+    NaiveTime::parse_from_str(time, "%H:%M").unwrap()
+}
+
+#[test]
+fn parsing() {
+    let window = TimeWindow::parse("01:00-05:30").unwrap();
+    assert_eq!(window.start, time("01:00"));
+    assert_eq!(window.end, time("05:30"));
+    assert_eq!(window.to_string(), "01:00-05:30");
+
+    assert_eq!(
+        TimeWindow::parse(" 23:00 - 02:00 ").unwrap().to_string(),
+        "23:00-02:00"
+    );
+
+    assert!(TimeWindow::parse("01:00").is_err());
+    assert!(TimeWindow::parse("01:00-25:00").is_err());
+    assert!(TimeWindow::parse("3am-4am").is_err());
+    assert!(TimeWindow::parse("04:00-04:00").is_err());
+}
+
+#[test]
+fn windows() {
+    let window = TimeWindow::parse("01:00-05:00").unwrap();
+
+    assert!(!window.contains(time("00:59")));
+    assert!(window.contains(time("01:00")));
+    assert!(window.contains(time("04:59")));
+    assert!(!window.contains(time("05:00")));
+
+    assert_eq!(
+        window.until_open(time("00:30")),
+        Duration::from_secs(30 * 60)
+    );
+    assert_eq!(window.until_open(time("02:00")), Duration::ZERO);
+    assert_eq!(
+        window.until_open(time("05:00")),
+        Duration::from_secs(20 * 60 * 60)
+    );
+    assert_eq!(
+        window.until_close(time("04:00")),
+        Duration::from_secs(60 * 60)
+    );
+    assert_eq!(window.until_close(time("06:00")), Duration::ZERO);
+}
+
+#[test]
+fn windows_past_midnight() {
+    let window = TimeWindow::parse("23:00-02:00").unwrap();
+
+    assert!(window.contains(time("23:30")));
+    assert!(window.contains(time("01:59")));
+    assert!(!window.contains(time("02:00")));
+    assert!(!window.contains(time("12:00")));
+
+    assert_eq!(
+        window.until_open(time("22:00")),
+        Duration::from_secs(60 * 60)
+    );
+    assert_eq!(
+        window.until_close(time("23:00")),
+        Duration::from_secs(3 * 60 * 60)
+    );
+    assert_eq!(
+        window.until_close(time("01:00")),
+        Duration::from_secs(60 * 60)
+    );
+}